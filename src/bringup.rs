@@ -0,0 +1,316 @@
+//! # One-call board bring-up.
+//!
+//! [BoardResources::take_and_init] consumes the PAC peripherals, resets and enables the GPIO
+//! ports selected with the [BringupConfig] and returns the pin resource management singletons of
+//! these ports together with the remaining peripherals as [BoardPeripherals]. The GPIO port
+//! peripherals of the selected ports are consumed by the pin singletons, so they can not be
+//! created a second time.
+//!
+//! The peripheral clock enables only set bits, so ports and peripherals which were already
+//! enabled, for example by a bootloader, keep working. The selected ports are reset, so their
+//! configuration is deterministic afterwards. Ports which are not used can be skipped to avoid
+//! touching them at all.
+//!
+//! The clock configuration of Vorago 4x devices is not changed. The clock configuration which
+//! is active at the time of the bring-up, for example the configuration applied by a bootloader,
+//! can be frozen with `BringupConfig::freeze_clocks`. The frequencies are calculated from the
+//! CLKGEN registers and returned as `BoardResources::clocks`.
+use portable_atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "vor1x")]
+use va108xx as pac;
+#[cfg(feature = "vor4x")]
+use va416xx as pac;
+
+use crate::{
+    Port,
+    pins::{PinsA, PinsB},
+};
+#[cfg(feature = "vor4x")]
+use crate::{
+    clock::{ClockRefreshError, Clocks},
+    pins::{PinsC, PinsD, PinsE, PinsF, PinsG},
+    time::Hertz,
+};
+
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BringupError {
+    #[error("board bring-up was already performed")]
+    AlreadyInitialized,
+    /// The clock frequencies can not be calculated from the active clock configuration.
+    #[cfg(feature = "vor4x")]
+    #[error("clock configuration can not be frozen: {0}")]
+    Clock(#[from] ClockRefreshError),
+}
+
+/// Selects the GPIO ports which are reset and enabled by [BoardResources::take_and_init].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BringupConfig {
+    /// Bitmask of the selected ports. The bit positions are the [Port] values.
+    ports: u8,
+    /// Freeze the active clock configuration with the given external clock frequency.
+    #[cfg(feature = "vor4x")]
+    freeze_clocks: Option<Option<Hertz>>,
+}
+
+impl Default for BringupConfig {
+    /// All ports are selected.
+    fn default() -> Self {
+        Self::all_ports()
+    }
+}
+
+impl BringupConfig {
+    #[cfg(feature = "vor1x")]
+    const ALL_PORTS: u8 = 0b11;
+    #[cfg(feature = "vor4x")]
+    const ALL_PORTS: u8 = 0b111_1111;
+
+    /// Configuration which selects all ports.
+    pub const fn all_ports() -> Self {
+        Self {
+            ports: Self::ALL_PORTS,
+            #[cfg(feature = "vor4x")]
+            freeze_clocks: None,
+        }
+    }
+
+    /// Configuration which does not select any port.
+    pub const fn no_ports() -> Self {
+        Self {
+            ports: 0,
+            #[cfg(feature = "vor4x")]
+            freeze_clocks: None,
+        }
+    }
+
+    /// Select or skip the given port.
+    pub const fn port(mut self, port: Port, enable: bool) -> Self {
+        if enable {
+            self.ports |= 1 << port as u8;
+        } else {
+            self.ports &= !(1 << port as u8);
+        }
+        self
+    }
+
+    /// Checks whether the given port is selected.
+    #[inline]
+    pub const fn port_enabled(&self, port: Port) -> bool {
+        (self.ports >> port as u8) & 1 == 1
+    }
+
+    /// Freeze the active clock configuration during the bring-up.
+    ///
+    /// `ext_clk` is the frequency of the external clock or crystal. It is only required if the
+    /// system clock is derived from it, see [Clocks::refresh_after_failover].
+    #[cfg(feature = "vor4x")]
+    pub const fn freeze_clocks(mut self, ext_clk: Option<Hertz>) -> Self {
+        self.freeze_clocks = Some(ext_clk);
+        self
+    }
+}
+
+/// Declares [BoardPeripherals] with the GPIO port peripherals as optional fields and all other
+/// PAC peripherals as they are.
+macro_rules! board_peripherals {
+    (
+        ports: [$($port:ident: $port_ty:ident),* $(,)?],
+        periphs: [$($field:ident: $ty:ident),* $(,)?] $(,)?
+    ) => {
+        /// PAC peripherals returned by [BoardResources::take_and_init].
+        ///
+        /// The GPIO port peripherals are only [Some] for the ports which were skipped in the
+        /// [BringupConfig]. The port peripherals of the selected ports are owned by the pin
+        /// singletons.
+        pub struct BoardPeripherals {
+            $(pub $port: Option<pac::$port_ty>,)*
+            $(pub $field: pac::$ty,)*
+        }
+
+        impl From<pac::Peripherals> for BoardPeripherals {
+            fn from(periphs: pac::Peripherals) -> Self {
+                Self {
+                    $($port: Some(periphs.$port),)*
+                    $($field: periphs.$field,)*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "vor1x")]
+board_peripherals!(
+    ports: [porta: Porta, portb: Portb],
+    periphs: [
+        sysconfig: Sysconfig, irqsel: Irqsel, ioconfig: Ioconfig, utility: Utility,
+        tim0: Tim0, tim1: Tim1, tim2: Tim2, tim3: Tim3, tim4: Tim4, tim5: Tim5, tim6: Tim6,
+        tim7: Tim7, tim8: Tim8, tim9: Tim9, tim10: Tim10, tim11: Tim11, tim12: Tim12,
+        tim13: Tim13, tim14: Tim14, tim15: Tim15, tim16: Tim16, tim17: Tim17, tim18: Tim18,
+        tim19: Tim19, tim20: Tim20, tim21: Tim21, tim22: Tim22, tim23: Tim23,
+        uarta: Uarta, uartb: Uartb, spia: Spia, spib: Spib, spic: Spic, i2ca: I2ca, i2cb: I2cb,
+    ],
+);
+
+#[cfg(feature = "vor4x")]
+board_peripherals!(
+    ports: [
+        porta: Porta, portb: Portb, portc: Portc, portd: Portd, porte: Porte, portf: Portf,
+        portg: Portg,
+    ],
+    periphs: [
+        clkgen: Clkgen, sysconfig: Sysconfig, dma: Dma, ioconfig: Ioconfig, utility: Utility,
+        tim0: Tim0, tim1: Tim1, tim2: Tim2, tim3: Tim3, tim4: Tim4, tim5: Tim5, tim6: Tim6,
+        tim7: Tim7, tim8: Tim8, tim9: Tim9, tim10: Tim10, tim11: Tim11, tim12: Tim12,
+        tim13: Tim13, tim14: Tim14, tim15: Tim15, tim16: Tim16, tim17: Tim17, tim18: Tim18,
+        tim19: Tim19, tim20: Tim20, tim21: Tim21, tim22: Tim22, tim23: Tim23,
+        uart0: Uart0, uart1: Uart1, uart2: Uart2, spi0: Spi0, spi1: Spi1, spi2: Spi2,
+        spi3: Spi3, i2c0: I2c0, i2c1: I2c1, i2c2: I2c2, can0: Can0, can1: Can1, adc: Adc,
+        dac0: Dac0, dac1: Dac1, spw: Spw, irq_router: IrqRouter, watch_dog: WatchDog,
+        trng: Trng, eth: Eth,
+    ],
+);
+
+/// Resources returned by [BoardResources::take_and_init].
+///
+/// The pin singletons are [None] for all ports which were skipped in the [BringupConfig].
+pub struct BoardResources {
+    pub pins_a: Option<PinsA>,
+    pub pins_b: Option<PinsB>,
+    #[cfg(feature = "vor4x")]
+    pub pins_c: Option<PinsC>,
+    #[cfg(feature = "vor4x")]
+    pub pins_d: Option<PinsD>,
+    #[cfg(feature = "vor4x")]
+    pub pins_e: Option<PinsE>,
+    #[cfg(feature = "vor4x")]
+    pub pins_f: Option<PinsF>,
+    #[cfg(feature = "vor4x")]
+    pub pins_g: Option<PinsG>,
+    /// Frozen clock frequencies if [BringupConfig::freeze_clocks] was used.
+    #[cfg(feature = "vor4x")]
+    pub clocks: Option<Clocks>,
+    /// PAC peripherals without the GPIO port peripherals of the selected ports.
+    pub periphs: BoardPeripherals,
+}
+
+impl BoardResources {
+    /// Perform the board bring-up. This can only be called once, all further calls return
+    /// [BringupError::AlreadyInitialized].
+    ///
+    /// Each selected port is reset and the clocks of the port and of the IOCONFIG peripheral
+    /// are enabled. The ports are brought up in alphabetical order. On Vorago 4x devices, the
+    /// clock configuration is frozen first if this was selected with
+    /// `BringupConfig::freeze_clocks`. No port is touched if this fails.
+    pub fn take_and_init(
+        periphs: pac::Peripherals,
+        config: BringupConfig,
+    ) -> Result<Self, BringupError> {
+        if TAKEN.swap(true, Ordering::Relaxed) {
+            return Err(BringupError::AlreadyInitialized);
+        }
+        let mut periphs = BoardPeripherals::from(periphs);
+        #[cfg(feature = "vor4x")]
+        let clocks = match config.freeze_clocks {
+            Some(ext_clk) => Some(
+                crate::clock::monitor::clocks_from_clkgen(&periphs.clkgen, ext_clk).inspect_err(
+                    |_| {
+                        TAKEN.store(false, Ordering::Relaxed);
+                    },
+                )?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            pins_a: config
+                .port_enabled(Port::A)
+                .then(|| periphs.porta.take().map(PinsA::new))
+                .flatten(),
+            pins_b: config
+                .port_enabled(Port::B)
+                .then(|| periphs.portb.take().map(PinsB::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            pins_c: config
+                .port_enabled(Port::C)
+                .then(|| periphs.portc.take().map(PinsC::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            pins_d: config
+                .port_enabled(Port::D)
+                .then(|| periphs.portd.take().map(PinsD::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            pins_e: config
+                .port_enabled(Port::E)
+                .then(|| periphs.porte.take().map(PinsE::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            pins_f: config
+                .port_enabled(Port::F)
+                .then(|| periphs.portf.take().map(PinsF::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            pins_g: config
+                .port_enabled(Port::G)
+                .then(|| periphs.portg.take().map(PinsG::new))
+                .flatten(),
+            #[cfg(feature = "vor4x")]
+            clocks,
+            periphs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_selects_all_ports() {
+        let config = BringupConfig::default();
+        assert_eq!(config, BringupConfig::all_ports());
+        assert!(config.port_enabled(Port::A));
+        assert!(config.port_enabled(Port::B));
+        #[cfg(feature = "vor4x")]
+        assert!(config.port_enabled(Port::G));
+    }
+
+    #[test]
+    fn skip_ports() {
+        let config = BringupConfig::all_ports().port(Port::B, false);
+        assert!(config.port_enabled(Port::A));
+        assert!(!config.port_enabled(Port::B));
+        let config = BringupConfig::no_ports().port(Port::B, true);
+        assert!(!config.port_enabled(Port::A));
+        assert!(config.port_enabled(Port::B));
+        assert_eq!(
+            BringupConfig::all_ports()
+                .port(Port::A, false)
+                .port(Port::A, true),
+            BringupConfig::all_ports()
+        );
+    }
+
+    #[test]
+    fn port_peripherals_are_optional() {
+        let periphs = BoardPeripherals::from(unsafe { pac::Peripherals::steal() });
+        assert!(periphs.porta.is_some());
+        assert!(periphs.portb.is_some());
+    }
+
+    #[cfg(feature = "vor4x")]
+    #[test]
+    fn freeze_clocks_config() {
+        use fugit::RateExtU32;
+
+        assert_eq!(BringupConfig::default().freeze_clocks, None);
+        let config = BringupConfig::default().freeze_clocks(Some(10.MHz()));
+        assert_eq!(config.freeze_clocks, Some(Some(10.MHz())));
+        assert_eq!(config.ports, BringupConfig::ALL_PORTS);
+    }
+}
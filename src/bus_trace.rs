@@ -0,0 +1,112 @@
+//! # Bus transaction tracing
+//!
+//! Enabled with the `bus-trace` feature. The blocking transaction entry points of the
+//! [crate::i2c::I2cMaster], the [embedded_hal::spi::SpiBus] implementation of [crate::spi::Spi]
+//! and the [embedded_io] implementations of [crate::uart::Tx] and [crate::uart::Rx] emit one
+//! defmt debug event per transaction. The event contains the peripheral, the [Direction], the
+//! device address for I2C, the transaction length, a preview of the transferred data and the
+//! result.
+//!
+//! The events are only emitted after a transaction has completed, and never from the interrupt
+//! handlers or the asynchronous drivers. No code is generated for the tracing if the feature is
+//! disabled.
+//!
+//! ## Preview length
+//!
+//! The preview contains at most [PREVIEW_LEN] words. The default of 8 words can be changed at
+//! compile time with the `VORAGO_BUS_TRACE_PREVIEW_LEN` environment variable, for example
+//! `VORAGO_BUS_TRACE_PREVIEW_LEN=16 cargo build`.
+//!
+//! ## Muting peripherals
+//!
+//! Tracing is enabled for all peripherals by default. A noisy peripheral can be muted at run time
+//! with [set_enabled].
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::PeripheralSelect;
+
+/// Maximum number of words in the data preview of an event.
+pub const PREVIEW_LEN: usize = match option_env!("VORAGO_BUS_TRACE_PREVIEW_LEN") {
+    Some(len) => parse_len(len),
+    None => 8,
+};
+
+const fn parse_len(len: &str) -> usize {
+    let bytes = len.as_bytes();
+    assert!(
+        !bytes.is_empty(),
+        "VORAGO_BUS_TRACE_PREVIEW_LEN must not be empty"
+    );
+    let mut value = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        assert!(
+            bytes[idx].is_ascii_digit(),
+            "VORAGO_BUS_TRACE_PREVIEW_LEN must be a decimal number"
+        );
+        value = value * 10 + (bytes[idx] - b'0') as usize;
+        idx += 1;
+    }
+    value
+}
+
+/// Bit N is set if tracing is muted for the peripheral with the [PeripheralSelect] value N.
+static MUTED: AtomicU32 = AtomicU32::new(0);
+
+/// Enable or disable the tracing for the given peripheral.
+pub fn set_enabled(periph: PeripheralSelect, enabled: bool) {
+    let bit = 1 << periph as u32;
+    if enabled {
+        MUTED.fetch_and(!bit, Ordering::Relaxed);
+    } else {
+        MUTED.fetch_or(bit, Ordering::Relaxed);
+    }
+}
+
+/// Check whether tracing is enabled for the given peripheral.
+#[inline]
+pub fn is_enabled(periph: PeripheralSelect) -> bool {
+    (MUTED.load(Ordering::Relaxed) >> periph as u32) & 1 == 0
+}
+
+/// Direction of a traced transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum Direction {
+    Read,
+    Write,
+    /// Full-duplex SPI transfer. The preview contains the received words.
+    Transfer,
+    /// Full-duplex SPI transfer using one buffer. The preview contains the received words.
+    TransferInPlace,
+    /// I2C write followed by a read with a repeated start. The preview contains the read bytes.
+    WriteRead,
+}
+
+/// Emit a trace event for a completed transaction.
+#[inline(never)]
+pub(crate) fn event<Word: Copy + Into<u32>, E: defmt::Format>(
+    periph: PeripheralSelect,
+    direction: Direction,
+    address: Option<u16>,
+    data: &[Word],
+    result: Result<(), &E>,
+) {
+    if !is_enabled(periph) {
+        return;
+    }
+    let mut preview = [0u32; PREVIEW_LEN];
+    let preview_len = core::cmp::min(data.len(), PREVIEW_LEN);
+    for (word, data_word) in preview.iter_mut().zip(data) {
+        *word = (*data_word).into();
+    }
+    defmt::debug!(
+        "{} {} addr={} len={} data={=[?]:#x} truncated={} result={}",
+        periph,
+        direction,
+        address,
+        data.len(),
+        &preview[..preview_len],
+        data.len() > PREVIEW_LEN,
+        result
+    );
+}
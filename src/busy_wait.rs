@@ -0,0 +1,97 @@
+//! # Cooperative hook for long blocking operations.
+//!
+//! Blocking driver functions like the [embedded_hal::spi::SpiBus] transfers of
+//! [crate::spi::Spi], the blocking transactions of [crate::i2c::I2cMaster], the
+//! [embedded_hal::delay::DelayNs] implementation of [crate::timer::CountdownTimer] or the flush
+//! of [crate::uart::Tx] can take longer than the period of a watchdog. A hook registered with
+//! [set_busy_wait_hook] is called by the busy-wait loops of these functions, for example to
+//! feed the watchdog or to toggle a heartbeat pin.
+//!
+//! Each blocking loop calls the hook once every [BUSY_WAIT_HOOK_INTERVAL] iterations. One
+//! iteration is a poll of a status register or a transferred word, so the time between two calls
+//! is bounded by [BUSY_WAIT_HOOK_INTERVAL] times the duration of a single iteration. Blocking
+//! operations which complete in less iterations do not call the hook at all.
+//!
+//! The hook is stored in a global atomic and can be set or cleared from any context, including
+//! interrupt handlers. It is called from the context of the blocking function, so it should be
+//! short and must not use the driver which is currently blocking.
+use portable_atomic::{AtomicPtr, Ordering};
+
+/// Number of busy-wait loop iterations between two calls of the hook.
+pub const BUSY_WAIT_HOOK_INTERVAL: u32 = 256;
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Set the hook which is called periodically by the blocking loops of the HAL.
+#[inline]
+pub fn set_busy_wait_hook(hook: fn()) {
+    HOOK.store(hook as *mut (), Ordering::Relaxed);
+}
+
+/// Remove the hook set with [set_busy_wait_hook].
+#[inline]
+pub fn clear_busy_wait_hook() {
+    HOOK.store(core::ptr::null_mut(), Ordering::Relaxed);
+}
+
+fn call_hook() {
+    let hook = HOOK.load(Ordering::Relaxed);
+    if !hook.is_null() {
+        // Safety: Only function pointers of the type fn() are stored by set_busy_wait_hook.
+        let hook = unsafe { core::mem::transmute::<*mut (), fn()>(hook) };
+        hook();
+    }
+}
+
+/// Iteration counter of a single blocking loop.
+pub(crate) struct BusyWait(u32);
+
+impl BusyWait {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Count one loop iteration and call the hook every [BUSY_WAIT_HOOK_INTERVAL] iterations.
+    #[inline]
+    pub(crate) fn tick(&mut self) {
+        self.0 += 1;
+        if self.0 >= BUSY_WAIT_HOOK_INTERVAL {
+            self.0 = 0;
+            call_hook();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portable_atomic::AtomicU32;
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_call() {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn hook_called_every_interval() {
+        let mut busy_wait = BusyWait::new();
+        // No hook set.
+        for _ in 0..BUSY_WAIT_HOOK_INTERVAL {
+            busy_wait.tick();
+        }
+        set_busy_wait_hook(count_call);
+        for _ in 0..BUSY_WAIT_HOOK_INTERVAL * 3 - 1 {
+            busy_wait.tick();
+        }
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+        busy_wait.tick();
+        assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+        clear_busy_wait_hook();
+        for _ in 0..BUSY_WAIT_HOOK_INTERVAL {
+            busy_wait.tick();
+        }
+        assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+    }
+}
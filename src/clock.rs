@@ -1,7 +1,24 @@
 use crate::time::Hertz;
 
+pub mod monitor;
+
+pub use monitor::{ClockEvents, ClockMonitor, ClockRefreshError, SysClkSource};
+
 pub const HBO_FREQ: Hertz = Hertz::from_raw(20_000_000);
 
+/// APB bus which clocks a peripheral.
+///
+/// The APB1 peripherals are located in the 0x4001_0000 to 0x4001_FFFF address range, and the
+/// APB2 peripherals in the 0x4002_0000 to 0x4002_FFFF address range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ApbBus {
+    /// Clocked with the system clock divided by 2.
+    Apb1,
+    /// Clocked with the system clock divided by 4.
+    Apb2,
+}
+
 /// Frozen clock frequencies
 ///
 /// The existence of this value indicates that the clock configuration can no longer be changed.
@@ -49,6 +66,14 @@ impl Clocks {
         self.apb2
     }
 
+    /// Returns the clock frequency of the given APB bus.
+    pub const fn clock_for(&self, bus: ApbBus) -> Hertz {
+        match bus {
+            ApbBus::Apb1 => self.apb1,
+            ApbBus::Apb2 => self.apb2,
+        }
+    }
+
     /// Returns the system (core) frequency
     pub const fn sysclk(&self) -> Hertz {
         self.sysclk
@@ -60,3 +85,113 @@ impl Clocks {
         self.adc_clk
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{i2c::I2cInstance, spi::SpiInstance, timer::TimId, uart::UartInstance};
+
+    /// APB bus according to the memory map of the reference manual.
+    fn bus_of_address(addr: usize) -> ApbBus {
+        match addr & 0xFFFF_0000 {
+            0x4001_0000 => ApbBus::Apb1,
+            0x4002_0000 => ApbBus::Apb2,
+            _ => panic!("{addr:#x} is not an APB address"),
+        }
+    }
+
+    #[test]
+    fn apb_bus_mapping() {
+        use crate::pac;
+        // Peripheral, APB bus of the HAL, expected APB bus, base address.
+        let table = [
+            (
+                "UART0",
+                <pac::Uart0 as UartInstance>::APB,
+                ApbBus::Apb2,
+                pac::Uart0::PTR as usize,
+            ),
+            (
+                "UART1",
+                <pac::Uart1 as UartInstance>::APB,
+                ApbBus::Apb2,
+                pac::Uart1::PTR as usize,
+            ),
+            (
+                "UART2",
+                <pac::Uart2 as UartInstance>::APB,
+                ApbBus::Apb1,
+                pac::Uart2::PTR as usize,
+            ),
+            (
+                "SPI0",
+                <pac::Spi0 as SpiInstance>::APB,
+                ApbBus::Apb1,
+                pac::Spi0::PTR as usize,
+            ),
+            (
+                "SPI1",
+                <pac::Spi1 as SpiInstance>::APB,
+                ApbBus::Apb1,
+                pac::Spi1::PTR as usize,
+            ),
+            (
+                "SPI2",
+                <pac::Spi2 as SpiInstance>::APB,
+                ApbBus::Apb1,
+                pac::Spi2::PTR as usize,
+            ),
+            (
+                "SPI3",
+                <pac::Spi3 as SpiInstance>::APB,
+                ApbBus::Apb1,
+                pac::Spi3::PTR as usize,
+            ),
+            (
+                "I2C0",
+                <pac::I2c0 as I2cInstance>::APB,
+                ApbBus::Apb1,
+                pac::I2c0::PTR as usize,
+            ),
+            (
+                "I2C1",
+                <pac::I2c1 as I2cInstance>::APB,
+                ApbBus::Apb1,
+                pac::I2c1::PTR as usize,
+            ),
+            (
+                "I2C2",
+                crate::i2c::Bank::I2c2.apb_bus(),
+                ApbBus::Apb1,
+                pac::I2c2::PTR as usize,
+            ),
+            (
+                "TIM15",
+                TimId::new_unchecked(15).apb_bus(),
+                ApbBus::Apb1,
+                pac::Tim15::PTR as usize,
+            ),
+            (
+                "TIM16",
+                TimId::new_unchecked(16).apb_bus(),
+                ApbBus::Apb2,
+                pac::Tim16::PTR as usize,
+            ),
+        ];
+        for (name, hal, expected, addr) in table {
+            assert_eq!(hal, expected, "{name}");
+            assert_eq!(bus_of_address(addr), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn clock_for_bus() {
+        let clks = Clocks::__new(
+            Hertz::MHz(100),
+            #[cfg(not(feature = "va41628"))]
+            Hertz::MHz(25),
+        );
+        assert_eq!(clks.clock_for(ApbBus::Apb1), Hertz::MHz(50));
+        assert_eq!(clks.clock_for(ApbBus::Apb2), Hertz::MHz(25));
+    }
+}
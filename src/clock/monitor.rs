@@ -0,0 +1,492 @@
+//! # Clock failure monitoring
+//!
+//! The CLKGEN peripheral has two detectors for a failing system clock:
+//!
+//! - The SYS_CLK lost detector triggers if the system clock drops below 1 MHz, for example
+//!   because the crystal or the external clock failed.
+//! - The PLL lock lost detector triggers if the PLL lost its lock.
+//!
+//! Both detectors raise the `LoCLK` interrupt. [ClockMonitor] enables and disables the
+//! detectors, and [on_interrupt] decodes which failure occurred. If the system clock was lost,
+//! the handler selects the internal HBO oscillator as the system clock source if the hardware
+//! has not already done so, so the CPU keeps running with [HBO_FREQ] and the configured
+//! divider. The detector which triggered is disabled by the handler to avoid an interrupt storm
+//! while the failure persists, and can be re-armed with [ClockMonitor::rearm].
+//!
+//! ## Reconfiguration after a failover
+//!
+//! After a failover, the frozen [Clocks] structure is stale. The handler records the events,
+//! which can be polled from thread context with [take_events]. The new frequencies can then be
+//! calculated from the hardware with [Clocks::refresh_after_failover]. All drivers which derived
+//! register values from the frozen frequencies need to be re-created with the refreshed
+//! [Clocks]:
+//!
+//! - The UART baud rate dividers and the SPI and I2C clock scale registers.
+//! - The reload values of the [crate::timer::CountdownTimer]s and [crate::pwm::PwmPin]s, and
+//!   the time bases built on them, like the embassy time driver or the
+//!   [crate::time::MonotonicClock].
+//! - The ADC clock, which is divided from the system clock.
+//!
+//! The `reinitialize` methods of the drivers replay the configuration with the frequencies
+//! which were passed to the constructor, so they do not apply the new frequencies.
+use portable_atomic::{AtomicU8, Ordering};
+
+use super::{Clocks, HBO_FREQ};
+use crate::time::Hertz;
+
+use va416xx as pac;
+
+/// System clock source, read back from the `CLKSEL_SYS` field of the CLKGEN CTRL0 register.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SysClkSource {
+    /// Internal heart beat oscillator.
+    Hbo,
+    /// External clock.
+    ExtClk,
+    Pll,
+    /// Crystal oscillator.
+    XtalN,
+}
+
+impl SysClkSource {
+    const fn from_raw(value: u8) -> Self {
+        match value & 0b11 {
+            0b00 => SysClkSource::Hbo,
+            0b01 => SysClkSource::ExtClk,
+            0b10 => SysClkSource::Pll,
+            _ => SysClkSource::XtalN,
+        }
+    }
+}
+
+/// Clock failures reported by the CLKGEN status register.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClockEvents {
+    /// The system clock dropped below 1 MHz.
+    pub sys_clk_lost: bool,
+    /// The PLL lost its lock.
+    pub pll_lock_lost: bool,
+}
+
+impl ClockEvents {
+    #[inline]
+    pub const fn any(&self) -> bool {
+        self.sys_clk_lost || self.pll_lock_lost
+    }
+
+    /// Decode the CLKGEN STAT register.
+    const fn from_stat(stat: u32) -> Self {
+        Self {
+            sys_clk_lost: stat & STAT_SYSCLKLOST != 0,
+            pll_lock_lost: stat & STAT_LOCKLOST != 0,
+        }
+    }
+
+    const fn to_bits(self) -> u8 {
+        (self.sys_clk_lost as u8) | ((self.pll_lock_lost as u8) << 1)
+    }
+
+    const fn from_bits(bits: u8) -> Self {
+        Self {
+            sys_clk_lost: bits & 0b01 != 0,
+            pll_lock_lost: bits & 0b10 != 0,
+        }
+    }
+}
+
+/// A frequency can not be calculated from the clock configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockRefreshError {
+    /// The active clock source is derived from an external clock, but no external clock
+    /// frequency was passed.
+    #[error("external clock frequency required for clock source {0:?}")]
+    ExtClkRequired(SysClkSource),
+    /// The PLL is the active clock source, but no PLL reference clock is selected.
+    #[error("no PLL reference clock selected")]
+    NoPllReference,
+}
+
+const STAT_LOCKLOST: u32 = 1 << 2;
+const STAT_SYSCLKLOST: u32 = 1 << 3;
+
+/// Events recorded by [on_interrupt] which were not taken with [take_events] yet.
+static EVENTS: AtomicU8 = AtomicU8::new(0);
+
+/// Clock configuration fields of the CLKGEN CTRL0 and CTRL1 registers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct ClkgenConfig {
+    source: SysClkSource,
+    ref_clk_sel: u8,
+    pll_clkf: u8,
+    pll_clkr: u8,
+    pll_clkod: u8,
+    clk_div_sel: u8,
+    #[cfg(not(feature = "va41628"))]
+    adc_clk_div_sel: u8,
+}
+
+impl ClkgenConfig {
+    fn new(
+        ctrl0: &pac::clkgen::ctrl0::R,
+        #[cfg_attr(feature = "va41628", allow(unused_variables))] ctrl1: &pac::clkgen::ctrl1::R,
+    ) -> Self {
+        Self {
+            source: SysClkSource::from_raw(ctrl0.clksel_sys().bits()),
+            ref_clk_sel: ctrl0.ref_clk_sel().bits(),
+            pll_clkf: ctrl0.pll_clkf().bits(),
+            pll_clkr: ctrl0.pll_clkr().bits(),
+            pll_clkod: ctrl0.pll_clkod().bits(),
+            clk_div_sel: ctrl0.clk_div_sel().bits(),
+            #[cfg(not(feature = "va41628"))]
+            adc_clk_div_sel: ctrl1.adc_clk_div_sel().bits(),
+        }
+    }
+
+    /// System clock frequency for the clock configuration.
+    ///
+    /// `ext_clk` is the frequency of the external clock or crystal, which is required if the
+    /// system clock or the PLL reference clock is derived from it.
+    fn sysclk(&self, ext_clk: Option<Hertz>) -> Result<Hertz, ClockRefreshError> {
+        let source_clk = match self.source {
+            SysClkSource::Hbo => HBO_FREQ,
+            SysClkSource::ExtClk | SysClkSource::XtalN => {
+                ext_clk.ok_or(ClockRefreshError::ExtClkRequired(self.source))?
+            }
+            SysClkSource::Pll => {
+                if self.ref_clk_sel == 0 {
+                    return Err(ClockRefreshError::NoPllReference);
+                }
+                let ref_clk = ext_clk.ok_or(ClockRefreshError::ExtClkRequired(self.source))?;
+                let clkf = self.pll_clkf as u64 + 1;
+                let clkr = self.pll_clkr as u64 + 1;
+                let clkod = self.pll_clkod as u64 + 1;
+                Hertz::from_raw(((ref_clk.raw() as u64 * clkf) / (clkr * clkod)) as u32)
+            }
+        };
+        Ok(source_clk / (1 << self.clk_div_sel))
+    }
+
+    /// Clock frequencies for the clock configuration.
+    fn clocks(&self, ext_clk: Option<Hertz>) -> Result<Clocks, ClockRefreshError> {
+        let sysclk = self.sysclk(ext_clk)?;
+        Ok(Clocks::__new(
+            sysclk,
+            // The ADC divider selects a division by 8, 4, 2 or 1.
+            #[cfg(not(feature = "va41628"))]
+            {
+                sysclk / (8 >> self.adc_clk_div_sel)
+            },
+        ))
+    }
+}
+
+impl Clocks {
+    /// Read the active system clock source from the hardware.
+    ///
+    /// In contrast to the frozen frequencies, this reflects a switch to the HBO after a clock
+    /// failure, see the [module documentation](self).
+    pub fn active_source(&self) -> SysClkSource {
+        let clkgen = unsafe { pac::Clkgen::steal() };
+        SysClkSource::from_raw(clkgen.ctrl0().read().clksel_sys().bits())
+    }
+
+    /// Calculate the clock frequencies from the current clock configuration of the hardware
+    /// and update the frozen frequencies, for example after a failover to the HBO.
+    ///
+    /// `ext_clk` is the frequency of the external clock or crystal. It is only required if the
+    /// active clock source is derived from it. The frozen frequencies are not changed if an
+    /// error is returned. The drivers which need to be re-created with the returned frequencies
+    /// are listed in the [module documentation](self).
+    pub fn refresh_after_failover(
+        &mut self,
+        ext_clk: Option<Hertz>,
+    ) -> Result<Clocks, ClockRefreshError> {
+        let clkgen = unsafe { pac::Clkgen::steal() };
+        *self = clocks_from_clkgen(&clkgen, ext_clk)?;
+        Ok(*self)
+    }
+}
+
+/// Clock frequencies for the active clock configuration of the CLKGEN peripheral.
+pub(crate) fn clocks_from_clkgen(
+    clkgen: &pac::Clkgen,
+    ext_clk: Option<Hertz>,
+) -> Result<Clocks, ClockRefreshError> {
+    ClkgenConfig::new(&clkgen.ctrl0().read(), &clkgen.ctrl1().read()).clocks(ext_clk)
+}
+
+//==================================================================================================
+// Clock monitor
+//==================================================================================================
+
+/// Driver for the clock failure detectors of the CLKGEN peripheral.
+pub struct ClockMonitor {
+    clkgen: pac::Clkgen,
+}
+
+impl ClockMonitor {
+    /// Create the monitor. The detectors are not changed.
+    pub fn new(clkgen: pac::Clkgen) -> Self {
+        Self { clkgen }
+    }
+
+    /// Retrieve the monitor without the CLKGEN peripheral singleton, for example because it
+    /// was consumed by the clock configuration of the HAL.
+    ///
+    /// # Safety
+    ///
+    /// The CLKGEN control registers are modified with read-modify-write operations, so they
+    /// must not be modified concurrently.
+    pub unsafe fn steal() -> Self {
+        Self {
+            clkgen: unsafe { pac::Clkgen::steal() },
+        }
+    }
+
+    /// Enable the SYS_CLK lost detector and optionally unmask the `LoCLK` interrupt in the
+    /// NVIC.
+    pub fn enable_sys_clk_lost_detection(&mut self, enable_in_nvic: bool) {
+        self.clkgen
+            .ctrl0()
+            .modify(|_, w| w.sys_clk_lost_det_en().set_bit());
+        if enable_in_nvic {
+            unsafe { crate::enable_nvic_interrupt(pac::Interrupt::LoCLK) };
+        }
+    }
+
+    #[inline]
+    pub fn disable_sys_clk_lost_detection(&mut self) {
+        disable_sys_clk_lost_detection(&self.clkgen);
+    }
+
+    #[inline]
+    pub fn sys_clk_lost_detection_enabled(&self) -> bool {
+        self.clkgen
+            .ctrl0()
+            .read()
+            .sys_clk_lost_det_en()
+            .bit_is_set()
+    }
+
+    /// Enable the PLL lock lost detector and optionally unmask the `LoCLK` interrupt in the
+    /// NVIC.
+    pub fn enable_pll_lock_lost_detection(&mut self, enable_in_nvic: bool) {
+        self.clkgen
+            .ctrl1()
+            .modify(|_, w| w.pll_lost_lock_det_en().set_bit());
+        if enable_in_nvic {
+            unsafe { crate::enable_nvic_interrupt(pac::Interrupt::LoCLK) };
+        }
+    }
+
+    #[inline]
+    pub fn disable_pll_lock_lost_detection(&mut self) {
+        disable_pll_lock_lost_detection(&self.clkgen);
+    }
+
+    #[inline]
+    pub fn pll_lock_lost_detection_enabled(&self) -> bool {
+        self.clkgen
+            .ctrl1()
+            .read()
+            .pll_lost_lock_det_en()
+            .bit_is_set()
+    }
+
+    /// Read the failures which are currently reported by the status register.
+    #[inline]
+    pub fn status(&self) -> ClockEvents {
+        ClockEvents::from_stat(self.clkgen.stat().read().bits())
+    }
+
+    /// Re-arm and enable the detectors of the given events, for example after the failure
+    /// reported by [on_interrupt] was handled. This does not unmask the interrupt in the NVIC.
+    pub fn rearm(&mut self, events: ClockEvents) {
+        if events.pll_lock_lost {
+            self.clkgen
+                .ctrl1()
+                .modify(|_, w| w.pll_lck_det_rearm().set_bit());
+            self.clkgen
+                .ctrl1()
+                .modify(|_, w| w.pll_lck_det_rearm().clear_bit());
+            self.enable_pll_lock_lost_detection(false);
+        }
+        if events.sys_clk_lost {
+            self.clkgen
+                .ctrl1()
+                .modify(|_, w| w.sys_clk_lost_det_rearm().set_bit());
+            self.clkgen
+                .ctrl1()
+                .modify(|_, w| w.sys_clk_lost_det_rearm().clear_bit());
+            self.enable_sys_clk_lost_detection(false);
+        }
+    }
+
+    pub fn release(self) -> pac::Clkgen {
+        self.clkgen
+    }
+}
+
+fn disable_sys_clk_lost_detection(clkgen: &pac::Clkgen) {
+    clkgen
+        .ctrl0()
+        .modify(|_, w| w.sys_clk_lost_det_en().clear_bit());
+}
+
+fn disable_pll_lock_lost_detection(clkgen: &pac::Clkgen) {
+    clkgen
+        .ctrl1()
+        .modify(|_, w| w.pll_lost_lock_det_en().clear_bit());
+}
+
+/// Interrupt handler for the `LoCLK` interrupt.
+///
+/// Returns the failures which are reported by enabled detectors. They are also recorded for
+/// [take_events]. If the system clock was lost, the HBO is selected as the system clock source.
+/// The detectors which triggered are disabled, see the [module documentation](self).
+pub fn on_interrupt() -> ClockEvents {
+    let clkgen = unsafe { pac::Clkgen::steal() };
+    let ctrl0 = clkgen.ctrl0().read();
+    let ctrl1 = clkgen.ctrl1().read();
+    let mut events = ClockEvents::from_stat(clkgen.stat().read().bits());
+    events.sys_clk_lost &= ctrl0.sys_clk_lost_det_en().bit_is_set();
+    events.pll_lock_lost &= ctrl1.pll_lost_lock_det_en().bit_is_set();
+    if events.sys_clk_lost {
+        if SysClkSource::from_raw(ctrl0.clksel_sys().bits()) != SysClkSource::Hbo {
+            clkgen
+                .ctrl0()
+                .modify(|_, w| unsafe { w.clksel_sys().bits(0b00) });
+        }
+        disable_sys_clk_lost_detection(&clkgen);
+    }
+    if events.pll_lock_lost {
+        disable_pll_lock_lost_detection(&clkgen);
+    }
+    EVENTS.fetch_or(events.to_bits(), Ordering::Relaxed);
+    events
+}
+
+/// Take the events which were recorded by [on_interrupt] since the last call.
+#[inline]
+pub fn take_events() -> ClockEvents {
+    ClockEvents::from_bits(EVENTS.swap(0, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn config(
+        source: u8,
+        ref_sel: u8,
+        clkf: u8,
+        clkr: u8,
+        clkod: u8,
+        div: u8,
+    ) -> ClkgenConfig {
+        ClkgenConfig {
+            source: SysClkSource::from_raw(source),
+            ref_clk_sel: ref_sel,
+            pll_clkf: clkf,
+            pll_clkr: clkr,
+            pll_clkod: clkod,
+            clk_div_sel: div,
+            #[cfg(not(feature = "va41628"))]
+            adc_clk_div_sel: 0,
+        }
+    }
+
+    #[test]
+    fn hbo_frequencies() {
+        for (div, sysclk) in [(0, 20), (1, 10), (2, 5)] {
+            let clks = config(0, 0, 0, 0, 0, div).clocks(None).unwrap();
+            assert_eq!(clks.sysclk(), Hertz::MHz(sysclk));
+            assert_eq!(clks.apb1(), Hertz::MHz(sysclk) / 2);
+            assert_eq!(clks.apb2(), Hertz::MHz(sysclk) / 4);
+        }
+        // The divider select of 8 is applied as well.
+        assert_eq!(config(0, 0, 0, 0, 0, 3).sysclk(None), Ok(Hertz::kHz(2500)));
+    }
+
+    #[test]
+    fn failover_keeps_divider_and_ignores_pll() {
+        // The PLL configuration stays in the register after the switch to the HBO.
+        let pll = config(2, 1, 9, 0, 1, 0);
+        assert_eq!(pll.sysclk(Some(Hertz::MHz(10))), Ok(Hertz::MHz(50)));
+        let failover = ClkgenConfig {
+            source: SysClkSource::Hbo,
+            ..pll
+        };
+        assert_eq!(failover.sysclk(None), Ok(HBO_FREQ));
+    }
+
+    #[test]
+    fn pll_frequencies() {
+        // 10 MHz * 20 / (1 * 2) = 100 MHz
+        let value = config(2, 1, 19, 0, 1, 0);
+        assert_eq!(value.sysclk(Some(Hertz::MHz(10))), Ok(Hertz::MHz(100)));
+        // 40 MHz * 10 / (4 * 1) / 2 = 50 MHz
+        let value = config(2, 2, 9, 3, 0, 1);
+        assert_eq!(value.sysclk(Some(Hertz::MHz(40))), Ok(Hertz::MHz(50)));
+        assert_eq!(
+            value.sysclk(None),
+            Err(ClockRefreshError::ExtClkRequired(SysClkSource::Pll))
+        );
+        assert_eq!(
+            config(2, 0, 9, 0, 0, 0).sysclk(Some(Hertz::MHz(10))),
+            Err(ClockRefreshError::NoPllReference)
+        );
+    }
+
+    #[test]
+    fn external_frequencies() {
+        for (source, expected) in [(1, SysClkSource::ExtClk), (3, SysClkSource::XtalN)] {
+            let value = config(source, 0, 0, 0, 0, 1);
+            assert_eq!(value.source, expected);
+            assert_eq!(value.sysclk(Some(Hertz::MHz(40))), Ok(Hertz::MHz(20)));
+            assert_eq!(
+                value.sysclk(None),
+                Err(ClockRefreshError::ExtClkRequired(expected))
+            );
+        }
+    }
+
+    #[cfg(not(feature = "va41628"))]
+    #[test]
+    fn adc_clock_divider() {
+        for (div_sel, adc_clk) in [(0, 2500), (1, 5000), (2, 10_000), (3, 20_000)] {
+            let clks = ClkgenConfig {
+                adc_clk_div_sel: div_sel,
+                ..config(0, 0, 0, 0, 0, 0)
+            }
+            .clocks(None)
+            .unwrap();
+            assert_eq!(clks.adc_clk(), Hertz::kHz(adc_clk));
+        }
+    }
+
+    #[test]
+    fn event_decoding() {
+        assert_eq!(ClockEvents::from_stat(0b0011), ClockEvents::default());
+        assert!(!ClockEvents::from_stat(0b0011).any());
+        let both = ClockEvents::from_stat(STAT_SYSCLKLOST | STAT_LOCKLOST);
+        assert_eq!(
+            both,
+            ClockEvents {
+                sys_clk_lost: true,
+                pll_lock_lost: true
+            }
+        );
+        for events in [
+            ClockEvents::default(),
+            ClockEvents::from_stat(STAT_SYSCLKLOST),
+            ClockEvents::from_stat(STAT_LOCKLOST),
+            both,
+        ] {
+            assert_eq!(ClockEvents::from_bits(events.to_bits()), events);
+        }
+    }
+}
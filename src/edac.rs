@@ -0,0 +1,465 @@
+//! # EDAC and memory scrubbing support.
+//!
+//! The ROM and RAM of the Vorago devices are protected by an EDAC (error detection and
+//! correction) code. Single bit errors (SBE) are corrected on access, multi bit errors (MBE) can
+//! only be detected. The memory scrubbers periodically read the memories in the background, so
+//! single bit errors are corrected before a second upset turns them into a multi bit error.
+//!
+//! The SYSCONFIG peripheral provides a counter for each memory and error type, and an interrupt
+//! status which can be routed to the NVIC. Each [EdacEvent] is also available as a
+//! [CascadeSource], so a TIM peripheral can count the events in hardware, see
+//! [EdacEvent::cascade_source].
+//!
+//! The hardware does not record the address of the erroneous memory word.
+#[cfg(feature = "vor1x")]
+use va108xx as pac;
+#[cfg(feature = "vor4x")]
+use va416xx as pac;
+
+#[cfg(feature = "vor1x")]
+use crate::{InterruptConfig, PeripheralSelect, enable_peripheral_clock};
+use crate::{enable_nvic_interrupt, timer::CascadeSource};
+
+/// Largest scrub period which can be configured.
+pub const MAX_SCRUB_PERIOD: u32 = 0x00FF_FFFF;
+
+/// Memory protected by the EDAC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Memory {
+    Rom,
+    #[cfg(feature = "vor1x")]
+    Ram,
+    #[cfg(feature = "vor4x")]
+    Ram0,
+    #[cfg(feature = "vor4x")]
+    Ram1,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorKind {
+    /// Single bit error, which was corrected.
+    SingleBit,
+    /// Multi bit error, which could not be corrected.
+    MultiBit,
+}
+
+/// EDAC error of a memory, used for telemetry and to select interrupts and counters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EdacEvent {
+    pub memory: Memory,
+    pub kind: ErrorKind,
+}
+
+impl EdacEvent {
+    pub const fn new(memory: Memory, kind: ErrorKind) -> Self {
+        Self { memory, kind }
+    }
+
+    /// Bit of the event in the EDAC interrupt registers.
+    pub const fn irq_bit(&self) -> u32 {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "vor1x")] {
+                let pos = match (self.memory, self.kind) {
+                    (Memory::Ram, ErrorKind::SingleBit) => 0,
+                    (Memory::Ram, ErrorKind::MultiBit) => 1,
+                    (Memory::Rom, ErrorKind::SingleBit) => 2,
+                    (Memory::Rom, ErrorKind::MultiBit) => 3,
+                };
+            } else if #[cfg(feature = "vor4x")] {
+                let pos = match (self.memory, self.kind) {
+                    (Memory::Rom, ErrorKind::MultiBit) => 0,
+                    (Memory::Rom, ErrorKind::SingleBit) => 1,
+                    (Memory::Ram0, ErrorKind::MultiBit) => 2,
+                    (Memory::Ram0, ErrorKind::SingleBit) => 3,
+                    (Memory::Ram1, ErrorKind::MultiBit) => 4,
+                    (Memory::Ram1, ErrorKind::SingleBit) => 5,
+                };
+            }
+        }
+        1 << pos
+    }
+
+    /// Cascade source of the event, which can be used to count the events with a TIM
+    /// peripheral, see [crate::timer::CountdownTimer::set_cascade_source].
+    pub const fn cascade_source(&self) -> CascadeSource {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "vor1x")] {
+                match (self.memory, self.kind) {
+                    (Memory::Ram, ErrorKind::SingleBit) => CascadeSource::RamSbe,
+                    (Memory::Ram, ErrorKind::MultiBit) => CascadeSource::RamMbe,
+                    (Memory::Rom, ErrorKind::SingleBit) => CascadeSource::RomSbe,
+                    (Memory::Rom, ErrorKind::MultiBit) => CascadeSource::RomMbe,
+                }
+            } else if #[cfg(feature = "vor4x")] {
+                match (self.memory, self.kind) {
+                    (Memory::Rom, ErrorKind::SingleBit) => CascadeSource::RomSbe,
+                    (Memory::Rom, ErrorKind::MultiBit) => CascadeSource::RomMbe,
+                    (Memory::Ram0, ErrorKind::SingleBit) => CascadeSource::Ram0Sbe,
+                    (Memory::Ram0, ErrorKind::MultiBit) => CascadeSource::Ram0Mbe,
+                    (Memory::Ram1, ErrorKind::SingleBit) => CascadeSource::Ram1Sbe,
+                    (Memory::Ram1, ErrorKind::MultiBit) => CascadeSource::Ram1Mbe,
+                }
+            }
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "vor1x")] {
+        /// All EDAC events of the device.
+        pub const ALL_EVENTS: [EdacEvent; 4] = [
+            EdacEvent::new(Memory::Ram, ErrorKind::SingleBit),
+            EdacEvent::new(Memory::Ram, ErrorKind::MultiBit),
+            EdacEvent::new(Memory::Rom, ErrorKind::SingleBit),
+            EdacEvent::new(Memory::Rom, ErrorKind::MultiBit),
+        ];
+    } else if #[cfg(feature = "vor4x")] {
+        /// All EDAC events of the device.
+        pub const ALL_EVENTS: [EdacEvent; 6] = [
+            EdacEvent::new(Memory::Rom, ErrorKind::MultiBit),
+            EdacEvent::new(Memory::Rom, ErrorKind::SingleBit),
+            EdacEvent::new(Memory::Ram0, ErrorKind::MultiBit),
+            EdacEvent::new(Memory::Ram0, ErrorKind::SingleBit),
+            EdacEvent::new(Memory::Ram1, ErrorKind::MultiBit),
+            EdacEvent::new(Memory::Ram1, ErrorKind::SingleBit),
+        ];
+    }
+}
+
+/// Set of [EdacEvent]s, for example the events which triggered an interrupt.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EdacEvents(u32);
+
+impl EdacEvents {
+    /// All events of the device.
+    pub const ALL: Self = Self::from_events(&ALL_EVENTS);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_events(events: &[EdacEvent]) -> Self {
+        let mut bits = 0;
+        let mut idx = 0;
+        while idx < events.len() {
+            bits |= events[idx].irq_bit();
+            idx += 1;
+        }
+        Self(bits)
+    }
+
+    /// All single bit or all multi bit events.
+    pub const fn of_kind(kind: ErrorKind) -> Self {
+        let mut bits = 0;
+        let mut idx = 0;
+        while idx < ALL_EVENTS.len() {
+            if ALL_EVENTS[idx].kind as u8 == kind as u8 {
+                bits |= ALL_EVENTS[idx].irq_bit();
+            }
+            idx += 1;
+        }
+        Self(bits)
+    }
+
+    /// Create the set from the raw value of an EDAC interrupt register. Unknown bits are
+    /// ignored.
+    pub const fn from_raw(raw: u32) -> Self {
+        Self(raw & Self::ALL.0)
+    }
+
+    pub const fn raw(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(&self, event: EdacEvent) -> bool {
+        self.0 & event.irq_bit() != 0
+    }
+
+    /// Checks whether the set contains an event of the given kind, for example to reset the
+    /// device on a multi bit error.
+    pub const fn contains_kind(&self, kind: ErrorKind) -> bool {
+        self.0 & Self::of_kind(kind).0 != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = EdacEvent> + use<> {
+        let events = *self;
+        ALL_EVENTS
+            .into_iter()
+            .filter(move |event| events.contains(*event))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("scrub period {0} larger than {MAX_SCRUB_PERIOD}")]
+pub struct InvalidScrubPeriodError(pub u32);
+
+//==================================================================================================
+// Register access
+//==================================================================================================
+
+#[inline(always)]
+fn sysconfig() -> pac::Sysconfig {
+    unsafe { pac::Sysconfig::steal() }
+}
+
+/// Enable the scrubber of the given memory.
+///
+/// The period is the divide value of the scrub counter, which is clocked with the system clock.
+/// A smaller value scrubs the memory more often, at the cost of more bus accesses competing
+/// with the CPU.
+pub fn enable_scrubbing(memory: Memory, period: u32) -> Result<(), InvalidScrubPeriodError> {
+    if period == 0 || period > MAX_SCRUB_PERIOD {
+        return Err(InvalidScrubPeriodError(period));
+    }
+    write_scrub_period(memory, period);
+    Ok(())
+}
+
+/// Disable the scrubber of the given memory, which is done by setting its period to 0.
+#[inline]
+pub fn disable_scrubbing(memory: Memory) {
+    write_scrub_period(memory, 0);
+}
+
+/// Scrub period of the given memory, which is 0 if the scrubber is disabled.
+pub fn scrub_period(memory: Memory) -> u32 {
+    let syscfg = sysconfig();
+    match memory {
+        Memory::Rom => syscfg.rom_scrub().read().value().bits(),
+        #[cfg(feature = "vor1x")]
+        Memory::Ram => syscfg.ram_scrub().read().bits() & MAX_SCRUB_PERIOD,
+        #[cfg(feature = "vor4x")]
+        Memory::Ram0 => syscfg.ram0_scrub().read().bits() & MAX_SCRUB_PERIOD,
+        #[cfg(feature = "vor4x")]
+        Memory::Ram1 => syscfg.ram1_scrub().read().bits() & MAX_SCRUB_PERIOD,
+    }
+}
+
+fn write_scrub_period(memory: Memory, period: u32) {
+    let syscfg = sysconfig();
+    match memory {
+        Memory::Rom => syscfg.rom_scrub().write(|w| unsafe { w.bits(period) }),
+        #[cfg(feature = "vor1x")]
+        Memory::Ram => syscfg.ram_scrub().write(|w| unsafe { w.bits(period) }),
+        #[cfg(feature = "vor4x")]
+        Memory::Ram0 => syscfg.ram0_scrub().write(|w| unsafe { w.bits(period) }),
+        #[cfg(feature = "vor4x")]
+        Memory::Ram1 => syscfg.ram1_scrub().write(|w| unsafe { w.bits(period) }),
+    };
+}
+
+/// Reads the error counter of the given event.
+pub fn error_count(event: EdacEvent) -> u32 {
+    let syscfg = sysconfig();
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "vor1x")] {
+            match (event.memory, event.kind) {
+                (Memory::Ram, ErrorKind::SingleBit) => syscfg.ram_sbe().read().bits(),
+                (Memory::Ram, ErrorKind::MultiBit) => syscfg.ram_mbe().read().bits(),
+                (Memory::Rom, ErrorKind::SingleBit) => syscfg.rom_sbe().read().bits(),
+                (Memory::Rom, ErrorKind::MultiBit) => syscfg.rom_mbe().read().bits(),
+            }
+        } else if #[cfg(feature = "vor4x")] {
+            match (event.memory, event.kind) {
+                (Memory::Rom, ErrorKind::SingleBit) => syscfg.rom_sbe().read().bits(),
+                (Memory::Rom, ErrorKind::MultiBit) => syscfg.rom_mbe().read().bits(),
+                (Memory::Ram0, ErrorKind::SingleBit) => syscfg.ram0_sbe().read().bits(),
+                (Memory::Ram0, ErrorKind::MultiBit) => syscfg.ram0_mbe().read().bits(),
+                (Memory::Ram1, ErrorKind::SingleBit) => syscfg.ram1_sbe().read().bits(),
+                (Memory::Ram1, ErrorKind::MultiBit) => syscfg.ram1_mbe().read().bits(),
+            }
+        }
+    }
+}
+
+/// Reads and clears the error counter of the given event.
+///
+/// The counter is cleared by writing 0 to it, so an error which occurs between reading and
+/// clearing the counter is not counted.
+pub fn read_and_clear_error_count(event: EdacEvent) -> u32 {
+    let count = error_count(event);
+    clear_error_count(event);
+    count
+}
+
+fn clear_error_count(event: EdacEvent) {
+    let syscfg = sysconfig();
+    match (event.memory, event.kind) {
+        (Memory::Rom, ErrorKind::SingleBit) => syscfg.rom_sbe().write(|w| unsafe { w.bits(0) }),
+        (Memory::Rom, ErrorKind::MultiBit) => syscfg.rom_mbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor1x")]
+        (Memory::Ram, ErrorKind::SingleBit) => syscfg.ram_sbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor1x")]
+        (Memory::Ram, ErrorKind::MultiBit) => syscfg.ram_mbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor4x")]
+        (Memory::Ram0, ErrorKind::SingleBit) => syscfg.ram0_sbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor4x")]
+        (Memory::Ram0, ErrorKind::MultiBit) => syscfg.ram0_mbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor4x")]
+        (Memory::Ram1, ErrorKind::SingleBit) => syscfg.ram1_sbe().write(|w| unsafe { w.bits(0) }),
+        #[cfg(feature = "vor4x")]
+        (Memory::Ram1, ErrorKind::MultiBit) => syscfg.ram1_mbe().write(|w| unsafe { w.bits(0) }),
+    };
+}
+
+//==================================================================================================
+// Interrupts
+//==================================================================================================
+
+/// Interrupt of the given error kind. The Vorago 4x devices have one interrupt for all single
+/// bit errors and one for all multi bit errors.
+#[cfg(feature = "vor4x")]
+pub const fn interrupt(kind: ErrorKind) -> pac::Interrupt {
+    match kind {
+        ErrorKind::SingleBit => pac::Interrupt::EDAC_SBE,
+        ErrorKind::MultiBit => pac::Interrupt::EDAC_MBE,
+    }
+}
+
+/// Route the interrupt of the given event to an interrupt line with the IRQSEL peripheral.
+#[cfg(feature = "vor1x")]
+pub fn route_interrupt(event: EdacEvent, irq: pac::Interrupt) {
+    enable_peripheral_clock(PeripheralSelect::Irqsel);
+    let mut irqsel = crate::irqsel::regs::Irqsel::new_mmio();
+    match (event.memory, event.kind) {
+        (Memory::Ram, ErrorKind::SingleBit) => irqsel.write_int_ram_sbe(irq as u32),
+        (Memory::Ram, ErrorKind::MultiBit) => irqsel.write_int_ram_mbe(irq as u32),
+        (Memory::Rom, ErrorKind::SingleBit) => irqsel.write_int_rom_sbe(irq as u32),
+        (Memory::Rom, ErrorKind::MultiBit) => irqsel.write_int_rom_mbe(irq as u32),
+    };
+}
+
+/// Enable the interrupts of the given events. All events are routed to the interrupt of the
+/// interrupt configuration.
+#[cfg(feature = "vor1x")]
+pub fn enable_interrupts(events: EdacEvents, irq_cfg: InterruptConfig) {
+    if irq_cfg.route {
+        for event in events.iter() {
+            route_interrupt(event, irq_cfg.id);
+        }
+    }
+    sysconfig()
+        .irq_enb()
+        .modify(|r, w| unsafe { w.bits(r.bits() | events.raw()) });
+    if irq_cfg.enable_in_nvic {
+        unsafe { enable_nvic_interrupt(irq_cfg.id) };
+    }
+}
+
+/// Enable the interrupts of the given events. The [interrupt] of each error kind of the events
+/// is unmasked in the NVIC if `enable_in_nvic` is set.
+#[cfg(feature = "vor4x")]
+pub fn enable_interrupts(events: EdacEvents, enable_in_nvic: bool) {
+    sysconfig()
+        .irq_enb()
+        .modify(|r, w| unsafe { w.bits(r.bits() | events.raw()) });
+    if enable_in_nvic {
+        for kind in [ErrorKind::SingleBit, ErrorKind::MultiBit] {
+            if events.contains_kind(kind) {
+                unsafe { enable_nvic_interrupt(interrupt(kind)) };
+            }
+        }
+    }
+}
+
+/// This function only clears the interrupt enable bits of the given events.
+///
+/// It does not mask the interrupt in the NVIC or un-route the IRQ.
+pub fn disable_interrupts(events: EdacEvents) {
+    sysconfig()
+        .irq_enb()
+        .modify(|r, w| unsafe { w.bits(r.bits() & !events.raw()) });
+}
+
+/// Events which occurred since they were last cleared, regardless of whether their interrupt is
+/// enabled.
+#[inline]
+pub fn raw_events() -> EdacEvents {
+    EdacEvents::from_raw(sysconfig().irq_raw().read().bits())
+}
+
+/// Clear the interrupt status of the given events.
+#[inline]
+pub fn clear_events(events: EdacEvents) {
+    sysconfig()
+        .irq_clr()
+        .write(|w| unsafe { w.bits(events.raw()) });
+}
+
+/// Interrupt handler for the EDAC interrupts.
+///
+/// Returns and clears the enabled events which are pending. The events can be converted into
+/// [EdacEvent]s for telemetry with [EdacEvents::iter]. The error counters are not modified.
+pub fn on_interrupt() -> EdacEvents {
+    let events = EdacEvents::from_raw(sysconfig().irq_end().read().bits());
+    clear_events(events);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_bits_unique() {
+        let mut all = 0;
+        for event in ALL_EVENTS {
+            assert_eq!(all & event.irq_bit(), 0);
+            all |= event.irq_bit();
+            let events = EdacEvents::from_raw(event.irq_bit());
+            assert!(events.contains(event));
+            assert!(events.contains_kind(event.kind));
+            assert_eq!(events.iter().collect::<heapless::Vec<_, 6>>(), [event]);
+        }
+        assert_eq!(EdacEvents::ALL.raw(), all);
+        assert_eq!(
+            EdacEvents::of_kind(ErrorKind::SingleBit).raw()
+                | EdacEvents::of_kind(ErrorKind::MultiBit).raw(),
+            all
+        );
+        // Unknown bits are ignored.
+        assert_eq!(EdacEvents::from_raw(u32::MAX), EdacEvents::ALL);
+    }
+
+    #[test]
+    fn event_decoding() {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "vor1x")] {
+                let rom_mbe = 0b1000;
+                let ram_sbe = 0b0001;
+                let ram = Memory::Ram;
+            } else if #[cfg(feature = "vor4x")] {
+                let rom_mbe = 0b00_0001;
+                let ram_sbe = 0b00_1000;
+                let ram = Memory::Ram0;
+            }
+        }
+        let events = EdacEvents::from_raw(rom_mbe | ram_sbe);
+        assert!(events.contains(EdacEvent::new(Memory::Rom, ErrorKind::MultiBit)));
+        assert!(events.contains(EdacEvent::new(ram, ErrorKind::SingleBit)));
+        assert!(!events.contains(EdacEvent::new(Memory::Rom, ErrorKind::SingleBit)));
+        assert_eq!(events.iter().count(), 2);
+        assert!(events.contains_kind(ErrorKind::MultiBit));
+        assert!(!EdacEvents::from_raw(ram_sbe).contains_kind(ErrorKind::MultiBit));
+        assert!(EdacEvents::empty().is_empty());
+    }
+
+    #[test]
+    fn event_cascade_sources() {
+        let mut used = heapless::Vec::<u8, 6>::new();
+        for event in ALL_EVENTS {
+            let id = event.cascade_source().id().unwrap().value();
+            assert!(!used.contains(&id));
+            used.push(id).unwrap();
+        }
+    }
+}
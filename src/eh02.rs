@@ -0,0 +1,213 @@
+//! # embedded-hal 0.2 compatibility shims.
+//!
+//! Enabled with the `eh02-compat` feature. The embedded-hal 0.2 traits are implemented for the
+//! drivers of this crate by delegating to their embedded-hal 1.0 implementations, so drivers
+//! which still consume the 0.2 traits can be used without modifications.
+//!
+//! The following implementations are provided:
+//!
+//! - [blocking::spi::Transfer] and [blocking::spi::Write] for the [u8] and [u16] variants of
+//!   [Spi]
+//! - [blocking::i2c::Read], [blocking::i2c::Write] and [blocking::i2c::WriteRead] for the 7-bit
+//!   and 10-bit address variants of [I2cMaster]
+//! - [digital::v2::OutputPin], [digital::v2::StatefulOutputPin] and
+//!   [digital::v2::ToggleableOutputPin] for [Output], [digital::v2::InputPin] for [Input]
+//! - [serial::Read] for [Rx], [serial::Write] and [blocking::serial::Write] for [Tx]
+//! - [timer::CountDown] and [timer::Periodic] for [CountdownTimer]
+//!
+//! The associated error types are the error types of the 1.0 implementations:
+//! [StaleDataInFifoError] for SPI, [crate::i2c::Error] for I2C and [Infallible] for GPIO and UART.
+//!
+//! ## Semantic differences
+//!
+//! - [blocking::spi::Transfer::transfer] performs an in-place transfer and returns the passed
+//!   buffer, which contains the read-back words.
+//! - [blocking::spi::Write::write] only returns after the transfer was completed, like the 1.0
+//!   `SpiBus::write` followed by `SpiBus::flush`. Hardware chip select lines are handled like for
+//!   the 1.0 implementation.
+//! - The [timer::CountDown::Time] of [CountdownTimer] is [Microseconds]. Durations are converted
+//!   to timer clock cycles with [crate::timer::duration_to_reload], which rounds down. A rate can
+//!   be converted with `Hertz::into_duration`. The timer is periodic unless
+//!   [CountdownTimer::auto_disable] was enabled.
+use core::convert::Infallible;
+
+use embedded_hal::i2c::{SevenBitAddress, TenBitAddress};
+use embedded_hal_02::{blocking, digital, serial, timer};
+use void::Void;
+
+use crate::{
+    gpio::{Input, Output},
+    i2c::I2cMaster,
+    spi::{Spi, StaleDataInFifoError},
+    time::Microseconds,
+    timer::CountdownTimer,
+    uart::{Rx, Tx},
+};
+
+//==================================================================================================
+// SPI
+//==================================================================================================
+
+macro_rules! impl_eh02_spi {
+    ($word:ty) => {
+        impl blocking::spi::Transfer<$word> for Spi<$word> {
+            type Error = StaleDataInFifoError;
+
+            fn transfer<'w>(&mut self, words: &'w mut [$word]) -> Result<&'w [$word], Self::Error> {
+                embedded_hal::spi::SpiBus::transfer_in_place(self, words)?;
+                Ok(words)
+            }
+        }
+
+        impl blocking::spi::Write<$word> for Spi<$word> {
+            type Error = StaleDataInFifoError;
+
+            fn write(&mut self, words: &[$word]) -> Result<(), Self::Error> {
+                embedded_hal::spi::SpiBus::write(self, words)?;
+                embedded_hal::spi::SpiBus::flush(self)
+            }
+        }
+    };
+}
+
+impl_eh02_spi!(u8);
+impl_eh02_spi!(u16);
+
+//==================================================================================================
+// I2C
+//==================================================================================================
+
+macro_rules! impl_eh02_i2c {
+    ($addr:ty) => {
+        impl blocking::i2c::Read<$addr> for I2cMaster<$addr> {
+            type Error = crate::i2c::Error;
+
+            fn read(&mut self, address: $addr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+                embedded_hal::i2c::I2c::read(self, address, buffer)
+            }
+        }
+
+        impl blocking::i2c::Write<$addr> for I2cMaster<$addr> {
+            type Error = crate::i2c::Error;
+
+            fn write(&mut self, address: $addr, bytes: &[u8]) -> Result<(), Self::Error> {
+                embedded_hal::i2c::I2c::write(self, address, bytes)
+            }
+        }
+
+        impl blocking::i2c::WriteRead<$addr> for I2cMaster<$addr> {
+            type Error = crate::i2c::Error;
+
+            fn write_read(
+                &mut self,
+                address: $addr,
+                bytes: &[u8],
+                buffer: &mut [u8],
+            ) -> Result<(), Self::Error> {
+                embedded_hal::i2c::I2c::write_read(self, address, bytes, buffer)
+            }
+        }
+    };
+}
+
+impl_eh02_i2c!(SevenBitAddress);
+impl_eh02_i2c!(TenBitAddress);
+
+//==================================================================================================
+// GPIO
+//==================================================================================================
+
+impl digital::v2::OutputPin for Output {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        embedded_hal::digital::OutputPin::set_low(self)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        embedded_hal::digital::OutputPin::set_high(self)
+    }
+}
+
+impl digital::v2::StatefulOutputPin for Output {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_set_high())
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_set_low())
+    }
+}
+
+impl digital::v2::ToggleableOutputPin for Output {
+    type Error = Infallible;
+
+    /// Toggle pin output with dedicated HW feature.
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        embedded_hal::digital::StatefulOutputPin::toggle(self)
+    }
+}
+
+impl<Interrupts> digital::v2::InputPin for Input<Interrupts> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_high())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(self.is_low())
+    }
+}
+
+//==================================================================================================
+// UART
+//==================================================================================================
+
+impl serial::Read<u8> for Rx {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        embedded_hal_nb::serial::Read::read(self)
+    }
+}
+
+impl serial::Write<u8> for Tx {
+    type Error = Infallible;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        embedded_hal_nb::serial::Write::write(self, word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        embedded_hal_nb::serial::Write::flush(self)
+    }
+}
+
+impl blocking::serial::write::Default<u8> for Tx {}
+
+//==================================================================================================
+// Timer
+//==================================================================================================
+
+impl timer::CountDown for CountdownTimer {
+    type Time = Microseconds;
+
+    fn start<T>(&mut self, count: T)
+    where
+        T: Into<Self::Time>,
+    {
+        self.load_duration(count);
+        self.enable();
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Void> {
+        match CountdownTimer::wait(self) {
+            Ok(()) => Ok(()),
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(e)) => match e {},
+        }
+    }
+}
+
+impl timer::Periodic for CountdownTimer {}
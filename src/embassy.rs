@@ -1,10 +1,13 @@
+pub mod irqs;
+
 use core::cell::{Cell, RefCell};
 
 use crate::{
     enable_nvic_interrupt,
+    time::{Timekeeper, tick_scale},
     timer::{
         TimId, TimInstance, assert_tim_reset_for_cycles, enable_tim_clk,
-        regs::{EnableControl, MmioTimer},
+        regs::{EnableControl, MmioTimer, Timer},
     },
 };
 use critical_section::{CriticalSection, Mutex};
@@ -12,16 +15,13 @@ use embassy_time_driver::TICK_HZ;
 use embassy_time_driver::{Driver, time_driver_impl};
 use embassy_time_queue_utils::Queue;
 use once_cell::sync::OnceCell;
-use portable_atomic::{AtomicU32, Ordering};
 
 #[cfg(feature = "vor1x")]
 use crate::time::Hertz;
-#[cfg(feature = "vor1x")]
-use crate::{PeripheralSelect, enable_peripheral_clock};
 
 time_driver_impl!(
     static TIME_DRIVER: TimerDriver = TimerDriver {
-        periods: AtomicU32::new(0),
+        timekeeper: Timekeeper::new(),
         alarms: Mutex::new(AlarmState::new()),
         queue: Mutex::new(RefCell::new(Queue::new())),
 });
@@ -45,12 +45,10 @@ impl AlarmState {
 
 unsafe impl Send for AlarmState {}
 
-static SCALE: OnceCell<u64> = OnceCell::new();
-static TIMEKEEPER_TIM: OnceCell<TimId> = OnceCell::new();
 static ALARM_TIM: OnceCell<TimId> = OnceCell::new();
 
 pub struct TimerDriver {
-    periods: AtomicU32,
+    timekeeper: Timekeeper,
     /// Timestamp at which to fire alarm. u64::MAX if no alarm is scheduled.
     alarms: Mutex<AlarmState>,
     queue: Mutex<RefCell<Queue>>,
@@ -67,35 +65,16 @@ impl TimerDriver {
         timekeeper_irq: va108xx::Interrupt,
         alarm_irq: va108xx::Interrupt,
     ) {
-        if ALARM_TIM.get().is_some() || TIMEKEEPER_TIM.get().is_some() {
+        if ALARM_TIM.get().is_some() || self.timekeeper.is_initialized() {
             return;
         }
         ALARM_TIM.set(AlarmTim::ID).ok();
-        TIMEKEEPER_TIM.set(TimekeeperTim::ID).ok();
-        enable_peripheral_clock(PeripheralSelect::Irqsel);
-        enable_tim_clk(TimekeeperTim::ID);
-        assert_tim_reset_for_cycles(TimekeeperTim::ID, 2);
-
-        let mut timekeeper_reg_block = unsafe { TimekeeperTim::ID.steal_regs() };
-        let mut alarm_tim_reg_block = unsafe { AlarmTim::ID.steal_regs() };
-        // Initiate scale value here. This is required to convert timer ticks back to a timestamp.
-        SCALE.set((sysclk.raw() / TICK_HZ as u32) as u64).unwrap();
-        timekeeper_reg_block.write_reset_value(u32::MAX);
-        // Decrementing counter.
-        timekeeper_reg_block.write_count_value(u32::MAX);
-        let irqsel = unsafe { va108xx::Irqsel::steal() };
-        // Switch on. Timekeeping should always be done.
-        irqsel
-            .tim(TimekeeperTim::ID.value() as usize)
-            .write(|w| unsafe { w.bits(timekeeper_irq as u32) });
-        unsafe {
-            enable_nvic_interrupt(timekeeper_irq);
-        }
-        timekeeper_reg_block.modify_control(|mut value| {
-            value.set_irq_enable(true);
-            value
-        });
-        timekeeper_reg_block.write_enable_control(EnableControl::new_enable());
+        let scale =
+            tick_scale(sysclk, TICK_HZ).expect("system clock is slower than the embassy tick rate");
+        self.timekeeper
+            .init(TimekeeperTim::ID, scale, timekeeper_irq);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(AlarmTim::ID));
+        let mut alarm_tim_reg_block = Timer::new_mmio(AlarmTim::ID);
 
         enable_tim_clk(AlarmTim::ID);
         assert_tim_reset_for_cycles(AlarmTim::ID, 2);
@@ -110,9 +89,7 @@ impl TimerDriver {
         unsafe {
             enable_nvic_interrupt(alarm_irq);
         }
-        irqsel
-            .tim(AlarmTim::ID.value() as usize)
-            .write(|w| unsafe { w.bits(alarm_irq as u32) });
+        crate::irqsel::route_tim(AlarmTim::ID, alarm_irq);
     }
 
     #[cfg(feature = "vor4x")]
@@ -123,34 +100,16 @@ impl TimerDriver {
         _alarm_tim: AlarmTim,
         clocks: &crate::clock::Clocks,
     ) {
-        if ALARM_TIM.get().is_some() || TIMEKEEPER_TIM.get().is_some() {
+        if ALARM_TIM.get().is_some() || self.timekeeper.is_initialized() {
             return;
         }
         ALARM_TIM.set(AlarmTim::ID).ok();
-        TIMEKEEPER_TIM.set(TimekeeperTim::ID).ok();
-        let mut timekeeper_regs = unsafe { TimekeeperTim::ID.steal_regs() };
-        let mut alarm_regs = unsafe { AlarmTim::ID.steal_regs() };
-
-        enable_tim_clk(TimekeeperTim::ID);
-        assert_tim_reset_for_cycles(TimekeeperTim::ID, 2);
-
-        // Initiate scale value here. This is required to convert timer ticks back to a timestamp.
-
-        SCALE
-            .set((TimekeeperTim::clock(clocks).raw() / TICK_HZ as u32) as u64)
-            .unwrap();
-        timekeeper_regs.write_reset_value(u32::MAX);
-        // Decrementing counter.
-        timekeeper_regs.write_count_value(u32::MAX);
-        // Switch on. Timekeeping should always be done.
-        unsafe {
-            enable_nvic_interrupt(TimekeeperTim::IRQ);
-        }
-        timekeeper_regs.modify_control(|mut value| {
-            value.set_irq_enable(true);
-            value
-        });
-        timekeeper_regs.write_enable_control(EnableControl::new_enable());
+        let scale = tick_scale(TimekeeperTim::clock(clocks), TICK_HZ)
+            .expect("timekeeper reference clock is slower than the embassy tick rate");
+        self.timekeeper
+            .init(TimekeeperTim::ID, scale, TimekeeperTim::IRQ);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(AlarmTim::ID));
+        let mut alarm_regs = Timer::new_mmio(AlarmTim::ID);
 
         enable_tim_clk(AlarmTim::ID);
         assert_tim_reset_for_cycles(AlarmTim::ID, 2);
@@ -166,16 +125,11 @@ impl TimerDriver {
         }
     }
 
-    fn timekeeper_tim() -> MmioTimer<'static> {
-        TIMEKEEPER_TIM
-            .get()
-            .map(|tim| unsafe { tim.steal_regs() })
-            .unwrap()
-    }
     fn alarm_tim() -> MmioTimer<'static> {
         ALARM_TIM
             .get()
-            .map(|tim| unsafe { tim.steal_regs() })
+            // Safety: The alarm timer is owned by the driver.
+            .map(|tim| unsafe { tim.force_steal_regs() })
             .unwrap()
     }
 
@@ -202,7 +156,7 @@ impl TimerDriver {
     }
 
     fn next_period(&self) {
-        let period = self.periods.fetch_add(1, Ordering::AcqRel) + 1;
+        let period = self.timekeeper.next_period();
         let t = (period as u64) << 32;
         critical_section::with(|cs| {
             let alarm = &self.alarms.borrow(cs);
@@ -212,7 +166,7 @@ impl TimerDriver {
             } else {
                 let mut alarm_tim = Self::alarm_tim();
 
-                let remaining_ticks = (at - t).checked_mul(*SCALE.get().unwrap());
+                let remaining_ticks = (at - t).checked_mul(self.timekeeper.scale().unwrap());
                 if remaining_ticks.is_some_and(|v| v <= u32::MAX as u64) {
                     alarm_tim.write_enable_control(EnableControl::new_disable());
                     alarm_tim.write_count_value(remaining_ticks.unwrap() as u32);
@@ -253,7 +207,7 @@ impl TimerDriver {
     }
 
     fn set_alarm(&self, cs: CriticalSection, timestamp: u64) -> bool {
-        if SCALE.get().is_none() {
+        if self.timekeeper.scale().is_none() {
             return false;
         }
         let mut alarm_tim = Self::alarm_tim();
@@ -283,7 +237,7 @@ impl TimerDriver {
         // by the Alarm trait contract. What's not allowed is triggering alarms *before* their scheduled time,
         // and we don't do that here.
         let safe_timestamp = timestamp.max(t + 3);
-        let timer_ticks = (safe_timestamp - t).checked_mul(*SCALE.get().unwrap());
+        let timer_ticks = (safe_timestamp - t).checked_mul(self.timekeeper.scale().unwrap());
         alarm_tim.write_reset_value(u32::MAX);
         if timer_ticks.is_some_and(|v| v <= u32::MAX as u64) {
             alarm_tim.write_count_value(timer_ticks.unwrap() as u32);
@@ -302,27 +256,7 @@ impl TimerDriver {
 
 impl Driver for TimerDriver {
     fn now(&self) -> u64 {
-        if SCALE.get().is_none() {
-            return 0;
-        }
-        let mut period1: u32;
-        let mut period2: u32;
-        let mut counter_val: u32;
-
-        loop {
-            // Acquire ensures that we get the latest value of `periods` and
-            // no instructions can be reordered before the load.
-            period1 = self.periods.load(Ordering::Acquire);
-
-            counter_val = u32::MAX - Self::timekeeper_tim().read_count_value();
-
-            // Double read to protect against race conditions when the counter is overflowing.
-            period2 = self.periods.load(Ordering::Relaxed);
-            if period1 == period2 {
-                let now = (((period1 as u64) << 32) | counter_val as u64) / *SCALE.get().unwrap();
-                return now;
-            }
-        }
+        self.timekeeper.now()
     }
 
     fn schedule_wake(&self, at: u64, waker: &core::task::Waker) {
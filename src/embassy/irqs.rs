@@ -0,0 +1,201 @@
+//! # Interrupt handler declaration macros
+//!
+//! The asynchronous drivers and the embassy time driver require the user to declare interrupt
+//! handlers which call the generic interrupt handlers provided by this crate. The following
+//! macros expand to these interrupt handlers for the selected chip family:
+//!
+//! - [embassy_time_driver_irqs](crate::embassy_time_driver_irqs) for the embassy time driver.
+//! - [uart_rx_async_irqs](crate::uart_rx_async_irqs) for asynchronous UART reception.
+//! - [uart_tx_async_irqs](crate::uart_tx_async_irqs) for asynchronous UART transmission.
+//! - [gpio_async_irqs](crate::gpio_async_irqs) for asynchronous GPIO pins.
+//! - [gpio_wakeup_irqs](crate::gpio_wakeup_irqs) for the blocking GPIO edge waits with WFI.
+//!
+//! The interrupt names are checked against the interrupt enumeration of the PAC. Each handler is
+//! declared as a function named after its interrupt, so claiming the same interrupt twice in one
+//! module is a compile error, and claiming it twice in different modules is a link error.
+//!
+//! The macros only declare the interrupt handlers. Routing the peripheral interrupts to the
+//! interrupt lines on Vorago 1x devices and enabling the interrupts in the NVIC is still done
+//! by the respective drivers and initialization functions.
+//!
+//! ## Example for Vorago 1x devices
+//!
+//! ```rust,ignore
+//! use core::cell::RefCell;
+//!
+//! use critical_section::Mutex;
+//! use embassy_executor::Spawner;
+//! use embassy_time::Timer;
+//! use heapless::spsc::{Producer, Queue};
+//! use vorago_shared_hal as hal;
+//!
+//! static UART0_PRODUCER: Mutex<RefCell<Option<Producer<'static, u8>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! // The timekeeper and alarm timer interrupts are routed to OC30 and OC29 when the time
+//! // driver is initialized.
+//! hal::embassy_time_driver_irqs!(timekeeper = OC30, alarm = OC29);
+//! // UART0 is routed to OC2 with the InterruptConfig passed to the UART constructor. Both RX and
+//! // TX are handled by the same interrupt line.
+//! hal::uart_rx_async_irqs!(Uart0 => OC2, producer = UART0_PRODUCER, with_tx);
+//! // Port A pin interrupts are routed to OC10 with the InterruptConfig of the pins.
+//! hal::gpio_async_irqs!(PortA => OC10);
+//!
+//! #[embassy_executor::main]
+//! async fn main(_spawner: Spawner) {
+//!     // Initialize the time driver, for example with the initialization function of the
+//!     // family HAL, and create the asynchronous drivers.
+//!     loop {
+//!         Timer::after_millis(500).await;
+//!     }
+//! }
+//! ```
+//!
+//! ## Example for Vorago 4x devices
+//!
+//! ```rust,ignore
+//! use core::cell::RefCell;
+//!
+//! use critical_section::Mutex;
+//! use embassy_executor::Spawner;
+//! use embassy_time::Timer;
+//! use heapless::spsc::{Producer, Queue};
+//! use vorago_shared_hal as hal;
+//!
+//! static UART0_PRODUCER: Mutex<RefCell<Option<Producer<'static, u8>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! // Each timer has a dedicated interrupt.
+//! hal::embassy_time_driver_irqs!(timekeeper = TIM23, alarm = TIM22);
+//! // RX and TX have dedicated interrupts.
+//! hal::uart_rx_async_irqs!(Uart0 => UART0_RX, producer = UART0_PRODUCER);
+//! hal::uart_tx_async_irqs!(Uart0 => UART0_TX);
+//! // Each pin has a dedicated interrupt.
+//! hal::gpio_async_irqs!(PortA => PORTA0, PortA => PORTA1);
+//!
+//! #[embassy_executor::main]
+//! async fn main(_spawner: Spawner) {
+//!     // Initialize the time driver, for example with the initialization function of the
+//!     // family HAL, and create the asynchronous drivers.
+//!     loop {
+//!         Timer::after_millis(500).await;
+//!     }
+//! }
+//! ```
+
+/// Declare the interrupt handlers of the embassy time driver.
+///
+/// The interrupts must be the interrupts of the timekeeper and alarm timers passed to the
+/// time driver initialization. See the [module documentation](crate::embassy::irqs).
+#[macro_export]
+macro_rules! embassy_time_driver_irqs {
+    (timekeeper = $timekeeper:ident, alarm = $alarm:ident $(,)?) => {
+        $crate::__declare_irq!($timekeeper, {
+            // Safety: Called once in the timekeeper interrupt.
+            unsafe { $crate::embassy::time_driver().on_interrupt_timekeeping() }
+        });
+        $crate::__declare_irq!($alarm, {
+            // Safety: Called once in the alarm interrupt.
+            unsafe { $crate::embassy::time_driver().on_interrupt_alarm() }
+        });
+    };
+}
+
+/// Declare the interrupt handler for asynchronous UART reception.
+///
+/// The producer must be a static of type
+/// `critical_section::Mutex<RefCell<Option<heapless::spsc::Producer<'static, u8>>>>`. The handler
+/// calls [on_interrupt_rx](crate::uart::on_interrupt_rx) if the producer was set. Reception
+/// errors are ignored, a manually written interrupt handler is required to handle them.
+///
+/// On Vorago 1x devices, RX and TX interrupts of a UART share one interrupt line. The optional
+/// `with_tx` argument additionally calls [on_interrupt_tx](crate::uart::on_interrupt_tx) for
+/// that case. See the [module documentation](crate::embassy::irqs).
+#[macro_export]
+macro_rules! uart_rx_async_irqs {
+    (@rx $bank:ident, $producer:path) => {
+        $crate::__macro_support::critical_section::with(|cs| {
+            if let Some(prod) = $producer.borrow(cs).borrow_mut().as_mut() {
+                let _ = $crate::uart::on_interrupt_rx($crate::uart::Bank::$bank, prod);
+            }
+        })
+    };
+    ($bank:ident => $irq:ident, producer = $producer:path, with_tx $(,)?) => {
+        $crate::__declare_irq!($irq, {
+            $crate::uart_rx_async_irqs!(@rx $bank, $producer);
+            $crate::uart::on_interrupt_tx($crate::uart::Bank::$bank);
+        });
+    };
+    ($bank:ident => $irq:ident, producer = $producer:path $(,)?) => {
+        $crate::__declare_irq!($irq, {
+            $crate::uart_rx_async_irqs!(@rx $bank, $producer);
+        });
+    };
+}
+
+/// Declare the interrupt handler for asynchronous UART transmission.
+///
+/// The handler calls [on_interrupt_tx](crate::uart::on_interrupt_tx). See the
+/// [module documentation](crate::embassy::irqs).
+#[macro_export]
+macro_rules! uart_tx_async_irqs {
+    ($($bank:ident => $irq:ident),+ $(,)?) => {
+        $(
+            $crate::__declare_irq!($irq, {
+                $crate::uart::on_interrupt_tx($crate::uart::Bank::$bank);
+            });
+        )+
+    };
+}
+
+/// Declare the interrupt handlers for asynchronous GPIO pins.
+///
+/// The handlers call [on_interrupt_for_async_gpio_for_port](crate::gpio::asynch::on_interrupt_for_async_gpio_for_port)
+/// for the given port. The ports are specified as `PortA`, `PortB` and so on. See the
+/// [module documentation](crate::embassy::irqs).
+#[macro_export]
+macro_rules! gpio_async_irqs {
+    (@port PortA) => { $crate::Port::A };
+    (@port PortB) => { $crate::Port::B };
+    (@port PortC) => { $crate::Port::C };
+    (@port PortD) => { $crate::Port::D };
+    (@port PortE) => { $crate::Port::E };
+    (@port PortF) => { $crate::Port::F };
+    ($($port:ident => $irq:ident),+ $(,)?) => {
+        $(
+            $crate::__declare_irq!($irq, {
+                let _ = $crate::gpio::asynch::on_interrupt_for_async_gpio_for_port(
+                    $crate::gpio_async_irqs!(@port $port),
+                );
+            });
+        )+
+    };
+}
+
+/// Declare empty interrupt handlers for GPIO wake-ups.
+///
+/// [Input::wait_for_edge_blocking](crate::gpio::Input::wait_for_edge_blocking) only requires
+/// the interrupt to wake the CPU from WFI, so the handler does not need to do anything. See
+/// the [module documentation](crate::embassy::irqs).
+#[macro_export]
+macro_rules! gpio_wakeup_irqs {
+    ($($irq:ident),+ $(,)?) => {
+        $(
+            $crate::__declare_irq!($irq, {});
+        )+
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __declare_irq {
+    ($irq:ident, $body:block) => {
+        // Checks that the interrupt exists for the selected family.
+        const _: $crate::__macro_support::pac::Interrupt =
+            $crate::__macro_support::pac::Interrupt::$irq;
+
+        #[allow(non_snake_case)]
+        #[unsafe(no_mangle)]
+        extern "C" fn $irq() $body
+    };
+}
@@ -6,16 +6,16 @@
 //! which must be provided for async support to work. However, it provides the
 //! [on_interrupt_for_async_gpio_for_port] generic interrupt handler. This should be called in all
 //! IRQ functions which handle any GPIO interrupts with the corresponding [Port] argument.
-use core::future::Future;
+use core::{future::Future, marker::PhantomData};
 
 use embassy_sync::waitqueue::AtomicWaker;
 use embedded_hal_async::digital::Wait;
-use portable_atomic::AtomicBool;
+use portable_atomic::{AtomicU32, Ordering};
 
 #[cfg(feature = "vor4x")]
 use crate::NUM_PORT_DEFAULT;
 #[cfg(feature = "vor1x")]
-use crate::{InterruptConfig, NUM_PORT_A, NUM_PORT_B};
+use crate::{NUM_PORT_A, NUM_PORT_B};
 
 #[cfg(feature = "vor4x")]
 use super::ll::PortDoesNotSupportInterrupts;
@@ -26,17 +26,25 @@ use va108xx as pac;
 pub use super::ll::InterruptEdge;
 use super::{
     Input, Port,
-    ll::{DynPinId, LowLevelGpio},
+    ll::{DynPinId, GpioIrqView, LowLevelGpio},
 };
+use crate::pins::InterruptCapable;
+#[cfg(feature = "async-timeouts")]
+use crate::time::{TimeoutError, with_timeout};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "vor1x")] {
         static WAKERS_FOR_PORT_A: [AtomicWaker; NUM_PORT_A] = [const { AtomicWaker::new() }; NUM_PORT_A];
         static WAKERS_FOR_PORT_B: [AtomicWaker; NUM_PORT_B] = [const { AtomicWaker::new() }; NUM_PORT_B];
-        static EDGE_DETECTION_PORT_A: [AtomicBool; NUM_PORT_A] =
-            [const { AtomicBool::new(false) }; NUM_PORT_A];
-        static EDGE_DETECTION_PORT_B: [AtomicBool; NUM_PORT_B] =
-            [const { AtomicBool::new(false) }; NUM_PORT_B];
+        static EDGE_COUNT_PORT_A: [AtomicU32; NUM_PORT_A] =
+            [const { AtomicU32::new(0) }; NUM_PORT_A];
+        static EDGE_COUNT_PORT_B: [AtomicU32; NUM_PORT_B] =
+            [const { AtomicU32::new(0) }; NUM_PORT_B];
+        static EDGE_TOTAL_PORT_A: [AtomicU32; NUM_PORT_A] =
+            [const { AtomicU32::new(0) }; NUM_PORT_A];
+        static EDGE_TOTAL_PORT_B: [AtomicU32; NUM_PORT_B] =
+            [const { AtomicU32::new(0) }; NUM_PORT_B];
+        static EDGE_COUNTING_PINS: [AtomicU32; 2] = [const { AtomicU32::new(0) }; 2];
     } else {
         static WAKERS_FOR_PORT_A: [AtomicWaker; NUM_PORT_DEFAULT] =
             [const { AtomicWaker::new() }; NUM_PORT_DEFAULT];
@@ -51,38 +59,86 @@ cfg_if::cfg_if! {
         static WAKERS_FOR_PORT_F: [AtomicWaker; NUM_PORT_DEFAULT] =
             [const { AtomicWaker::new() }; NUM_PORT_DEFAULT];
 
-        static EDGE_DETECTION_PORT_A: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
-        static EDGE_DETECTION_PORT_B: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
-        static EDGE_DETECTION_PORT_C: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
-        static EDGE_DETECTION_PORT_D: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
-        static EDGE_DETECTION_PORT_E: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
-        static EDGE_DETECTION_PORT_F: [AtomicBool; NUM_PORT_DEFAULT] =
-            [const { AtomicBool::new(false) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_A: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_B: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_C: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_D: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_E: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNT_PORT_F: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+
+        static EDGE_TOTAL_PORT_A: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_TOTAL_PORT_B: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_TOTAL_PORT_C: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_TOTAL_PORT_D: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_TOTAL_PORT_E: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_TOTAL_PORT_F: [AtomicU32; NUM_PORT_DEFAULT] =
+            [const { AtomicU32::new(0) }; NUM_PORT_DEFAULT];
+        static EDGE_COUNTING_PINS: [AtomicU32; 6] = [const { AtomicU32::new(0) }; 6];
     }
 }
 
+/// Returns [None] for ports which do not support interrupts.
 #[inline]
-fn pin_group_to_waker_and_edge_detection_group(
+fn pin_group_to_waker_and_edge_count_group(
     port: Port,
-) -> (&'static [AtomicWaker], &'static [AtomicBool]) {
+) -> Option<(&'static [AtomicWaker], &'static [AtomicU32])> {
     match port {
-        Port::A => (WAKERS_FOR_PORT_A.as_ref(), EDGE_DETECTION_PORT_A.as_ref()),
-        Port::B => (WAKERS_FOR_PORT_B.as_ref(), EDGE_DETECTION_PORT_B.as_ref()),
+        Port::A => Some((WAKERS_FOR_PORT_A.as_ref(), EDGE_COUNT_PORT_A.as_ref())),
+        Port::B => Some((WAKERS_FOR_PORT_B.as_ref(), EDGE_COUNT_PORT_B.as_ref())),
         #[cfg(feature = "vor4x")]
-        Port::C => (WAKERS_FOR_PORT_C.as_ref(), EDGE_DETECTION_PORT_C.as_ref()),
+        Port::C => Some((WAKERS_FOR_PORT_C.as_ref(), EDGE_COUNT_PORT_C.as_ref())),
         #[cfg(feature = "vor4x")]
-        Port::D => (WAKERS_FOR_PORT_D.as_ref(), EDGE_DETECTION_PORT_D.as_ref()),
+        Port::D => Some((WAKERS_FOR_PORT_D.as_ref(), EDGE_COUNT_PORT_D.as_ref())),
         #[cfg(feature = "vor4x")]
-        Port::E => (WAKERS_FOR_PORT_E.as_ref(), EDGE_DETECTION_PORT_E.as_ref()),
+        Port::E => Some((WAKERS_FOR_PORT_E.as_ref(), EDGE_COUNT_PORT_E.as_ref())),
         #[cfg(feature = "vor4x")]
-        Port::F => (WAKERS_FOR_PORT_F.as_ref(), EDGE_DETECTION_PORT_F.as_ref()),
+        Port::F => Some((WAKERS_FOR_PORT_F.as_ref(), EDGE_COUNT_PORT_F.as_ref())),
         #[cfg(feature = "vor4x")]
-        Port::G => unreachable!(),
+        Port::G => None,
+    }
+}
+
+/// Edge total counters which accumulate the edges of the pins in edge counting mode, and the
+/// bitmask of these pins. Returns [None] for ports which do not support interrupts.
+#[inline]
+fn edge_total_group(port: Port) -> Option<(&'static [AtomicU32], &'static AtomicU32)> {
+    let totals: &'static [AtomicU32] = match port {
+        Port::A => EDGE_TOTAL_PORT_A.as_ref(),
+        Port::B => EDGE_TOTAL_PORT_B.as_ref(),
+        #[cfg(feature = "vor4x")]
+        Port::C => EDGE_TOTAL_PORT_C.as_ref(),
+        #[cfg(feature = "vor4x")]
+        Port::D => EDGE_TOTAL_PORT_D.as_ref(),
+        #[cfg(feature = "vor4x")]
+        Port::E => EDGE_TOTAL_PORT_E.as_ref(),
+        #[cfg(feature = "vor4x")]
+        Port::F => EDGE_TOTAL_PORT_F.as_ref(),
+        #[cfg(feature = "vor4x")]
+        Port::G => return None,
+    };
+    Some((totals, &EDGE_COUNTING_PINS[port as usize]))
+}
+
+/// Add or remove the pin from the pins whose edges are accumulated in the edge total counter.
+fn set_edge_counting(id: DynPinId, enable: bool) {
+    // Unwrap okay, only called for pins which support interrupts.
+    let (_, counting) = edge_total_group(id.port()).unwrap();
+    let mask = 1 << id.offset();
+    if enable {
+        counting.fetch_or(mask, Ordering::Relaxed);
+    } else {
+        counting.fetch_and(!mask, Ordering::Relaxed);
     }
 }
 
@@ -91,7 +147,7 @@ fn pin_group_to_waker_and_edge_detection_group(
 /// This function should be called in all interrupt handlers which handle any GPIO interrupts
 /// matching the [Port] argument.
 /// The handler will wake the corresponding wakers for the pins that triggered an interrupts
-/// as well as update the static edge detection structures. This allows the pin future to complete
+/// as well as increment the static edge counters of these pins. This allows the pin futures to
 /// complete async operations.
 #[cfg(feature = "vor1x")]
 pub fn on_interrupt_for_async_gpio_for_port(port: Port) {
@@ -109,21 +165,36 @@ pub fn on_interrupt_for_async_gpio_for_port(
 }
 
 fn on_interrupt_for_async_gpio_for_port_generic(port: Port) {
-    let gpio = unsafe { port.steal_gpio() };
-
-    let irq_enb = gpio.read_irq_enable();
-    let edge_status = gpio.read_edge_status();
-    let (wakers, edge_detection) = pin_group_to_waker_and_edge_detection_group(port);
-
-    on_interrupt_for_port(irq_enb, edge_status, wakers, edge_detection);
+    // Never panic inside an ISR, even if the interrupt was mis-routed.
+    let (Some((wakers, edge_counts)), Some((edge_totals, counting))) = (
+        pin_group_to_waker_and_edge_count_group(port),
+        edge_total_group(port),
+    ) else {
+        return;
+    };
+    let view = GpioIrqView::new(port);
+
+    let irq_enb = view.irq_enabled();
+    let edge_status = view.edge_status();
+
+    on_interrupt_for_port(
+        irq_enb,
+        edge_status,
+        wakers,
+        edge_counts,
+        counting.load(Ordering::Relaxed),
+        edge_totals,
+    );
 }
 
 #[inline]
 fn on_interrupt_for_port(
     mut irq_enb: u32,
     edge_status: u32,
-    wakers: &'static [AtomicWaker],
-    edge_detection: &'static [AtomicBool],
+    wakers: &[AtomicWaker],
+    edge_counts: &[AtomicU32],
+    counting: u32,
+    edge_totals: &[AtomicU32],
 ) {
     while irq_enb != 0 {
         let bit_pos = irq_enb.trailing_zeros() as usize;
@@ -132,14 +203,58 @@ fn on_interrupt_for_port(
         wakers[bit_pos].wake();
 
         if edge_status & bit_mask != 0 {
-            edge_detection[bit_pos].store(true, core::sync::atomic::Ordering::Relaxed);
-
-            // Clear the processed bit
-            irq_enb &= !bit_mask;
+            saturating_increment(&edge_counts[bit_pos]);
+            if counting & bit_mask != 0 {
+                saturating_increment(&edge_totals[bit_pos]);
+            }
         }
+        // Clear the processed bit
+        irq_enb &= !bit_mask;
     }
 }
 
+/// Increment the edge counter. The counter saturates at [u32::MAX] instead of wrapping, so
+/// a counter which was not read for a long time never appears to have seen fewer edges.
+#[inline]
+fn saturating_increment(counter: &AtomicU32) {
+    // An error is only returned if the counter is already saturated.
+    let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+        count.checked_add(1)
+    });
+}
+
+/// Prepare the edge counter and the interrupt of the pin for a future.
+#[cfg(feature = "vor1x")]
+fn setup_edge_interrupt(
+    pin: &mut Input,
+    irq: pac::Interrupt,
+    edge: InterruptEdge,
+) -> (&'static [AtomicWaker], &'static [AtomicU32]) {
+    // Unwrap okay, all ports support interrupts.
+    let (waker_group, edge_count_group) =
+        pin_group_to_waker_and_edge_count_group(pin.id().port()).unwrap();
+    edge_count_group[pin.id().offset()].store(0, Ordering::Relaxed);
+    pin.0.arm_edge_interrupt(irq, edge);
+    (waker_group, edge_count_group)
+}
+
+/// Prepare the edge counter and the interrupt of the pin for a future.
+#[cfg(feature = "vor4x")]
+fn setup_edge_interrupt(
+    pin: &mut Input,
+    edge: InterruptEdge,
+) -> Result<(&'static [AtomicWaker], &'static [AtomicU32]), PortDoesNotSupportInterrupts> {
+    let (waker_group, edge_count_group) = pin_group_to_waker_and_edge_count_group(pin.id().port())
+        .ok_or(PortDoesNotSupportInterrupts)?;
+    edge_count_group[pin.id().offset()].store(0, Ordering::Relaxed);
+    pin.0.arm_edge_interrupt(edge)?;
+    Ok((waker_group, edge_count_group))
+}
+
+fn disable_edge_interrupt(id: DynPinId) {
+    LowLevelGpio::new(id).disarm_edge_interrupt();
+}
+
 /// Input pin future which implements the [Future] trait.
 ///
 /// Generally, you want to use the [InputPinAsync] types instead of this
@@ -148,22 +263,21 @@ fn on_interrupt_for_port(
 pub struct InputPinFuture {
     id: DynPinId,
     waker_group: &'static [AtomicWaker],
-    edge_detection_group: &'static [AtomicBool],
+    edge_count_group: &'static [AtomicU32],
+    /// Re-arm the rising edge interrupt of the edge counting mode of [InputPinAsync] on drop,
+    /// instead of disabling the interrupt.
+    restore_edge_counting: bool,
 }
 
 impl InputPinFuture {
     #[cfg(feature = "vor1x")]
     pub fn new_with_input_pin(pin: &mut Input, irq: pac::Interrupt, edge: InterruptEdge) -> Self {
-        let (waker_group, edge_detection_group) =
-            pin_group_to_waker_and_edge_detection_group(pin.id().port());
-        edge_detection_group[pin.id().offset()].store(false, core::sync::atomic::Ordering::Relaxed);
-        pin.configure_edge_interrupt(edge);
-        #[cfg(feature = "vor1x")]
-        pin.enable_interrupt(InterruptConfig::new(irq, true, true));
+        let (waker_group, edge_count_group) = setup_edge_interrupt(pin, irq, edge);
         Self {
             id: pin.id(),
             waker_group,
-            edge_detection_group,
+            edge_count_group,
+            restore_edge_counting: false,
         }
     }
     #[cfg(feature = "vor4x")]
@@ -171,25 +285,31 @@ impl InputPinFuture {
         pin: &mut Input,
         edge: InterruptEdge,
     ) -> Result<Self, PortDoesNotSupportInterrupts> {
-        let (waker_group, edge_detection_group) =
-            pin_group_to_waker_and_edge_detection_group(pin.id().port());
-        pin.configure_edge_interrupt(edge);
-        pin.enable_interrupt(true)?;
+        let (waker_group, edge_count_group) = setup_edge_interrupt(pin, edge)?;
         Ok(Self {
             id: pin.id(),
             waker_group,
-            edge_detection_group,
+            edge_count_group,
+            restore_edge_counting: false,
         })
     }
+
+    /// Keep the pin interrupt enabled and restore the rising edge detection of the edge
+    /// counting mode when the future is dropped.
+    fn restore_edge_counting(mut self) -> Self {
+        self.restore_edge_counting = true;
+        self
+    }
 }
 
 impl Drop for InputPinFuture {
     fn drop(&mut self) {
-        let mut ll = LowLevelGpio::new(self.id);
-        #[cfg(feature = "vor1x")]
-        ll.disable_interrupt(false);
-        #[cfg(feature = "vor4x")]
-        ll.disable_interrupt();
+        if self.restore_edge_counting {
+            LowLevelGpio::new(self.id).configure_edge_interrupt(InterruptEdge::LowToHigh);
+            set_edge_counting(self.id, true);
+        } else {
+            disable_edge_interrupt(self.id);
+        }
     }
 }
 
@@ -201,49 +321,162 @@ impl Future for InputPinFuture {
     ) -> core::task::Poll<Self::Output> {
         let idx = self.id.offset();
         self.waker_group[idx].register(cx.waker());
-        if self.edge_detection_group[idx].swap(false, core::sync::atomic::Ordering::Relaxed) {
+        // All edges which were counted since the last poll are consumed.
+        if self.edge_count_group[idx].swap(0, Ordering::Relaxed) != 0 {
             return core::task::Poll::Ready(());
         }
         core::task::Poll::Pending
     }
 }
 
+/// Edge counter future which implements the [Future] trait.
+///
+/// Unlike the [InputPinFuture], this future counts all detected edges and only completes after a
+/// minimum number of edges were detected. All counted edges are consumed and returned on
+/// completion. This is used by [InputPinAsync::wait_for_rising_edges].
+///
+/// A future created with [Self::new_with_input_pin] only counts the edges which are detected
+/// during its lifetime, and disables the pin interrupt when it is dropped.
+pub struct EdgeCounterFuture {
+    id: DynPinId,
+    min_count: u32,
+    waker_group: &'static [AtomicWaker],
+    edge_totals: &'static [AtomicU32],
+    /// Disable the pin interrupt and stop the edge counting on drop.
+    disarm_on_drop: bool,
+}
+
+impl EdgeCounterFuture {
+    #[cfg(feature = "vor1x")]
+    pub fn new_with_input_pin(
+        pin: &mut Input,
+        irq: pac::Interrupt,
+        edge: InterruptEdge,
+        min_count: u32,
+    ) -> Self {
+        let (waker_group, _) = setup_edge_interrupt(pin, irq, edge);
+        let mut fut = Self::new_for_counting_pin(pin.id(), waker_group, min_count);
+        fut.start_counting();
+        fut
+    }
+    #[cfg(feature = "vor4x")]
+    pub fn new_with_input_pin(
+        pin: &mut Input,
+        edge: InterruptEdge,
+        min_count: u32,
+    ) -> Result<Self, PortDoesNotSupportInterrupts> {
+        let (waker_group, _) = setup_edge_interrupt(pin, edge)?;
+        let mut fut = Self::new_for_counting_pin(pin.id(), waker_group, min_count);
+        fut.start_counting();
+        Ok(fut)
+    }
+
+    /// Future for a pin whose interrupt is already armed for the edge counting mode. The edges
+    /// which were counted before are included.
+    fn new_for_counting_pin(
+        id: DynPinId,
+        waker_group: &'static [AtomicWaker],
+        min_count: u32,
+    ) -> Self {
+        // Unwrap okay, only called for pins which support interrupts.
+        let (edge_totals, _) = edge_total_group(id.port()).unwrap();
+        Self {
+            id,
+            min_count,
+            waker_group,
+            edge_totals,
+            disarm_on_drop: false,
+        }
+    }
+
+    fn start_counting(&mut self) {
+        self.edge_totals[self.id.offset()].store(0, Ordering::Relaxed);
+        set_edge_counting(self.id, true);
+        self.disarm_on_drop = true;
+    }
+}
+
+impl Drop for EdgeCounterFuture {
+    fn drop(&mut self) {
+        if self.disarm_on_drop {
+            set_edge_counting(self.id, false);
+            disable_edge_interrupt(self.id);
+        }
+    }
+}
+
+impl Future for EdgeCounterFuture {
+    type Output = u32;
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Self::Output> {
+        let idx = self.id.offset();
+        self.waker_group[idx].register(cx.waker());
+        let counter = &self.edge_totals[idx];
+        if counter.load(Ordering::Relaxed) >= self.min_count {
+            return core::task::Poll::Ready(counter.swap(0, Ordering::Relaxed));
+        }
+        core::task::Poll::Pending
+    }
+}
+
+/// Asynchronous input pin.
+///
+/// The edge counting mode, which is started by [Self::start_edge_counting] or the first
+/// [Self::wait_for_rising_edges] call, keeps the rising edge interrupt of the pin enabled and
+/// accumulates the rising edges for as long as the pin exists. The counted edges are returned by
+/// [Self::wait_for_rising_edges] and [Self::take_edge_count]. The other wait operations change
+/// the detected edge while they are active and restore the rising edge detection afterwards.
+/// Edges detected by them are not counted, except for rising edges during
+/// [Self::wait_for_rising_edge].
 pub struct InputPinAsync {
     pin: Input,
     #[cfg(feature = "vor1x")]
     irq: va108xx::Interrupt,
+    edge_counting: bool,
 }
 
 impl InputPinAsync {
     /// Create a new asynchronous input pin from an [Input] pin. The interrupt ID to be used must be
     /// passed as well and is used to route and enable the interrupt.
     ///
+    /// Only [Input] pins of interrupt capable pins are accepted, which excludes the port G pins
+    /// of the VA416xx family at compile time, see [crate::pins::InterruptCapablePin].
+    ///
     /// Please note that the interrupt handler itself must be provided by the user and the
     /// generic [on_interrupt_for_async_gpio_for_port] function must be called inside that function
     /// for the asynchronous functionality to work.
     #[cfg(feature = "vor1x")]
-    pub fn new(pin: Input, irq: va108xx::Interrupt) -> Self {
-        Self { pin, irq }
+    pub fn new(pin: Input<InterruptCapable>, irq: va108xx::Interrupt) -> Self {
+        Self {
+            pin,
+            irq,
+            edge_counting: false,
+        }
     }
+
+    /// Create a new asynchronous input pin from an [Input] pin.
+    ///
+    /// Only [Input] pins of interrupt capable pins are accepted, which excludes the port G pins
+    /// at compile time, see [crate::pins::InterruptCapablePin].
+    ///
+    /// Please note that the interrupt handler itself must be provided by the user and the
+    /// generic [on_interrupt_for_async_gpio_for_port] function must be called inside that function
+    /// for the asynchronous functionality to work.
     #[cfg(feature = "vor4x")]
-    pub fn new(pin: Input) -> Result<Self, PortDoesNotSupportInterrupts> {
-        if pin.id().port() == Port::G {
-            return Err(PortDoesNotSupportInterrupts);
+    pub fn new(pin: Input<InterruptCapable>) -> Self {
+        Self {
+            pin,
+            edge_counting: false,
         }
-        Ok(Self { pin })
     }
 
     /// Asynchronously wait until the pin is high.
     ///
     /// This returns immediately if the pin is already high.
     pub async fn wait_for_high(&mut self) {
-        // Unwrap okay, checked pin in constructor.
-        #[cfg(feature = "vor1x")]
-        let fut =
-            InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, InterruptEdge::LowToHigh);
-        #[cfg(feature = "vor4x")]
-        let fut =
-            InputPinFuture::new_with_input_pin(&mut self.pin, InterruptEdge::LowToHigh).unwrap();
+        let fut = self.edge_future(InterruptEdge::LowToHigh);
         if self.pin.is_high() {
             return;
         }
@@ -254,13 +487,7 @@ impl InputPinAsync {
     ///
     /// This returns immediately if the pin is already high.
     pub async fn wait_for_low(&mut self) {
-        // Unwrap okay, checked pin in constructor.
-        #[cfg(feature = "vor1x")]
-        let fut =
-            InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, InterruptEdge::HighToLow);
-        #[cfg(feature = "vor4x")]
-        let fut =
-            InputPinFuture::new_with_input_pin(&mut self.pin, InterruptEdge::HighToLow).unwrap();
+        let fut = self.edge_future(InterruptEdge::HighToLow);
         if self.pin.is_low() {
             return;
         }
@@ -269,35 +496,186 @@ impl InputPinAsync {
 
     /// Asynchronously wait until the pin sees a falling edge.
     pub async fn wait_for_falling_edge(&mut self) {
-        // Unwrap okay, checked pin in constructor.
-        #[cfg(feature = "vor1x")]
-        InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, InterruptEdge::HighToLow).await;
-        #[cfg(feature = "vor4x")]
-        InputPinFuture::new_with_input_pin(&mut self.pin, InterruptEdge::HighToLow)
-            .unwrap()
-            .await;
+        self.edge_future(InterruptEdge::HighToLow).await;
     }
 
     /// Asynchronously wait until the pin sees a rising edge.
     pub async fn wait_for_rising_edge(&mut self) {
+        self.edge_future(InterruptEdge::LowToHigh).await;
+    }
+
+    /// Create the future for a wait operation. In the edge counting mode, the rising edge
+    /// detection is restored when the future is dropped.
+    fn edge_future(&mut self, edge: InterruptEdge) -> InputPinFuture {
+        if self.edge_counting && edge != InterruptEdge::LowToHigh {
+            set_edge_counting(self.pin.id(), false);
+        }
         // Unwrap okay, checked pin in constructor.
         #[cfg(feature = "vor1x")]
-        InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, InterruptEdge::LowToHigh).await;
+        let fut = InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, edge);
+        #[cfg(feature = "vor4x")]
+        let fut = InputPinFuture::new_with_input_pin(&mut self.pin, edge).unwrap();
+        if self.edge_counting {
+            return fut.restore_edge_counting();
+        }
+        fut
     }
 
-    /// Asynchronously wait until the pin sees any edge (either rising or falling).
-    pub async fn wait_for_any_edge(&mut self) {
+    /// Start the edge counting mode, see the [type documentation](Self).
+    ///
+    /// The rising edge interrupt of the pin is enabled and stays enabled until the pin is
+    /// dropped or released. The edge counter starts at 0. Calling this again while the edge
+    /// counting mode is active has no effect.
+    pub fn start_edge_counting(&mut self) {
+        if self.edge_counting {
+            return;
+        }
         // Unwrap okay, checked pin in constructor.
+        let (edge_totals, _) = edge_total_group(self.pin.id().port()).unwrap();
+        edge_totals[self.pin.id().offset()].store(0, Ordering::Relaxed);
+        set_edge_counting(self.pin.id(), true);
         #[cfg(feature = "vor1x")]
-        InputPinFuture::new_with_input_pin(&mut self.pin, self.irq, InterruptEdge::BothEdges).await;
+        self.pin
+            .0
+            .arm_edge_interrupt(self.irq, InterruptEdge::LowToHigh);
         #[cfg(feature = "vor4x")]
-        InputPinFuture::new_with_input_pin(&mut self.pin, InterruptEdge::BothEdges)
-            .unwrap()
-            .await;
+        self.pin
+            .0
+            .arm_edge_interrupt(InterruptEdge::LowToHigh)
+            .unwrap();
+        self.edge_counting = true;
+    }
+
+    /// Checks whether the edge counting mode is active.
+    #[inline]
+    pub const fn edge_counting(&self) -> bool {
+        self.edge_counting
+    }
+
+    /// Asynchronously wait until at least `min_count` rising edges were counted.
+    ///
+    /// This starts the edge counting mode if it is not active yet. Returns and consumes all
+    /// counted rising edges, including the edges which were counted before this function was
+    /// called, so no edge is lost between two calls. The result can be larger than `min_count`
+    /// if multiple edges occurred between the interrupt and the poll of the future. The counter
+    /// saturates at [u32::MAX].
+    pub async fn wait_for_rising_edges(&mut self, min_count: u32) -> u32 {
+        self.start_edge_counting();
+        // Unwrap okay, checked pin in constructor.
+        let (waker_group, _) =
+            pin_group_to_waker_and_edge_count_group(self.pin.id().port()).unwrap();
+        EdgeCounterFuture::new_for_counting_pin(self.pin.id(), waker_group, min_count).await
+    }
+
+    /// Take the number of rising edges which were counted in the edge counting mode since the
+    /// last call of this function or the completion of [Self::wait_for_rising_edges], and reset
+    /// the counter.
+    ///
+    /// Returns 0 if the edge counting mode is not active.
+    pub fn take_edge_count(&mut self) -> u32 {
+        if !self.edge_counting {
+            return 0;
+        }
+        // Unwrap okay, checked pin in constructor.
+        let (edge_totals, _) = edge_total_group(self.pin.id().port()).unwrap();
+        edge_totals[self.pin.id().offset()].swap(0, Ordering::Relaxed)
     }
 
+    /// Asynchronously wait until the pin sees any edge (either rising or falling).
+    pub async fn wait_for_any_edge(&mut self) {
+        self.edge_future(InterruptEdge::BothEdges).await;
+    }
+
+    /// [Self::wait_for_high] with an upper bound for the waiting time, using the embassy time
+    /// driver.
+    ///
+    /// The pin interrupt is disabled when the timeout expires, so the next wait operation can be
+    /// started immediately.
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_high_timeout(
+        &mut self,
+        timeout: embassy_time::Duration,
+    ) -> Result<(), TimeoutError> {
+        with_timeout(timeout, self.wait_for_high()).await
+    }
+
+    /// [Self::wait_for_low] with an upper bound for the waiting time, see
+    /// [Self::wait_for_high_timeout].
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_low_timeout(
+        &mut self,
+        timeout: embassy_time::Duration,
+    ) -> Result<(), TimeoutError> {
+        with_timeout(timeout, self.wait_for_low()).await
+    }
+
+    /// [Self::wait_for_rising_edge] with an upper bound for the waiting time, see
+    /// [Self::wait_for_high_timeout].
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_rising_edge_timeout(
+        &mut self,
+        timeout: embassy_time::Duration,
+    ) -> Result<(), TimeoutError> {
+        with_timeout(timeout, self.wait_for_rising_edge()).await
+    }
+
+    /// [Self::wait_for_falling_edge] with an upper bound for the waiting time, see
+    /// [Self::wait_for_high_timeout].
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_falling_edge_timeout(
+        &mut self,
+        timeout: embassy_time::Duration,
+    ) -> Result<(), TimeoutError> {
+        with_timeout(timeout, self.wait_for_falling_edge()).await
+    }
+
+    /// [Self::wait_for_any_edge] with an upper bound for the waiting time, see
+    /// [Self::wait_for_high_timeout].
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_any_edge_timeout(
+        &mut self,
+        timeout: embassy_time::Duration,
+    ) -> Result<(), TimeoutError> {
+        with_timeout(timeout, self.wait_for_any_edge()).await
+    }
+
+    /// [Self::wait_for_rising_edges] with an upper bound for the waiting time, see
+    /// [Self::wait_for_high_timeout].
+    ///
+    /// The edges which were counted before the timeout expired are not lost and can be taken
+    /// with [Self::take_edge_count].
+    #[cfg(feature = "async-timeouts")]
+    pub async fn wait_for_rising_edges_timeout(
+        &mut self,
+        min_count: u32,
+        timeout: embassy_time::Duration,
+    ) -> Result<u32, TimeoutError> {
+        with_timeout(timeout, self.wait_for_rising_edges(min_count)).await
+    }
+
+    #[inline]
+    pub fn is_high(&self) -> bool {
+        self.pin.is_high()
+    }
+
+    #[inline]
+    pub fn is_low(&self) -> bool {
+        self.pin.is_low()
+    }
+
+    /// Release the pin. The edge counting mode is stopped.
     pub fn release(self) -> Input {
-        self.pin
+        // The pin interrupt is disabled when the pin is dropped.
+        Input(LowLevelGpio::new(self.pin.id()), PhantomData)
+    }
+}
+
+impl Drop for InputPinAsync {
+    fn drop(&mut self) {
+        if self.edge_counting {
+            set_edge_counting(self.pin.id(), false);
+            disable_edge_interrupt(self.pin.id());
+        }
     }
 }
 
@@ -331,3 +709,55 @@ impl Wait for InputPinAsync {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edge_counter_increments() {
+        let counter = AtomicU32::new(0);
+        saturating_increment(&counter);
+        saturating_increment(&counter);
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn edge_counter_saturates() {
+        let counter = AtomicU32::new(u32::MAX - 1);
+        saturating_increment(&counter);
+        assert_eq!(counter.load(Ordering::Relaxed), u32::MAX);
+        saturating_increment(&counter);
+        assert_eq!(counter.load(Ordering::Relaxed), u32::MAX);
+    }
+
+    #[test]
+    fn interrupt_counts_edges_of_counting_pins() {
+        let wakers = [const { AtomicWaker::new() }; 4];
+        let edge_counts = [const { AtomicU32::new(0) }; 4];
+        let edge_totals = [const { AtomicU32::new(0) }; 4];
+        // Pin 0 and 1 are enabled and saw an edge, only pin 1 is in the edge counting mode.
+        on_interrupt_for_port(0b11, 0b11, &wakers, &edge_counts, 0b10, &edge_totals);
+        assert_eq!(edge_counts[0].load(Ordering::Relaxed), 1);
+        assert_eq!(edge_counts[1].load(Ordering::Relaxed), 1);
+        assert_eq!(edge_totals[0].load(Ordering::Relaxed), 0);
+        assert_eq!(edge_totals[1].load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn edge_totals_accumulate_across_waits() {
+        let wakers = [const { AtomicWaker::new() }; 4];
+        let edge_counts = [const { AtomicU32::new(0) }; 4];
+        let edge_totals = [const { AtomicU32::new(0) }; 4];
+        on_interrupt_for_port(0b100, 0b100, &wakers, &edge_counts, 0b100, &edge_totals);
+        // A wait operation resets its own counter, the total keeps accumulating.
+        edge_counts[2].store(0, Ordering::Relaxed);
+        on_interrupt_for_port(0b100, 0b100, &wakers, &edge_counts, 0b100, &edge_totals);
+        // No edge detected, only the interrupt of the pin is enabled.
+        on_interrupt_for_port(0b100, 0b000, &wakers, &edge_counts, 0b100, &edge_totals);
+        assert_eq!(edge_counts[2].load(Ordering::Relaxed), 1);
+        assert_eq!(edge_totals[2].swap(0, Ordering::Relaxed), 2);
+        on_interrupt_for_port(0b100, 0b100, &wakers, &edge_counts, 0b100, &edge_totals);
+        assert_eq!(edge_totals[2].load(Ordering::Relaxed), 1);
+    }
+}
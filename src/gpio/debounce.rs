@@ -0,0 +1,350 @@
+//! # Debouncing of input pins.
+//!
+//! The input filter of the IOCONFIG peripheral, configured with [super::FilterType] and
+//! [super::FilterClockSelect], only suppresses glitches which are a few filter clock cycles
+//! long. Mechanical switches bounce for several milliseconds, which requires a debounce time in
+//! the same range. This module provides a time-qualified debouncing state machine: A level change
+//! is only reported after the raw level was stable for the debounce time. Level changes which
+//! revert before the debounce time are counted as rejected bounces.
+//!
+//! - [Debouncer]: The state machine itself, which is fed with raw levels and timestamps. This
+//!   can be used with any time source.
+//! - [DebouncedInput]: Polled debouncing of an [Input] with the [MonotonicClock] as the time
+//!   source.
+//! - [DebouncedInputAsync]: Asynchronous debouncing of an [InputPinAsync] with an asynchronous
+//!   [DelayNs] implementation as the time source, for example the delay of the embassy time
+//!   driver.
+//!
+//! The hardware filter can still be used in addition to filter short glitches, which then do
+//! not wake up the CPU.
+use core::{future::Future, pin::pin, task::Poll};
+
+use embedded_hal_async::delay::DelayNs;
+
+use super::{Input, InterruptCapable, PinState, asynch::InputPinAsync};
+use crate::time::{Duration, Instant, MonotonicClock};
+
+//==================================================================================================
+// Debouncer state machine
+//==================================================================================================
+
+/// Time-qualified debouncing state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Debouncer {
+    debounce_time: Duration,
+    stable: PinState,
+    /// Start of a pending level change, which is reported once it is stable for the debounce
+    /// time.
+    change_start: Option<Instant>,
+    rejected_bounces: u32,
+}
+
+impl Debouncer {
+    /// Create a new debouncer with the given initial stable level.
+    pub const fn new(initial: PinState, debounce_time: Duration) -> Self {
+        Self {
+            debounce_time,
+            stable: initial,
+            change_start: None,
+            rejected_bounces: 0,
+        }
+    }
+
+    /// Feed a raw level sampled at the given time into the state machine.
+    ///
+    /// Returns the new stable level if the raw level differed from the stable level for at
+    /// least the debounce time. The level must be sampled repeatedly while a change is pending,
+    /// because the change is only reported by this function.
+    pub fn update(&mut self, raw: PinState, now: Instant) -> Option<PinState> {
+        if raw == self.stable {
+            if self.change_start.take().is_some() {
+                self.rejected_bounces = self.rejected_bounces.saturating_add(1);
+            }
+            return None;
+        }
+        let start = *self.change_start.get_or_insert(now);
+        let elapsed = now
+            .checked_duration_since(start)
+            .unwrap_or(Duration::from_ticks(0));
+        if elapsed < self.debounce_time {
+            return None;
+        }
+        self.stable = raw;
+        self.change_start = None;
+        Some(raw)
+    }
+
+    /// Current stable level.
+    #[inline]
+    pub const fn state(&self) -> PinState {
+        self.stable
+    }
+
+    /// Checks whether a level change is pending.
+    #[inline]
+    pub const fn change_pending(&self) -> bool {
+        self.change_start.is_some()
+    }
+
+    #[inline]
+    pub const fn debounce_time(&self) -> Duration {
+        self.debounce_time
+    }
+
+    /// Number of level changes which reverted before the debounce time. The counter saturates
+    /// at [u32::MAX].
+    #[inline]
+    pub const fn rejected_bounces(&self) -> u32 {
+        self.rejected_bounces
+    }
+
+    /// Reset the rejected bounce counter.
+    #[inline]
+    pub fn reset_rejected_bounces(&mut self) {
+        self.rejected_bounces = 0;
+    }
+}
+
+#[inline]
+fn pin_state(high: bool) -> PinState {
+    if high { PinState::High } else { PinState::Low }
+}
+
+//==================================================================================================
+// Polled debouncing
+//==================================================================================================
+
+/// Polled debouncing of an [Input] pin.
+///
+/// [Self::update] needs to be called periodically, with a period which is well below the
+/// debounce time.
+pub struct DebouncedInput<'clock, Interrupts = InterruptCapable> {
+    input: Input<Interrupts>,
+    clock: &'clock MonotonicClock,
+    debouncer: Debouncer,
+}
+
+impl<'clock, Interrupts> DebouncedInput<'clock, Interrupts> {
+    /// Create a new debounced input. The current level of the pin is used as the initial stable
+    /// level.
+    pub fn new(
+        input: Input<Interrupts>,
+        clock: &'clock MonotonicClock,
+        debounce_time: Duration,
+    ) -> Self {
+        let initial = pin_state(input.is_high());
+        Self {
+            input,
+            clock,
+            debouncer: Debouncer::new(initial, debounce_time),
+        }
+    }
+
+    /// Sample the pin. Returns the new stable level if it changed.
+    pub fn update(&mut self) -> Option<PinState> {
+        let raw = pin_state(self.input.is_high());
+        self.debouncer.update(raw, self.clock.now())
+    }
+
+    /// Current stable level.
+    #[inline]
+    pub fn state(&self) -> PinState {
+        self.debouncer.state()
+    }
+
+    /// See [Debouncer::rejected_bounces].
+    #[inline]
+    pub fn rejected_bounces(&self) -> u32 {
+        self.debouncer.rejected_bounces()
+    }
+
+    #[inline]
+    pub fn debouncer(&self) -> &Debouncer {
+        &self.debouncer
+    }
+
+    pub fn release(self) -> Input<Interrupts> {
+        self.input
+    }
+}
+
+//==================================================================================================
+// Async debouncing
+//==================================================================================================
+
+/// Asynchronous debouncing of an [InputPinAsync].
+///
+/// The pin interrupt is used to detect level changes, and the delay is used to qualify them: A
+/// level is considered stable if no edge was detected for the debounce time.
+pub struct DebouncedInputAsync<Delay: DelayNs> {
+    input: InputPinAsync,
+    delay: Delay,
+    debounce_time: Duration,
+    stable: PinState,
+    rejected_bounces: u32,
+}
+
+impl<Delay: DelayNs> DebouncedInputAsync<Delay> {
+    /// Create a new debounced input. The current level of the pin is used as the initial stable
+    /// level.
+    pub fn new(input: InputPinAsync, delay: Delay, debounce_time: Duration) -> Self {
+        let stable = pin_state(input.is_high());
+        Self {
+            input,
+            delay,
+            debounce_time,
+            stable,
+            rejected_bounces: 0,
+        }
+    }
+
+    /// Wait until the pin was stable high for the debounce time.
+    ///
+    /// This returns immediately if the stable level already is high and the pin is high.
+    pub async fn wait_for_stable_high(&mut self) {
+        self.wait_for_stable(PinState::High).await
+    }
+
+    /// Wait until the pin was stable low for the debounce time.
+    ///
+    /// This returns immediately if the stable level already is low and the pin is low.
+    pub async fn wait_for_stable_low(&mut self) {
+        self.wait_for_stable(PinState::Low).await
+    }
+
+    async fn wait_for_stable(&mut self, level: PinState) {
+        loop {
+            if pin_state(self.input.is_high()) != level {
+                match level {
+                    PinState::High => self.input.wait_for_high().await,
+                    PinState::Low => self.input.wait_for_low().await,
+                }
+            } else if self.stable == level {
+                return;
+            }
+            let timeout_us = self.debounce_time.to_micros().min(u32::MAX as u64) as u32;
+            let edge_detected = {
+                let mut edge = pin!(self.input.wait_for_any_edge());
+                let mut timeout = pin!(self.delay.delay_us(timeout_us));
+                core::future::poll_fn(|cx| {
+                    if edge.as_mut().poll(cx).is_ready() {
+                        return Poll::Ready(true);
+                    }
+                    if timeout.as_mut().poll(cx).is_ready() {
+                        return Poll::Ready(false);
+                    }
+                    Poll::Pending
+                })
+                .await
+            };
+            if edge_detected {
+                self.rejected_bounces = self.rejected_bounces.saturating_add(1);
+                continue;
+            }
+            if pin_state(self.input.is_high()) == level {
+                self.stable = level;
+                return;
+            }
+        }
+    }
+
+    /// Current stable level, which is updated by the wait functions.
+    #[inline]
+    pub fn state(&self) -> PinState {
+        self.stable
+    }
+
+    /// Number of level changes which reverted before the debounce time. The counter saturates
+    /// at [u32::MAX].
+    #[inline]
+    pub fn rejected_bounces(&self) -> u32 {
+        self.rejected_bounces
+    }
+
+    pub fn release(self) -> (InputPinAsync, Delay) {
+        (self.input, self.delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: u64) -> Instant {
+        Instant::from_ticks(ms * 1000)
+    }
+
+    /// Feeds the scripted (time in ms, level) samples into the debouncer and returns the
+    /// reported changes.
+    fn run(
+        debouncer: &mut Debouncer,
+        script: &[(u64, PinState)],
+    ) -> heapless::Vec<(u64, PinState), 16> {
+        script
+            .iter()
+            .filter_map(|(ms, level)| {
+                debouncer
+                    .update(*level, at(*ms))
+                    .map(|change| (*ms, change))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stable_change_reported_after_debounce_time() {
+        use PinState::*;
+        let mut debouncer = Debouncer::new(Low, Duration::millis(10));
+        let changes = run(
+            &mut debouncer,
+            &[
+                (0, Low),
+                (1, High),
+                (5, High),
+                (10, High),
+                (11, High),
+                (20, High),
+            ],
+        );
+        assert_eq!(changes, [(11, High)]);
+        assert_eq!(debouncer.state(), High);
+        assert!(!debouncer.change_pending());
+        assert_eq!(debouncer.rejected_bounces(), 0);
+    }
+
+    #[test]
+    fn bounces_are_rejected() {
+        use PinState::*;
+        let mut debouncer = Debouncer::new(High, Duration::millis(5));
+        let changes = run(
+            &mut debouncer,
+            &[
+                (0, Low),
+                (1, High),
+                (2, Low),
+                (3, High),
+                (4, Low),
+                (6, Low),
+                (9, Low),
+                (10, High),
+                (12, High),
+            ],
+        );
+        assert_eq!(changes, [(9, Low)]);
+        assert_eq!(debouncer.rejected_bounces(), 2);
+        // The release bounce after the press.
+        let changes = run(&mut debouncer, &[(13, High), (14, Low), (30, Low)]);
+        assert!(changes.is_empty());
+        assert_eq!(debouncer.rejected_bounces(), 3);
+        debouncer.reset_rejected_bounces();
+        assert_eq!(debouncer.rejected_bounces(), 0);
+    }
+
+    #[test]
+    fn zero_debounce_time_reports_immediately() {
+        use PinState::*;
+        let mut debouncer = Debouncer::new(Low, Duration::millis(0));
+        let changes = run(&mut debouncer, &[(0, High), (0, Low), (1, Low)]);
+        assert_eq!(changes, [(0, High), (0, Low)]);
+    }
+}
@@ -0,0 +1,514 @@
+//! # Coherent access to groups of pins of one port.
+//!
+//! Reading several [Input] pins one after another yields levels from different instants. For
+//! multi-bit signals like the state of a rotary encoder or a parallel bus, this produces values
+//! which never existed on the pins. [InputGroup] reads all pins of the group with a single read
+//! of the DATAIN register of the port, which is also available as [read_port_snapshot].
+//!
+//! [PortGroup] is the output counterpart. It updates all pins of the group with a single store
+//! to the DATAOUT register, configures the output delays of the pins in one pass and can generate
+//! hardware-timed strobes with the pulse mode of the GPIO peripheral.
+use super::{DynPinId, Input, InterruptCapable, Output, PinDelay, PinState, Port};
+
+/// Maximum number of pins of an [InputGroup].
+pub const MAX_GROUP_PINS: usize = 16;
+
+/// Reads the input levels of all pins of a port with a single read of the DATAIN register.
+///
+/// Bit N is set if pin N of the port is high.
+#[inline]
+pub fn read_port_snapshot(port: Port) -> u32 {
+    super::regs::Gpio::new_mmio(port).read_data_in()
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InputGroupError {
+    #[error("a pin group requires 1 to {MAX_GROUP_PINS} pins, got {0}")]
+    InvalidPinCount(usize),
+    #[error("all pins of a group must be on port {expected:?}, found pin on port {found:?}")]
+    PortMismatch { expected: Port, found: Port },
+}
+
+/// Maps the pins of the port snapshot to the group bit order. Bit N of the result is the level of
+/// the pin with the offset `offsets[N]`.
+const fn snapshot_to_group_bits(snapshot: u32, offsets: &[u8]) -> u16 {
+    let mut bits = 0;
+    let mut idx = 0;
+    while idx < offsets.len() {
+        bits |= (((snapshot >> offsets[idx]) & 1) as u16) << idx;
+        idx += 1;
+    }
+    bits
+}
+
+/// Pins of the mask which changed their level between two snapshots.
+#[inline]
+const fn changed_pins(previous: u32, snapshot: u32, mask: u32) -> u32 {
+    (previous ^ snapshot) & mask
+}
+
+/// Group of up to [MAX_GROUP_PINS] input pins of one port which are read coherently.
+///
+/// The group bit order is the order of the pins passed to [Self::new]: Bit N of the values
+/// returned by [Self::read] is the level of the N-th pin.
+#[derive(Debug)]
+pub struct InputGroup<const N: usize, Interrupts = InterruptCapable> {
+    pins: [Input<Interrupts>; N],
+    port: Port,
+    offsets: [u8; N],
+    /// Port mask of all pins of the group.
+    mask: u32,
+    /// Snapshot of the previous [Self::changed_since] call.
+    last_snapshot: u32,
+}
+
+impl<const N: usize, Interrupts> InputGroup<N, Interrupts> {
+    /// Create a group from input pins which are all on the same port.
+    ///
+    /// The snapshot for the first [Self::changed_since] call is taken here.
+    pub fn new(pins: [Input<Interrupts>; N]) -> Result<Self, InputGroupError> {
+        if N == 0 || N > MAX_GROUP_PINS {
+            return Err(InputGroupError::InvalidPinCount(N));
+        }
+        let port = pins[0].id().port();
+        let mut offsets = [0; N];
+        let mut mask = 0;
+        for (offset, pin) in offsets.iter_mut().zip(pins.iter()) {
+            if pin.id().port() != port {
+                return Err(InputGroupError::PortMismatch {
+                    expected: port,
+                    found: pin.id().port(),
+                });
+            }
+            *offset = pin.id().offset() as u8;
+            mask |= 1 << *offset;
+        }
+        Ok(Self {
+            pins,
+            port,
+            offsets,
+            mask,
+            last_snapshot: read_port_snapshot(port),
+        })
+    }
+
+    #[inline]
+    pub fn port(&self) -> Port {
+        self.port
+    }
+
+    /// Port mask of all pins of the group. Bit N is set if pin N of the port is part of the
+    /// group.
+    #[inline]
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Reads the levels of all pins of the group from a single register read, in the group bit
+    /// order.
+    #[inline]
+    pub fn read(&self) -> u16 {
+        self.to_group_bits(read_port_snapshot(self.port))
+    }
+
+    /// Calls the given function with the raw DATAIN value of a single register read. Bit N is
+    /// the level of pin N of the port, pins which are not part of the group are not masked.
+    #[inline]
+    pub fn read_with<R>(&self, f: impl FnOnce(u32) -> R) -> R {
+        f(read_port_snapshot(self.port))
+    }
+
+    /// Takes a new snapshot and returns the pins of the group which changed their level since
+    /// the snapshot of the previous call, or since the creation of the group for the first call.
+    ///
+    /// The result uses the port bit order like [Self::mask] and can be converted to the group bit
+    /// order with [Self::to_group_bits].
+    pub fn changed_since(&mut self) -> u32 {
+        let snapshot = read_port_snapshot(self.port);
+        let changed = changed_pins(self.last_snapshot, snapshot, self.mask);
+        self.last_snapshot = snapshot;
+        changed
+    }
+
+    /// Maps a value in the port bit order, for example a [read_port_snapshot] value, to the group
+    /// bit order.
+    #[inline]
+    pub fn to_group_bits(&self, port_bits: u32) -> u16 {
+        snapshot_to_group_bits(port_bits, &self.offsets)
+    }
+
+    /// Release the pins of the group.
+    #[inline]
+    pub fn release(self) -> [Input<Interrupts>; N] {
+        self.pins
+    }
+}
+
+//==================================================================================================
+// Output groups
+//==================================================================================================
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PortGroupError {
+    #[error("a pin group requires 1 to {MAX_GROUP_PINS} pins, got {0}")]
+    InvalidPinCount(usize),
+    #[error("all pins of a group must be on port {expected:?}, found pin on port {found:?}")]
+    PortMismatch { expected: Port, found: Port },
+    #[error("expected one delay for each of the {expected} pins of the group, got {found}")]
+    DelayCountMismatch { expected: usize, found: usize },
+    #[error("strobe pin {0:?} is not a member of the group")]
+    StrobeNotInGroup(DynPinId),
+}
+
+/// Output configuration of a pin of a [PortGroup], read back from the GPIO registers.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PinOutputConfig {
+    pub delay: PinDelay,
+    /// Pulse mode enabled, which is the case for the strobe pins of the group.
+    pub pulse: bool,
+    /// Level the pin returns to after a pulse. Only relevant if [Self::pulse] is set.
+    pub pulse_base: PinState,
+}
+
+/// Maps a value in the group bit order to the port bit order. Bit `offsets[N]` of the result is
+/// bit N of the value.
+const fn group_to_port_bits(value: u16, offsets: &[u8]) -> u32 {
+    let mut bits = 0;
+    let mut idx = 0;
+    while idx < offsets.len() {
+        bits |= (((value >> idx) & 1) as u32) << offsets[idx];
+        idx += 1;
+    }
+    bits
+}
+
+/// Port masks of the pins which have the DELAY1 and the DELAY2 bit set for the given delays in
+/// the group bit order.
+fn delay_masks(offsets: &[u8], delays: &[PinDelay]) -> (u32, u32) {
+    let mut delay_1 = 0;
+    let mut delay_2 = 0;
+    for (offset, delay) in offsets.iter().zip(delays.iter()) {
+        if delay.delay_1() {
+            delay_1 |= 1 << offset;
+        }
+        if delay.delay_2() {
+            delay_2 |= 1 << offset;
+        }
+    }
+    (delay_1, delay_2)
+}
+
+/// New DATAOUT value for the given data bits in the port bit order.
+///
+/// The pins outside the group mask keep their previous level. The strobe pins are driven to
+/// their idle level, or to the inverted idle level if `pulse` is set, which makes the hardware
+/// generate a pulse of one clock cycle.
+#[inline]
+const fn port_write_value(
+    previous: u32,
+    data: u32,
+    mask: u32,
+    strobe_mask: u32,
+    strobe_idle: u32,
+    pulse: bool,
+) -> u32 {
+    let strobes = if pulse {
+        !strobe_idle & strobe_mask
+    } else {
+        strobe_idle & strobe_mask
+    };
+    (previous & !mask) | (data & mask & !strobe_mask) | strobes
+}
+
+/// Group of up to [MAX_GROUP_PINS] output pins of one port which are updated coherently, for
+/// example the data and strobe lines of a parallel bus.
+///
+/// The group bit order is the order of the pins passed to [Self::new]: Bit N of the values passed
+/// to [Self::write] is the level of the N-th pin.
+///
+/// Member pins can be designated as strobe pins with [Self::configure_strobe]. A strobe pin uses
+/// the pulse mode of the GPIO peripheral: It is held at its idle level by [Self::write], and
+/// [Self::write_pulsed] generates a pulse of one GPIO clock cycle on it with the same store
+/// which updates the data pins. Together with the output delays configured with
+/// [Self::configure_delays], this allows strobes with a deterministic timing relative to the
+/// data pins.
+///
+/// The DATAOUT register is updated with a read-modify-write sequence. Pins of the same port
+/// which are not part of the group must not be modified from an interrupt during a write, and
+/// must not use the pulse mode.
+#[derive(Debug)]
+pub struct PortGroup<const N: usize> {
+    pins: [Output; N],
+    port: Port,
+    offsets: [u8; N],
+    /// Port mask of all pins of the group.
+    mask: u32,
+    /// Port mask of the strobe pins of the group.
+    strobe_mask: u32,
+    /// Idle levels of the strobe pins in the port bit order.
+    strobe_idle: u32,
+}
+
+impl<const N: usize> PortGroup<N> {
+    /// Create a group from output pins which are all on the same port.
+    pub fn new(pins: [Output; N]) -> Result<Self, PortGroupError> {
+        if N == 0 || N > MAX_GROUP_PINS {
+            return Err(PortGroupError::InvalidPinCount(N));
+        }
+        let port = pins[0].port();
+        let mut offsets = [0; N];
+        let mut mask = 0;
+        for (offset, pin) in offsets.iter_mut().zip(pins.iter()) {
+            if pin.port() != port {
+                return Err(PortGroupError::PortMismatch {
+                    expected: port,
+                    found: pin.port(),
+                });
+            }
+            *offset = pin.offset() as u8;
+            mask |= 1 << *offset;
+        }
+        Ok(Self {
+            pins,
+            port,
+            offsets,
+            mask,
+            strobe_mask: 0,
+            strobe_idle: 0,
+        })
+    }
+
+    #[inline]
+    pub fn port(&self) -> Port {
+        self.port
+    }
+
+    /// Port mask of all pins of the group. Bit N is set if pin N of the port is part of the
+    /// group.
+    #[inline]
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Port mask of the strobe pins of the group.
+    #[inline]
+    pub fn strobe_mask(&self) -> u32 {
+        self.strobe_mask
+    }
+
+    /// Configure the output delays of all pins of the group, in the group bit order. See
+    /// [PinDelay] for details.
+    ///
+    /// The DELAY1 and DELAY2 registers are each updated with a single read-modify-write
+    /// sequence.
+    pub fn configure_delays(&mut self, per_pin: &[PinDelay]) -> Result<(), PortGroupError> {
+        if per_pin.len() != N {
+            return Err(PortGroupError::DelayCountMismatch {
+                expected: N,
+                found: per_pin.len(),
+            });
+        }
+        let (delay_1, delay_2) = delay_masks(&self.offsets, per_pin);
+        let mut regs = super::regs::Gpio::new_mmio(self.port);
+        regs.modify_delay1(|value| (value & !self.mask) | delay_1);
+        regs.modify_delay2(|value| (value & !self.mask) | delay_2);
+        Ok(())
+    }
+
+    /// Designate a member pin as a strobe pin with the given idle level and enable its pulse
+    /// mode. The pin is driven to its idle level immediately.
+    pub fn configure_strobe(
+        &mut self,
+        pin: DynPinId,
+        idle_level: PinState,
+    ) -> Result<(), PortGroupError> {
+        let Some(idx) = self.pins.iter().position(|member| member.id() == pin) else {
+            return Err(PortGroupError::StrobeNotInGroup(pin));
+        };
+        let pin_mask = 1 << self.offsets[idx];
+        self.strobe_mask |= pin_mask;
+        match idle_level {
+            PinState::Low => {
+                self.strobe_idle &= !pin_mask;
+                self.pins[idx].set_low();
+            }
+            PinState::High => {
+                self.strobe_idle |= pin_mask;
+                self.pins[idx].set_high();
+            }
+        }
+        self.pins[idx].configure_pulse_mode(true, idle_level);
+        Ok(())
+    }
+
+    /// Disable the pulse mode of all strobe pins, which turns them into regular data pins.
+    pub fn clear_strobes(&mut self) {
+        let mut regs = super::regs::Gpio::new_mmio(self.port);
+        regs.modify_pulse(|value| value & !self.strobe_mask);
+        self.strobe_mask = 0;
+        self.strobe_idle = 0;
+    }
+
+    /// Write the levels of all data pins of the group with a single store to the DATAOUT
+    /// register, in the group bit order. Strobe pins are held at their idle level.
+    #[inline]
+    pub fn write(&mut self, value: u16) {
+        self.write_port(value, false);
+    }
+
+    /// Like [Self::write], but the same store also generates a pulse of one GPIO clock cycle on
+    /// all strobe pins.
+    #[inline]
+    pub fn write_pulsed(&mut self, value: u16) {
+        self.write_port(value, true);
+    }
+
+    fn write_port(&mut self, value: u16, pulse: bool) {
+        let data = group_to_port_bits(value, &self.offsets);
+        let mut regs = super::regs::Gpio::new_mmio(self.port);
+        regs.modify_data_out(|previous| {
+            port_write_value(
+                previous,
+                data,
+                self.mask,
+                self.strobe_mask,
+                self.strobe_idle,
+                pulse,
+            )
+        });
+    }
+
+    /// Read back the effective delay and pulse configuration of all pins of the group from the
+    /// GPIO registers, in the group bit order. This can be used to verify the configuration.
+    pub fn read_back_config(&self) -> [PinOutputConfig; N] {
+        let regs = super::regs::Gpio::new_mmio(self.port);
+        let delay_1 = regs.read_delay1();
+        let delay_2 = regs.read_delay2();
+        let pulse = regs.read_pulse();
+        let pulse_base = regs.read_pulsebase();
+        core::array::from_fn(|idx| {
+            let bit = |reg: u32| (reg >> self.offsets[idx]) & 1 != 0;
+            PinOutputConfig {
+                delay: PinDelay::from_bits(bit(delay_1), bit(delay_2)),
+                pulse: bit(pulse),
+                pulse_base: PinState::from(bit(pulse_base)),
+            }
+        })
+    }
+
+    /// Maps a value in the group bit order to the port bit order.
+    #[inline]
+    pub fn to_port_bits(&self, value: u16) -> u32 {
+        group_to_port_bits(value, &self.offsets)
+    }
+
+    /// Disable the pulse mode of the strobe pins and release the pins of the group.
+    pub fn release(mut self) -> [Output; N] {
+        self.clear_strobes();
+        self.pins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_bit_order() {
+        // Encoder on the pins 7, 2, 12 and 3.
+        let offsets = [7, 2, 12, 3];
+        assert_eq!(snapshot_to_group_bits(0, &offsets), 0);
+        assert_eq!(snapshot_to_group_bits(1 << 7, &offsets), 0b0001);
+        assert_eq!(snapshot_to_group_bits(1 << 3, &offsets), 0b1000);
+        assert_eq!(
+            snapshot_to_group_bits((1 << 12) | (1 << 2), &offsets),
+            0b0110
+        );
+        // Other pins are ignored.
+        assert_eq!(
+            snapshot_to_group_bits(!((1 << 7) | (1 << 3)), &offsets),
+            0b0110
+        );
+        let offsets: [u8; MAX_GROUP_PINS] = core::array::from_fn(|idx| 31 - idx as u8);
+        assert_eq!(snapshot_to_group_bits(0x8000_0001, &offsets), 0b1);
+        assert_eq!(snapshot_to_group_bits(0x0001_0000, &offsets), 1 << 15);
+    }
+
+    #[test]
+    fn changed_pins_masked() {
+        let mask = (1 << 7) | (1 << 2);
+        assert_eq!(changed_pins(0, 0, mask), 0);
+        assert_eq!(changed_pins(0, 1 << 7, mask), 1 << 7);
+        assert_eq!(changed_pins(1 << 7, 0, mask), 1 << 7);
+        assert_eq!(changed_pins(1 << 7, (1 << 7) | (1 << 2), mask), 1 << 2);
+        // Pins outside of the group are ignored.
+        assert_eq!(changed_pins(0, !mask, mask), 0);
+    }
+
+    #[test]
+    fn group_to_port_order() {
+        let offsets = [7, 2, 12, 3];
+        assert_eq!(group_to_port_bits(0, &offsets), 0);
+        assert_eq!(group_to_port_bits(0b0001, &offsets), 1 << 7);
+        assert_eq!(group_to_port_bits(0b1000, &offsets), 1 << 3);
+        assert_eq!(group_to_port_bits(0b0110, &offsets), (1 << 12) | (1 << 2));
+        // Bits above the group size are ignored.
+        assert_eq!(group_to_port_bits(0xfff0, &offsets), 0);
+        for value in 0..16 {
+            assert_eq!(
+                snapshot_to_group_bits(group_to_port_bits(value, &offsets), &offsets),
+                value
+            );
+        }
+        let offsets: [u8; MAX_GROUP_PINS] = core::array::from_fn(|idx| 31 - idx as u8);
+        assert_eq!(group_to_port_bits(1 << 15, &offsets), 1 << 16);
+    }
+
+    #[test]
+    fn delay_mask_mapping() {
+        let offsets = [0, 1, 2, 9];
+        // Data pins without delay, strobe on pin 9 delayed by two taps.
+        let delays = [
+            PinDelay::None,
+            PinDelay::One,
+            PinDelay::Three,
+            PinDelay::Two,
+        ];
+        assert_eq!(
+            delay_masks(&offsets, &delays),
+            ((1 << 1) | (1 << 2), (1 << 2) | (1 << 9))
+        );
+        assert_eq!(delay_masks(&offsets, &[PinDelay::None; 4]), (0, 0));
+    }
+
+    #[test]
+    fn write_value_with_strobes() {
+        let mask = 0b1111 << 4;
+        // Pin 7 is a strobe with an idle low level, pin 6 a strobe with an idle high level.
+        let strobe_mask = (1 << 7) | (1 << 6);
+        let strobe_idle = 1 << 6;
+        let others = 0x8000_0001;
+        // Strobes are held at their idle level for regular writes.
+        assert_eq!(
+            port_write_value(others, 0xff, mask, strobe_mask, strobe_idle, false),
+            others | (0b0111 << 4)
+        );
+        assert_eq!(
+            port_write_value(others | mask, 0, mask, strobe_mask, strobe_idle, false),
+            others | (1 << 6)
+        );
+        // Pulsed writes drive the strobes to the inverted idle level.
+        assert_eq!(
+            port_write_value(others, 0b0001 << 4, mask, strobe_mask, strobe_idle, true),
+            others | (1 << 7) | (1 << 4)
+        );
+        // No strobes: Pulsed and regular writes are the same.
+        assert_eq!(
+            port_write_value(0, 0b1010 << 4, mask, 0, 0, true),
+            0b1010 << 4
+        );
+    }
+}
@@ -2,13 +2,11 @@ pub use embedded_hal::digital::PinState;
 
 use crate::ioconfig::FilterClockSelect;
 use crate::ioconfig::FilterType;
-#[cfg(feature = "vor1x")]
-use crate::{PeripheralSelect, sysconfig::enable_peripheral_clock};
 
 pub use crate::InvalidOffsetError;
 pub use crate::Port;
 pub use crate::ioconfig::regs::Pull;
-use crate::ioconfig::regs::{FunctionSelect, IoConfig, MmioIoConfig};
+use crate::ioconfig::regs::{Config, FunctionSelect, IoConfig, MmioIoConfig};
 use crate::pins::PinId;
 
 use super::Pin;
@@ -28,15 +26,126 @@ pub enum InterruptLevel {
     High = 1,
 }
 
+/// Additional delay of the output signal of a pin.
+///
+/// The delay is implemented with the DELAY1 and DELAY2 registers of the GPIO peripheral, which
+/// delay the output by one and two clock cycles of the GPIO peripheral clock respectively. The
+/// delays are added, which allows a delay of up to three clock cycles. The GPIO peripheral clock
+/// is the system clock on Vorago 1x devices and the APB1 clock on Vorago 4x devices.
+///
+/// The delay only applies to the output path, input values are not delayed. This can be used to
+/// create a deterministic skew between pins, for example for strobe signals.
+///
+/// The IOCONFIG peripheral of both device families has no drive strength or slew rate control.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PinDelay {
+    #[default]
+    None = 0b00,
+    /// DELAY1 bit set.
+    One = 0b01,
+    /// DELAY2 bit set.
+    Two = 0b10,
+    /// DELAY1 and DELAY2 bits set.
+    Three = 0b11,
+}
+
+impl PinDelay {
+    /// Delay from the raw DELAY1 and DELAY2 bits.
+    #[inline]
+    pub const fn from_bits(delay_1: bool, delay_2: bool) -> Self {
+        match (delay_1, delay_2) {
+            (false, false) => PinDelay::None,
+            (true, false) => PinDelay::One,
+            (false, true) => PinDelay::Two,
+            (true, true) => PinDelay::Three,
+        }
+    }
+
+    /// Raw DELAY1 bit.
+    #[inline]
+    pub const fn delay_1(&self) -> bool {
+        (*self as u8) & 0b01 != 0
+    }
+
+    /// Raw DELAY2 bit.
+    #[inline]
+    pub const fn delay_2(&self) -> bool {
+        (*self as u8) & 0b10 != 0
+    }
+
+    /// Output delay in GPIO peripheral clock cycles.
+    #[inline]
+    pub const fn cycles(&self) -> u32 {
+        *self as u32
+    }
+}
+
 /// Pin identifier for all physical pins exposed by Vorago MCUs.
+///
+/// With the `serde` feature, the pin identifier is serialized in its compact form, see
+/// [Self::to_compact], and validated when it is deserialized.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u8", into = "u8")
+)]
 pub struct DynPinId {
     port: Port,
     /// Offset within the port.
     offset: u8,
 }
 
+/// Pins of the VA416xx family which are not bonded out on the VA41628.
+#[cfg(feature = "va41628")]
+const VA41628_MISSING_PINS: &[(Port, u8)] = &[
+    (Port::B, 5),
+    (Port::B, 6),
+    (Port::B, 7),
+    (Port::B, 8),
+    (Port::B, 9),
+    (Port::B, 10),
+    (Port::B, 11),
+    (Port::C, 13),
+    (Port::C, 15),
+    (Port::D, 0),
+    (Port::D, 1),
+    (Port::D, 2),
+    (Port::D, 3),
+    (Port::D, 4),
+    (Port::D, 5),
+    (Port::D, 6),
+    (Port::D, 7),
+    (Port::D, 8),
+    (Port::D, 9),
+    (Port::E, 10),
+    (Port::E, 11),
+    (Port::F, 2),
+    (Port::F, 3),
+    (Port::F, 4),
+    (Port::F, 5),
+    (Port::F, 6),
+    (Port::F, 7),
+    (Port::F, 8),
+    (Port::F, 10),
+];
+
+/// Invalid compact pin identifier, see [DynPinId::from_compact].
+#[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InvalidCompactPinIdError {
+    #[error("invalid port index {0}")]
+    InvalidPort(u8),
+    #[error("{0}")]
+    InvalidOffset(#[from] InvalidOffsetError),
+    /// The pin exists in the device family, but is not available on the selected device.
+    #[error("pin {0:?} is not available on this device")]
+    NotAvailable(DynPinId),
+}
+
 #[derive(Debug, thiserror::Error)]
 #[cfg(feature = "vor4x")]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -73,6 +182,73 @@ impl DynPinId {
         self.offset as usize
     }
 
+    /// Checks whether the pin is available on the selected device. This is [false] for the pins
+    /// which are not bonded out on the VA41628.
+    pub const fn is_available(&self) -> bool {
+        #[cfg(feature = "va41628")]
+        {
+            let mut idx = 0;
+            while idx < VA41628_MISSING_PINS.len() {
+                let (port, offset) = VA41628_MISSING_PINS[idx];
+                if port as u8 == self.port as u8 && offset == self.offset {
+                    return false;
+                }
+                idx += 1;
+            }
+        }
+        true
+    }
+
+    /// Compact representation of the pin identifier, for example to store pin assignments in
+    /// non-volatile memory. The port index is stored in the upper 3 bits and the offset in the
+    /// lower 5 bits.
+    ///
+    /// The representation is the same for all devices of a family. [Self::from_compact] performs
+    /// the reverse conversion.
+    #[inline]
+    pub const fn to_compact(&self) -> u8 {
+        ((self.port as u8) << 5) | self.offset
+    }
+
+    /// Convert the compact representation created by [Self::to_compact] back to the pin
+    /// identifier.
+    ///
+    /// The offset is validated like for [Self::new]. Additionally, pins which are not
+    /// [available][Self::is_available] on the selected device are rejected.
+    pub const fn from_compact(compact: u8) -> Result<Self, InvalidCompactPinIdError> {
+        let port_idx = compact >> 5;
+        let port = match port_idx {
+            0 => Port::A,
+            1 => Port::B,
+            #[cfg(feature = "vor4x")]
+            2 => Port::C,
+            #[cfg(feature = "vor4x")]
+            3 => Port::D,
+            #[cfg(feature = "vor4x")]
+            4 => Port::E,
+            #[cfg(feature = "vor4x")]
+            5 => Port::F,
+            #[cfg(feature = "vor4x")]
+            6 => Port::G,
+            _ => return Err(InvalidCompactPinIdError::InvalidPort(port_idx)),
+        };
+        let id = match Self::new(port, (compact & 0x1F) as usize) {
+            Ok(id) => id,
+            Err(e) => return Err(InvalidCompactPinIdError::InvalidOffset(e)),
+        };
+        if !id.is_available() {
+            return Err(InvalidCompactPinIdError::NotAvailable(id));
+        }
+        Ok(id)
+    }
+
+    /// Interrupt the pin was routed to with the IRQSEL peripheral by the HAL.
+    #[cfg(feature = "vor1x")]
+    #[inline]
+    pub fn routed_interrupt(&self) -> Option<va108xx::Interrupt> {
+        crate::irq_routing::pin_interrupt(*self)
+    }
+
     /// This function panics if the port is [Port::G].
     #[cfg(feature = "vor4x")]
     pub fn irq(&self) -> Result<va416xx::Interrupt, PortDoesNotSupportInterrupts> {
@@ -205,6 +381,470 @@ impl DynPinId {
     }
 }
 
+impl From<DynPinId> for u8 {
+    #[inline]
+    fn from(id: DynPinId) -> Self {
+        id.to_compact()
+    }
+}
+
+impl TryFrom<u8> for DynPinId {
+    type Error = InvalidCompactPinIdError;
+
+    #[inline]
+    fn try_from(compact: u8) -> Result<Self, Self::Error> {
+        Self::from_compact(compact)
+    }
+}
+
+/// Minimal view of the interrupt registers of a GPIO port for interrupt handlers.
+///
+/// Bit N of all masks corresponds to pin N of the port. The view only reads the interrupt
+/// registers and clears the latched edge status, so it can be created safely while the pins of
+/// the port are owned by drivers, see [crate::ownership].
+pub struct GpioIrqView(super::regs::MmioGpio<'static>);
+
+impl GpioIrqView {
+    #[inline]
+    pub fn new(port: Port) -> Self {
+        Self(super::regs::Gpio::new_mmio(port))
+    }
+
+    /// Pins with an enabled interrupt.
+    #[inline]
+    pub fn irq_enabled(&self) -> u32 {
+        self.0.read_irq_enable()
+    }
+
+    /// Pins with an enabled and active interrupt.
+    #[inline]
+    pub fn irq_status(&self) -> u32 {
+        self.0.read_irq_status()
+    }
+
+    /// Pins with a latched edge event.
+    #[inline]
+    pub fn edge_status(&self) -> u32 {
+        self.0.read_edge_status()
+    }
+
+    /// Clear the latched edge status of the pins set in the mask, see [clear_port_edge_status].
+    #[inline]
+    pub fn clear_edge_status(&mut self, mask: u32) {
+        self.0.write_edge_status(mask);
+    }
+}
+
+/// Returns the interrupt status of all pins of a port.
+///
+/// Bit N is set if pin N of the port has an active interrupt. The value is masked with the
+/// interrupt enable register, so pins with disabled interrupts are never reported. Reading this
+/// status has no side effects.
+pub fn port_interrupt_status(port: Port) -> u32 {
+    let view = GpioIrqView::new(port);
+    view.irq_status() & view.irq_enabled()
+}
+
+/// Clears the latched edge status of all pins of a port which are set in the mask.
+///
+/// The edge status register uses write-one-to-clear semantics: every bit set in `mask`
+/// clears the latched edge event of the corresponding pin, while bits which are zero in
+/// `mask` leave the edge status of the corresponding pin untouched. This only acknowledges
+/// edge-triggered interrupts. A level-triggered interrupt remains active as long as the
+/// configured input level is present on the pin.
+pub fn clear_port_edge_status(port: Port, mask: u32) {
+    GpioIrqView::new(port).clear_edge_status(mask);
+}
+
+/// Pins which are routed to a specific interrupt vector using the IRQSEL peripheral.
+///
+/// Bit N of each mask corresponds to pin N of the respective port.
+#[cfg(feature = "vor1x")]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RoutedPins {
+    pub port_a: u32,
+    pub port_b: u32,
+}
+
+#[cfg(feature = "vor1x")]
+impl RoutedPins {
+    /// Only keep the pins which currently have an active interrupt, using
+    /// [port_interrupt_status].
+    pub fn pending(&self) -> RoutedPins {
+        RoutedPins {
+            port_a: self.port_a & port_interrupt_status(Port::A),
+            port_b: self.port_b & port_interrupt_status(Port::B),
+        }
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.port_a == 0 && self.port_b == 0
+    }
+}
+
+/// Reads back the IRQSEL configuration to determine all pins which are routed to the given
+/// interrupt vector.
+///
+/// Multiple pins can share the same interrupt vector, so this can be used inside an interrupt
+/// handler to determine the set of candidate pins which might have triggered the interrupt.
+/// [RoutedPins::pending] can then be used to find the pins with an active interrupt.
+#[cfg(feature = "vor1x")]
+pub fn pins_routed_to_interrupt(irq: va108xx::Interrupt) -> RoutedPins {
+    crate::irqsel::routing_for(irq).pins
+}
+
+/// Enables the peripheral clocks required to configure the pins of the given port: the IOCONFIG
+/// clock, the port clock and on Vorago 1x devices the common GPIO clock.
+///
+/// These clocks are enabled by the port constructors like [crate::pins::PinsA::new], but may be
+/// disabled if the pins were created by other means, for example after a bootloader handoff.
+/// Writes to a peripheral with a disabled clock are silently lost. The configuration functions
+/// of [LowLevelGpio] call this function, and it only writes the clock enable register if one of
+/// the clocks is disabled.
+#[inline]
+pub fn ensure_port_clocks(port: Port) {
+    crate::ensure_peripheral_clocks(&[
+        crate::PeripheralSelect::IoConfig,
+        #[cfg(feature = "vor1x")]
+        crate::PeripheralSelect::Gpio,
+        port.periph_sel(),
+    ]);
+}
+
+/// Sets all pins of a port in `high_mask` and clears all pins of a port in `low_mask`.
+///
+/// This uses two consecutive stores to the SETOUT and CLROUT registers, so the pins set high
+/// are updated before the pins set low, with only a few clock cycles in between. If a bit is set
+/// in both masks, the pin will be low.
+///
+/// # Safety
+///
+/// This circumvents ownership rules of the HAL. The caller must ensure that it owns all pins
+/// specified in the masks.
+#[inline]
+pub unsafe fn write_multiple(port: Port, high_mask: u32, low_mask: u32) {
+    let mut gpio = super::regs::Gpio::new_mmio(port);
+    gpio.write_set_out(high_mask);
+    gpio.write_clr_out(low_mask);
+}
+
+/// Lightweight handle for the output operations of a single pin.
+///
+/// Only contains the GPIO register block pointer and the precomputed pin mask, so setting,
+/// clearing or toggling the pin is a single store to the SETOUT, CLROUT or TOGOUT register.
+pub struct OutputHandle {
+    gpio: super::regs::MmioGpio<'static>,
+    mask: u32,
+}
+
+impl OutputHandle {
+    pub const fn new(id: DynPinId) -> Self {
+        OutputHandle {
+            gpio: super::regs::Gpio::new_mmio(id.port),
+            mask: 1 << id.offset,
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_high(&mut self) {
+        self.gpio.write_set_out(self.mask);
+    }
+
+    #[inline(always)]
+    pub fn set_low(&mut self) {
+        self.gpio.write_clr_out(self.mask);
+    }
+
+    #[inline(always)]
+    pub fn toggle(&mut self) {
+        self.gpio.write_tog_out(self.mask);
+    }
+
+    #[inline(always)]
+    pub fn is_set_high(&self) -> bool {
+        self.gpio.read_data_out() & self.mask != 0
+    }
+
+    #[inline(always)]
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+
+    #[inline(always)]
+    pub const fn mask_32(&self) -> u32 {
+        self.mask
+    }
+}
+
+/// Output configuration of a GPIO pin, see the [module documentation](super#output-configuration)
+/// for the interaction of the settings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OutputConfig {
+    pub open_drain: bool,
+    /// Pull resistor, [None] to disable it.
+    pub pull: Option<Pull>,
+    /// PLEVEL bit: The pull resistor is also connected while the pin is an output.
+    pub pull_when_output_active: bool,
+    /// Invert the output register value on the pin.
+    pub invert_output: bool,
+    /// Output delay, see [PinDelay].
+    pub delay: PinDelay,
+}
+
+impl OutputConfig {
+    /// Push-pull output without a pull resistor.
+    pub const fn push_pull() -> Self {
+        Self {
+            open_drain: false,
+            pull: None,
+            pull_when_output_active: false,
+            invert_output: false,
+            delay: PinDelay::None,
+        }
+    }
+
+    /// Open-drain output with the pull-up, which is disconnected while the pin is an output.
+    pub const fn open_drain() -> Self {
+        Self {
+            open_drain: true,
+            pull: Some(Pull::Up),
+            pull_when_output_active: false,
+            invert_output: false,
+            delay: PinDelay::None,
+        }
+    }
+
+    pub const fn pull(mut self, pull: Option<Pull>) -> Self {
+        self.pull = pull;
+        self
+    }
+
+    pub const fn pull_when_output_active(mut self, enable: bool) -> Self {
+        self.pull_when_output_active = enable;
+        self
+    }
+
+    pub const fn invert_output(mut self, invert: bool) -> Self {
+        self.invert_output = invert;
+        self
+    }
+
+    pub const fn delay(mut self, delay: PinDelay) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self::push_pull()
+    }
+}
+
+/// Input configuration of a GPIO pin.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InputConfig {
+    /// Pull resistor, [None] to disable it.
+    pub pull: Option<Pull>,
+    /// Invert the pin level in the input path.
+    pub invert_input: bool,
+    /// Input filter and filter clock. [None] keeps the filter settings of the pin.
+    pub filter: Option<(FilterType, FilterClockSelect)>,
+}
+
+impl InputConfig {
+    /// Floating input without inversion. The filter settings are kept.
+    pub const fn floating() -> Self {
+        Self {
+            pull: None,
+            invert_input: false,
+            filter: None,
+        }
+    }
+
+    pub const fn pull(mut self, pull: Option<Pull>) -> Self {
+        self.pull = pull;
+        self
+    }
+
+    pub const fn invert_input(mut self, invert: bool) -> Self {
+        self.invert_input = invert;
+        self
+    }
+
+    pub const fn filter(mut self, filter: FilterType, clksel: FilterClockSelect) -> Self {
+        self.filter = Some((filter, clksel));
+        self
+    }
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self::floating()
+    }
+}
+
+/// Configuration of a pin decoded from its IOCONFIG register and the GPIO registers of its port,
+/// see [LowLevelGpio::current_config].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DecodedPinConfig {
+    pub id: DynPinId,
+    /// Function select. The GPIO function is [FunctionSelect::Sel0].
+    pub funsel: FunctionSelect,
+    /// DIR register bit. Only relevant for the GPIO function.
+    pub output: bool,
+    /// Pull resistor, [None] if it is disabled.
+    pub pull: Option<Pull>,
+    /// PLEVEL bit: The pull resistor is also active while the output is enabled.
+    pub pull_when_output_active: bool,
+    pub open_drain: bool,
+    pub invert_input: bool,
+    pub invert_output: bool,
+    /// IEWO bit: The input is enabled while the output is active.
+    pub input_enable_when_output: bool,
+    pub io_disable: bool,
+    /// Input filter, or the raw value of a reserved filter type encoding.
+    pub filter_type: Result<FilterType, u8>,
+    pub filter_clk_sel: FilterClockSelect,
+    pub delay: PinDelay,
+}
+
+impl DecodedPinConfig {
+    /// Decode the raw IOCONFIG register value of the pin and the DIR, DELAY1 and DELAY2 registers
+    /// of its port.
+    pub fn from_registers(id: DynPinId, config: u32, dir: u32, delay1: u32, delay2: u32) -> Self {
+        let config = Config::new_with_raw_value(config);
+        let mask = 1 << id.offset();
+        Self {
+            id,
+            funsel: config.funsel(),
+            output: dir & mask != 0,
+            pull: if config.pull_enable() {
+                Some(config.pull_dir())
+            } else {
+                None
+            },
+            pull_when_output_active: config.pull_when_output_active(),
+            open_drain: config.open_drain(),
+            invert_input: config.invert_input(),
+            invert_output: config.invert_output(),
+            input_enable_when_output: config.input_enable_when_output(),
+            io_disable: config.io_disable(),
+            filter_type: config.filter_type(),
+            filter_clk_sel: config.filter_clk_sel(),
+            delay: PinDelay::from_bits(delay1 & mask != 0, delay2 & mask != 0),
+        }
+    }
+
+    /// GPIO mode of the pin, or [None] if a peripheral function is selected.
+    pub fn gpio_mode(&self) -> Option<super::PinMode> {
+        if self.funsel != FunctionSelect::Sel0 {
+            return None;
+        }
+        Some(match (self.output, self.open_drain, self.pull) {
+            (true, true, _) => super::PinMode::OutputOpenDrain,
+            (true, false, _) => super::PinMode::OutputPushPull,
+            (false, _, Some(pull)) => super::PinMode::InputWithPull(pull),
+            (false, _, None) => super::PinMode::InputFloating,
+        })
+    }
+}
+
+/// Decoded configuration of all available pins of a port, for example for bring-up logging.
+///
+/// The registers are read when the iterator is advanced. The port clocks must be enabled, see
+/// [ensure_port_clocks].
+pub fn dump_port_config(port: Port) -> impl Iterator<Item = DecodedPinConfig> {
+    (0..port.max_offset())
+        .map(move |offset| DynPinId::new_unchecked(port, offset))
+        .filter(DynPinId::is_available)
+        .map(|id| LowLevelGpio::new(id).current_config())
+}
+
+/// IOCONFIG word of a GPIO input, derived from the current word of the pin. The filter settings
+/// are kept unless the input configuration has a filter.
+pub(crate) fn input_pin_config(mut config: Config, input_config: &InputConfig) -> Config {
+    config.set_funsel(FunctionSelect::Sel0);
+    config.set_io_disable(false);
+    config.set_invert_input(input_config.invert_input);
+    config.set_open_drain(false);
+    config.set_pull_enable(input_config.pull.is_some());
+    if let Some(pull) = input_config.pull {
+        config.set_pull_dir(pull);
+    }
+    if let Some((filter, clksel)) = input_config.filter {
+        config.set_filter_type(filter);
+        config.set_filter_clk_sel(clksel);
+    }
+    config.set_pull_when_output_active(false);
+    config.set_invert_output(false);
+    config.set_input_enable_when_output(false);
+    config
+}
+
+/// Update the DELAY1 and DELAY2 bits of the pin with the given offset.
+pub(crate) fn modify_pin_delay(
+    gpio: &mut super::regs::MmioGpio<'static>,
+    offset: usize,
+    delay: PinDelay,
+) {
+    gpio.modify_delay1(|mut value| {
+        if delay.delay_1() {
+            value |= 1 << offset;
+        } else {
+            value &= !(1 << offset);
+        }
+        value
+    });
+    gpio.modify_delay2(|mut value| {
+        if delay.delay_2() {
+            value |= 1 << offset;
+        } else {
+            value &= !(1 << offset);
+        }
+        value
+    });
+}
+
+/// IOCONFIG word of a GPIO output, derived from the current word of the pin. The filter settings
+/// are kept.
+pub(crate) fn output_pin_config(mut config: Config, output_config: &OutputConfig) -> Config {
+    config.set_funsel(FunctionSelect::Sel0);
+    config.set_io_disable(false);
+    config.set_invert_input(false);
+    config.set_open_drain(output_config.open_drain);
+    config.set_pull_enable(output_config.pull.is_some());
+    config.set_pull_dir(output_config.pull.unwrap_or(Pull::Up));
+    config.set_pull_when_output_active(output_config.pull_when_output_active);
+    config.set_invert_output(output_config.invert_output);
+    config.set_input_enable_when_output(true);
+    config
+}
+
+/// IOCONFIG word of a peripheral pin, derived from the current word of the pin. The filter, PLEVEL
+/// and IEWO settings are kept.
+pub(crate) fn peripheral_pin_config(
+    mut config: Config,
+    fun_sel: FunctionSelect,
+    pull: Option<Pull>,
+    invert_input: bool,
+    invert_output: bool,
+) -> Config {
+    config.set_funsel(fun_sel);
+    config.set_io_disable(false);
+    config.set_invert_input(invert_input);
+    config.set_open_drain(false);
+    config.set_pull_enable(pull.is_some());
+    config.set_pull_dir(pull.unwrap_or(Pull::Up));
+    config.set_invert_output(invert_output);
+    config
+}
+
 /// Low-level driver structure for GPIO pins.
 pub struct LowLevelGpio {
     gpio: super::regs::MmioGpio<'static>,
@@ -254,36 +894,18 @@ impl LowLevelGpio {
     }
 
     pub fn configure_as_input_floating(&mut self) {
-        self.ioconfig.modify_pin_config(self.id, |mut config| {
-            config.set_funsel(FunctionSelect::Sel0);
-            config.set_io_disable(false);
-            config.set_invert_input(false);
-            config.set_open_drain(false);
-            config.set_pull_enable(false);
-            config.set_pull_when_output_active(false);
-            config.set_invert_output(false);
-            config.set_input_enable_when_output(false);
-            config
-        });
-        self.gpio.modify_dir(|mut dir| {
-            dir &= !(1 << self.id.offset());
-            dir
-        });
+        self.configure_as_input(InputConfig::floating());
     }
 
     pub fn configure_as_input_with_pull(&mut self, pull: Pull) {
-        self.ioconfig.modify_pin_config(self.id, |mut config| {
-            config.set_funsel(FunctionSelect::Sel0);
-            config.set_io_disable(false);
-            config.set_invert_input(false);
-            config.set_open_drain(false);
-            config.set_pull_enable(true);
-            config.set_pull_dir(pull);
-            config.set_pull_when_output_active(false);
-            config.set_invert_output(false);
-            config.set_input_enable_when_output(false);
-            config
-        });
+        self.configure_as_input(InputConfig::floating().pull(Some(pull)));
+    }
+
+    /// Configure the pin as an input with the given pull, inversion and filter settings.
+    pub fn configure_as_input(&mut self, input_config: InputConfig) {
+        ensure_port_clocks(self.port());
+        self.ioconfig
+            .modify_pin_config(self.id, |config| input_pin_config(config, &input_config));
         self.gpio.modify_dir(|mut dir| {
             dir &= !(1 << self.id.offset());
             dir
@@ -291,40 +913,33 @@ impl LowLevelGpio {
     }
 
     pub fn configure_as_output_push_pull(&mut self, init_level: PinState) {
-        self.ioconfig.modify_pin_config(self.id, |mut config| {
-            config.set_funsel(FunctionSelect::Sel0);
-            config.set_io_disable(false);
-            config.set_invert_input(false);
-            config.set_open_drain(false);
-            config.set_pull_enable(false);
-            config.set_pull_when_output_active(false);
-            config.set_invert_output(false);
-            config.set_input_enable_when_output(true);
-            config
-        });
-        match init_level {
-            PinState::Low => self.gpio.write_clr_out(self.mask_32()),
-            PinState::High => self.gpio.write_set_out(self.mask_32()),
-        }
-        self.gpio.modify_dir(|mut dir| {
-            dir |= 1 << self.id.offset();
-            dir
-        });
+        self.configure_as_output(init_level, OutputConfig::push_pull());
     }
 
-    pub fn configure_as_output_open_drain(&mut self, init_level: PinState) {
-        self.ioconfig.modify_pin_config(self.id, |mut config| {
-            config.set_funsel(FunctionSelect::Sel0);
-            config.set_io_disable(false);
-            config.set_invert_input(false);
-            config.set_open_drain(true);
-            config.set_pull_enable(true);
-            config.set_pull_dir(Pull::Up);
-            config.set_pull_when_output_active(false);
-            config.set_invert_output(false);
-            config.set_input_enable_when_output(true);
-            config
-        });
+    /// Configure the pin as an open-drain output with the given pull configuration. See
+    /// [OutputConfig] and the [module documentation](super#output-configuration) for the
+    /// interaction of the pull with the output.
+    pub fn configure_as_output_open_drain(
+        &mut self,
+        init_level: PinState,
+        pull: Option<Pull>,
+        pull_when_output_active: bool,
+    ) {
+        self.configure_as_output(
+            init_level,
+            OutputConfig::open_drain()
+                .pull(pull)
+                .pull_when_output_active(pull_when_output_active),
+        );
+    }
+
+    /// Configure the pin as an output. The initial level is the value of the output register,
+    /// which is inverted on the pin if [OutputConfig::invert_output] is set.
+    pub fn configure_as_output(&mut self, init_level: PinState, output_config: OutputConfig) {
+        ensure_port_clocks(self.port());
+        self.ioconfig
+            .modify_pin_config(self.id, |config| output_pin_config(config, &output_config));
+        modify_pin_delay(&mut self.gpio, self.id.offset(), output_config.delay);
         let mask32 = self.mask_32();
         match init_level {
             PinState::Low => self.gpio.write_clr_out(mask32),
@@ -337,15 +952,21 @@ impl LowLevelGpio {
     }
 
     pub fn configure_as_peripheral_pin(&mut self, fun_sel: FunctionSelect, pull: Option<Pull>) {
-        self.ioconfig.modify_pin_config(self.id, |mut config| {
-            config.set_funsel(fun_sel);
-            config.set_io_disable(false);
-            config.set_invert_input(false);
-            config.set_open_drain(false);
-            config.set_pull_enable(pull.is_some());
-            config.set_pull_dir(pull.unwrap_or(Pull::Up));
-            config.set_invert_output(false);
-            config
+        self.configure_as_peripheral_pin_with_inversion(fun_sel, pull, false, false);
+    }
+
+    /// Configure the pin as a peripheral pin with the given inversion of the input and output
+    /// path of the IOCONFIG peripheral.
+    pub fn configure_as_peripheral_pin_with_inversion(
+        &mut self,
+        fun_sel: FunctionSelect,
+        pull: Option<Pull>,
+        invert_input: bool,
+        invert_output: bool,
+    ) {
+        ensure_port_clocks(self.port());
+        self.ioconfig.modify_pin_config(self.id, |config| {
+            peripheral_pin_config(config, fun_sel, pull, invert_input, invert_output)
         });
     }
 
@@ -386,6 +1007,7 @@ impl LowLevelGpio {
 
     #[cfg(feature = "vor1x")]
     pub fn enable_interrupt(&mut self, irq_cfg: crate::InterruptConfig) {
+        ensure_port_clocks(self.port());
         if irq_cfg.route {
             self.configure_irqsel(irq_cfg.id);
         }
@@ -403,6 +1025,16 @@ impl LowLevelGpio {
         &mut self,
         enable_in_nvic: bool,
     ) -> Result<(), PortDoesNotSupportInterrupts> {
+        self.id.irq()?;
+        self.enable_interrupt_unchecked(enable_in_nvic);
+        Ok(())
+    }
+
+    /// Enable the interrupt without checking the port. This panics for [Port::G] pins if the
+    /// interrupt is enabled in the NVIC.
+    #[cfg(feature = "vor4x")]
+    pub(crate) fn enable_interrupt_unchecked(&mut self, enable_in_nvic: bool) {
+        ensure_port_clocks(self.port());
         if enable_in_nvic {
             unsafe { crate::enable_nvic_interrupt(self.id().irq_unchecked()) };
         }
@@ -410,7 +1042,6 @@ impl LowLevelGpio {
             value |= 1 << self.id.offset;
             value
         });
-        Ok(())
     }
 
     #[cfg(feature = "vor1x")]
@@ -433,10 +1064,81 @@ impl LowLevelGpio {
         });
     }
 
+    /// Returns whether the interrupt of the pin is enabled in the GPIO peripheral.
+    #[inline]
+    pub fn interrupt_enabled(&self) -> bool {
+        self.gpio.read_irq_enable() & self.mask_32() != 0
+    }
+
+    /// Configure the edge interrupt and enable it, which routes the interrupt to the given
+    /// vector and unmasks it in the NVIC. Edges which were latched before are cleared, so only
+    /// edges after this call are detected.
+    ///
+    /// This is the common setup of the blocking and the asynchronous edge waits.
+    #[cfg(feature = "vor1x")]
+    pub(crate) fn arm_edge_interrupt(&mut self, irq: va108xx::Interrupt, edge: InterruptEdge) {
+        self.configure_edge_interrupt(edge);
+        self.clear_interrupt_pending();
+        self.enable_interrupt(crate::InterruptConfig::new(irq, true, true));
+    }
+
+    /// Configure the edge interrupt and enable it, which unmasks the pin interrupt in the NVIC.
+    /// Edges which were latched before are cleared, so only edges after this call are detected.
+    ///
+    /// This is the common setup of the blocking and the asynchronous edge waits.
+    #[cfg(feature = "vor4x")]
+    pub(crate) fn arm_edge_interrupt(
+        &mut self,
+        edge: InterruptEdge,
+    ) -> Result<(), PortDoesNotSupportInterrupts> {
+        self.id.irq()?;
+        self.arm_edge_interrupt_unchecked(edge);
+        Ok(())
+    }
+
+    /// [Self::arm_edge_interrupt] without checking the port. This panics for [Port::G] pins.
+    #[cfg(feature = "vor4x")]
+    pub(crate) fn arm_edge_interrupt_unchecked(&mut self, edge: InterruptEdge) {
+        self.configure_edge_interrupt(edge);
+        self.clear_interrupt_pending();
+        self.enable_interrupt_unchecked(true);
+    }
+
+    /// Disable the pin interrupt after an edge wait. The IRQSEL routing and the NVIC are not
+    /// changed, because other pins might share the interrupt vector.
+    pub(crate) fn disarm_edge_interrupt(&mut self) {
+        #[cfg(feature = "vor1x")]
+        self.disable_interrupt(false);
+        #[cfg(feature = "vor4x")]
+        self.disable_interrupt();
+    }
+
+    /// Returns whether an edge was latched for this pin. The edge status is latched even if the
+    /// interrupt is not enabled.
+    #[inline]
+    pub(crate) fn edge_detected(&self) -> bool {
+        self.gpio.read_edge_status() & self.mask_32() != 0
+    }
+
+    /// Returns whether the pin has an active interrupt. Interrupts which are not enabled are
+    /// never reported as pending.
+    #[inline]
+    pub fn interrupt_pending(&self) -> bool {
+        port_interrupt_status(self.port()) & self.mask_32() != 0
+    }
+
+    /// Clears the latched edge status of this pin by writing a one to its bit in the edge
+    /// status register. The edge status of all other pins of the port is not affected.
+    #[inline]
+    pub fn clear_interrupt_pending(&mut self) {
+        self.gpio.write_edge_status(self.mask_32());
+    }
+
     /// Only useful for interrupt pins. Configure whether to use edges or level as interrupt soure
     /// When using edge mode, it is possible to generate interrupts on both edges as well
     #[inline]
     pub fn configure_edge_interrupt(&mut self, edge_type: InterruptEdge) {
+        ensure_port_clocks(self.port());
         let mask32 = self.mask_32();
         self.gpio.modify_irq_sen(|mut value| {
             value &= !mask32;
@@ -467,6 +1169,7 @@ impl LowLevelGpio {
     /// Configure which edge or level type triggers an interrupt
     #[inline]
     pub fn configure_level_interrupt(&mut self, level: InterruptLevel) {
+        ensure_port_clocks(self.port());
         let mask32 = self.mask_32();
         self.gpio.modify_irq_sen(|mut value| {
             value |= mask32;
@@ -488,6 +1191,7 @@ impl LowLevelGpio {
     /// Only useful for input pins
     #[inline]
     pub fn configure_filter_type(&mut self, filter: FilterType, clksel: FilterClockSelect) {
+        ensure_port_clocks(self.port());
         self.ioconfig.modify_pin_config(self.id, |mut config| {
             config.set_filter_type(filter);
             config.set_filter_clk_sel(clksel);
@@ -498,6 +1202,7 @@ impl LowLevelGpio {
     /// Only useful for output pins.
     #[inline]
     pub fn configure_pulse_mode(&mut self, enable: bool, default_state: PinState) {
+        ensure_port_clocks(self.port());
         self.gpio.modify_pulse(|mut value| {
             if enable {
                 value |= 1 << self.id.offset;
@@ -517,64 +1222,56 @@ impl LowLevelGpio {
     }
 
     /// Only useful for output pins
+    #[deprecated(note = "use set_delay with a PinDelay instead")]
     #[inline]
     pub fn configure_delay(&mut self, delay_1: bool, delay_2: bool) {
-        self.gpio.modify_delay1(|mut value| {
-            if delay_1 {
-                value |= 1 << self.id.offset;
-            } else {
-                value &= !(1 << self.id.offset);
-            }
-            value
-        });
-        self.gpio.modify_delay2(|mut value| {
-            if delay_2 {
-                value |= 1 << self.id.offset;
-            } else {
-                value &= !(1 << self.id.offset);
-            }
-            value
-        });
+        self.set_delay(PinDelay::from_bits(delay_1, delay_2));
+    }
+
+    /// Configure the output delay of the pin. See [PinDelay] for details.
+    #[inline]
+    pub fn set_delay(&mut self, delay: PinDelay) {
+        ensure_port_clocks(self.port());
+        modify_pin_delay(&mut self.gpio, self.id.offset(), delay);
+    }
+
+    /// Checks whether the pin is configured as an output in the DIR register of the port.
+    #[inline]
+    pub fn is_output(&self) -> bool {
+        self.gpio.read_dir() & self.mask_32() != 0
+    }
+
+    /// Read back and decode the current configuration of the pin.
+    pub fn current_config(&self) -> DecodedPinConfig {
+        DecodedPinConfig::from_registers(
+            self.id,
+            self.ioconfig.read_pin_config(self.id).raw_value(),
+            self.gpio.read_dir(),
+            self.gpio.read_delay1(),
+            self.gpio.read_delay2(),
+        )
+    }
+
+    /// Configured output delay of the pin.
+    #[inline]
+    pub fn delay(&self) -> PinDelay {
+        let mask = self.mask_32();
+        PinDelay::from_bits(
+            self.gpio.read_delay1() & mask != 0,
+            self.gpio.read_delay2() & mask != 0,
+        )
     }
 
     #[cfg(feature = "vor1x")]
     /// Configure the IRQSEL peripheral for this particular pin with the given interrupt ID.
     pub fn configure_irqsel(&mut self, id: va108xx::Interrupt) {
-        let irqsel = unsafe { va108xx::Irqsel::steal() };
-        enable_peripheral_clock(PeripheralSelect::Irqsel);
-        match self.id().port() {
-            // Set the correct interrupt number in the IRQSEL register
-            super::Port::A => {
-                irqsel
-                    .porta(self.id().offset())
-                    .write(|w| unsafe { w.bits(id as u32) });
-            }
-            super::Port::B => {
-                irqsel
-                    .portb(self.id().offset())
-                    .write(|w| unsafe { w.bits(id as u32) });
-            }
-        }
+        crate::irqsel::route_gpio(self.id(), id);
     }
 
     #[cfg(feature = "vor1x")]
     /// Reset the IRQSEL peripheral value for this particular pin.
     pub fn reset_irqsel(&mut self) {
-        let irqsel = unsafe { va108xx::Irqsel::steal() };
-        enable_peripheral_clock(PeripheralSelect::Irqsel);
-        match self.id().port() {
-            // Set the correct interrupt number in the IRQSEL register
-            super::Port::A => {
-                irqsel
-                    .porta(self.id().offset())
-                    .write(|w| unsafe { w.bits(u32::MAX) });
-            }
-            super::Port::B => {
-                irqsel
-                    .portb(self.id().offset())
-                    .write(|w| unsafe { w.bits(u32::MAX) });
-            }
-        }
+        crate::irqsel::unroute_gpio(self.id());
     }
 
     #[inline(always)]
@@ -582,3 +1279,163 @@ impl LowLevelGpio {
         1 << self.id.offset()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "vor1x")]
+    const PORTS: &[Port] = &[Port::A, Port::B];
+    #[cfg(feature = "vor4x")]
+    const PORTS: &[Port] = &[
+        Port::A,
+        Port::B,
+        Port::C,
+        Port::D,
+        Port::E,
+        Port::F,
+        Port::G,
+    ];
+
+    #[test]
+    fn decode_pin_config() {
+        let id = DynPinId::new_unchecked(Port::A, 5);
+        let config = (0b01 << 13) // FUNSEL 1
+            | (1 << 11) // PEN
+            | (1 << 10) // PAD pull-down
+            | (1 << 9) // INVOUT
+            | (1 << 8) // OPENDRN
+            | (3 << 3) // FLTCLK 3
+            | 3; // Two cycle filter
+        let decoded = DecodedPinConfig::from_registers(id, config, 1 << 5, 0, (1 << 5) | 1);
+        assert_eq!(
+            decoded,
+            DecodedPinConfig {
+                id,
+                funsel: FunctionSelect::Sel1,
+                output: true,
+                pull: Some(Pull::Down),
+                pull_when_output_active: false,
+                open_drain: true,
+                invert_input: false,
+                invert_output: true,
+                input_enable_when_output: false,
+                io_disable: false,
+                filter_type: Ok(FilterType::FilterTwoCycles),
+                filter_clk_sel: FilterClockSelect::Clk3,
+                delay: PinDelay::Two,
+            }
+        );
+        assert_eq!(decoded.gpio_mode(), None);
+    }
+
+    #[test]
+    fn decode_pin_config_flags() {
+        let id = DynPinId::new_unchecked(Port::B, 2);
+        // IODIS, PLEVEL, IEWO, INVINP and a reserved filter type.
+        let config = (1 << 16) | (1 << 12) | (1 << 7) | (1 << 6) | 7;
+        // Only the bits of the pin are decoded.
+        let decoded = DecodedPinConfig::from_registers(id, config, !(1 << 2), 1 << 2, !(1 << 2));
+        assert!(decoded.io_disable);
+        assert!(decoded.pull_when_output_active);
+        assert!(decoded.input_enable_when_output);
+        assert!(decoded.invert_input);
+        assert!(!decoded.invert_output);
+        assert_eq!(decoded.funsel, FunctionSelect::Sel0);
+        assert_eq!(decoded.filter_type, Err(7));
+        assert_eq!(decoded.filter_clk_sel, FilterClockSelect::SysClk);
+        assert_eq!(decoded.pull, None);
+        assert!(!decoded.output);
+        assert_eq!(decoded.delay, PinDelay::One);
+    }
+
+    #[test]
+    fn decode_gpio_mode() {
+        use crate::gpio::PinMode;
+        let id = DynPinId::new_unchecked(Port::A, 0);
+        let mode =
+            |config, dir| DecodedPinConfig::from_registers(id, config, dir, 0, 0).gpio_mode();
+        assert_eq!(mode(0, 0), Some(PinMode::InputFloating));
+        assert_eq!(mode(1 << 11, 0), Some(PinMode::InputWithPull(Pull::Up)));
+        assert_eq!(
+            mode((1 << 11) | (1 << 10), 0),
+            Some(PinMode::InputWithPull(Pull::Down))
+        );
+        assert_eq!(mode(1 << 7, 1), Some(PinMode::OutputPushPull));
+        assert_eq!(
+            mode((1 << 8) | (1 << 11), 1),
+            Some(PinMode::OutputOpenDrain)
+        );
+        assert_eq!(mode(0b11 << 13, 1), None);
+    }
+
+    #[test]
+    fn compact_roundtrip() {
+        let mut count = 0;
+        for &port in PORTS {
+            for offset in 0..port.max_offset() {
+                let id = DynPinId::new(port, offset).unwrap();
+                let compact = id.to_compact();
+                assert_eq!(compact >> 5, port as u8);
+                assert_eq!((compact & 0x1F) as usize, offset);
+                if id.is_available() {
+                    assert_eq!(DynPinId::from_compact(compact).unwrap(), id);
+                    assert_eq!(DynPinId::try_from(u8::from(id)).unwrap(), id);
+                    count += 1;
+                } else {
+                    assert!(matches!(
+                        DynPinId::from_compact(compact),
+                        Err(InvalidCompactPinIdError::NotAvailable(missing)) if missing == id
+                    ));
+                }
+            }
+        }
+        #[cfg(feature = "vor1x")]
+        assert_eq!(count, 56);
+        #[cfg(all(feature = "vor4x", not(feature = "va41628")))]
+        assert_eq!(count, 104);
+        #[cfg(feature = "va41628")]
+        assert_eq!(count, 104 - VA41628_MISSING_PINS.len());
+    }
+
+    #[test]
+    fn compact_rejected() {
+        let mut accepted = 0;
+        for compact in 0..=u8::MAX {
+            match DynPinId::from_compact(compact) {
+                Ok(_) => accepted += 1,
+                Err(InvalidCompactPinIdError::InvalidPort(port)) => {
+                    assert_eq!(port, compact >> 5);
+                    assert!(port as usize >= PORTS.len());
+                }
+                Err(InvalidCompactPinIdError::InvalidOffset(_)) => {
+                    let port = PORTS[(compact >> 5) as usize];
+                    assert!((compact & 0x1F) as usize >= port.max_offset());
+                }
+                Err(InvalidCompactPinIdError::NotAvailable(id)) => {
+                    assert!(!id.is_available());
+                }
+            }
+        }
+        let available = PORTS
+            .iter()
+            .flat_map(|&port| (0..port.max_offset()).map(move |offset| (port, offset)))
+            .filter(|&(port, offset)| DynPinId::new_unchecked(port, offset).is_available())
+            .count();
+        assert_eq!(accepted, available);
+        #[cfg(feature = "vor1x")]
+        assert!(matches!(
+            DynPinId::from_compact((1 << 5) | 24),
+            Err(InvalidCompactPinIdError::InvalidOffset(_))
+        ));
+        #[cfg(feature = "vor4x")]
+        assert!(matches!(
+            DynPinId::from_compact((6 << 5) | 8),
+            Err(InvalidCompactPinIdError::InvalidOffset(_))
+        ));
+        assert!(matches!(
+            DynPinId::from_compact(7 << 5),
+            Err(InvalidCompactPinIdError::InvalidPort(7))
+        ));
+    }
+}
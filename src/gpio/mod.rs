@@ -1,70 +1,233 @@
 //! GPIO support module.
-use core::convert::Infallible;
-
+//!
+//! ## Output performance
+//!
+//! [Output] stores the GPIO register block pointer and the precomputed pin mask. Setting,
+//! clearing and toggling an output pin therefore is a single store to the SETOUT, CLROUT or
+//! TOGOUT register, which takes 2 clock cycles on the Cortex-M0 of the VA108xx and 1-2 clock
+//! cycles on the Cortex-M4 of the VA416xx, not counting peripheral bus wait states. The
+//! [write_multiple] function can be used for grouped updates of multiple pins of one port. It
+//! uses one store to SETOUT followed by one store to CLROUT, so all pins are updated within a
+//! few clock cycles of each other.
+//!
+//! ## Output configuration
+//!
+//! The [OutputConfig] of an [Output] selects the output driver, the pull resistor and the
+//! inversion of the IOCONFIG peripheral. The pin level is the output register value, inverted
+//! if `invert_output` is set. The pull is only connected while the pin is an output if
+//! `pull_when_output_active` (PLEVEL bit) is set:
+//!
+//! | `open_drain` | `pull` | `pull_when_output_active` | Pin level high | Pin level low |
+//! |--------------|--------|---------------------------|----------------|---------------|
+//! | no  | [None] | ignored | Driven high | Driven low |
+//! | no  | [Some] | no  | Driven high | Driven low |
+//! | no  | [Some] | yes | Driven high, a pull-down draws current | Driven low, a pull-up draws current |
+//! | yes | [None] | ignored | Released, requires an external pull-up | Driven low |
+//! | yes | [Some] | no  | Released, requires an external pull-up | Driven low |
+//! | yes | [Some] | yes | Released and pulled by the internal pull | Driven low, a pull-up draws current |
+//!
+//! For an open-drain bus which relies on the internal pull-up, `pull_when_output_active` must be
+//! set. Otherwise, the pull-up is disconnected as long as the pin is an output, so the released
+//! line is only charged by external resistors. This is the case for [OutputConfig::open_drain],
+//! which keeps the behaviour of previous releases. A pull-down is only useful for an open-drain
+//! output if the pin is inverted or switched to an input later. With `invert_output`, setting
+//! the output of an open-drain pin drives it low and clearing the output releases it.
+use core::{convert::Infallible, marker::PhantomData};
+
+use crate::busy_wait::BusyWait;
 pub use crate::ioconfig::{FilterClockSelect, FilterType, regs::FunctionSelect};
-pub use crate::pins::{Pin, PinId};
+pub use crate::pins::{InterruptCapable, NoInterrupts, Pin, PinId};
+use crate::power::SleepParticipant;
 pub use embedded_hal::digital::PinState;
-pub use ll::{DynPinId, InterruptEdge, InterruptLevel, Port, Pull};
+pub use group::{InputGroup, PortGroup, read_port_snapshot};
+pub use ll::{
+    DecodedPinConfig, DynPinId, GpioIrqView, InputConfig, InterruptEdge, InterruptLevel,
+    InvalidCompactPinIdError, OutputConfig, PinDelay, Port, Pull, clear_port_edge_status,
+    dump_port_config, ensure_port_clocks, port_interrupt_status, write_multiple,
+};
+#[cfg(feature = "vor1x")]
+pub use ll::{RoutedPins, pins_routed_to_interrupt};
+pub use mux::{MuxFunction, PeripheralFunction, PinMux, PinMuxError};
 
 pub mod asynch;
+pub mod debounce;
+pub mod group;
 pub mod ll;
+pub mod mux;
 pub mod regs;
 
-/// Push-Pull output pin.
-#[derive(Debug)]
-pub struct Output(ll::LowLevelGpio);
+/// Output pin, which is a push-pull output unless it is created with a different
+/// [OutputConfig].
+pub struct Output {
+    id: DynPinId,
+    handle: ll::OutputHandle,
+    sleep_level: Option<PinState>,
+    /// Output level recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
+}
+
+impl core::fmt::Debug for Output {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Output").field("id", &self.id).finish()
+    }
+}
 
 impl Output {
     pub fn new<I: PinId>(_pin: Pin<I>, init_level: PinState) -> Self {
-        let mut ll = ll::LowLevelGpio::new(I::ID);
-        ll.configure_as_output_push_pull(init_level);
-        Output(ll)
+        Self::new_for_pin_id(I::ID, init_level)
+    }
+
+    /// Create a new output pin with the given output configuration, see the
+    /// [module documentation](self#output-configuration).
+    pub fn new_with_config<I: PinId>(
+        _pin: Pin<I>,
+        init_level: PinState,
+        config: OutputConfig,
+    ) -> Self {
+        Self::new_for_pin_id_with_config(I::ID, init_level, config)
+    }
+
+    /// Create a new open-drain output pin with the given pull configuration, see the
+    /// [module documentation](self#output-configuration).
+    pub fn new_open_drain<I: PinId>(
+        pin: Pin<I>,
+        init_level: PinState,
+        pull: Option<Pull>,
+        pull_when_output_active: bool,
+    ) -> Self {
+        Self::new_with_config(
+            pin,
+            init_level,
+            OutputConfig::open_drain()
+                .pull(pull)
+                .pull_when_output_active(pull_when_output_active),
+        )
+    }
+
+    /// Configure the pin with the given ID as an output. The caller must own the pin.
+    pub(crate) fn new_for_pin_id(id: DynPinId, init_level: PinState) -> Self {
+        Self::new_for_pin_id_with_config(id, init_level, OutputConfig::push_pull())
+    }
+
+    fn new_for_pin_id_with_config(
+        id: DynPinId,
+        init_level: PinState,
+        config: OutputConfig,
+    ) -> Self {
+        let mut ll = ll::LowLevelGpio::new(id);
+        ll.configure_as_output(init_level, config);
+        Output {
+            id,
+            handle: ll::OutputHandle::new(id),
+            sleep_level: None,
+            sleep_state: None,
+        }
+    }
+
+    /// Create a new push-pull output pin with the given output delay. See [PinDelay] for
+    /// details and [OutputConfig::delay] for other output configurations.
+    pub fn new_with_delay<I: PinId>(pin: Pin<I>, init_level: PinState, delay: PinDelay) -> Self {
+        Self::new_with_config(pin, init_level, OutputConfig::push_pull().delay(delay))
+    }
+
+    #[inline]
+    pub fn id(&self) -> DynPinId {
+        self.id
     }
 
     #[inline]
     pub fn port(&self) -> Port {
-        self.0.port()
+        self.id.port()
     }
 
     #[inline]
     pub fn offset(&self) -> usize {
-        self.0.offset()
+        self.id.offset()
     }
 
-    #[inline]
+    #[inline(always)]
     pub fn set_high(&mut self) {
-        self.0.set_high();
+        self.handle.set_high();
     }
 
-    #[inline]
+    #[inline(always)]
     pub fn set_low(&mut self) {
-        self.0.set_low();
+        self.handle.set_low();
     }
 
     #[inline]
     pub fn is_set_high(&self) -> bool {
-        self.0.is_set_high()
+        self.handle.is_set_high()
     }
 
     #[inline]
     pub fn is_set_low(&self) -> bool {
-        self.0.is_set_low()
+        self.handle.is_set_low()
     }
 
     /// Toggle pin output with dedicated HW feature.
-    #[inline]
+    #[inline(always)]
     pub fn toggle(&mut self) {
-        self.0.toggle();
+        self.handle.toggle();
     }
 
     #[inline]
     pub fn configure_pulse_mode(&mut self, enable: bool, default_state: PinState) {
-        self.0.configure_pulse_mode(enable, default_state);
+        ll::LowLevelGpio::new(self.id).configure_pulse_mode(enable, default_state);
     }
 
+    #[deprecated(note = "use set_delay with a PinDelay instead")]
     #[inline]
     pub fn configure_delay(&mut self, delay_1: bool, delay_2: bool) {
-        self.0.configure_delay(delay_1, delay_2);
+        self.set_delay(PinDelay::from_bits(delay_1, delay_2));
+    }
+
+    /// Configure the output delay of the pin. See [PinDelay] for details.
+    #[inline]
+    pub fn set_delay(&mut self, delay: PinDelay) {
+        ll::LowLevelGpio::new(self.id).set_delay(delay);
+    }
+
+    #[inline]
+    pub fn delay(&self) -> PinDelay {
+        ll::LowLevelGpio::new(self.id).delay()
+    }
+
+    /// Set the level which is driven while the pin is prepared for sleep, for example the
+    /// inactive level of a chip select. With [None], which is the default, the level is not
+    /// changed. See [crate::power].
+    #[inline]
+    pub fn set_sleep_level(&mut self, level: Option<PinState>) {
+        self.sleep_level = level;
+    }
+
+    #[inline]
+    pub fn sleep_level(&self) -> Option<PinState> {
+        self.sleep_level
+    }
+}
+
+impl SleepParticipant for Output {
+    /// Drives the level set with [Output::set_sleep_level].
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_some() {
+            return;
+        }
+        self.sleep_state = Some(self.handle.is_set_high());
+        match self.sleep_level {
+            Some(PinState::Low) => self.handle.set_low(),
+            Some(PinState::High) => self.handle.set_high(),
+            None => (),
+        }
+    }
+
+    /// Restores the level before [SleepParticipant::prepare_sleep].
+    fn resume(&mut self) {
+        match self.sleep_state.take() {
+            Some(true) => self.handle.set_high(),
+            Some(false) => self.handle.set_low(),
+            None => (),
+        }
     }
 }
 
@@ -74,54 +237,60 @@ impl embedded_hal::digital::ErrorType for Output {
 
 impl embedded_hal::digital::OutputPin for Output {
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.0.set_low();
+        self.handle.set_low();
         Ok(())
     }
 
     fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.0.set_high();
+        self.handle.set_high();
         Ok(())
     }
 }
 
 impl embedded_hal::digital::StatefulOutputPin for Output {
     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
-        Ok(self.0.is_set_high())
+        Ok(self.handle.is_set_high())
     }
 
     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
-        Ok(self.0.is_set_low())
+        Ok(self.handle.is_set_low())
     }
 
     /// Toggle pin output with dedicated HW feature.
     fn toggle(&mut self) -> Result<(), Self::Error> {
-        self.0.toggle();
+        self.handle.toggle();
         Ok(())
     }
 }
 
 /// Input pin.
 ///
-/// Can be created as a floating input pin or as an input pin with pull-up or pull-down.
+/// Can be created as a floating input pin or as an input pin with pull-up or pull-down. The
+/// type parameter is [InterruptCapable] for pins which can generate interrupts and
+/// [NoInterrupts] for the port G pins of the VA416xx family, which is determined by the
+/// [PinId::Interrupts] of the pin passed to the constructor. The interrupt functions are only
+/// available for interrupt capable pins.
 #[derive(Debug)]
-pub struct Input(ll::LowLevelGpio);
+pub struct Input<Interrupts = InterruptCapable>(ll::LowLevelGpio, PhantomData<Interrupts>);
 
 impl Input {
-    pub fn new_floating<I: PinId>(_pin: Pin<I>) -> Self {
+    pub fn new_floating<I: PinId>(_pin: Pin<I>) -> Input<I::Interrupts> {
         let mut ll = ll::LowLevelGpio::new(I::ID);
         ll.configure_as_input_floating();
-        Input(ll)
+        Input(ll, PhantomData)
     }
 
-    pub fn new_with_pull<I: PinId>(_pin: Pin<I>, pull: Pull) -> Self {
+    pub fn new_with_pull<I: PinId>(_pin: Pin<I>, pull: Pull) -> Input<I::Interrupts> {
         let mut ll = ll::LowLevelGpio::new(I::ID);
         ll.configure_as_input_with_pull(pull);
-        Input(ll)
+        Input(ll, PhantomData)
     }
 
-    #[inline]
-    pub fn id(&self) -> DynPinId {
-        self.0.id()
+    /// Create a new input pin with the given pull, inversion and filter settings.
+    pub fn new_with_config<I: PinId>(_pin: Pin<I>, config: InputConfig) -> Input<I::Interrupts> {
+        let mut ll = ll::LowLevelGpio::new(I::ID);
+        ll.configure_as_input(config);
+        Input(ll, PhantomData)
     }
 
     #[cfg(feature = "vor1x")]
@@ -132,11 +301,10 @@ impl Input {
 
     #[cfg(feature = "vor4x")]
     #[inline]
-    pub fn enable_interrupt(
-        &mut self,
-        enable_in_nvic: bool,
-    ) -> Result<(), ll::PortDoesNotSupportInterrupts> {
-        self.0.enable_interrupt(enable_in_nvic)
+    pub fn enable_interrupt(&mut self, enable_in_nvic: bool) {
+        // Port G pins, which have no interrupt, can not be converted to an interrupt capable
+        // input.
+        self.0.enable_interrupt_unchecked(enable_in_nvic);
     }
 
     #[inline]
@@ -144,14 +312,135 @@ impl Input {
         self.0.configure_edge_interrupt(edge);
     }
 
+    /// Returns whether this pin has an active interrupt.
+    #[inline]
+    pub fn interrupt_pending(&self) -> bool {
+        self.0.interrupt_pending()
+    }
+
+    /// Acknowledges a latched edge interrupt of this pin. See [clear_port_edge_status] for
+    /// details on the write-one-to-clear semantics.
+    #[inline]
+    pub fn clear_interrupt_pending(&mut self) {
+        self.0.clear_interrupt_pending();
+    }
+
+    /// Checks whether the interrupt of this pin is pending in the NVIC. Unlike
+    /// [Self::interrupt_pending], this checks the NVIC and not the GPIO peripheral.
+    ///
+    /// On Vorago 1x devices, this returns [false] if the pin interrupt was not routed by the
+    /// HAL, see [DynPinId::routed_interrupt].
+    #[inline]
+    pub fn irq_pending(&self) -> bool {
+        #[cfg(feature = "vor1x")]
+        return self
+            .id()
+            .routed_interrupt()
+            .is_some_and(crate::is_interrupt_pending);
+        #[cfg(feature = "vor4x")]
+        return self.id().irq().is_ok_and(crate::is_interrupt_pending);
+    }
+
     #[inline]
     pub fn configure_level_interrupt(&mut self, edge: InterruptLevel) {
         self.0.configure_level_interrupt(edge);
     }
 
+    /// Block until the given edge is detected on this pin, without an async executor.
+    ///
+    /// The edge interrupt is configured and enabled with the same setup as the asynchronous
+    /// edge waits of [asynch::InputPinAsync]. Edges which were latched before this call are
+    /// discarded. If `wfi` is set, the CPU sleeps with the WFI instruction between the checks
+    /// of the edge status. Otherwise, the edge status is polled. The detected edge is cleared
+    /// before returning, and the interrupt enable of the pin is restored. The edge
+    /// configuration is kept.
+    ///
+    /// The edge status is checked with interrupts disabled right before each WFI. An edge which
+    /// occurs between the arming and the WFI therefore leaves the interrupt pending, and WFI
+    /// returns immediately.
+    ///
+    /// The pin interrupt is routed to `irq` and unmasked in the NVIC, because only unmasked
+    /// interrupts wake the CPU from WFI. The interrupt is taken after the wake-up, so a handler
+    /// must be declared for `irq`, but it can be empty: [crate::gpio_wakeup_irqs] declares one.
+    /// A `DefaultHandler` which returns is sufficient as well. The handler of
+    /// [asynch::on_interrupt_for_async_gpio_for_port] can also be used if the vector is shared
+    /// with asynchronous pins.
+    #[cfg(feature = "vor1x")]
+    pub fn wait_for_edge_blocking(
+        &mut self,
+        irq: va108xx::Interrupt,
+        edge: InterruptEdge,
+        wfi: bool,
+    ) {
+        let was_enabled = self.0.interrupt_enabled();
+        self.0.arm_edge_interrupt(irq, edge);
+        self.wait_for_armed_edge(was_enabled, wfi);
+    }
+
+    /// Block until the given edge is detected on this pin, without an async executor.
+    ///
+    /// The edge interrupt is configured and enabled with the same setup as the asynchronous
+    /// edge waits of [asynch::InputPinAsync]. Edges which were latched before this call are
+    /// discarded. If `wfi` is set, the CPU sleeps with the WFI instruction between the checks
+    /// of the edge status. Otherwise, the edge status is polled. The detected edge is cleared
+    /// before returning, and the interrupt enable of the pin is restored. The edge
+    /// configuration is kept.
+    ///
+    /// The edge status is checked with interrupts disabled right before each WFI. An edge which
+    /// occurs between the arming and the WFI therefore leaves the interrupt pending, and WFI
+    /// returns immediately.
+    ///
+    /// The pin interrupt is unmasked in the NVIC, because only unmasked interrupts wake the CPU
+    /// from WFI. The interrupt is taken after the wake-up, so a handler must be declared for the
+    /// pin interrupt, but it can be empty: [crate::gpio_wakeup_irqs] declares one. A
+    /// `DefaultHandler` which returns is sufficient as well.
+    #[cfg(feature = "vor4x")]
+    pub fn wait_for_edge_blocking(&mut self, edge: InterruptEdge, wfi: bool) {
+        let was_enabled = self.0.interrupt_enabled();
+        // Port G pins, which have no interrupt, can not be converted to an interrupt capable
+        // input.
+        self.0.arm_edge_interrupt_unchecked(edge);
+        self.wait_for_armed_edge(was_enabled, wfi);
+    }
+
+    fn wait_for_armed_edge(&mut self, was_enabled: bool, wfi: bool) {
+        let mut busy_wait = BusyWait::new();
+        loop {
+            busy_wait.tick();
+            let detected = if wfi {
+                // A pending interrupt wakes the CPU even if interrupts are disabled.
+                cortex_m::interrupt::free(|_| {
+                    let detected = self.0.edge_detected();
+                    if !detected {
+                        cortex_m::asm::wfi();
+                    }
+                    detected
+                })
+            } else {
+                self.0.edge_detected()
+            };
+            if detected {
+                break;
+            }
+        }
+        self.0.clear_interrupt_pending();
+        if !was_enabled {
+            self.0.disarm_edge_interrupt();
+        }
+    }
+}
+
+impl<Interrupts> Input<Interrupts> {
+    #[inline]
+    pub fn id(&self) -> DynPinId {
+        self.0.id()
+    }
+
+    /// The output delay has no effect on input pins.
+    #[deprecated(note = "the output delay has no effect on input pins")]
     #[inline]
     pub fn configure_delay(&mut self, delay_1: bool, delay_2: bool) {
-        self.0.configure_delay(delay_1, delay_2);
+        self.0.set_delay(PinDelay::from_bits(delay_1, delay_2));
     }
 
     #[inline]
@@ -170,11 +459,11 @@ impl Input {
     }
 }
 
-impl embedded_hal::digital::ErrorType for Input {
+impl<Interrupts> embedded_hal::digital::ErrorType for Input<Interrupts> {
     type Error = Infallible;
 }
 
-impl embedded_hal::digital::InputPin for Input {
+impl<Interrupts> embedded_hal::digital::InputPin for Input<Interrupts> {
     fn is_low(&mut self) -> Result<bool, Self::Error> {
         Ok(self.0.is_low())
     }
@@ -184,7 +473,7 @@ impl embedded_hal::digital::InputPin for Input {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PinMode {
     InputFloating,
@@ -212,21 +501,38 @@ impl PinMode {
 ///  - Output Push-Pull
 ///  - Output Open-Drain.
 ///
-/// Flex pins are always floating input pins after construction.
+/// Flex pins are always floating input pins after construction. The mode is not cached but read
+/// back from the hardware, so it is also correct if the pin was re-configured by other means.
 #[derive(Debug)]
 pub struct Flex {
     ll: ll::LowLevelGpio,
-    mode: PinMode,
 }
 
 impl Flex {
     pub fn new<I: PinId>(_pin: Pin<I>) -> Self {
         let mut ll = ll::LowLevelGpio::new(I::ID);
         ll.configure_as_input_floating();
-        Flex {
-            ll,
-            mode: PinMode::InputFloating,
-        }
+        Flex { ll }
+    }
+
+    /// Current GPIO mode derived from the pin configuration, or [None] if a peripheral function
+    /// is selected.
+    #[inline]
+    pub fn current_mode(&self) -> Option<PinMode> {
+        self.ll.current_config().gpio_mode()
+    }
+
+    /// Full decoded configuration of the pin, see [ll::LowLevelGpio::current_config].
+    #[inline]
+    pub fn current_config(&self) -> DecodedPinConfig {
+        self.ll.current_config()
+    }
+
+    /// Checks whether the pin is an output, so [Self::set_low] and [Self::set_high] have an
+    /// effect.
+    #[inline]
+    fn is_gpio_output(&self) -> bool {
+        self.current_mode().is_some_and(|mode| mode.is_output())
     }
 
     #[inline]
@@ -251,19 +557,26 @@ impl Flex {
         self.ll.is_high()
     }
 
-    /// If the pin is configured as an input pin, this function does nothing.
+    /// Configure the output delay of the pin, which is only applied in output mode. See
+    /// [PinDelay] for details.
+    #[inline]
+    pub fn set_delay(&mut self, delay: PinDelay) {
+        self.ll.set_delay(delay);
+    }
+
+    /// If the pin is not configured as a GPIO output pin, this function does nothing.
     #[inline]
     pub fn set_low(&mut self) {
-        if !self.mode.is_input() {
+        if !self.is_gpio_output() {
             return;
         }
         self.ll.set_low();
     }
 
-    /// If the pin is configured as an input pin, this function does nothing.
+    /// If the pin is not configured as a GPIO output pin, this function does nothing.
     #[inline]
     pub fn set_high(&mut self) {
-        if !self.mode.is_input() {
+        if !self.is_gpio_output() {
             return;
         }
         self.ll.set_high();
@@ -287,13 +600,13 @@ impl embedded_hal::digital::InputPin for Flex {
 }
 
 impl embedded_hal::digital::OutputPin for Flex {
-    /// If the pin is configured as an input pin, this function does nothing.
+    /// If the pin is not configured as a GPIO output pin, this function does nothing.
     fn set_low(&mut self) -> Result<(), Self::Error> {
         self.set_low();
         Ok(())
     }
 
-    /// If the pin is configured as an input pin, this function does nothing.
+    /// If the pin is not configured as a GPIO output pin, this function does nothing.
     fn set_high(&mut self) -> Result<(), Self::Error> {
         self.set_high();
         Ok(())
@@ -348,6 +661,20 @@ impl IoPeriphPin {
         IoPeriphPin { ll, fun_sel }
     }
 
+    /// Like [Self::new], but the input and the output path of the pin can be inverted with the
+    /// IOCONFIG peripheral. This is transparent for the peripheral using the pin.
+    pub fn new_with_inversion(
+        pin_id: DynPinId,
+        fun_sel: FunctionSelect,
+        pull: Option<Pull>,
+        invert_input: bool,
+        invert_output: bool,
+    ) -> Self {
+        let mut ll = ll::LowLevelGpio::new(pin_id);
+        ll.configure_as_peripheral_pin_with_inversion(fun_sel, pull, invert_input, invert_output);
+        IoPeriphPin { ll, fun_sel }
+    }
+
     pub fn port(&self) -> Port {
         self.ll.port()
     }
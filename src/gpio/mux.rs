@@ -0,0 +1,276 @@
+//! # Pin function multiplexing
+//!
+//! [PinMux] switches a pin between a fixed set of functions, for example between the SCK
+//! function of a SPI peripheral and a GPIO output for a test mode. All IOCONFIG register words
+//! and the GPIO direction and output states are built and validated once at setup time, so
+//! [PinMux::select] only writes the prepared values.
+//!
+//! ## Switch sequence and glitch behaviour
+//!
+//! The DIR and output registers of the GPIO peripheral only apply while the GPIO function is
+//! selected. [PinMux::select] therefore prepares them first and writes the IOCONFIG word last:
+//!
+//! 1. For a GPIO output, the output level is written to SETOUT or CLROUT and the output delay
+//!    of the [OutputConfig] is written to the DELAY1 and DELAY2 registers.
+//! 2. The DIR bit of the pin is updated if the direction changes. This is a read-modify-write of
+//!    the DIR register of the port.
+//! 3. The prepared IOCONFIG word is written with a single store. This store switches the pin.
+//!
+//! Switching between a peripheral function and a GPIO output therefore does not glitch: The pin
+//! changes from the peripheral signal directly to the prepared output level. Switching between
+//! two GPIO personalities changes the level or direction before the IOCONFIG word, so the pin
+//! briefly has the new level or direction with the previous pull, drive and inversion settings.
+//!
+//! ## Switch latency
+//!
+//! The latency was not measured on hardware. It follows from the sequence above: Switching
+//! between a peripheral function and a GPIO output with the same direction as the previous
+//! GPIO personality takes two stores to the peripheral bus, a direction change adds one load and
+//! one store. With the peripheral bus wait states, this is in the order of 10 clock cycles, or
+//! 0.2 us at 50 MHz. The pin is switched by the last store, so the switch itself is not delayed
+//! further. Re-configuring the pin with the regular constructors instead performs a
+//! read-modify-write of the IOCONFIG register and enables the port clocks each time.
+use super::{
+    DynPinId, InputConfig, OutputConfig, Pin, PinDelay, PinId, PinState, Pull,
+    ll::{input_pin_config, modify_pin_delay, output_pin_config, peripheral_pin_config},
+    regs::{Gpio, MmioGpio},
+};
+use crate::{
+    FunctionSelect,
+    ioconfig::regs::{Config, IoConfig, MmioIoConfig},
+    spi::{PinMiso, PinMosi, PinSck},
+    timer::TimPin,
+    uart::{RxPin, TxPin},
+};
+
+/// Peripheral function of a pin, which is taken from the pin function tables of the HAL.
+///
+/// The constructors accept the pin types which implement the pin traits of the respective
+/// peripheral, so only valid function selections can be created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PeripheralFunction {
+    id: DynPinId,
+    fun_sel: FunctionSelect,
+    pull: Option<Pull>,
+}
+
+impl PeripheralFunction {
+    pub const fn spi_sck<Sck: PinSck>(pull: Option<Pull>) -> Self {
+        Self::new(Sck::ID, Sck::FUN_SEL, pull)
+    }
+
+    pub const fn spi_mosi<Mosi: PinMosi>(pull: Option<Pull>) -> Self {
+        Self::new(Mosi::ID, Mosi::FUN_SEL, pull)
+    }
+
+    pub const fn spi_miso<Miso: PinMiso>(pull: Option<Pull>) -> Self {
+        Self::new(Miso::ID, Miso::FUN_SEL, pull)
+    }
+
+    pub const fn uart_tx<Tx: TxPin>(pull: Option<Pull>) -> Self {
+        Self::new(Tx::ID, Tx::FUN_SEL, pull)
+    }
+
+    pub const fn uart_rx<Rx: RxPin>(pull: Option<Pull>) -> Self {
+        Self::new(Rx::ID, Rx::FUN_SEL, pull)
+    }
+
+    pub const fn tim<Tim: TimPin>(pull: Option<Pull>) -> Self {
+        Self::new(Tim::PIN_ID, Tim::FUN_SEL, pull)
+    }
+
+    const fn new(id: DynPinId, fun_sel: FunctionSelect, pull: Option<Pull>) -> Self {
+        Self { id, fun_sel, pull }
+    }
+
+    #[inline]
+    pub const fn id(&self) -> DynPinId {
+        self.id
+    }
+
+    #[inline]
+    pub const fn fun_sel(&self) -> FunctionSelect {
+        self.fun_sel
+    }
+
+    #[inline]
+    pub const fn pull(&self) -> Option<Pull> {
+        self.pull
+    }
+}
+
+/// Function of a pin which can be selected with a [PinMux].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MuxFunction {
+    /// GPIO input, like [super::Input].
+    Input { pull: Option<Pull> },
+    /// GPIO output with the given level of the output register, like [super::Output].
+    Output {
+        config: OutputConfig,
+        level: PinState,
+    },
+    /// Peripheral function, like [super::IoPeriphPin].
+    Peripheral(PeripheralFunction),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PinMuxError {
+    /// A peripheral function of another pin was passed.
+    #[error("peripheral function of pin {found:?} can not be used for pin {expected:?}")]
+    PinMissmatch { expected: DynPinId, found: DynPinId },
+    #[error("invalid function index {0}")]
+    InvalidIndex(usize),
+}
+
+/// Prepared register values of one [MuxFunction].
+#[derive(Debug, Copy, Clone)]
+struct PreparedFunction {
+    config: Config,
+    /// Direction of the GPIO function.
+    output: bool,
+    level: Option<PinState>,
+    delay: Option<PinDelay>,
+}
+
+/// Switches a pin between `N` functions which are prepared at setup time, see the
+/// [module documentation](self).
+pub struct PinMux<const N: usize> {
+    id: DynPinId,
+    gpio: MmioGpio<'static>,
+    ioconfig: MmioIoConfig<'static>,
+    functions: [PreparedFunction; N],
+    selected: usize,
+    /// DIR bit of the pin.
+    output: bool,
+}
+
+impl<const N: usize> PinMux<N> {
+    /// Prepare the functions for the pin and select the function with the given index.
+    ///
+    /// The IOCONFIG words are derived from the current configuration of the pin, so the filter
+    /// settings are kept for all functions. They are the same words which the regular
+    /// constructors of the functions would write.
+    pub fn new<I: PinId>(
+        _pin: Pin<I>,
+        functions: [MuxFunction; N],
+        initial: usize,
+    ) -> Result<Self, PinMuxError> {
+        if initial >= N {
+            return Err(PinMuxError::InvalidIndex(initial));
+        }
+        let id = I::ID;
+        for function in &functions {
+            if let MuxFunction::Peripheral(periph) = function
+                && periph.id != id
+            {
+                return Err(PinMuxError::PinMissmatch {
+                    expected: id,
+                    found: periph.id,
+                });
+            }
+        }
+        super::ensure_port_clocks(id.port());
+        let ioconfig = IoConfig::new_mmio();
+        let base = ioconfig.read_pin_config(id);
+        let gpio = Gpio::new_mmio(id.port());
+        let output = gpio.read_dir() & (1 << id.offset()) != 0;
+        let mut mux = Self {
+            id,
+            gpio,
+            ioconfig,
+            functions: functions.map(|function| Self::prepare(base, function)),
+            selected: initial,
+            output,
+        };
+        mux.write(initial);
+        Ok(mux)
+    }
+
+    fn prepare(base: Config, function: MuxFunction) -> PreparedFunction {
+        match function {
+            MuxFunction::Input { pull } => PreparedFunction {
+                config: input_pin_config(base, &InputConfig::floating().pull(pull)),
+                output: false,
+                level: None,
+                delay: None,
+            },
+            MuxFunction::Output { config, level } => PreparedFunction {
+                config: output_pin_config(base, &config),
+                output: true,
+                level: Some(level),
+                delay: Some(config.delay),
+            },
+            MuxFunction::Peripheral(periph) => PreparedFunction {
+                config: peripheral_pin_config(base, periph.fun_sel, periph.pull, false, false),
+                output: false,
+                level: None,
+                delay: None,
+            },
+        }
+    }
+
+    #[inline]
+    pub fn id(&self) -> DynPinId {
+        self.id
+    }
+
+    /// IOCONFIG register word which is written for the function with the given index.
+    #[inline]
+    pub fn config_word(&self, index: usize) -> Option<u32> {
+        self.functions.get(index).map(|f| f.config.raw_value())
+    }
+
+    /// Select the function with the given index, see the [module documentation](self) for the
+    /// register sequence.
+    pub fn select(&mut self, index: usize) -> Result<(), PinMuxError> {
+        if index >= N {
+            return Err(PinMuxError::InvalidIndex(index));
+        }
+        self.write(index);
+        Ok(())
+    }
+
+    fn write(&mut self, index: usize) {
+        let function = self.functions[index];
+        let mask = 1 << self.id.offset();
+        match function.level {
+            Some(PinState::High) => self.gpio.write_set_out(mask),
+            Some(PinState::Low) => self.gpio.write_clr_out(mask),
+            None => (),
+        }
+        if let Some(delay) = function.delay {
+            modify_pin_delay(&mut self.gpio, self.id.offset(), delay);
+        }
+        // Peripheral functions ignore the DIR bit, so it is left as it is.
+        if function.config.funsel() == FunctionSelect::Sel0 && function.output != self.output {
+            self.gpio.modify_dir(|dir| {
+                if function.output {
+                    dir | mask
+                } else {
+                    dir & !mask
+                }
+            });
+            self.output = function.output;
+        }
+        self.ioconfig.write_pin_config(self.id, function.config);
+        self.selected = index;
+    }
+
+    /// Index of the function which is currently configured in the hardware, or [None] if the
+    /// pin was re-configured by other means.
+    pub fn current(&self) -> Option<usize> {
+        let config = self.ioconfig.read_pin_config(self.id);
+        let output = self.gpio.read_dir() & (1 << self.id.offset()) != 0;
+        let matches = |function: &PreparedFunction| {
+            function.config.raw_value() == config.raw_value()
+                && (function.config.funsel() != FunctionSelect::Sel0 || function.output == output)
+        };
+        if matches(&self.functions[self.selected]) {
+            return Some(self.selected);
+        }
+        self.functions.iter().position(matches)
+    }
+}
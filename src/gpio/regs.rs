@@ -58,7 +58,9 @@ pub struct Gpio {
     /// Read-only register which shows enabled and active interrupts. Called IRQ_end by Vorago.
     #[mmio(PureRead)]
     irq_status: u32,
-    #[mmio(PureRead)]
+    /// Latched edge status. Writing a one to a bit clears it, bits written as zero are not
+    /// affected.
+    #[mmio(PureRead, Write)]
     edge_status: u32,
 
     #[cfg(feature = "vor1x")]
@@ -79,6 +81,7 @@ cfg_if::cfg_if! {
 }
 
 impl Gpio {
+    #[cfg(not(feature = "test-harness"))]
     const fn new_mmio_at(base: usize) -> MmioGpio<'static> {
         MmioGpio {
             ptr: base as *mut _,
@@ -87,6 +90,12 @@ impl Gpio {
     }
 
     pub const fn new_mmio(port: Port) -> MmioGpio<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioGpio {
+            ptr: crate::test_harness::gpio(port).as_mut_ptr(),
+            phantom: core::marker::PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         match port {
             Port::A => Self::new_mmio_at(GPIO_0_BASE),
             Port::B => Self::new_mmio_at(GPIO_1_BASE),
@@ -105,6 +114,29 @@ impl Gpio {
 }
 
 impl MmioGpio<'_> {
+    #[cfg(feature = "test-harness")]
+    pub fn port(&self) -> Port {
+        let ptr = unsafe { self.ptr() };
+        *[
+            Port::A,
+            Port::B,
+            #[cfg(feature = "vor4x")]
+            Port::C,
+            #[cfg(feature = "vor4x")]
+            Port::D,
+            #[cfg(feature = "vor4x")]
+            Port::E,
+            #[cfg(feature = "vor4x")]
+            Port::F,
+            #[cfg(feature = "vor4x")]
+            Port::G,
+        ]
+        .iter()
+        .find(|port| core::ptr::eq(crate::test_harness::gpio(**port).as_mut_ptr(), ptr))
+        .expect("unexpected base address of GPIO register block")
+    }
+
+    #[cfg(not(feature = "test-harness"))]
     pub fn port(&self) -> Port {
         match unsafe { self.ptr() } as usize {
             GPIO_0_BASE => Port::A,
@@ -1,14 +1,22 @@
 pub mod regs;
+pub mod share;
+pub mod smbus;
 
 use crate::{
-    PeripheralSelect, enable_peripheral_clock, sealed::Sealed,
-    sysconfig::reset_peripheral_for_cycles, time::Hertz,
+    PeripheralSelect,
+    busy_wait::BusyWait,
+    enable_peripheral_clock,
+    power::SleepParticipant,
+    sealed::Sealed,
+    sysconfig::reset_peripheral_for_cycles,
+    time::{Duration, Hertz},
 };
-use arbitrary_int::{u4, u10, u11, u20};
+use arbitrary_int::{u4, u5, u10, u11, u20};
 use core::marker::PhantomData;
 use embedded_hal::i2c::{self, Operation, SevenBitAddress, TenBitAddress};
-use regs::ClockTimeoutLimit;
+use portable_atomic::{AtomicBool, Ordering};
 pub use regs::{Bank, I2cSpeed, RxFifoFullMode, TxFifoEmptyMode};
+use regs::{ClockTimeoutLimit, TriggerLevel};
 
 #[cfg(feature = "vor1x")]
 use va108xx as pac;
@@ -23,16 +31,34 @@ const CLK_100K: Hertz = Hertz::from_raw(100_000);
 const CLK_400K: Hertz = Hertz::from_raw(400_000);
 const MIN_CLK_400K: Hertz = Hertz::from_raw(8_000_000);
 
+/// Maximum number of status register polls for a zero-length write.
+///
+/// This is large enough for the address phase at 100 kHz even for fast system clocks.
+pub const ZERO_LEN_WRITE_MAX_POLLS: u32 = 100_000;
+
+#[cfg(feature = "vor1x")]
+const NUM_BANKS: usize = 2;
+#[cfg(feature = "vor4x")]
+const NUM_BANKS: usize = 3;
+
+/// Maximum number of status register polls while waiting for the bus to become idle before a
+/// transaction is retried, see [RetryPolicy].
+pub const RETRY_BUS_IDLE_MAX_POLLS: u32 = 100_000;
+
+/// Set while a blocking transaction is performed on the bank.
+static TRANSACTION_ACTIVE: [AtomicBool; NUM_BANKS] = [const { AtomicBool::new(false) }; NUM_BANKS];
+
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[error("clock too slow for fast I2C mode")]
 pub struct ClockTooSlowForFastI2cError;
 
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[error("invalid timing parameters")]
 pub struct InvalidTimingParamsError;
 
-#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error {
     #[error("arbitration lost")]
@@ -42,14 +68,34 @@ pub enum Error {
     /// Data not acknowledged in write operation
     #[error("data not acknowledged in write operation")]
     NackData,
-    /// Not enough data received in read operation
-    #[error("insufficient data received")]
-    InsufficientDataReceived,
-    /// Number of bytes in transfer too large (larger than 0x7fe)
+    /// Not enough data received in read operation. `received` is the number of bytes which
+    /// were transferred on the bus according to the hardware RX counter, see
+    /// [I2cMaster::rx_progress]. It can be equal to `expected` if received bytes were lost
+    /// before they could be read from the FIFO.
+    #[error("insufficient data received: {received} of {expected} bytes")]
+    InsufficientDataReceived { expected: usize, received: usize },
+    /// More bytes than requested were received in a read operation. The additional bytes were
+    /// discarded, the requested bytes are valid.
+    #[error("{0} bytes more than requested received")]
+    ExcessDataReceived(usize),
+    /// Number of bytes in transfer too large (larger than [MAX_CHUNK_LEN]) while chunked
+    /// transfers are disabled, see [I2cMaster::set_chunked_transfers].
     #[error("data too large (larger than 0x7fe)")]
     DataTooLarge,
     #[error("clock timeout, SCL was low for {0} clock cycles")]
     ClockTimeout(u20),
+    /// Zero-length write did not complete within [ZERO_LEN_WRITE_MAX_POLLS] status polls.
+    #[error("zero-length write did not complete")]
+    ZeroLengthWriteTimeout,
+    /// Another blocking transaction is already in progress on the same bank, for example
+    /// because a driver was used from an interrupt handler while the main thread was
+    /// performing a transaction.
+    #[error("transaction already in progress on the bus")]
+    BusBusy,
+    /// All attempts of the [RetryPolicy] failed. The error of the last attempt is returned as
+    /// the cause.
+    #[error("{cause} after {attempts} attempts")]
+    RetriesExhausted { attempts: u8, cause: RetryableError },
 }
 
 #[derive(Debug, PartialEq, Eq, thiserror::Error)]
@@ -73,9 +119,13 @@ impl embedded_hal::i2c::Error for Error {
             Error::NackData => {
                 embedded_hal::i2c::ErrorKind::NoAcknowledge(i2c::NoAcknowledgeSource::Data)
             }
-            Error::DataTooLarge | Error::InsufficientDataReceived | Error::ClockTimeout(_) => {
-                embedded_hal::i2c::ErrorKind::Other
-            }
+            Error::RetriesExhausted { cause, .. } => Error::from(*cause).kind(),
+            Error::DataTooLarge
+            | Error::InsufficientDataReceived { .. }
+            | Error::ExcessDataReceived(_)
+            | Error::ClockTimeout(_)
+            | Error::ZeroLengthWriteTimeout
+            | Error::BusBusy => embedded_hal::i2c::ErrorKind::Other,
         }
     }
 }
@@ -116,6 +166,10 @@ impl I2cAddress {
 pub trait I2cInstance: Sealed {
     const ID: Bank;
     const PERIPH_SEL: PeripheralSelect;
+    const FIFO_DEPTH: usize = Self::ID.fifo_depth();
+    /// APB bus which clocks the peripheral.
+    #[cfg(feature = "vor4x")]
+    const APB: crate::clock::ApbBus = Self::ID.apb_bus();
 }
 
 #[cfg(feature = "vor1x")]
@@ -161,9 +215,10 @@ fn calc_clk_div_generic(
 #[cfg(feature = "vor4x")]
 fn calc_clk_div(
     clks: &crate::clock::Clocks,
+    bank: Bank,
     speed_mode: I2cSpeed,
 ) -> Result<u8, ClockTooSlowForFastI2cError> {
-    calc_clk_div_generic(clks.apb1(), speed_mode)
+    calc_clk_div_generic(clks.clock_for(bank.apb_bus()), speed_mode)
 }
 
 #[cfg(feature = "vor1x")]
@@ -184,6 +239,40 @@ pub struct TimingConfig {
     pub t_buf: u4,
 }
 
+impl TimingConfig {
+    /// Check the timing parameters against the given speed mode.
+    ///
+    /// The timing parameters are specified in prescaled clock cycles. The HAL configures the
+    /// clock prescaler so that one SCL period is 20 cycles in regular mode and 25 cycles in fast
+    /// mode. The following conditions are checked:
+    ///
+    /// - The sum of the rise, fall, high and low times must fit into one SCL period.
+    /// - The SCL low time including the fall time must be at least 4.7 us in regular mode and
+    ///   1.3 us in fast mode.
+    /// - The SCL high time including the rise time must be at least 4.0 us in regular mode and
+    ///   0.6 us in fast mode.
+    pub const fn validate(&self, speed_mode: I2cSpeed) -> Result<(), InvalidTimingParamsError> {
+        // SCL period, minimum low time and minimum high time in prescaled clock cycles.
+        let (period, min_low, min_high) = match speed_mode {
+            // 0.5 us per cycle.
+            I2cSpeed::Regular100khz => (20, 10, 8),
+            // 0.1 us per cycle.
+            I2cSpeed::Fast400khz => (25, 13, 6),
+        };
+        let t_rise = self.t_rise.value();
+        let t_fall = self.t_fall.value();
+        let t_high = self.t_high.value();
+        let t_low = self.t_low.value();
+        if t_rise + t_fall + t_high + t_low > period
+            || t_low + t_fall < min_low
+            || t_high + t_rise < min_high
+        {
+            return Err(InvalidTimingParamsError);
+        }
+        Ok(())
+    }
+}
+
 /// Default configuration are the register reset value which are used by default.
 impl Default for TimingConfig {
     fn default() -> Self {
@@ -200,6 +289,7 @@ impl Default for TimingConfig {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MasterConfig {
     pub tx_empty_mode: TxFifoEmptyMode,
@@ -211,8 +301,14 @@ pub struct MasterConfig {
     pub timing_config: Option<TimingConfig>,
     /// See [I2cMaster::set_clock_low_timeout] documentation.
     pub timeout: Option<u20>,
-    // Loopback mode
-    // lbm: bool,
+    /// See [I2cMaster::set_rx_fifo_trigger] documentation. The reset value is kept if this is
+    /// [None].
+    pub rx_fifo_trigger: Option<u5>,
+    /// See [I2cMaster::set_tx_fifo_trigger] documentation. The reset value is kept if this is
+    /// [None].
+    pub tx_fifo_trigger: Option<u5>,
+    /// See [I2cMaster::set_loopback] documentation.
+    pub loopback: bool,
 }
 
 impl Default for MasterConfig {
@@ -224,13 +320,195 @@ impl Default for MasterConfig {
             dlg_filt: false,
             timeout: None,
             timing_config: None,
+            rx_fifo_trigger: None,
+            tx_fifo_trigger: None,
+            loopback: false,
+        }
+    }
+}
+
+impl MasterConfig {
+    /// Builder which validates the timing configuration against the speed mode.
+    pub fn builder() -> MasterConfigBuilder {
+        MasterConfigBuilder {
+            cfg: Self::default(),
+            speed_mode: I2cSpeed::Regular100khz,
         }
     }
 }
 
 impl Sealed for MasterConfig {}
 
-#[derive(Debug, PartialEq, Eq)]
+/// Builder for the [MasterConfig], created with [MasterConfig::builder].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MasterConfigBuilder {
+    cfg: MasterConfig,
+    speed_mode: I2cSpeed,
+}
+
+impl MasterConfigBuilder {
+    /// Speed mode the timing configuration is validated against. This should be the same
+    /// speed mode which is passed to [I2cMaster::new]. The default is [I2cSpeed::Regular100khz].
+    pub fn speed_mode(mut self, speed_mode: I2cSpeed) -> Self {
+        self.speed_mode = speed_mode;
+        self
+    }
+
+    pub fn tx_empty_mode(mut self, mode: TxFifoEmptyMode) -> Self {
+        self.cfg.tx_empty_mode = mode;
+        self
+    }
+
+    pub fn rx_full_mode(mut self, mode: RxFifoFullMode) -> Self {
+        self.cfg.rx_full_mode = mode;
+        self
+    }
+
+    /// Enable the analog delay glitch filter
+    pub fn alg_filt(mut self, enable: bool) -> Self {
+        self.cfg.alg_filt = enable;
+        self
+    }
+
+    /// Enable the digital glitch filter
+    pub fn dlg_filt(mut self, enable: bool) -> Self {
+        self.cfg.dlg_filt = enable;
+        self
+    }
+
+    pub fn timing_config(mut self, timing_config: TimingConfig) -> Self {
+        self.cfg.timing_config = Some(timing_config);
+        self
+    }
+
+    /// See [I2cMaster::set_clock_low_timeout] documentation.
+    pub fn timeout(mut self, timeout: u20) -> Self {
+        self.cfg.timeout = Some(timeout);
+        self
+    }
+
+    /// See [I2cMaster::set_rx_fifo_trigger] documentation.
+    pub fn rx_fifo_trigger(mut self, level: u5) -> Self {
+        self.cfg.rx_fifo_trigger = Some(level);
+        self
+    }
+
+    /// See [I2cMaster::set_tx_fifo_trigger] documentation.
+    pub fn tx_fifo_trigger(mut self, level: u5) -> Self {
+        self.cfg.tx_fifo_trigger = Some(level);
+        self
+    }
+
+    /// See [I2cMaster::set_loopback] documentation.
+    pub fn loopback(mut self, enable: bool) -> Self {
+        self.cfg.loopback = enable;
+        self
+    }
+
+    /// Validate the timing configuration, if one was set, and build the configuration.
+    pub fn build(self) -> Result<MasterConfig, InvalidTimingParamsError> {
+        if let Some(timing_config) = self.cfg.timing_config {
+            timing_config.validate(self.speed_mode)?;
+        }
+        Ok(self.cfg)
+    }
+}
+
+/// Largest FIFO depth of all I2C peripherals, see [Bank::fifo_depth].
+pub const MAX_FIFO_DEPTH: usize = 16;
+
+/// Number of bytes which can be read from the RX FIFO after one status check.
+///
+/// If the RX trigger status flag is set, at least the trigger level number of bytes is
+/// available, limited by the number of bytes which are still expected. Otherwise, one byte is
+/// read if the FIFO is not empty.
+const fn rx_drain_count(
+    rx_trigger: bool,
+    rx_not_empty: bool,
+    trigger_level: u8,
+    remaining: usize,
+) -> usize {
+    if !rx_not_empty {
+        return 0;
+    }
+    let mut level = trigger_level as usize;
+    if level > MAX_FIFO_DEPTH {
+        level = MAX_FIFO_DEPTH;
+    }
+    if rx_trigger && level > 1 && remaining > 1 {
+        if remaining < level {
+            return remaining;
+        }
+        return level;
+    }
+    1
+}
+
+/// Bookkeeping of the bytes received by a read chunk.
+///
+/// Bytes are stored in the buffer until it is full. Additional bytes are discarded and counted,
+/// so the FIFO is always drained and a following transfer does not receive stale data.
+struct RxAccounting<'buf> {
+    buffer: &'buf mut [u8],
+    received: usize,
+}
+
+impl<'buf> RxAccounting<'buf> {
+    const fn new(buffer: &'buf mut [u8]) -> Self {
+        Self {
+            buffer,
+            received: 0,
+        }
+    }
+
+    /// Number of bytes which are still expected.
+    const fn remaining(&self) -> usize {
+        self.buffer.len().saturating_sub(self.received)
+    }
+
+    fn push(&mut self, byte: u8) {
+        if let Some(slot) = self.buffer.get_mut(self.received) {
+            *slot = byte;
+        }
+        self.received += 1;
+    }
+
+    /// Check the number of bytes stored in the buffer. The `hw_received` count of the hardware
+    /// RX counter is reported in the [Error::InsufficientDataReceived] error.
+    fn finish(self, hw_received: u16) -> Result<(), Error> {
+        let len = self.buffer.len();
+        if self.received < len {
+            return Err(Error::InsufficientDataReceived {
+                expected: len,
+                received: hw_received as usize,
+            });
+        }
+        if self.received > len {
+            return Err(Error::ExcessDataReceived(self.received - len));
+        }
+        Ok(())
+    }
+}
+
+/// Largest number of bytes which can be transferred with a single command, which is limited by
+/// the 11 bit WORDS register.
+pub const MAX_CHUNK_LEN: usize = 0x7fe;
+
+/// Splits a transfer of the given length into chunks of at most [MAX_CHUNK_LEN] bytes.
+///
+/// Yields the byte range of each chunk and whether it is the last chunk. A transfer of length 0
+/// consists of one empty chunk.
+fn chunk_ranges(len: usize) -> impl Iterator<Item = (core::ops::Range<usize>, bool)> {
+    let num_chunks = len.div_ceil(MAX_CHUNK_LEN).max(1);
+    (0..num_chunks).map(move |idx| {
+        let start = idx * MAX_CHUNK_LEN;
+        let end = core::cmp::min(start + MAX_CHUNK_LEN, len);
+        (start..end, idx == num_chunks - 1)
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum WriteCompletionCondition {
     Idle,
     Waiting,
@@ -280,6 +558,236 @@ impl Drop for TimeoutGuard {
         }
     }
 }
+
+/// Leaves the controller idle if a blocking transfer is not completed with [Self::complete],
+/// for example because of an early error return or a panic.
+///
+/// On drop, a transfer which is still in progress without an error, like a transfer which is
+/// stalled by a clock stretching slave, is cancelled. A bus which is still held after a start
+/// without stop is released with a stop condition. Both FIFOs and the sticky interrupt status
+/// bits are cleared afterwards.
+struct TransferGuard {
+    bus_held: bool,
+    regs: regs::MmioI2c<'static>,
+}
+
+impl TransferGuard {
+    fn new(regs: &regs::MmioI2c<'static>, init_cmd: I2cCommand) -> Self {
+        TransferGuard {
+            bus_held: init_cmd == I2cCommand::Start,
+            regs: unsafe { regs.clone() },
+        }
+    }
+
+    fn write_command(&mut self, cmd: I2cCommand) {
+        self.regs
+            .write_cmd(regs::Command::new_with_raw_value(cmd as u32));
+    }
+
+    /// Skip the cleanup if the transfer was successful.
+    fn complete(self, result: Result<(), Error>) -> Result<(), Error> {
+        if result.is_ok() {
+            core::mem::forget(self);
+        }
+        result
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        let status = self.regs.read_status();
+        // The controller ends a transfer itself after these errors.
+        let failed = status.arb_lost() || status.nack_addr() || status.nack_data();
+        if !failed && !status.idle() {
+            self.write_command(I2cCommand::Cancel);
+        }
+        if self.bus_held {
+            self.write_command(I2cCommand::Stop);
+        }
+        self.regs.write_fifo_clear(
+            regs::FifoClear::builder()
+                .with_tx_fifo(true)
+                .with_rx_fifo(true)
+                .build(),
+        );
+        self.regs.write_irq_clear(
+            regs::InterruptClear::builder()
+                .with_clock_timeout(true)
+                .with_tx_overflow(true)
+                .with_rx_overflow(true)
+                .build(),
+        );
+    }
+}
+
+//==================================================================================================
+// Retry policy
+//==================================================================================================
+
+/// Errors which can be retried by a [RetryPolicy].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RetryableError {
+    #[error("arbitration lost")]
+    ArbitrationLost,
+    #[error("nack address")]
+    NackAddr,
+}
+
+impl From<RetryableError> for Error {
+    fn from(value: RetryableError) -> Self {
+        match value {
+            RetryableError::ArbitrationLost => Error::ArbitrationLost,
+            RetryableError::NackAddr => Error::NackAddr,
+        }
+    }
+}
+
+/// Selects the errors which are retried by a [RetryPolicy].
+///
+/// Only errors which occur before any data was transferred can be retried. A
+/// [Error::NackData] is never retried because the slave already received a part of the data.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RetryOn {
+    /// Retry if the arbitration was lost to another master.
+    pub arbitration_lost: bool,
+    /// Retry if the address was not acknowledged, for example by a slave which is busy.
+    pub nack_addr: bool,
+}
+
+impl RetryOn {
+    /// Checks whether the error is retried, and returns the retryable error in that case.
+    pub const fn retryable(&self, error: &Error) -> Option<RetryableError> {
+        match error {
+            Error::ArbitrationLost if self.arbitration_lost => {
+                Some(RetryableError::ArbitrationLost)
+            }
+            Error::NackAddr if self.nack_addr => Some(RetryableError::NackAddr),
+            _ => None,
+        }
+    }
+}
+
+/// Wait performed before a transaction is retried.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Backoff {
+    /// Only wait until the bus is idle.
+    BusIdleWait,
+    /// Busy wait for the given duration, and then wait until the bus is idle.
+    Delay(Duration),
+}
+
+/// Retry policy of the blocking transactions of the [I2cMaster], set with
+/// [I2cMaster::set_retry_policy].
+///
+/// A transaction is attempted up to [Self::max_attempts] times if it fails with one of the
+/// errors selected with [Self::retry_on]. Before an attempt is retried, the [Self::backoff] is
+/// performed and the driver waits until the bus is idle, which is bounded by
+/// [RETRY_BUS_IDLE_MAX_POLLS] status polls. If all attempts fail, [Error::RetriesExhausted]
+/// is returned. All other errors are returned immediately.
+///
+/// The default policy does not retry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RetryPolicy {
+    /// Maximum number of attempts including the first one. 0 and 1 both disable retries.
+    pub max_attempts: u8,
+    pub retry_on: RetryOn,
+    pub backoff: Backoff,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Policy which does not retry.
+    pub const fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            retry_on: RetryOn {
+                arbitration_lost: false,
+                nack_addr: false,
+            },
+            backoff: Backoff::BusIdleWait,
+        }
+    }
+
+    /// Decide what happens after the given attempt, which starts at 1, failed with the given
+    /// error.
+    ///
+    /// Returns [None] if the transaction should be retried, and the error to return otherwise.
+    pub const fn check_attempt(&self, error: Error, attempt: u8) -> Option<Error> {
+        match self.retry_on.retryable(&error) {
+            Some(_) if attempt < self.max_attempts => None,
+            Some(cause) if attempt > 1 => Some(Error::RetriesExhausted {
+                attempts: attempt,
+                cause,
+            }),
+            _ => Some(error),
+        }
+    }
+}
+
+/// Marks a blocking transaction as active on a bank for its lifetime, so the flag is released
+/// on all return paths.
+struct TransactionGuard(&'static AtomicBool);
+
+impl TransactionGuard {
+    fn acquire(flag: &'static AtomicBool) -> Result<Self, Error> {
+        if flag.swap(true, Ordering::Acquire) {
+            return Err(Error::BusBusy);
+        }
+        Ok(TransactionGuard(flag))
+    }
+}
+
+impl Drop for TransactionGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+//==================================================================================================
+// Diagnostics
+//==================================================================================================
+
+/// Decoded content of the internal STATE register of the I2C master.
+///
+/// This is a debugging aid for transactions which do not complete, for example because a slave
+/// holds SDA low. The state and step encodings of the controller state machine are not
+/// documented, so they are provided as raw values which can be compared between a working and a
+/// stuck transaction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I2cDiagnostics {
+    /// State of the controller state machine.
+    pub state: u8,
+    /// Step within the current state.
+    pub step: u8,
+    /// Number of words in the RX FIFO.
+    pub rx_fifo: u8,
+    /// Number of words in the TX FIFO.
+    pub tx_fifo: u8,
+    /// Phase of the bit currently transferred on the bus.
+    pub bitstate: u16,
+}
+
+impl From<regs::State> for I2cDiagnostics {
+    fn from(state: regs::State) -> Self {
+        Self {
+            state: state.state().value(),
+            step: state.step().value(),
+            rx_fifo: state.rx_fifo().value(),
+            tx_fifo: state.tx_fifo().value(),
+            bitstate: state.bitstate().value(),
+        }
+    }
+}
+
 //==================================================================================================
 // I2C Master
 //==================================================================================================
@@ -287,6 +795,14 @@ impl Drop for TimeoutGuard {
 pub struct I2cMaster<Addr = SevenBitAddress> {
     id: Bank,
     regs: regs::MmioI2c<'static>,
+    cfg: MasterConfig,
+    clk_scale: regs::ClockScale,
+    /// CPU clock, used for the [Backoff::Delay] of the retry policy.
+    sys_clk: Hertz,
+    retry_policy: RetryPolicy,
+    chunked_transfers: bool,
+    /// Enable state recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
     addr: PhantomData<Addr>,
 }
 
@@ -298,33 +814,100 @@ impl<Addr> I2cMaster<Addr> {
         cfg: MasterConfig,
         speed_mode: I2cSpeed,
     ) -> Result<Self, ClockTooSlowForFastI2cError> {
-        reset_peripheral_for_cycles(I2c::PERIPH_SEL, 2);
-        enable_peripheral_clock(I2c::PERIPH_SEL);
-        let mut regs = regs::I2c::new_mmio(I2c::ID);
+        // Safety: The peripheral singleton was passed to the constructor.
+        let i2c = unsafe {
+            Self::steal(
+                I2c::ID,
+                #[cfg(feature = "vor1x")]
+                sysclk,
+                #[cfg(feature = "vor4x")]
+                clks,
+                cfg,
+                speed_mode,
+            )
+        }?;
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::I2c(I2c::ID));
+        Ok(i2c)
+    }
+
+    /// Create an I2C master driver for the given bank without requiring the peripheral
+    /// singleton.
+    ///
+    /// The peripheral is reset and initialized like in [Self::new].
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL. The caller must ensure that the
+    /// I2C peripheral is not used by another driver. Debug builds panic if the I2C peripheral is
+    /// owned by a driver, see [crate::ownership].
+    #[track_caller]
+    pub unsafe fn steal(
+        bank: Bank,
+        #[cfg(feature = "vor1x")] sysclk: Hertz,
+        #[cfg(feature = "vor4x")] clks: &crate::clock::Clocks,
+        cfg: MasterConfig,
+        speed_mode: I2cSpeed,
+    ) -> Result<Self, ClockTooSlowForFastI2cError> {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::I2c(bank));
+        reset_peripheral_for_cycles(bank.periph_sel(), 2);
         #[cfg(feature = "vor1x")]
         let clk_div = calc_clk_div(sysclk, speed_mode)?;
         #[cfg(feature = "vor4x")]
-        let clk_div = calc_clk_div(clks, speed_mode)?;
-        regs.write_clkscale(
-            regs::ClockScale::builder()
+        let clk_div = calc_clk_div(clks, bank, speed_mode)?;
+        let mut i2c_master = I2cMaster {
+            addr: PhantomData,
+            id: bank,
+            regs: regs::I2c::new_mmio(bank),
+            cfg,
+            clk_scale: regs::ClockScale::builder()
                 .with_div(clk_div)
                 .with_fastmode(speed_mode)
                 .build(),
-        );
-        regs.modify_control(|mut value| {
+            #[cfg(feature = "vor1x")]
+            sys_clk: sysclk,
+            #[cfg(feature = "vor4x")]
+            sys_clk: clks.sysclk(),
+            retry_policy: RetryPolicy::none(),
+            chunked_transfers: true,
+            sleep_state: None,
+        };
+        i2c_master.init_peripheral();
+        Ok(i2c_master)
+    }
+
+    /// Reset the peripheral and replay the full register initialization with the configuration
+    /// passed to the constructor.
+    ///
+    /// This can be used to recover from faults like single event upsets without re-creating the
+    /// driver and losing its pins. The clock scale and clock low timeout configured after
+    /// construction are applied as well. The interrupt enables are restored to the values before
+    /// the reset.
+    pub fn reinitialize(&mut self) {
+        let irq_enb = self.regs.read_irq_enb();
+        reset_peripheral_for_cycles(self.id.periph_sel(), 2);
+        self.init_peripheral();
+        self.regs.write_irq_enb(irq_enb);
+    }
+
+    fn init_peripheral(&mut self) {
+        enable_peripheral_clock(self.id.periph_sel());
+        self.regs.write_clkscale(self.clk_scale);
+        let cfg = &self.cfg;
+        self.regs.modify_control(|mut value| {
             value.set_tx_fifo_empty_mode(cfg.tx_empty_mode);
             value.set_rx_fifo_full_mode(cfg.rx_full_mode);
             value.set_analog_filter(cfg.alg_filt);
             value.set_digital_filter(cfg.dlg_filt);
+            value.set_loopback(cfg.loopback);
             value
         });
 
         if let Some(ref timing_cfg) = cfg.timing_config {
-            regs.modify_control(|mut value| {
+            self.regs.modify_control(|mut value| {
                 value.set_enable_timing_config(true);
                 value
             });
-            regs.write_timing_config(
+            self.regs.write_timing_config(
                 regs::TimingConfig::builder()
                     .with_t_rise(timing_cfg.t_rise)
                     .with_t_fall(timing_cfg.t_fall)
@@ -337,33 +920,46 @@ impl<Addr> I2cMaster<Addr> {
                     .build(),
             );
         }
-        regs.write_fifo_clear(
+        self.regs.write_fifo_clear(
             regs::FifoClear::builder()
                 .with_tx_fifo(true)
                 .with_rx_fifo(true)
                 .build(),
         );
         if let Some(timeout) = cfg.timeout {
-            regs.write_clk_timeout_limit(ClockTimeoutLimit::new(timeout));
+            self.regs
+                .write_clk_timeout_limit(ClockTimeoutLimit::new(timeout));
         }
-        let mut i2c_master = I2cMaster {
-            addr: PhantomData,
-            id: I2c::ID,
-            regs,
-        };
-        i2c_master.enable();
-        Ok(i2c_master)
+        if let Some(level) = cfg.rx_fifo_trigger {
+            self.regs.write_rx_fifo_trigger(TriggerLevel::new(level));
+        }
+        if let Some(level) = cfg.tx_fifo_trigger {
+            self.regs.write_tx_fifo_trigger(TriggerLevel::new(level));
+        }
+        self.enable();
     }
 
     pub const fn id(&self) -> Bank {
         self.id
     }
 
+    /// Depth of the TX and RX FIFO of the I2C peripheral in bytes.
+    #[inline]
+    pub const fn fifo_depth(&self) -> usize {
+        self.id.fifo_depth()
+    }
+
     #[inline]
     pub fn perid(&self) -> u32 {
         self.regs.read_perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.perid() == regs::EXPECTED_PERID
+    }
+
     /// Configures the clock scale for a given speed mode setting
     pub fn set_clk_scale(
         &mut self,
@@ -375,13 +971,12 @@ impl<Addr> I2cMaster<Addr> {
         #[cfg(feature = "vor1x")]
         let clk_div = calc_clk_div(sys_clk, speed_mode)?;
         #[cfg(feature = "vor4x")]
-        let clk_div = calc_clk_div(clks, speed_mode)?;
-        self.regs.write_clkscale(
-            regs::ClockScale::builder()
-                .with_div(clk_div)
-                .with_fastmode(speed_mode)
-                .build(),
-        );
+        let clk_div = calc_clk_div(clks, self.id, speed_mode)?;
+        self.clk_scale = regs::ClockScale::builder()
+            .with_div(clk_div)
+            .with_fastmode(speed_mode)
+            .build();
+        self.regs.write_clkscale(self.clk_scale);
         self.enable();
         Ok(())
     }
@@ -425,16 +1020,37 @@ impl<Addr> I2cMaster<Addr> {
     /// hang-ups of the I2C bus.
     #[inline]
     pub fn set_clock_low_timeout(&mut self, clock_cycles: u20) {
+        self.cfg.timeout = Some(clock_cycles);
         self.regs
             .write_clk_timeout_limit(ClockTimeoutLimit::new(clock_cycles));
     }
 
     #[inline]
     pub fn disable_clock_low_timeout(&mut self) {
+        self.cfg.timeout = None;
         self.regs
             .write_clk_timeout_limit(ClockTimeoutLimit::new(u20::new(0)));
     }
 
+    /// Set the RX FIFO trigger level. The RX trigger interrupt and status flag are active if
+    /// the RX FIFO count is larger than or equal to this level.
+    ///
+    /// [Self::read_blocking] reads up to this number of bytes per status check once the RX
+    /// trigger status flag is set, so a larger level reduces the CPU load of long reads.
+    #[inline]
+    pub fn set_rx_fifo_trigger(&mut self, level: u5) {
+        self.cfg.rx_fifo_trigger = Some(level);
+        self.regs.write_rx_fifo_trigger(TriggerLevel::new(level));
+    }
+
+    /// Set the TX FIFO trigger level. The TX trigger interrupt and status flag are active if
+    /// the TX FIFO count is smaller than or equal to this level.
+    #[inline]
+    pub fn set_tx_fifo_trigger(&mut self, level: u5) {
+        self.cfg.tx_fifo_trigger = Some(level);
+        self.regs.write_tx_fifo_trigger(TriggerLevel::new(level));
+    }
+
     #[inline]
     pub fn enable(&mut self) {
         self.regs.modify_control(|mut value| {
@@ -451,6 +1067,27 @@ impl<Addr> I2cMaster<Addr> {
         });
     }
 
+    /// Enable or disable the internal loopback mode.
+    ///
+    /// In loopback mode, the SCL and SDA lines of the master are connected internally to the
+    /// slave of the same I2C peripheral instead of the pins. This allows testing transfers
+    /// without external hardware, for example with [crate::selftest::i2c_loopback_test]. If the
+    /// slave is not enabled, all addresses are not acknowledged, which still allows verifying
+    /// the clock and timing configuration, for example by measuring the duration of a
+    /// [Self::probe] call.
+    pub fn set_loopback(&mut self, enable: bool) {
+        self.cfg.loopback = enable;
+        self.regs.modify_control(|mut value| {
+            value.set_loopback(enable);
+            value
+        });
+    }
+
+    #[inline]
+    pub fn loopback(&self) -> bool {
+        self.regs.read_control().loopback()
+    }
+
     #[inline(always)]
     fn write_fifo_unchecked(&mut self, word: u8) {
         self.regs.write_data(regs::Data::new(word));
@@ -466,6 +1103,49 @@ impl<Addr> I2cMaster<Addr> {
         self.regs.read_status()
     }
 
+    /// Reads the decoded internal state of the master for debugging purposes.
+    ///
+    /// This is a pure register read which does not disturb a transfer in progress.
+    #[inline]
+    pub fn diagnostic_state(&self) -> I2cDiagnostics {
+        self.regs.read_state().into()
+    }
+
+    /// Number of bytes which were transmitted on the bus by the current or last transfer,
+    /// read from the hardware TX counter.
+    ///
+    /// In contrast to the number of bytes written into the TX FIFO, this only counts bytes which
+    /// were actually clocked out, so it can be used to report the progress of long transfers.
+    /// Transfers which are split into chunks, see [Self::set_chunked_transfers], restart the
+    /// count for each chunk. This is a pure register read which does not disturb a transfer in
+    /// progress.
+    #[inline]
+    pub fn tx_progress(&self) -> u16 {
+        self.regs.read_tx_count().value().value()
+    }
+
+    /// Number of bytes which were received on the bus by the current or last transfer, read
+    /// from the hardware RX counter.
+    ///
+    /// This also counts received bytes which are still in the RX FIFO. See [Self::tx_progress]
+    /// for the behaviour with chunked transfers.
+    #[inline]
+    pub fn rx_progress(&self) -> u16 {
+        self.regs.read_rx_count().value().value()
+    }
+
+    /// Reads the raw levels of the SDA and SCL lines as a `(sda, scl)` tuple.
+    ///
+    /// This can be used to detect a stuck bus, for example a slave holding SDA low after a reset
+    /// in the middle of a transfer, without reconfiguring the pins. In loopback mode, the
+    /// internal lines are read. This is a pure register read which does not disturb a transfer
+    /// in progress.
+    #[inline]
+    pub fn bus_lines(&self) -> (bool, bool) {
+        let status = self.regs.read_status();
+        (status.raw_sda(), status.raw_scl())
+    }
+
     #[inline]
     pub fn write_command(&mut self, cmd: I2cCommand) {
         self.regs
@@ -483,34 +1163,241 @@ impl<Addr> I2cMaster<Addr> {
         );
     }
 
-    fn error_handler_write(&mut self, init_cmd: I2cCommand) {
-        if init_cmd == I2cCommand::Start {
-            self.write_command(I2cCommand::Stop);
+    /// Checks whether a blocking transaction is in progress on the bank of this driver.
+    ///
+    /// All blocking transactions return [Error::BusBusy] while this is the case. This is also
+    /// true for transactions of other drivers for the same bank, for example ones created with
+    /// [Self::steal].
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        TRANSACTION_ACTIVE[self.id as usize].load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn start_transaction(&self) -> Result<TransactionGuard, Error> {
+        TransactionGuard::acquire(&TRANSACTION_ACTIVE[self.id as usize])
+    }
+
+    /// Set the retry policy of [Self::write_blocking], [Self::read_blocking] and
+    /// [Self::write_read_blocking]. See [RetryPolicy] for more details.
+    #[inline]
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    #[inline]
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Enable or disable chunked transfers, which are enabled by default.
+    ///
+    /// The WORDS register limits a single transfer to [MAX_CHUNK_LEN] bytes. Larger reads and
+    /// writes are split into chunks which are separated by repeated starts, so the bus is not
+    /// released until the final chunk which ends with a stop condition. The device is addressed
+    /// again for every chunk. This works for devices which keep their internal address counter
+    /// within a session, like EEPROMs for sequential reads. Devices which interpret the first
+    /// bytes after each start condition differently, like EEPROMs for writes, can not tolerate
+    /// chunking. With chunked transfers disabled, larger transfers are rejected with
+    /// [Error::DataTooLarge].
+    ///
+    /// The retry policy applies to the whole transfer. A retry after an error in a later chunk
+    /// repeats all chunks.
+    #[inline]
+    pub fn set_chunked_transfers(&mut self, enable: bool) {
+        self.chunked_transfers = enable;
+    }
+
+    #[inline]
+    pub fn chunked_transfers(&self) -> bool {
+        self.chunked_transfers
+    }
+
+    #[inline]
+    fn check_transfer_len(&self, len: usize) -> Result<(), Error> {
+        if len > MAX_CHUNK_LEN && !self.chunked_transfers {
+            return Err(Error::DataTooLarge);
         }
-        // The other case is start with stop where, so a CANCEL command should not be necessary
-        // because the hardware takes care of it.
-        self.clear_tx_fifo();
+        Ok(())
+    }
+
+    /// Perform a transaction with the retry policy.
+    fn with_retries(
+        &mut self,
+        mut transaction: impl FnMut(&mut Self) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut attempt = 1;
+        loop {
+            let error = match transaction(self) {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            let policy = self.retry_policy;
+            if let Some(error) = policy.check_attempt(error, attempt) {
+                return Err(error);
+            }
+            if let Backoff::Delay(duration) = policy.backoff {
+                let cycles = duration.to_micros() * self.sys_clk.raw() as u64 / 1_000_000;
+                cortex_m::asm::delay(cycles.min(u32::MAX as u64) as u32);
+            }
+            if !self.wait_for_bus_idle() {
+                // Report the retryable error of the last attempt.
+                return Err(policy
+                    .check_attempt(error, policy.max_attempts)
+                    .unwrap_or(error));
+            }
+            attempt += 1;
+        }
+    }
+
+    fn wait_for_bus_idle(&mut self) -> bool {
+        let mut busy_wait = BusyWait::new();
+        (0..RETRY_BUS_IDLE_MAX_POLLS).any(|_| {
+            busy_wait.tick();
+            self.read_status().i2c_idle()
+        })
     }
 
     /// Blocking write transaction on the I2C bus.
+    ///
+    /// Writes larger than [MAX_CHUNK_LEN] bytes are split into chunks, see
+    /// [Self::set_chunked_transfers].
+    ///
+    /// An empty output slice performs a zero-length write which only addresses the device,
+    /// which is also known as the SMBus Quick Command.
     pub fn write_blocking(&mut self, addr: I2cAddress, output: &[u8]) -> Result<(), Error> {
-        self.write_blocking_generic(
-            I2cCommand::StartWithStop,
-            addr,
-            output,
-            WriteCompletionCondition::Idle,
-        )
+        let _guard = self.start_transaction()?;
+        let result = self.with_retries(|i2c| {
+            if output.is_empty() {
+                return i2c.write_zero_len_blocking(addr);
+            }
+            i2c.write_blocking_generic(
+                I2cCommand::StartWithStop,
+                addr,
+                output,
+                WriteCompletionCondition::Idle,
+            )
+        });
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::Write, addr, output, &result);
+        result
+    }
+
+    /// Check whether a device acknowledges the given address by performing a zero-length
+    /// write.
+    ///
+    /// Returns [false] if the address was not acknowledged. All other errors are returned.
+    pub fn probe(&mut self, addr: I2cAddress) -> Result<bool, Error> {
+        let _guard = self.start_transaction()?;
+        match self.write_zero_len_blocking(addr) {
+            Ok(()) => Ok(true),
+            Err(Error::NackAddr) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Probe all 7-bit addresses in the given range and mark the acknowledged addresses in
+    /// the passed bitmap, which is indexed by the address. Addresses larger than 0x7F are
+    /// ignored.
+    ///
+    /// The addresses 0x00 to 0x07 and 0x78 to 0x7F are reserved by the I2C specification and
+    /// should usually be excluded from the range. Returns the number of found devices.
+    pub fn scan(
+        &mut self,
+        range: core::ops::RangeInclusive<u8>,
+        found: &mut [bool; 128],
+    ) -> Result<usize, Error> {
+        let mut num_found = 0;
+        for addr in range.filter(|addr| *addr <= 0x7F) {
+            let ack = self.probe(I2cAddress::Regular(addr))?;
+            found[addr as usize] = ack;
+            num_found += ack as usize;
+        }
+        Ok(num_found)
+    }
+
+    fn write_zero_len_blocking(&mut self, addr: I2cAddress) -> Result<(), Error> {
+        self.clear_tx_fifo();
+
+        let transfer_guard = TransferGuard::new(&self.regs, I2cCommand::StartWithStop);
+        let timeout_guard = TimeoutGuard::new(&self.regs);
+
+        self.regs.write_words(regs::Words::new(u11::new(0)));
+        self.write_address(addr, regs::Direction::Send);
+        self.write_command(I2cCommand::StartWithStop);
+        // The completion is detected from the status flags only: A NACK of the address is
+        // latched, and the controller and the bus are idle after the stop condition was sent.
+        // This does not depend on observing the short busy phase of the controller, which can
+        // be missed if the polling loop is interrupted.
+        for _ in 0..ZERO_LEN_WRITE_MAX_POLLS {
+            let status = self.read_status();
+            if status.arb_lost() {
+                return Err(Error::ArbitrationLost);
+            }
+            if status.nack_addr() {
+                return Err(Error::NackAddr);
+            }
+            if status.idle() && status.i2c_idle() {
+                return transfer_guard.complete(Ok(()));
+            }
+            if timeout_guard.timeout_enabled() && self.regs.read_irq_status().clock_timeout() {
+                return Err(Error::ClockTimeout(
+                    self.regs.read_clk_timeout_limit().value(),
+                ));
+            }
+        }
+        // The transfer guard cancels the transfer if the controller is still busy.
+        Err(Error::ZeroLengthWriteTimeout)
     }
 
     /// Blocking read transaction on the I2C bus.
+    ///
+    /// Reads larger than [MAX_CHUNK_LEN] bytes are split into chunks, see
+    /// [Self::set_chunked_transfers].
     pub fn read_blocking(&mut self, addr: I2cAddress, buffer: &mut [u8]) -> Result<(), Error> {
-        let len = buffer.len();
-        if len > 0x7fe {
-            return Err(Error::DataTooLarge);
+        let _guard = self.start_transaction()?;
+        let result = self.with_retries(|i2c| i2c.read_blocking_generic(addr, buffer));
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::Read, addr, buffer, &result);
+        result
+    }
+
+    fn read_blocking_generic(&mut self, addr: I2cAddress, buffer: &mut [u8]) -> Result<(), Error> {
+        self.check_transfer_len(buffer.len())?;
+        for (range, last) in chunk_ranges(buffer.len()) {
+            let (init_cmd, end_condition) = match last {
+                true => (I2cCommand::StartWithStop, WriteCompletionCondition::Idle),
+                false => (I2cCommand::Start, WriteCompletionCondition::Waiting),
+            };
+            let offset = range.start;
+            let total = buffer.len();
+            self.read_chunk(init_cmd, addr, &mut buffer[range], end_condition)
+                .map_err(|error| match error {
+                    Error::InsufficientDataReceived { received, .. } => {
+                        Error::InsufficientDataReceived {
+                            expected: total,
+                            received: offset + received,
+                        }
+                    }
+                    error => error,
+                })?;
         }
+        Ok(())
+    }
+
+    /// Read a chunk of at most [MAX_CHUNK_LEN] bytes.
+    fn read_chunk(
+        &mut self,
+        init_cmd: I2cCommand,
+        addr: I2cAddress,
+        buffer: &mut [u8],
+        end_condition: WriteCompletionCondition,
+    ) -> Result<(), Error> {
+        let len = buffer.len();
         // Clear the receive FIFO
         self.clear_rx_fifo();
 
+        let transfer_guard = TransferGuard::new(&self.regs, init_cmd);
         let timeout_guard = TimeoutGuard::new(&self.regs);
 
         // Load number of words
@@ -519,36 +1406,49 @@ impl<Addr> I2cMaster<Addr> {
         // Load address
         self.write_address(addr, regs::Direction::Receive);
 
-        let mut buf_iter = buffer.iter_mut();
-        let mut read_bytes = 0;
+        let rx_trigger_level = self.regs.read_rx_fifo_trigger().value().value();
+        let mut rx = RxAccounting::new(buffer);
         // Start receive transfer
-        self.write_command(I2cCommand::StartWithStop);
+        self.write_command(init_cmd);
+        let mut busy_wait = BusyWait::new();
         loop {
+            busy_wait.tick();
             let status = self.read_status();
             if status.arb_lost() {
-                self.clear_rx_fifo();
                 return Err(Error::ArbitrationLost);
             }
             if status.nack_addr() {
-                self.clear_rx_fifo();
                 return Err(Error::NackAddr);
             }
-            if status.idle() {
-                if read_bytes != len {
-                    return Err(Error::InsufficientDataReceived);
+            // The controller holds the bus after the last byte of a chunk was received.
+            let completed = match end_condition {
+                WriteCompletionCondition::Idle => status.idle(),
+                WriteCompletionCondition::Waiting => status.waiting(),
+            };
+            if completed {
+                // All received bytes are in the FIFO now, which is drained completely before the
+                // chunk is considered complete.
+                for _ in 0..MAX_FIFO_DEPTH {
+                    if !self.read_status().rx_not_empty() {
+                        break;
+                    }
+                    rx.push(self.read_fifo_unchecked());
                 }
-                return Ok(());
+                return transfer_guard.complete(rx.finish(self.rx_progress()));
             }
             if timeout_guard.timeout_enabled() && self.regs.read_irq_status().clock_timeout() {
                 return Err(Error::ClockTimeout(
                     self.regs.read_clk_timeout_limit().value(),
                 ));
             }
-            if status.rx_not_empty() {
-                if let Some(next_byte) = buf_iter.next() {
-                    *next_byte = self.read_fifo_unchecked();
-                }
-                read_bytes += 1;
+            let drain_count = rx_drain_count(
+                status.rx_trigger(),
+                status.rx_not_empty(),
+                rx_trigger_level,
+                rx.remaining(),
+            );
+            for _ in 0..drain_count {
+                rx.push(self.read_fifo_unchecked());
             }
         }
     }
@@ -560,22 +1460,40 @@ impl<Addr> I2cMaster<Addr> {
         output: &[u8],
         end_condition: WriteCompletionCondition,
     ) -> Result<(), Error> {
-        let len = output.len();
-        if len > 0x7fe {
-            return Err(Error::DataTooLarge);
+        self.check_transfer_len(output.len())?;
+        for (range, last) in chunk_ranges(output.len()) {
+            // All chunks except the last one end with a repeated start.
+            let (chunk_cmd, chunk_end) = match last {
+                true => (init_cmd, end_condition),
+                false => (I2cCommand::Start, WriteCompletionCondition::Waiting),
+            };
+            self.write_chunk(chunk_cmd, addr, &output[range], chunk_end)?;
         }
+        Ok(())
+    }
+
+    /// Write a chunk of at most [MAX_CHUNK_LEN] bytes.
+    fn write_chunk(
+        &mut self,
+        init_cmd: I2cCommand,
+        addr: I2cAddress,
+        output: &[u8],
+        end_condition: WriteCompletionCondition,
+    ) -> Result<(), Error> {
+        let len = output.len();
         // Clear the send FIFO
         self.clear_tx_fifo();
 
+        let transfer_guard = TransferGuard::new(&self.regs, init_cmd);
         let timeout_guard = TimeoutGuard::new(&self.regs);
 
         // Load number of words
         self.regs
             .write_words(regs::Words::new(u11::new(len as u16)));
         let mut bytes = output.iter();
-        // FIFO has a depth of 16. We load slightly above the trigger level
-        // but not all of it because the transaction might fail immediately
-        const FILL_DEPTH: usize = 12;
+        // We load slightly above the trigger level but not the whole FIFO because the
+        // transaction might fail immediately
+        const FILL_DEPTH: usize = MAX_FIFO_DEPTH - 4;
 
         let mut current_index = core::cmp::min(FILL_DEPTH, len);
         // load the FIFO
@@ -584,32 +1502,25 @@ impl<Addr> I2cMaster<Addr> {
         }
         self.write_address(addr, regs::Direction::Send);
         self.write_command(init_cmd);
+        let mut busy_wait = BusyWait::new();
         loop {
+            busy_wait.tick();
             let status = self.regs.read_status();
             if status.arb_lost() {
-                self.error_handler_write(init_cmd);
                 return Err(Error::ArbitrationLost);
             }
             if status.nack_addr() {
-                self.error_handler_write(init_cmd);
                 return Err(Error::NackAddr);
             }
             if status.nack_data() {
-                self.error_handler_write(init_cmd);
                 return Err(Error::NackData);
             }
-            match end_condition {
-                WriteCompletionCondition::Idle => {
-                    if status.idle() {
-                        return Ok(());
-                    }
-                }
-
-                WriteCompletionCondition::Waiting => {
-                    if status.waiting() {
-                        return Ok(());
-                    }
-                }
+            let completed = match end_condition {
+                WriteCompletionCondition::Idle => status.idle(),
+                WriteCompletionCondition::Waiting => status.waiting(),
+            };
+            if completed {
+                return transfer_guard.complete(Ok(()));
             }
             if timeout_guard.timeout_enabled() && self.regs.read_irq_status().clock_timeout() {
                 return Err(Error::ClockTimeout(
@@ -630,13 +1541,63 @@ impl<Addr> I2cMaster<Addr> {
         write: &[u8],
         read: &mut [u8],
     ) -> Result<(), Error> {
-        self.write_blocking_generic(
-            I2cCommand::Start,
+        // Both lengths are checked up front, because the write phase ends with the bus held
+        // for the repeated start of the read.
+        self.check_transfer_len(write.len())?;
+        self.check_transfer_len(read.len())?;
+        let _guard = self.start_transaction()?;
+        let result = self.with_retries(|i2c| {
+            i2c.write_blocking_generic(
+                I2cCommand::Start,
+                address,
+                write,
+                WriteCompletionCondition::Waiting,
+            )?;
+            i2c.read_blocking_generic(address, read)
+        });
+        #[cfg(feature = "bus-trace")]
+        self.trace(
+            crate::bus_trace::Direction::WriteRead,
             address,
-            write,
-            WriteCompletionCondition::Waiting,
-        )?;
-        self.read_blocking(address, read)
+            read,
+            &result,
+        );
+        result
+    }
+
+    #[cfg(feature = "bus-trace")]
+    fn trace(
+        &self,
+        direction: crate::bus_trace::Direction,
+        addr: I2cAddress,
+        data: &[u8],
+        result: &Result<(), Error>,
+    ) {
+        crate::bus_trace::event(
+            self.id.periph_sel(),
+            direction,
+            Some(addr.raw()),
+            data,
+            result.as_ref().map(|_| ()),
+        );
+    }
+}
+
+impl<Addr> SleepParticipant for I2cMaster<Addr> {
+    /// Disables the controller. The blocking transactions are always complete when they return,
+    /// so no transfer is interrupted.
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_none() {
+            self.sleep_state = Some(self.regs.read_control().enable());
+            self.disable();
+        }
+    }
+
+    /// Enables the controller again if it was enabled before.
+    fn resume(&mut self) {
+        if self.sleep_state.take() == Some(true) {
+            self.enable();
+        }
     }
 }
 
@@ -703,3 +1664,387 @@ impl embedded_hal::i2c::I2c<TenBitAddress> for I2cMaster<TenBitAddress> {
         self.write_read_blocking(addr, write, read)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_depth() {
+        assert_eq!(Bank::I2c0.fifo_depth(), MAX_FIFO_DEPTH);
+        assert_eq!(Bank::I2c1.fifo_depth(), MAX_FIFO_DEPTH);
+        #[cfg(feature = "vor4x")]
+        assert_eq!(Bank::I2c2.fifo_depth(), MAX_FIFO_DEPTH);
+        assert_eq!(<I2c0 as I2cInstance>::FIFO_DEPTH, MAX_FIFO_DEPTH);
+    }
+
+    #[test]
+    fn transaction_guard_blocks_second_entry() {
+        static FLAG: AtomicBool = AtomicBool::new(false);
+        let guard = TransactionGuard::acquire(&FLAG).unwrap();
+        assert!(FLAG.load(Ordering::Relaxed));
+        assert!(matches!(
+            TransactionGuard::acquire(&FLAG),
+            Err(Error::BusBusy)
+        ));
+        // The failed attempt must not release the flag of the active transaction.
+        assert!(FLAG.load(Ordering::Relaxed));
+        drop(guard);
+        assert!(!FLAG.load(Ordering::Relaxed));
+        assert!(TransactionGuard::acquire(&FLAG).is_ok());
+        assert!(!FLAG.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn chunk_splitting() {
+        fn collect(len: usize) -> heapless::Vec<(core::ops::Range<usize>, bool), 64> {
+            chunk_ranges(len).collect()
+        }
+        assert_eq!(collect(0), [(0..0, true)]);
+        assert_eq!(collect(1), [(0..1, true)]);
+        assert_eq!(collect(MAX_CHUNK_LEN), [(0..MAX_CHUNK_LEN, true)]);
+        assert_eq!(
+            collect(MAX_CHUNK_LEN + 1),
+            [
+                (0..MAX_CHUNK_LEN, false),
+                (MAX_CHUNK_LEN..MAX_CHUNK_LEN + 1, true)
+            ]
+        );
+        assert_eq!(
+            collect(3 * MAX_CHUNK_LEN + 5),
+            [
+                (0..MAX_CHUNK_LEN, false),
+                (MAX_CHUNK_LEN..2 * MAX_CHUNK_LEN, false),
+                (2 * MAX_CHUNK_LEN..3 * MAX_CHUNK_LEN, false),
+                (3 * MAX_CHUNK_LEN..3 * MAX_CHUNK_LEN + 5, true)
+            ]
+        );
+        // 64 KiB EEPROM dump: The chunks are contiguous and cover the whole buffer, only the
+        // last chunk ends with a stop condition.
+        let chunks = collect(64 * 1024);
+        assert_eq!(chunks.len(), 33);
+        let mut next_start = 0;
+        for (idx, (range, last)) in chunks.iter().enumerate() {
+            assert_eq!(range.start, next_start);
+            assert!(range.len() <= MAX_CHUNK_LEN && !range.is_empty());
+            assert_eq!(*last, idx == chunks.len() - 1);
+            next_start = range.end;
+        }
+        assert_eq!(next_start, 64 * 1024);
+    }
+
+    #[test]
+    fn transaction_guard_released_on_early_returns() {
+        static FLAG: AtomicBool = AtomicBool::new(false);
+        fn transaction(result: Result<(), Error>) -> Result<(), Error> {
+            let _guard = TransactionGuard::acquire(&FLAG)?;
+            result?;
+            Ok(())
+        }
+        // All early return paths of the blocking read and write transactions.
+        for error in [
+            Error::DataTooLarge,
+            Error::ArbitrationLost,
+            Error::NackAddr,
+            Error::NackData,
+            Error::InsufficientDataReceived {
+                expected: 4,
+                received: 2,
+            },
+            Error::ClockTimeout(u20::new(100)),
+            Error::ZeroLengthWriteTimeout,
+        ] {
+            assert!(transaction(Err(error)).is_err());
+            assert!(!FLAG.load(Ordering::Relaxed));
+        }
+        assert_eq!(transaction(Ok(())), Ok(()));
+        assert!(!FLAG.load(Ordering::Relaxed));
+    }
+
+    fn timing(t_rise: u8, t_fall: u8, t_high: u8, t_low: u8) -> TimingConfig {
+        TimingConfig {
+            t_rise: u4::new(t_rise),
+            t_fall: u4::new(t_fall),
+            t_high: u4::new(t_high),
+            t_low: u4::new(t_low),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_timing_valid_for_regular_mode() {
+        assert_eq!(
+            TimingConfig::default().validate(I2cSpeed::Regular100khz),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn accepted_timing() {
+        assert_eq!(
+            timing(1, 1, 8, 10).validate(I2cSpeed::Regular100khz),
+            Ok(())
+        );
+        assert_eq!(
+            timing(0, 0, 8, 10).validate(I2cSpeed::Regular100khz),
+            Ok(())
+        );
+        assert_eq!(timing(2, 2, 4, 15).validate(I2cSpeed::Fast400khz), Ok(()));
+        assert_eq!(timing(0, 0, 6, 13).validate(I2cSpeed::Fast400khz), Ok(()));
+    }
+
+    #[test]
+    fn rejected_timing() {
+        // SCL low time too short.
+        assert_eq!(
+            timing(2, 0, 8, 9).validate(I2cSpeed::Regular100khz),
+            Err(InvalidTimingParamsError)
+        );
+        // SCL high time too short.
+        assert_eq!(
+            timing(0, 1, 7, 9).validate(I2cSpeed::Regular100khz),
+            Err(InvalidTimingParamsError)
+        );
+        // Does not fit into one SCL period.
+        assert_eq!(
+            timing(2, 2, 8, 9).validate(I2cSpeed::Regular100khz),
+            Err(InvalidTimingParamsError)
+        );
+        // The default timing violates the fast mode low time.
+        assert_eq!(
+            TimingConfig::default().validate(I2cSpeed::Fast400khz),
+            Err(InvalidTimingParamsError)
+        );
+        assert_eq!(
+            timing(3, 3, 10, 10).validate(I2cSpeed::Fast400khz),
+            Err(InvalidTimingParamsError)
+        );
+    }
+
+    #[test]
+    fn rx_drain_count_empty_fifo() {
+        assert_eq!(rx_drain_count(false, false, 8, 100), 0);
+        assert_eq!(rx_drain_count(true, false, 0, 100), 0);
+    }
+
+    #[test]
+    fn rx_drain_count_below_trigger_level() {
+        for level in [1, 8, 16] {
+            assert_eq!(rx_drain_count(false, true, level, 100), 1);
+        }
+    }
+
+    #[test]
+    fn rx_drain_count_trigger_levels() {
+        assert_eq!(rx_drain_count(true, true, 1, 100), 1);
+        assert_eq!(rx_drain_count(true, true, 8, 100), 8);
+        assert_eq!(rx_drain_count(true, true, 16, 100), 16);
+        // Levels larger than the FIFO depth are limited to the FIFO depth.
+        assert_eq!(rx_drain_count(true, true, 31, 100), 16);
+        // A level of 0 sets the trigger flag for an empty FIFO as well.
+        assert_eq!(rx_drain_count(true, true, 0, 100), 1);
+    }
+
+    #[test]
+    fn rx_drain_count_short_transfer() {
+        assert_eq!(rx_drain_count(true, true, 8, 3), 3);
+        assert_eq!(rx_drain_count(true, true, 16, 15), 15);
+        assert_eq!(rx_drain_count(false, true, 16, 3), 1);
+        // Unexpected additional bytes are still read one by one.
+        assert_eq!(rx_drain_count(true, true, 8, 0), 1);
+    }
+
+    #[test]
+    fn rx_accounting_exact_length() {
+        let mut buf = [0; 4];
+        let mut rx = RxAccounting::new(&mut buf);
+        for byte in 1..=4 {
+            rx.push(byte);
+        }
+        assert_eq!(rx.remaining(), 0);
+        assert_eq!(rx.finish(4), Ok(()));
+        assert_eq!(buf, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn rx_accounting_bursts() {
+        let mut buf = [0; 5];
+        let mut rx = RxAccounting::new(&mut buf);
+        // The FIFO delivers the bytes in bursts of different lengths.
+        for burst in [&[1, 2][..], &[], &[3], &[4, 5]] {
+            for byte in burst {
+                rx.push(*byte);
+            }
+        }
+        assert_eq!(rx.finish(5), Ok(()));
+        assert_eq!(buf, [1, 2, 3, 4, 5]);
+
+        let mut rx = RxAccounting::new(&mut buf);
+        rx.push(6);
+        rx.push(7);
+        assert_eq!(rx.remaining(), 3);
+        assert_eq!(
+            rx.finish(2),
+            Err(Error::InsufficientDataReceived {
+                expected: 5,
+                received: 2
+            })
+        );
+    }
+
+    #[test]
+    fn rx_accounting_reports_hardware_count() {
+        let mut buf = [0; 4];
+        let mut rx = RxAccounting::new(&mut buf);
+        rx.push(1);
+        // Three bytes were clocked in, but one was lost before it could be read from the FIFO.
+        assert_eq!(
+            rx.finish(3),
+            Err(Error::InsufficientDataReceived {
+                expected: 4,
+                received: 3
+            })
+        );
+        let mut rx = RxAccounting::new(&mut buf);
+        rx.push(1);
+        rx.push(2);
+        assert_eq!(
+            rx.finish(0),
+            Err(Error::InsufficientDataReceived {
+                expected: 4,
+                received: 0
+            })
+        );
+    }
+
+    #[test]
+    fn rx_accounting_excess_data() {
+        let mut buf = [0; 2];
+        let mut rx = RxAccounting::new(&mut buf);
+        for byte in 1..=5 {
+            rx.push(byte);
+        }
+        assert_eq!(rx.remaining(), 0);
+        assert_eq!(rx.finish(5), Err(Error::ExcessDataReceived(3)));
+        assert_eq!(buf, [1, 2]);
+
+        let mut rx = RxAccounting::new(&mut []);
+        rx.push(1);
+        assert_eq!(rx.finish(1), Err(Error::ExcessDataReceived(1)));
+    }
+
+    #[test]
+    fn builder() {
+        let cfg = MasterConfig::builder()
+            .alg_filt(true)
+            .timeout(u20::new(100))
+            .rx_fifo_trigger(u5::new(8))
+            .build()
+            .unwrap();
+        assert_eq!(
+            cfg,
+            MasterConfig {
+                alg_filt: true,
+                timeout: Some(u20::new(100)),
+                rx_fifo_trigger: Some(u5::new(8)),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            MasterConfig::builder()
+                .speed_mode(I2cSpeed::Fast400khz)
+                .timing_config(TimingConfig::default())
+                .build(),
+            Err(InvalidTimingParamsError)
+        );
+        let timing_config = timing(0, 0, 6, 13);
+        assert_eq!(
+            MasterConfig::builder()
+                .speed_mode(I2cSpeed::Fast400khz)
+                .timing_config(timing_config)
+                .build()
+                .unwrap()
+                .timing_config,
+            Some(timing_config)
+        );
+    }
+
+    #[test]
+    fn retry_decision_table() {
+        let all = RetryOn {
+            arbitration_lost: true,
+            nack_addr: true,
+        };
+        assert_eq!(
+            all.retryable(&Error::ArbitrationLost),
+            Some(RetryableError::ArbitrationLost)
+        );
+        assert_eq!(
+            all.retryable(&Error::NackAddr),
+            Some(RetryableError::NackAddr)
+        );
+        // Errors after data was transferred or which are not caused by the bus are never
+        // retried.
+        for error in [
+            Error::NackData,
+            Error::InsufficientDataReceived {
+                expected: 4,
+                received: 2,
+            },
+            Error::DataTooLarge,
+            Error::ClockTimeout(u20::new(10)),
+            Error::ZeroLengthWriteTimeout,
+            Error::BusBusy,
+        ] {
+            assert_eq!(all.retryable(&error), None);
+        }
+        let arb_only = RetryOn {
+            arbitration_lost: true,
+            nack_addr: false,
+        };
+        assert_eq!(arb_only.retryable(&Error::NackAddr), None);
+        assert_eq!(RetryOn::default().retryable(&Error::ArbitrationLost), None);
+    }
+
+    #[test]
+    fn retry_policy_attempts() {
+        // The default policy returns all errors unchanged.
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.check_attempt(Error::ArbitrationLost, 1),
+            Some(Error::ArbitrationLost)
+        );
+
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            retry_on: RetryOn {
+                arbitration_lost: true,
+                nack_addr: false,
+            },
+            backoff: Backoff::BusIdleWait,
+        };
+        assert_eq!(policy.check_attempt(Error::ArbitrationLost, 1), None);
+        assert_eq!(policy.check_attempt(Error::ArbitrationLost, 2), None);
+        let exhausted = policy.check_attempt(Error::ArbitrationLost, 3).unwrap();
+        assert_eq!(
+            exhausted,
+            Error::RetriesExhausted {
+                attempts: 3,
+                cause: RetryableError::ArbitrationLost
+            }
+        );
+        assert_eq!(
+            embedded_hal::i2c::Error::kind(&exhausted),
+            embedded_hal::i2c::ErrorKind::ArbitrationLoss
+        );
+        // Errors which are not retried are returned unchanged, even after retries.
+        assert_eq!(
+            policy.check_attempt(Error::NackData, 2),
+            Some(Error::NackData)
+        );
+        assert_eq!(
+            policy.check_attempt(Error::NackAddr, 1),
+            Some(Error::NackAddr)
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use core::marker::PhantomData;
 
+use crate::PeripheralSelect;
+
 use arbitrary_int::{u4, u5, u9, u10, u11, u20};
 
 pub use crate::shared::{FifoClear, TriggerLevel};
@@ -10,6 +12,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_0: usize = 0x4006_0000;
         /// I2C B base address
         pub const BASE_ADDR_1: usize = 0x4006_1000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0014_07E1;
     } else if #[cfg(feature = "vor4x")] {
         /// I2C 0 base address
         pub const BASE_ADDR_0: usize = 0x4001_6000;
@@ -17,6 +21,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_1: usize = 0x4001_6400;
         /// I2C 2 base address
         pub const BASE_ADDR_2: usize = 0x4001_6800;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0214_07E9;
     }
 }
 
@@ -30,14 +36,56 @@ pub enum Bank {
 }
 
 impl Bank {
-    /// Unsafely steal the I2C peripheral block for the given port.
+    /// Unsafely steal the I2C peripheral block for the given bank.
+    ///
+    /// In debug builds, this panics if the bank is owned by a driver created with a safe
+    /// constructor, see [crate::ownership].
     ///
     /// # Safety
     ///
     /// Circumvents ownership and safety guarantees by the HAL.
+    #[track_caller]
     pub unsafe fn steal_regs(&self) -> MmioI2c<'static> {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::I2c(*self));
+        I2c::new_mmio(*self)
+    }
+
+    /// Same as [Self::steal_regs], but without the ownership check.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL.
+    pub unsafe fn force_steal_regs(&self) -> MmioI2c<'static> {
         I2c::new_mmio(*self)
     }
+
+    /// Peripheral select value used for the clock enable and reset registers.
+    pub const fn periph_sel(&self) -> PeripheralSelect {
+        match self {
+            Bank::I2c0 => PeripheralSelect::I2c0,
+            Bank::I2c1 => PeripheralSelect::I2c1,
+            #[cfg(feature = "vor4x")]
+            Bank::I2c2 => PeripheralSelect::I2c2,
+        }
+    }
+
+    /// Depth of the TX and RX FIFO in bytes. All I2C peripherals of the supported families have
+    /// 16 byte deep FIFOs.
+    pub const fn fifo_depth(&self) -> usize {
+        match self {
+            Bank::I2c0 | Bank::I2c1 => 16,
+            #[cfg(feature = "vor4x")]
+            Bank::I2c2 => 16,
+        }
+    }
+
+    /// APB bus which clocks the I2C peripheral. All I2C peripherals are APB1 peripherals.
+    #[cfg(feature = "vor4x")]
+    pub const fn apb_bus(&self) -> crate::clock::ApbBus {
+        match self {
+            Bank::I2c0 | Bank::I2c1 | Bank::I2c2 => crate::clock::ApbBus::Apb1,
+        }
+    }
 }
 
 #[bitbybit::bitenum(u1, exhaustive = true)]
@@ -209,6 +257,12 @@ pub struct State {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DataCount(arbitrary_int::UInt<u32, 11>);
 
+impl DataCount {
+    pub const fn value(&self) -> u11 {
+        u11::new(self.0.value() as u16)
+    }
+}
+
 #[bitbybit::bitfield(u32, debug, defmt_bitfields(feature = "defmt"))]
 pub struct InterruptControl {
     #[bit(0, rw)]
@@ -643,6 +697,7 @@ cfg_if::cfg_if! {
 }
 
 impl I2c {
+    #[cfg(not(feature = "test-harness"))]
     fn new_mmio_at(base: usize) -> MmioI2c<'static> {
         MmioI2c {
             ptr: base as *mut _,
@@ -651,6 +706,12 @@ impl I2c {
     }
 
     pub fn new_mmio(bank: Bank) -> MmioI2c<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioI2c {
+            ptr: crate::test_harness::i2c(bank).as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         match bank {
             Bank::I2c0 => Self::new_mmio_at(BASE_ADDR_0),
             Bank::I2c1 => Self::new_mmio_at(BASE_ADDR_1),
@@ -0,0 +1,226 @@
+//! # Sharing one [I2cMaster] between multiple device drivers.
+//!
+//! The [I2cBusManager] owns the [I2cMaster] and hands out [I2cBusDevice] handles which implement
+//! the [embedded_hal::i2c::I2c] trait. Each handle locks the bus for the duration of one
+//! transaction, so multiple device drivers can be used on the same physical bus.
+//!
+//! The bus is taken out of a [critical_section::Mutex] for a transaction, but the transaction
+//! itself is performed with interrupts enabled. The blocking [embedded_hal::i2c::I2c]
+//! implementation waits until the bus is released again. This must not be used from an interrupt
+//! handler which can preempt a transaction in progress, because the bus would never be released.
+//! The [I2cBusDevice::try_transaction] variant returns [SharedBusError::BusBusy] instead of
+//! waiting and can be used from interrupt handlers.
+//!
+//! The manager is generic over the address mode of the [I2cMaster], but devices with 7-bit and
+//! 10-bit addresses can be used on the same bus by using [I2cBusManager::device] and
+//! [I2cBusManager::ten_bit_device] respectively.
+use core::{cell::RefCell, marker::PhantomData};
+
+use critical_section::Mutex;
+use embedded_hal::i2c::{Operation, SevenBitAddress, TenBitAddress};
+
+use super::{Error, I2cAddress, I2cMaster};
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SharedBusError {
+    /// The bus is currently used by another device handle.
+    #[error("I2C bus busy")]
+    BusBusy,
+    #[error("I2C error: {0}")]
+    I2c(#[from] Error),
+}
+
+/// Owner of a shared [I2cMaster].
+pub struct I2cBusManager<BusAddr = SevenBitAddress> {
+    bus: Mutex<RefCell<Option<I2cMaster<BusAddr>>>>,
+}
+
+impl<BusAddr> I2cBusManager<BusAddr> {
+    pub const fn new(i2c: I2cMaster<BusAddr>) -> Self {
+        Self {
+            bus: Mutex::new(RefCell::new(Some(i2c))),
+        }
+    }
+
+    /// Device handle for a device with a 7-bit address.
+    #[inline]
+    pub fn device(&self) -> I2cBusDevice<'_, SevenBitAddress, BusAddr> {
+        I2cBusDevice {
+            manager: self,
+            addr: PhantomData,
+        }
+    }
+
+    /// Device handle for a device with a 10-bit address.
+    #[inline]
+    pub fn ten_bit_device(&self) -> I2cBusDevice<'_, TenBitAddress, BusAddr> {
+        I2cBusDevice {
+            manager: self,
+            addr: PhantomData,
+        }
+    }
+
+    /// Retrieve the [I2cMaster]. No device handle can exist anymore at this point.
+    pub fn release(self) -> I2cMaster<BusAddr> {
+        self.bus
+            .into_inner()
+            .into_inner()
+            .expect("I2C bus not returned after transaction")
+    }
+
+    /// Perform the transaction if the bus is not in use, otherwise return
+    /// [SharedBusError::BusBusy].
+    pub fn try_transaction(
+        &self,
+        addr: I2cAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), SharedBusError> {
+        let mut i2c = self.try_lock().ok_or(SharedBusError::BusBusy)?;
+        let result = transaction(&mut i2c, addr, operations);
+        self.unlock(i2c);
+        Ok(result?)
+    }
+
+    /// Perform the transaction, waiting for the bus to be released if it is currently in use.
+    pub fn transaction(
+        &self,
+        addr: I2cAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        let mut i2c = self.lock();
+        let result = transaction(&mut i2c, addr, operations);
+        self.unlock(i2c);
+        result
+    }
+
+    /// Write and read with a repeated start in between, waiting for the bus to be released if
+    /// it is currently in use. See [I2cMaster::write_read_blocking].
+    pub fn write_read(&self, addr: I2cAddress, write: &[u8], read: &mut [u8]) -> Result<(), Error> {
+        let mut i2c = self.lock();
+        let result = i2c.write_read_blocking(addr, write, read);
+        self.unlock(i2c);
+        result
+    }
+
+    fn lock(&self) -> I2cMaster<BusAddr> {
+        loop {
+            if let Some(i2c) = self.try_lock() {
+                break i2c;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    fn try_lock(&self) -> Option<I2cMaster<BusAddr>> {
+        critical_section::with(|cs| self.bus.borrow_ref_mut(cs).take())
+    }
+
+    fn unlock(&self, i2c: I2cMaster<BusAddr>) {
+        critical_section::with(|cs| {
+            self.bus.borrow_ref_mut(cs).replace(i2c);
+        })
+    }
+}
+
+fn transaction<BusAddr>(
+    i2c: &mut I2cMaster<BusAddr>,
+    addr: I2cAddress,
+    operations: &mut [Operation<'_>],
+) -> Result<(), Error> {
+    for operation in operations {
+        match operation {
+            Operation::Read(buf) => i2c.read_blocking(addr, buf)?,
+            Operation::Write(buf) => i2c.write_blocking(addr, buf)?,
+        }
+    }
+    Ok(())
+}
+
+/// Handle for one device on a bus shared with the [I2cBusManager].
+///
+/// The `Addr` type parameter specifies the address mode of the device.
+pub struct I2cBusDevice<'bus, Addr = SevenBitAddress, BusAddr = SevenBitAddress> {
+    manager: &'bus I2cBusManager<BusAddr>,
+    addr: PhantomData<Addr>,
+}
+
+impl<BusAddr> I2cBusDevice<'_, SevenBitAddress, BusAddr> {
+    /// Perform the transaction if the bus is not in use, otherwise return
+    /// [SharedBusError::BusBusy]. This never waits for the bus and can be used from interrupt
+    /// handlers.
+    #[inline]
+    pub fn try_transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), SharedBusError> {
+        self.manager
+            .try_transaction(I2cAddress::Regular(address), operations)
+    }
+}
+
+impl<BusAddr> I2cBusDevice<'_, TenBitAddress, BusAddr> {
+    /// Perform the transaction if the bus is not in use, otherwise return
+    /// [SharedBusError::BusBusy]. This never waits for the bus and can be used from interrupt
+    /// handlers.
+    #[inline]
+    pub fn try_transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), SharedBusError> {
+        self.manager
+            .try_transaction(I2cAddress::TenBit(address), operations)
+    }
+}
+
+//======================================================================================
+// Embedded HAL I2C implementations
+//======================================================================================
+
+impl<Addr, BusAddr> embedded_hal::i2c::ErrorType for I2cBusDevice<'_, Addr, BusAddr> {
+    type Error = Error;
+}
+
+impl<BusAddr> embedded_hal::i2c::I2c for I2cBusDevice<'_, SevenBitAddress, BusAddr> {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.manager
+            .transaction(I2cAddress::Regular(address), operations)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddress,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.manager
+            .write_read(I2cAddress::Regular(address), write, read)
+    }
+}
+
+impl<BusAddr> embedded_hal::i2c::I2c<TenBitAddress> for I2cBusDevice<'_, TenBitAddress, BusAddr> {
+    fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.manager
+            .transaction(I2cAddress::TenBit(address), operations)
+    }
+
+    fn write_read(
+        &mut self,
+        address: TenBitAddress,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.manager
+            .write_read(I2cAddress::TenBit(address), write, read)
+    }
+}
@@ -0,0 +1,339 @@
+//! # SMBus support built on top of the [I2cMaster].
+//!
+//! This module provides the [SmbusMaster] which implements the most common SMBus protocols,
+//! including the block read and block write protocols. All protocols can optionally use Packet
+//! Error Checking (PEC). The PEC is a CRC-8 with the polynomial x^8 + x^2 + x + 1 which is
+//! calculated over the whole frame, including the address bytes with the R/W bit.
+//!
+//! The I2C master peripheral requires the number of bytes to read to be known before a read
+//! transfer is started. The block read protocol therefore always reads the byte count, a block
+//! of [MAX_BLOCK_LEN] bytes and the optional PEC in a single write-read transaction with a
+//! repeated start. The bytes after the end of the frame are discarded.
+use embedded_hal::i2c::SevenBitAddress;
+
+use super::{Error, I2cAddress, I2cMaster};
+
+/// Maximum number of data bytes in a SMBus block transfer.
+pub const MAX_BLOCK_LEN: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmbusError {
+    #[error("I2C error: {0}")]
+    I2c(#[from] Error),
+    /// The received PEC did not match the calculated PEC.
+    #[error("PEC mismatch: calculated {calculated:#04x}, received {received:#04x}")]
+    PecMismatch { calculated: u8, received: u8 },
+    /// Block data is larger than [MAX_BLOCK_LEN].
+    #[error("block length {0} larger than 32")]
+    BlockTooLarge(usize),
+}
+
+/// Update a SMBus Packet Error Code (CRC-8 with polynomial 0x07) with the given data.
+///
+/// The PEC calculation starts with a value of 0.
+pub const fn pec_update(mut crc: u8, data: &[u8]) -> u8 {
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            if crc & 0x80 != 0 {
+                crc = (crc << 1) ^ 0x07;
+            } else {
+                crc <<= 1;
+            }
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// Clamps the byte count received in a block read to the SMBus maximum block length.
+///
+/// Returns the number of bytes which are part of the frame and the number of bytes which fit
+/// into a caller buffer of the given length.
+#[inline]
+pub const fn clamp_block_count(count: u8, buf_len: usize) -> (usize, usize) {
+    let frame_len = if count as usize > MAX_BLOCK_LEN {
+        MAX_BLOCK_LEN
+    } else {
+        count as usize
+    };
+    let copy_len = if frame_len > buf_len {
+        buf_len
+    } else {
+        frame_len
+    };
+    (frame_len, copy_len)
+}
+
+#[inline(always)]
+const fn write_addr_byte(addr: u8) -> u8 {
+    addr << 1
+}
+
+#[inline(always)]
+const fn read_addr_byte(addr: u8) -> u8 {
+    (addr << 1) | 1
+}
+
+/// SMBus master driver wrapping a 7-bit [I2cMaster].
+pub struct SmbusMaster {
+    i2c: I2cMaster<SevenBitAddress>,
+    pec: bool,
+}
+
+impl SmbusMaster {
+    /// Create a new SMBus master. The `pec` argument specifies whether Packet Error Checking
+    /// is used for all transfers.
+    pub fn new(i2c: I2cMaster<SevenBitAddress>, pec: bool) -> Self {
+        Self { i2c, pec }
+    }
+
+    #[inline]
+    pub fn set_pec(&mut self, pec: bool) {
+        self.pec = pec;
+    }
+
+    #[inline]
+    pub const fn pec(&self) -> bool {
+        self.pec
+    }
+
+    /// Access to the underlying [I2cMaster], for example for plain I2C transfers.
+    #[inline]
+    pub fn i2c(&mut self) -> &mut I2cMaster<SevenBitAddress> {
+        &mut self.i2c
+    }
+
+    #[inline]
+    pub fn release(self) -> I2cMaster<SevenBitAddress> {
+        self.i2c
+    }
+
+    /// SMBus Read Byte protocol.
+    pub fn read_byte_data(&mut self, addr: u8, cmd: u8) -> Result<u8, SmbusError> {
+        let mut buf = [0; 1];
+        self.read_data(addr, cmd, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// SMBus Write Byte protocol.
+    pub fn write_byte_data(&mut self, addr: u8, cmd: u8, value: u8) -> Result<(), SmbusError> {
+        self.write_data(addr, &[cmd, value])
+    }
+
+    /// SMBus Read Word protocol. The word is transferred with the low byte first.
+    pub fn read_word_data(&mut self, addr: u8, cmd: u8) -> Result<u16, SmbusError> {
+        let mut buf = [0; 2];
+        self.read_data(addr, cmd, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// SMBus Write Word protocol. The word is transferred with the low byte first.
+    pub fn write_word_data(&mut self, addr: u8, cmd: u8, value: u16) -> Result<(), SmbusError> {
+        let value = value.to_le_bytes();
+        self.write_data(addr, &[cmd, value[0], value[1]])
+    }
+
+    /// SMBus Block Read protocol.
+    ///
+    /// The byte count sent by the device is clamped to [MAX_BLOCK_LEN]. If the block is larger
+    /// than the passed buffer, the remaining bytes are discarded. The PEC, if enabled, is always
+    /// verified over the whole frame. Returns the number of bytes written to the buffer.
+    pub fn block_read(&mut self, addr: u8, cmd: u8, buf: &mut [u8]) -> Result<usize, SmbusError> {
+        block_read_with(addr, cmd, self.pec, buf, |write, read| {
+            self.i2c
+                .write_read_blocking(I2cAddress::Regular(addr), write, read)
+        })
+    }
+
+    /// SMBus Block Write protocol. The data can not be larger than [MAX_BLOCK_LEN].
+    pub fn block_write(&mut self, addr: u8, cmd: u8, data: &[u8]) -> Result<(), SmbusError> {
+        if data.len() > MAX_BLOCK_LEN {
+            return Err(SmbusError::BlockTooLarge(data.len()));
+        }
+        // Command byte, byte count, block data and optional PEC byte.
+        let mut frame = [0; MAX_BLOCK_LEN + 3];
+        frame[0] = cmd;
+        frame[1] = data.len() as u8;
+        frame[2..2 + data.len()].copy_from_slice(data);
+        self.write_data(addr, &frame[0..2 + data.len()])
+    }
+
+    /// Write the frame and append the PEC if enabled. The frame can not be larger than the
+    /// largest block write frame.
+    fn write_data(&mut self, addr: u8, frame: &[u8]) -> Result<(), SmbusError> {
+        let mut buf = [0; MAX_BLOCK_LEN + 3];
+        buf[0..frame.len()].copy_from_slice(frame);
+        let mut len = frame.len();
+        if self.pec {
+            let crc = pec_update(0, &[write_addr_byte(addr)]);
+            buf[len] = pec_update(crc, frame);
+            len += 1;
+        }
+        self.i2c
+            .write_blocking(I2cAddress::Regular(addr), &buf[0..len])?;
+        Ok(())
+    }
+
+    /// Read data after writing the command byte and verify the PEC if enabled. The buffer can
+    /// not be larger than two bytes.
+    fn read_data(&mut self, addr: u8, cmd: u8, buf: &mut [u8]) -> Result<(), SmbusError> {
+        let mut frame = [0; 3];
+        let read_len = buf.len() + self.pec as usize;
+        self.i2c
+            .write_read_blocking(I2cAddress::Regular(addr), &[cmd], &mut frame[0..read_len])?;
+        if self.pec {
+            let mut crc = pec_update(0, &[write_addr_byte(addr), cmd, read_addr_byte(addr)]);
+            crc = pec_update(crc, &frame[0..buf.len()]);
+            check_pec(crc, frame[buf.len()])?;
+        }
+        buf.copy_from_slice(&frame[0..buf.len()]);
+        Ok(())
+    }
+}
+
+/// SMBus Block Read protocol with the given write-read transaction, see
+/// [SmbusMaster::block_read].
+fn block_read_with(
+    addr: u8,
+    cmd: u8,
+    pec: bool,
+    buf: &mut [u8],
+    write_read: impl FnOnce(&[u8], &mut [u8]) -> Result<(), Error>,
+) -> Result<usize, SmbusError> {
+    // Count byte, largest block and optional PEC byte. The byte count is only known after the
+    // transfer was started.
+    let mut frame = [0; MAX_BLOCK_LEN + 2];
+    write_read(&[cmd], &mut frame[0..1 + MAX_BLOCK_LEN + pec as usize])?;
+    let (frame_len, copy_len) = clamp_block_count(frame[0], buf.len());
+    if pec {
+        let mut crc = pec_update(0, &[write_addr_byte(addr), cmd, read_addr_byte(addr)]);
+        crc = pec_update(crc, &frame[0..1 + frame_len]);
+        check_pec(crc, frame[1 + frame_len])?;
+    }
+    buf[0..copy_len].copy_from_slice(&frame[1..1 + copy_len]);
+    Ok(copy_len)
+}
+
+#[inline]
+fn check_pec(calculated: u8, received: u8) -> Result<(), SmbusError> {
+    if calculated != received {
+        return Err(SmbusError::PecMismatch {
+            calculated,
+            received,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pec_check_value() {
+        // Standard check value of the CRC-8 with polynomial 0x07 and initial value 0.
+        assert_eq!(pec_update(0, b"123456789"), 0xF4);
+        assert_eq!(pec_update(0, &[]), 0);
+    }
+
+    #[test]
+    fn pec_incremental_update() {
+        let crc = pec_update(0, b"1234");
+        assert_eq!(pec_update(crc, b"56789"), 0xF4);
+    }
+
+    /// Runs a block read against a device which answers the command with the given bytes,
+    /// followed by idle bus bytes. Returns the number of write-read transactions.
+    fn block_read_from(
+        response: &[u8],
+        pec: bool,
+        buf: &mut [u8],
+    ) -> (Result<usize, SmbusError>, usize) {
+        let mut transactions = 0;
+        let result = block_read_with(0x0B, 0x23, pec, buf, |write, read| {
+            transactions += 1;
+            assert_eq!(write, [0x23]);
+            assert_eq!(read.len(), 1 + MAX_BLOCK_LEN + pec as usize);
+            read.fill(0xFF);
+            read[0..response.len()].copy_from_slice(response);
+            Ok(())
+        });
+        (result, transactions)
+    }
+
+    #[test]
+    fn block_read_single_transaction() {
+        let mut buf = [0; 8];
+        let (result, transactions) = block_read_from(&[3, 1, 2, 3], false, &mut buf);
+        assert_eq!(result, Ok(3));
+        assert_eq!(transactions, 1);
+        assert_eq!(buf[0..3], [1, 2, 3]);
+        // The idle bus bytes after the frame are not copied.
+        assert_eq!(buf[3..], [0; 5]);
+    }
+
+    #[test]
+    fn block_read_pec() {
+        let mut response = [2, 0xAB, 0xCD, 0];
+        response[3] = pec_update(pec_update(0, &[0x16, 0x23, 0x17]), &response[0..3]);
+        let mut buf = [0; 4];
+        let (result, transactions) = block_read_from(&response, true, &mut buf);
+        assert_eq!(result, Ok(2));
+        assert_eq!(transactions, 1);
+        assert_eq!(buf[0..2], [0xAB, 0xCD]);
+
+        let calculated = response[3];
+        response[3] ^= 1;
+        assert_eq!(
+            block_read_from(&response, true, &mut buf).0,
+            Err(SmbusError::PecMismatch {
+                calculated,
+                received: response[3]
+            })
+        );
+    }
+
+    #[test]
+    fn block_read_clamps_count() {
+        // The byte count is clamped to the largest block, which is copied to a smaller buffer.
+        let mut response = [0x5A; 1 + MAX_BLOCK_LEN];
+        response[0] = u8::MAX;
+        let mut buf = [0; 4];
+        assert_eq!(block_read_from(&response, false, &mut buf).0, Ok(4));
+        assert_eq!(buf, [0x5A; 4]);
+    }
+
+    #[test]
+    fn block_read_error() {
+        let mut buf = [0; 4];
+        assert_eq!(
+            block_read_with(0x0B, 0x23, false, &mut buf, |_, _| Err(Error::NackAddr)),
+            Err(SmbusError::I2c(Error::NackAddr))
+        );
+    }
+
+    #[test]
+    fn clamp_block_count_zero() {
+        assert_eq!(clamp_block_count(0, 8), (0, 0));
+        assert_eq!(clamp_block_count(0, 0), (0, 0));
+    }
+
+    #[test]
+    fn clamp_block_count_max() {
+        assert_eq!(clamp_block_count(32, MAX_BLOCK_LEN), (32, 32));
+        // A smaller caller buffer only limits the copied bytes.
+        assert_eq!(clamp_block_count(32, 8), (32, 8));
+    }
+
+    #[test]
+    fn clamp_block_count_too_large() {
+        assert_eq!(clamp_block_count(33, MAX_BLOCK_LEN), (32, 32));
+        assert_eq!(clamp_block_count(u8::MAX, 64), (32, 32));
+        assert_eq!(clamp_block_count(u8::MAX, 4), (32, 4));
+    }
+}
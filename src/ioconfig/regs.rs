@@ -12,6 +12,7 @@ pub const BASE_ADDR: usize = 0x4001_1000;
 #[bitbybit::bitenum(u3)]
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FilterType {
     SysClk = 0,
     DirectInput = 1,
@@ -24,6 +25,7 @@ pub enum FilterType {
 #[derive(Debug, PartialEq, Eq)]
 #[bitbybit::bitenum(u3, exhaustive = true)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FilterClockSelect {
     SysClk = 0,
     Clk1 = 1,
@@ -38,6 +40,7 @@ pub enum FilterClockSelect {
 #[derive(Debug, PartialEq, Eq)]
 #[bitbybit::bitenum(u1, exhaustive = true)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Pull {
     Up = 0,
     Down = 1,
@@ -46,6 +49,7 @@ pub enum Pull {
 #[derive(Debug, Eq, PartialEq)]
 #[bitbybit::bitenum(u2, exhaustive = true)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionSelect {
     Sel0 = 0b00,
     Sel1 = 0b01,
@@ -128,6 +132,12 @@ static_assertions::const_assert_eq!(core::mem::size_of::<IoConfig>(), 0x1000);
 
 impl IoConfig {
     pub const fn new_mmio() -> MmioIoConfig<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioIoConfig {
+            ptr: crate::test_harness::ioconfig().as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         MmioIoConfig {
             ptr: BASE_ADDR as *mut _,
             phantom: PhantomData,
@@ -0,0 +1,387 @@
+//! # IRQ router peripheral of the Vorago 4x devices.
+//!
+//! The interrupt lines of the Vorago 4x peripherals are wired to fixed NVIC interrupts, so unlike
+//! the IRQSEL peripheral of the Vorago 1x devices, the IRQ router can not remap peripheral
+//! interrupts. It selects:
+//!
+//! - The interrupt which triggers each of the [NUM_DMA_CHANNELS] DMA channels, which is how the
+//!   SPI and UART DMA requests reach the DMA controller, see [route_dma_request]. The trigger
+//!   type of each channel is selected with [set_dma_trigger_type].
+//! - The TIM peripheral which triggers the ADC and the two DAC conversions, see
+//!   [route_adc_trigger] and [route_dac_trigger].
+//!
+//! Additionally, the state of all interrupt lines can be read with [interrupt_line_active] for
+//! debugging purposes.
+//!
+//! All routing functions enable the peripheral clock of the IRQ router. [init] resets all
+//! selections to their reset values, which do not trigger anything.
+use arbitrary_int::{prelude::*, u4, u5, u7};
+use va416xx as pac;
+
+use crate::{
+    PeripheralSelect, enable_peripheral_clock, i2c, reset_peripheral_for_cycles, spi, timer::TimId,
+    uart,
+};
+
+pub mod regs;
+
+use regs::{DMA_SELECT_NONE, IrqRouter, MmioIrqRouter, TRIGGER_SELECT_NONE, TriggerSelect};
+
+/// Number of DMA channels.
+pub const NUM_DMA_CHANNELS: usize = 4;
+
+const SPI_IRQ_BASE: u8 = pac::Interrupt::SPI0_TX as u8;
+const UART_IRQ_BASE: u8 = pac::Interrupt::UART0_TX as u8;
+const I2C_IRQ_BASE: u8 = pac::Interrupt::I2C0_MS as u8;
+const TIM_IRQ_BASE: u8 = pac::Interrupt::TIM0 as u8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("invalid DMA channel {0}, must be below {NUM_DMA_CHANNELS}")]
+pub struct InvalidDmaChannelError(pub u8);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DmaRoutingError {
+    #[error("{0}")]
+    InvalidChannel(#[from] InvalidDmaChannelError),
+    /// [DmaRequestSource::Unknown] can not be routed.
+    #[error("unknown DMA request source {0}")]
+    UnknownSource(u8),
+}
+
+/// Source which triggers a DMA channel.
+///
+/// The DMA channels are triggered by the interrupt signal of the source, so the interrupt of the
+/// source has to be enabled inside the peripheral, for example with the FIFO trigger level
+/// interrupts of the UART and SPI peripherals. The interrupt does not need to be unmasked in the
+/// NVIC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DmaRequestSource {
+    /// The channel is not triggered by the IRQ router. This is the reset value.
+    None,
+    SpiTx(spi::Bank),
+    SpiRx(spi::Bank),
+    UartTx(uart::Bank),
+    UartRx(uart::Bank),
+    I2cMaster(i2c::Bank),
+    I2cSlave(i2c::Bank),
+    Dac0,
+    Dac1,
+    Adc,
+    Tim(TimId),
+    /// Selection value read back from the register which does not belong to a DMA capable
+    /// source, for example because it was written with the PAC directly. This can not be routed.
+    Unknown(u8),
+}
+
+impl DmaRequestSource {
+    /// Value of the DMASEL register, which is the interrupt number of the source. Returns [None]
+    /// for [Self::Unknown].
+    pub const fn selection(&self) -> Option<u7> {
+        match self.encode() {
+            Ok(selection) => Some(selection),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the DMASEL register value, or the raw value of [Self::Unknown] as the error.
+    const fn encode(&self) -> Result<u7, u8> {
+        let irq = match *self {
+            DmaRequestSource::None => return Ok(DMA_SELECT_NONE),
+            DmaRequestSource::SpiTx(bank) => SPI_IRQ_BASE + 2 * bank as u8,
+            DmaRequestSource::SpiRx(bank) => SPI_IRQ_BASE + 2 * bank as u8 + 1,
+            DmaRequestSource::UartTx(bank) => UART_IRQ_BASE + 2 * bank as u8,
+            DmaRequestSource::UartRx(bank) => UART_IRQ_BASE + 2 * bank as u8 + 1,
+            DmaRequestSource::I2cMaster(bank) => I2C_IRQ_BASE + 2 * bank as u8,
+            DmaRequestSource::I2cSlave(bank) => I2C_IRQ_BASE + 2 * bank as u8 + 1,
+            DmaRequestSource::Dac0 => pac::Interrupt::DAC0 as u8,
+            DmaRequestSource::Dac1 => pac::Interrupt::DAC1 as u8,
+            DmaRequestSource::Adc => pac::Interrupt::ADC as u8,
+            DmaRequestSource::Tim(id) => TIM_IRQ_BASE + id.value(),
+            DmaRequestSource::Unknown(value) => return Err(value),
+        };
+        Ok(u7::new(irq))
+    }
+
+    /// Decode the value of the DMASEL register.
+    pub const fn from_selection(selection: u7) -> Self {
+        let value = selection.value();
+        if value == DMA_SELECT_NONE.value() {
+            return DmaRequestSource::None;
+        }
+        if value >= TIM_IRQ_BASE && value < TIM_IRQ_BASE + 24 {
+            return DmaRequestSource::Tim(TimId::new_unchecked((value - TIM_IRQ_BASE) as usize));
+        }
+        if value == pac::Interrupt::DAC0 as u8 {
+            return DmaRequestSource::Dac0;
+        }
+        if value == pac::Interrupt::DAC1 as u8 {
+            return DmaRequestSource::Dac1;
+        }
+        if value == pac::Interrupt::ADC as u8 {
+            return DmaRequestSource::Adc;
+        }
+        if value >= SPI_IRQ_BASE && value < UART_IRQ_BASE {
+            let offset = value - SPI_IRQ_BASE;
+            let bank = match offset / 2 {
+                0 => spi::Bank::Spi0,
+                1 => spi::Bank::Spi1,
+                2 => spi::Bank::Spi2,
+                _ => spi::Bank::Spi3,
+            };
+            return if offset.is_multiple_of(2) {
+                DmaRequestSource::SpiTx(bank)
+            } else {
+                DmaRequestSource::SpiRx(bank)
+            };
+        }
+        if value >= UART_IRQ_BASE && value < I2C_IRQ_BASE {
+            let offset = value - UART_IRQ_BASE;
+            let bank = match offset / 2 {
+                0 => uart::Bank::Uart0,
+                1 => uart::Bank::Uart1,
+                _ => uart::Bank::Uart2,
+            };
+            return if offset.is_multiple_of(2) {
+                DmaRequestSource::UartTx(bank)
+            } else {
+                DmaRequestSource::UartRx(bank)
+            };
+        }
+        if value >= I2C_IRQ_BASE && value < I2C_IRQ_BASE + 6 {
+            let offset = value - I2C_IRQ_BASE;
+            let bank = match offset / 2 {
+                0 => i2c::Bank::I2c0,
+                1 => i2c::Bank::I2c1,
+                _ => i2c::Bank::I2c2,
+            };
+            return if offset.is_multiple_of(2) {
+                DmaRequestSource::I2cMaster(bank)
+            } else {
+                DmaRequestSource::I2cSlave(bank)
+            };
+        }
+        DmaRequestSource::Unknown(value)
+    }
+}
+
+/// Trigger type of a DMA channel, which selects the request input of the DMA controller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DmaTriggerType {
+    /// Single transfer request. This is the reset value.
+    Single = 0,
+    /// Burst transfer request.
+    Burst = 1,
+}
+
+/// DAC which can be triggered by a TIM peripheral.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Dac {
+    Dac0 = 0,
+    Dac1 = 1,
+}
+
+#[inline]
+fn regs() -> MmioIrqRouter<'static> {
+    enable_peripheral_clock(PeripheralSelect::IrqRouter);
+    IrqRouter::new_mmio()
+}
+
+#[inline]
+const fn check_channel(channel: u8) -> Result<usize, InvalidDmaChannelError> {
+    if channel as usize >= NUM_DMA_CHANNELS {
+        return Err(InvalidDmaChannelError(channel));
+    }
+    Ok(channel as usize)
+}
+
+/// Enable the peripheral clock and reset the IRQ router, so no DMA channel, ADC or DAC is
+/// triggered by it.
+pub fn init() {
+    enable_peripheral_clock(PeripheralSelect::IrqRouter);
+    reset_peripheral_for_cycles(PeripheralSelect::IrqRouter, 2);
+}
+
+/// Route the request source to the DMA channel. [DmaRequestSource::None] disables the trigger of
+/// the channel.
+///
+/// Every source can trigger every channel, and multiple channels can be triggered by the same
+/// source.
+pub fn route_dma_request(source: DmaRequestSource, channel: u8) -> Result<(), DmaRoutingError> {
+    let channel = check_channel(channel)?;
+    let selection = source.encode().map_err(DmaRoutingError::UnknownSource)?;
+    // Safety: The channel was checked.
+    unsafe {
+        regs().write_dmasel_unchecked(
+            channel,
+            regs::DmaSelect::new_with_raw_value(0).with_sel(selection),
+        )
+    };
+    Ok(())
+}
+
+/// Read back the request source routed to the DMA channel.
+pub fn dma_request_routing(channel: u8) -> Result<DmaRequestSource, InvalidDmaChannelError> {
+    let channel = check_channel(channel)?;
+    // Safety: The channel was checked.
+    let dmasel = unsafe { regs().read_dmasel_unchecked(channel) };
+    Ok(DmaRequestSource::from_selection(dmasel.sel()))
+}
+
+/// Set the trigger type of the DMA channel.
+pub fn set_dma_trigger_type(
+    channel: u8,
+    trigger_type: DmaTriggerType,
+) -> Result<(), InvalidDmaChannelError> {
+    let channel = check_channel(channel)?;
+    regs().modify_dmattsel(|value| {
+        let mut bits = value.sel().value();
+        bits &= !(1 << channel);
+        bits |= (trigger_type as u8) << channel;
+        value.with_sel(u4::new(bits))
+    });
+    Ok(())
+}
+
+/// Read back the trigger type of the DMA channel.
+pub fn dma_trigger_type(channel: u8) -> Result<DmaTriggerType, InvalidDmaChannelError> {
+    let channel = check_channel(channel)?;
+    if (regs().read_dmattsel().sel().value() >> channel) & 1 == 1 {
+        return Ok(DmaTriggerType::Burst);
+    }
+    Ok(DmaTriggerType::Single)
+}
+
+#[inline]
+fn trigger_select(tim: Option<TimId>) -> TriggerSelect {
+    TriggerSelect::new_with_raw_value(0).with_sel(match tim {
+        Some(id) => u5::new(id.value()),
+        None => TRIGGER_SELECT_NONE,
+    })
+}
+
+#[inline]
+fn trigger_source(select: TriggerSelect) -> Option<TimId> {
+    TimId::new(select.sel().as_usize()).ok()
+}
+
+/// Select the TIM peripheral which triggers the ADC conversions. [None] disables the trigger.
+pub fn route_adc_trigger(tim: Option<TimId>) {
+    regs().write_adcsel(trigger_select(tim));
+}
+
+/// Read back the TIM peripheral which triggers the ADC conversions.
+pub fn adc_trigger() -> Option<TimId> {
+    trigger_source(regs().read_adcsel())
+}
+
+/// Select the TIM peripheral which triggers the DAC conversions. [None] disables the trigger.
+pub fn route_dac_trigger(dac: Dac, tim: Option<TimId>) {
+    // Safety: The DAC enumeration only contains valid indices.
+    unsafe { regs().write_dacsel_unchecked(dac as usize, trigger_select(tim)) };
+}
+
+/// Read back the TIM peripheral which triggers the DAC conversions.
+pub fn dac_trigger(dac: Dac) -> Option<TimId> {
+    // Safety: The DAC enumeration only contains valid indices.
+    trigger_source(unsafe { regs().read_dacsel_unchecked(dac as usize) })
+}
+
+/// Checks whether the interrupt line is currently active, independently of the NVIC
+/// configuration. This is intended for debugging.
+pub fn interrupt_line_active(irq: pac::Interrupt) -> bool {
+    let number = irq as usize;
+    // Safety: The interrupt numbers are below 192, so the index is below 6.
+    let irq_out = unsafe { IrqRouter::new_mmio().read_irq_out_unchecked(number / 32) };
+    (irq_out >> (number % 32)) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dma_source_selection() {
+        assert_eq!(
+            DmaRequestSource::SpiTx(spi::Bank::Spi0).selection(),
+            Some(u7::new(pac::Interrupt::SPI0_TX as u8))
+        );
+        assert_eq!(
+            DmaRequestSource::SpiRx(spi::Bank::Spi3).selection(),
+            Some(u7::new(pac::Interrupt::SPI3_RX as u8))
+        );
+        assert_eq!(
+            DmaRequestSource::UartRx(uart::Bank::Uart2).selection(),
+            Some(u7::new(pac::Interrupt::UART2_RX as u8))
+        );
+        assert_eq!(
+            DmaRequestSource::I2cSlave(i2c::Bank::I2c1).selection(),
+            Some(u7::new(pac::Interrupt::I2C1_SL as u8))
+        );
+        assert_eq!(
+            DmaRequestSource::Tim(TimId::new_unchecked(23)).selection(),
+            Some(u7::new(pac::Interrupt::TIM23 as u8))
+        );
+        assert_eq!(DmaRequestSource::None.selection(), Some(DMA_SELECT_NONE));
+        assert_eq!(DmaRequestSource::Unknown(0).selection(), None);
+    }
+
+    #[test]
+    fn dma_source_roundtrip() {
+        let mut sources = heapless::Vec::<DmaRequestSource, 64>::new();
+        for bank in [
+            spi::Bank::Spi0,
+            spi::Bank::Spi1,
+            spi::Bank::Spi2,
+            spi::Bank::Spi3,
+        ] {
+            sources.push(DmaRequestSource::SpiTx(bank)).unwrap();
+            sources.push(DmaRequestSource::SpiRx(bank)).unwrap();
+        }
+        for bank in [uart::Bank::Uart0, uart::Bank::Uart1, uart::Bank::Uart2] {
+            sources.push(DmaRequestSource::UartTx(bank)).unwrap();
+            sources.push(DmaRequestSource::UartRx(bank)).unwrap();
+        }
+        for bank in [i2c::Bank::I2c0, i2c::Bank::I2c1, i2c::Bank::I2c2] {
+            sources.push(DmaRequestSource::I2cMaster(bank)).unwrap();
+            sources.push(DmaRequestSource::I2cSlave(bank)).unwrap();
+        }
+        for id in 0..24 {
+            sources
+                .push(DmaRequestSource::Tim(TimId::new_unchecked(id)))
+                .unwrap();
+        }
+        for source in [
+            DmaRequestSource::Dac0,
+            DmaRequestSource::Dac1,
+            DmaRequestSource::Adc,
+            DmaRequestSource::None,
+        ] {
+            sources.push(source).unwrap();
+        }
+        for source in sources {
+            assert_eq!(
+                DmaRequestSource::from_selection(source.selection().unwrap()),
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn dma_source_unknown() {
+        for irq in [
+            pac::Interrupt::U0,
+            pac::Interrupt::Ethernet,
+            pac::Interrupt::PORTA0,
+        ] {
+            assert_eq!(
+                DmaRequestSource::from_selection(u7::new(irq as u8)),
+                DmaRequestSource::Unknown(irq as u8)
+            );
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use core::marker::PhantomData;
+
+use arbitrary_int::{prelude::*, u4, u5, u7};
+
+#[cfg_attr(feature = "test-harness", allow(dead_code))]
+const BASE_ADDR: usize = 0x4000_2000;
+
+/// Selection value of the DMASEL registers which does not trigger the DMA channel. This is the
+/// reset value.
+pub const DMA_SELECT_NONE: u7 = u7::MAX;
+/// Selection value of the ADCSEL and DACSEL registers which does not trigger the converter.
+/// This is the reset value.
+pub const TRIGGER_SELECT_NONE: u5 = u5::MAX;
+
+/// DMA trigger source selection of one DMA channel.
+#[bitbybit::bitfield(u32, default = 0x7F, debug, defmt_bitfields(feature = "defmt"))]
+pub struct DmaSelect {
+    /// Interrupt number of the source, or [DMA_SELECT_NONE].
+    #[bits(0..=6, rw)]
+    sel: u7,
+}
+
+/// DMA trigger type selection, one bit for each DMA channel.
+#[bitbybit::bitfield(u32, default = 0x0, debug, defmt_bitfields(feature = "defmt"))]
+pub struct DmaTriggerTypeSelect {
+    #[bits(0..=3, rw)]
+    sel: u4,
+}
+
+/// ADC or DAC trigger source selection.
+#[bitbybit::bitfield(u32, default = 0x1F, debug, defmt_bitfields(feature = "defmt"))]
+pub struct TriggerSelect {
+    /// TIM ID of the source, or [TRIGGER_SELECT_NONE].
+    #[bits(0..=4, rw)]
+    sel: u5,
+}
+
+#[derive(derive_mmio::Mmio)]
+#[mmio(no_ctors)]
+#[repr(C)]
+pub struct IrqRouter {
+    dmasel: [DmaSelect; 4],
+    dmattsel: DmaTriggerTypeSelect,
+    adcsel: TriggerSelect,
+    dacsel: [TriggerSelect; 2],
+    /// Debug view of the interrupt lines, one bit for each interrupt number.
+    #[mmio(PureRead)]
+    irq_out: [u32; 6],
+    _reserved: [u32; 0x3F1],
+    #[mmio(PureRead)]
+    perid: u32,
+}
+
+static_assertions::const_assert_eq!(core::mem::size_of::<IrqRouter>(), 0x1000);
+
+impl IrqRouter {
+    pub fn new_mmio() -> MmioIrqRouter<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioIrqRouter {
+            ptr: crate::test_harness::irq_router().as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
+        MmioIrqRouter {
+            ptr: BASE_ADDR as *mut _,
+            phantom: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! # IRQSEL routing bookkeeping for Vorago 1x devices.
+//!
+//! The interrupts of the Vorago 1x peripherals are routed to one of the 32 NVIC interrupt lines
+//! with the IRQSEL peripheral. The HAL records the interrupt line for each UART, TIM and GPIO pin
+//! whenever it configures the IRQSEL peripheral, so drivers can unmask and mask the correct NVIC
+//! interrupt later without the user passing the interrupt ID again.
+//!
+//! Only routing performed with the [crate::irqsel] module is recorded. Interrupts routed by
+//! writing to the IRQSEL peripheral directly are not known to the registry, but can be read back
+//! with [crate::irqsel::routing_for].
+use portable_atomic::{AtomicU8, Ordering};
+use va108xx as pac;
+
+use crate::{NUM_PORT_A, NUM_PORT_B, Port, gpio::DynPinId, timer::TimId, uart};
+
+const NOT_ROUTED: u8 = u8::MAX;
+
+static UART_IRQS: [AtomicU8; 2] = [const { AtomicU8::new(NOT_ROUTED) }; 2];
+static TIM_IRQS: [AtomicU8; 24] = [const { AtomicU8::new(NOT_ROUTED) }; 24];
+static PORTA_IRQS: [AtomicU8; NUM_PORT_A] = [const { AtomicU8::new(NOT_ROUTED) }; NUM_PORT_A];
+static PORTB_IRQS: [AtomicU8; NUM_PORT_B] = [const { AtomicU8::new(NOT_ROUTED) }; NUM_PORT_B];
+
+const INTERRUPTS: [pac::Interrupt; 32] = [
+    pac::Interrupt::OC0,
+    pac::Interrupt::OC1,
+    pac::Interrupt::OC2,
+    pac::Interrupt::OC3,
+    pac::Interrupt::OC4,
+    pac::Interrupt::OC5,
+    pac::Interrupt::OC6,
+    pac::Interrupt::OC7,
+    pac::Interrupt::OC8,
+    pac::Interrupt::OC9,
+    pac::Interrupt::OC10,
+    pac::Interrupt::OC11,
+    pac::Interrupt::OC12,
+    pac::Interrupt::OC13,
+    pac::Interrupt::OC14,
+    pac::Interrupt::OC15,
+    pac::Interrupt::OC16,
+    pac::Interrupt::OC17,
+    pac::Interrupt::OC18,
+    pac::Interrupt::OC19,
+    pac::Interrupt::OC20,
+    pac::Interrupt::OC21,
+    pac::Interrupt::OC22,
+    pac::Interrupt::OC23,
+    pac::Interrupt::OC24,
+    pac::Interrupt::OC25,
+    pac::Interrupt::OC26,
+    pac::Interrupt::OC27,
+    pac::Interrupt::OC28,
+    pac::Interrupt::OC29,
+    pac::Interrupt::OC30,
+    pac::Interrupt::OC31,
+];
+
+/// Convert an interrupt number to the interrupt. Returns [None] for numbers larger than 31.
+pub const fn interrupt_from_number(number: u8) -> Option<pac::Interrupt> {
+    if number as usize >= INTERRUPTS.len() {
+        return None;
+    }
+    Some(INTERRUPTS[number as usize])
+}
+
+#[inline]
+fn load(slot: &AtomicU8) -> Option<pac::Interrupt> {
+    interrupt_from_number(slot.load(Ordering::Relaxed))
+}
+
+#[inline]
+fn pin_slot(id: DynPinId) -> &'static AtomicU8 {
+    match id.port() {
+        Port::A => &PORTA_IRQS[id.offset()],
+        Port::B => &PORTB_IRQS[id.offset()],
+    }
+}
+
+/// Interrupt the UART was routed to by the HAL.
+#[inline]
+pub fn uart_interrupt(bank: uart::Bank) -> Option<pac::Interrupt> {
+    load(&UART_IRQS[bank as usize])
+}
+
+/// Interrupt the TIM peripheral was routed to by the HAL.
+#[inline]
+pub fn tim_interrupt(id: TimId) -> Option<pac::Interrupt> {
+    load(&TIM_IRQS[id.value() as usize])
+}
+
+/// Interrupt the GPIO pin was routed to by the HAL.
+#[inline]
+pub fn pin_interrupt(id: DynPinId) -> Option<pac::Interrupt> {
+    load(pin_slot(id))
+}
+
+#[inline]
+fn store(slot: &AtomicU8, irq: Option<pac::Interrupt>) {
+    slot.store(irq.map_or(NOT_ROUTED, |irq| irq as u8), Ordering::Relaxed);
+}
+
+/// Record the routing of the UART interrupt, called by [crate::irqsel].
+pub(crate) fn record_uart(bank: uart::Bank, irq: Option<pac::Interrupt>) {
+    store(&UART_IRQS[bank as usize], irq);
+}
+
+/// Record the routing of the TIM interrupt, called by [crate::irqsel].
+pub(crate) fn record_tim(id: TimId, irq: Option<pac::Interrupt>) {
+    store(&TIM_IRQS[id.value() as usize], irq);
+}
+
+/// Record the routing of the GPIO pin interrupt, called by [crate::irqsel].
+pub(crate) fn record_pin(id: DynPinId, irq: Option<pac::Interrupt>) {
+    store(pin_slot(id), irq);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupt_numbers() {
+        assert_eq!(interrupt_from_number(0), Some(pac::Interrupt::OC0));
+        assert_eq!(interrupt_from_number(31), Some(pac::Interrupt::OC31));
+        assert_eq!(interrupt_from_number(32), None);
+        assert_eq!(interrupt_from_number(NOT_ROUTED), None);
+    }
+
+    #[test]
+    fn uart_routing() {
+        assert_eq!(uart_interrupt(uart::Bank::Uart0), None);
+        record_uart(uart::Bank::Uart0, Some(pac::Interrupt::OC17));
+        assert_eq!(
+            uart_interrupt(uart::Bank::Uart0),
+            Some(pac::Interrupt::OC17)
+        );
+        // Routing the UART again replaces the recorded interrupt.
+        record_uart(uart::Bank::Uart0, Some(pac::Interrupt::OC0));
+        assert_eq!(uart_interrupt(uart::Bank::Uart0), Some(pac::Interrupt::OC0));
+        record_uart(uart::Bank::Uart0, None);
+        assert_eq!(uart_interrupt(uart::Bank::Uart0), None);
+    }
+
+    #[test]
+    fn tim_routing() {
+        let id = TimId::new_unchecked(23);
+        assert_eq!(tim_interrupt(id), None);
+        record_tim(id, Some(pac::Interrupt::OC31));
+        assert_eq!(tim_interrupt(id), Some(pac::Interrupt::OC31));
+        // Other TIMs are not affected.
+        assert_eq!(tim_interrupt(TimId::new_unchecked(22)), None);
+        record_tim(id, None);
+        assert_eq!(tim_interrupt(id), None);
+    }
+
+    #[test]
+    fn pin_routing() {
+        let pin_a = DynPinId::new(Port::A, 31).unwrap();
+        let pin_b = DynPinId::new(Port::B, 23).unwrap();
+        record_pin(pin_a, Some(pac::Interrupt::OC12));
+        record_pin(pin_b, Some(pac::Interrupt::OC13));
+        assert_eq!(pin_interrupt(pin_a), Some(pac::Interrupt::OC12));
+        assert_eq!(pin_interrupt(pin_b), Some(pac::Interrupt::OC13));
+        // Other pins are not affected.
+        assert_eq!(pin_interrupt(DynPinId::new(Port::B, 22).unwrap()), None);
+        record_pin(pin_a, None);
+        assert_eq!(pin_interrupt(pin_a), None);
+        assert_eq!(pin_interrupt(pin_b), Some(pac::Interrupt::OC13));
+        record_pin(pin_b, None);
+    }
+}
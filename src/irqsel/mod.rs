@@ -0,0 +1,251 @@
+//! # IRQSEL peripheral of the Vorago 1x devices.
+//!
+//! The interrupts of the Vorago 1x peripherals are not wired to fixed NVIC interrupts. Instead,
+//! the IRQSEL peripheral has one selection register for every interrupt source, which routes the
+//! source to one of the 32 NVIC interrupt lines OC0 to OC31. Multiple sources can be routed to
+//! the same interrupt line.
+//!
+//! The routing functions of this module are used by all drivers of the HAL, and they record the
+//! routing in the [crate::irq_routing] registry, so the routed interrupt can be retrieved later,
+//! for example with [crate::uart::Bank::routed_interrupt]. All routing functions enable the
+//! peripheral clock of the IRQSEL peripheral.
+//!
+//! The routing can be read back with [routing_for] and [routing_table], and [find_conflicts]
+//! finds interrupt lines which are shared by different peripherals, which is usually a
+//! configuration error.
+use va108xx as pac;
+
+use crate::{
+    NUM_PORT_A, NUM_PORT_B, PeripheralSelect, Port, enable_peripheral_clock, gpio::DynPinId,
+    gpio::ll::RoutedPins, irq_routing, timer::TimId, uart,
+};
+
+pub mod regs;
+
+use regs::{IRQSEL_DISABLED, Irqsel, MmioIrqsel};
+
+/// Number of NVIC interrupt lines an interrupt source can be routed to.
+pub const NUM_INTERRUPT_LINES: usize = 32;
+
+#[inline]
+fn regs() -> MmioIrqsel<'static> {
+    enable_peripheral_clock(PeripheralSelect::Irqsel);
+    Irqsel::new_mmio()
+}
+
+/// Route the UART interrupt to the given interrupt line.
+pub fn route_uart(bank: uart::Bank, irq: pac::Interrupt) {
+    // Safety: All UART banks have a selection register.
+    unsafe { regs().write_uart_unchecked(bank as usize, irq as u32) };
+    irq_routing::record_uart(bank, Some(irq));
+}
+
+/// Disable the routing of the UART interrupt.
+pub fn unroute_uart(bank: uart::Bank) {
+    // Safety: All UART banks have a selection register.
+    unsafe { regs().write_uart_unchecked(bank as usize, IRQSEL_DISABLED) };
+    irq_routing::record_uart(bank, None);
+}
+
+/// Route the TIM interrupt to the given interrupt line.
+pub fn route_tim(id: TimId, irq: pac::Interrupt) {
+    // Safety: All TIM IDs have a selection register.
+    unsafe { regs().write_tim_unchecked(id.value() as usize, irq as u32) };
+    irq_routing::record_tim(id, Some(irq));
+}
+
+/// Disable the routing of the TIM interrupt.
+pub fn unroute_tim(id: TimId) {
+    // Safety: All TIM IDs have a selection register.
+    unsafe { regs().write_tim_unchecked(id.value() as usize, IRQSEL_DISABLED) };
+    irq_routing::record_tim(id, None);
+}
+
+/// Route the GPIO pin interrupt to the given interrupt line.
+pub fn route_gpio(id: DynPinId, irq: pac::Interrupt) {
+    write_pin_selection(id, irq as u32);
+    irq_routing::record_pin(id, Some(irq));
+}
+
+/// Disable the routing of the GPIO pin interrupt.
+pub fn unroute_gpio(id: DynPinId) {
+    write_pin_selection(id, IRQSEL_DISABLED);
+    irq_routing::record_pin(id, None);
+}
+
+fn write_pin_selection(id: DynPinId, value: u32) {
+    let mut regs = regs();
+    // Safety: The offset of a pin ID is always valid for its port.
+    unsafe {
+        match id.port() {
+            Port::A => regs.write_porta_unchecked(id.offset(), value),
+            Port::B => regs.write_portb_unchecked(id.offset(), value),
+        }
+    }
+}
+
+/// All interrupt sources which are routed to one interrupt line.
+///
+/// Bit N of each mask corresponds to the peripheral or pin with the index N.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RoutingInfo {
+    pub pins: RoutedPins,
+    pub tims: u32,
+    pub uarts: u8,
+    pub spis: u8,
+    pub i2c_masters: u8,
+    pub i2c_slaves: u8,
+    /// EDAC error interrupts. Bit 0: RAM single bit error, bit 1: RAM multi bit error, bit 2:
+    /// ROM single bit error, bit 3: ROM multi bit error.
+    pub edac: u8,
+    /// TXEV event of the processor.
+    pub txev: bool,
+}
+
+impl RoutingInfo {
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.num_sources() == 0
+    }
+
+    /// Number of different peripherals routed to the interrupt line. All GPIO pins count as one
+    /// peripheral, because sharing an interrupt line between pins is the normal use case, see
+    /// [crate::gpio::pins_routed_to_interrupt].
+    pub const fn num_sources(&self) -> u32 {
+        (!self.pins.is_empty()) as u32
+            + self.tims.count_ones()
+            + self.uarts.count_ones()
+            + self.spis.count_ones()
+            + self.i2c_masters.count_ones()
+            + self.i2c_slaves.count_ones()
+            + self.edac.count_ones()
+            + self.txev as u32
+    }
+
+    fn add(&mut self, source: Source) {
+        match source {
+            Source::PortA(idx) => self.pins.port_a |= 1 << idx,
+            Source::PortB(idx) => self.pins.port_b |= 1 << idx,
+            Source::Tim(idx) => self.tims |= 1 << idx,
+            Source::Uart(idx) => self.uarts |= 1 << idx,
+            Source::Spi(idx) => self.spis |= 1 << idx,
+            Source::I2cMaster(idx) => self.i2c_masters |= 1 << idx,
+            Source::I2cSlave(idx) => self.i2c_slaves |= 1 << idx,
+            Source::Edac(idx) => self.edac |= 1 << idx,
+            Source::Txev => self.txev = true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Source {
+    PortA(usize),
+    PortB(usize),
+    Tim(usize),
+    Uart(usize),
+    Spi(usize),
+    I2cMaster(usize),
+    I2cSlave(usize),
+    Edac(usize),
+    Txev,
+}
+
+/// Calls the given function with the selection register value of every interrupt source.
+fn for_each_selection(mut f: impl FnMut(u32, Source)) {
+    let regs = regs();
+    // Safety: All indices are within the bounds of the register arrays.
+    unsafe {
+        for idx in 0..NUM_PORT_A {
+            f(regs.read_porta_unchecked(idx), Source::PortA(idx));
+        }
+        for idx in 0..NUM_PORT_B {
+            f(regs.read_portb_unchecked(idx), Source::PortB(idx));
+        }
+        for idx in 0..24 {
+            f(regs.read_tim_unchecked(idx), Source::Tim(idx));
+        }
+        for idx in 0..2 {
+            f(regs.read_uart_unchecked(idx), Source::Uart(idx));
+        }
+        for idx in 0..3 {
+            f(regs.read_spi_unchecked(idx), Source::Spi(idx));
+        }
+        for idx in 0..2 {
+            f(regs.read_i2c_ms_unchecked(idx), Source::I2cMaster(idx));
+            f(regs.read_i2c_sl_unchecked(idx), Source::I2cSlave(idx));
+        }
+    }
+    f(regs.read_int_ram_sbe(), Source::Edac(0));
+    f(regs.read_int_ram_mbe(), Source::Edac(1));
+    f(regs.read_int_rom_sbe(), Source::Edac(2));
+    f(regs.read_int_rom_mbe(), Source::Edac(3));
+    f(regs.read_txev(), Source::Txev);
+}
+
+/// Reads back all interrupt sources which are routed to the given interrupt line.
+///
+/// Unlike the [crate::irq_routing] registry, this also includes routing which was not performed
+/// by the HAL.
+pub fn routing_for(irq: pac::Interrupt) -> RoutingInfo {
+    let mut info = RoutingInfo::default();
+    for_each_selection(|selection, source| {
+        if selection == irq as u32 {
+            info.add(source);
+        }
+    });
+    info
+}
+
+/// Reads back the routing of all interrupt lines with a single pass over the selection
+/// registers. The index of the table is the interrupt number.
+pub fn routing_table() -> [RoutingInfo; NUM_INTERRUPT_LINES] {
+    let mut table = [RoutingInfo::default(); NUM_INTERRUPT_LINES];
+    for_each_selection(|selection, source| {
+        if let Some(info) = table.get_mut(selection as usize) {
+            info.add(source);
+        }
+    });
+    table
+}
+
+/// Interrupt lines which are shared by more than one peripheral, see
+/// [RoutingInfo::num_sources]. Bit N corresponds to the interrupt number N.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Conflicts(pub u32);
+
+impl Conflicts {
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn contains(&self, irq: pac::Interrupt) -> bool {
+        (self.0 >> irq as u32) & 1 == 1
+    }
+
+    /// Iterator over all shared interrupt lines.
+    pub fn iter(&self) -> impl Iterator<Item = pac::Interrupt> {
+        let mask = self.0;
+        (0..NUM_INTERRUPT_LINES as u8)
+            .filter(move |number| (mask >> number) & 1 == 1)
+            .filter_map(irq_routing::interrupt_from_number)
+    }
+}
+
+/// Finds all interrupt lines which are shared by more than one peripheral.
+///
+/// Sharing an interrupt line is possible, but the interrupt handler then has to serve all
+/// peripherals, so an unintended conflict usually leads to lost interrupts. GPIO pins sharing a
+/// line with each other are not reported.
+pub fn find_conflicts() -> Conflicts {
+    let mut conflicts = 0;
+    for (number, info) in routing_table().iter().enumerate() {
+        if info.num_sources() > 1 {
+            conflicts |= 1 << number;
+        }
+    }
+    Conflicts(conflicts)
+}
@@ -0,0 +1,62 @@
+use core::marker::PhantomData;
+
+#[cfg_attr(feature = "test-harness", allow(dead_code))]
+const BASE_ADDR: usize = 0x4000_1000;
+
+/// Value of a selection register which does not route the interrupt. This is the reset value.
+pub const IRQSEL_DISABLED: u32 = u32::MAX;
+
+#[derive(derive_mmio::Mmio)]
+#[mmio(no_ctors)]
+#[repr(C)]
+pub struct Irqsel {
+    /// Interrupt selection of the PORTA pins.
+    porta: [u32; 32],
+    /// Interrupt selection of the PORTB pins.
+    portb: [u32; 32],
+    tim: [u32; 32],
+    uart: [u32; 4],
+    spi: [u32; 4],
+    i2c_ms: [u32; 4],
+    i2c_sl: [u32; 4],
+    int_ram_sbe: u32,
+    int_ram_mbe: u32,
+    int_rom_sbe: u32,
+    int_rom_mbe: u32,
+    txev: u32,
+    _reserved0: [u32; 0x18B],
+    /// Status of the interrupt lines, one register for each interrupt number.
+    #[mmio(PureRead)]
+    irqs: [u32; 32],
+    _reserved1: [u32; 0x1A],
+    #[mmio(PureRead)]
+    edbgrq: u32,
+    #[mmio(PureRead)]
+    mereset: u32,
+    #[mmio(PureRead)]
+    watchdog: u32,
+    #[mmio(PureRead)]
+    rxev: u32,
+    #[mmio(PureRead)]
+    nmi: u32,
+    _reserved2: [u32; 0x1C0],
+    #[mmio(PureRead)]
+    perid: u32,
+}
+
+static_assertions::const_assert_eq!(core::mem::size_of::<Irqsel>(), 0x1000);
+
+impl Irqsel {
+    pub fn new_mmio() -> MmioIrqsel<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioIrqsel {
+            ptr: crate::test_harness::irqsel().as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
+        MmioIrqsel {
+            ptr: BASE_ADDR as *mut _,
+            phantom: PhantomData,
+        }
+    }
+}
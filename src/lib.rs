@@ -1,28 +1,56 @@
 //! Shared HAL code for Vorago VA108xx and VA416xx microcontrollers.
 #![no_std]
+pub mod bringup;
+#[cfg(feature = "bus-trace")]
+pub mod bus_trace;
+pub mod busy_wait;
 #[cfg(feature = "vor4x")]
 pub mod clock;
+pub mod edac;
+#[cfg(feature = "eh02-compat")]
+pub mod eh02;
 pub mod embassy;
 pub mod gpio;
 pub mod i2c;
 pub mod ioconfig;
+#[cfg(feature = "vor4x")]
+pub mod irq_router;
+#[cfg(feature = "vor1x")]
+pub mod irq_routing;
+#[cfg(feature = "vor1x")]
+pub mod irqsel;
+pub mod ownership;
 pub mod pins;
+pub mod power;
+pub mod prelude;
+pub mod pulse;
 pub mod pwm;
+#[cfg(feature = "selftest")]
+pub mod selftest;
 pub mod spi;
 pub mod sysconfig;
+#[cfg(feature = "test-harness")]
+pub mod test_harness;
 pub mod time;
 pub mod timer;
 pub mod uart;
 
 pub use sysconfig::{
-    assert_peripheral_reset, deassert_peripheral_reset, disable_peripheral_clock,
-    enable_peripheral_clock, reset_peripheral_for_cycles,
+    PeridMismatch, TimPeridMismatch, assert_peripheral_reset, deassert_peripheral_reset,
+    disable_peripheral_clock, enable_peripheral_clock, ensure_peripheral_clocks,
+    is_peripheral_clock_enabled, reset_peripheral_for_cycles, verify_peripheral_ids,
+    verify_tim_peripheral_ids,
 };
 
 #[cfg(not(feature = "_family-selected"))]
 compile_error!("no Vorago CPU family was select. Choices: vor1x or vor4x");
 
 pub use ioconfig::regs::FunctionSelect;
+
+/// Alias for the UART configuration [uart::Config].
+pub type UartConfig = uart::Config;
+/// Alias for the I2C master configuration [i2c::MasterConfig].
+pub type I2cMasterConfig = i2c::MasterConfig;
 #[cfg(feature = "vor1x")]
 use va108xx as pac;
 #[cfg(feature = "vor4x")]
@@ -43,6 +71,9 @@ pub enum PeripheralSelect {
     I2c1 = 17,
     Irqsel = 21,
     IoConfig = 22,
+    /// Utility peripheral, which contains the syndrome (EDAC) encoding and checking logic used
+    /// for the memory scrubbing. It does not contain a CRC engine, so CRCs have to be calculated
+    /// in software.
     Utility = 23,
     Gpio = 24,
 }
@@ -105,6 +136,7 @@ cfg_if::cfg_if! {
 /// GPIO port enumeration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Port {
     A = 0,
     B = 1,
@@ -132,12 +164,45 @@ impl Port {
         }
     }
 
+    /// Peripheral clock of the GPIO port.
+    pub const fn periph_sel(&self) -> PeripheralSelect {
+        match self {
+            Port::A => PeripheralSelect::PortA,
+            Port::B => PeripheralSelect::PortB,
+            #[cfg(feature = "vor4x")]
+            Port::C => PeripheralSelect::PortC,
+            #[cfg(feature = "vor4x")]
+            Port::D => PeripheralSelect::PortD,
+            #[cfg(feature = "vor4x")]
+            Port::E => PeripheralSelect::PortE,
+            #[cfg(feature = "vor4x")]
+            Port::F => PeripheralSelect::PortF,
+            #[cfg(feature = "vor4x")]
+            Port::G => PeripheralSelect::PortG,
+        }
+    }
+
     /// Unsafely steal the GPIO peripheral block for the given port.
     ///
+    /// In debug builds, this panics if the port is owned by the pins created with a safe
+    /// constructor, see [ownership].
+    ///
     /// # Safety
     ///
     /// Circumvents ownership and safety guarantees by the HAL.
+    #[track_caller]
     pub unsafe fn steal_gpio(&self) -> gpio::regs::MmioGpio<'static> {
+        ownership::check_steal(ownership::RegisterBlock::Gpio(*self));
+        gpio::regs::Gpio::new_mmio(*self)
+    }
+
+    /// Same as [Self::steal_gpio], but without the ownership check. [gpio::GpioIrqView] is a
+    /// safe alternative for interrupt handlers.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL.
+    pub unsafe fn force_steal_gpio(&self) -> gpio::regs::MmioGpio<'static> {
         gpio::regs::Gpio::new_mmio(*self)
     }
 }
@@ -196,6 +261,59 @@ pub fn disable_nvic_interrupt(irq: pac::Interrupt) {
     cortex_m::peripheral::NVIC::mask(irq);
 }
 
+/// Checks whether a specific interrupt is pending in the NVIC peripheral.
+#[inline]
+pub fn is_interrupt_pending(irq: pac::Interrupt) -> bool {
+    cortex_m::peripheral::NVIC::is_pending(irq)
+}
+
+/// Clear the pending state of a specific interrupt in the NVIC peripheral.
+#[inline]
+pub fn clear_pending(irq: pac::Interrupt) {
+    cortex_m::peripheral::NVIC::unpend(irq);
+}
+
+/// Set a specific interrupt pending in the NVIC peripheral.
+///
+/// If the interrupt is enabled in the NVIC, the interrupt handler is executed as if the
+/// peripheral had requested the interrupt. This can be used to test interrupt handlers on the
+/// hardware. The peripheral interrupt status is not changed, so the interrupt handler needs to
+/// cope with an interrupt without any active interrupt source.
+///
+/// ## Example
+///
+/// Software-triggered test of a timer interrupt handler on a Vorago 1x device:
+///
+/// ```rust,ignore
+/// use vorago_shared_hal as hal;
+///
+/// // The timer interrupt was routed to OC7 with the InterruptConfig passed to the timer.
+/// let irq = timer.id().routed_interrupt().unwrap();
+/// unsafe { hal::enable_nvic_interrupt(irq) };
+/// hal::set_pending(irq);
+/// // The interrupt handler was executed and the pending state was cleared by the hardware.
+/// assert!(!timer.irq_pending());
+///
+/// // With the interrupt masked, the pending state can be checked and cleared manually.
+/// hal::disable_nvic_interrupt(irq);
+/// hal::set_pending(irq);
+/// assert!(hal::is_interrupt_pending(irq));
+/// hal::clear_pending(irq);
+/// ```
+#[inline]
+pub fn set_pending(irq: pac::Interrupt) {
+    cortex_m::peripheral::NVIC::pend(irq);
+}
+
+#[doc(hidden)]
+pub mod __macro_support {
+    pub use critical_section;
+    #[cfg(feature = "vor1x")]
+    pub use va108xx as pac;
+    #[cfg(feature = "vor4x")]
+    pub use va416xx as pac;
+}
+
 #[allow(dead_code)]
 pub(crate) mod sealed {
     pub trait Sealed {}
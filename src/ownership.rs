@@ -0,0 +1,114 @@
+//! # Debug ownership tracking
+//!
+//! The safe driver constructors consume the peripheral singletons, so a register block can only
+//! be aliased with the unsafe steal functions, for example [crate::uart::Bank::steal_regs] or
+//! [crate::uart::Rx::steal]. To make these functions easier to audit, debug builds track which
+//! register blocks are owned by a driver created with a safe constructor. The steal functions
+//! panic with a debug assertion if the register block is owned.
+//!
+//! Interrupt handlers usually only need the interrupt status. The minimal views
+//! [crate::uart::UartIrqView], [crate::timer::TimIrqView] and [crate::gpio::GpioIrqView] can be
+//! created safely while a driver owns the register block. For all other cases where aliasing
+//! a driver is intended, the `force_steal` variants like [crate::uart::Bank::force_steal_regs]
+//! skip the check.
+//!
+//! The drivers do not give back the peripheral singletons, so a register block stays marked as
+//! owned after its driver was dropped. Nothing is tracked in release builds.
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::{Port, i2c, spi, timer::TimId, uart};
+
+/// Register block which is tracked by the ownership registry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RegisterBlock {
+    Uart(uart::Bank),
+    Spi(spi::Bank),
+    I2c(i2c::Bank),
+    Tim(TimId),
+    Gpio(Port),
+}
+
+static UART: AtomicU32 = AtomicU32::new(0);
+static SPI: AtomicU32 = AtomicU32::new(0);
+static I2C: AtomicU32 = AtomicU32::new(0);
+static TIM: AtomicU32 = AtomicU32::new(0);
+static GPIO: AtomicU32 = AtomicU32::new(0);
+
+impl RegisterBlock {
+    fn flag(&self) -> (&'static AtomicU32, u32) {
+        match self {
+            RegisterBlock::Uart(bank) => (&UART, 1 << *bank as u32),
+            RegisterBlock::Spi(bank) => (&SPI, 1 << *bank as u32),
+            RegisterBlock::I2c(bank) => (&I2C, 1 << *bank as u32),
+            RegisterBlock::Tim(id) => (&TIM, 1 << id.value()),
+            RegisterBlock::Gpio(port) => (&GPIO, 1 << *port as u32),
+        }
+    }
+}
+
+/// Returns whether the register block is owned by a driver which was created with a safe
+/// constructor. Always returns [false] in release builds.
+pub fn is_owned(block: RegisterBlock) -> bool {
+    if !cfg!(debug_assertions) {
+        return false;
+    }
+    let (flags, mask) = block.flag();
+    flags.load(Ordering::Relaxed) & mask != 0
+}
+
+/// Called by the safe constructors.
+#[inline]
+pub(crate) fn mark_owned(block: RegisterBlock) {
+    if cfg!(debug_assertions) {
+        let (flags, mask) = block.flag();
+        flags.fetch_or(mask, Ordering::Relaxed);
+    }
+}
+
+/// Called by the steal functions.
+#[inline]
+#[track_caller]
+pub(crate) fn check_steal(block: RegisterBlock) {
+    debug_assert!(
+        !is_owned(block),
+        "{block:?} is owned by a driver, use a view or the force steal variant to alias it"
+    );
+}
+
+/// Clears all ownership flags.
+#[cfg(feature = "test-harness")]
+pub(crate) fn reset() {
+    for flags in [&UART, &SPI, &I2C, &TIM, &GPIO] {
+        flags.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owned_flags_are_independent() {
+        let tim = RegisterBlock::Tim(TimId::new_unchecked(23));
+        let uart = RegisterBlock::Uart(uart::Bank::Uart1);
+        assert!(!is_owned(tim));
+        check_steal(tim);
+        mark_owned(tim);
+        assert!(is_owned(tim));
+        assert!(!is_owned(RegisterBlock::Tim(TimId::new_unchecked(22))));
+        assert!(!is_owned(uart));
+        mark_owned(uart);
+        assert!(is_owned(uart));
+        assert!(!is_owned(RegisterBlock::Uart(uart::Bank::Uart0)));
+        assert!(!is_owned(RegisterBlock::I2c(i2c::Bank::I2c1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "is owned by a driver")]
+    fn steal_of_owned_block_panics() {
+        let block = RegisterBlock::Spi(spi::Bank::Spi2);
+        mark_owned(block);
+        check_steal(block);
+    }
+}
@@ -12,12 +12,31 @@ use va416xx as pac;
 /// Trait implemented by data structures associated with pin identification.
 pub trait PinId {
     const ID: crate::gpio::ll::DynPinId;
+    /// [InterruptCapable] for pins which can generate GPIO interrupts, [NoInterrupts] otherwise.
+    type Interrupts;
 }
 
 pub trait AnyPin: Sealed {
     const ID: DynPinId;
 }
 
+/// Marker trait for all pins which can generate GPIO interrupts.
+///
+/// This is implemented for all pins except the port G pins of the VA416xx family. It allows
+/// checking at compile time that a pin supports interrupts, instead of relying on the
+/// [crate::gpio::ll::DynPinId] based runtime checks.
+pub trait InterruptCapablePin: PinId<Interrupts = InterruptCapable> {}
+
+/// Interrupt support marker of [PinId::Interrupts] and [crate::gpio::Input] for pins which can
+/// generate GPIO interrupts.
+#[derive(Debug)]
+pub enum InterruptCapable {}
+
+/// Interrupt support marker of [PinId::Interrupts] and [crate::gpio::Input] for pins which can
+/// not generate GPIO interrupts, which are the port G pins of the VA416xx family.
+#[derive(Debug)]
+pub enum NoInterrupts {}
+
 /// Primary Pin structure for the physical pins exposed by Vorago MCUs.
 ///
 /// This pin structure is only used for resource management and does not do anything on its
@@ -52,6 +71,13 @@ impl<I: PinId> Pin<I> {
 
 macro_rules! pin_id {
     ($Id:ident, $Port:path, $num:literal) => {
+        pin_id!(@id $Id, $Port, $num, InterruptCapable);
+        impl InterruptCapablePin for $Id {}
+    };
+    ($Id:ident, $Port:path, $num:literal, no_interrupts) => {
+        pin_id!(@id $Id, $Port, $num, NoInterrupts);
+    };
+    (@id $Id:ident, $Port:path, $num:literal, $Interrupts:ty) => {
         // Need paste macro to use ident in doc attribute
         paste::paste! {
             #[doc = "Pin ID representing pin " $Id]
@@ -61,6 +87,7 @@ macro_rules! pin_id {
             impl $crate::sealed::Sealed for $Id {}
             impl PinId for $Id {
                 const ID: DynPinId = DynPinId::new_unchecked($Port, $num);
+                type Interrupts = $Interrupts;
             }
         }
     };
@@ -250,14 +277,14 @@ cfg_if::cfg_if! {
         pin_id!(Pf14, Port::F, 14);
         pin_id!(Pf15, Port::F, 15);
 
-        pin_id!(Pg0, Port::G, 0);
-        pin_id!(Pg1, Port::G, 1);
-        pin_id!(Pg2, Port::G, 2);
-        pin_id!(Pg3, Port::G, 3);
-        pin_id!(Pg4, Port::G, 4);
-        pin_id!(Pg5, Port::G, 5);
-        pin_id!(Pg6, Port::G, 6);
-        pin_id!(Pg7, Port::G, 7);
+        pin_id!(Pg0, Port::G, 0, no_interrupts);
+        pin_id!(Pg1, Port::G, 1, no_interrupts);
+        pin_id!(Pg2, Port::G, 2, no_interrupts);
+        pin_id!(Pg3, Port::G, 3, no_interrupts);
+        pin_id!(Pg4, Port::G, 4, no_interrupts);
+        pin_id!(Pg5, Port::G, 5, no_interrupts);
+        pin_id!(Pg6, Port::G, 6, no_interrupts);
+        pin_id!(Pg7, Port::G, 7, no_interrupts);
     }
 }
 
@@ -316,6 +343,7 @@ pub struct PinsA {
 impl PinsA {
     pub fn new(_port_a: pac::Porta) -> Self {
         let syscfg = unsafe { pac::Sysconfig::steal() };
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::A));
         reset_peripheral_for_cycles(PeripheralSelect::PortA, 2);
         syscfg.peripheral_clk_enable().modify(|_, w| {
             w.porta().set_bit();
@@ -422,6 +450,7 @@ pub struct PinsB {
 impl PinsB {
     pub fn new(_port_b: pac::Portb) -> Self {
         let syscfg = unsafe { pac::Sysconfig::steal() };
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::B));
         reset_peripheral_for_cycles(PeripheralSelect::PortB, 2);
         syscfg.peripheral_clk_enable().modify(|_, w| {
             w.portb().set_bit();
@@ -500,6 +529,7 @@ cfg_if::cfg_if! {
         impl PinsC {
             pub fn new(_port_c: pac::Portc) -> Self {
                 let syscfg = unsafe { pac::Sysconfig::steal() };
+                crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::C));
                 reset_peripheral_for_cycles(PeripheralSelect::PortC, 2);
                 syscfg.peripheral_clk_enable().modify(|_, w| {
                     w.portc().set_bit();
@@ -561,6 +591,7 @@ cfg_if::cfg_if! {
         impl PinsD {
             pub fn new(_port_d: pac::Portd) -> Self {
                 let syscfg = unsafe { pac::Sysconfig::steal() };
+                crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::D));
                 reset_peripheral_for_cycles(PeripheralSelect::PortD, 2);
                 syscfg.peripheral_clk_enable().modify(|_, w| {
                     w.portd().set_bit();
@@ -622,6 +653,7 @@ cfg_if::cfg_if! {
         impl PinsE {
             pub fn new(_port_e: pac::Porte) -> Self {
                 let syscfg = unsafe { pac::Sysconfig::steal() };
+                crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::E));
                 reset_peripheral_for_cycles(PeripheralSelect::PortE, 2);
                 syscfg.peripheral_clk_enable().modify(|_, w| {
                     w.porte().set_bit();
@@ -681,6 +713,7 @@ cfg_if::cfg_if! {
         impl PinsF {
             pub fn new(_port_f: pac::Portf) -> Self {
                 let syscfg = unsafe { pac::Sysconfig::steal() };
+                crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::F));
                 reset_peripheral_for_cycles(PeripheralSelect::PortF, 2);
                 syscfg.peripheral_clk_enable().modify(|_, w| {
                     w.portf().set_bit();
@@ -730,6 +763,7 @@ cfg_if::cfg_if! {
         impl PinsG {
             pub fn new(_port_g: pac::Portg) -> Self {
                 let syscfg = unsafe { pac::Sysconfig::steal() };
+                crate::ownership::mark_owned(crate::ownership::RegisterBlock::Gpio(Port::G));
                 reset_peripheral_for_cycles(PeripheralSelect::PortG, 2);
                 syscfg.peripheral_clk_enable().modify(|_, w| {
                     w.portg().set_bit();
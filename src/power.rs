@@ -0,0 +1,138 @@
+//! # Sleep preparation
+//!
+//! Before the CPU enters a WFI based sleep with most clocks gated, the peripherals managed by
+//! the HAL should be quiesced in a defined order, and restored after the wake-up. Drivers which
+//! support this implement [SleepParticipant]:
+//!
+//! - [crate::uart::Tx] waits until the transmission including the shift register is complete
+//!   and disables the transmitter. [crate::uart::Rx] disables the receiver, so noise on the line
+//!   does not latch reception errors. [crate::uart::Uart] does both.
+//! - [crate::spi::Spi] waits until the current transfer is complete, disables the interrupts and
+//!   the peripheral, which deasserts the hardware chip select.
+//! - [crate::i2c::I2cMaster], [crate::timer::CountdownTimer] and [crate::pwm::PwmPin] are
+//!   disabled.
+//! - [crate::gpio::Output] drives the level set with [crate::gpio::Output::set_sleep_level],
+//!   for example the inactive level of a chip select which is controlled by software.
+//!
+//! Each participant records the state it changed and restores exactly this state on resume, so
+//! a disabled driver stays disabled. A timer which is used as the wake-up source should simply
+//! not be registered.
+//!
+//! [SleepGuard::enter] prepares all participants in the passed order. They are resumed in the
+//! reverse order when the guard is dropped or [SleepGuard::resume] is called.
+//!
+//! ```ignore
+//! let guard = SleepGuard::enter(&mut [&mut uart, &mut spi, &mut cs_pin]);
+//! guard.wait_for_interrupt();
+//! guard.resume();
+//! ```
+
+/// Driver which can be quiesced before sleep and restored afterwards.
+pub trait SleepParticipant {
+    /// Quiesce the driver and record the state which is restored by [Self::resume].
+    ///
+    /// Calling this again before [Self::resume] has no effect.
+    fn prepare_sleep(&mut self);
+
+    /// Restore the state recorded by [Self::prepare_sleep].
+    ///
+    /// This has no effect if the driver was not prepared.
+    fn resume(&mut self);
+}
+
+/// Token for the prepared sleep state of a set of [SleepParticipant]s.
+///
+/// The participants are resumed in the reverse order of their preparation when the guard is
+/// dropped.
+pub struct SleepGuard<'a, 'p> {
+    participants: &'a mut [&'p mut dyn SleepParticipant],
+}
+
+impl<'a, 'p> SleepGuard<'a, 'p> {
+    /// Prepare all participants for sleep in the passed order.
+    pub fn enter(participants: &'a mut [&'p mut dyn SleepParticipant]) -> Self {
+        for participant in participants.iter_mut() {
+            participant.prepare_sleep();
+        }
+        Self { participants }
+    }
+
+    /// Wait for an interrupt with the WFI instruction.
+    #[inline]
+    pub fn wait_for_interrupt(&self) {
+        cortex_m::asm::wfi();
+    }
+
+    /// Resume all participants in the reverse order. This is the same as dropping the guard.
+    #[inline]
+    pub fn resume(self) {}
+}
+
+impl Drop for SleepGuard<'_, '_> {
+    fn drop(&mut self) {
+        for participant in self.participants.iter_mut().rev() {
+            participant.resume();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+
+    struct Participant<'log> {
+        id: u8,
+        prepared: bool,
+        log: &'log RefCell<heapless::Vec<(u8, bool), 8>>,
+    }
+
+    impl SleepParticipant for Participant<'_> {
+        fn prepare_sleep(&mut self) {
+            self.prepared = true;
+            self.log.borrow_mut().push((self.id, true)).unwrap();
+        }
+
+        fn resume(&mut self) {
+            self.prepared = false;
+            self.log.borrow_mut().push((self.id, false)).unwrap();
+        }
+    }
+
+    #[test]
+    fn prepare_in_order_resume_in_reverse() {
+        let log = RefCell::new(heapless::Vec::new());
+        let mut first = Participant {
+            id: 1,
+            prepared: false,
+            log: &log,
+        };
+        let mut second = Participant {
+            id: 2,
+            prepared: false,
+            log: &log,
+        };
+        {
+            let participants: &mut [&mut dyn SleepParticipant] = &mut [&mut first, &mut second];
+            let guard = SleepGuard::enter(participants);
+            assert_eq!(log.borrow().as_slice(), &[(1, true), (2, true)]);
+            guard.resume();
+        }
+        assert_eq!(
+            log.borrow().as_slice(),
+            &[(1, true), (2, true), (2, false), (1, false)]
+        );
+        assert!(!first.prepared && !second.prepared);
+
+        log.borrow_mut().clear();
+        {
+            let participants: &mut [&mut dyn SleepParticipant] = &mut [&mut second, &mut first];
+            let _guard = SleepGuard::enter(participants);
+        }
+        assert_eq!(
+            log.borrow().as_slice(),
+            &[(2, true), (1, true), (1, false), (2, false)]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! # Prelude
+//!
+//! Re-exports of the commonly used traits and types, so they can be imported with a single
+//! glob import:
+//!
+//! ```rust,ignore
+//! use vorago_shared_hal::prelude::*;
+//! ```
+//!
+//! The traits are re-exported anonymously, which brings their methods into scope without
+//! reserving their names. Configuration types with generic names are re-exported with a prefix,
+//! for example [crate::uart::Config] as [UartConfig]. The original names are still available in
+//! the peripheral modules.
+//!
+//! The serial traits of [embedded_hal_nb] are not part of the prelude: The UART drivers
+//! implement them as well as the [embedded_io] traits, and methods like `write` would be
+//! ambiguous if both were in scope. They need to be imported explicitly if required.
+pub use embedded_hal::delay::DelayNs as _;
+pub use embedded_hal::digital::{InputPin as _, OutputPin as _, StatefulOutputPin as _};
+pub use embedded_hal::i2c::I2c as _;
+pub use embedded_hal::pwm::SetDutyCycle as _;
+pub use embedded_hal::spi::SpiBus as _;
+pub use embedded_io::{Read as _, Write as _};
+pub use fugit::{ExtU32 as _, RateExtU32 as _};
+
+#[cfg(feature = "vor1x")]
+pub use crate::InterruptConfig;
+pub use crate::gpio::{Flex, Input, Output, PinState, Port, Pull};
+pub use crate::i2c::I2cMaster;
+pub use crate::pins::{PinsA, PinsB};
+#[cfg(feature = "vor4x")]
+pub use crate::pins::{PinsC, PinsD, PinsE, PinsF, PinsG};
+pub use crate::spi::{Spi, SpiConfig};
+pub use crate::time::Hertz;
+pub use crate::timer::CountdownTimer;
+pub use crate::uart::Uart;
+pub use crate::{FunctionSelect, I2cMasterConfig, UartConfig};
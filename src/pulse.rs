@@ -0,0 +1,278 @@
+//! # Pulse generation with a defined pulse width.
+//!
+//! The pulse mode of the GPIO peripheral only generates pulses which are one system clock cycle
+//! wide. This module provides two pulse generators which generate pulses of a configurable width
+//! using a TIM peripheral:
+//!
+//! - [PulseGenerator]: The TIM status output is routed to a TIM pin. The status output is
+//!   active while the timer is enabled and the timer disables itself when the count reaches 0.
+//!   Both edges are generated by hardware, so the pulse width has a resolution of one timer clock
+//!   cycle and no jitter caused by software. No interrupt is required.
+//! - [IrqPulseGenerator]: Any [Output] pin is set by software and reset in the timer interrupt
+//!   handler by calling [IrqPulseGenerator::on_interrupt]. The falling edge is delayed by the
+//!   interrupt latency, which depends on the other active interrupts, the interrupt priorities
+//!   and the code executed before the handler calls [IrqPulseGenerator::on_interrupt].
+//!
+//! On a vor1x device clocked with 20 MHz, only the [PulseGenerator] reliably achieves a jitter
+//! below 1 us for pulse widths in the range of 5 us to 500 us. The interrupt entry alone takes at
+//! least 12 cycles (0.6 us) and varies with the instruction being interrupted, the flash wait
+//! states and preempting interrupts. The [IrqPulseGenerator] should therefore only be used if the
+//! pin can not be routed to a TIM peripheral, or for pulses where a jitter of several
+//! microseconds is acceptable.
+use core::convert::Infallible;
+
+use crate::gpio::{IoPeriphPin, Output, PinState};
+use crate::pwm::TimMissmatchError;
+use crate::time::{Duration, Hertz};
+use crate::timer::regs::{Control, EnableControl, StatusSelect};
+use crate::timer::{self, CountdownTimer, TimId, TimInstance, TimPin, enable_tim_clk};
+use crate::{PeripheralSelect, enable_peripheral_clock};
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PulseError {
+    /// The pulse width is shorter than one timer clock cycle or does not fit into the 32-bit
+    /// counter.
+    #[error("invalid pulse width")]
+    InvalidWidth,
+    /// A pulse is currently generated.
+    #[error("pulse in progress")]
+    Busy,
+}
+
+/// Converts a pulse width into timer clock cycles. Fractional clock cycles are truncated.
+pub const fn pulse_width_to_ticks(width: Duration, ref_clk: Hertz) -> Result<u32, PulseError> {
+    let ticks = width.to_micros() as u128 * ref_clk.raw() as u128 / 1_000_000;
+    if ticks == 0 || ticks > u32::MAX as u128 {
+        return Err(PulseError::InvalidWidth);
+    }
+    Ok(ticks as u32)
+}
+
+//==================================================================================================
+// Hardware pulse generator
+//==================================================================================================
+
+/// Pulse generator which uses the TIM status output routed to a TIM pin.
+///
+/// The pulse is generated fully in hardware: The status output is active while the timer is
+/// enabled, and the Auto-Disable feature disables the timer when the count reaches 0.
+pub struct PulseGenerator<Pin, Tim> {
+    pin: Pin,
+    tim: Tim,
+    regs: timer::regs::MmioTimer<'static>,
+    ref_clk: Hertz,
+}
+
+impl<Pin: TimPin, Tim: TimInstance> PulseGenerator<Pin, Tim> {
+    /// Create a new pulse generator. The pin is driven to the inactive level, which is low for
+    /// [PinState::High] pulses and high for [PinState::Low] pulses.
+    pub fn new(
+        pin: Pin,
+        tim: Tim,
+        #[cfg(feature = "vor1x")] sys_clk: Hertz,
+        #[cfg(feature = "vor4x")] clks: &crate::clock::Clocks,
+        active_level: PinState,
+    ) -> Result<Self, TimMissmatchError> {
+        if Pin::TIM_ID != Tim::ID {
+            return Err(TimMissmatchError::new(Pin::TIM_ID, Tim::ID));
+        }
+        IoPeriphPin::new(Pin::PIN_ID, Pin::FUN_SEL, None);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(Tim::ID));
+        #[cfg(feature = "vor1x")]
+        enable_peripheral_clock(PeripheralSelect::Gpio);
+        enable_peripheral_clock(PeripheralSelect::IoConfig);
+        enable_tim_clk(Tim::ID);
+        let mut regs = timer::regs::Timer::new_mmio(Tim::ID);
+        regs.write_enable_control(EnableControl::new_disable());
+        regs.write_control(
+            Control::new_with_raw_value(0)
+                .with_status_sel(StatusSelect::EnabledBit)
+                .with_status_invert(active_level == PinState::Low)
+                .with_auto_disable(true),
+        );
+        Ok(Self {
+            pin,
+            tim,
+            regs,
+            #[cfg(feature = "vor1x")]
+            ref_clk: sys_clk,
+            #[cfg(feature = "vor4x")]
+            ref_clk: Tim::clock(clks),
+        })
+    }
+
+    #[inline]
+    pub fn tim_id(&self) -> TimId {
+        Tim::ID
+    }
+
+    /// Start a pulse with the given width. This function returns immediately.
+    ///
+    /// The pulse width has a resolution of one timer clock cycle.
+    pub fn pulse(&mut self, width: Duration) -> Result<(), PulseError> {
+        let ticks = pulse_width_to_ticks(width, self.ref_clk)?;
+        if self.is_busy() {
+            return Err(PulseError::Busy);
+        }
+        self.regs.write_reset_value(ticks);
+        self.regs.write_count_value(ticks);
+        self.regs.write_enable_control(EnableControl::new_enable());
+        Ok(())
+    }
+
+    /// Returns [true] while a pulse is generated.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.regs.read_control().enable()
+    }
+
+    /// Returns `Ok` once the pulse is complete.
+    #[inline]
+    pub fn wait(&mut self) -> nb::Result<(), Infallible> {
+        if self.is_busy() {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(())
+    }
+
+    /// Abort a pulse in progress, which drives the pin to the inactive level immediately.
+    #[inline]
+    pub fn abort(&mut self) {
+        self.regs.write_enable_control(EnableControl::new_disable());
+    }
+
+    /// Abort a pulse in progress and return the pin and the TIM peripheral. The pin is still
+    /// configured for the TIM function and needs to be re-configured.
+    #[inline]
+    pub fn release(mut self) -> (Pin, Tim) {
+        self.abort();
+        (self.pin, self.tim)
+    }
+}
+
+//==================================================================================================
+// Interrupt driven pulse generator
+//==================================================================================================
+
+/// Pulse generator which sets an [Output] pin by software and resets it in the timer interrupt
+/// handler.
+///
+/// The interrupt of the [CountdownTimer] must be enabled with
+/// [CountdownTimer::enable_interrupt] and the interrupt handler must call
+/// [Self::on_interrupt].
+pub struct IrqPulseGenerator {
+    output: Output,
+    timer: CountdownTimer,
+    active_level: PinState,
+}
+
+impl IrqPulseGenerator {
+    /// Create a new pulse generator. The pin is driven to the inactive level.
+    pub fn new(mut output: Output, mut timer: CountdownTimer, active_level: PinState) -> Self {
+        timer.disable();
+        timer.auto_disable(true);
+        set_level(&mut output, !active_level);
+        Self {
+            output,
+            timer,
+            active_level,
+        }
+    }
+
+    /// Start a pulse with the given width. This function returns immediately after setting the
+    /// pin and starting the timer.
+    pub fn pulse(&mut self, width: Duration) -> Result<(), PulseError> {
+        let ticks = pulse_width_to_ticks(width, self.timer.ref_clk())?;
+        if self.is_busy() {
+            return Err(PulseError::Busy);
+        }
+        self.timer.set_reload(ticks);
+        self.timer.set_count(ticks);
+        set_level(&mut self.output, self.active_level);
+        self.timer.enable();
+        Ok(())
+    }
+
+    /// Returns [true] while a pulse is generated.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.timer.is_enabled()
+            || self.output.is_set_high() == (self.active_level == PinState::High)
+    }
+
+    /// Must be called in the interrupt handler of the timer. Drives the pin to the inactive
+    /// level.
+    #[inline]
+    pub fn on_interrupt(&mut self) {
+        set_level(&mut self.output, !self.active_level);
+    }
+
+    /// Abort a pulse in progress, which drives the pin to the inactive level immediately.
+    pub fn abort(&mut self) {
+        self.timer.disable();
+        set_level(&mut self.output, !self.active_level);
+    }
+
+    #[inline]
+    pub fn release(self) -> (Output, CountdownTimer) {
+        (self.output, self.timer)
+    }
+}
+
+#[inline(always)]
+fn set_level(output: &mut Output, level: PinState) {
+    match level {
+        PinState::Low => output.set_low(),
+        PinState::High => output.set_high(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fugit::{ExtU64, RateExtU32};
+
+    use super::*;
+
+    #[test]
+    fn zero_width() {
+        assert_eq!(
+            pulse_width_to_ticks(0.micros(), 50.MHz()),
+            Err(PulseError::InvalidWidth)
+        );
+    }
+
+    #[test]
+    fn shorter_than_one_cycle() {
+        // 1 us at 500 kHz is half a clock cycle.
+        assert_eq!(
+            pulse_width_to_ticks(1.micros(), 500.kHz()),
+            Err(PulseError::InvalidWidth)
+        );
+        assert_eq!(pulse_width_to_ticks(2.micros(), 500.kHz()), Ok(1));
+    }
+
+    #[test]
+    fn fractional_cycles_are_truncated() {
+        assert_eq!(pulse_width_to_ticks(1.micros(), 1_500.kHz()), Ok(1));
+        assert_eq!(pulse_width_to_ticks(3.micros(), 1_500.kHz()), Ok(4));
+        assert_eq!(pulse_width_to_ticks(5.micros(), 20.MHz()), Ok(100));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            pulse_width_to_ticks((u32::MAX as u64).micros(), 1.MHz()),
+            Ok(u32::MAX)
+        );
+        assert_eq!(
+            pulse_width_to_ticks((u32::MAX as u64 + 1).micros(), 1.MHz()),
+            Err(PulseError::InvalidWidth)
+        );
+        // The intermediate product does not overflow for the largest widths.
+        assert_eq!(
+            pulse_width_to_ticks(u64::MAX.micros(), 100.MHz()),
+            Err(PulseError::InvalidWidth)
+        );
+    }
+}
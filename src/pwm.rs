@@ -1,10 +1,12 @@
 use core::convert::Infallible;
 use core::marker::PhantomData;
 
-use crate::gpio::IoPeriphPin;
-use crate::timer::enable_tim_clk;
-use crate::timer::regs::{EnableControl, StatusSelect};
-use crate::{PeripheralSelect, enable_peripheral_clock};
+use crate::gpio::{IoPeriphPin, Output};
+use crate::timer::regs::{
+    CascadeInvert, CascadeSource, CascadeSourceReg, EnableControl, StatusSelect,
+};
+use crate::timer::{CountdownTimer, assert_tim_reset_for_cycles, enable_tim_clk};
+use crate::{PeripheralSelect, enable_peripheral_clock, power::SleepParticipant};
 
 use crate::time::Hertz;
 use crate::timer::{self, TimId, TimInstance, TimPin};
@@ -26,6 +28,122 @@ pub struct TimMissmatchError {
     tim_id: TimId,
 }
 
+impl TimMissmatchError {
+    pub(crate) const fn new(pin_tim: TimId, tim_id: TimId) -> Self {
+        Self { pin_tim, tim_id }
+    }
+}
+
+/// Error returned by [PwmPin::new_with_resolution].
+#[derive(Debug, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PwmInitError {
+    #[error("{0}")]
+    TimMissmatch(#[from] TimMissmatchError),
+    /// The reference clock does not provide the requested duty cycle resolution at the
+    /// requested frequency, see [PwmPin::resolution_bits].
+    #[error("PWM resolution of {available} bits is below the requested {requested} bits")]
+    InsufficientResolution { requested: u8, available: u8 },
+}
+
+/// The high time passed to [PwmPin::set_duty_exact] is longer than the period.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("duty of {ticks} ticks exceeds the maximum of {max} ticks")]
+pub struct DutyOutOfRange {
+    pub ticks: u32,
+    pub max: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PulseTrainError {
+    /// The counter timer must be a different TIM peripheral than the PWM timer.
+    #[error("counter timer is the PWM timer")]
+    SameTimer,
+    /// The counter timer does not use the clock of the PWM timer, so it can not count the
+    /// clock cycles of the PWM output. See [TimId::clock].
+    #[cfg(feature = "vor4x")]
+    #[error("counter timer {counter:?} and PWM timer {pwm:?} use different clocks")]
+    ClockMismatch { pwm: TimId, counter: TimId },
+    /// The PWM output has no low phase, which is required to stop at a period boundary.
+    #[error("PWM output has no low phase")]
+    NoLowPhase,
+    /// The number of counted clock cycles does not fit into the 32-bit counter.
+    #[error("pulse count too large for the current period")]
+    CountTooLarge,
+}
+
+/// Checks whether the counter timer can count the PWM output of the PWM timer.
+///
+/// Every TIM peripheral can select the status output of every other TIM peripheral as a
+/// cascade source. The counter counts the clock cycles in which the PWM output is high, so both
+/// timers need to use the same clock. On Vorago 4x devices, this requires both timers to be
+/// either in the TIM0 to TIM15 or in the TIM16 to TIM23 group.
+pub const fn check_pulse_train_timers(pwm: TimId, counter: TimId) -> Result<(), PulseTrainError> {
+    if pwm.value() == counter.value() {
+        return Err(PulseTrainError::SameTimer);
+    }
+    #[cfg(feature = "vor4x")]
+    if (pwm.value() <= 15) != (counter.value() <= 15) {
+        return Err(PulseTrainError::ClockMismatch { pwm, counter });
+    }
+    Ok(())
+}
+
+/// Number of clock cycles per period for which the PWMA output is high, and the counter value
+/// for the given number of pulses.
+///
+/// The timer counts down from the reload value to 0, and the PWMA output is high while the
+/// counter value is larger than or equal to the PWMA value. The output needs a low phase, so
+/// the PWMA value must not be 0.
+const fn pulse_train_counter_value(
+    reload: u32,
+    pwma: u32,
+    count: u32,
+) -> Result<(u32, u32), PulseTrainError> {
+    if pwma == 0 || pwma > reload {
+        return Err(PulseTrainError::NoLowPhase);
+    }
+    let high_cycles = reload - pwma + 1;
+    match high_cycles.checked_mul(count) {
+        Some(value) => Ok((high_cycles, value)),
+        None => Err(PulseTrainError::CountTooLarge),
+    }
+}
+
+/// Number of full bits of the duty cycle resolution for the given reset value.
+///
+/// The PWMA value can be set to all values from 0 to the reset value, so there are
+/// `reset_value + 1` distinct duty cycles.
+const fn resolution_bits(reset_value: u32) -> u8 {
+    (u32::BITS - 1 - reset_value.saturating_add(1).leading_zeros()) as u8
+}
+
+/// PWMA value for the 16-bit duty cycle of [embedded_hal::pwm::SetDutyCycle].
+const fn duty_to_pwma(reset_value: u32, duty: u16) -> u32 {
+    ((reset_value as u64 * (DUTY_MAX as u64 - duty as u64)) / DUTY_MAX as u64) as u32
+}
+
+/// PWMA value for the given number of clock cycles per period in which the PWMA output is high.
+///
+/// The output is high while the counter value is larger than or equal to the PWMA value, so
+/// there are `reset_value - pwma + 1` high cycles. A PWMA value larger than the reset value
+/// keeps the output low.
+const fn high_ticks_to_pwma(reset_value: u32, ticks: u32) -> Result<u32, DutyOutOfRange> {
+    let max = reset_value.saturating_add(1);
+    if ticks > max {
+        return Err(DutyOutOfRange { ticks, max });
+    }
+    Ok(max - ticks)
+}
+
+/// Second timer of a pulse train, see [PwmPin::pulse_train].
+struct PulseCounter {
+    regs: timer::regs::MmioTimer<'static>,
+    high_cycles: u32,
+}
+
 //==================================================================================================
 // PWM pin
 //==================================================================================================
@@ -41,10 +159,50 @@ pub struct PwmPin<Mode = PwmA> {
     current_lower_limit: u16,
     current_period: Hertz,
     current_rst_val: u32,
+    pulse_counter: Option<PulseCounter>,
+    /// Enable state recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
     mode: PhantomData<Mode>,
 }
 
 impl<Mode> PwmPin<Mode> {
+    /// Create a new PWM pin, checking that the requested frequency provides a duty cycle
+    /// resolution of at least `min_bits` bits, see [Self::resolution_bits].
+    ///
+    /// The pin and the timer are not configured if the resolution is insufficient.
+    pub fn new_with_resolution<Pin: TimPin, Tim: TimInstance>(
+        pin: Pin,
+        tim: Tim,
+        #[cfg(feature = "vor1x")] sys_clk: Hertz,
+        #[cfg(feature = "vor4x")] clks: &crate::clock::Clocks,
+        initial_frequency: Hertz,
+        min_bits: u8,
+    ) -> Result<Self, PwmInitError> {
+        #[cfg(feature = "vor1x")]
+        let ref_clk = sys_clk;
+        #[cfg(feature = "vor4x")]
+        let ref_clk = Tim::clock(clks);
+        let available = match initial_frequency.raw() {
+            0 => 0,
+            freq => resolution_bits(ref_clk.raw() / freq),
+        };
+        if available < min_bits {
+            return Err(PwmInitError::InsufficientResolution {
+                requested: min_bits,
+                available,
+            });
+        }
+        Ok(Self::new(
+            pin,
+            tim,
+            #[cfg(feature = "vor1x")]
+            sys_clk,
+            #[cfg(feature = "vor4x")]
+            clks,
+            initial_frequency,
+        )?)
+    }
+
     /// Create a new PWM pin
     pub fn new<Pin: TimPin, Tim: TimInstance>(
         _pin: Pin,
@@ -60,6 +218,7 @@ impl<Mode> PwmPin<Mode> {
             });
         }
         IoPeriphPin::new(Pin::PIN_ID, Pin::FUN_SEL, None);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(Tim::ID));
         let mut pin = PwmPin {
             tim_id: Tim::ID,
             regs: timer::regs::Timer::new_mmio(Tim::ID),
@@ -67,10 +226,12 @@ impl<Mode> PwmPin<Mode> {
             current_lower_limit: 0,
             current_period: initial_frequency,
             current_rst_val: 0,
+            pulse_counter: None,
+            sleep_state: None,
             #[cfg(feature = "vor1x")]
             ref_clk: sys_clk,
             #[cfg(feature = "vor4x")]
-            ref_clk: clks.apb1(),
+            ref_clk: Tim::clock(clks),
             mode: PhantomData,
         };
         // For Vorago 4x, the presence of the pin structure ensures that its respective peripheral
@@ -86,7 +247,7 @@ impl<Mode> PwmPin<Mode> {
 
     #[inline]
     fn enable_pwm_a(&mut self) {
-        self.regs.modify_control(|mut value| {
+        timer::modify_control(&mut self.regs, |mut value| {
             value.set_status_sel(StatusSelect::PwmaOutput);
             value
         });
@@ -94,12 +255,23 @@ impl<Mode> PwmPin<Mode> {
 
     #[inline]
     fn enable_pwm_b(&mut self) {
-        self.regs.modify_control(|mut value| {
+        timer::modify_control(&mut self.regs, |mut value| {
             value.set_status_sel(StatusSelect::PwmbOutput);
             value
         });
     }
 
+    #[inline]
+    pub fn tim_id(&self) -> TimId {
+        self.tim_id
+    }
+
+    /// Reload value of the timer for the current period.
+    #[inline]
+    pub fn reload(&self) -> u32 {
+        self.current_rst_val
+    }
+
     #[inline]
     pub fn get_period(&self) -> Hertz {
         self.current_period
@@ -121,8 +293,17 @@ impl<Mode> PwmPin<Mode> {
         self.regs.write_enable_control(EnableControl::new_disable());
     }
 
+    /// Enable the PWM output. A pulse train configured with [PwmPin::pulse_train] is stopped
+    /// and the PWM output runs continuously again.
     #[inline]
     pub fn enable(&mut self) {
+        if let Some(mut counter) = self.pulse_counter.take() {
+            counter
+                .regs
+                .write_enable_control(EnableControl::new_disable());
+            self.regs
+                .write_cascade_control(timer::regs::CascadeControl::new_with_raw_value(0));
+        }
         self.regs.write_enable_control(EnableControl::new_enable());
     }
 
@@ -135,6 +316,43 @@ impl<Mode> PwmPin<Mode> {
     pub fn duty(&self) -> u16 {
         self.current_duty
     }
+
+    /// Number of full bits of the duty cycle resolution for the current period.
+    ///
+    /// The timer counts with the reference clock, so a period has only `reload + 1` distinct
+    /// duty cycles. For example, a 200 kHz PWM with a 50 MHz reference clock has a reload value
+    /// of 250 and a resolution of 7 bits. The 16-bit duty cycle of
+    /// [embedded_hal::pwm::SetDutyCycle] is quantized to this resolution.
+    #[inline]
+    pub fn resolution_bits(&self) -> u8 {
+        resolution_bits(self.current_rst_val)
+    }
+
+    /// Number of distinct non-zero duty cycle steps of the current period, saturated to
+    /// [u16::MAX]. The 16-bit duty cycle is quantized to this number of steps.
+    #[inline]
+    pub fn max_effective_duty(&self) -> u16 {
+        self.current_rst_val.min(u16::MAX as u32) as u16
+    }
+}
+
+impl<Mode> SleepParticipant for PwmPin<Mode> {
+    /// Pauses the PWM timer. The pulse counter of a pulse train only counts the PWM pulses, so
+    /// it is paused as well.
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_none() {
+            self.sleep_state = Some(self.regs.read_control().enable());
+            self.regs.write_enable_control(EnableControl::new_disable());
+        }
+    }
+
+    /// Continues the PWM timer if it was enabled before. In contrast to [PwmPin::enable], a
+    /// pulse train is continued as well.
+    fn resume(&mut self) {
+        if self.sleep_state.take() == Some(true) {
+            self.regs.write_enable_control(EnableControl::new_enable());
+        }
+    }
 }
 
 impl From<PwmPin<PwmA>> for PwmPin<PwmB> {
@@ -148,6 +366,8 @@ impl From<PwmPin<PwmA>> for PwmPin<PwmB> {
             current_lower_limit: other.current_lower_limit,
             current_period: other.current_period,
             current_rst_val: other.current_rst_val,
+            pulse_counter: other.pulse_counter,
+            sleep_state: other.sleep_state,
         };
         pwmb.enable_pwm_b();
         pwmb
@@ -165,12 +385,153 @@ impl From<PwmPin<PwmB>> for PwmPin<PwmA> {
             current_lower_limit: other.current_lower_limit,
             current_period: other.current_period,
             current_rst_val: other.current_rst_val,
+            pulse_counter: other.pulse_counter,
+            sleep_state: other.sleep_state,
         };
         pwmb.enable_pwm_a();
         pwmb
     }
 }
 
+//==================================================================================================
+// PWMA duty cycle
+//==================================================================================================
+
+impl PwmPin<PwmA> {
+    /// Set the duty cycle as the number of reference clock cycles per period in which the
+    /// output is high, without the scaling of the 16-bit duty cycle.
+    ///
+    /// A period consists of [Self::reload] + 1 clock cycles, which is the largest valid value.
+    /// 0 keeps the output low. [Self::duty] returns the corresponding 16-bit duty cycle, which
+    /// is rounded down.
+    pub fn set_duty_exact(&mut self, ticks: u32) -> Result<(), DutyOutOfRange> {
+        let pwma = high_ticks_to_pwma(self.current_rst_val, ticks)?;
+        let period_ticks = self.current_rst_val as u64 + 1;
+        self.current_duty = ((ticks as u64 * DUTY_MAX as u64) / period_ticks) as u16;
+        self.regs.write_pwma_value(pwma);
+        Ok(())
+    }
+}
+
+//==================================================================================================
+// Pulse train
+//==================================================================================================
+
+impl PwmPin<PwmA> {
+    /// Emit exactly `count` PWM periods and stop the output afterwards.
+    ///
+    /// The second timer `counter_tim` counts the clock cycles in which the PWM output is high,
+    /// using the PWM timer as its cascade 0 source. Once the count of `count` periods is reached,
+    /// the counter timer disables itself. Its enable status is the cascade 2 source of the PWM
+    /// timer, which then disables the PWM timer when its counter reaches 0 at the end of the
+    /// last period. No interrupts or CPU interaction are required.
+    ///
+    /// The current period and duty cycle are used and must not be changed while the pulse
+    /// train is running, because the counter value is calculated from them. The output must
+    /// have a low phase, so the maximum duty cycle is rejected with
+    /// [PulseTrainError::NoLowPhase]. The PWM output is restarted at the beginning of a period.
+    /// A count of 0 only stops the PWM output.
+    ///
+    /// The counter timer is consumed. [Self::enable] stops the pulse train and runs the PWM
+    /// output continuously again.
+    pub fn pulse_train<CounterTim: TimInstance>(
+        &mut self,
+        count: u32,
+        _counter_tim: CounterTim,
+    ) -> Result<(), PulseTrainError> {
+        check_pulse_train_timers(self.tim_id, CounterTim::ID)?;
+        let (high_cycles, counter_value) =
+            pulse_train_counter_value(self.current_rst_val, self.regs.read_pwma_value(), count)?;
+        self.disable();
+        if count == 0 {
+            return Ok(());
+        }
+        enable_tim_clk(CounterTim::ID);
+        assert_tim_reset_for_cycles(CounterTim::ID, 2);
+        let mut counter_regs = timer::regs::Timer::new_mmio(CounterTim::ID);
+        counter_regs.write_control(
+            timer::regs::Control::new_with_raw_value(0)
+                .with_status_sel(StatusSelect::EnabledBit)
+                .with_auto_disable(true),
+        );
+        counter_regs.write_reset_value(counter_value);
+        counter_regs.write_count_value(counter_value);
+        // Safety: The cascade indices 0 and 2 are valid. The timer IDs are always valid cascade
+        // sources.
+        unsafe {
+            counter_regs.write_cascade_unchecked(
+                0,
+                CascadeSourceReg::new(CascadeSource::Tim(self.tim_id.value())).unwrap(),
+            );
+            self.regs.write_cascade_unchecked(
+                2,
+                CascadeSourceReg::new(CascadeSource::Tim(CounterTim::ID.value())).unwrap(),
+            );
+        }
+        counter_regs.write_cascade_control(
+            timer::regs::CascadeControl::builder()
+                .with_trigger2(false)
+                .with_inv2(CascadeInvert::ActiveHigh)
+                .with_en2(false)
+                .with_trigger1(false)
+                .with_trigger0(false)
+                .with_dual_cascade_op(Default::default())
+                .with_inv1(CascadeInvert::ActiveHigh)
+                .with_en1(false)
+                .with_inv0(CascadeInvert::ActiveHigh)
+                .with_en0(true)
+                .build(),
+        );
+        // The PWM timer is disabled at the end of a period once the counter timer is disabled.
+        self.regs.write_cascade_control(
+            timer::regs::CascadeControl::builder()
+                .with_trigger2(true)
+                .with_inv2(CascadeInvert::ActiveLow)
+                .with_en2(false)
+                .with_trigger1(false)
+                .with_trigger0(false)
+                .with_dual_cascade_op(Default::default())
+                .with_inv1(CascadeInvert::ActiveHigh)
+                .with_en1(false)
+                .with_inv0(CascadeInvert::ActiveHigh)
+                .with_en0(false)
+                .build(),
+        );
+        self.regs.write_count_value(self.current_rst_val);
+        // The counter timer needs to be enabled first, otherwise the PWM timer stops after the
+        // first period.
+        counter_regs.write_enable_control(EnableControl::new_enable());
+        self.regs.write_enable_control(EnableControl::new_enable());
+        self.pulse_counter = Some(PulseCounter {
+            regs: counter_regs,
+            high_cycles,
+        });
+        Ok(())
+    }
+
+    /// Number of remaining pulses of the pulse train, including the pulse which is currently
+    /// emitted. Returns 0 if no pulse train is running.
+    pub fn pulses_remaining(&self) -> u32 {
+        match &self.pulse_counter {
+            Some(counter) if counter.regs.read_enable_control().enabled() => counter
+                .regs
+                .read_count_value()
+                .div_ceil(counter.high_cycles),
+            _ => 0,
+        }
+    }
+
+    /// Abort the pulse train. The current period is completed, so the last pulse is not cut
+    /// off.
+    pub fn abort(&mut self) {
+        if let Some(counter) = &mut self.pulse_counter {
+            counter
+                .regs
+                .write_enable_control(EnableControl::new_disable());
+        }
+    }
+}
+
 //==================================================================================================
 // PWMB implementations
 //==================================================================================================
@@ -231,10 +592,8 @@ impl embedded_hal::pwm::SetDutyCycle for PwmPin {
     #[inline]
     fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
         self.current_duty = duty;
-        let pwma_val: u64 = (self.current_rst_val as u64
-            * (DUTY_MAX as u64 - self.current_duty as u64))
-            / DUTY_MAX as u64;
-        self.regs.write_pwma_value(pwma_val as u32);
+        self.regs
+            .write_pwma_value(duty_to_pwma(self.current_rst_val, duty));
         Ok(())
     }
 }
@@ -252,3 +611,386 @@ pub fn get_duty_from_percent(percent: f32) -> u16 {
         (percent * DUTY_MAX as f32) as u16
     }
 }
+
+//==================================================================================================
+// Software PWM
+//==================================================================================================
+
+/// Maximum number of channels of a [SoftPwm].
+pub const SOFT_PWM_MAX_CHANNELS: usize = 8;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SoftPwmError {
+    /// The resolution is smaller than 2 steps.
+    #[error("invalid resolution")]
+    InvalidResolution,
+    /// The step frequency, which is the PWM frequency multiplied with the resolution, is 0 or
+    /// larger than the timer reference clock.
+    #[error("invalid frequency")]
+    InvalidFrequency,
+    /// All channels are in use.
+    #[error("no free channel")]
+    NoFreeChannel,
+}
+
+/// Channel handle returned by [SoftPwm::add_channel].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SoftPwmChannelId(usize);
+
+impl SoftPwmChannelId {
+    #[inline]
+    pub const fn value(&self) -> usize {
+        self.0
+    }
+}
+
+/// Converts a duty cycle into the step at which the output is driven low.
+const fn duty_to_compare(duty: u16, resolution: u16) -> u16 {
+    ((duty as u32 * resolution as u32 + DUTY_MAX as u32 / 2) / DUTY_MAX as u32) as u16
+}
+
+/// Level change of a channel with the given compare value at the given step of the period, or
+/// [None] if the level does not change.
+const fn level_at_step(step: u16, compare: u16) -> Option<bool> {
+    if step == 0 {
+        return Some(compare > 0);
+    }
+    if step == compare {
+        return Some(false);
+    }
+    None
+}
+
+struct SoftPwmChannel {
+    output: Output,
+    duty: u16,
+    /// Compare value of the current period. Duty cycle changes are applied at the start of the
+    /// next period to avoid glitches.
+    compare: u16,
+}
+
+/// PWM on arbitrary [Output] pins, generated by software in the interrupt handler of a
+/// [CountdownTimer].
+///
+/// This can be used if a pin has no TIM function. One timer drives up to
+/// [SOFT_PWM_MAX_CHANNELS] channels with the same frequency. The duty cycle uses the same
+/// scaling as [PwmPin], so duty cycle values can be passed to [SoftPwm::set_duty] and to the
+/// [embedded_hal::pwm::SetDutyCycle] implementation of [PwmPin] interchangeably.
+///
+/// A PWM period is divided into `resolution` steps. The timer interrupt is generated once per
+/// step, so the interrupt rate is the PWM frequency multiplied with the resolution, for example
+/// 20 kHz for a 200 Hz PWM with a resolution of 100 steps. The interrupt of the
+/// [CountdownTimer] must be enabled with [CountdownTimer::enable_interrupt] and the interrupt
+/// handler must call [Self::on_interrupt].
+///
+/// Each call of [Self::on_interrupt] iterates over all channels and performs at most one GPIO
+/// write per channel, so the CPU cost per channel is `resolution` compares and at most 2 GPIO
+/// writes per PWM period. In addition, the interrupt entry and exit is required for every step,
+/// which is the dominant cost for few channels. The edges are delayed by the interrupt latency,
+/// see the [crate::pulse] module documentation.
+pub struct SoftPwm {
+    timer: CountdownTimer,
+    channels: [Option<SoftPwmChannel>; SOFT_PWM_MAX_CHANNELS],
+    frequency: Hertz,
+    resolution: u16,
+    step: u16,
+}
+
+impl SoftPwm {
+    /// Create a new software PWM with the given PWM frequency and the number of steps per
+    /// period. The timer is started immediately.
+    pub fn new(
+        mut timer: CountdownTimer,
+        frequency: Hertz,
+        resolution: u16,
+    ) -> Result<Self, SoftPwmError> {
+        if resolution < 2 {
+            return Err(SoftPwmError::InvalidResolution);
+        }
+        let step_frequency = frequency.raw() as u64 * resolution as u64;
+        if step_frequency == 0 || step_frequency > timer.ref_clk().raw() as u64 {
+            return Err(SoftPwmError::InvalidFrequency);
+        }
+        timer.auto_disable(false);
+        timer.start(Hertz::from_raw(step_frequency as u32));
+        Ok(Self {
+            timer,
+            channels: [const { None }; SOFT_PWM_MAX_CHANNELS],
+            frequency,
+            resolution,
+            step: 0,
+        })
+    }
+
+    /// Add a channel with a duty cycle of 0. The pin is driven low until the start of the next
+    /// period.
+    pub fn add_channel(&mut self, mut output: Output) -> Result<SoftPwmChannelId, SoftPwmError> {
+        let idx = self
+            .channels
+            .iter()
+            .position(Option::is_none)
+            .ok_or(SoftPwmError::NoFreeChannel)?;
+        output.set_low();
+        self.channels[idx] = Some(SoftPwmChannel {
+            output,
+            duty: 0,
+            compare: 0,
+        });
+        Ok(SoftPwmChannelId(idx))
+    }
+
+    /// Remove a channel and return its pin. The pin keeps its current level.
+    pub fn remove_channel(&mut self, id: SoftPwmChannelId) -> Option<Output> {
+        self.channels
+            .get_mut(id.0)
+            .and_then(Option::take)
+            .map(|channel| channel.output)
+    }
+
+    /// Set the duty cycle of a channel, which is applied at the start of the next period.
+    ///
+    /// [u16::MAX] is a duty cycle of 100 %, like for [PwmPin]. The duty cycle is rounded to the
+    /// resolution. Duty cycles of unknown channels are ignored.
+    pub fn set_duty(&mut self, id: SoftPwmChannelId, duty: u16) {
+        if let Some(Some(channel)) = self.channels.get_mut(id.0) {
+            channel.duty = duty;
+        }
+    }
+
+    /// Duty cycle of a channel, or [None] for unknown channels.
+    pub fn duty(&self, id: SoftPwmChannelId) -> Option<u16> {
+        self.channels
+            .get(id.0)
+            .and_then(Option::as_ref)
+            .map(|channel| channel.duty)
+    }
+
+    #[inline]
+    pub fn max_duty_cycle(&self) -> u16 {
+        DUTY_MAX
+    }
+
+    #[inline]
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    #[inline]
+    pub fn resolution(&self) -> u16 {
+        self.resolution
+    }
+
+    /// Must be called in the interrupt handler of the timer. Advances the PWM by one step and
+    /// updates the pin levels.
+    pub fn on_interrupt(&mut self) {
+        let step = self.step;
+        for channel in self.channels.iter_mut().flatten() {
+            if step == 0 {
+                channel.compare = duty_to_compare(channel.duty, self.resolution);
+            }
+            match level_at_step(step, channel.compare) {
+                Some(true) => channel.output.set_high(),
+                Some(false) => channel.output.set_low(),
+                None => (),
+            }
+        }
+        self.step += 1;
+        if self.step >= self.resolution {
+            self.step = 0;
+        }
+    }
+
+    /// Stop the timer and return it. All channel pins keep their current level.
+    pub fn release(mut self) -> CountdownTimer {
+        self.timer.disable();
+        self.timer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cycle-accurate model of a pulse train which returns the number of emitted pulses.
+    fn simulate_pulse_train(reload: u32, pwma: u32, count: u32) -> u32 {
+        let (_, mut counter) = pulse_train_counter_value(reload, pwma, count).unwrap();
+        let mut counter_enabled = counter > 0;
+        let mut pwm_count = reload;
+        let mut pwm_enabled = counter_enabled;
+        let mut last_output = false;
+        let mut pulses = 0;
+        while pwm_enabled {
+            let output = pwm_count >= pwma;
+            if output && !last_output {
+                pulses += 1;
+            }
+            last_output = output;
+            // The counter timer counts while its cascade 0 source is active and disables itself
+            // when it reaches 0.
+            if counter_enabled && output {
+                counter -= 1;
+                counter_enabled = counter > 0;
+            }
+            if pwm_count == 0 {
+                // Cascade 2 is the inverted enable status of the counter timer.
+                pwm_enabled = counter_enabled;
+                pwm_count = reload;
+            } else {
+                pwm_count -= 1;
+            }
+        }
+        pulses
+    }
+
+    #[test]
+    fn pulse_train_count() {
+        for (reload, pwma) in [(1, 1), (9, 1), (9, 5), (9, 9), (100, 37)] {
+            for count in [0, 1, 2, 3, 17] {
+                assert_eq!(
+                    simulate_pulse_train(reload, pwma, count),
+                    count,
+                    "reload {reload} pwma {pwma}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pulse_train_counter_values() {
+        assert_eq!(pulse_train_counter_value(99, 50, 3), Ok((50, 150)));
+        assert_eq!(pulse_train_counter_value(99, 99, 3), Ok((1, 3)));
+        assert_eq!(
+            pulse_train_counter_value(99, 0, 3),
+            Err(PulseTrainError::NoLowPhase)
+        );
+        assert_eq!(
+            pulse_train_counter_value(99, 100, 3),
+            Err(PulseTrainError::NoLowPhase)
+        );
+        assert_eq!(
+            pulse_train_counter_value(u32::MAX, 1, 2),
+            Err(PulseTrainError::CountTooLarge)
+        );
+    }
+
+    #[test]
+    fn pulse_train_timers() {
+        let tim = |idx| TimId::new(idx).unwrap();
+        assert_eq!(
+            check_pulse_train_timers(tim(3), tim(3)),
+            Err(PulseTrainError::SameTimer)
+        );
+        assert_eq!(check_pulse_train_timers(tim(3), tim(4)), Ok(()));
+        assert_eq!(check_pulse_train_timers(tim(16), tim(23)), Ok(()));
+        #[cfg(feature = "vor1x")]
+        assert_eq!(check_pulse_train_timers(tim(3), tim(20)), Ok(()));
+        #[cfg(feature = "vor4x")]
+        assert_eq!(
+            check_pulse_train_timers(tim(3), tim(20)),
+            Err(PulseTrainError::ClockMismatch {
+                pwm: tim(3),
+                counter: tim(20)
+            })
+        );
+    }
+
+    /// Number of clock cycles per period in which the PWMA output is high.
+    fn simulate_high_ticks(reload: u32, pwma: u32) -> u32 {
+        (0..=reload).filter(|count| *count >= pwma).count() as u32
+    }
+
+    #[test]
+    fn pwm_resolution_bits() {
+        assert_eq!(resolution_bits(0), 0);
+        assert_eq!(resolution_bits(1), 1);
+        assert_eq!(resolution_bits(2), 1);
+        assert_eq!(resolution_bits(3), 2);
+        // 50 MHz reference clock at 200 kHz and 2 MHz.
+        assert_eq!(resolution_bits(250), 7);
+        assert_eq!(resolution_bits(255), 8);
+        assert_eq!(resolution_bits(25), 4);
+        assert_eq!(resolution_bits(u16::MAX as u32), 16);
+        assert_eq!(resolution_bits(u32::MAX), 31);
+    }
+
+    #[test]
+    fn pwm_duty_scaling_small_reload() {
+        for reload in [1, 4, 25, 250] {
+            assert_eq!(duty_to_pwma(reload, DUTY_MAX), 0);
+            assert_eq!(duty_to_pwma(reload, 0), reload);
+            // The 16-bit duty cycle is quantized to reload + 1 levels, which are all reachable.
+            let mut levels = 0;
+            let mut last = None;
+            for duty in 0..=DUTY_MAX {
+                let pwma = duty_to_pwma(reload, duty);
+                assert!(pwma <= reload);
+                if last != Some(pwma) {
+                    // Monotonic, without gaps.
+                    if let Some(last) = last {
+                        assert_eq!(pwma, last - 1);
+                    }
+                    levels += 1;
+                    last = Some(pwma);
+                }
+            }
+            assert_eq!(levels, reload + 1, "reload {reload}");
+            assert!(levels <= 1 << (resolution_bits(reload) + 1));
+            assert!(levels >= 1 << resolution_bits(reload));
+        }
+        // With a reload value of 4, a duty cycle just below a quarter is rounded down.
+        assert_eq!(duty_to_pwma(4, DUTY_MAX / 4), 3);
+        assert_eq!(duty_to_pwma(4, DUTY_MAX / 4 + 1), 2);
+    }
+
+    #[test]
+    fn pwm_exact_duty() {
+        for reload in [1, 4, 250] {
+            for ticks in 0..=reload + 1 {
+                let pwma = high_ticks_to_pwma(reload, ticks).unwrap();
+                assert_eq!(simulate_high_ticks(reload, pwma), ticks, "reload {reload}");
+            }
+            assert_eq!(
+                high_ticks_to_pwma(reload, reload + 2),
+                Err(DutyOutOfRange {
+                    ticks: reload + 2,
+                    max: reload + 1
+                })
+            );
+        }
+    }
+
+    /// Number of steps per period for which the output is high.
+    fn high_steps(compare: u16, resolution: u16) -> u16 {
+        let mut level = false;
+        let mut high = 0;
+        for step in 0..resolution {
+            if let Some(new_level) = level_at_step(step, compare) {
+                level = new_level;
+            }
+            if level {
+                high += 1;
+            }
+        }
+        high
+    }
+
+    #[test]
+    fn duty_scaling() {
+        assert_eq!(duty_to_compare(0, 100), 0);
+        assert_eq!(duty_to_compare(DUTY_MAX, 100), 100);
+        assert_eq!(duty_to_compare(DUTY_MAX / 2, 100), 50);
+        assert_eq!(duty_to_compare(DUTY_MAX / 4, 4), 1);
+        assert_eq!(duty_to_compare(DUTY_MAX, u16::MAX), u16::MAX);
+    }
+
+    #[test]
+    fn high_steps_match_duty() {
+        for resolution in [2, 3, 10, 100] {
+            for compare in 0..=resolution {
+                assert_eq!(high_steps(compare, resolution), compare);
+            }
+        }
+    }
+}
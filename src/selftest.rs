@@ -0,0 +1,655 @@
+//! # Built-in self-tests using the internal loopback modes.
+//!
+//! The functions in this module check the UART, SPI and I2C peripherals on the actual hardware
+//! without requiring any external connections, except for [uart_inverted_pair_test], which
+//! requires a wire between two UART pins. Each loopback test function creates the driver with the
+//! respective `steal` constructor, configures the internal loopback mode of the peripheral and
+//! transfers a deterministic data pattern through the loopback path. Where the hardware allows
+//! it, errors are injected to check that the error detection works as well. The result of every
+//! check is recorded in the returned [SelfTestReport].
+//!
+//! The peripheral is reset before and after the test and its clock is disabled again if it was
+//! disabled before, so all touched peripherals are left in their reset state. This also means
+//! that the tests must not be run while the peripheral is used by another driver. All waiting
+//! is bounded, so a defective peripheral leads to a failed check instead of a hang.
+//!
+//! On Vorago 4x devices, `clock_monitor_test` checks the clock failure detection of the CLKGEN
+//! peripheral, which is not reset.
+//!
+//! These tests can be used as a built-in test at boot time, for example in combination with
+//! [crate::sysconfig::verify_peripheral_ids].
+use embedded_hal::spi::SpiBus as _;
+
+#[cfg(feature = "vor4x")]
+use crate::clock::Clocks;
+use crate::{
+    PeripheralSelect,
+    busy_wait::BusyWait,
+    disable_peripheral_clock, enable_peripheral_clock,
+    gpio::IoPeriphPin,
+    i2c::{self, I2cAddress, I2cMaster, I2cSpeed, MasterConfig},
+    is_peripheral_clock_enabled, reset_peripheral_for_cycles,
+    spi::{self, Spi, SpiConfig, SpiLowLevel},
+    time::{Deadline, Hertz, poll_with_deadline},
+    uart::{self, InvalidUartPinError, RX_PIN_MAPPINGS, TX_PIN_MAPPINGS, Uart, UartPinMapping},
+};
+
+/// Maximum number of checks of a single self-test.
+pub const MAX_CHECKS: usize = 6;
+
+/// Data pattern which is transferred through the loopback paths. It contains the all-zero and
+/// all-one words, alternating bits and walking ones.
+pub const PATTERN: [u8; 16] = [
+    0x00, 0xFF, 0x55, 0xAA, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0xFE, 0x7F, 0x3C, 0xC3,
+];
+
+/// Address used for the I2C slave in the I2C self-test.
+pub const I2C_SELFTEST_ADDR: u8 = 0x50;
+/// Address which is probed to check the address NACK detection in the I2C self-test.
+pub const I2C_SELFTEST_UNUSED_ADDR: u8 = 0x51;
+
+/// Number of bytes which are written to the I2C slave. This is smaller than the slave RX FIFO
+/// depth so the slave never has to stretch the clock.
+const I2C_PATTERN_LEN: usize = 8;
+/// SPI FIFO depth in words.
+const SPI_FIFO_DEPTH: usize = 16;
+
+/// Result of a single check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestCheck {
+    /// Short name of the check.
+    pub name: &'static str,
+    pub passed: bool,
+}
+
+/// Results of one self-test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    periph_sel: PeripheralSelect,
+    checks: heapless::Vec<SelfTestCheck, MAX_CHECKS>,
+}
+
+impl SelfTestReport {
+    const fn new(periph_sel: PeripheralSelect) -> Self {
+        Self {
+            periph_sel,
+            checks: heapless::Vec::new(),
+        }
+    }
+
+    fn record(&mut self, name: &'static str, passed: bool) {
+        // The capacity is large enough for every self-test in this module.
+        let _ = self.checks.push(SelfTestCheck { name, passed });
+    }
+
+    /// Peripheral which was tested.
+    #[inline]
+    pub const fn peripheral(&self) -> PeripheralSelect {
+        self.periph_sel
+    }
+
+    /// All checks in the order they were performed.
+    #[inline]
+    pub fn checks(&self) -> &[SelfTestCheck] {
+        &self.checks
+    }
+
+    /// Checks which failed.
+    #[inline]
+    pub fn failed_checks(&self) -> impl Iterator<Item = &SelfTestCheck> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+
+    /// Returns [true] if at least one check was performed and all checks passed.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|check| check.passed)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for SelfTestReport {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "SelfTestReport {{ periph_sel: {}, checks: {} }}",
+            self.periph_sel,
+            self.checks.as_slice()
+        );
+    }
+}
+
+/// Resets the peripheral on creation and on drop. The peripheral clock is disabled again on drop
+/// if it was disabled on creation.
+struct PeripheralGuard {
+    periph_sel: PeripheralSelect,
+    clk_was_enabled: bool,
+}
+
+impl PeripheralGuard {
+    fn new(periph_sel: PeripheralSelect) -> Self {
+        let clk_was_enabled = is_peripheral_clock_enabled(periph_sel);
+        enable_peripheral_clock(periph_sel);
+        reset_peripheral_for_cycles(periph_sel, 2);
+        Self {
+            periph_sel,
+            clk_was_enabled,
+        }
+    }
+}
+
+impl Drop for PeripheralGuard {
+    fn drop(&mut self) {
+        reset_peripheral_for_cycles(self.periph_sel, 2);
+        if !self.clk_was_enabled {
+            disable_peripheral_clock(self.periph_sel);
+        }
+    }
+}
+
+/// Deadline which expires after a fixed number of poll iterations.
+struct PollDeadline(u32);
+
+impl Deadline for PollDeadline {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        if self.0 == 0 {
+            return true;
+        }
+        self.0 -= 1;
+        false
+    }
+}
+
+/// Polls until the function returns [true] or the number of polls is exhausted. The busy-wait
+/// hook is called like in the blocking loops of the drivers.
+fn poll_until(polls: u32, mut done: impl FnMut() -> bool) -> bool {
+    let mut busy_wait = BusyWait::new();
+    for _ in 0..polls {
+        busy_wait.tick();
+        if done() {
+            return true;
+        }
+    }
+    false
+}
+
+//==================================================================================================
+// UART
+//==================================================================================================
+
+#[cfg(feature = "vor1x")]
+const fn uart_ref_clk(_bank: uart::Bank, sys_clk: Hertz) -> Hertz {
+    sys_clk
+}
+
+#[cfg(feature = "vor4x")]
+fn uart_ref_clk(bank: uart::Bank, clks: &Clocks) -> Hertz {
+    clks.clock_for(bank.apb_bus())
+}
+
+/// Sends the [PATTERN] with the byte based APIs and checks that it is received without errors.
+/// The bytes are masked to the word size by the driver, so the received bytes must match the
+/// masked pattern exactly. A transmitter which does not accept a byte fails the check.
+fn uart_transfer_pattern(tx: &mut uart::Tx, rx: &mut uart::Rx, char_polls: u32, mask: u8) -> bool {
+    for byte in PATTERN {
+        let written = poll_with_deadline(&mut PollDeadline(char_polls), || {
+            embedded_hal_nb::serial::Write::write(tx, byte).ok()
+        });
+        if written.is_err() {
+            return false;
+        }
+        let mut received = [0; 1];
+        let result = rx.read_with_timeout(&mut received, &mut PollDeadline(char_polls));
+        if result != Ok(1) || received[0] != byte & mask || rx.poll_errors().is_some() {
+            return false;
+        }
+    }
+    true
+}
+
+/// UART self-test using the internal loopback mode.
+///
+/// The following checks are performed:
+///
+/// 1. `perid`: The peripheral ID matches the expected value.
+/// 2. `data`: The [PATTERN] is received without errors. The pattern is masked to the configured
+///    word size by the driver, which is checked as well.
+/// 3. `parity_valid`: A character with a correct parity bit, which is set manually with the
+///    manual parity mode, is received without a parity error.
+/// 4. `parity_mismatch`: A character with an inverted parity bit is detected by the receiver
+///    parity check.
+/// 5. `break`: A break condition is detected by the receiver.
+///
+/// The TX output is blocked while the loopback mode is active.
+pub fn uart_loopback_test(
+    bank: uart::Bank,
+    #[cfg(feature = "vor1x")] sys_clk: Hertz,
+    #[cfg(feature = "vor4x")] clks: &Clocks,
+    config: uart::Config,
+) -> SelfTestReport {
+    let ref_clk = uart_ref_clk(
+        bank,
+        #[cfg(feature = "vor1x")]
+        sys_clk,
+        #[cfg(feature = "vor4x")]
+        clks,
+    );
+    let mut report = SelfTestReport::new(bank.periph_sel());
+    let _guard = PeripheralGuard::new(bank.periph_sel());
+
+    let mut config = config;
+    config.enable_tx = true;
+    config.enable_rx = true;
+    // Safety: The peripheral is reset after the test and the user must ensure it is not used
+    // by another driver.
+    let uart = unsafe {
+        Uart::steal(
+            bank,
+            ref_clk,
+            config,
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    };
+    report.record("perid", uart.perid_matches());
+
+    let mut regs = unsafe { bank.steal_regs() };
+    regs.modify_ctrl(|mut value| {
+        value.set_loopback(true);
+        value.set_loopback_block(true);
+        value
+    });
+    let (mut tx, mut rx) = uart.split();
+    // Generous upper bound for the number of polls for one character, assuming that one poll
+    // takes at least one reference clock cycle.
+    let char_polls = (ref_clk.raw() / config.baudrate.raw()).saturating_mul(16);
+    let mask = config.wordsize.data_mask();
+
+    report.record(
+        "data",
+        uart_transfer_pattern(&mut tx, &mut rx, char_polls, mask),
+    );
+
+    // Even parity with the parity bit supplied by the DPARITY bit of the data register. 0x01
+    // has an odd number of set bits, so the correct even parity bit is 1.
+    regs.modify_ctrl(|mut value| {
+        value.set_parity_enable(true);
+        value.set_parity_even(true);
+        value.set_parity_manual(true);
+        value
+    });
+    let mut send_with_parity = |dparity: bool| {
+        rx.clear_fifo();
+        regs.write_data(
+            uart::regs::Data::new_with_raw_value(0)
+                .with_value(0x01)
+                .with_dparity(dparity),
+        );
+        if !poll_until(char_polls, || regs.read_rx_status().data_available()) {
+            return None;
+        }
+        let parity_error = regs.read_rx_status().parity_error();
+        let received = regs.read_data();
+        Some((parity_error, received))
+    };
+    report.record(
+        "parity_valid",
+        matches!(send_with_parity(true), Some((false, data)) if data.value() == 0x01),
+    );
+    report.record(
+        "parity_mismatch",
+        matches!(send_with_parity(false), Some((true, _))),
+    );
+
+    // Restore the configured parity and send a break which lasts longer than one character.
+    regs.modify_ctrl(|mut value| {
+        value.set_parity_manual(false);
+        value.set_parity_enable(config.parity != uart::Parity::None);
+        value.set_parity_even(config.parity == uart::Parity::Even);
+        value
+    });
+    rx.clear_fifo();
+    regs.write_txbreak(32);
+    let break_detected = poll_until(char_polls.saturating_mul(4), || {
+        regs.read_rx_status().break_error()
+    });
+    regs.write_txbreak(0);
+    report.record("break", break_detected);
+    report
+}
+
+/// UART self-test for the TX and RX signal inversion, see [uart::Config::invert_tx].
+///
+/// The IOCONFIG inversion is not part of the internal loopback path, so this test requires an
+/// external wire from the TX pin of one UART to the RX pin of a second UART. The pins are
+/// validated against [TX_PIN_MAPPINGS] and [RX_PIN_MAPPINGS]. The inversion settings of the
+/// passed configuration are ignored. The following checks are performed:
+///
+/// 1. `plain`: The [PATTERN] is received without errors if neither line is inverted.
+/// 2. `mismatch`: The [PATTERN] is not received correctly if only the TX line is inverted. The
+///    receiver sees a line which idles low, which is a break condition.
+/// 3. `inverted`: The [PATTERN] is received without errors if both lines are inverted.
+///
+/// Both peripherals are reset after the test, but the pins keep their peripheral function with
+/// the inversion disabled. The report uses the peripheral of the transmitting UART.
+pub fn uart_inverted_pair_test(
+    tx_pin: UartPinMapping,
+    rx_pin: UartPinMapping,
+    #[cfg(feature = "vor1x")] sys_clk: Hertz,
+    #[cfg(feature = "vor4x")] clks: &Clocks,
+    config: uart::Config,
+) -> Result<SelfTestReport, InvalidUartPinError> {
+    for (table, mapping) in [(TX_PIN_MAPPINGS, tx_pin), (RX_PIN_MAPPINGS, rx_pin)] {
+        if !table.contains(&mapping) {
+            return Err(InvalidUartPinError {
+                bank: mapping.bank,
+                pin: mapping.pin,
+                fun_sel: mapping.fun_sel,
+            });
+        }
+    }
+    let ref_clk = |bank| {
+        uart_ref_clk(
+            bank,
+            #[cfg(feature = "vor1x")]
+            sys_clk,
+            #[cfg(feature = "vor4x")]
+            clks,
+        )
+    };
+    let mut report = SelfTestReport::new(tx_pin.bank.periph_sel());
+    let _tx_guard = PeripheralGuard::new(tx_pin.bank.periph_sel());
+    let _rx_guard =
+        (rx_pin.bank != tx_pin.bank).then(|| PeripheralGuard::new(rx_pin.bank.periph_sel()));
+
+    let mut config = config.invert_tx(false).invert_rx(false);
+    config.enable_tx = true;
+    config.enable_rx = true;
+    // Safety: The peripherals are reset after the test and the user must ensure they are not used
+    // by another driver.
+    let (mut tx, _) = unsafe {
+        Uart::steal(
+            tx_pin.bank,
+            ref_clk(tx_pin.bank),
+            config,
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    }
+    .split();
+    let (_, mut rx) = unsafe {
+        Uart::steal(
+            rx_pin.bank,
+            ref_clk(rx_pin.bank),
+            config,
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    }
+    .split();
+    // Generous upper bound for the number of polls for one character, see uart_loopback_test.
+    // The slower reference clock is used.
+    let char_polls = (ref_clk(tx_pin.bank).raw().max(ref_clk(rx_pin.bank).raw())
+        / config.baudrate.raw())
+    .saturating_mul(16);
+    let mask = config.wordsize.data_mask();
+
+    let mut run = |invert_tx: bool, invert_rx: bool| {
+        IoPeriphPin::new_with_inversion(tx_pin.pin, tx_pin.fun_sel, None, false, invert_tx);
+        IoPeriphPin::new_with_inversion(rx_pin.pin, rx_pin.fun_sel, None, invert_rx, false);
+        // Let the line settle for a few characters and discard anything received while the
+        // inversion was changed.
+        poll_until(char_polls.saturating_mul(2), || false);
+        rx.clear_fifo();
+        let _ = rx.poll_errors();
+        uart_transfer_pattern(&mut tx, &mut rx, char_polls, mask)
+    };
+    report.record("plain", run(false, false));
+    report.record("mismatch", !run(true, false));
+    report.record("inverted", run(true, true));
+    IoPeriphPin::new(tx_pin.pin, tx_pin.fun_sel, None);
+    IoPeriphPin::new(rx_pin.pin, rx_pin.fun_sel, None);
+    Ok(report)
+}
+
+//==================================================================================================
+// SPI
+//==================================================================================================
+
+/// SPI self-test using the internal loopback mode.
+///
+/// The loopback mode is always enabled, independently of [SpiConfig::loopback_mode]. The
+/// following checks are performed:
+///
+/// 1. `perid`: The peripheral ID matches the expected value.
+/// 2. `data`: The [PATTERN] is received with a full-duplex transfer.
+/// 3. `be_bytes`: An odd number of [PATTERN] bytes transferred as big endian 16-bit words with
+///    [Spi::transfer_be_bytes_with_timeout] is received like the 8-bit transfer.
+/// 4. `rx_overrun`: Sending more words than the RX FIFO can hold without reading sets the RX
+///    overrun status.
+pub fn spi_loopback_test(bank: spi::Bank, config: SpiConfig) -> SelfTestReport {
+    let mut report = SelfTestReport::new(bank.periph_sel());
+    let _guard = PeripheralGuard::new(bank.periph_sel());
+
+    // Safety: The peripheral is reset after the test and the user must ensure it is not used
+    // by another driver.
+    let mut spi: Spi<u8> = unsafe { Spi::steal(bank, config.loopback(true)) };
+    report.record("perid", spi.perid_matches());
+
+    // Upper bound for the number of polls. The slowest SPI clock is the reference clock divided
+    // by 256 * 256, and each word has 8 bits.
+    let polls = (PATTERN.len() as u32 + 1) * 8 * 256 * 256;
+    let mut received = [0; PATTERN.len()];
+    let data_ok = spi
+        .transfer_with_timeout(&mut received, &PATTERN, &mut PollDeadline(polls))
+        .is_ok()
+        && received == PATTERN;
+    report.record("data", data_ok);
+
+    let mut spi: Spi<u16> = spi.into();
+    let mut received_be = [0; PATTERN.len() - 1];
+    let be_bytes_ok = spi
+        .transfer_be_bytes_with_timeout(
+            &mut received_be,
+            &PATTERN[..PATTERN.len() - 1],
+            &mut PollDeadline(polls),
+        )
+        .is_ok()
+        && received_be == received[..PATTERN.len() - 1];
+    report.record("be_bytes", data_ok && be_bytes_ok);
+
+    spi.flush().ok();
+    let mut regs = unsafe { bank.steal_regs() };
+    regs.write_irq_clear(
+        spi::regs::InterruptClear::new_with_raw_value(0)
+            .with_rx_timeout(true)
+            .with_rx_overrun(true),
+    );
+    let mut written = 0;
+    let overrun = poll_until(polls, || {
+        if regs.read_irq_raw().rx_overrun() {
+            return true;
+        }
+        // Write one word more than the RX FIFO can hold.
+        if written <= SPI_FIFO_DEPTH && regs.read_status().tx_not_full() {
+            spi.write_fifo_unchecked(PATTERN[written % PATTERN.len()] as u32);
+            written += 1;
+        }
+        false
+    });
+    report.record("rx_overrun", overrun);
+    report
+}
+
+//==================================================================================================
+// I2C
+//==================================================================================================
+
+/// I2C self-test using the internal loopback mode, which connects the master to the slave of
+/// the same I2C peripheral.
+///
+/// The slave is configured with the [I2C_SELFTEST_ADDR] address. The following checks are
+/// performed:
+///
+/// 1. `perid`: The peripheral ID matches the expected value.
+/// 2. `nack_addr`: Probing the [I2C_SELFTEST_UNUSED_ADDR] address is not acknowledged.
+/// 3. `ack_addr`: Probing the slave address is acknowledged.
+/// 4. `data`: The first bytes of the [PATTERN] written by the master are received by the slave.
+///
+/// If the clock is too slow for the I2C peripheral, only a failed `clock` check is recorded.
+pub fn i2c_loopback_test(
+    bank: i2c::Bank,
+    #[cfg(feature = "vor1x")] sys_clk: Hertz,
+    #[cfg(feature = "vor4x")] clks: &Clocks,
+) -> SelfTestReport {
+    let mut report = SelfTestReport::new(bank.periph_sel());
+    let _guard = PeripheralGuard::new(bank.periph_sel());
+
+    let cfg = MasterConfig {
+        // Bound all blocking transfers.
+        timeout: Some(arbitrary_int::u20::new(0xFFFF)),
+        loopback: true,
+        ..Default::default()
+    };
+    // Safety: The peripheral is reset after the test and the user must ensure it is not used
+    // by another driver.
+    let i2c: Result<I2cMaster, _> = unsafe {
+        I2cMaster::steal(
+            bank,
+            #[cfg(feature = "vor1x")]
+            sys_clk,
+            #[cfg(feature = "vor4x")]
+            clks,
+            cfg,
+            I2cSpeed::Regular100khz,
+        )
+    };
+    let mut i2c = match i2c {
+        Ok(i2c) => i2c,
+        Err(_) => {
+            report.record("clock", false);
+            return report;
+        }
+    };
+    report.record("perid", i2c.perid_matches());
+
+    let mut regs = unsafe { bank.steal_regs() };
+    let mut slave = regs.slave();
+    slave.write_s0_address(
+        i2c::regs::slave::Address::new_with_raw_value(0)
+            .with_address(arbitrary_int::u10::new(I2C_SELFTEST_ADDR as u16)),
+    );
+    slave.write_s0_fifo_clear(
+        i2c::regs::FifoClear::builder()
+            .with_tx_fifo(true)
+            .with_rx_fifo(true)
+            .build(),
+    );
+    slave.modify_s0_ctrl(|mut value| {
+        value.set_enable(true);
+        value
+    });
+
+    report.record(
+        "nack_addr",
+        i2c.probe(I2cAddress::Regular(I2C_SELFTEST_UNUSED_ADDR)) == Ok(false),
+    );
+    report.record(
+        "ack_addr",
+        i2c.probe(I2cAddress::Regular(I2C_SELFTEST_ADDR)) == Ok(true),
+    );
+
+    let mut data_ok = i2c
+        .write_blocking(
+            I2cAddress::Regular(I2C_SELFTEST_ADDR),
+            &PATTERN[..I2C_PATTERN_LEN],
+        )
+        .is_ok();
+    if data_ok {
+        for byte in &PATTERN[..I2C_PATTERN_LEN] {
+            if !slave.read_s0_status().rx_not_empty() || slave.read_s0_data().data() != *byte {
+                data_ok = false;
+                break;
+            }
+        }
+    }
+    report.record("data", data_ok);
+    report
+}
+
+//==================================================================================================
+// Clock monitor
+//==================================================================================================
+
+/// Self-test of the clock failure detection, see [crate::clock::monitor].
+///
+/// A clock failure can not be injected safely, so the event paths are checked without a
+/// failure. In contrast to the other self-tests, the CLKGEN peripheral is not reset. The
+/// detector configuration is restored after the test. `ext_clk` is the frequency of the
+/// external clock or crystal, see [Clocks::refresh_after_failover]. The following checks are
+/// performed:
+///
+/// 1. `frequencies`: The frequencies calculated from the clock configuration of the hardware
+///    match the frozen frequencies, so no failover happened and the bookkeeping is consistent.
+/// 2. `sys_clk_lost_enable`: The SYS_CLK lost detector can be enabled and disabled.
+/// 3. `pll_lock_lost_enable`: The PLL lock lost detector can be enabled and disabled.
+/// 4. `no_failure`: No system clock loss is reported. If the PLL is the active clock source, no
+///    PLL lock loss is reported either.
+/// 5. `handler`: The interrupt handler reports no events for disabled detectors and does not
+///    change the clock source.
+#[cfg(feature = "vor4x")]
+pub fn clock_monitor_test(clks: &Clocks, ext_clk: Option<Hertz>) -> SelfTestReport {
+    use crate::clock::{ClockEvents, ClockMonitor, SysClkSource, monitor};
+
+    let mut report = SelfTestReport::new(PeripheralSelect::Clkgen);
+    let mut refreshed = *clks;
+    report.record(
+        "frequencies",
+        refreshed.refresh_after_failover(ext_clk) == Ok(*clks),
+    );
+
+    // Safety: The user must ensure that the CLKGEN control registers are not modified
+    // concurrently.
+    let mut monitor = unsafe { ClockMonitor::steal() };
+    let sys_clk_lost_enabled = monitor.sys_clk_lost_detection_enabled();
+    let pll_lock_lost_enabled = monitor.pll_lock_lost_detection_enabled();
+    monitor.disable_sys_clk_lost_detection();
+    monitor.enable_sys_clk_lost_detection(false);
+    let enabled = monitor.sys_clk_lost_detection_enabled();
+    monitor.disable_sys_clk_lost_detection();
+    report.record(
+        "sys_clk_lost_enable",
+        enabled && !monitor.sys_clk_lost_detection_enabled(),
+    );
+    monitor.disable_pll_lock_lost_detection();
+    monitor.enable_pll_lock_lost_detection(false);
+    let enabled = monitor.pll_lock_lost_detection_enabled();
+    monitor.disable_pll_lock_lost_detection();
+    report.record(
+        "pll_lock_lost_enable",
+        enabled && !monitor.pll_lock_lost_detection_enabled(),
+    );
+
+    let source = clks.active_source();
+    let status = monitor.status();
+    report.record(
+        "no_failure",
+        !status.sys_clk_lost && (source != SysClkSource::Pll || !status.pll_lock_lost),
+    );
+
+    // Both detectors are disabled at this point.
+    report.record(
+        "handler",
+        monitor::on_interrupt() == ClockEvents::default() && clks.active_source() == source,
+    );
+
+    if sys_clk_lost_enabled {
+        monitor.enable_sys_clk_lost_detection(false);
+    }
+    if pll_lock_lost_enabled {
+        monitor.enable_pll_lock_lost_detection(false);
+    }
+    report
+}
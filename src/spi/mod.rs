@@ -1,10 +1,22 @@
 use crate::FunctionSelect;
-use crate::gpio::{DynPinId, IoPeriphPin};
-use crate::{PeripheralSelect, enable_peripheral_clock, pins::AnyPin, sealed::Sealed, time::Hertz};
+use crate::gpio::{DynPinId, IoPeriphPin, Output, PinState};
+use crate::{
+    PeripheralSelect,
+    busy_wait::BusyWait,
+    enable_peripheral_clock,
+    pins::AnyPin,
+    power::SleepParticipant,
+    reset_peripheral_for_cycles,
+    sealed::Sealed,
+    time::{Deadline, Hertz, NoDeadline, TimeoutError, poll_with_deadline},
+};
 use core::{convert::Infallible, fmt::Debug, marker::PhantomData};
 use embedded_hal::spi::{MODE_0, Mode};
 
-use regs::{ClockPrescaler, Data, FifoClear, WordSize};
+use arbitrary_int::u5;
+use regs::{
+    ClockPrescaler, Data, FifoClear, InterruptClear, InterruptControl, TriggerLevel, WordSize,
+};
 #[cfg(feature = "vor1x")]
 use va108xx as pac;
 #[cfg(feature = "vor4x")]
@@ -14,11 +26,76 @@ pub use regs::{Bank, HwChipSelectId};
 
 pub mod regs;
 
-pub fn configure_pin_as_hw_cs_pin<P: AnyPin + HwCsProvider>(_pin: P) -> HwChipSelectId {
-    IoPeriphPin::new(P::ID, P::FUN_SEL, None);
+/// Configure the pin as a hardware chip select pin and return its chip select ID.
+///
+/// The pin is consumed and can not be used for other purposes anymore. [HwCsPin] can be used
+/// instead to keep the pin, so it can be released or used as a GPIO temporarily.
+pub fn configure_pin_as_hw_cs_pin<P: HwCsProvider>(_pin: P) -> HwChipSelectId {
+    if let Some(pin_id) = P::PIN_ID {
+        IoPeriphPin::new(pin_id, P::FUN_SEL, None);
+    }
     P::CS_ID
 }
 
+/// Pin which is configured as a hardware chip select pin.
+///
+/// The pin is owned by this structure, which keeps the association of the pin, the SPI bank and
+/// the chip select ID. It can be passed to [Spi::cfg_hw_cs_pin].
+pub struct HwCsPin<P: HwCsProvider> {
+    pin: P,
+}
+
+impl<P: HwCsProvider> HwCsPin<P> {
+    /// Configure the pin as a hardware chip select pin.
+    pub fn new(pin: P) -> Self {
+        if let Some(pin_id) = P::PIN_ID {
+            IoPeriphPin::new(pin_id, P::FUN_SEL, None);
+        }
+        Self { pin }
+    }
+
+    /// Pin ID, or [None] for the dedicated ROM chip select [RomCs].
+    #[inline]
+    pub const fn pin_id(&self) -> Option<DynPinId> {
+        P::PIN_ID
+    }
+
+    #[inline]
+    pub const fn cs_id(&self) -> HwChipSelectId {
+        P::CS_ID
+    }
+
+    #[inline]
+    pub const fn bank(&self) -> Bank {
+        P::SPI_ID
+    }
+
+    /// Use the pin as a push-pull GPIO output while the closure is running, for example to
+    /// drive the chip select manually during a recovery sequence of a memory device.
+    ///
+    /// The output is initialized with the given level. The chip select function is restored
+    /// after the closure returned. Returns [None] without calling the closure for [RomCs],
+    /// which can not be used as a GPIO.
+    pub fn as_gpio_temporarily<R>(
+        &mut self,
+        init_level: PinState,
+        f: impl FnOnce(&mut Output) -> R,
+    ) -> Option<R> {
+        let pin_id = P::PIN_ID?;
+        let mut output = Output::new_for_pin_id(pin_id, init_level);
+        let result = f(&mut output);
+        IoPeriphPin::new(pin_id, P::FUN_SEL, None);
+        Some(result)
+    }
+
+    /// Release the pin. The pin is still configured as a chip select pin and needs to be
+    /// re-configured, for example by creating an [Output] with it.
+    #[inline]
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
 //==================================================================================================
 // Pins and traits.
 //==================================================================================================
@@ -39,7 +116,9 @@ pub trait PinMiso: AnyPin {
 }
 
 pub trait HwCsProvider {
-    const PIN_ID: DynPinId;
+    /// Pin which is routed to the chip select, or [None] for the dedicated ROM chip select which
+    /// has no IOCONFIG entry.
+    const PIN_ID: Option<DynPinId>;
     const SPI_ID: Bank;
     const FUN_SEL: FunctionSelect;
     const CS_ID: HwChipSelectId;
@@ -75,7 +154,7 @@ mod macros {
             impl crate::sealed::Sealed for $name {}
 
             impl HwCsProvider for $name {
-                const PIN_ID: DynPinId = <$pin_id as PinId>::ID;
+                const PIN_ID: Option<DynPinId> = Some(<$pin_id as PinId>::ID);
                 const SPI_ID: Bank = $spi_id;
                 const FUN_SEL: FunctionSelect = $fun_sel;
                 const CS_ID: HwChipSelectId = $cs_id;
@@ -88,7 +167,7 @@ mod macros {
         ($SpiId:path, $(($Px:ident, $FunSel:path, $HwCsIdent:path)$(,)?)+) => {
             $(
                 impl HwCsProvider for Pin<$Px> {
-                    const PIN_ID: DynPinId = $Px::ID;
+                    const PIN_ID: Option<DynPinId> = Some($Px::ID);
                     const SPI_ID: Bank = $SpiId;
                     const FUN_SEL: FunctionSelect = $FunSel;
                     const CS_ID: HwChipSelectId = $HwCsIdent;
@@ -98,6 +177,42 @@ mod macros {
     }
 }
 
+/// SPI bank which is connected to the dedicated ROM SPI pins.
+#[cfg(feature = "vor1x")]
+pub const ROM_SPI_BANK: Bank = Bank::Spi2;
+/// SPI bank which is connected to the dedicated ROM SPI pins.
+#[cfg(feature = "vor4x")]
+pub const ROM_SPI_BANK: Bank = Bank::Spi3;
+
+/// Dedicated ROM SPI clock pin.
+///
+/// The ROM SPI pins are not part of a GPIO port, so they are modelled as markers instead of
+/// [Pin](crate::pins::Pin)s. The IOCONFIG register map of the vendor SVD only contains the pin
+/// configuration registers of the GPIO ports (PORTA and PORTB on Vorago 1x, PORTA to PORTG on
+/// Vorago 4x), so there is no function select which could be configured for these pins.
+#[derive(Debug, Default)]
+pub struct RomSck;
+
+/// Dedicated ROM SPI MISO pin. See [RomSck] for more details.
+#[derive(Debug, Default)]
+pub struct RomMiso;
+
+/// Dedicated ROM SPI MOSI pin. See [RomSck] for more details.
+#[derive(Debug, Default)]
+pub struct RomMosi;
+
+/// Dedicated ROM SPI chip select pin, which is driven by [HwChipSelectId::Id0] of
+/// [ROM_SPI_BANK]. See [RomSck] for more details.
+#[derive(Debug, Default)]
+pub struct RomCs;
+
+impl HwCsProvider for RomCs {
+    const PIN_ID: Option<DynPinId> = None;
+    const SPI_ID: Bank = ROM_SPI_BANK;
+    const FUN_SEL: FunctionSelect = FunctionSelect::Sel0;
+    const CS_ID: HwChipSelectId = HwChipSelectId::Id0;
+}
+
 #[cfg(feature = "vor1x")]
 pub mod pins_vor1x;
 #[cfg(feature = "vor4x")]
@@ -107,8 +222,12 @@ pub mod pins_vor4x;
 // Defintions
 //==================================================================================================
 
-// FIFO has a depth of 16.
-const FILL_DEPTH: usize = 12;
+/// Largest FIFO depth of all SPI peripherals, see [Bank::fifo_depth].
+pub const MAX_FIFO_DEPTH: usize = 16;
+
+/// The default FIFO prefill leaves some headroom to the FIFO depth, see
+/// [SpiConfig::fifo_prefill].
+const DEFAULT_PREFILL_HEADROOM: usize = 4;
 
 pub const BMSTART_BMSTOP_MASK: u32 = 1 << 31;
 pub const BMSKIPDATA_MASK: u32 = 1 << 30;
@@ -120,6 +239,10 @@ pub const DEFAULT_CLK_DIV: u16 = 2;
 pub trait SpiInstance: Sealed {
     const ID: Bank;
     const PERIPH_SEL: PeripheralSelect;
+    const FIFO_DEPTH: usize = Self::ID.fifo_depth();
+    /// APB bus which clocks the peripheral.
+    #[cfg(feature = "vor4x")]
+    const APB: crate::clock::ApbBus = Self::ID.apb_bus();
 }
 
 #[cfg(feature = "vor1x")]
@@ -177,7 +300,7 @@ pub trait TransferConfigProvider {
 
 /// Type erased variant of the transfer configuration. This is required to avoid generics in
 /// the SPI constructor.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct TransferConfig {
     pub clk_cfg: Option<SpiClockConfig>,
@@ -213,8 +336,57 @@ impl TransferConfig {
     }
 }
 
+/// Chip select handling of the SPI driver, see [Spi::current_cs].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChipSelect {
+    /// No chip select is handled. The hardware chip select outputs are disabled. This is the
+    /// initial state if [SpiConfig::slave_output_disable] is set.
+    None,
+    /// The chip select is handled externally, for example with GPIO pins. The hardware chip
+    /// select outputs are disabled.
+    Gpio,
+    /// The hardware chip select with the given ID is asserted during transfers.
+    Hw(HwChipSelectId),
+}
+
+impl ChipSelect {
+    /// Initial state after the peripheral was configured with the given configuration. The
+    /// peripheral drives [HwChipSelectId::Id0] unless the slave output is disabled.
+    const fn initial(cfg: &SpiConfig) -> Self {
+        if cfg.slave_output_disable {
+            return ChipSelect::None;
+        }
+        ChipSelect::Hw(HwChipSelectId::Id0)
+    }
+
+    /// State after applying a [TransferConfig].
+    ///
+    /// A hardware chip select is only enabled with an explicit [TransferConfig::hw_cs] ID. If
+    /// the slave output was disabled before, a transfer configuration without an ID keeps it
+    /// disabled instead of re-enabling the last configured ID.
+    const fn after_transfer_cfg(self, sod: bool, hw_cs: Option<HwChipSelectId>) -> Self {
+        if sod {
+            return ChipSelect::Gpio;
+        }
+        match hw_cs {
+            Some(id) => ChipSelect::Hw(id),
+            None => self,
+        }
+    }
+
+    /// Apply the state to the SOD and SS fields of the CTRL1 register. The SS field is left
+    /// unchanged if the slave output is disabled.
+    const fn apply(self, ctrl1: regs::Control1) -> regs::Control1 {
+        match self {
+            ChipSelect::None | ChipSelect::Gpio => ctrl1.with_sod(true),
+            ChipSelect::Hw(id) => ctrl1.with_sod(false).with_ss(id),
+        }
+    }
+}
+
 /// Configuration options for the whole SPI bus. See Programmer Guide p.92 for more details
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SpiConfig {
     clk: SpiClockConfig,
@@ -231,8 +403,16 @@ pub struct SpiConfig {
     pub slave_output_disable: bool,
     /// Loopback mode. If you use this, don't connect MISO to MOSI, they will be tied internally
     pub loopback_mode: bool,
-    /// Enable Master Delayer Capture Mode. See Programmers Guide p.92 for more details
+    /// Enable Master Delayer Capture Mode. See Programmers Guide p.92 for more details.
+    ///
+    /// The master normally samples MISO on the clock edge given by the SPI mode. With this mode
+    /// enabled, MISO is sampled one half SCK period later. This gives slow slaves, long cables
+    /// or level shifters more time to drive MISO, which is mostly relevant at high SCK
+    /// frequencies. Because the sampling edge is derived from the CPOL and CPHA setting, a
+    /// working setting found for one mode is not necessarily valid for another mode.
+    /// [Spi::calibrate_sampling] can be used to determine the setting at run-time.
     pub master_delayer_capture: bool,
+    fifo_prefill: Option<usize>,
 }
 
 impl Default for SpiConfig {
@@ -246,6 +426,7 @@ impl Default for SpiConfig {
             slave_output_disable: Default::default(),
             loopback_mode: Default::default(),
             master_delayer_capture: Default::default(),
+            fifo_prefill: None,
         }
     }
 }
@@ -280,8 +461,72 @@ impl SpiConfig {
         self.slave_output_disable = sod;
         self
     }
+
+    /// Number of words which are written to the FIFO at the start of a transfer before the
+    /// driver starts polling the FIFO status. This also limits the number of words in flight
+    /// for [Spi::transfer_with_timeout].
+    ///
+    /// Defaults to the FIFO depth of the bank minus 4. A smaller value can avoid TX underruns
+    /// in blockmode if the bus is fast compared to the CPU. The value is limited to the FIFO
+    /// depth of the bank when the driver is created.
+    pub fn fifo_prefill(mut self, prefill: usize) -> Result<Self, InvalidFifoPrefillError> {
+        validate_fifo_prefill(prefill, MAX_FIFO_DEPTH)?;
+        self.fifo_prefill = Some(prefill);
+        Ok(self)
+    }
+}
+
+//==================================================================================================
+// Interrupts
+//==================================================================================================
+
+/// SPI interrupt sources.
+///
+/// The peripheral does not provide a TX underrun interrupt. The TX and RX trigger interrupts are
+/// level based and remain active as long as the FIFO fill level condition is met.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SpiInterrupts {
+    /// TX FIFO count <= TX FIFO trigger level.
+    pub tx_trigger: bool,
+    /// RX FIFO count >= RX FIFO trigger level.
+    pub rx_trigger: bool,
+    /// RX FIFO not empty and not read for 32 SPI clock ticks.
+    pub rx_timeout: bool,
+    /// Data was received while the RX FIFO was full.
+    pub rx_overrun: bool,
+}
+
+impl SpiInterrupts {
+    pub const fn all() -> Self {
+        Self {
+            tx_trigger: true,
+            rx_trigger: true,
+            rx_timeout: true,
+            rx_overrun: true,
+        }
+    }
+
+    pub const fn none() -> Self {
+        Self {
+            tx_trigger: false,
+            rx_trigger: false,
+            rx_timeout: false,
+            rx_overrun: false,
+        }
+    }
+
+    /// At least one interrupt source is set.
+    #[inline]
+    pub const fn any(&self) -> bool {
+        self.tx_trigger || self.rx_trigger || self.rx_timeout || self.rx_overrun
+    }
 }
 
+/// Interrupt status returned by [Spi::read_and_clear_irq_status]. Only enabled interrupts are
+/// reported.
+pub type SpiIrqStatus = SpiInterrupts;
+
 //==================================================================================================
 // Word Size
 //==================================================================================================
@@ -310,6 +555,97 @@ impl SpiWord for u16 {
     }
 }
 
+/// Data of a received FIFO value, without the BMSTART bit and the bits above the word size.
+#[inline]
+fn word_from_fifo<Word: SpiWord>(value: u32) -> Word
+where
+    <Word as TryFrom<u32>>::Error: core::fmt::Debug,
+{
+    (value & Word::MASK).try_into().unwrap()
+}
+
+/// Block mode control bits of a FIFO value.
+#[inline]
+const fn bm_flags(bm_stop: bool, bm_skip: bool) -> u32 {
+    let mut flags = 0;
+    if bm_stop {
+        flags |= BMSTART_BMSTOP_MASK;
+    }
+    if bm_skip {
+        flags |= BMSKIPDATA_MASK;
+    }
+    flags
+}
+
+/// Word for the raw transfer API of [Spi], with explicit control of the block mode bits.
+///
+/// The flags are only evaluated in blockmode:
+///
+/// - `bm_stop` ends the frame after this word, so the chip select is deasserted. With BMSTALL
+///   enabled, the last word of every frame must have this flag set. Otherwise, SCK is stalled
+///   with the chip select asserted until more words are written. Without BMSTALL, the frame
+///   also ends when the TX FIFO runs empty.
+/// - `bm_skip` together with `bm_stop` ends the frame without transmitting the data of this
+///   word, for example to end a frame whose length was only known after the last data word was
+///   written. Such a word does not produce a received word. Without `bm_stop`, it is ignored.
+///
+/// In non-blockmode, both flags are ignored and the chip select is toggled for every word.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawSpiWord {
+    pub data: u16,
+    pub bm_stop: bool,
+    pub bm_skip: bool,
+}
+
+impl RawSpiWord {
+    /// Word which ends the current frame without transmitting additional data.
+    pub const FRAME_END: Self = Self {
+        data: 0,
+        bm_stop: true,
+        bm_skip: true,
+    };
+
+    /// Data word without block mode flags.
+    #[inline]
+    pub const fn new(data: u16) -> Self {
+        Self {
+            data,
+            bm_stop: false,
+            bm_skip: false,
+        }
+    }
+
+    /// Data word which ends the current frame.
+    #[inline]
+    pub const fn new_with_stop(data: u16) -> Self {
+        Self {
+            data,
+            bm_stop: true,
+            bm_skip: false,
+        }
+    }
+
+    /// Value which is written to the FIFO.
+    #[inline]
+    pub const fn raw_value(&self) -> u32 {
+        self.data as u32 | bm_flags(self.bm_stop, self.bm_skip)
+    }
+
+    /// Whether a word is received for this word.
+    #[inline]
+    pub const fn is_received(&self) -> bool {
+        !(self.bm_stop && self.bm_skip)
+    }
+}
+
+impl From<RawSpiWord> for u32 {
+    #[inline]
+    fn from(word: RawSpiWord) -> Self {
+        word.raw_value()
+    }
+}
+
 //==================================================================================================
 // Spi
 //==================================================================================================
@@ -387,18 +723,118 @@ impl SpiClockConfig {
         clk_div_for_target_clock(sys_clk, spi_clk).map(|div| spi_clk_config_from_div(div).unwrap())
     }
 
+    /// All SPI peripherals are clocked by the APB1 clock, see [Bank::apb_bus].
     #[cfg(feature = "vor4x")]
     pub fn from_clks(clks: &crate::clock::Clocks, spi_clk: Hertz) -> Option<Self> {
-        Self::from_apb1_clk(clks.apb1(), spi_clk)
+        Self::from_apb1_clk(clks.clock_for(crate::clock::ApbBus::Apb1), spi_clk)
     }
 
     #[cfg(feature = "vor4x")]
     pub fn from_apb1_clk(apb1_clk: Hertz, spi_clk: Hertz) -> Option<Self> {
         clk_div_for_target_clock(apb1_clk, spi_clk).map(|div| spi_clk_config_from_div(div).unwrap())
     }
+
+    /// Find the clock configuration which is closest to the requested SCK frequency, and check
+    /// that the frequency error does not exceed the given tolerance.
+    ///
+    /// The reference clock is the system clock on Vorago 1x devices and the APB1 clock on Vorago
+    /// 4x devices. All combinations of the prescaler and the SCRDV value are considered, so the
+    /// result can be closer to the requested frequency than the one of [Self::from_div].
+    ///
+    /// Returns the configuration together with the achieved SCK frequency. If the achievable
+    /// frequency is not within `max_error_ppm` of the requested frequency,
+    /// [SpiClockConfigError::ToleranceExceeded] is returned.
+    pub fn from_clk_checked(
+        ref_clk: Hertz,
+        spi_clk: Hertz,
+        max_error_ppm: u32,
+        rounding: ClockRounding,
+    ) -> Result<(Self, Hertz), SpiClockConfigError> {
+        if spi_clk.raw() == 0 {
+            return Err(SpiClockConfigError::DivIsZero);
+        }
+        let (cfg, achieved) = best_clk_config(ref_clk.raw(), spi_clk.raw(), rounding)
+            .ok_or(SpiClockConfigError::ScrdvValueTooLarge)?;
+        let achieved = Hertz::from_raw(achieved);
+        if clk_error_ppm(spi_clk.raw(), achieved.raw()) > max_error_ppm {
+            return Err(SpiClockConfigError::ToleranceExceeded {
+                requested: spi_clk,
+                achievable: achieved,
+            });
+        }
+        Ok((cfg, achieved))
+    }
+
+    /// SCK frequency generated with this configuration from the given reference clock, rounded
+    /// down to full Hertz.
+    ///
+    /// The reference clock is the system clock on Vorago 1x devices and the APB1 clock on Vorago
+    /// 4x devices.
+    pub const fn achieved_clk(&self, ref_clk: Hertz) -> Hertz {
+        let div = self.prescale_val as u32 * (self.scrdv as u32 + 1);
+        if div == 0 {
+            return Hertz::from_raw(0);
+        }
+        Hertz::from_raw(ref_clk.raw() / div)
+    }
 }
 
-#[derive(Debug, thiserror::Error)]
+/// Rounding of the SCK frequency for [SpiClockConfig::from_clk_checked].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClockRounding {
+    /// Use the achievable frequency which is closest to the requested frequency.
+    #[default]
+    Nearest,
+    /// Use the closest achievable frequency which does not exceed the requested frequency, for
+    /// example for devices with a hard maximum clock frequency.
+    NeverFaster,
+}
+
+/// Frequency error in ppm of the requested frequency.
+const fn clk_error_ppm(requested: u32, achieved: u32) -> u32 {
+    let error = (requested.abs_diff(achieved) as u64 * 1_000_000).div_ceil(requested as u64);
+    if error > u32::MAX as u64 {
+        return u32::MAX;
+    }
+    error as u32
+}
+
+/// Returns the configuration with the achieved SCK frequency which is closest to the requested
+/// frequency for the rounding mode. The divider is the even prescaler multiplied with
+/// SCRDV + 1.
+fn best_clk_config(
+    ref_clk: u32,
+    spi_clk: u32,
+    rounding: ClockRounding,
+) -> Option<(SpiClockConfig, u32)> {
+    let mut best: Option<(SpiClockConfig, u32)> = None;
+    for prescale_val in (2..=0xFE_u32).step_by(2) {
+        let base = prescale_val as u64 * spi_clk as u64;
+        let floor = ref_clk as u64 / base;
+        for scrdv_plus_one in [floor, floor + 1] {
+            if !(1..=u8::MAX as u64 + 1).contains(&scrdv_plus_one) {
+                continue;
+            }
+            // Exact check, the achieved frequency is rounded down.
+            if rounding == ClockRounding::NeverFaster && base * scrdv_plus_one < ref_clk as u64 {
+                continue;
+            }
+            let achieved = (ref_clk as u64 / (prescale_val as u64 * scrdv_plus_one)) as u32;
+            if best
+                .is_none_or(|(_, best_clk)| spi_clk.abs_diff(achieved) < spi_clk.abs_diff(best_clk))
+            {
+                best = Some((
+                    SpiClockConfig::new(prescale_val as u8, (scrdv_plus_one - 1) as u8),
+                    achieved,
+                ));
+            }
+        }
+    }
+    best
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SpiClockConfigError {
     #[error("division by zero")]
@@ -407,6 +843,8 @@ pub enum SpiClockConfigError {
     DivideValueNotEven,
     #[error("scrdv value is too large")]
     ScrdvValueTooLarge,
+    #[error("requested SCK frequency {requested} not achievable, closest is {achievable}")]
+    ToleranceExceeded { requested: Hertz, achievable: Hertz },
 }
 
 #[inline]
@@ -414,14 +852,14 @@ pub fn spi_clk_config_from_div(mut div: u16) -> Result<SpiClockConfig, SpiClockC
     if div == 0 {
         return Err(SpiClockConfigError::DivIsZero);
     }
-    if div % 2 != 0 {
+    if !div.is_multiple_of(2) {
         return Err(SpiClockConfigError::DivideValueNotEven);
     }
     let mut prescale_val = 0;
 
     // find largest (even) prescale value that divides into div
     for i in (2..=0xfe).rev().step_by(2) {
-        if div % i == 0 {
+        if div.is_multiple_of(i) {
             prescale_val = i;
             break;
         }
@@ -472,6 +910,205 @@ pub fn clk_div_for_target_clock(sys_clk: Hertz, spi_clk: Hertz) -> Option<u16> {
 #[error("peripheral or peripheral pin ID is not consistent")]
 pub struct SpiIdMissmatchError;
 
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("FIFO prefill {prefill} is not in the range 1..={fifo_depth}")]
+pub struct InvalidFifoPrefillError {
+    pub prefill: usize,
+    pub fifo_depth: usize,
+}
+
+/// Error returned by the [embedded_hal::spi::SpiBus] implementation if
+/// [FlushPolicy::ErrorOnStale] is configured and the RX FIFO contains words at the start of a
+/// transfer.
+///
+/// The stale words are kept in the FIFO and can be retrieved with [Spi::drain_rx].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("{words} stale words in the RX FIFO")]
+pub struct StaleDataInFifoError {
+    /// Number of words in the RX FIFO.
+    pub words: usize,
+}
+
+impl embedded_hal::spi::Error for StaleDataInFifoError {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+impl From<Infallible> for StaleDataInFifoError {
+    fn from(value: Infallible) -> Self {
+        match value {}
+    }
+}
+
+/// Handling of words which are in the RX FIFO at the start of a transfer.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlushPolicy {
+    /// Silently discard all stale words.
+    #[default]
+    DiscardStale,
+    /// Return [StaleDataInFifoError] and keep the stale words in the FIFO. This can reveal
+    /// driver or protocol bugs which would be hidden by discarding the words.
+    ///
+    /// Write-only transfers wait for the transfer to complete and discard the received words
+    /// in this mode, so they do not produce stale words.
+    ErrorOnStale,
+}
+
+const fn validate_fifo_prefill(
+    prefill: usize,
+    fifo_depth: usize,
+) -> Result<(), InvalidFifoPrefillError> {
+    if prefill == 0 || prefill > fifo_depth {
+        return Err(InvalidFifoPrefillError {
+            prefill,
+            fifo_depth,
+        });
+    }
+    Ok(())
+}
+
+/// Number of words written by the initial FIFO pumping of a transfer with the given length.
+#[inline]
+const fn prefill_len(fifo_prefill: usize, len: usize) -> usize {
+    if fifo_prefill < len {
+        fifo_prefill
+    } else {
+        len
+    }
+}
+
+/// Exchanges the words of a blocking full-duplex transfer after the initial FIFO prefill of
+/// `prefilled` words.
+///
+/// The number of words in flight, which were written to the TX FIFO but not read from the RX FIFO
+/// yet, is limited to `max_in_flight`. With the RX FIFO depth as limit, the RX FIFO can not
+/// overflow even if the CPU is much faster than the SPI clock. Both FIFOs are polled without
+/// blocking, so received words are read as soon as they are available.
+fn exchange_words<Buf: ?Sized>(
+    spi: &mut impl SpiLowLevel,
+    buf: &mut Buf,
+    len: usize,
+    prefilled: usize,
+    max_in_flight: usize,
+    write_word: impl Fn(&Buf, usize) -> u32,
+    mut read_word: impl FnMut(&mut Buf, usize, u32),
+) {
+    let mut read_idx = 0;
+    let mut write_idx = prefilled;
+    let mut busy_wait = BusyWait::new();
+    while read_idx < len {
+        busy_wait.tick();
+        if write_idx < len
+            && write_idx - read_idx < max_in_flight
+            && spi.write_fifo(write_word(buf, write_idx)).is_ok()
+        {
+            write_idx += 1;
+        }
+        if read_idx < write_idx
+            && let Ok(word) = spi.read_fifo()
+        {
+            read_word(buf, read_idx, word);
+            read_idx += 1;
+        }
+    }
+}
+
+/// FIFO value for the word at the given index of a transfer with the given length. With
+/// BMSTALL, only the last word of the transfer has the BMSTOP bit set.
+#[inline]
+const fn fifo_word(word: u32, idx: usize, len: usize, bmstall: bool) -> u32 {
+    word | bm_flags(bmstall && idx + 1 == len, false)
+}
+
+/// 16-bit word at the given word index of a byte stream where the first byte of each word pair
+/// is the most significant byte. Missing bytes at the end of the stream are taken from the same
+/// position of the fill word.
+#[inline]
+fn be_word(bytes: &[u8], idx: usize, fill_word: u16) -> u16 {
+    let fill = fill_word.to_be_bytes();
+    let byte = |n: usize| bytes.get(2 * idx + n).copied().unwrap_or(fill[n]);
+    u16::from_be_bytes([byte(0), byte(1)])
+}
+
+/// 16-bit word at the given word index of a byte stream where the first byte of each word pair
+/// is the least significant byte. Missing bytes at the end of the stream are taken from the same
+/// position of the fill word.
+#[inline]
+fn le_word(bytes: &[u8], idx: usize, fill_word: u16) -> u16 {
+    let fill = fill_word.to_le_bytes();
+    let byte = |n: usize| bytes.get(2 * idx + n).copied().unwrap_or(fill[n]);
+    u16::from_le_bytes([byte(0), byte(1)])
+}
+
+/// Store a received 16-bit word at the given word index of a byte stream, most significant byte
+/// first. Bytes which do not fit into the stream are discarded.
+#[inline]
+fn store_be_word(bytes: &mut [u8], idx: usize, word: u16) {
+    for (n, byte) in word.to_be_bytes().into_iter().enumerate() {
+        if let Some(target) = bytes.get_mut(2 * idx + n) {
+            *target = byte;
+        }
+    }
+}
+
+/// Result of [Spi::calibrate_sampling].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SamplingCalibration {
+    /// The test passed with the master delayer capture mode disabled.
+    pub normal_capture_ok: bool,
+    /// The test passed with the master delayer capture mode enabled.
+    pub delayed_capture_ok: bool,
+}
+
+impl SamplingCalibration {
+    /// Recommended master delayer capture setting, or [None] if the test failed for both
+    /// settings.
+    ///
+    /// The normal capture mode is preferred if both settings work, because it is the reset
+    /// configuration of the peripheral.
+    pub const fn recommended(&self) -> Option<bool> {
+        if self.normal_capture_ok {
+            Some(false)
+        } else if self.delayed_capture_ok {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// Working sampling configuration found by [Spi::calibrate_sampling_with_dividers].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SamplingPoint {
+    pub clk_div: u16,
+    pub master_delayer_capture: bool,
+}
+
+/// Control register values written to change the master delayer capture mode: The peripheral
+/// is disabled first, then the mode is changed and the previous enable state is restored with
+/// the new mode.
+const fn mdlycap_ctrl1_sequence(current: regs::Control1, enable: bool) -> [regs::Control1; 3] {
+    let disabled = current.with_enable(false);
+    let changed = disabled.with_mdlycap(enable);
+    [disabled, changed, changed.with_enable(current.enable())]
+}
+
+/// CTRL1 values written when a transfer is aborted: The peripheral is disabled with the block
+/// mode start and the TX pause cleared, and re-enabled after the FIFOs were cleared.
+const fn abort_ctrl1_sequence(current: regs::Control1) -> [regs::Control1; 2] {
+    let disabled = current
+        .with_enable(false)
+        .with_bm_start(false)
+        .with_mtxpause(false);
+    [disabled, disabled.with_enable(true)]
+}
+
 /// SPI peripheral driver structure.
 pub struct Spi<Word = u8> {
     id: Bank,
@@ -481,6 +1118,11 @@ pub struct Spi<Word = u8> {
     fill_word: Word,
     blockmode: bool,
     bmstall: bool,
+    cs: ChipSelect,
+    fifo_prefill: usize,
+    flush_policy: FlushPolicy,
+    /// Control register and interrupt enables recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<(regs::Control1, InterruptControl)>,
     word: PhantomData<Word>,
 }
 
@@ -490,23 +1132,31 @@ where
 {
     /// Create a new SPI struct for using SPI with the fixed ROM SPI pins.
     ///
+    /// The ROM SPI pins are dedicated pins without an IOCONFIG entry, so no pin configuration
+    /// is performed, see [RomSck]. The [RomCs] can be used with [configure_pin_as_hw_cs_pin] to
+    /// retrieve the chip select ID of the boot memory.
+    ///
     /// ## Arguments
     ///
-    /// * `spi` - SPI bus to use
+    /// * `spi` - SPI bus to use, which must be [ROM_SPI_BANK]
+    /// * `pins` - Markers for the dedicated ROM SPI pins
     /// * `spi_cfg` - Configuration specific to the SPI bus
+    /// * `transfer_cfg` - Optional transfer configuration which is applied after the
+    ///   initialization, for example to set the clock and mode required by the boot memory.
     pub fn new_for_rom<SpiI: SpiInstance>(
         spi: SpiI,
+        _pins: (RomSck, RomMiso, RomMosi),
         spi_cfg: SpiConfig,
+        transfer_cfg: Option<&TransferConfig>,
     ) -> Result<Self, SpiIdMissmatchError> {
-        #[cfg(feature = "vor1x")]
-        if SpiI::ID != Bank::Spi2 {
+        if SpiI::ID != ROM_SPI_BANK {
             return Err(SpiIdMissmatchError);
         }
-        #[cfg(feature = "vor4x")]
-        if SpiI::ID != Bank::Spi3 {
-            return Err(SpiIdMissmatchError);
+        let mut spi = Self::new_generic(spi, spi_cfg);
+        if let Some(transfer_cfg) = transfer_cfg {
+            spi.cfg_transfer(transfer_cfg);
         }
-        Ok(Self::new_generic(spi, spi_cfg))
+        Ok(spi)
     }
 
     /// Create a new SPI peripheral driver.
@@ -532,33 +1182,91 @@ where
     }
 
     pub fn new_generic<SpiI: SpiInstance>(_spi: SpiI, spi_cfg: SpiConfig) -> Self {
-        enable_peripheral_clock(SpiI::PERIPH_SEL);
-        let mut regs = regs::Spi::new_mmio(SpiI::ID);
-        let (cpo_bit, cph_bit) = mode_to_cpo_cph_bit(spi_cfg.init_mode);
-        regs.write_ctrl0(
+        // Safety: The peripheral singleton was passed to the constructor.
+        let spi = unsafe { Self::steal(SpiI::ID, spi_cfg) };
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Spi(SpiI::ID));
+        spi
+    }
+
+    /// Create a SPI driver for the given bank without requiring the peripheral singleton.
+    ///
+    /// The peripheral is initialized like in [Self::new_generic], no pins are configured.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL. The caller must ensure that the
+    /// SPI is not used by another driver. Debug builds panic if the SPI is owned by a driver,
+    /// see [crate::ownership].
+    #[track_caller]
+    pub unsafe fn steal(bank: Bank, spi_cfg: SpiConfig) -> Self {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::Spi(bank));
+        let mut spi = Spi {
+            id: bank,
+            regs: regs::Spi::new_mmio(bank),
+            cfg: spi_cfg,
+            fill_word: Default::default(),
+            bmstall: spi_cfg.bmstall,
+            blockmode: spi_cfg.blockmode,
+            cs: ChipSelect::initial(&spi_cfg),
+            fifo_prefill: spi_cfg
+                .fifo_prefill
+                .unwrap_or(bank.fifo_depth() - DEFAULT_PREFILL_HEADROOM)
+                .min(bank.fifo_depth()),
+            flush_policy: FlushPolicy::default(),
+            sleep_state: None,
+            word: PhantomData,
+        };
+        spi.init_peripheral();
+        spi
+    }
+
+    /// Reset the peripheral and replay the full register initialization with the configuration
+    /// passed to the constructor.
+    ///
+    /// This can be used to recover from faults like single event upsets without re-creating the
+    /// driver and losing its pins. The interrupt enables are restored to the values before the
+    /// reset. Configuration changes applied after construction, for example with
+    /// [Self::cfg_transfer], need to be applied again.
+    pub fn reinitialize(&mut self) {
+        let irq_enb = self.regs.read_irq_enb();
+        reset_peripheral_for_cycles(self.id.periph_sel(), 2);
+        self.blockmode = self.cfg.blockmode;
+        self.bmstall = self.cfg.bmstall;
+        self.cs = ChipSelect::initial(&self.cfg);
+        self.init_peripheral();
+        self.regs.write_irq_enb(irq_enb);
+    }
+
+    fn init_peripheral(&mut self) {
+        enable_peripheral_clock(self.id.periph_sel());
+        let (cpo_bit, cph_bit) = mode_to_cpo_cph_bit(self.cfg.init_mode);
+        self.regs.write_ctrl0(
             regs::Control0::builder()
-                .with_scrdv(spi_cfg.clk.scrdv)
+                .with_scrdv(self.cfg.clk.scrdv)
                 .with_sph(cph_bit)
                 .with_spo(cpo_bit)
                 .with_word_size(Word::WORD_SIZE)
                 .build(),
         );
-        regs.write_ctrl1(
-            regs::Control1::builder()
-                .with_mtxpause(false)
-                .with_mdlycap(spi_cfg.master_delayer_capture)
-                .with_bm_stall(spi_cfg.bmstall)
-                .with_bm_start(false)
-                .with_blockmode(spi_cfg.blockmode)
-                .with_ss(HwChipSelectId::Id0)
-                .with_sod(spi_cfg.slave_output_disable)
-                .with_slave_mode(false)
-                .with_enable(false)
-                .with_lbm(spi_cfg.loopback_mode)
-                .build(),
+        self.regs.write_ctrl1(
+            self.cs.apply(
+                regs::Control1::builder()
+                    .with_mtxpause(false)
+                    .with_mdlycap(self.cfg.master_delayer_capture)
+                    .with_bm_stall(self.cfg.bmstall)
+                    .with_bm_start(false)
+                    .with_blockmode(self.cfg.blockmode)
+                    .with_ss(HwChipSelectId::Id0)
+                    .with_sod(false)
+                    .with_slave_mode(false)
+                    .with_enable(false)
+                    .with_lbm(self.cfg.loopback_mode)
+                    .build(),
+            ),
         );
-        regs.write_clkprescale(ClockPrescaler::new(spi_cfg.clk.prescale_val));
-        regs.write_fifo_clear(
+        self.regs
+            .write_clkprescale(ClockPrescaler::new(self.cfg.clk.prescale_val));
+        self.regs.write_fifo_clear(
             FifoClear::builder()
                 .with_tx_fifo(true)
                 .with_rx_fifo(true)
@@ -566,19 +1274,10 @@ where
         );
         // Enable the peripheral as the last step as recommended in the
         // programmers guide
-        regs.modify_ctrl1(|mut value| {
+        self.regs.modify_ctrl1(|mut value| {
             value.set_enable(true);
             value
         });
-        Spi {
-            id: SpiI::ID,
-            regs: regs::Spi::new_mmio(SpiI::ID),
-            cfg: spi_cfg,
-            fill_word: Default::default(),
-            bmstall: spi_cfg.bmstall,
-            blockmode: spi_cfg.blockmode,
-            word: PhantomData,
-        }
     }
 
     #[inline]
@@ -591,10 +1290,74 @@ where
             .write_clkprescale(regs::ClockPrescaler::new(cfg.prescale_val));
     }
 
+    /// SCK frequency generated with the current prescaler and SCRDV register values, see
+    /// [SpiClockConfig::achieved_clk].
+    pub fn current_sck(&self, ref_clk: Hertz) -> Hertz {
+        SpiClockConfig::new(
+            self.regs.read_clkprescale().value(),
+            self.regs.read_ctrl0().scrdv(),
+        )
+        .achieved_clk(ref_clk)
+    }
+
     pub fn set_fill_word(&mut self, fill_word: Word) {
         self.fill_word = fill_word;
     }
 
+    /// Depth of the TX and RX FIFO in words.
+    #[inline]
+    pub const fn fifo_depth(&self) -> usize {
+        self.id.fifo_depth()
+    }
+
+    /// Number of words written to the FIFO at the start of a transfer, see
+    /// [SpiConfig::fifo_prefill].
+    #[inline]
+    pub const fn fifo_prefill(&self) -> usize {
+        self.fifo_prefill
+    }
+
+    /// Set the number of words written to the FIFO at the start of a transfer, see
+    /// [SpiConfig::fifo_prefill].
+    pub fn set_fifo_prefill(&mut self, prefill: usize) -> Result<(), InvalidFifoPrefillError> {
+        validate_fifo_prefill(prefill, self.fifo_depth())?;
+        self.fifo_prefill = prefill;
+        Ok(())
+    }
+
+    /// Set the handling of words which are in the RX FIFO at the start of a transfer of the
+    /// [embedded_hal::spi::SpiBus] implementation or [Self::write_vectored].
+    ///
+    /// [Self::transfer_with_timeout] always discards stale words.
+    #[inline]
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.flush_policy = policy;
+    }
+
+    #[inline]
+    pub const fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+
+    /// Number of words currently in the RX FIFO.
+    #[inline]
+    pub fn rx_fifo_level(&self) -> usize {
+        self.regs.read_state().rx_fifo() as usize
+    }
+
+    /// Read the words in the RX FIFO into the given buffer without starting a transfer.
+    ///
+    /// Returns the number of read words. Reading stops when the FIFO is empty or the buffer is
+    /// full.
+    pub fn drain_rx(&mut self, buf: &mut [Word]) -> usize {
+        let mut read = 0;
+        while read < buf.len() && self.regs.read_status().rx_not_empty() {
+            buf[read] = word_from_fifo(self.read_fifo_unchecked());
+            read += 1;
+        }
+        read
+    }
+
     #[inline]
     pub fn cfg_clock_from_div(&mut self, div: u16) -> Result<(), SpiClockConfigError> {
         let val = spi_clk_config_from_div(div)?;
@@ -612,13 +1375,94 @@ where
         });
     }
 
-    #[inline]
-    pub fn fill_word(&self) -> Word {
-        self.fill_word
+    /// Enable or disable the master delayer capture mode at run-time.
+    ///
+    /// The peripheral is disabled while the control register is changed, so this must only be
+    /// called while no transfer is ongoing. A disabled peripheral stays disabled. The setting is
+    /// also stored in the configuration used by [Self::reinitialize]. See
+    /// [SpiConfig::master_delayer_capture] for more details.
+    pub fn set_master_delayer_capture(&mut self, enable: bool) {
+        for value in mdlycap_ctrl1_sequence(self.regs.read_ctrl1(), enable) {
+            self.regs.write_ctrl1(value);
+        }
+        self.cfg.master_delayer_capture = enable;
     }
 
+    /// Current master delayer capture mode setting.
     #[inline]
-    pub fn clear_tx_fifo(&mut self) {
+    pub fn master_delayer_capture(&self) -> bool {
+        self.regs.read_ctrl1().mdlycap()
+    }
+
+    /// Determine a working MISO sampling point at the current clock and mode.
+    ///
+    /// The user provided `test_device` closure is called once with the master delayer capture
+    /// mode disabled and once with the mode enabled. It should perform a transfer with a known
+    /// response, for example reading an ID register of the slave device, and return whether the
+    /// response was correct. The [recommended][SamplingCalibration::recommended] setting is
+    /// applied afterwards. If both tests failed, the original setting is restored.
+    ///
+    /// The result is only valid for the current clock configuration and SPI mode, so this
+    /// needs to be repeated after changing either of them.
+    pub fn calibrate_sampling(
+        &mut self,
+        test_device: &mut impl FnMut(&mut Spi<Word>) -> bool,
+    ) -> SamplingCalibration {
+        let original = self.master_delayer_capture();
+        self.set_master_delayer_capture(false);
+        let normal_capture_ok = test_device(self);
+        self.set_master_delayer_capture(true);
+        let delayed_capture_ok = test_device(self);
+        let calibration = SamplingCalibration {
+            normal_capture_ok,
+            delayed_capture_ok,
+        };
+        self.set_master_delayer_capture(calibration.recommended().unwrap_or(original));
+        calibration
+    }
+
+    /// Determine a working MISO sampling point for a list of clock dividers.
+    ///
+    /// The dividers are tried in the given order, so they should be sorted from the fastest to
+    /// the slowest clock. For each divider, [Self::calibrate_sampling] is performed and the first
+    /// working divider and master delayer capture setting is applied and returned. If no
+    /// working setting is found, the original clock configuration and capture mode are restored
+    /// and [None] is returned. All dividers are validated before the first test is performed.
+    pub fn calibrate_sampling_with_dividers(
+        &mut self,
+        clk_divs: &[u16],
+        test_device: &mut impl FnMut(&mut Spi<Word>) -> bool,
+    ) -> Result<Option<SamplingPoint>, SpiClockConfigError> {
+        for clk_div in clk_divs {
+            spi_clk_config_from_div(*clk_div)?;
+        }
+        let original_clk = SpiClockConfig::new(
+            self.regs.read_clkprescale().value(),
+            self.regs.read_ctrl0().scrdv(),
+        );
+        let original_mdlycap = self.master_delayer_capture();
+        for clk_div in clk_divs {
+            self.cfg_clock_from_div(*clk_div)?;
+            if let Some(master_delayer_capture) = self.calibrate_sampling(test_device).recommended()
+            {
+                return Ok(Some(SamplingPoint {
+                    clk_div: *clk_div,
+                    master_delayer_capture,
+                }));
+            }
+        }
+        self.cfg_clock(original_clk);
+        self.set_master_delayer_capture(original_mdlycap);
+        Ok(None)
+    }
+
+    #[inline]
+    pub fn fill_word(&self) -> Word {
+        self.fill_word
+    }
+
+    #[inline]
+    pub fn clear_tx_fifo(&mut self) {
         self.regs.write_fifo_clear(
             regs::FifoClear::builder()
                 .with_tx_fifo(true)
@@ -637,11 +1481,44 @@ where
         );
     }
 
+    /// Abort an in-progress transfer and recover the bus.
+    ///
+    /// This can be used if a transfer went wrong, for example because the device signalled an
+    /// error on an external line, or if the bus is needed by a higher priority task. The
+    /// blocking transfer methods can not be interrupted, so this is intended to be called from
+    /// another context after the peripheral was taken over, or after a transfer returned early.
+    ///
+    /// 1. The peripheral is disabled. The block mode start and TX pause flags are cleared, so the
+    ///    hardware chip select is deasserted and the current block mode frame is terminated.
+    /// 2. Both FIFOs and the RX timeout and RX overrun interrupts are cleared.
+    /// 3. The peripheral is re-enabled.
+    ///
+    /// CTRL0, the other CTRL1 fields like the chip select, block mode and BMSTALL settings, and
+    /// the clock prescaler are preserved. The interrupt enables are not changed. The fill word
+    /// is reset to its default value like after construction, and the tracked block mode and
+    /// BMSTALL settings are synchronized with CTRL1.
+    ///
+    /// Chip selects which are not driven by the peripheral, see [ChipSelect::Gpio], are
+    /// controlled by the caller and need to be deasserted by it.
+    pub fn abort_and_recover(&mut self) {
+        self.abort_transfer();
+        let ctrl1 = self.regs.read_ctrl1();
+        self.blockmode = ctrl1.blockmode();
+        self.bmstall = ctrl1.bm_stall();
+        self.fill_word = Default::default();
+    }
+
     #[inline]
     pub fn perid(&self) -> u32 {
         self.regs.read_perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.perid() == regs::EXPECTED_PERID
+    }
+
     /// Configure the hardware chip select given a hardware chip select ID.
     ///
     /// The pin also needs to be configured to be used as a HW CS pin. This can be done
@@ -649,26 +1526,104 @@ where
     /// corresponding [HwChipSelectId].
     #[inline]
     pub fn cfg_hw_cs(&mut self, hw_cs: HwChipSelectId) {
-        self.regs.modify_ctrl1(|mut value| {
-            value.set_sod(false);
-            value.set_ss(hw_cs);
-            value
-        });
+        self.cfg_cs(ChipSelect::Hw(hw_cs));
+    }
+
+    /// Configure the hardware chip select of the given chip select pin.
+    ///
+    /// Returns [SpiIdMissmatchError] if the pin belongs to another SPI bank.
+    pub fn cfg_hw_cs_pin<P: HwCsProvider>(
+        &mut self,
+        pin: &HwCsPin<P>,
+    ) -> Result<(), SpiIdMissmatchError> {
+        if pin.bank() != self.id {
+            return Err(SpiIdMissmatchError);
+        }
+        self.cfg_hw_cs(pin.cs_id());
+        Ok(())
     }
 
     /// Disables the hardware chip select functionality. This can be used when performing
     /// external chip select handling, for example with GPIO pins.
     #[inline]
     pub fn cfg_hw_cs_disable(&mut self) {
-        self.regs.modify_ctrl1(|mut value| {
-            value.set_sod(true);
-            value
+        self.cfg_cs(ChipSelect::Gpio);
+    }
+
+    /// Configure the chip select handling.
+    pub fn cfg_cs(&mut self, cs: ChipSelect) {
+        self.cs = cs;
+        self.regs.modify_ctrl1(|value| cs.apply(value));
+    }
+
+    /// Currently active chip select handling.
+    #[inline]
+    pub const fn current_cs(&self) -> ChipSelect {
+        self.cs
+    }
+
+    /// Set the RX FIFO trigger level. The RX trigger interrupt and status flag are active if
+    /// the RX FIFO count is larger than or equal to this level.
+    #[inline]
+    pub fn set_rx_fifo_trigger(&mut self, level: u5) {
+        self.regs.write_rx_fifo_trigger(TriggerLevel::new(level));
+    }
+
+    /// Set the TX FIFO trigger level. The TX trigger interrupt and status flag are active if
+    /// the TX FIFO count is smaller than or equal to this level.
+    #[inline]
+    pub fn set_tx_fifo_trigger(&mut self, level: u5) {
+        self.regs.write_tx_fifo_trigger(TriggerLevel::new(level));
+    }
+
+    /// Enable the given interrupt sources. Other interrupt sources are not affected.
+    ///
+    /// This only enables the interrupts in the peripheral, the interrupt still needs to be
+    /// enabled in the NVIC.
+    pub fn enable_interrupts(&mut self, irqs: SpiInterrupts) {
+        self.regs.modify_irq_enb(|value| {
+            InterruptControl::new_with_raw_value(
+                value.raw_value() | InterruptControl::from(irqs).raw_value(),
+            )
+        });
+    }
+
+    /// Disable the given interrupt sources. Other interrupt sources are not affected.
+    pub fn disable_interrupts(&mut self, irqs: SpiInterrupts) {
+        self.regs.modify_irq_enb(|value| {
+            InterruptControl::new_with_raw_value(
+                value.raw_value() & !InterruptControl::from(irqs).raw_value(),
+            )
         });
     }
 
+    /// Read the status of all enabled interrupts and clear the RX timeout and RX overrun
+    /// interrupts if they are active. The trigger interrupts can not be cleared and are only
+    /// deactivated by reading from or writing to the FIFOs.
+    pub fn read_and_clear_irq_status(&mut self) -> SpiIrqStatus {
+        let status = self.regs.read_irq_status();
+        if status.rx_timeout() || status.rx_overrun() {
+            self.regs.write_irq_clear(
+                InterruptClear::new_with_raw_value(0)
+                    .with_rx_timeout(status.rx_timeout())
+                    .with_rx_overrun(status.rx_overrun()),
+            );
+        }
+        SpiIrqStatus {
+            tx_trigger: status.tx(),
+            rx_trigger: status.rx(),
+            rx_timeout: status.rx_timeout(),
+            rx_overrun: status.rx_overrun(),
+        }
+    }
+
     /// Utility function to configure all relevant transfer parameters in one go.
     /// This is useful if multiple devices with different clock and mode configurations
     /// are connected to one bus.
+    ///
+    /// If [TransferConfig::sod] is set, the hardware chip select is disabled. Otherwise, the
+    /// hardware chip select is only changed if [TransferConfig::hw_cs] contains an ID. A
+    /// previously disabled hardware chip select is never re-enabled without an explicit ID.
     pub fn cfg_transfer(&mut self, transfer_cfg: &TransferConfig) {
         if let Some(trans_clk_div) = transfer_cfg.clk_cfg {
             self.cfg_clock(trans_clk_div);
@@ -677,88 +1632,302 @@ where
             self.cfg_mode(mode);
         }
         self.blockmode = transfer_cfg.blockmode;
-        self.regs.modify_ctrl1(|mut value| {
-            if transfer_cfg.sod {
-                value.set_sod(transfer_cfg.sod);
-            } else {
-                value.set_sod(false);
-                if let Some(hw_cs) = transfer_cfg.hw_cs {
-                    value.set_ss(hw_cs);
-                }
-            }
+        self.cs = self
+            .cs
+            .after_transfer_cfg(transfer_cfg.sod, transfer_cfg.hw_cs);
+        let cs = self.cs;
+        self.regs.modify_ctrl1(|value| {
+            let mut value = cs.apply(value);
             value.set_blockmode(transfer_cfg.blockmode);
             value.set_bm_stall(transfer_cfg.bmstall);
             value
         });
     }
 
-    fn flush_internal(&mut self) {
-        let mut status_reg = self.regs.read_status();
-        while !status_reg.tx_empty() || status_reg.rx_not_empty() || status_reg.busy() {
-            if status_reg.rx_not_empty() {
+    /// Full-duplex transfer with an upper bound for the transfer duration.
+    ///
+    /// This works like [embedded_hal::spi::SpiBus::transfer]: If the read buffer is longer than
+    /// the write buffer, the fill word is sent for the remaining words. Received words which do
+    /// not fit into the read buffer are discarded.
+    ///
+    /// If the deadline expires, the transfer is aborted: The peripheral is disabled, both FIFOs
+    /// are cleared and the peripheral is re-enabled, so it can be used for the next transfer.
+    pub fn transfer_with_timeout(
+        &mut self,
+        read: &mut [Word],
+        write: &[Word],
+        deadline: &mut impl Deadline,
+    ) -> Result<(), TimeoutError> {
+        let fill_word = self.fill_word;
+        self.transfer_words_with_deadline(
+            core::cmp::max(read.len(), write.len()),
+            |idx| write.get(idx).copied().unwrap_or(fill_word).into(),
+            |idx, word| {
+                if let Some(read_word) = read.get_mut(idx) {
+                    *read_word = word_from_fifo(word);
+                }
+            },
+            deadline,
+        )
+    }
+
+    /// Full-duplex transfer of `len` words. The words to send are provided by `write_word` and
+    /// every received word is passed to `read_word`, together with its index.
+    fn transfer_words_with_deadline(
+        &mut self,
+        len: usize,
+        write_word: impl Fn(usize) -> u32,
+        mut read_word: impl FnMut(usize, u32),
+        deadline: &mut impl Deadline,
+    ) -> Result<(), TimeoutError> {
+        if len == 0 {
+            return Ok(());
+        }
+        let result = poll_with_deadline(deadline, || {
+            let status = self.regs.read_status();
+            if status.rx_not_empty() {
                 self.read_fifo_unchecked();
             }
-            status_reg = self.regs.read_status();
+            (status.tx_empty() && !status.rx_not_empty() && !status.busy()).then_some(())
+        })
+        .and_then(|_| {
+            let mut read_idx = 0;
+            let mut write_idx = 0;
+            poll_with_deadline(deadline, || {
+                let initial_fill = write_idx == 0 && self.blockmode;
+                if initial_fill {
+                    self.regs.modify_ctrl1(|mut value| {
+                        value.set_mtxpause(true);
+                        value
+                    });
+                }
+                // Limit the number of words in flight so the RX FIFO can not overflow.
+                while write_idx < len
+                    && write_idx - read_idx < self.fifo_prefill
+                    && self.regs.read_status().tx_not_full()
+                {
+                    self.write_fifo_unchecked(fifo_word(
+                        write_word(write_idx),
+                        write_idx,
+                        len,
+                        self.bmstall,
+                    ));
+                    write_idx += 1;
+                }
+                if initial_fill {
+                    self.regs.modify_ctrl1(|mut value| {
+                        value.set_mtxpause(false);
+                        value
+                    });
+                }
+                while read_idx < write_idx && self.regs.read_status().rx_not_empty() {
+                    let word = self.read_fifo_unchecked();
+                    read_word(read_idx, word);
+                    read_idx += 1;
+                }
+                (read_idx == len).then_some(())
+            })
+        });
+        if result.is_err() {
+            self.abort_transfer();
         }
+        result
     }
 
-    fn transfer_preparation(&mut self, words: &[Word]) -> Result<(), Infallible> {
-        if words.is_empty() {
+    /// Write the words of multiple slices, in order, without copying them into one contiguous
+    /// buffer first.
+    ///
+    /// In blockmode, all words are sent in a single frame, so the chip select stays asserted
+    /// across all slices. If BMSTALL is enabled, only the last word of the last non-empty slice
+    /// has the BMSTOP bit set. Empty slices are skipped. Received words are discarded like for
+    /// [embedded_hal::spi::SpiBus::write]. The [FlushPolicy] is applied at the start of the
+    /// transfer.
+    pub fn write_vectored(&mut self, words_list: &[&[Word]]) -> Result<(), StaleDataInFifoError> {
+        let len: usize = words_list.iter().map(|words| words.len()).sum();
+        let bmstall = self.bmstall;
+        let words = words_list.iter().flat_map(|words| words.iter());
+        self.write_words(
+            len,
+            words
+                .enumerate()
+                .map(|(idx, word)| fifo_word((*word).into(), idx, len, bmstall)),
+        )
+    }
+
+    /// Write words with explicit block mode flags. Received words are discarded like for
+    /// [embedded_hal::spi::SpiBus::write].
+    ///
+    /// Unlike the [embedded_hal::spi::SpiBus] implementation, which sets the BMSTOP bit on the
+    /// last word of a transfer if BMSTALL is enabled, no flags are added. This allows frames
+    /// whose length is decided while the transfer is ongoing, across multiple calls. See
+    /// [RawSpiWord] for the meaningful combinations with the blockmode and BMSTALL settings.
+    /// The data bits above the configured word size are ignored.
+    pub fn write_raw_words(&mut self, words: &[RawSpiWord]) -> Result<(), StaleDataInFifoError> {
+        self.write_words(words.len(), words.iter().map(RawSpiWord::raw_value))
+    }
+
+    /// Full-duplex transfer of words with explicit block mode flags, see [Self::write_raw_words].
+    ///
+    /// The received FIFO values are stored without masking, so the BMSTART bit
+    /// ([BMSTART_BMSTOP_MASK]) marks the first word of a frame in blockmode. A word is received
+    /// for every written word except for frame end words ([RawSpiWord::is_received]). Received
+    /// words which do not fit into the read buffer are discarded.
+    pub fn transfer_raw_words(
+        &mut self,
+        read: &mut [u32],
+        write: &[RawSpiWord],
+    ) -> Result<(), StaleDataInFifoError> {
+        if write.is_empty() {
             return Ok(());
         }
-        self.flush_internal();
+        self.handle_stale_words()?;
+        let prefilled = self.initial_send_fifo_pumping(write.len(), |idx| write[idx].raw_value());
+        let mut expected = write[..prefilled]
+            .iter()
+            .filter(|word| word.is_received())
+            .count();
+        let max_in_flight = self.fifo_depth();
+        let mut read_idx = 0;
+        let mut write_idx = prefilled;
+        let mut busy_wait = BusyWait::new();
+        while write_idx < write.len() || read_idx < expected {
+            busy_wait.tick();
+            if write_idx < write.len()
+                && expected - read_idx < max_in_flight
+                && self.write_fifo(write[write_idx].raw_value()).is_ok()
+            {
+                if write[write_idx].is_received() {
+                    expected += 1;
+                }
+                write_idx += 1;
+            }
+            if read_idx < expected
+                && let Ok(word) = self.read_fifo()
+            {
+                if let Some(read_word) = read.get_mut(read_idx) {
+                    *read_word = word;
+                }
+                read_idx += 1;
+            }
+        }
         Ok(())
     }
 
-    // The FIFO can hold a guaranteed amount of data, so we can pump it on transfer
-    // initialization. Returns the amount of written bytes.
-    fn initial_send_fifo_pumping_with_words(&mut self, words: &[Word]) -> usize {
-        //let reg_block = self.reg_block();
+    /// Write `len` FIFO values, including the block mode bits, provided by an iterator.
+    /// Received words are discarded.
+    fn write_words(
+        &mut self,
+        len: usize,
+        words: impl Iterator<Item = u32>,
+    ) -> Result<(), StaleDataInFifoError> {
+        if len == 0 {
+            return Ok(());
+        }
+        self.handle_stale_words()?;
         if self.blockmode {
             self.regs.modify_ctrl1(|mut value| {
                 value.set_mtxpause(true);
                 value
             });
         }
-        // Fill the first half of the write FIFO
-        let mut current_write_idx = 0;
-        let smaller_idx = core::cmp::min(FILL_DEPTH, words.len());
-        for _ in 0..smaller_idx {
-            if current_write_idx == smaller_idx.saturating_sub(1) && self.bmstall {
-                self.write_fifo_unchecked(words[current_write_idx].into() | BMSTART_BMSTOP_MASK);
-            } else {
-                self.write_fifo_unchecked(words[current_write_idx].into());
+        let mut busy_wait = BusyWait::new();
+        for (idx, word) in words.take(len).enumerate() {
+            busy_wait.tick();
+            // The FIFO can hold a guaranteed amount of data, so it is pumped first.
+            if idx < self.fifo_prefill {
+                self.write_fifo_unchecked(word);
+                if idx == prefill_len(self.fifo_prefill, len) - 1 && self.blockmode {
+                    self.regs.modify_ctrl1(|mut value| {
+                        value.set_mtxpause(false);
+                        value
+                    });
+                }
+                continue;
+            }
+            nb::block!(self.write_fifo(word))?;
+            // Ignore received words.
+            if self.regs.read_status().rx_not_empty() {
+                self.clear_rx_fifo();
             }
-            current_write_idx += 1;
         }
-        if self.blockmode {
-            self.regs.modify_ctrl1(|mut value| {
-                value.set_mtxpause(false);
-                value
+        self.finish_write();
+        Ok(())
+    }
+
+    /// Abort an ongoing transfer and bring the peripheral back into an idle state.
+    ///
+    /// The peripheral is disabled with the block mode start and the TX pause cleared, which
+    /// deasserts the hardware chip select. Both FIFOs and the RX timeout and RX overrun
+    /// interrupts are cleared before the peripheral is re-enabled. CTRL0, the clock prescaler
+    /// and all other CTRL1 fields are preserved.
+    fn abort_transfer(&mut self) {
+        let [disabled, enabled] = abort_ctrl1_sequence(self.regs.read_ctrl1());
+        self.regs.write_ctrl1(disabled);
+        self.regs.write_fifo_clear(
+            regs::FifoClear::builder()
+                .with_tx_fifo(true)
+                .with_rx_fifo(true)
+                .build(),
+        );
+        self.regs.write_irq_clear(
+            InterruptClear::new_with_raw_value(0)
+                .with_rx_timeout(true)
+                .with_rx_overrun(true),
+        );
+        self.regs.write_ctrl1(enabled);
+    }
+
+    fn flush_internal(&mut self) {
+        let mut busy_wait = BusyWait::new();
+        let mut status_reg = self.regs.read_status();
+        while !status_reg.tx_empty() || status_reg.rx_not_empty() || status_reg.busy() {
+            busy_wait.tick();
+            if status_reg.rx_not_empty() {
+                self.read_fifo_unchecked();
+            }
+            status_reg = self.regs.read_status();
+        }
+    }
+
+    fn transfer_preparation(&mut self, words: &[Word]) -> Result<(), StaleDataInFifoError> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        self.handle_stale_words()
+    }
+
+    fn handle_stale_words(&mut self) -> Result<(), StaleDataInFifoError> {
+        if self.flush_policy == FlushPolicy::ErrorOnStale && self.regs.read_status().rx_not_empty()
+        {
+            return Err(StaleDataInFifoError {
+                words: self.rx_fifo_level(),
             });
         }
-        current_write_idx
+        self.flush_internal();
+        Ok(())
+    }
+
+    /// Wait for the end of a write-only transfer and discard the received words if stale words
+    /// are reported as an error.
+    fn finish_write(&mut self) {
+        if self.flush_policy == FlushPolicy::ErrorOnStale {
+            self.flush_internal();
+        }
     }
 
     // The FIFO can hold a guaranteed amount of data, so we can pump it on transfer
-    // initialization.
-    fn initial_send_fifo_pumping_with_fill_words(&mut self, send_len: usize) -> usize {
+    // initialization. `word` returns the FIFO value including the block mode bits. Returns the
+    // amount of written words.
+    fn initial_send_fifo_pumping(&mut self, len: usize, word: impl Fn(usize) -> u32) -> usize {
         if self.blockmode {
             self.regs.modify_ctrl1(|mut value| {
                 value.set_mtxpause(true);
                 value
             });
         }
-        // Fill the first half of the write FIFO
-        let mut current_write_idx = 0;
-        let smaller_idx = core::cmp::min(FILL_DEPTH, send_len);
-        for _ in 0..smaller_idx {
-            if current_write_idx == smaller_idx.saturating_sub(1) && self.bmstall {
-                self.write_fifo_unchecked(self.fill_word.into() | BMSTART_BMSTOP_MASK);
-            } else {
-                self.write_fifo_unchecked(self.fill_word.into());
-            }
-            current_write_idx += 1;
+        let prefill = prefill_len(self.fifo_prefill, len);
+        for idx in 0..prefill {
+            self.write_fifo_unchecked(word(idx));
         }
         if self.blockmode {
             self.regs.modify_ctrl1(|mut value| {
@@ -766,7 +1935,99 @@ where
                 value
             });
         }
-        current_write_idx
+        prefill
+    }
+
+    fn initial_send_fifo_pumping_with_words(&mut self, words: &[Word]) -> usize {
+        let (len, bmstall) = (words.len(), self.bmstall);
+        self.initial_send_fifo_pumping(len, |idx| fifo_word(words[idx].into(), idx, len, bmstall))
+    }
+
+    fn initial_send_fifo_pumping_with_fill_words(&mut self, send_len: usize) -> usize {
+        let (fill_word, bmstall) = (self.fill_word.into(), self.bmstall);
+        self.initial_send_fifo_pumping(send_len, |idx| fifo_word(fill_word, idx, send_len, bmstall))
+    }
+}
+
+/// Byte stream API for 16-bit words.
+///
+/// The SPI peripheral always shifts out the most significant bit of a word first. For a 16-bit
+/// word, bits 15 to 8 are therefore on MOSI before bits 7 to 0. The byte stream functions map
+/// pairs of bytes to words:
+///
+/// - Big endian: The first byte of each pair is the upper byte of the word, so the bytes are on
+///   the wire in the same order as in the slice. A big endian transfer is equivalent to an 8-bit
+///   transfer of the same bytes, except that the chip select is not toggled between byte pairs
+///   in non-blockmode.
+/// - Little endian: The first byte of each pair is the lower byte of the word, so the two bytes
+///   of each pair are swapped on the wire.
+///
+/// A slice with an odd length is padded to a full word with the byte of the fill word at the
+/// missing position: for big endian, the last word consists of the last byte followed by the
+/// lower byte of the fill word. For little endian, the upper byte of the fill word is sent first,
+/// followed by the last byte. The words are converted on the fly, so no intermediate buffer is
+/// required.
+impl Spi<u16> {
+    /// Write a byte stream, with the first byte of each pair being the upper byte of a word.
+    ///
+    /// Works like [embedded_hal::spi::SpiBus::write], with the same [FlushPolicy] handling.
+    pub fn write_be_bytes(&mut self, bytes: &[u8]) -> Result<(), StaleDataInFifoError> {
+        let fill_word = self.fill_word;
+        let len = bytes.len().div_ceil(2);
+        self.write_words(
+            len,
+            (0..len).map(|idx| be_word(bytes, idx, fill_word).into()),
+        )
+    }
+
+    /// Write a byte stream, with the first byte of each pair being the lower byte of a word.
+    ///
+    /// Works like [embedded_hal::spi::SpiBus::write], with the same [FlushPolicy] handling.
+    pub fn write_le_bytes(&mut self, bytes: &[u8]) -> Result<(), StaleDataInFifoError> {
+        let fill_word = self.fill_word;
+        let len = bytes.len().div_ceil(2);
+        self.write_words(
+            len,
+            (0..len).map(|idx| le_word(bytes, idx, fill_word).into()),
+        )
+    }
+
+    /// Full-duplex transfer of big endian byte streams.
+    ///
+    /// Works like [embedded_hal::spi::SpiBus::transfer]: The number of transferred words is
+    /// determined by the longer slice. If the read slice is longer than the write slice, fill
+    /// words are sent for the remaining words. If the read slice has an odd length, the lower
+    /// byte of the last received word is discarded.
+    pub fn transfer_be_bytes(
+        &mut self,
+        read: &mut [u8],
+        write: &[u8],
+    ) -> Result<(), StaleDataInFifoError> {
+        if read.is_empty() && write.is_empty() {
+            return Ok(());
+        }
+        self.handle_stale_words()?;
+        self.transfer_be_bytes_with_timeout(read, write, &mut NoDeadline)
+            .expect("transfer without deadline can not time out");
+        Ok(())
+    }
+
+    /// [Self::transfer_be_bytes] with an upper bound for the transfer duration.
+    ///
+    /// The transfer is aborted like for [Self::transfer_with_timeout] if the deadline expires.
+    pub fn transfer_be_bytes_with_timeout(
+        &mut self,
+        read: &mut [u8],
+        write: &[u8],
+        deadline: &mut impl Deadline,
+    ) -> Result<(), TimeoutError> {
+        let fill_word = self.fill_word;
+        self.transfer_words_with_deadline(
+            core::cmp::max(read.len(), write.len()).div_ceil(2),
+            |idx| be_word(write, idx, fill_word).into(),
+            |idx, word| store_be_word(read, idx, (word & u16::MASK) as u16),
+            deadline,
+        )
     }
 }
 
@@ -803,116 +2064,151 @@ where
 }
 
 impl<Word: SpiWord> embedded_hal::spi::ErrorType for Spi<Word> {
-    type Error = Infallible;
+    type Error = StaleDataInFifoError;
 }
 
-impl<Word: SpiWord> embedded_hal::spi::SpiBus<Word> for Spi<Word>
+impl<Word: SpiWord> Spi<Word>
 where
     <Word as TryFrom<u32>>::Error: core::fmt::Debug,
 {
-    fn read(&mut self, words: &mut [Word]) -> Result<(), Self::Error> {
+    fn bus_read(&mut self, words: &mut [Word]) -> Result<(), StaleDataInFifoError> {
         self.transfer_preparation(words)?;
-        let mut current_read_idx = 0;
-        let mut current_write_idx = self.initial_send_fifo_pumping_with_fill_words(words.len());
-        loop {
-            if current_read_idx < words.len() {
-                words[current_read_idx] = (nb::block!(self.read_fifo())? & Word::MASK)
-                    .try_into()
-                    .unwrap();
-                current_read_idx += 1;
-            }
-            if current_write_idx < words.len() {
-                if current_write_idx == words.len() - 1 && self.bmstall {
-                    nb::block!(self.write_fifo(self.fill_word.into() | BMSTART_BMSTOP_MASK))?;
-                } else {
-                    nb::block!(self.write_fifo(self.fill_word.into()))?;
-                }
-                current_write_idx += 1;
-            }
-            if current_read_idx >= words.len() && current_write_idx >= words.len() {
-                break;
-            }
-        }
+        let prefilled = self.initial_send_fifo_pumping_with_fill_words(words.len());
+        let (len, fill_word, bmstall) = (words.len(), self.fill_word.into(), self.bmstall);
+        let max_in_flight = self.fifo_depth();
+        exchange_words(
+            self,
+            words,
+            len,
+            prefilled,
+            max_in_flight,
+            |_, idx| fifo_word(fill_word, idx, len, bmstall),
+            |words, idx, word| words[idx] = word_from_fifo(word),
+        );
         Ok(())
     }
 
-    fn write(&mut self, words: &[Word]) -> Result<(), Self::Error> {
+    fn bus_write(&mut self, words: &[Word]) -> Result<(), StaleDataInFifoError> {
         self.transfer_preparation(words)?;
         let mut current_write_idx = self.initial_send_fifo_pumping_with_words(words);
+        let mut busy_wait = BusyWait::new();
         while current_write_idx < words.len() {
-            if current_write_idx == words.len() - 1 && self.bmstall {
-                nb::block!(self.write_fifo(words[current_write_idx].into() | BMSTART_BMSTOP_MASK))?;
-            } else {
-                nb::block!(self.write_fifo(words[current_write_idx].into()))?;
-            }
+            busy_wait.tick();
+            nb::block!(self.write_fifo(fifo_word(
+                words[current_write_idx].into(),
+                current_write_idx,
+                words.len(),
+                self.bmstall
+            )))?;
             current_write_idx += 1;
             // Ignore received words.
             if self.regs.read_status().rx_not_empty() {
                 self.clear_rx_fifo();
             }
         }
+        self.finish_write();
         Ok(())
     }
 
-    fn transfer(&mut self, read: &mut [Word], write: &[Word]) -> Result<(), Self::Error> {
-        self.transfer_preparation(write)?;
-        let mut current_read_idx = 0;
-        let mut current_write_idx = self.initial_send_fifo_pumping_with_words(write);
-        let max_idx = core::cmp::max(read.len(), write.len());
-        while current_read_idx < read.len() || current_write_idx < write.len() {
-            if current_write_idx < max_idx {
-                if current_write_idx == write.len() - 1 && self.bmstall {
-                    nb::block!(
-                        self.write_fifo(write[current_write_idx].into() | BMSTART_BMSTOP_MASK)
-                    )?;
-                } else if current_write_idx < write.len() {
-                    nb::block!(self.write_fifo(write[current_write_idx].into()))?;
-                } else {
-                    nb::block!(self.write_fifo(0))?;
-                }
-                current_write_idx += 1;
-            }
-            if current_read_idx < max_idx {
-                if current_read_idx < read.len() {
-                    read[current_read_idx] = (nb::block!(self.read_fifo())? & Word::MASK)
-                        .try_into()
-                        .unwrap();
-                } else {
-                    nb::block!(self.read_fifo()).unwrap();
-                }
-                current_read_idx += 1;
-            }
+    fn bus_transfer(
+        &mut self,
+        read: &mut [Word],
+        write: &[Word],
+    ) -> Result<(), StaleDataInFifoError> {
+        let len = core::cmp::max(read.len(), write.len());
+        if len == 0 {
+            return Ok(());
         }
-
+        self.handle_stale_words()?;
+        let bmstall = self.bmstall;
+        let prefilled = self.initial_send_fifo_pumping(len, |idx| {
+            fifo_word(write.get(idx).map_or(0, |&w| w.into()), idx, len, bmstall)
+        });
+        let max_in_flight = self.fifo_depth();
+        exchange_words(
+            self,
+            read,
+            len,
+            prefilled,
+            max_in_flight,
+            |_, idx| fifo_word(write.get(idx).map_or(0, |&w| w.into()), idx, len, bmstall),
+            |read, idx, word| {
+                if let Some(read_word) = read.get_mut(idx) {
+                    *read_word = word_from_fifo(word);
+                }
+            },
+        );
         Ok(())
     }
 
-    fn transfer_in_place(&mut self, words: &mut [Word]) -> Result<(), Self::Error> {
+    fn bus_transfer_in_place(&mut self, words: &mut [Word]) -> Result<(), StaleDataInFifoError> {
         self.transfer_preparation(words)?;
-        let mut current_read_idx = 0;
-        let mut current_write_idx = self.initial_send_fifo_pumping_with_words(words);
-
-        while current_read_idx < words.len() || current_write_idx < words.len() {
-            if current_write_idx < words.len() {
-                if current_write_idx == words.len() - 1 && self.bmstall {
-                    nb::block!(
-                        self.write_fifo(words[current_write_idx].into() | BMSTART_BMSTOP_MASK)
-                    )?;
-                } else {
-                    nb::block!(self.write_fifo(words[current_write_idx].into()))?;
-                }
-                current_write_idx += 1;
-            }
-            if current_read_idx < words.len() && current_read_idx < current_write_idx {
-                words[current_read_idx] = (nb::block!(self.read_fifo())? & Word::MASK)
-                    .try_into()
-                    .unwrap();
-                current_read_idx += 1;
-            }
-        }
+        let prefilled = self.initial_send_fifo_pumping_with_words(words);
+        let (len, bmstall) = (words.len(), self.bmstall);
+        let max_in_flight = self.fifo_depth();
+        // Words are only overwritten after they were written to the FIFO, because reads never
+        // overtake writes.
+        exchange_words(
+            self,
+            words,
+            len,
+            prefilled,
+            max_in_flight,
+            |words, idx| fifo_word(words[idx].into(), idx, len, bmstall),
+            |words, idx, word| words[idx] = word_from_fifo(word),
+        );
         Ok(())
     }
 
+    #[cfg(feature = "bus-trace")]
+    fn trace(
+        &self,
+        direction: crate::bus_trace::Direction,
+        data: &[Word],
+        result: &Result<(), StaleDataInFifoError>,
+    ) {
+        crate::bus_trace::event(
+            self.id.periph_sel(),
+            direction,
+            None,
+            data,
+            result.as_ref().map(|_| ()),
+        );
+    }
+}
+
+impl<Word: SpiWord> embedded_hal::spi::SpiBus<Word> for Spi<Word>
+where
+    <Word as TryFrom<u32>>::Error: core::fmt::Debug,
+{
+    fn read(&mut self, words: &mut [Word]) -> Result<(), Self::Error> {
+        let result = self.bus_read(words);
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::Read, words, &result);
+        result
+    }
+
+    fn write(&mut self, words: &[Word]) -> Result<(), Self::Error> {
+        let result = self.bus_write(words);
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::Write, words, &result);
+        result
+    }
+
+    fn transfer(&mut self, read: &mut [Word], write: &[Word]) -> Result<(), Self::Error> {
+        let result = self.bus_transfer(read, write);
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::Transfer, read, &result);
+        result
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [Word]) -> Result<(), Self::Error> {
+        let result = self.bus_transfer_in_place(words);
+        #[cfg(feature = "bus-trace")]
+        self.trace(crate::bus_trace::Direction::TransferInPlace, words, &result);
+        result
+    }
+
     fn flush(&mut self) -> Result<(), Self::Error> {
         self.flush_internal();
         Ok(())
@@ -933,6 +2229,10 @@ impl From<Spi<u8>> for Spi<u16> {
             blockmode: old_spi.blockmode,
             fill_word: Default::default(),
             bmstall: old_spi.bmstall,
+            cs: old_spi.cs,
+            fifo_prefill: old_spi.fifo_prefill,
+            flush_policy: old_spi.flush_policy,
+            sleep_state: old_spi.sleep_state,
             word: PhantomData,
         }
     }
@@ -951,7 +2251,551 @@ impl From<Spi<u16>> for Spi<u8> {
             blockmode: old_spi.blockmode,
             fill_word: Default::default(),
             bmstall: old_spi.bmstall,
+            cs: old_spi.cs,
+            fifo_prefill: old_spi.fifo_prefill,
+            flush_policy: old_spi.flush_policy,
+            sleep_state: old_spi.sleep_state,
             word: PhantomData,
         }
     }
 }
+
+impl<Word: SpiWord> SleepParticipant for Spi<Word>
+where
+    <Word as TryFrom<u32>>::Error: core::fmt::Debug,
+{
+    /// Waits until the current transfer is complete, and disables the interrupts and the
+    /// peripheral. The block mode start and the TX pause are cleared as well, so the hardware
+    /// chip select is deasserted. Received words which were not read are discarded.
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_some() {
+            return;
+        }
+        self.flush_internal();
+        self.sleep_state = Some((self.regs.read_ctrl1(), self.regs.read_irq_enb()));
+        self.regs
+            .write_irq_enb(InterruptControl::new_with_raw_value(0));
+        self.regs.modify_ctrl1(|mut value| {
+            value.set_enable(false);
+            value.set_bm_start(false);
+            value.set_mtxpause(false);
+            value
+        });
+    }
+
+    /// Restores the control register and the interrupt enables.
+    fn resume(&mut self) {
+        if let Some((ctrl1, irq_enb)) = self.sleep_state.take() {
+            self.regs.write_ctrl1(ctrl1);
+            self.regs.write_irq_enb(irq_enb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loopback model of the SPI FIFOs with a depth of [MAX_FIFO_DEPTH]. Each FIFO access is one
+    /// CPU step, and one word is shifted from the TX FIFO into the RX FIFO every `steps_per_word`
+    /// steps. The received word is the inverted transmitted word.
+    ///
+    /// Every `irq_interval` steps, the CPU is preempted by an interrupt which takes long enough to
+    /// shift out all words of the TX FIFO.
+    struct FifoModel {
+        tx: heapless::Deque<u32, MAX_FIFO_DEPTH>,
+        rx: heapless::Deque<u32, MAX_FIFO_DEPTH>,
+        steps_per_word: usize,
+        irq_interval: usize,
+        steps: usize,
+    }
+
+    impl FifoModel {
+        fn new(steps_per_word: usize, irq_interval: usize) -> Self {
+            Self {
+                tx: heapless::Deque::new(),
+                rx: heapless::Deque::new(),
+                steps_per_word,
+                irq_interval,
+                steps: 0,
+            }
+        }
+
+        fn shift(&mut self) {
+            if let Some(word) = self.tx.pop_front() {
+                self.rx.push_back(!word & 0xffff).expect("RX FIFO overflow");
+            }
+        }
+
+        fn step(&mut self) {
+            self.steps += 1;
+            if self.steps.is_multiple_of(self.irq_interval) {
+                while !self.tx.is_empty() {
+                    self.shift();
+                }
+            } else if self.steps.is_multiple_of(self.steps_per_word) {
+                self.shift();
+            }
+        }
+    }
+
+    impl SpiLowLevel for FifoModel {
+        fn write_fifo(&mut self, data: u32) -> nb::Result<(), Infallible> {
+            self.step();
+            if self.tx.is_full() {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.write_fifo_unchecked(data);
+            Ok(())
+        }
+
+        fn write_fifo_unchecked(&mut self, data: u32) {
+            self.tx.push_back(data).expect("TX FIFO overflow");
+        }
+
+        fn read_fifo(&mut self) -> nb::Result<u32, Infallible> {
+            self.step();
+            self.rx.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+
+        fn read_fifo_unchecked(&mut self) -> u32 {
+            self.rx.pop_front().unwrap_or(0)
+        }
+    }
+
+    #[test]
+    fn exchange_words_bounds_words_in_flight() {
+        for len in [15, 16, 17, 32, 100] {
+            for (steps_per_word, irq_interval) in [(1, 1000), (3, 1000), (50, 1000), (50, 7)] {
+                for prefill in [0, MAX_FIFO_DEPTH - DEFAULT_PREFILL_HEADROOM, MAX_FIFO_DEPTH] {
+                    let mut fifo = FifoModel::new(steps_per_word, irq_interval);
+                    let mut words = [0u16; 100];
+                    for (idx, word) in words.iter_mut().enumerate() {
+                        *word = idx as u16;
+                    }
+                    let words = &mut words[..len];
+                    let prefilled = prefill_len(prefill, len);
+                    for &word in &words[..prefilled] {
+                        fifo.write_fifo_unchecked(word as u32);
+                    }
+                    // In-place transfer, which overwrites the words with the received words.
+                    exchange_words(
+                        &mut fifo,
+                        words,
+                        len,
+                        prefilled,
+                        MAX_FIFO_DEPTH,
+                        |words, idx| words[idx] as u32,
+                        |words, idx, word| words[idx] = word as u16,
+                    );
+                    for (idx, word) in words.iter().enumerate() {
+                        assert_eq!(*word, !(idx as u16), "len {len}, word {idx}");
+                    }
+                    assert!(fifo.tx.is_empty());
+                    assert!(fifo.rx.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "RX FIFO overflow")]
+    fn exchange_words_unbounded_overflows() {
+        // Without the bound for the words in flight, the TX FIFO is full while the RX FIFO still
+        // contains words when the interrupt occurs.
+        let mut fifo = FifoModel::new(50, 7);
+        let mut words = [0u16; 100];
+        for &word in &words[..MAX_FIFO_DEPTH] {
+            fifo.write_fifo_unchecked(word as u32);
+        }
+        exchange_words(
+            &mut fifo,
+            &mut words[..],
+            100,
+            MAX_FIFO_DEPTH,
+            usize::MAX,
+            |words, idx| words[idx] as u32,
+            |words, idx, word| words[idx] = word as u16,
+        );
+    }
+
+    const ALL_CS_STATES: [ChipSelect; 3] = [
+        ChipSelect::None,
+        ChipSelect::Gpio,
+        ChipSelect::Hw(HwChipSelectId::Id1),
+    ];
+
+    #[test]
+    fn transfer_cfg_cs_transitions() {
+        // (sod, hw_cs) of the transfer configuration.
+        let cfgs = [
+            (false, None),
+            (false, Some(HwChipSelectId::Id2)),
+            (true, None),
+            (true, Some(HwChipSelectId::Id2)),
+        ];
+        for previous in ALL_CS_STATES {
+            // The SS field still contains the last configured ID.
+            let ctrl1 =
+                previous.apply(regs::Control1::new_with_raw_value(0).with_ss(HwChipSelectId::Id1));
+            for (sod, hw_cs) in cfgs {
+                let next = previous.after_transfer_cfg(sod, hw_cs);
+                let expected = match (previous, sod, hw_cs) {
+                    (_, true, _) => ChipSelect::Gpio,
+                    (_, false, Some(id)) => ChipSelect::Hw(id),
+                    (previous, false, None) => previous,
+                };
+                assert_eq!(next, expected, "{previous:?} {sod} {hw_cs:?}");
+                let next_ctrl1 = next.apply(ctrl1);
+                let (expected_sod, expected_ss) = match (previous, sod, hw_cs) {
+                    (_, false, Some(id)) => (false, id),
+                    (ChipSelect::Hw(id), false, None) => (false, id),
+                    // A stale ID is never enabled again.
+                    _ => (true, HwChipSelectId::Id1),
+                };
+                assert_eq!(
+                    next_ctrl1.sod(),
+                    expected_sod,
+                    "{previous:?} {sod} {hw_cs:?}"
+                );
+                assert_eq!(next_ctrl1.ss(), expected_ss, "{previous:?} {sod} {hw_cs:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn initial_cs() {
+        assert_eq!(
+            ChipSelect::initial(&SpiConfig::default()),
+            ChipSelect::Hw(HwChipSelectId::Id0)
+        );
+        assert_eq!(
+            ChipSelect::initial(&SpiConfig::default().slave_output_disable(true)),
+            ChipSelect::None
+        );
+    }
+
+    #[test]
+    fn mdlycap_sequence_disables_peripheral_first() {
+        let current = regs::Control1::builder()
+            .with_mtxpause(false)
+            .with_mdlycap(false)
+            .with_bm_stall(true)
+            .with_bm_start(false)
+            .with_blockmode(true)
+            .with_ss(HwChipSelectId::Id2)
+            .with_sod(false)
+            .with_slave_mode(false)
+            .with_enable(true)
+            .with_lbm(false)
+            .build();
+        let [disabled, changed, enabled] = mdlycap_ctrl1_sequence(current, true);
+        assert!(!disabled.enable());
+        assert!(!disabled.mdlycap());
+        assert!(!changed.enable());
+        assert!(changed.mdlycap());
+        assert!(enabled.enable());
+        assert!(enabled.mdlycap());
+        // All other fields are preserved.
+        assert_eq!(enabled.raw_value(), current.with_mdlycap(true).raw_value());
+
+        let [disabled, changed, enabled] = mdlycap_ctrl1_sequence(enabled, false);
+        assert!(!disabled.enable());
+        assert!(disabled.mdlycap());
+        assert!(!changed.enable());
+        assert!(!changed.mdlycap());
+        assert_eq!(enabled.raw_value(), current.raw_value());
+
+        // A disabled peripheral is not enabled.
+        let disabled_current = current.with_enable(false);
+        let [disabled, changed, restored] = mdlycap_ctrl1_sequence(disabled_current, true);
+        assert!(!disabled.enable());
+        assert!(!changed.enable());
+        assert!(changed.mdlycap());
+        assert!(!restored.enable());
+        assert_eq!(
+            restored.raw_value(),
+            disabled_current.with_mdlycap(true).raw_value()
+        );
+    }
+
+    #[test]
+    fn abort_ctrl1_values() {
+        let current = regs::Control1::builder()
+            .with_mtxpause(true)
+            .with_mdlycap(true)
+            .with_bm_stall(true)
+            .with_bm_start(true)
+            .with_blockmode(true)
+            .with_ss(HwChipSelectId::Id3)
+            .with_sod(false)
+            .with_slave_mode(false)
+            .with_enable(true)
+            .with_lbm(true)
+            .build();
+        let [disabled, enabled] = abort_ctrl1_sequence(current);
+        assert!(!disabled.enable());
+        assert!(!disabled.bm_start());
+        assert!(!disabled.mtxpause());
+        assert!(enabled.enable());
+        assert!(!enabled.bm_start());
+        assert!(!enabled.mtxpause());
+        // All other fields are preserved.
+        assert_eq!(
+            enabled.raw_value(),
+            current
+                .with_bm_start(false)
+                .with_mtxpause(false)
+                .raw_value()
+        );
+    }
+
+    #[test]
+    fn sampling_recommendation() {
+        let calibration = |normal_capture_ok, delayed_capture_ok| SamplingCalibration {
+            normal_capture_ok,
+            delayed_capture_ok,
+        };
+        assert_eq!(calibration(true, true).recommended(), Some(false));
+        assert_eq!(calibration(true, false).recommended(), Some(false));
+        assert_eq!(calibration(false, true).recommended(), Some(true));
+        assert_eq!(calibration(false, false).recommended(), None);
+    }
+
+    #[test]
+    fn prefill_never_exceeds_transfer_length() {
+        for prefill in 1..=MAX_FIFO_DEPTH {
+            for len in 0..2 * MAX_FIFO_DEPTH {
+                let written = prefill_len(prefill, len);
+                assert!(written <= len);
+                assert!(written <= prefill);
+                assert!(written == len || written == prefill);
+            }
+        }
+    }
+
+    #[test]
+    fn bmstop_on_last_word_only() {
+        for prefill in 1..=MAX_FIFO_DEPTH {
+            for len in 1..2 * MAX_FIFO_DEPTH {
+                let bmstop_words: heapless::Vec<usize, { 2 * MAX_FIFO_DEPTH }> =
+                    (0..prefill_len(prefill, len))
+                        .filter(|&idx| fifo_word(0, idx, len, true) & BMSTART_BMSTOP_MASK != 0)
+                        .collect();
+                if prefill >= len {
+                    assert_eq!(bmstop_words, [len - 1]);
+                } else {
+                    assert!(bmstop_words.is_empty());
+                }
+            }
+        }
+        assert_eq!(fifo_word(0x5a, 0, 1, false), 0x5a);
+        assert_eq!(fifo_word(0x5a, 0, 1, true), 0x5a | BMSTART_BMSTOP_MASK);
+    }
+
+    #[test]
+    fn raw_word_flags() {
+        let word = RawSpiWord::new(0x1234);
+        assert_eq!(word.raw_value(), 0x1234);
+        assert!(word.is_received());
+        let word = RawSpiWord::new_with_stop(0x1234);
+        assert_eq!(word.raw_value(), 0x1234 | BMSTART_BMSTOP_MASK);
+        assert!(word.is_received());
+        assert_eq!(
+            RawSpiWord::FRAME_END.raw_value(),
+            BMSTART_BMSTOP_MASK | BMSKIPDATA_MASK
+        );
+        assert!(!RawSpiWord::FRAME_END.is_received());
+        // Skip without stop is ignored by the hardware, so a word is received.
+        let word = RawSpiWord {
+            bm_skip: true,
+            ..RawSpiWord::new(0x5a)
+        };
+        assert!(word.is_received());
+
+        // The masks match the data register fields.
+        let data = Data::new_with_raw_value(RawSpiWord::FRAME_END.raw_value() | 0xA5A5);
+        assert!(data.bm_start_stop());
+        assert!(data.bm_skipdata());
+        assert_eq!(data.data(), 0xA5A5);
+
+        // The high-level transfers use the same flag placement.
+        assert_eq!(
+            fifo_word(0x5a, 3, 4, true),
+            RawSpiWord::new_with_stop(0x5a).raw_value()
+        );
+        assert_eq!(
+            fifo_word(0x5a, 2, 4, true),
+            RawSpiWord::new(0x5a).raw_value()
+        );
+        assert_eq!(word_from_fifo::<u8>(0x5a | BMSTART_BMSTOP_MASK), 0x5a);
+        assert_eq!(word_from_fifo::<u16>(0x1234 | BMSTART_BMSTOP_MASK), 0x1234);
+    }
+
+    /// Bits on MOSI for a sequence of words, with the MSB of each word shifted out first.
+    fn wire_bits(words: impl Iterator<Item = u32>, bits: u32) -> heapless::Vec<bool, 128> {
+        words
+            .flat_map(|word| (0..bits).rev().map(move |bit| (word >> bit) & 1 == 1))
+            .collect()
+    }
+
+    #[test]
+    fn be_bytes_match_u8_wire_order() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        for len in 0..=bytes.len() {
+            let bytes = &bytes[..len];
+            let words = (0..len.div_ceil(2)).map(|idx| be_word(bytes, idx, 0xA5C3) as u32);
+            // An odd length is padded with the lower byte of the fill word.
+            let mut padded: heapless::Vec<u8, 6> = bytes.iter().copied().collect();
+            if len % 2 == 1 {
+                padded.push(0xC3).unwrap();
+            }
+            let u8_words = padded.iter().map(|&byte| byte as u32);
+            assert_eq!(wire_bits(words, 16), wire_bits(u8_words, 8));
+        }
+    }
+
+    #[test]
+    fn le_bytes_swap_byte_pairs() {
+        let bytes = [0x12, 0x34, 0x56];
+        assert_eq!(le_word(&bytes, 0, 0xA5C3), 0x3412);
+        // The upper byte of the fill word is sent first for the odd byte.
+        assert_eq!(le_word(&bytes, 1, 0xA5C3), 0xA556);
+        // Beyond the end of the stream, the fill word is sent unchanged.
+        assert_eq!(le_word(&bytes, 2, 0xA5C3), 0xA5C3);
+        assert_eq!(be_word(&bytes, 2, 0xA5C3), 0xA5C3);
+    }
+
+    #[test]
+    fn store_be_word_odd_length() {
+        let mut read = [0; 3];
+        store_be_word(&mut read, 0, 0x1234);
+        store_be_word(&mut read, 1, 0x5678);
+        store_be_word(&mut read, 2, 0x9ABC);
+        assert_eq!(read, [0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn prefill_validation() {
+        assert!(SpiConfig::default().fifo_prefill(1).is_ok());
+        assert!(SpiConfig::default().fifo_prefill(MAX_FIFO_DEPTH).is_ok());
+        assert_eq!(
+            SpiConfig::default().fifo_prefill(0),
+            Err(InvalidFifoPrefillError {
+                prefill: 0,
+                fifo_depth: MAX_FIFO_DEPTH
+            })
+        );
+        assert!(
+            SpiConfig::default()
+                .fifo_prefill(MAX_FIFO_DEPTH + 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn clk_checked_exact() {
+        let ref_clk = Hertz::MHz(50);
+        let (cfg, achieved) =
+            SpiClockConfig::from_clk_checked(ref_clk, Hertz::MHz(5), 0, ClockRounding::Nearest)
+                .unwrap();
+        assert_eq!(achieved, Hertz::MHz(5));
+        assert_eq!(cfg.achieved_clk(ref_clk), achieved);
+        assert_eq!(cfg.prescale_val() as u32 * (cfg.scrdv() as u32 + 1), 10);
+    }
+
+    #[test]
+    fn clk_checked_rounding_directions() {
+        let ref_clk = Hertz::MHz(50);
+        // Divider 2.5: 25 MHz is closer than 12.5 MHz.
+        let (_, achieved) = SpiClockConfig::from_clk_checked(
+            ref_clk,
+            Hertz::MHz(20),
+            u32::MAX,
+            ClockRounding::Nearest,
+        )
+        .unwrap();
+        assert_eq!(achieved, Hertz::MHz(25));
+        let (_, achieved) = SpiClockConfig::from_clk_checked(
+            ref_clk,
+            Hertz::MHz(20),
+            u32::MAX,
+            ClockRounding::NeverFaster,
+        )
+        .unwrap();
+        assert_eq!(achieved, Hertz::kHz(12_500));
+        // Divider 5: 8.33 MHz is closer than 12.5 MHz.
+        let (_, achieved) = SpiClockConfig::from_clk_checked(
+            ref_clk,
+            Hertz::MHz(10),
+            u32::MAX,
+            ClockRounding::Nearest,
+        )
+        .unwrap();
+        assert_eq!(achieved, Hertz::Hz(8_333_333));
+    }
+
+    #[test]
+    fn clk_checked_never_exceeds_requested() {
+        for ref_clk in [20_000_000, 50_000_000, 100_000_000] {
+            for spi_clk in (10_000..=10_000_000).step_by(99_991) {
+                let (cfg, achieved) = SpiClockConfig::from_clk_checked(
+                    Hertz::from_raw(ref_clk),
+                    Hertz::from_raw(spi_clk),
+                    u32::MAX,
+                    ClockRounding::NeverFaster,
+                )
+                .unwrap();
+                assert!(achieved.raw() <= spi_clk);
+                // Exact check without the rounding of the achieved frequency.
+                let div = cfg.prescale_val() as u64 * (cfg.scrdv() as u64 + 1);
+                assert!(div * spi_clk as u64 >= ref_clk as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn clk_checked_tolerance() {
+        let ref_clk = Hertz::MHz(50);
+        assert_eq!(
+            SpiClockConfig::from_clk_checked(
+                ref_clk,
+                Hertz::MHz(10),
+                10_000,
+                ClockRounding::Nearest
+            ),
+            Err(SpiClockConfigError::ToleranceExceeded {
+                requested: Hertz::MHz(10),
+                achievable: Hertz::Hz(8_333_333),
+            })
+        );
+        // 1 MHz + 100 ppm is achieved exactly with a divider of 50.
+        assert!(
+            SpiClockConfig::from_clk_checked(
+                ref_clk,
+                Hertz::Hz(1_000_100),
+                100,
+                ClockRounding::Nearest
+            )
+            .is_ok()
+        );
+        assert!(
+            SpiClockConfig::from_clk_checked(
+                ref_clk,
+                Hertz::Hz(1_000_100),
+                99,
+                ClockRounding::Nearest
+            )
+            .is_err()
+        );
+        assert_eq!(
+            SpiClockConfig::from_clk_checked(ref_clk, Hertz::Hz(0), 100, ClockRounding::Nearest),
+            Err(SpiClockConfigError::DivIsZero)
+        );
+        // Largest divider is 254 * 256.
+        assert_eq!(
+            SpiClockConfig::from_clk_checked(ref_clk, Hertz::Hz(100), 100, ClockRounding::Nearest),
+            Err(SpiClockConfigError::ScrdvValueTooLarge)
+        );
+    }
+}
@@ -142,7 +142,7 @@ hw_cs_pins!(
 hw_cs_multi_pin!(
     PinPf2Spi1HwCsId0,
     Pf2,
-    Bank::Spi2,
+    Bank::Spi1,
     FunctionSelect::Sel1,
     HwChipSelectId::Id0
 );
@@ -179,7 +179,7 @@ impl PinMiso for Pin<Pf6> {
 }
 
 hw_cs_pins!(
-    Bank::Spi1,
+    Bank::Spi2,
     (Pa4, FunctionSelect::Sel2, HwChipSelectId::Id0),
     (Pa3, FunctionSelect::Sel2, HwChipSelectId::Id1),
     (Pa2, FunctionSelect::Sel2, HwChipSelectId::Id2),
@@ -193,7 +193,7 @@ hw_cs_pins!(
 
 #[cfg(not(feature = "va41628"))]
 hw_cs_pins!(
-    Bank::Spi1,
+    Bank::Spi2,
     (Pf3, FunctionSelect::Sel2, HwChipSelectId::Id1),
     (Pf4, FunctionSelect::Sel2, HwChipSelectId::Id0),
 );
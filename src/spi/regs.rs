@@ -1,5 +1,7 @@
 use core::marker::PhantomData;
 
+use crate::PeripheralSelect;
+
 pub use crate::shared::{FifoClear, TriggerLevel};
 
 cfg_if::cfg_if! {
@@ -10,6 +12,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_1: usize = 0x4005_1000;
         /// SPI C base address
         pub const BASE_ADDR_2: usize = 0x4005_2000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0113_07E1;
     } else if #[cfg(feature = "vor4x")] {
         /// SPI 0 base address
         pub const BASE_ADDR_0: usize = 0x4001_5000;
@@ -19,6 +23,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_2: usize = 0x4001_5800;
         /// SPI 3 base address
         pub const BASE_ADDR_3: usize = 0x4001_5C00;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0213_07E9;
     }
 }
 
@@ -33,14 +39,57 @@ pub enum Bank {
 }
 
 impl Bank {
-    /// Unsafely steal the SPI peripheral block for the given port.
+    /// Unsafely steal the SPI peripheral block for the given bank.
+    ///
+    /// In debug builds, this panics if the bank is owned by a driver created with a safe
+    /// constructor, see [crate::ownership].
     ///
     /// # Safety
     ///
     /// Circumvents ownership and safety guarantees by the HAL.
+    #[track_caller]
     pub unsafe fn steal_regs(&self) -> MmioSpi<'static> {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::Spi(*self));
         Spi::new_mmio(*self)
     }
+
+    /// Same as [Self::steal_regs], but without the ownership check.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL.
+    pub unsafe fn force_steal_regs(&self) -> MmioSpi<'static> {
+        Spi::new_mmio(*self)
+    }
+
+    /// Peripheral select value used for the clock enable and reset registers.
+    pub const fn periph_sel(&self) -> PeripheralSelect {
+        match self {
+            Bank::Spi0 => PeripheralSelect::Spi0,
+            Bank::Spi1 => PeripheralSelect::Spi1,
+            Bank::Spi2 => PeripheralSelect::Spi2,
+            #[cfg(feature = "vor4x")]
+            Bank::Spi3 => PeripheralSelect::Spi3,
+        }
+    }
+
+    /// Depth of the TX and RX FIFO in words. All SPI peripherals of the supported families
+    /// have 16 word deep FIFOs.
+    pub const fn fifo_depth(&self) -> usize {
+        match self {
+            Bank::Spi0 | Bank::Spi1 | Bank::Spi2 => 16,
+            #[cfg(feature = "vor4x")]
+            Bank::Spi3 => 16,
+        }
+    }
+
+    /// APB bus which clocks the SPI. All SPI peripherals are APB1 peripherals.
+    #[cfg(feature = "vor4x")]
+    pub const fn apb_bus(&self) -> crate::clock::ApbBus {
+        match self {
+            Bank::Spi0 | Bank::Spi1 | Bank::Spi2 | Bank::Spi3 => crate::clock::ApbBus::Apb1,
+        }
+    }
 }
 
 #[bitbybit::bitenum(u4)]
@@ -174,6 +223,16 @@ pub struct InterruptControl {
     rx_overrun: bool,
 }
 
+impl From<crate::spi::SpiInterrupts> for InterruptControl {
+    fn from(irqs: crate::spi::SpiInterrupts) -> Self {
+        InterruptControl::new_with_raw_value(0)
+            .with_tx(irqs.tx_trigger)
+            .with_rx(irqs.rx_trigger)
+            .with_rx_timeout(irqs.rx_timeout)
+            .with_rx_overrun(irqs.rx_overrun)
+    }
+}
+
 #[bitbybit::bitfield(u32, debug, defmt_bitfields(feature = "defmt"))]
 pub struct InterruptStatus {
     /// TX FIFO count <= TX FIFO trigger level.
@@ -235,7 +294,7 @@ pub struct Spi {
     #[mmio(Write)]
     fifo_clear: FifoClear,
     #[mmio(PureRead)]
-    state: u32,
+    state: State,
     #[cfg(feature = "vor1x")]
     _reserved: [u32; 0x3F2],
     #[cfg(feature = "vor4x")]
@@ -254,6 +313,7 @@ cfg_if::cfg_if! {
 }
 
 impl Spi {
+    #[cfg(not(feature = "test-harness"))]
     fn new_mmio_at(base: usize) -> MmioSpi<'static> {
         MmioSpi {
             ptr: base as *mut _,
@@ -262,12 +322,18 @@ impl Spi {
     }
 
     pub fn new_mmio(bank: Bank) -> MmioSpi<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioSpi {
+            ptr: crate::test_harness::spi(bank).as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         match bank {
             Bank::Spi0 => Self::new_mmio_at(BASE_ADDR_0),
             Bank::Spi1 => Self::new_mmio_at(BASE_ADDR_1),
             Bank::Spi2 => Self::new_mmio_at(BASE_ADDR_2),
             #[cfg(feature = "vor4x")]
-            Bank::Spi3 => Self::new_mmio_at(BASE_ADDR_2),
+            Bank::Spi3 => Self::new_mmio_at(BASE_ADDR_3),
         }
     }
 }
@@ -1,43 +1,236 @@
-#[cfg(feature = "vor1x")]
-use va108xx as pac;
-#[cfg(feature = "vor4x")]
-use va416xx as pac;
+use crate::timer::{self, TimId};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "test-harness")] {
+        use portable_atomic::Ordering;
+
+        use crate::test_harness::{PERIPHERAL_CLK_ENABLE, PERIPHERAL_RESET};
+
+        #[inline]
+        fn read_clk_enable() -> u32 {
+            PERIPHERAL_CLK_ENABLE.load(Ordering::Relaxed)
+        }
+
+        #[inline]
+        fn modify_clk_enable(f: impl FnOnce(u32) -> u32) {
+            PERIPHERAL_CLK_ENABLE.store(f(read_clk_enable()), Ordering::Relaxed);
+        }
+
+        #[inline]
+        fn modify_reset(f: impl FnOnce(u32) -> u32) {
+            PERIPHERAL_RESET.store(f(PERIPHERAL_RESET.load(Ordering::Relaxed)), Ordering::Relaxed);
+        }
+    } else {
+        #[cfg(feature = "vor1x")]
+        use va108xx as pac;
+        #[cfg(feature = "vor4x")]
+        use va416xx as pac;
+
+        #[inline]
+        fn read_clk_enable() -> u32 {
+            let syscfg = unsafe { pac::Sysconfig::steal() };
+            syscfg.peripheral_clk_enable().read().bits()
+        }
+
+        #[inline]
+        fn modify_clk_enable(f: impl FnOnce(u32) -> u32) {
+            let syscfg = unsafe { pac::Sysconfig::steal() };
+            syscfg
+                .peripheral_clk_enable()
+                .modify(|r, w| unsafe { w.bits(f(r.bits())) });
+        }
+
+        #[inline]
+        fn modify_reset(f: impl FnOnce(u32) -> u32) {
+            let syscfg = unsafe { pac::Sysconfig::steal() };
+            syscfg
+                .peripheral_reset()
+                .modify(|r, w| unsafe { w.bits(f(r.bits())) });
+        }
+    }
+}
 
 #[inline]
 pub fn enable_peripheral_clock(clock: crate::PeripheralSelect) {
-    let syscfg = unsafe { pac::Sysconfig::steal() };
-    syscfg
-        .peripheral_clk_enable()
-        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << clock as u8)) });
+    modify_clk_enable(|value| value | (1 << clock as u8));
 }
 
 #[inline]
 pub fn disable_peripheral_clock(clock: crate::PeripheralSelect) {
-    let syscfg = unsafe { pac::Sysconfig::steal() };
-    syscfg
-        .peripheral_clk_enable()
-        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << clock as u8)) });
+    modify_clk_enable(|value| value & !(1 << clock as u8));
+}
+
+/// Enables the clocks of all given peripherals.
+///
+/// The clock enable register is only written if at least one of the clocks is disabled, so this
+/// is cheap to call before every access to a peripheral which may have been configured without
+/// its driver constructor.
+#[inline]
+pub fn ensure_peripheral_clocks(clocks: &[crate::PeripheralSelect]) {
+    let mask = clocks
+        .iter()
+        .fold(0, |mask, clock| mask | (1 << *clock as u8));
+    if read_clk_enable() & mask != mask {
+        modify_clk_enable(|value| value | mask);
+    }
+}
+
+/// Checks whether the clock of the given peripheral is enabled.
+#[inline]
+pub fn is_peripheral_clock_enabled(clock: crate::PeripheralSelect) -> bool {
+    (read_clk_enable() >> clock as u8) & 1 == 1
 }
 
 #[inline]
 pub fn assert_peripheral_reset(periph_sel: crate::PeripheralSelect) {
-    let syscfg = unsafe { pac::Sysconfig::steal() };
-    syscfg
-        .peripheral_reset()
-        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << periph_sel as u8)) });
+    modify_reset(|value| value & !(1 << periph_sel as u8));
 }
 
 #[inline]
 pub fn deassert_peripheral_reset(periph_sel: crate::PeripheralSelect) {
-    let syscfg = unsafe { pac::Sysconfig::steal() };
-    syscfg
-        .peripheral_reset()
-        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << periph_sel as u8)) });
+    modify_reset(|value| value | (1 << periph_sel as u8));
 }
 
 #[inline]
 pub fn reset_peripheral_for_cycles(periph_sel: crate::PeripheralSelect, cycles: usize) {
     assert_peripheral_reset(periph_sel);
+    #[cfg(not(feature = "test-harness"))]
     cortex_m::asm::delay(cycles as u32);
+    #[cfg(feature = "test-harness")]
+    {
+        let _ = cycles;
+        crate::test_harness::emulate_peripheral_reset(periph_sel);
+    }
     deassert_peripheral_reset(periph_sel);
 }
+
+/// Peripheral ID mismatch detected by [verify_peripheral_ids].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("peripheral ID mismatch for peripheral mask {mask:#010x}")]
+pub struct PeridMismatch {
+    /// Mask of all peripherals with a mismatching peripheral ID. The bit positions are the
+    /// [crate::PeripheralSelect] values.
+    pub mask: u32,
+}
+
+impl PeridMismatch {
+    /// Checks whether the peripheral ID of the given peripheral did not match.
+    #[inline]
+    pub const fn contains(&self, periph_sel: crate::PeripheralSelect) -> bool {
+        (self.mask >> periph_sel as u32) & 1 == 1
+    }
+}
+
+/// Checks the peripheral ID of all given peripherals against the expected value for the selected
+/// CPU family.
+///
+/// This can be used as a built-in test at boot time to detect a mismatch between the selected
+/// CPU family and the actual hardware. The peripheral clocks of peripherals which are not enabled
+/// are enabled for the check and disabled again afterwards. Only the UART, SPI and I2C
+/// peripherals have a peripheral ID check, all other peripherals are ignored. The TIM peripherals
+/// are not part of [crate::PeripheralSelect] and can be checked with
+/// [verify_tim_peripheral_ids].
+pub fn verify_peripheral_ids(periphs: &[crate::PeripheralSelect]) -> Result<(), PeridMismatch> {
+    let clks_enabled = read_clk_enable();
+    let mut mismatch_mask = 0;
+    for periph_sel in periphs {
+        let bit = 1 << *periph_sel as u32;
+        if clks_enabled & bit == 0 {
+            enable_peripheral_clock(*periph_sel);
+        }
+        if perid_matches(*periph_sel) == Some(false) {
+            mismatch_mask |= bit;
+        }
+    }
+    for periph_sel in periphs {
+        if clks_enabled & (1 << *periph_sel as u32) == 0 {
+            disable_peripheral_clock(*periph_sel);
+        }
+    }
+    if mismatch_mask != 0 {
+        return Err(PeridMismatch {
+            mask: mismatch_mask,
+        });
+    }
+    Ok(())
+}
+
+/// TIM peripheral ID mismatch detected by [verify_tim_peripheral_ids].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("peripheral ID mismatch for TIM mask {mask:#010x}")]
+pub struct TimPeridMismatch {
+    /// Mask of all TIM peripherals with a mismatching peripheral ID. The bit positions are the
+    /// [TimId] values.
+    pub mask: u32,
+}
+
+impl TimPeridMismatch {
+    /// Checks whether the peripheral ID of the given TIM peripheral did not match.
+    #[inline]
+    pub const fn contains(&self, id: TimId) -> bool {
+        (self.mask >> id.value()) & 1 == 1
+    }
+}
+
+/// Checks the peripheral ID of all given TIM peripherals against the expected value for the
+/// selected CPU family.
+///
+/// This is the counterpart of [verify_peripheral_ids] for the TIM peripherals, which have their
+/// own clock enable register. TIM clocks which are not enabled are enabled for the check and
+/// disabled again afterwards.
+pub fn verify_tim_peripheral_ids(tims: &[TimId]) -> Result<(), TimPeridMismatch> {
+    let clks_enabled = tims.iter().fold(0u32, |mask, id| {
+        mask | ((timer::is_tim_clk_enabled(*id) as u32) << id.value())
+    });
+    let mut mismatch_mask = 0;
+    for id in tims {
+        let bit = 1 << id.value();
+        if clks_enabled & bit == 0 {
+            timer::enable_tim_clk(*id);
+        }
+        if timer::regs::Timer::new_mmio(*id).read_perid() != timer::regs::EXPECTED_PERID {
+            mismatch_mask |= bit;
+        }
+    }
+    for id in tims {
+        if clks_enabled & (1 << id.value()) == 0 {
+            timer::disable_tim_clk(*id);
+        }
+    }
+    if mismatch_mask != 0 {
+        return Err(TimPeridMismatch {
+            mask: mismatch_mask,
+        });
+    }
+    Ok(())
+}
+
+/// Returns [None] if the peripheral does not have a peripheral ID check.
+fn perid_matches(periph_sel: crate::PeripheralSelect) -> Option<bool> {
+    use crate::{PeripheralSelect, i2c, spi, uart};
+
+    let uart_matches =
+        |bank| uart::regs::Uart::new_mmio(bank).read_perid() == uart::regs::EXPECTED_PERID;
+    let spi_matches =
+        |bank| spi::regs::Spi::new_mmio(bank).read_perid() == spi::regs::EXPECTED_PERID;
+    let i2c_matches =
+        |bank| i2c::regs::I2c::new_mmio(bank).read_perid() == i2c::regs::EXPECTED_PERID;
+    Some(match periph_sel {
+        PeripheralSelect::Uart0 => uart_matches(uart::Bank::Uart0),
+        PeripheralSelect::Uart1 => uart_matches(uart::Bank::Uart1),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::Uart2 => uart_matches(uart::Bank::Uart2),
+        PeripheralSelect::Spi0 => spi_matches(spi::Bank::Spi0),
+        PeripheralSelect::Spi1 => spi_matches(spi::Bank::Spi1),
+        PeripheralSelect::Spi2 => spi_matches(spi::Bank::Spi2),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::Spi3 => spi_matches(spi::Bank::Spi3),
+        PeripheralSelect::I2c0 => i2c_matches(i2c::Bank::I2c0),
+        PeripheralSelect::I2c1 => i2c_matches(i2c::Bank::I2c1),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::I2c2 => i2c_matches(i2c::Bank::I2c2),
+        _ => return None,
+    })
+}
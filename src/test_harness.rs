@@ -0,0 +1,277 @@
+//! # Register images for host-side tests.
+//!
+//! With the `test-harness` feature, the register blocks of the UART, SPI, I2C, TIM, GPIO and
+//! IOCONFIG peripherals, of the IRQSEL peripheral of the Vorago 1x devices and of the IRQ router of the
+//! Vorago 4x devices point into static RAM-backed register images instead of the hardware
+//! addresses.
+//! The peripheral clock enable and peripheral reset registers and the TIM clock enable and TIM
+//! reset registers of the SYSCONFIG peripheral are replaced as well. This allows running the driver constructors and other register level logic
+//! on the host, and inspecting the written register values with the regular register block
+//! accessors, for example [crate::uart::regs::Uart::new_mmio].
+//!
+//! The images do not emulate any hardware behaviour, except that a peripheral reset of a UART,
+//! SPI or I2C peripheral sets its image to 0. Status registers only contain the values written
+//! by the test, so driver functions which poll a status register may never complete.
+//!
+//! All images are shared by the whole test binary, and the test harness of Rust runs tests in
+//! parallel. Tests using the images should therefore hold the guard returned by [lock], which
+//! also resets all images and the ownership flags of [crate::ownership].
+//!
+//! This feature is only intended for host-side tests and must never be enabled for target
+//! builds.
+use core::cell::UnsafeCell;
+
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::{Port, i2c, spi, timer::TimId, uart};
+
+#[cfg(feature = "vor1x")]
+const BLOCK_WORDS: usize = 0x400;
+#[cfg(feature = "vor4x")]
+const BLOCK_WORDS: usize = 0x100;
+/// The UART register block has the same size on all families.
+const UART_WORDS: usize = 0x400;
+#[cfg(feature = "vor4x")]
+const IRQ_ROUTER_WORDS: usize = 0x400;
+const IOCONFIG_WORDS: usize = 0x400;
+
+#[cfg(feature = "vor1x")]
+const NUM_UARTS: usize = 2;
+#[cfg(feature = "vor4x")]
+const NUM_UARTS: usize = 3;
+#[cfg(feature = "vor1x")]
+const NUM_SPIS: usize = 3;
+#[cfg(feature = "vor4x")]
+const NUM_SPIS: usize = 4;
+#[cfg(feature = "vor1x")]
+const NUM_I2CS: usize = 2;
+#[cfg(feature = "vor4x")]
+const NUM_I2CS: usize = 3;
+const NUM_TIMS: usize = 24;
+#[cfg(feature = "vor1x")]
+const NUM_PORTS: usize = 2;
+#[cfg(feature = "vor4x")]
+const NUM_PORTS: usize = 7;
+
+/// Reset value of the peripheral reset register image: All peripherals are out of reset.
+pub const PERIPHERAL_RESET_DEFAULT: u32 = u32::MAX;
+
+/// Reset value of the TIM reset register image: All timers are out of reset.
+pub const TIM_RESET_DEFAULT: u32 = (1 << NUM_TIMS) - 1;
+
+/// RAM-backed image of a register block with the given number of 32-bit words.
+#[repr(C, align(4))]
+pub struct RegisterImage<const WORDS: usize>([UnsafeCell<u32>; WORDS]);
+
+// Safety: The image is only accessed with volatile reads and writes of whole words, like the
+// hardware register block it replaces.
+unsafe impl<const WORDS: usize> Sync for RegisterImage<WORDS> {}
+
+impl<const WORDS: usize> RegisterImage<WORDS> {
+    const fn new() -> Self {
+        Self([const { UnsafeCell::new(0) }; WORDS])
+    }
+
+    /// Pointer used as the base address of the register block.
+    #[inline]
+    pub(crate) const fn as_mut_ptr<T>(&'static self) -> *mut T {
+        self.0.as_ptr() as *mut T
+    }
+
+    /// Read the register at the given byte offset.
+    pub fn read(&self, offset: usize) -> u32 {
+        // Safety: Volatile access to a word of the image.
+        unsafe { self.word(offset).get().read_volatile() }
+    }
+
+    /// Write the register at the given byte offset, for example to emulate a status flag set
+    /// by the hardware.
+    pub fn write(&self, offset: usize, value: u32) {
+        // Safety: Volatile access to a word of the image.
+        unsafe { self.word(offset).get().write_volatile(value) }
+    }
+
+    /// Set all registers to 0.
+    pub fn reset(&self) {
+        self.fill(0);
+    }
+
+    /// Set all registers to the given value.
+    pub fn fill(&self, value: u32) {
+        for word in self.0.iter() {
+            // Safety: Volatile access to a word of the image.
+            unsafe { word.get().write_volatile(value) };
+        }
+    }
+
+    fn word(&self, offset: usize) -> &UnsafeCell<u32> {
+        assert_eq!(offset % 4, 0, "register offset must be word aligned");
+        &self.0[offset / 4]
+    }
+}
+
+static UART_IMAGES: [RegisterImage<UART_WORDS>; NUM_UARTS] =
+    [const { RegisterImage::new() }; NUM_UARTS];
+static SPI_IMAGES: [RegisterImage<BLOCK_WORDS>; NUM_SPIS] =
+    [const { RegisterImage::new() }; NUM_SPIS];
+static I2C_IMAGES: [RegisterImage<BLOCK_WORDS>; NUM_I2CS] =
+    [const { RegisterImage::new() }; NUM_I2CS];
+static TIM_IMAGES: [RegisterImage<BLOCK_WORDS>; NUM_TIMS] =
+    [const { RegisterImage::new() }; NUM_TIMS];
+static GPIO_IMAGES: [RegisterImage<BLOCK_WORDS>; NUM_PORTS] =
+    [const { RegisterImage::new() }; NUM_PORTS];
+static IOCONFIG_IMAGE: RegisterImage<IOCONFIG_WORDS> = RegisterImage::new();
+#[cfg(feature = "vor1x")]
+static IRQSEL_IMAGE: RegisterImage<BLOCK_WORDS> = RegisterImage::new();
+#[cfg(feature = "vor4x")]
+static IRQ_ROUTER_IMAGE: RegisterImage<IRQ_ROUTER_WORDS> = RegisterImage::new();
+
+pub(crate) static PERIPHERAL_CLK_ENABLE: AtomicU32 = AtomicU32::new(0);
+pub(crate) static PERIPHERAL_RESET: AtomicU32 = AtomicU32::new(PERIPHERAL_RESET_DEFAULT);
+pub(crate) static TIM_CLK_ENABLE: AtomicU32 = AtomicU32::new(0);
+pub(crate) static TIM_RESET: AtomicU32 = AtomicU32::new(TIM_RESET_DEFAULT);
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Register image of the UART bank.
+#[inline]
+pub const fn uart(bank: uart::Bank) -> &'static RegisterImage<UART_WORDS> {
+    &UART_IMAGES[bank as usize]
+}
+
+/// Register image of the SPI bank.
+#[inline]
+pub const fn spi(bank: spi::Bank) -> &'static RegisterImage<BLOCK_WORDS> {
+    &SPI_IMAGES[bank as usize]
+}
+
+/// Register image of the I2C bank.
+#[inline]
+pub const fn i2c(bank: i2c::Bank) -> &'static RegisterImage<BLOCK_WORDS> {
+    &I2C_IMAGES[bank as usize]
+}
+
+/// Register image of the TIM peripheral.
+#[inline]
+pub const fn timer(id: TimId) -> &'static RegisterImage<BLOCK_WORDS> {
+    &TIM_IMAGES[id.value() as usize]
+}
+
+/// Register image of the GPIO port.
+#[inline]
+pub const fn gpio(port: Port) -> &'static RegisterImage<BLOCK_WORDS> {
+    &GPIO_IMAGES[port as usize]
+}
+
+/// Register image of the IOCONFIG peripheral.
+#[inline]
+pub const fn ioconfig() -> &'static RegisterImage<IOCONFIG_WORDS> {
+    &IOCONFIG_IMAGE
+}
+
+/// Register image of the IRQSEL peripheral.
+///
+/// Unlike the other images, the selection registers are reset to their hardware reset value
+/// 0xFFFF_FFFF, which does not route the interrupt.
+#[cfg(feature = "vor1x")]
+#[inline]
+pub const fn irqsel() -> &'static RegisterImage<BLOCK_WORDS> {
+    &IRQSEL_IMAGE
+}
+
+/// Register image of the IRQ router.
+#[cfg(feature = "vor4x")]
+#[inline]
+pub const fn irq_router() -> &'static RegisterImage<IRQ_ROUTER_WORDS> {
+    &IRQ_ROUTER_IMAGE
+}
+
+/// Value of the peripheral clock enable register image.
+#[inline]
+pub fn peripheral_clk_enable() -> u32 {
+    PERIPHERAL_CLK_ENABLE.load(Ordering::Relaxed)
+}
+
+/// Value of the peripheral reset register image.
+#[inline]
+pub fn peripheral_reset() -> u32 {
+    PERIPHERAL_RESET.load(Ordering::Relaxed)
+}
+
+/// Value of the TIM clock enable register image.
+#[inline]
+pub fn tim_clk_enable() -> u32 {
+    TIM_CLK_ENABLE.load(Ordering::Relaxed)
+}
+
+/// Value of the TIM reset register image.
+#[inline]
+pub fn tim_reset() -> u32 {
+    TIM_RESET.load(Ordering::Relaxed)
+}
+
+/// Reset all register images to their default values and clear the ownership flags of
+/// [crate::ownership].
+pub fn reset_all() {
+    UART_IMAGES.iter().for_each(RegisterImage::reset);
+    SPI_IMAGES.iter().for_each(RegisterImage::reset);
+    I2C_IMAGES.iter().for_each(RegisterImage::reset);
+    TIM_IMAGES.iter().for_each(RegisterImage::reset);
+    GPIO_IMAGES.iter().for_each(RegisterImage::reset);
+    IOCONFIG_IMAGE.reset();
+    #[cfg(feature = "vor1x")]
+    IRQSEL_IMAGE.fill(u32::MAX);
+    #[cfg(feature = "vor4x")]
+    IRQ_ROUTER_IMAGE.reset();
+    PERIPHERAL_CLK_ENABLE.store(0, Ordering::Relaxed);
+    PERIPHERAL_RESET.store(PERIPHERAL_RESET_DEFAULT, Ordering::Relaxed);
+    TIM_CLK_ENABLE.store(0, Ordering::Relaxed);
+    TIM_RESET.store(TIM_RESET_DEFAULT, Ordering::Relaxed);
+    crate::ownership::reset();
+}
+
+/// Reset the register image of a UART, SPI or I2C peripheral, like the peripheral reset does
+/// for the hardware registers. The images of the other peripherals are not touched.
+pub(crate) fn emulate_peripheral_reset(periph_sel: crate::PeripheralSelect) {
+    use crate::PeripheralSelect;
+
+    match periph_sel {
+        PeripheralSelect::Uart0 => uart(uart::Bank::Uart0).reset(),
+        PeripheralSelect::Uart1 => uart(uart::Bank::Uart1).reset(),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::Uart2 => uart(uart::Bank::Uart2).reset(),
+        PeripheralSelect::Spi0 => spi(spi::Bank::Spi0).reset(),
+        PeripheralSelect::Spi1 => spi(spi::Bank::Spi1).reset(),
+        PeripheralSelect::Spi2 => spi(spi::Bank::Spi2).reset(),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::Spi3 => spi(spi::Bank::Spi3).reset(),
+        PeripheralSelect::I2c0 => i2c(i2c::Bank::I2c0).reset(),
+        PeripheralSelect::I2c1 => i2c(i2c::Bank::I2c1).reset(),
+        #[cfg(feature = "vor4x")]
+        PeripheralSelect::I2c2 => i2c(i2c::Bank::I2c2).reset(),
+        _ => (),
+    }
+}
+
+/// Exclusive access to the register images, returned by [lock].
+pub struct HarnessGuard(());
+
+impl Drop for HarnessGuard {
+    fn drop(&mut self) {
+        LOCKED.store(false, Ordering::Release);
+    }
+}
+
+/// Wait for exclusive access to the register images and reset all images.
+///
+/// The images are released when the returned guard is dropped.
+pub fn lock() -> HarnessGuard {
+    while LOCKED
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    reset_all();
+    HarnessGuard(())
+}
@@ -1,4 +1,19 @@
-//! Time units
+//! Time units, the [MonotonicClock] and timeouts for blocking and asynchronous operations
+use embedded_hal::delay::DelayNs;
+use once_cell::sync::OnceCell;
+use portable_atomic::{AtomicU32, Ordering};
+
+#[cfg(feature = "vor1x")]
+use crate::{PeripheralSelect, enable_peripheral_clock};
+use crate::{
+    busy_wait::BusyWait,
+    enable_nvic_interrupt,
+    timer::{TimId, TimInstance, assert_tim_reset_for_cycles, enable_tim_clk, regs::EnableControl},
+};
+#[cfg(feature = "vor1x")]
+use va108xx as pac;
+#[cfg(feature = "vor4x")]
+use va416xx as pac;
 
 // Frequency based
 
@@ -24,3 +39,462 @@ pub type Microseconds = fugit::MicrosDurationU32;
 
 /// Nanoseconds
 pub type Nanoseconds = fugit::NanosDurationU32;
+
+//==================================================================================================
+// Monotonic clock
+//==================================================================================================
+
+/// Instant with microsecond resolution returned by the [MonotonicClock].
+pub type Instant = fugit::Instant<u64, 1, 1_000_000>;
+
+/// Duration with microsecond resolution.
+pub type Duration = fugit::MicrosDurationU64;
+
+/// The reference clock of the timer is slower than the requested tick rate, so the timer can not
+/// provide the tick resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("reference clock {ref_clk} is slower than the tick rate of {tick_hz} Hz")]
+pub struct RefClockTooSlowError {
+    pub ref_clk: Hertz,
+    pub tick_hz: u64,
+}
+
+/// Timer ticks per timestamp tick for the given reference clock and tick rate.
+pub(crate) fn tick_scale(ref_clk: Hertz, tick_hz: u64) -> Result<u64, RefClockTooSlowError> {
+    match ref_clk.raw() as u64 / tick_hz {
+        0 => Err(RefClockTooSlowError { ref_clk, tick_hz }),
+        scale => Ok(scale),
+    }
+}
+
+/// Extends a free-running decrementing 32-bit timer to a 64-bit timestamp by counting the
+/// timer periods in its interrupt handler.
+///
+/// This is the common building block of the [MonotonicClock] and the embassy time driver.
+pub(crate) struct Timekeeper {
+    periods: AtomicU32,
+    tim: OnceCell<TimId>,
+    /// Timer ticks per timestamp tick.
+    scale: OnceCell<u64>,
+}
+
+impl Timekeeper {
+    pub(crate) const fn new() -> Self {
+        Self {
+            periods: AtomicU32::new(0),
+            tim: OnceCell::new(),
+            scale: OnceCell::new(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_initialized(&self) -> bool {
+        self.tim.get().is_some()
+    }
+
+    /// Timer ticks per timestamp tick, if the timekeeper was initialized.
+    #[inline]
+    pub(crate) fn scale(&self) -> Option<u64> {
+        self.scale.get().copied()
+    }
+
+    /// Start the timekeeping timer with the timer ticks per timestamp tick calculated with
+    /// [tick_scale]. Returns [false] if the timekeeper was already initialized.
+    pub(crate) fn init(&self, tim: TimId, scale: u64, irq: pac::Interrupt) -> bool {
+        if self.tim.set(tim).is_err() {
+            return false;
+        }
+        #[cfg(feature = "vor1x")]
+        enable_peripheral_clock(PeripheralSelect::Irqsel);
+        enable_tim_clk(tim);
+        assert_tim_reset_for_cycles(tim, 2);
+
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(tim));
+        let mut regs = crate::timer::regs::Timer::new_mmio(tim);
+        // Initiate scale value here. This is required to convert timer ticks back to a timestamp.
+        self.scale.set(scale).ok();
+        regs.write_reset_value(u32::MAX);
+        // Decrementing counter.
+        regs.write_count_value(u32::MAX);
+        #[cfg(feature = "vor1x")]
+        crate::irqsel::route_tim(tim, irq);
+        unsafe {
+            enable_nvic_interrupt(irq);
+        }
+        crate::timer::modify_control(&mut regs, |mut value| {
+            value.set_irq_enable(true);
+            value
+        });
+        // Switch on. Timekeeping should always be done.
+        regs.write_enable_control(EnableControl::new_enable());
+        true
+    }
+
+    /// Account for a timer period. Returns the new period count.
+    #[inline]
+    pub(crate) fn next_period(&self) -> u32 {
+        self.periods.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// Current timestamp in timestamp ticks, or 0 if the timekeeper was not initialized yet.
+    pub(crate) fn now(&self) -> u64 {
+        let (Some(scale), Some(tim)) = (self.scale.get(), self.tim.get()) else {
+            return 0;
+        };
+        let view = crate::timer::TimIrqView::new(*tim);
+        loop {
+            // Acquire ensures that we get the latest value of `periods` and
+            // no instructions can be reordered before the load.
+            let period1 = self.periods.load(Ordering::Acquire);
+
+            let counter_val = u32::MAX - view.count_value();
+
+            // Double read to protect against race conditions when the counter is overflowing.
+            let period2 = self.periods.load(Ordering::Relaxed);
+            if period1 == period2 {
+                return (((period1 as u64) << 32) | counter_val as u64) / scale;
+            }
+        }
+    }
+}
+
+/// Monotonic 64-bit microsecond clock based on a free-running timer.
+///
+/// This is independent of the embassy time driver and can be used for timestamps and timeouts
+/// in applications which do not use embassy. The clock can be used concurrently from thread and
+/// interrupt context. [MonotonicClock::on_interrupt] needs to be called in the interrupt
+/// handler of the timer.
+///
+/// The reference clock of the timer should be a multiple of 1 MHz, otherwise the timestamps
+/// drift.
+pub struct MonotonicClock(Timekeeper);
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonotonicClock {
+    /// Create a new clock. This can be used to declare the clock as a static. It needs to
+    /// be initialized with [MonotonicClock::init] before it is used.
+    pub const fn new() -> Self {
+        Self(Timekeeper::new())
+    }
+
+    /// Start the clock. Subsequent calls are ignored.
+    ///
+    /// The interrupt is routed to the timer and enabled in the NVIC. Returns
+    /// [RefClockTooSlowError] without starting the timer if the system clock is slower than
+    /// 1 MHz.
+    #[cfg(feature = "vor1x")]
+    pub fn init<Tim: TimInstance>(
+        &self,
+        _tim: Tim,
+        sysclk: Hertz,
+        irq: pac::Interrupt,
+    ) -> Result<(), RefClockTooSlowError> {
+        let scale = tick_scale(sysclk, 1_000_000)?;
+        self.0.init(Tim::ID, scale, irq);
+        Ok(())
+    }
+
+    /// Start the clock. Subsequent calls are ignored.
+    ///
+    /// The interrupt of the timer is enabled in the NVIC. Returns [RefClockTooSlowError] without
+    /// starting the timer if the reference clock of the timer is slower than 1 MHz.
+    #[cfg(feature = "vor4x")]
+    pub fn init<Tim: TimInstance>(
+        &self,
+        _tim: Tim,
+        clocks: &crate::clock::Clocks,
+    ) -> Result<(), RefClockTooSlowError> {
+        let scale = tick_scale(Tim::clock(clocks), 1_000_000)?;
+        self.0.init(Tim::ID, scale, Tim::IRQ);
+        Ok(())
+    }
+
+    /// Current time. Returns an instant with 0 ticks if the clock was not initialized yet.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        Instant::from_ticks(self.0.now())
+    }
+
+    /// Time elapsed since the given instant, or a zero duration if the instant lies in the
+    /// future.
+    #[inline]
+    pub fn elapsed_since(&self, instant: Instant) -> Duration {
+        self.now()
+            .checked_duration_since(instant)
+            .unwrap_or(Duration::from_ticks(0))
+    }
+
+    /// Should be called inside the IRQ of the timer used by the clock.
+    ///
+    /// # Safety
+    ///
+    /// This function has to be called once by the TIM IRQ used for the clock.
+    #[inline]
+    pub unsafe fn on_interrupt(&self) {
+        self.0.next_period();
+    }
+}
+
+//==================================================================================================
+// Timeouts
+//==================================================================================================
+
+/// Blocking operation timed out.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("operation timed out")]
+pub struct TimeoutError;
+
+/// Error of an asynchronous operation with a timeout, which distinguishes the expired timeout
+/// from the errors of the operation itself.
+#[cfg(feature = "async-timeouts")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AsyncTimeoutError<E> {
+    /// The timeout expired before the operation completed. The peripheral was left in a state
+    /// which allows to start the next operation immediately.
+    #[error("operation timed out")]
+    Timeout,
+    #[error("{0}")]
+    Operation(E),
+}
+
+#[cfg(feature = "async-timeouts")]
+impl<E> From<TimeoutError> for AsyncTimeoutError<E> {
+    fn from(_: TimeoutError) -> Self {
+        Self::Timeout
+    }
+}
+
+#[cfg(feature = "async-timeouts")]
+impl<E: embedded_io::Error> embedded_io::Error for AsyncTimeoutError<E> {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        match self {
+            AsyncTimeoutError::Timeout => embedded_io::ErrorKind::TimedOut,
+            AsyncTimeoutError::Operation(e) => e.kind(),
+        }
+    }
+}
+
+/// Runs the future until it completes or the timeout of the embassy time driver expires. The
+/// future is dropped if the timeout expires, so the caller is responsible for the cleanup.
+#[cfg(feature = "async-timeouts")]
+pub(crate) async fn with_timeout<F: core::future::Future>(
+    timeout: embassy_time::Duration,
+    fut: F,
+) -> Result<F::Output, TimeoutError> {
+    embassy_time::with_timeout(timeout, fut)
+        .await
+        .map_err(|_| TimeoutError)
+}
+
+/// Upper bound for a blocking operation.
+pub trait Deadline {
+    /// Returns [true] if the deadline has passed. This is called once per poll iteration of the
+    /// blocking operation.
+    fn expired(&mut self) -> bool;
+}
+
+/// Deadline based on a [DelayNs] implementation, for example a
+/// [CountdownTimer](crate::timer::CountdownTimer).
+///
+/// Every poll iteration delays for the poll interval and the elapsed time is approximated by the
+/// sum of all delays. The time spent polling is not accounted for, so the effective timeout is
+/// always longer than the specified timeout.
+pub struct DelayDeadline<'delay, Delay: DelayNs> {
+    delay: &'delay mut Delay,
+    remaining_us: u64,
+    poll_interval_us: u32,
+}
+
+impl<'delay, Delay: DelayNs> DelayDeadline<'delay, Delay> {
+    /// Create a new deadline with a poll interval of 1 us.
+    pub fn new(delay: &'delay mut Delay, timeout: Duration) -> Self {
+        Self::new_with_poll_interval(delay, timeout, 1)
+    }
+
+    /// Create a new deadline with the given poll interval in microseconds. A poll interval of
+    /// 0 is treated like a poll interval of 1 us.
+    pub fn new_with_poll_interval(
+        delay: &'delay mut Delay,
+        timeout: Duration,
+        poll_interval_us: u32,
+    ) -> Self {
+        Self {
+            delay,
+            remaining_us: timeout.ticks(),
+            poll_interval_us: poll_interval_us.max(1),
+        }
+    }
+}
+
+impl<Delay: DelayNs> Deadline for DelayDeadline<'_, Delay> {
+    fn expired(&mut self) -> bool {
+        if self.remaining_us == 0 {
+            return true;
+        }
+        let step = self.remaining_us.min(self.poll_interval_us as u64);
+        self.delay.delay_us(step as u32);
+        self.remaining_us -= step;
+        false
+    }
+}
+
+/// Deadline based on the [MonotonicClock].
+pub struct ClockDeadline<'clock> {
+    clock: &'clock MonotonicClock,
+    start: u64,
+    timeout: u64,
+}
+
+impl<'clock> ClockDeadline<'clock> {
+    /// Create a new deadline which expires the given timeout after the current time.
+    pub fn new(clock: &'clock MonotonicClock, timeout: Duration) -> Self {
+        Self {
+            clock,
+            start: clock.now().ticks(),
+            timeout: timeout.ticks(),
+        }
+    }
+}
+
+impl Deadline for ClockDeadline<'_> {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        deadline_expired(self.start, self.clock.now().ticks(), self.timeout)
+    }
+}
+
+/// Deadline which never expires, used for the unbounded blocking operations.
+pub(crate) struct NoDeadline;
+
+impl Deadline for NoDeadline {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        false
+    }
+}
+
+/// Checks whether the timeout has passed since the start time. This also works if the
+/// underlying counter wrapped around once in between.
+#[inline]
+pub(crate) const fn deadline_expired(start: u64, now: u64, timeout: u64) -> bool {
+    now.wrapping_sub(start) >= timeout
+}
+
+/// Calls the poll function until it returns a value or the deadline expires. The poll function
+/// is always called at least once.
+pub(crate) fn poll_with_deadline<T>(
+    deadline: &mut impl Deadline,
+    mut poll: impl FnMut() -> Option<T>,
+) -> Result<T, TimeoutError> {
+    let mut busy_wait = BusyWait::new();
+    loop {
+        busy_wait.tick();
+        if let Some(value) = poll() {
+            return Ok(value);
+        }
+        if deadline.expired() {
+            return Err(TimeoutError);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Delay which only records the requested delays.
+    #[derive(Default)]
+    struct RecordingDelay {
+        total_ns: u64,
+        calls: u32,
+    }
+
+    impl DelayNs for RecordingDelay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.total_ns += ns as u64;
+            self.calls += 1;
+        }
+    }
+
+    #[test]
+    fn tick_scale_requires_ref_clock_at_least_tick_rate() {
+        assert_eq!(tick_scale(Hertz::from_raw(50_000_000), 1_000_000), Ok(50));
+        assert_eq!(tick_scale(Hertz::from_raw(1_000_000), 1_000_000), Ok(1));
+        assert_eq!(
+            tick_scale(Hertz::from_raw(999_999), 1_000_000),
+            Err(RefClockTooSlowError {
+                ref_clk: Hertz::from_raw(999_999),
+                tick_hz: 1_000_000,
+            })
+        );
+    }
+
+    #[test]
+    fn deadline_arithmetic() {
+        assert!(!deadline_expired(100, 100, 10));
+        assert!(!deadline_expired(100, 109, 10));
+        assert!(deadline_expired(100, 110, 10));
+        assert!(deadline_expired(100, 100, 0));
+    }
+
+    #[test]
+    fn deadline_arithmetic_wraparound() {
+        let start = u64::MAX - 4;
+        assert!(!deadline_expired(start, u64::MAX, 10));
+        assert!(!deadline_expired(start, 4, 10));
+        assert!(deadline_expired(start, 5, 10));
+        assert!(deadline_expired(start, 6, 10));
+    }
+
+    #[test]
+    fn delay_deadline_accounts_poll_intervals() {
+        let mut delay = RecordingDelay::default();
+        let mut deadline =
+            DelayDeadline::new_with_poll_interval(&mut delay, Duration::from_ticks(25), 10);
+        assert!(!deadline.expired());
+        assert!(!deadline.expired());
+        // The last step is clamped to the remaining time.
+        assert!(!deadline.expired());
+        assert!(deadline.expired());
+        assert_eq!(delay.calls, 3);
+        assert_eq!(delay.total_ns, 25_000);
+    }
+
+    #[test]
+    fn delay_deadline_zero_timeout() {
+        let mut delay = RecordingDelay::default();
+        let mut deadline = DelayDeadline::new(&mut delay, Duration::from_ticks(0));
+        assert!(deadline.expired());
+        assert_eq!(delay.calls, 0);
+    }
+
+    #[test]
+    fn poll_with_deadline_success_and_timeout() {
+        let mut delay = RecordingDelay::default();
+        let mut deadline = DelayDeadline::new(&mut delay, Duration::from_ticks(5));
+        let mut polls = 0;
+        let result = poll_with_deadline(&mut deadline, || {
+            polls += 1;
+            (polls == 3).then_some(polls)
+        });
+        assert_eq!(result, Ok(3));
+
+        let mut polls = 0;
+        let result: Result<(), _> = poll_with_deadline(&mut deadline, || {
+            polls += 1;
+            None
+        });
+        assert_eq!(result, Err(TimeoutError));
+        // The two delays of the first poll were already consumed.
+        assert_eq!(polls, 4);
+        assert_eq!(delay.total_ns, 5_000);
+    }
+}
@@ -1,20 +1,27 @@
+pub mod phase;
 pub mod regs;
 
 use core::convert::Infallible;
 
+use portable_atomic::{AtomicU32, Ordering};
+
 #[cfg(feature = "vor1x")]
 pub use crate::InterruptConfig;
-#[cfg(feature = "vor1x")]
-use crate::sysconfig::enable_peripheral_clock;
-pub use regs::{CascadeSource, InvalidTimerIndex, TimId};
+pub use phase::{PhaseTrigger, PhaseTriggerError};
+pub use regs::{
+    CascadeSource, InvalidCascadeSourceId, InvalidCascadeSourceReason, InvalidTimerIndex, TimId,
+};
 
-use crate::{enable_nvic_interrupt, sealed::Sealed, time::Hertz};
+use crate::{
+    busy_wait::BusyWait,
+    enable_nvic_interrupt,
+    power::SleepParticipant,
+    sealed::Sealed,
+    time::{Hertz, Microseconds},
+};
 use crate::{gpio::DynPinId, ioconfig::regs::FunctionSelect, pins::AnyPin};
 use fugit::RateExtU32;
 
-#[cfg(feature = "vor1x")]
-use crate::PeripheralSelect;
-
 #[cfg(feature = "vor1x")]
 use va108xx as pac;
 #[cfg(feature = "vor4x")]
@@ -23,6 +30,166 @@ use va416xx as pac;
 #[cfg(feature = "vor4x")]
 pub const TIM_IRQ_OFFSET: usize = 48;
 
+/// Expiration counters incremented by [on_interrupt], one for each TIM peripheral.
+static TIM_EXPIRATIONS: [AtomicU32; 24] = [const { AtomicU32::new(0) }; 24];
+
+/// Bitmask of the TIM peripherals for which [on_interrupt] was called at least once.
+static TIM_COUNTING_ACTIVE: AtomicU32 = AtomicU32::new(0);
+
+/// Interrupt handler which counts the expirations of the timer for [CountdownTimer::wait] and
+/// [CountdownTimer::poll_expirations].
+///
+/// This should be called in the interrupt handler of every TIM peripheral whose
+/// [CountdownTimer] has the interrupt enabled and is polled with these functions. The counting
+/// is opt-in: The polling functions only use the counted expirations after this handler was
+/// called for the timer at least once, and infer the expirations from the counter value before.
+#[inline]
+pub fn on_interrupt(id: TimId) {
+    TIM_EXPIRATIONS[id.value() as usize].fetch_add(1, Ordering::Relaxed);
+    TIM_COUNTING_ACTIVE.fetch_or(1 << id.value(), Ordering::Relaxed);
+}
+
+/// Checks whether [on_interrupt] was called for the given timer at least once.
+#[inline]
+fn interrupt_counting_active(id: TimId) -> bool {
+    (TIM_COUNTING_ACTIVE.load(Ordering::Relaxed) >> id.value()) & 1 == 1
+}
+
+/// Number of expirations counted by [on_interrupt] since the last reset of the MCU. The counter
+/// wraps around.
+#[inline]
+pub fn expiration_count(id: TimId) -> u32 {
+    TIM_EXPIRATIONS[id.value() as usize].load(Ordering::Relaxed)
+}
+
+/// Read-modify-write of the control register of a timer inside a critical section.
+///
+/// [TimIrqView::disable_irq] may modify the control register from an interrupt handler while a
+/// driver owns the timer, so neither side may be preempted between the read and the write.
+#[inline]
+pub(crate) fn modify_control(
+    regs: &mut regs::MmioTimer<'static>,
+    f: impl FnOnce(regs::Control) -> regs::Control,
+) {
+    critical_section::with(|_| regs.modify_control(f));
+}
+
+/// Minimal view of a TIM peripheral for interrupt handlers.
+///
+/// The view reads the control and counter registers and can only disable the interrupt of the
+/// timer, so it can be created safely while a driver owns the timer, see [crate::ownership].
+/// All read-modify-write accesses to the control register are done in a critical section.
+pub struct TimIrqView(regs::MmioTimer<'static>);
+
+impl TimIrqView {
+    #[inline]
+    pub const fn new(id: TimId) -> Self {
+        Self(regs::Timer::new_mmio(id))
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.0.read_control().enable()
+    }
+
+    /// Active status of the timer, which is set while the counter is running.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.0.read_control().active()
+    }
+
+    #[inline]
+    pub fn irq_enabled(&self) -> bool {
+        self.0.read_control().irq_enable()
+    }
+
+    /// Disable the interrupt of the timer, for example for a one-shot expiration.
+    #[inline]
+    pub fn disable_irq(&mut self) {
+        modify_control(&mut self.0, |mut value| {
+            value.set_irq_enable(false);
+            value
+        });
+    }
+
+    #[inline]
+    pub fn count_value(&self) -> u32 {
+        self.0.read_count_value()
+    }
+}
+
+/// Expirations counted by the interrupt handler which were not consumed by the polling
+/// functions of [CountdownTimer] yet.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+struct ExpirationLatch {
+    /// Value of the expiration counter at the last update.
+    seen: u32,
+    pending: u32,
+    /// An expiry was reported by the count heuristic before the interrupt handler counted an
+    /// expiry. Only the expiry which raised the first interrupt can be counted twice, because
+    /// the heuristic is not used anymore afterwards.
+    inferred: bool,
+}
+
+impl ExpirationLatch {
+    /// Discard all pending expirations, starting from the given counter value.
+    fn reset(&mut self, counter: u32) {
+        self.seen = counter;
+        self.pending = 0;
+        self.inferred = false;
+    }
+
+    /// Record an expiry which was reported by the count heuristic.
+    fn record_inferred(&mut self) {
+        self.inferred = true;
+    }
+
+    /// Latch the expirations counted since the last update. If the count heuristic reported an
+    /// expiry before, the first counted expiration is consumed.
+    fn update(&mut self, counter: u32) {
+        self.pending = self.pending.saturating_add(counter.wrapping_sub(self.seen));
+        self.seen = counter;
+        if self.inferred && self.pending > 0 {
+            self.pending -= 1;
+            self.inferred = false;
+        }
+    }
+
+    fn take_one(&mut self) -> bool {
+        if self.pending == 0 {
+            return false;
+        }
+        self.pending -= 1;
+        true
+    }
+
+    fn take_all(&mut self) -> u32 {
+        core::mem::take(&mut self.pending)
+    }
+}
+
+/// Expiry heuristic of [CountdownTimer::wait] without the interrupt: The count was reloaded
+/// since the last poll. This can detect at most one expiry between two polls.
+#[inline]
+const fn count_wrapped(last_cnt: u32, cnt: u32) -> bool {
+    cnt > last_cnt || cnt == 0
+}
+
+/// Converts a duration to the reload value of a timer with the given reference clock.
+///
+/// Like for [CountdownTimer::load], the reload value is the number of timer clock cycles of the
+/// duration, rounded down. The result is clamped to the range from 1 to [u32::MAX].
+pub const fn duration_to_reload(ref_clk: Hertz, duration: Microseconds) -> u32 {
+    let ticks = ref_clk.raw() as u64 * duration.ticks() as u64 / 1_000_000;
+    if ticks == 0 {
+        return 1;
+    }
+    if ticks > u32::MAX as u64 {
+        return u32::MAX;
+    }
+    ticks as u32
+}
+
 //==================================================================================================
 // Defintions
 //==================================================================================================
@@ -81,13 +248,10 @@ pub trait TimInstance: Sealed {
     #[cfg(feature = "vor4x")]
     const IRQ: va416xx::Interrupt;
 
+    /// See [TimId::clock].
     #[cfg(feature = "vor4x")]
     fn clock(clocks: &crate::clock::Clocks) -> Hertz {
-        if Self::ID.value() <= 15 {
-            clocks.apb1()
-        } else {
-            clocks.apb2()
-        }
+        Self::ID.clock(clocks)
     }
 }
 
@@ -198,6 +362,18 @@ pub struct CountdownTimer {
     ref_clk: Hertz,
     rst_val: u32,
     last_cnt: u32,
+    expirations: ExpirationLatch,
+    // Configuration applied by the driver, replayed by [Self::reinitialize].
+    #[cfg(feature = "vor1x")]
+    irq_cfg: Option<InterruptConfig>,
+    irq_enabled: bool,
+    auto_disable: bool,
+    auto_deactivate: bool,
+    cascade_ctrl: CascadeControl,
+    cascade_src: [regs::CascadeSourceReg; 3],
+    compare: u32,
+    /// Enable state recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
 }
 
 impl CountdownTimer {
@@ -209,6 +385,7 @@ impl CountdownTimer {
     #[cfg(feature = "vor1x")]
     pub fn new<Tim: TimInstance>(_tim: Tim, sys_clk: Hertz) -> Self {
         enable_tim_clk(Tim::ID);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(Tim::ID));
         assert_tim_reset_for_cycles(Tim::ID, 2);
         CountdownTimer {
             id: Tim::ID,
@@ -217,6 +394,15 @@ impl CountdownTimer {
             rst_val: 0,
             curr_freq: 0.Hz(),
             last_cnt: 0,
+            expirations: ExpirationLatch::default(),
+            irq_cfg: None,
+            irq_enabled: false,
+            auto_disable: false,
+            auto_deactivate: false,
+            cascade_ctrl: CascadeControl::default(),
+            cascade_src: [regs::CascadeSourceReg::new_with_raw_value(0); 3],
+            compare: 0,
+            sleep_state: None,
         }
     }
 
@@ -224,18 +410,27 @@ impl CountdownTimer {
     ///
     /// This does not enable the timer. You can use the [Self::load], [Self::start],
     /// [Self::enable_interrupt] and [Self::enable] API to set up and configure the countdown
-    /// timer.
+    /// timer. The reference clock is the APB clock of the TIM peripheral, see [TimId::clock].
     #[cfg(feature = "vor4x")]
     pub fn new<Tim: TimInstance>(_tim: Tim, clks: &crate::clock::Clocks) -> Self {
         enable_tim_clk(Tim::ID);
+        crate::ownership::mark_owned(crate::ownership::RegisterBlock::Tim(Tim::ID));
         assert_tim_reset_for_cycles(Tim::ID, 2);
         CountdownTimer {
             id: Tim::ID,
             regs: regs::Timer::new_mmio(Tim::ID),
-            ref_clk: clks.apb1(),
+            ref_clk: Tim::clock(clks),
             rst_val: 0,
             curr_freq: 0.Hz(),
             last_cnt: 0,
+            expirations: ExpirationLatch::default(),
+            irq_enabled: false,
+            auto_disable: false,
+            auto_deactivate: false,
+            cascade_ctrl: CascadeControl::default(),
+            cascade_src: [regs::CascadeSourceReg::new_with_raw_value(0); 3],
+            compare: 0,
+            sleep_state: None,
         }
     }
 
@@ -244,6 +439,12 @@ impl CountdownTimer {
         self.regs.read_perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.perid() == regs::EXPECTED_PERID
+    }
+
     #[inline(always)]
     pub fn enable(&mut self) {
         self.regs
@@ -257,29 +458,35 @@ impl CountdownTimer {
 
     #[cfg(feature = "vor1x")]
     pub fn enable_interrupt(&mut self, irq_cfg: InterruptConfig) {
+        self.irq_cfg = Some(irq_cfg);
+        self.irq_enabled = true;
+        self.reset_expirations();
+        self.route_interrupt(irq_cfg);
+        modify_control(&mut self.regs, |mut value| {
+            value.set_irq_enable(true);
+            value
+        });
+    }
+
+    #[cfg(feature = "vor1x")]
+    fn route_interrupt(&self, irq_cfg: InterruptConfig) {
         if irq_cfg.route {
-            let irqsel = unsafe { pac::Irqsel::steal() };
-            enable_peripheral_clock(PeripheralSelect::Irqsel);
-            irqsel
-                .tim(self.id.value() as usize)
-                .write(|w| unsafe { w.bits(irq_cfg.id as u32) });
+            crate::irqsel::route_tim(self.id, irq_cfg.id);
         }
         if irq_cfg.enable_in_nvic {
             unsafe { enable_nvic_interrupt(irq_cfg.id) };
         }
-        self.regs.modify_control(|mut value| {
-            value.set_irq_enable(true);
-            value
-        });
     }
 
     #[cfg(feature = "vor4x")]
     #[inline(always)]
     pub fn enable_interrupt(&mut self, enable_in_nvic: bool) {
+        self.irq_enabled = true;
+        self.reset_expirations();
         if enable_in_nvic {
             unsafe { enable_nvic_interrupt(self.id.interrupt_id()) };
         }
-        self.regs.modify_control(|mut value| {
+        modify_control(&mut self.regs, |mut value| {
             value.set_irq_enable(true);
             value
         });
@@ -290,7 +497,8 @@ impl CountdownTimer {
     /// It does not mask the interrupt in the NVIC or un-route the IRQ.
     #[inline(always)]
     pub fn disable_interrupt(&mut self) {
-        self.regs.modify_control(|mut value| {
+        self.irq_enabled = false;
+        modify_control(&mut self.regs, |mut value| {
             value.set_irq_enable(false);
             value
         });
@@ -302,12 +510,36 @@ impl CountdownTimer {
         self.enable();
     }
 
-    /// Return `Ok` if the timer has wrapped. Peripheral will automatically clear the
-    /// flag and restart the time if configured correctly
+    /// Return `Ok` if the timer has expired since the last call.
+    ///
+    /// If the interrupt is enabled and the interrupt handler [on_interrupt] is called for this
+    /// timer, the expirations are counted by the handler. This function then returns `Ok` once
+    /// for every expiration, so expirations are not lost if it is polled less often than once per
+    /// timer period. A periodic task polling this function therefore catches up on missed
+    /// periods instead of drifting.
+    ///
+    /// Otherwise, the expiry is inferred by comparing the current count to the count of the
+    /// previous call. This can only detect one expiry between two calls, so whole periods are
+    /// missed if this function is polled less often than once per timer period. This is also
+    /// used with the interrupt enabled until [on_interrupt] was called for this timer for the
+    /// first time, so an interrupt handler which does not call it does not block this function.
+    /// If the expiry which raised the first interrupt was already reported this way, it is not
+    /// reported again when it is counted by [on_interrupt].
     pub fn wait(&mut self) -> nb::Result<(), Infallible> {
+        if self.isr_counting() {
+            self.expirations.update(expiration_count(self.id));
+            if self.expirations.take_one() {
+                return Ok(());
+            }
+            return Err(nb::Error::WouldBlock);
+        }
         let cnt = self.counter();
-        if (cnt > self.last_cnt) || cnt == 0 {
+        if count_wrapped(self.last_cnt, cnt) {
             self.last_cnt = self.rst_val;
+            if self.irq_enabled {
+                // The interrupt handler may count this expiry as well once it is called.
+                self.expirations.record_inferred();
+            }
             Ok(())
         } else {
             self.last_cnt = cnt;
@@ -315,6 +547,32 @@ impl CountdownTimer {
         }
     }
 
+    /// Returns and consumes the number of expirations since the last call of this function or
+    /// [Self::wait].
+    ///
+    /// This requires the interrupt to be enabled and the interrupt handler [on_interrupt] to be
+    /// called for this timer. Otherwise, this works like [Self::wait] and returns at most 1.
+    pub fn poll_expirations(&mut self) -> u32 {
+        if self.isr_counting() {
+            self.expirations.update(expiration_count(self.id));
+            return self.expirations.take_all();
+        }
+        self.wait().is_ok() as u32
+    }
+
+    /// The expirations are counted by [on_interrupt].
+    #[inline]
+    fn isr_counting(&self) -> bool {
+        self.irq_enabled && interrupt_counting_active(self.id)
+    }
+
+    /// Discard the expirations which were counted by [on_interrupt] but not consumed yet. The
+    /// count based expiry detection restarts from the reload value.
+    pub fn reset_expirations(&mut self) {
+        self.expirations.reset(expiration_count(self.id));
+        self.last_cnt = self.rst_val;
+    }
+
     /// Load the count down timer with a timeout but do not start it.
     pub fn load(&mut self, timeout: impl Into<Hertz>) {
         self.disable();
@@ -322,10 +580,24 @@ impl CountdownTimer {
         self.rst_val = self.ref_clk.raw() / self.curr_freq.raw();
         self.set_reload(self.rst_val);
         self.set_count(self.rst_val);
+        self.reset_expirations();
+    }
+
+    /// Load the count down timer with a timeout given as a duration but do not start it.
+    ///
+    /// The duration is converted to timer clock cycles with [duration_to_reload].
+    pub fn load_duration(&mut self, timeout: impl Into<Microseconds>) {
+        self.disable();
+        self.rst_val = duration_to_reload(self.ref_clk, timeout.into());
+        self.curr_freq = Hertz::from_raw(self.ref_clk.raw() / self.rst_val);
+        self.set_reload(self.rst_val);
+        self.set_count(self.rst_val);
+        self.reset_expirations();
     }
 
     #[inline(always)]
     pub fn set_reload(&mut self, val: u32) {
+        self.rst_val = val;
         self.regs.write_reset_value(val);
     }
 
@@ -342,7 +614,8 @@ impl CountdownTimer {
     /// Disable the counter, setting both enable and active bit to 0
     #[inline]
     pub fn auto_disable(&mut self, enable: bool) {
-        self.regs.modify_control(|mut value| {
+        self.auto_disable = enable;
+        modify_control(&mut self.regs, |mut value| {
             value.set_auto_disable(enable);
             value
         });
@@ -354,7 +627,8 @@ impl CountdownTimer {
     /// enabled. When Auto-Disable is 1, Auto-Deactivate is implied
     #[inline]
     pub fn auto_deactivate(&mut self, enable: bool) {
-        self.regs.modify_control(|mut value| {
+        self.auto_deactivate = enable;
+        modify_control(&mut self.regs, |mut value| {
             value.set_auto_deactivate(enable);
             value
         });
@@ -362,6 +636,7 @@ impl CountdownTimer {
 
     /// Configure the cascade parameters
     pub fn cascade_control(&mut self, ctrl: CascadeControl) {
+        self.cascade_ctrl = ctrl;
         self.regs.write_cascade_control(
             regs::CascadeControl::builder()
                 .with_trigger2(ctrl.trigger_mode_2)
@@ -378,29 +653,166 @@ impl CountdownTimer {
         );
     }
 
-    pub fn cascade_source(
+    /// Configure the source for the given cascade.
+    pub fn set_cascade_source(
         &mut self,
         cascade_index: CascadeSelect,
         src: regs::CascadeSource,
     ) -> Result<(), regs::InvalidCascadeSourceId> {
+        let idx = cascade_index as usize;
+        let src_reg = regs::CascadeSourceReg::new(src)?;
+        self.cascade_src[idx] = src_reg;
         // Safety: Index range safe by enum values.
         unsafe {
-            self.regs
-                .write_cascade_unchecked(cascade_index as usize, regs::CascadeSourceReg::new(src)?);
+            self.regs.write_cascade_unchecked(idx, src_reg);
         }
         Ok(())
     }
 
+    /// Read back the configured source for the given cascade.
+    pub fn cascade_source(
+        &self,
+        cascade_index: CascadeSelect,
+    ) -> Result<regs::CascadeSource, regs::InvalidCascadeSourceId> {
+        // Safety: Index range safe by enum values.
+        unsafe { self.regs.read_cascade_unchecked(cascade_index as usize) }.as_cascade_source()
+    }
+
+    /// Set the compare value, which is the PWM A value of the TIM peripheral.
+    ///
+    /// The TIM peripheral has no interrupt or status flag for a compare match, the interrupt is
+    /// only generated when the count reaches 0. The compare value only affects the status output
+    /// for the [regs::StatusSelect::PwmaOutput] and [regs::StatusSelect::PwmaActiveBit]
+    /// selections, which can be routed to a TIM pin or used as a cascade source of another timer.
+    /// [phase::PhaseTrigger] can be used to generate an interrupt at an arbitrary phase of the
+    /// timer period.
+    #[inline]
+    pub fn set_compare(&mut self, value: u32) {
+        self.compare = value;
+        self.regs.write_pwma_value(value);
+    }
+
+    #[inline]
+    pub fn compare(&self) -> u32 {
+        self.regs.read_pwma_value()
+    }
+
+    /// Reload value of the counter, which was set with [Self::load] or [Self::set_reload]. The
+    /// timer period is this value plus one timer clock cycles.
+    #[inline]
+    pub fn reload(&self) -> u32 {
+        self.rst_val
+    }
+
+    #[inline]
+    pub fn id(&self) -> TimId {
+        self.id
+    }
+
     pub fn curr_freq(&self) -> Hertz {
         self.curr_freq
     }
 
+    /// Reference clock of the timer peripheral.
+    #[inline]
+    pub fn ref_clk(&self) -> Hertz {
+        self.ref_clk
+    }
+
+    /// Returns the enable bit of the timer, which is cleared by hardware if Auto-Disable is
+    /// enabled and the count reaches 0.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.regs.read_control().enable()
+    }
+
+    /// Checks whether the timer interrupt is pending in the NVIC.
+    ///
+    /// The TIM peripheral does not latch an interrupt status, it only emits a single cycle
+    /// pulse when the count reaches 0, which is latched as the pending state in the NVIC. On
+    /// Vorago 1x devices, this state belongs to the interrupt line and can not be attributed to a
+    /// single timer if multiple timers are routed to the same line. It returns [false] if the
+    /// timer interrupt was not routed by the HAL, see [TimId::routed_interrupt].
+    #[inline]
+    pub fn irq_pending(&self) -> bool {
+        #[cfg(feature = "vor1x")]
+        return self
+            .id
+            .routed_interrupt()
+            .is_some_and(crate::is_interrupt_pending);
+        #[cfg(feature = "vor4x")]
+        return crate::is_interrupt_pending(self.id.interrupt_id());
+    }
+
+    /// Clear the pending state of the timer interrupt in the NVIC.
+    ///
+    /// This can be used to acknowledge a timer expiry which was polled with [Self::irq_pending]
+    /// while the interrupt is masked. The same restrictions as for [Self::irq_pending] apply.
+    #[inline]
+    pub fn clear_irq(&mut self) {
+        #[cfg(feature = "vor1x")]
+        if let Some(irq) = self.id.routed_interrupt() {
+            crate::clear_pending(irq);
+        }
+        #[cfg(feature = "vor4x")]
+        crate::clear_pending(self.id.interrupt_id());
+    }
+
+    /// Reset the timer peripheral and replay the configuration applied with the driver.
+    ///
+    /// This can be used to recover from faults like single event upsets without re-creating the
+    /// driver. The reload value, compare value, interrupt configuration, auto-disable and
+    /// auto-deactivate settings and the cascade configuration are restored. The counter is restarted with the
+    /// reload value if the timer was enabled before the reset.
+    pub fn reinitialize(&mut self) {
+        let enabled = self.regs.read_control().enable();
+        enable_tim_clk(self.id);
+        assert_tim_reset_for_cycles(self.id, 2);
+        self.regs.write_control(
+            regs::Control::new_with_raw_value(0)
+                .with_irq_enable(self.irq_enabled)
+                .with_auto_disable(self.auto_disable)
+                .with_auto_deactivate(self.auto_deactivate),
+        );
+        #[cfg(feature = "vor1x")]
+        if let Some(irq_cfg) = self.irq_cfg {
+            self.route_interrupt(irq_cfg);
+        }
+        self.cascade_control(self.cascade_ctrl);
+        for (idx, src_reg) in self.cascade_src.iter().enumerate() {
+            // Safety: Index range safe by array length.
+            unsafe { self.regs.write_cascade_unchecked(idx, *src_reg) };
+        }
+        self.regs.write_pwma_value(self.compare);
+        self.set_reload(self.rst_val);
+        self.set_count(self.rst_val);
+        self.reset_expirations();
+        if enabled {
+            self.enable();
+        }
+    }
+
     /// Disables the TIM and the dedicated TIM clock.
     pub fn stop_with_clock_disable(mut self) {
         self.disable();
-        unsafe { pac::Sysconfig::steal() }
-            .tim_clk_enable()
-            .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << self.id.value())) });
+        disable_tim_clk(self.id);
+    }
+}
+
+impl SleepParticipant for CountdownTimer {
+    /// Pauses the timer. A timer which is used as the wake-up source should not be prepared.
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_none() {
+            self.sleep_state = Some(self.is_enabled());
+            self.disable();
+        }
+    }
+
+    /// Continues the timer if it was enabled before.
+    fn resume(&mut self) {
+        if self.sleep_state.take() == Some(true) {
+            self.enable();
+        }
     }
 }
 
@@ -413,6 +825,7 @@ impl embedded_hal::delay::DelayNs for CountdownTimer {
         let ticks = (u64::from(ns)) * (u64::from(self.ref_clk.raw())) / 1_000_000_000;
 
         let full_cycles = ticks >> 32;
+        let mut busy_wait = BusyWait::new();
         let mut last_count;
         let mut new_count;
         if full_cycles > 0 {
@@ -425,6 +838,7 @@ impl embedded_hal::delay::DelayNs for CountdownTimer {
                 new_count = self.counter();
                 last_count = new_count;
                 loop {
+                    busy_wait.tick();
                     new_count = self.counter();
                     if new_count == 0 {
                         // Wait till timer has wrapped.
@@ -450,6 +864,7 @@ impl embedded_hal::delay::DelayNs for CountdownTimer {
             last_count = ticks;
 
             loop {
+                busy_wait.tick();
                 new_count = self.counter();
                 if new_count == 0 || (new_count > last_count) {
                     break;
@@ -462,18 +877,60 @@ impl embedded_hal::delay::DelayNs for CountdownTimer {
     }
 }
 
+cfg_if::cfg_if! {
+    if #[cfg(feature = "test-harness")] {
+        use crate::test_harness::{TIM_CLK_ENABLE, TIM_RESET};
+
+        #[inline]
+        fn read_tim_clk_enable() -> u32 {
+            TIM_CLK_ENABLE.load(Ordering::Relaxed)
+        }
+
+        #[inline]
+        fn modify_tim_clk_enable(f: impl FnOnce(u32) -> u32) {
+            TIM_CLK_ENABLE.store(f(TIM_CLK_ENABLE.load(Ordering::Relaxed)), Ordering::Relaxed);
+        }
+
+        #[inline]
+        fn modify_tim_reset(f: impl FnOnce(u32) -> u32) {
+            TIM_RESET.store(f(TIM_RESET.load(Ordering::Relaxed)), Ordering::Relaxed);
+        }
+    } else {
+        #[inline]
+        fn read_tim_clk_enable() -> u32 {
+            unsafe { pac::Sysconfig::steal() }.tim_clk_enable().read().bits()
+        }
+
+        #[inline]
+        fn modify_tim_clk_enable(f: impl FnOnce(u32) -> u32) {
+            unsafe { pac::Sysconfig::steal() }
+                .tim_clk_enable()
+                .modify(|r, w| unsafe { w.bits(f(r.bits())) });
+        }
+
+        #[inline]
+        fn modify_tim_reset(f: impl FnOnce(u32) -> u32) {
+            unsafe { pac::Sysconfig::steal() }
+                .tim_reset()
+                .modify(|r, w| unsafe { w.bits(f(r.bits())) });
+        }
+    }
+}
+
 #[inline(always)]
 pub fn enable_tim_clk(id: TimId) {
-    unsafe { pac::Sysconfig::steal() }
-        .tim_clk_enable()
-        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << id.value())) });
+    modify_tim_clk_enable(|value| value | (1 << id.value()));
 }
 
 #[inline(always)]
 pub fn disable_tim_clk(id: TimId) {
-    unsafe { pac::Sysconfig::steal() }
-        .tim_clk_enable()
-        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << (id.value()))) });
+    modify_tim_clk_enable(|value| value & !(1 << id.value()));
+}
+
+/// Checks whether the clock of the given TIM peripheral is enabled.
+#[inline]
+pub fn is_tim_clk_enabled(id: TimId) -> bool {
+    (read_tim_clk_enable() >> id.value()) & 1 == 1
 }
 
 /// Clear the reset bit of the TIM, holding it in reset
@@ -483,22 +940,160 @@ pub fn disable_tim_clk(id: TimId) {
 /// Only the bit related to the corresponding TIM peripheral is modified
 #[inline]
 pub fn assert_tim_reset(id: TimId) {
-    unsafe { pac::Peripherals::steal() }
-        .sysconfig
-        .tim_reset()
-        .modify(|r, w| unsafe { w.bits(r.bits() & !(1 << id.value())) });
+    modify_tim_reset(|value| value & !(1 << id.value()));
 }
 
 #[inline]
 pub fn deassert_tim_reset(tim: TimId) {
-    unsafe { pac::Peripherals::steal() }
-        .sysconfig
-        .tim_reset()
-        .modify(|r, w| unsafe { w.bits(r.bits() | (1 << tim.value())) });
+    modify_tim_reset(|value| value | (1 << tim.value()));
 }
 
 pub fn assert_tim_reset_for_cycles(tim: TimId, cycles: u32) {
     assert_tim_reset(tim);
+    #[cfg(not(feature = "test-harness"))]
     cortex_m::asm::delay(cycles);
+    #[cfg(feature = "test-harness")]
+    let _ = cycles;
     deassert_tim_reset(tim);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "vor4x")]
+    #[test]
+    fn tim_clock_domains() {
+        let clks = crate::clock::Clocks::__new(
+            Hertz::MHz(100),
+            #[cfg(not(feature = "va41628"))]
+            Hertz::MHz(25),
+        );
+        for index in 0..=23 {
+            let id = TimId::new_unchecked(index);
+            let expected = if index <= 15 {
+                clks.apb1()
+            } else {
+                clks.apb2()
+            };
+            assert_eq!(id.clock(&clks), expected);
+        }
+        // A 1 ms period on both APB domains.
+        let period = Microseconds::millis(1);
+        assert_eq!(
+            duration_to_reload(TimId::new_unchecked(15).clock(&clks), period),
+            50_000
+        );
+        assert_eq!(
+            duration_to_reload(TimId::new_unchecked(16).clock(&clks), period),
+            25_000
+        );
+    }
+
+    #[test]
+    fn duration_reload() {
+        let ref_clk = Hertz::MHz(50);
+        assert_eq!(duration_to_reload(ref_clk, Microseconds::millis(1)), 50_000);
+        assert_eq!(duration_to_reload(ref_clk, Microseconds::micros(3)), 150);
+        // Rounded down, but at least one cycle.
+        assert_eq!(
+            duration_to_reload(Hertz::kHz(1), Microseconds::micros(1500)),
+            1
+        );
+        assert_eq!(duration_to_reload(ref_clk, Microseconds::micros(0)), 1);
+        // No overflow of the intermediate value, clamped to the largest reload value.
+        assert_eq!(
+            duration_to_reload(Hertz::MHz(100), Microseconds::secs(42)),
+            4_200_000_000
+        );
+        assert_eq!(
+            duration_to_reload(Hertz::MHz(100), Microseconds::secs(43)),
+            u32::MAX
+        );
+    }
+
+    /// Feeds a sequence of expiration counter values, one per poll, and collects the results of
+    /// [ExpirationLatch::take_one].
+    fn poll_sequence<const N: usize>(latch: &mut ExpirationLatch, counters: [u32; N]) -> [bool; N] {
+        counters.map(|counter| {
+            latch.update(counter);
+            latch.take_one()
+        })
+    }
+
+    #[test]
+    fn expirations_inferred_before_counting() {
+        let mut latch = ExpirationLatch::default();
+        latch.reset(0);
+        // Two expirations were reported by the count heuristic before the interrupt handler
+        // counted the first one. Only the expiry which raised the first interrupt is consumed,
+        // the following periods are kept.
+        latch.record_inferred();
+        latch.record_inferred();
+        assert_eq!(
+            poll_sequence(&mut latch, [1, 2, 3, 3]),
+            [false, true, true, false]
+        );
+        // The first interrupt counted several expirations.
+        latch.record_inferred();
+        latch.update(6);
+        assert_eq!(latch.take_all(), 2);
+        // Resetting discards the inferred expiry.
+        latch.record_inferred();
+        latch.reset(6);
+        latch.update(7);
+        assert_eq!(latch.take_all(), 1);
+    }
+
+    #[test]
+    fn expirations_no_expiry() {
+        let mut latch = ExpirationLatch::default();
+        latch.reset(5);
+        assert_eq!(poll_sequence(&mut latch, [5, 5, 5]), [false; 3]);
+        assert_eq!(latch.take_all(), 0);
+    }
+
+    #[test]
+    fn expirations_exactly_one_period() {
+        let mut latch = ExpirationLatch::default();
+        latch.reset(0);
+        assert_eq!(
+            poll_sequence(&mut latch, [0, 1, 1, 2, 3]),
+            [false, true, false, true, true]
+        );
+        assert_eq!(latch.take_all(), 0);
+    }
+
+    #[test]
+    fn expirations_multiple_missed_periods() {
+        let mut latch = ExpirationLatch::default();
+        latch.reset(10);
+        // Three periods expired before the first poll. Every missed period is reported once.
+        assert_eq!(
+            poll_sequence(&mut latch, [13, 13, 13, 13]),
+            [true, true, true, false]
+        );
+        latch.update(17);
+        assert_eq!(latch.take_all(), 4);
+        assert_eq!(latch.take_all(), 0);
+        // The counter wraps around.
+        latch.reset(u32::MAX - 1);
+        latch.update(2);
+        assert_eq!(latch.take_all(), 4);
+        // Resetting discards pending expirations.
+        latch.update(5);
+        latch.reset(5);
+        assert!(!latch.take_one());
+    }
+
+    #[test]
+    fn count_wrap_heuristic() {
+        // Counting down without expiry.
+        assert!(!count_wrapped(1000, 900));
+        // Reloaded since the last poll.
+        assert!(count_wrapped(100, 900));
+        assert!(count_wrapped(100, 0));
+        // A whole missed period looks like no expiry at all.
+        assert!(!count_wrapped(900, 800));
+    }
+}
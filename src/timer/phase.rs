@@ -0,0 +1,202 @@
+//! # Interrupt at an arbitrary phase of a timer period.
+//!
+//! The TIM peripheral only generates an interrupt when the count reaches 0. A compare match with
+//! the PWM A or PWM B value only changes the status output of the timer, there is no interrupt or
+//! status flag for it. [PhaseTrigger] uses a second helper timer to generate an interrupt at a
+//! fixed phase of the period of a main timer, for example to start an ADC conversion in the
+//! middle of a PWM cycle.
+//!
+//! The helper timer uses the status output of the main timer as the Cascade 0 source in trigger
+//! mode. It is armed with the same reload value as the main timer and a start count equal to the
+//! phase offset. The first rising edge of the main status output starts the helper timer, which
+//! reaches 0 after the phase offset, and then keeps counting with the same period as the main
+//! timer because the cascade input is ignored once the counter is active. Both timers must
+//! therefore use the same reference clock.
+//!
+//! The phase is relative to the rising edge of the status output of the main timer. This is the
+//! point where the count reaches 0 for the default [StatusSelect::OneCyclePulse] selection of a
+//! [CountdownTimer], and the point where the count wraps to the reload value for the PWM A mode of
+//! a [crate::pwm::PwmPin] with a duty cycle which is neither 0 nor 100 %. The hardware adds a
+//! constant synchronization delay of a few timer clock cycles.
+//!
+//! The helper timer stays synchronized as long as the reload value of the main timer does not
+//! change. It must be re-armed with [PhaseTrigger::set_phase] after the main timer period was
+//! changed.
+//!
+//! [StatusSelect::OneCyclePulse]: super::regs::StatusSelect::OneCyclePulse
+use super::{CascadeControl, CascadeSelect, CascadeSource, CountdownTimer, TimId};
+
+/// Phase resolution: The phase is specified in 1/1000 of the timer period.
+pub const PHASE_FULL_PERIOD: u16 = 1000;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PhaseTriggerError {
+    #[error("phase {0} out of range, must be below {PHASE_FULL_PERIOD}")]
+    InvalidPhase(u16),
+    /// The main timer period must be at least two timer clock cycles.
+    #[error("main timer period too short")]
+    PeriodTooShort,
+    #[error("main and helper timer use different reference clocks")]
+    ClockMismatch,
+}
+
+/// Calculates the number of timer clock cycles between the rising edge of the main timer status
+/// output and the expiry of the helper timer for the given main timer reload value and phase.
+///
+/// The result is clamped to the range from 1 to the reload value, which are the start counts the
+/// helper timer can be armed with.
+pub const fn phase_delay_ticks(reload: u32, phase_permille: u16) -> Result<u32, PhaseTriggerError> {
+    if phase_permille >= PHASE_FULL_PERIOD {
+        return Err(PhaseTriggerError::InvalidPhase(phase_permille));
+    }
+    if reload == 0 {
+        return Err(PhaseTriggerError::PeriodTooShort);
+    }
+    let period = reload as u64 + 1;
+    let ticks = period * phase_permille as u64 / PHASE_FULL_PERIOD as u64;
+    if ticks == 0 {
+        return Ok(1);
+    }
+    if ticks > reload as u64 {
+        return Ok(reload);
+    }
+    Ok(ticks as u32)
+}
+
+/// Generates an interrupt on a helper timer at a fixed phase of the period of a main timer.
+///
+/// The interrupt of the helper timer must be enabled with [CountdownTimer::enable_interrupt],
+/// which can be done before or after creating the phase trigger.
+pub struct PhaseTrigger {
+    helper: CountdownTimer,
+    main_id: TimId,
+    main_reload: u32,
+    delay_ticks: u32,
+}
+
+impl PhaseTrigger {
+    /// Create a phase trigger for a [CountdownTimer] main timer and arm the helper timer.
+    ///
+    /// The reload value of the main timer must already be configured, for example with
+    /// [CountdownTimer::load]. The phase is specified in 1/1000 of the timer period.
+    pub fn new(
+        main: &CountdownTimer,
+        helper: CountdownTimer,
+        phase_permille: u16,
+    ) -> Result<Self, PhaseTriggerError> {
+        if main.ref_clk() != helper.ref_clk() {
+            return Err(PhaseTriggerError::ClockMismatch);
+        }
+        Self::new_with_main_id(main.id(), main.reload(), helper, phase_permille)
+    }
+
+    /// Create a phase trigger for any main timer, for example a [crate::pwm::PwmPin], given its
+    /// TIM ID and its reload value, and arm the helper timer.
+    ///
+    /// The caller must ensure that both timers use the same reference clock.
+    pub fn new_with_main_id(
+        main_id: TimId,
+        main_reload: u32,
+        mut helper: CountdownTimer,
+        phase_permille: u16,
+    ) -> Result<Self, PhaseTriggerError> {
+        let delay_ticks = phase_delay_ticks(main_reload, phase_permille)?;
+        helper.disable();
+        helper.auto_disable(false);
+        helper.auto_deactivate(false);
+        helper.cascade_control(CascadeControl {
+            enable_src_0: true,
+            trigger_mode_0: true,
+            ..Default::default()
+        });
+        helper
+            .set_cascade_source(CascadeSelect::Csd0, CascadeSource::Tim(main_id.value()))
+            .expect("all TIM IDs are valid cascade sources");
+        let mut trigger = Self {
+            helper,
+            main_id,
+            main_reload,
+            delay_ticks,
+        };
+        trigger.arm();
+        Ok(trigger)
+    }
+
+    /// Change the phase and re-arm the helper timer. This must also be called after the reload
+    /// value of the main timer was changed.
+    pub fn set_phase(
+        &mut self,
+        main_reload: u32,
+        phase_permille: u16,
+    ) -> Result<(), PhaseTriggerError> {
+        self.delay_ticks = phase_delay_ticks(main_reload, phase_permille)?;
+        self.main_reload = main_reload;
+        self.arm();
+        Ok(())
+    }
+
+    fn arm(&mut self) {
+        self.helper.disable();
+        self.helper.set_reload(self.main_reload);
+        self.helper.set_count(self.delay_ticks);
+        self.helper.enable();
+    }
+
+    #[inline]
+    pub fn main_id(&self) -> TimId {
+        self.main_id
+    }
+
+    /// Number of timer clock cycles between the rising edge of the main timer status output and
+    /// the interrupt of the helper timer, excluding the synchronization delay.
+    #[inline]
+    pub fn delay_ticks(&self) -> u32 {
+        self.delay_ticks
+    }
+
+    /// Access to the helper timer, for example to enable its interrupt.
+    #[inline]
+    pub fn helper(&mut self) -> &mut CountdownTimer {
+        &mut self.helper
+    }
+
+    /// Disable the helper timer and release it.
+    pub fn release(mut self) -> CountdownTimer {
+        self.helper.disable();
+        self.helper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_delay() {
+        // 10 kHz period at 50 MHz: 5000 cycles.
+        assert_eq!(phase_delay_ticks(4999, 300), Ok(1500));
+        assert_eq!(phase_delay_ticks(4999, 500), Ok(2500));
+        assert_eq!(phase_delay_ticks(4999, 999), Ok(4995));
+        // The helper timer needs at least one cycle.
+        assert_eq!(phase_delay_ticks(4999, 0), Ok(1));
+        // Rounded down.
+        assert_eq!(phase_delay_ticks(2, 600), Ok(1));
+        assert_eq!(phase_delay_ticks(2, 999), Ok(2));
+        // No overflow for the largest reload value.
+        assert_eq!(phase_delay_ticks(u32::MAX, 500), Ok(1 << 31));
+        assert_eq!(phase_delay_ticks(u32::MAX, 999), Ok(4_290_672_328));
+    }
+
+    #[test]
+    fn phase_delay_invalid() {
+        assert_eq!(
+            phase_delay_ticks(4999, PHASE_FULL_PERIOD),
+            Err(PhaseTriggerError::InvalidPhase(PHASE_FULL_PERIOD))
+        );
+        assert_eq!(
+            phase_delay_ticks(0, 500),
+            Err(PhaseTriggerError::PeriodTooShort)
+        );
+    }
+}
@@ -2,10 +2,19 @@ use core::marker::PhantomData;
 
 use arbitrary_int::{prelude::*, u7};
 
-#[cfg(feature = "vor1x")]
-const BASE_ADDR: usize = 0x4002_0000;
-#[cfg(feature = "vor4x")]
-const BASE_ADDR: usize = 0x4001_8000;
+cfg_if::cfg_if! {
+    if #[cfg(feature = "vor1x")] {
+        #[cfg_attr(feature = "test-harness", allow(dead_code))]
+        const BASE_ADDR: usize = 0x4002_0000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0111_07E1;
+    } else if #[cfg(feature = "vor4x")] {
+        #[cfg_attr(feature = "test-harness", allow(dead_code))]
+        const BASE_ADDR: usize = 0x4001_8000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0211_07E9;
+    }
+}
 
 #[bitbybit::bitenum(u3)]
 #[derive(Debug, PartialEq, Eq)]
@@ -126,9 +135,43 @@ pub struct CascadeControl {
     en0: bool,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Reason why a cascade source is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InvalidCascadeSourceReason {
+    /// The offset of a [CascadeSource] variant with an index is out of range.
+    OffsetOutOfRange,
+    /// The raw cascade source ID does not map to a cascade source.
+    UnknownId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct InvalidCascadeSourceId;
+#[error("invalid cascade source {value}: {reason:?}")]
+pub struct InvalidCascadeSourceId {
+    /// Offending value. This is the offset for [InvalidCascadeSourceReason::OffsetOutOfRange]
+    /// and the raw ID for [InvalidCascadeSourceReason::UnknownId].
+    pub value: u32,
+    pub reason: InvalidCascadeSourceReason,
+}
+
+impl InvalidCascadeSourceId {
+    #[inline]
+    const fn offset_out_of_range(offset: u8) -> Self {
+        Self {
+            value: offset as u32,
+            reason: InvalidCascadeSourceReason::OffsetOutOfRange,
+        }
+    }
+
+    #[inline]
+    const fn unknown_id(raw: u8) -> Self {
+        Self {
+            value: raw as u32,
+            reason: InvalidCascadeSourceReason::UnknownId,
+        }
+    }
+}
 
 #[cfg(feature = "vor1x")]
 #[derive(Debug, PartialEq, Eq)]
@@ -173,13 +216,14 @@ impl CascadeSource {
     pub fn id(&self) -> Result<u7, InvalidCascadeSourceId> {
         let port_check = |base: u8, id: u8, len: u8| -> Result<u7, InvalidCascadeSourceId> {
             if id > len - 1 {
-                return Err(InvalidCascadeSourceId);
+                return Err(InvalidCascadeSourceId::offset_out_of_range(id));
             }
             Ok(u7::new(base + id))
         };
         match self {
             CascadeSource::PortA(id) => port_check(0, *id, 32),
-            CascadeSource::PortB(id) => port_check(32, *id, 32),
+            // Port B only has 24 pins.
+            CascadeSource::PortB(id) => port_check(32, *id, 24),
             CascadeSource::Tim(id) => port_check(64, *id, 24),
             CascadeSource::RamSbe => Ok(u7::new(96)),
             CascadeSource::RamMbe => Ok(u7::new(97)),
@@ -191,10 +235,10 @@ impl CascadeSource {
     }
 
     #[cfg(feature = "vor4x")]
-    fn id(&self) -> Result<u7, InvalidCascadeSourceId> {
+    pub fn id(&self) -> Result<u7, InvalidCascadeSourceId> {
         let port_check = |base: u8, id: u8| -> Result<u7, InvalidCascadeSourceId> {
             if id > 15 {
-                return Err(InvalidCascadeSourceId);
+                return Err(InvalidCascadeSourceId::offset_out_of_range(id));
             }
             Ok(u7::new(base + id))
         };
@@ -206,14 +250,14 @@ impl CascadeSource {
             CascadeSource::PortE(id) => port_check(64, *id),
             CascadeSource::Tim(id) => {
                 if *id > 23 {
-                    return Err(InvalidCascadeSourceId);
+                    return Err(InvalidCascadeSourceId::offset_out_of_range(*id));
                 }
                 Ok(u7::new(80 + id))
             }
             CascadeSource::TxEv => Ok(u7::new(104)),
             CascadeSource::AdcIrq => Ok(u7::new(105)),
             CascadeSource::RomSbe => Ok(u7::new(106)),
-            CascadeSource::RomMbe => Ok(u7::new(106)),
+            CascadeSource::RomMbe => Ok(u7::new(107)),
             CascadeSource::Ram0Sbe => Ok(u7::new(108)),
             CascadeSource::Ram0Mbe => Ok(u7::new(109)),
             CascadeSource::Ram1Sbe => Ok(u7::new(110)),
@@ -222,20 +266,17 @@ impl CascadeSource {
         }
     }
 
+    /// Decode a raw cascade source ID. Only the lowest 7 bits are used.
     #[cfg(feature = "vor1x")]
     pub fn from_raw(raw: u32) -> Result<Self, InvalidCascadeSourceId> {
-        let id = u7::new((raw & 0x7F) as u8);
-        if id.value() > 127 {
-            return Err(InvalidCascadeSourceId);
-        }
-        let id = id.as_u8();
+        let id = (raw & 0x7F) as u8;
         if id < 32 {
             return Ok(CascadeSource::PortA(id));
         } else if (32..56).contains(&id) {
             return Ok(CascadeSource::PortB(id - 32));
         } else if (64..88).contains(&id) {
             return Ok(CascadeSource::Tim(id - 64));
-        } else if id > 120 {
+        } else if id >= 120 {
             return Ok(CascadeSource::ClockDivider(id - 120));
         }
         match id {
@@ -244,18 +285,16 @@ impl CascadeSource {
             98 => Ok(CascadeSource::RomSbe),
             99 => Ok(CascadeSource::RomMbe),
             100 => Ok(CascadeSource::Txev),
-            _ => Err(InvalidCascadeSourceId),
+            _ => Err(InvalidCascadeSourceId::unknown_id(id)),
         }
     }
+
+    /// Decode a raw cascade source ID. Only the lowest 7 bits are used.
     #[cfg(feature = "vor4x")]
     pub fn from_raw(raw: u32) -> Result<Self, InvalidCascadeSourceId> {
         use crate::NUM_PORT_DEFAULT;
 
-        let id = u7::new((raw & 0x7F) as u8);
-        if id.value() > 127 {
-            return Err(InvalidCascadeSourceId);
-        }
-        let id = id.as_u8();
+        let id = (raw & 0x7F) as u8;
         if id < 16 {
             return Ok(CascadeSource::PortA(id));
         } else if (16..16 + NUM_PORT_DEFAULT as u8).contains(&id) {
@@ -279,7 +318,7 @@ impl CascadeSource {
             110 => Ok(CascadeSource::Ram1Sbe),
             111 => Ok(CascadeSource::Ram1Mbe),
             112 => Ok(CascadeSource::WdogIrq),
-            _ => Err(InvalidCascadeSourceId),
+            _ => Err(InvalidCascadeSourceId::unknown_id(id)),
         }
     }
 }
@@ -312,6 +351,7 @@ pub struct Timer {
     cascade_control: CascadeControl,
     /// CASCADE0 and CASCADE1 are used to control the counting and activation of the counter.
     /// CASCADE2 is used to request stopping of the timer.
+    #[mmio(PureRead, Write)]
     cascade: [CascadeSourceReg; 3],
     /// PWM A compare value.
     pwma_value: u32,
@@ -358,10 +398,25 @@ impl TimId {
 
     /// Unsafely steal the TIM peripheral block for the TIM ID.
     ///
+    /// In debug builds, this panics if the timer is owned by a driver created with a safe
+    /// constructor, see [crate::ownership].
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL.
+    #[track_caller]
+    pub unsafe fn steal_regs(&self) -> MmioTimer<'static> {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::Tim(*self));
+        Timer::new_mmio(*self)
+    }
+
+    /// Same as [Self::steal_regs], but without the ownership check. [super::TimIrqView] is a
+    /// safe alternative for interrupt handlers.
+    ///
     /// # Safety
     ///
     /// Circumvents ownership and safety guarantees by the HAL.
-    pub const unsafe fn steal_regs(&self) -> MmioTimer<'static> {
+    pub const unsafe fn force_steal_regs(&self) -> MmioTimer<'static> {
         Timer::new_mmio(*self)
     }
 
@@ -369,6 +424,33 @@ impl TimId {
         self.0
     }
 
+    /// Interrupt the TIM peripheral was routed to with the IRQSEL peripheral by the HAL.
+    #[cfg(feature = "vor1x")]
+    #[inline]
+    pub fn routed_interrupt(&self) -> Option<va108xx::Interrupt> {
+        crate::irq_routing::tim_interrupt(*self)
+    }
+
+    /// APB bus which clocks the TIM peripheral. TIM0 to TIM15 are APB1 peripherals and TIM16 to
+    /// TIM23 are APB2 peripherals.
+    #[cfg(feature = "vor4x")]
+    #[inline]
+    pub const fn apb_bus(&self) -> crate::clock::ApbBus {
+        if self.value() <= 15 {
+            crate::clock::ApbBus::Apb1
+        } else {
+            crate::clock::ApbBus::Apb2
+        }
+    }
+
+    /// Reference clock of the TIM peripheral. The TIM peripherals have no other clock source
+    /// than their APB bus, the cascade inputs only gate the counting.
+    #[cfg(feature = "vor4x")]
+    #[inline]
+    pub const fn clock(&self, clocks: &crate::clock::Clocks) -> crate::time::Hertz {
+        clocks.clock_for(self.apb_bus())
+    }
+
     #[cfg(feature = "vor4x")]
     pub const fn interrupt_id(&self) -> va416xx::Interrupt {
         match self.value() {
@@ -402,6 +484,7 @@ impl TimId {
 }
 
 impl Timer {
+    #[cfg(not(feature = "test-harness"))]
     const fn new_mmio_at(base: usize) -> MmioTimer<'static> {
         MmioTimer {
             ptr: base as *mut _,
@@ -410,6 +493,12 @@ impl Timer {
     }
 
     pub const fn new_mmio(id: TimId) -> MmioTimer<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioTimer {
+            ptr: crate::test_harness::timer(id).as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         if cfg!(feature = "vor1x") {
             Timer::new_mmio_at(BASE_ADDR + 0x1000 * id.value() as usize)
         } else {
@@ -422,6 +511,9 @@ impl Timer {
         if timer_index > 23 {
             return Err(InvalidTimerIndex(timer_index));
         }
+        #[cfg(feature = "test-harness")]
+        return Ok(Timer::new_mmio(TimId::new_unchecked(timer_index)));
+        #[cfg(not(feature = "test-harness"))]
         if cfg!(feature = "vor1x") {
             Ok(Timer::new_mmio_at(BASE_ADDR + 0x1000 * timer_index))
         } else {
@@ -429,3 +521,120 @@ impl Timer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calls the closure for every valid cascade source.
+    fn for_each_source(mut f: impl FnMut(CascadeSource)) {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "vor1x")] {
+                for offset in 0..32 {
+                    f(CascadeSource::PortA(offset));
+                }
+                for offset in 0..24 {
+                    f(CascadeSource::PortB(offset));
+                    f(CascadeSource::Tim(offset));
+                }
+                for offset in 0..8 {
+                    f(CascadeSource::ClockDivider(offset));
+                }
+                f(CascadeSource::RamSbe);
+                f(CascadeSource::RamMbe);
+                f(CascadeSource::RomSbe);
+                f(CascadeSource::RomMbe);
+                f(CascadeSource::Txev);
+            } else if #[cfg(feature = "vor4x")] {
+                for offset in 0..16 {
+                    f(CascadeSource::PortA(offset));
+                    f(CascadeSource::PortB(offset));
+                    f(CascadeSource::PortC(offset));
+                    f(CascadeSource::PortD(offset));
+                    f(CascadeSource::PortE(offset));
+                }
+                for offset in 0..24 {
+                    f(CascadeSource::Tim(offset));
+                }
+                f(CascadeSource::TxEv);
+                f(CascadeSource::AdcIrq);
+                f(CascadeSource::RomSbe);
+                f(CascadeSource::RomMbe);
+                f(CascadeSource::Ram0Sbe);
+                f(CascadeSource::Ram0Mbe);
+                f(CascadeSource::Ram1Sbe);
+                f(CascadeSource::Ram1Mbe);
+                f(CascadeSource::WdogIrq);
+            }
+        }
+    }
+
+    #[test]
+    fn cascade_source_round_trip() {
+        let mut used_ids = [false; 128];
+        for_each_source(|src| {
+            let id = src.id().unwrap();
+            assert!(!used_ids[id.as_usize()], "ID {id} used twice");
+            used_ids[id.as_usize()] = true;
+            assert_eq!(CascadeSource::from_raw(id.as_u32()).unwrap(), src);
+            let reg = CascadeSourceReg::new(src).unwrap();
+            assert_eq!(reg.raw(), id);
+        });
+        for (raw, used) in used_ids.iter().enumerate() {
+            let raw = raw as u32;
+            match CascadeSource::from_raw(raw) {
+                Ok(src) => {
+                    assert!(used, "ID {raw} decoded but not produced by any source");
+                    assert_eq!(src.id().unwrap().as_u32(), raw);
+                }
+                Err(e) => {
+                    assert!(!used, "ID {raw} produced by a source but not decoded");
+                    assert_eq!(
+                        e,
+                        InvalidCascadeSourceId {
+                            value: raw,
+                            reason: InvalidCascadeSourceReason::UnknownId
+                        }
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cascade_source_offset_out_of_range() {
+        let check = |src: CascadeSource, offset: u8| {
+            assert_eq!(
+                src.id().unwrap_err(),
+                InvalidCascadeSourceId {
+                    value: offset as u32,
+                    reason: InvalidCascadeSourceReason::OffsetOutOfRange
+                }
+            );
+        };
+        for offset in 24..=u8::MAX {
+            check(CascadeSource::Tim(offset), offset);
+        }
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "vor1x")] {
+                for offset in 32..=u8::MAX {
+                    check(CascadeSource::PortA(offset), offset);
+                }
+                for offset in 24..=u8::MAX {
+                    check(CascadeSource::PortB(offset), offset);
+                }
+                for offset in 8..=u8::MAX {
+                    check(CascadeSource::ClockDivider(offset), offset);
+                }
+            } else if #[cfg(feature = "vor4x")] {
+                for offset in 16..=u8::MAX {
+                    check(CascadeSource::PortA(offset), offset);
+                    check(CascadeSource::PortB(offset), offset);
+                    check(CascadeSource::PortC(offset), offset);
+                    check(CascadeSource::PortD(offset), offset);
+                    check(CascadeSource::PortE(offset), offset);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,556 @@
+//! # Buffered reception
+//!
+//! [BufferedRx] and [BufferedRxAsync] add an internal buffer of `N` bytes on top of a receiver
+//! and implement the [embedded_io::BufRead] and [embedded_io_async::BufRead] traits. Both also
+//! provide a `read_line` method for line based protocols.
+//!
+//! ## Buffer and FIFO sizes
+//!
+//! `fill_buf` only reads from the receiver if the buffer does not contain any unconsumed data.
+//! Each read transfers as much data as is available, limited by the free space of the buffer.
+//! Consumed data is released immediately and the remaining data is moved to the start of the
+//! buffer before new data is read, so the full buffer size is available for each read.
+//!
+//! While the buffered data is processed, the receiver keeps buffering new data on its own: the
+//! blocking [Rx] in its hardware FIFO of [Rx::fifo_capacity] bytes, the [RxAsync] in the queue
+//! filled by the interrupt handler. Data which arrives while those are full is lost, so the
+//! data should be consumed quickly enough, or the queue of the asynchronous receiver should be
+//! sized for the expected bursts. A buffer size `N` larger than the maximum line length avoids
+//! splitting lines over multiple reads, but it is not required for [BufferedRx::read_line].
+//!
+//! ## Line reception
+//!
+//! `read_line` copies the next line into the provided buffer and returns its length:
+//!
+//! - Lines are terminated by LF. A CR directly before the LF is removed as well, so CRLF and LF
+//!   terminated lines are both supported. The terminator is never copied into the buffer.
+//! - If the line does not fit into the buffer, the buffer is filled with the start of the line,
+//!   the rest of the line including the terminator is discarded and
+//!   [LineError::Truncated] is returned. The next call continues with the next line.
+//! - If the receiver reports the end of the data, for example because the blocking receiver was
+//!   disabled and its FIFO is empty, a non-empty unterminated line is returned as it is. If no
+//!   data of the line was received, [LineError::EndOfStream] is returned.
+use embedded_io::BufRead as _;
+use embedded_io_async::BufRead as _;
+
+use super::{Rx, RxAsync};
+
+/// Error of the `read_line` methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LineError<E> {
+    /// The line was longer than the provided buffer. The buffer contains the first `stored`
+    /// bytes of the line, the rest of the line was discarded.
+    #[error("line of {line_len} bytes truncated to {stored} bytes")]
+    Truncated {
+        /// Number of bytes which were stored in the buffer.
+        stored: usize,
+        /// Length of the complete line without the terminator.
+        line_len: usize,
+    },
+    /// The receiver reported the end of the data before any byte of the line was received.
+    #[error("end of stream")]
+    EndOfStream,
+    /// The receiver returned an error.
+    #[error("read error: {0:?}")]
+    Read(E),
+}
+
+//==================================================================================================
+// Buffer
+//==================================================================================================
+
+/// Linear buffer which holds the unconsumed data in `buf[start..end]`.
+struct LineBuffer<const N: usize> {
+    buf: [u8; N],
+    start: usize,
+    end: usize,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    #[inline]
+    fn data(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+
+    /// Move the unconsumed data to the start of the buffer and return the free space behind it.
+    fn free_space(&mut self) -> &mut [u8] {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        &mut self.buf[self.end..]
+    }
+
+    #[inline]
+    fn commit(&mut self, amt: usize) {
+        self.end = (self.end + amt).min(N);
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.start = (self.start + amt).min(self.end);
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        }
+    }
+
+    /// Copy buffered data into `buf` and consume it.
+    fn read_into(&mut self, buf: &mut [u8]) -> usize {
+        let data = self.data();
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.consume(len);
+        len
+    }
+}
+
+/// State of a line which is copied from the buffered data in multiple steps.
+struct LineState {
+    /// Length of the line received so far, including bytes which did not fit into the output.
+    len: usize,
+    /// The last received byte of the line was a CR.
+    last_cr: bool,
+}
+
+impl LineState {
+    const fn new() -> Self {
+        Self {
+            len: 0,
+            last_cr: false,
+        }
+    }
+
+    /// Process the buffered `data`. Returns the number of bytes which were processed, and
+    /// whether the line terminator was found.
+    fn process(&mut self, data: &[u8], out: &mut [u8]) -> (usize, bool) {
+        for (idx, &byte) in data.iter().enumerate() {
+            if byte == b'\n' {
+                return (idx + 1, true);
+            }
+            if let Some(slot) = out.get_mut(self.len) {
+                *slot = byte;
+            }
+            self.len += 1;
+            self.last_cr = byte == b'\r';
+        }
+        (data.len(), false)
+    }
+
+    /// Result for a line which was terminated or ended with the end of the data.
+    fn finish<E>(self, out_len: usize, terminated: bool) -> Result<usize, LineError<E>> {
+        if !terminated && self.len == 0 {
+            return Err(LineError::EndOfStream);
+        }
+        let line_len = if terminated && self.last_cr {
+            self.len - 1
+        } else {
+            self.len
+        };
+        if line_len > out_len {
+            return Err(LineError::Truncated {
+                stored: out_len,
+                line_len,
+            });
+        }
+        Ok(line_len)
+    }
+}
+
+//==================================================================================================
+// Blocking buffered receiver
+//==================================================================================================
+
+/// Blocking receiver with an internal buffer of `N` bytes.
+///
+/// The receiver can be any [embedded_io::Read] implementation and defaults to the UART [Rx].
+/// Please note that the [Rx] only returns 0, which is treated as the end of the data, if it is
+/// disabled and its FIFO is empty.
+pub struct BufferedRx<const N: usize, R = Rx> {
+    rx: R,
+    buf: LineBuffer<N>,
+}
+
+impl<const N: usize, R: embedded_io::Read> BufferedRx<N, R> {
+    /// Create a buffered receiver. The buffer size `N` must not be 0.
+    pub const fn new(rx: R) -> Self {
+        const { assert!(N > 0, "buffer size must not be 0") };
+        Self {
+            rx,
+            buf: LineBuffer::new(),
+        }
+    }
+
+    /// Data which was received, but not consumed yet. Does not read from the receiver.
+    #[inline]
+    pub fn buffered(&self) -> &[u8] {
+        self.buf.data()
+    }
+
+    /// Discard all buffered data. Data in the receiver itself is not affected.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buf.consume(usize::MAX);
+    }
+
+    /// Read the next line into `out` and return its length, not including the line terminator.
+    ///
+    /// Blocks until a line terminator was received. Please see the
+    /// [module documentation](self#line-reception) for the handling of overlong lines and the
+    /// end of the data.
+    pub fn read_line(&mut self, out: &mut [u8]) -> Result<usize, LineError<R::Error>> {
+        let mut line = LineState::new();
+        loop {
+            let data = self.fill_buf().map_err(LineError::Read)?;
+            if data.is_empty() {
+                return line.finish(out.len(), false);
+            }
+            let (processed, terminated) = line.process(data, out);
+            self.buf.consume(processed);
+            if terminated {
+                return line.finish(out.len(), true);
+            }
+        }
+    }
+
+    /// Release the receiver. Buffered data is discarded.
+    #[inline]
+    pub fn release(self) -> R {
+        self.rx
+    }
+}
+
+impl<const N: usize, R: embedded_io::ErrorType> embedded_io::ErrorType for BufferedRx<N, R> {
+    type Error = R::Error;
+}
+
+impl<const N: usize, R: embedded_io::Read> embedded_io::BufRead for BufferedRx<N, R> {
+    /// Return the buffered data. If no data is buffered, this blocks until data was received.
+    fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.buf.is_empty() {
+            let read = self.rx.read(self.buf.free_space())?;
+            self.buf.commit(read);
+        }
+        Ok(self.buf.data())
+    }
+
+    /// Consume `amt` bytes of the buffered data. Values larger than the number of buffered bytes
+    /// consume all buffered data.
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.buf.consume(amt);
+    }
+}
+
+impl<const N: usize, R: embedded_io::Read> embedded_io::Read for BufferedRx<N, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // Large reads bypass the empty buffer.
+        if self.buf.is_empty() && buf.len() >= N {
+            return self.rx.read(buf);
+        }
+        self.fill_buf()?;
+        Ok(self.buf.read_into(buf))
+    }
+}
+
+//==================================================================================================
+// Asynchronous buffered receiver
+//==================================================================================================
+
+/// Asynchronous receiver with an internal buffer of `N` bytes.
+///
+/// The receiver can be any [embedded_io_async::Read] implementation and defaults to the UART
+/// [RxAsync]. Reception errors of the [RxAsync] are returned by `fill_buf` and `read_line`
+/// after the data which was received before the errors was consumed.
+pub struct BufferedRxAsync<const N: usize, R = RxAsync> {
+    rx: R,
+    buf: LineBuffer<N>,
+}
+
+impl<const N: usize, R: embedded_io_async::Read> BufferedRxAsync<N, R> {
+    /// Create a buffered receiver. The buffer size `N` must not be 0.
+    pub const fn new(rx: R) -> Self {
+        const { assert!(N > 0, "buffer size must not be 0") };
+        Self {
+            rx,
+            buf: LineBuffer::new(),
+        }
+    }
+
+    /// Data which was received, but not consumed yet. Does not read from the receiver.
+    #[inline]
+    pub fn buffered(&self) -> &[u8] {
+        self.buf.data()
+    }
+
+    /// Discard all buffered data. Data in the receiver itself is not affected.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buf.consume(usize::MAX);
+    }
+
+    /// Read the next line into `out` and return its length, not including the line terminator.
+    ///
+    /// Please see the [module documentation](self#line-reception) for the handling of overlong
+    /// lines and the end of the data.
+    pub async fn read_line(&mut self, out: &mut [u8]) -> Result<usize, LineError<R::Error>> {
+        let mut line = LineState::new();
+        loop {
+            let data = self.fill_buf().await.map_err(LineError::Read)?;
+            if data.is_empty() {
+                return line.finish(out.len(), false);
+            }
+            let (processed, terminated) = line.process(data, out);
+            self.buf.consume(processed);
+            if terminated {
+                return line.finish(out.len(), true);
+            }
+        }
+    }
+
+    /// Release the receiver. Buffered data is discarded.
+    #[inline]
+    pub fn release(self) -> R {
+        self.rx
+    }
+}
+
+impl<const N: usize, R: embedded_io_async::ErrorType> embedded_io_async::ErrorType
+    for BufferedRxAsync<N, R>
+{
+    type Error = R::Error;
+}
+
+impl<const N: usize, R: embedded_io_async::Read> embedded_io_async::BufRead
+    for BufferedRxAsync<N, R>
+{
+    /// Return the buffered data. If no data is buffered, this waits until data was received.
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        if self.buf.is_empty() {
+            let read = self.rx.read(self.buf.free_space()).await?;
+            self.buf.commit(read);
+        }
+        Ok(self.buf.data())
+    }
+
+    /// Consume `amt` bytes of the buffered data. Values larger than the number of buffered bytes
+    /// consume all buffered data.
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.buf.consume(amt);
+    }
+}
+
+impl<const N: usize, R: embedded_io_async::Read> embedded_io_async::Read for BufferedRxAsync<N, R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // Large reads bypass the empty buffer.
+        if self.buf.is_empty() && buf.len() >= N {
+            return self.rx.read(buf).await;
+        }
+        self.fill_buf().await?;
+        Ok(self.buf.read_into(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use embedded_io::Read as _;
+
+    use super::*;
+
+    /// Receiver which delivers a scripted byte sequence in chunks of at most `burst` bytes and
+    /// then reports the end of the data.
+    struct Script {
+        data: &'static [u8],
+        pos: usize,
+        burst: usize,
+        reads: usize,
+    }
+
+    impl Script {
+        fn new(data: &'static [u8], burst: usize) -> Self {
+            Self {
+                data,
+                pos: 0,
+                burst,
+                reads: 0,
+            }
+        }
+
+        fn next(&mut self, buf: &mut [u8]) -> usize {
+            self.reads += 1;
+            let len = buf.len().min(self.burst).min(self.data.len() - self.pos);
+            buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+            self.pos += len;
+            len
+        }
+    }
+
+    impl embedded_io::ErrorType for Script {
+        type Error = Infallible;
+    }
+
+    impl embedded_io::Read for Script {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            Ok(self.next(buf))
+        }
+    }
+
+    impl embedded_io_async::Read for Script {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            Ok(self.next(buf))
+        }
+    }
+
+    /// The scripted receiver never waits, so a single poll completes the future.
+    fn poll_once<T>(fut: impl Future<Output = T>) -> T {
+        match pin!(fut).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(value) => value,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[test]
+    fn fill_buf_and_consume() {
+        let mut rx = BufferedRx::<4, _>::new(Script::new(b"abcdef", 8));
+        assert_eq!(rx.fill_buf().unwrap(), b"abcd");
+        rx.consume(1);
+        // Buffered data is returned without reading.
+        assert_eq!(rx.fill_buf().unwrap(), b"bcd");
+        assert_eq!(rx.rx.reads, 1);
+        rx.consume(10);
+        assert!(rx.buffered().is_empty());
+        assert_eq!(rx.fill_buf().unwrap(), b"ef");
+        rx.consume(2);
+        assert_eq!(rx.fill_buf().unwrap(), b"");
+    }
+
+    #[test]
+    fn compaction() {
+        let mut buf = LineBuffer::<4>::new();
+        buf.free_space()[..4].copy_from_slice(b"abcd");
+        buf.commit(4);
+        buf.consume(3);
+        assert_eq!(buf.free_space().len(), 3);
+        assert_eq!(buf.data(), b"d");
+        buf.free_space()[..3].copy_from_slice(b"efg");
+        buf.commit(3);
+        assert_eq!(buf.data(), b"defg");
+    }
+
+    #[test]
+    fn read_lines_lf_and_crlf() {
+        let mut rx = BufferedRx::<8, _>::new(Script::new(b"one\r\ntwo\n\r\nthree\nx\r", 3));
+        let mut out = [0; 16];
+        assert_eq!(rx.read_line(&mut out), Ok(3));
+        assert_eq!(&out[..3], b"one");
+        assert_eq!(rx.read_line(&mut out), Ok(3));
+        assert_eq!(&out[..3], b"two");
+        assert_eq!(rx.read_line(&mut out), Ok(0));
+        assert_eq!(rx.read_line(&mut out), Ok(5));
+        assert_eq!(&out[..5], b"three");
+        // The unterminated last line is returned as it is.
+        assert_eq!(rx.read_line(&mut out), Ok(2));
+        assert_eq!(&out[..2], b"x\r");
+        assert_eq!(rx.read_line(&mut out), Err(LineError::EndOfStream));
+    }
+
+    #[test]
+    fn read_line_longer_than_buffer() {
+        let mut rx = BufferedRx::<4, _>::new(Script::new(b"0123456789\r\nok\n", 16));
+        let mut out = [0; 16];
+        assert_eq!(rx.read_line(&mut out), Ok(10));
+        assert_eq!(&out[..10], b"0123456789");
+        assert_eq!(rx.read_line(&mut out), Ok(2));
+    }
+
+    #[test]
+    fn read_line_truncated() {
+        let mut rx = BufferedRx::<8, _>::new(Script::new(b"abcdefgh\r\nabcd\r\nnext\n", 5));
+        let mut out = [0; 4];
+        assert_eq!(
+            rx.read_line(&mut out),
+            Err(LineError::Truncated {
+                stored: 4,
+                line_len: 8
+            })
+        );
+        assert_eq!(&out, b"abcd");
+        // A CR which does not fit into the output does not truncate the line.
+        assert_eq!(rx.read_line(&mut out), Ok(4));
+        assert_eq!(rx.read_line(&mut out), Ok(4));
+        assert_eq!(&out, b"next");
+    }
+
+    #[test]
+    fn read_through_buffer() {
+        let mut rx = BufferedRx::<4, _>::new(Script::new(b"abcdefghij", 16));
+        let mut out = [0; 2];
+        assert_eq!(rx.read(&mut out), Ok(2));
+        assert_eq!(&out, b"ab");
+        assert_eq!(rx.buffered(), b"cd");
+        let mut large = [0; 8];
+        // Buffered data is returned first.
+        assert_eq!(rx.read(&mut large), Ok(2));
+        assert_eq!(&large[..2], b"cd");
+        assert_eq!(rx.read(&mut large), Ok(6));
+        assert_eq!(&large[..6], b"efghij");
+        assert!(rx.buffered().is_empty());
+    }
+
+    #[test]
+    fn async_read_lines() {
+        let mut rx = BufferedRxAsync::<4, _>::new(Script::new(b"ab\r\n0123456\nrest", 3));
+        let mut out = [0; 4];
+        assert_eq!(poll_once(rx.read_line(&mut out)), Ok(2));
+        assert_eq!(&out[..2], b"ab");
+        assert_eq!(
+            poll_once(rx.read_line(&mut out)),
+            Err(LineError::Truncated {
+                stored: 4,
+                line_len: 7
+            })
+        );
+        assert_eq!(poll_once(rx.read_line(&mut out)), Ok(4));
+        assert_eq!(&out, b"rest");
+        assert_eq!(
+            poll_once(rx.read_line(&mut out)),
+            Err(LineError::EndOfStream)
+        );
+    }
+
+    #[test]
+    fn async_fill_buf_and_consume() {
+        let mut rx = BufferedRxAsync::<4, _>::new(Script::new(b"abcdef", 8));
+        assert_eq!(poll_once(rx.fill_buf()).unwrap(), b"abcd");
+        rx.consume(3);
+        assert_eq!(poll_once(rx.fill_buf()).unwrap(), b"d");
+        rx.consume(1);
+        assert_eq!(poll_once(rx.fill_buf()).unwrap(), b"ef");
+        assert_eq!(rx.rx.reads, 2);
+    }
+}
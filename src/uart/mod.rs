@@ -2,7 +2,8 @@
 //!
 //! The core of this API are the [Uart], [Rx] and [Tx] structures.
 //! The RX structure also has a dedicated [RxWithInterrupt] variant which allows reading the receiver
-//! using interrupts.
+//! using interrupts. [Uart::new_tx_only] and [Uart::new_rx_only] can be used to create only
+//! one half of the driver if only one direction is required.
 //!
 //! The [rx_asynch] and [tx_asynch] modules provide an asynchronous non-blocking API for the UART
 //! peripheral.
@@ -12,23 +13,94 @@
 //! - [UART simple example](https://egit.irs.uni-stuttgart.de/rust/va108xx-rs/src/branch/main/examples/simple/examples/uart.rs)
 //! - [UART with IRQ and RTIC](https://egit.irs.uni-stuttgart.de/rust/va108xx-rs/src/branch/main/examples/rtic/src/bin/uart-echo-rtic.rs)
 //! - [Flashloader exposing a CCSDS interface via UART](https://egit.irs.uni-stuttgart.de/rust/va108xx-rs/src/branch/main/flashloader)
-use core::convert::Infallible;
+use core::{convert::Infallible, num::NonZeroU8};
 pub mod regs;
 #[cfg(feature = "vor1x")]
 use crate::InterruptConfig;
-use crate::{FunctionSelect, gpio::IoPeriphPin, pins::AnyPin, sealed::Sealed};
-use arbitrary_int::{prelude::*, u6, u18};
+use crate::{
+    FunctionSelect,
+    gpio::{DynPinId, IoPeriphPin},
+    pins::AnyPin,
+    sealed::Sealed,
+};
+use arbitrary_int::prelude::*;
 use fugit::RateExtU32;
-use regs::{ClockScale, Control, Data, Enable, FifoClear, InterruptClear, MmioUart};
-
-use crate::{PeripheralSelect, enable_nvic_interrupt, enable_peripheral_clock, time::Hertz};
+use regs::{ClockScale, Control, Data, Enable, FifoClear, FifoTrigger, InterruptClear, MmioUart};
+
+use crate::{
+    PeripheralSelect,
+    busy_wait::BusyWait,
+    enable_nvic_interrupt, enable_peripheral_clock,
+    ownership::RegisterBlock,
+    power::SleepParticipant,
+    reset_peripheral_for_cycles,
+    time::{
+        Deadline, DelayDeadline, Duration, Hertz, Nanoseconds, NoDeadline, TimeoutError,
+        poll_with_deadline,
+    },
+};
 use embedded_hal_nb::serial::Read;
 pub use regs::{Bank, Stopbits, WordSize};
 
+#[macro_use]
+mod macros {
+    /// Implements [TxPin] and [RxPin] for the listed pins and creates the [TX_PIN_MAPPINGS] and
+    /// [RX_PIN_MAPPINGS] tables from the same lists.
+    macro_rules! uart_pins {
+        (
+            tx: [$($(#[$tx_meta:meta])* ($TxPx:ident, $TxBank:path, $TxFunSel:path)),* $(,)?],
+            rx: [$($(#[$rx_meta:meta])* ($RxPx:ident, $RxBank:path, $RxFunSel:path)),* $(,)?]
+        ) => {
+            $(
+                $(#[$tx_meta])*
+                impl TxPin for Pin<$TxPx> {
+                    const BANK: Bank = $TxBank;
+                    const FUN_SEL: FunctionSelect = $TxFunSel;
+                }
+            )*
+            $(
+                $(#[$rx_meta])*
+                impl RxPin for Pin<$RxPx> {
+                    const BANK: Bank = $RxBank;
+                    const FUN_SEL: FunctionSelect = $RxFunSel;
+                }
+            )*
+
+            /// All pins which implement [TxPin].
+            pub const TX_PIN_MAPPINGS: &[UartPinMapping] = &[
+                $(
+                    $(#[$tx_meta])*
+                    UartPinMapping {
+                        bank: $TxBank,
+                        pin: <$TxPx as $crate::pins::PinId>::ID,
+                        fun_sel: $TxFunSel,
+                    },
+                )*
+            ];
+
+            /// All pins which implement [RxPin].
+            pub const RX_PIN_MAPPINGS: &[UartPinMapping] = &[
+                $(
+                    $(#[$rx_meta])*
+                    UartPinMapping {
+                        bank: $RxBank,
+                        pin: <$RxPx as $crate::pins::PinId>::ID,
+                        fun_sel: $RxFunSel,
+                    },
+                )*
+            ];
+        };
+    }
+}
+
 #[cfg(feature = "vor1x")]
 mod pins_vor1x;
+#[cfg(feature = "vor1x")]
+pub use pins_vor1x::{RX_PIN_MAPPINGS, TX_PIN_MAPPINGS};
 #[cfg(feature = "vor4x")]
 mod pins_vor4x;
+#[cfg(feature = "vor4x")]
+pub use pins_vor4x::{RX_PIN_MAPPINGS, TX_PIN_MAPPINGS};
 
 #[cfg(feature = "vor4x")]
 use crate::clock::Clocks;
@@ -43,6 +115,12 @@ pub use tx_asynch::*;
 pub mod rx_asynch;
 pub use rx_asynch::*;
 
+pub mod transact;
+pub use transact::*;
+
+pub mod buffered;
+pub use buffered::*;
+
 //==================================================================================================
 // Type-Level support
 //==================================================================================================
@@ -56,6 +134,26 @@ pub trait RxPin: AnyPin {
     const FUN_SEL: FunctionSelect;
 }
 
+/// Run-time description of a UART pin, see [TX_PIN_MAPPINGS] and [RX_PIN_MAPPINGS].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UartPinMapping {
+    pub bank: Bank,
+    pub pin: DynPinId,
+    pub fun_sel: FunctionSelect,
+}
+
+/// Checks whether the pin can be used with the given bank and function select according to
+/// the pin table.
+pub fn is_valid_pin_mapping(
+    table: &[UartPinMapping],
+    bank: Bank,
+    pin: DynPinId,
+    fun_sel: FunctionSelect,
+) -> bool {
+    table.contains(&UartPinMapping { bank, pin, fun_sel })
+}
+
 //==================================================================================================
 // Regular Definitions
 //==================================================================================================
@@ -70,6 +168,20 @@ pub struct NoInterruptIdWasSet;
 #[error("transer is pending")]
 pub struct TransferPendingError;
 
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("manual parity mode is not enabled")]
+pub struct ParityManualDisabledError;
+
+/// A byte has bits set above the configured [WordSize].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("data {data:#04x} exceeds the word size {wordsize:?}")]
+pub struct WordSizeExceededError {
+    pub data: u8,
+    pub wordsize: WordSize,
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Event {
@@ -95,6 +207,22 @@ pub enum Event {
     TxCts,
 }
 
+impl Event {
+    /// Interrupt enables with the interrupt of this event set or cleared.
+    fn apply(self, mut value: regs::InterruptControl, enable: bool) -> regs::InterruptControl {
+        match self {
+            Event::RxError => value.set_rx_status(enable),
+            Event::RxFifoHalfFull => value.set_rx(enable),
+            Event::RxTimeout => value.set_rx_timeout(enable),
+            Event::TxEmpty => value.set_tx_empty(enable),
+            Event::TxError => value.set_tx_status(enable),
+            Event::TxFifoHalfFull => value.set_tx(enable),
+            Event::TxCts => value.set_tx_cts(enable),
+        }
+        value
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Parity {
@@ -114,6 +242,15 @@ pub struct Config {
     pub wordsize: WordSize,
     pub enable_tx: bool,
     pub enable_rx: bool,
+    /// Manual parity mode, see [Self::parity_manual].
+    pub parity_manual: bool,
+    /// RX FIFO level for the automatic RTS flow control, see [Rx::set_rts_threshold]. The reset
+    /// value is kept if this is [None].
+    pub rts_threshold: Option<u5>,
+    /// Invert the TX line, see [Self::invert_tx].
+    pub invert_tx: bool,
+    /// Invert the RX line, see [Self::invert_rx].
+    pub invert_rx: bool,
 }
 
 impl Config {
@@ -137,11 +274,27 @@ impl Config {
         self
     }
 
+    /// Enable the manual parity mode, where the parity bit of each word is supplied by the
+    /// software instead of being calculated by the hardware.
+    ///
+    /// This can be used for 9-bit protocols which use the parity bit as an address or data
+    /// marker. Words are written with [Tx::write_word_with_parity] and read with
+    /// [Rx::read_word_with_parity]. The parity bit of all other write functions is 0, and parity
+    /// errors are not reported in this mode. The configured [Parity] is ignored.
+    pub fn parity_manual(mut self) -> Self {
+        self.parity_manual = true;
+        self
+    }
+
     pub fn stopbits(mut self, stopbits: Stopbits) -> Self {
         self.stopbits = stopbits;
         self
     }
 
+    /// Set the number of data bits of one character.
+    ///
+    /// With less than 8 data bits, the byte based APIs of [Rx] and [Tx] only use the lower bits
+    /// of each byte, see the [Tx] and [Rx] documentation.
     pub fn wordsize(mut self, wordsize: WordSize) -> Self {
         self.wordsize = wordsize;
         self
@@ -151,6 +304,156 @@ impl Config {
         self.baud8 = baud;
         self
     }
+
+    /// See [Rx::set_rts_threshold] documentation.
+    pub fn rts_threshold(mut self, level: u5) -> Self {
+        self.rts_threshold = Some(level);
+        self
+    }
+
+    /// Invert the TX line, so the idle level is low. This can be used for transceivers which
+    /// invert the line.
+    ///
+    /// The UART has no polarity setting, so the inversion is done with the output inversion of
+    /// the IOCONFIG peripheral when the TX pin is configured by the constructor. It is therefore
+    /// only applied by constructors which configure the pins, and it is not affected by
+    /// [Uart::reinitialize], which does not touch the pin configuration.
+    pub fn invert_tx(mut self, invert: bool) -> Self {
+        self.invert_tx = invert;
+        self
+    }
+
+    /// Invert the RX line, so the idle level is low. See [Self::invert_tx] for details.
+    ///
+    /// The receiver detects a break condition if the line is at the inverted idle level for
+    /// longer than one character. With an inverted RX line, a disconnected line which is pulled
+    /// low is therefore not reported as a break, but a line stuck high is. Break and framing error
+    /// detection otherwise only work if the inversion matches the transmitter.
+    pub fn invert_rx(mut self, invert: bool) -> Self {
+        self.invert_rx = invert;
+        self
+    }
+
+    /// Highest baudrate which can be generated from the given reference clock. The baud clock
+    /// is 16 times the baudrate, or 8 times the baudrate if [Self::baud8] is enabled.
+    #[inline]
+    pub const fn max_baudrate(&self, ref_clk: Hertz) -> Hertz {
+        Hertz::from_raw(ref_clk.raw() / self.baud_multiplier())
+    }
+
+    /// Lowest baudrate for which the clock divisor fits into the clock scale register.
+    pub const fn min_baudrate(&self, ref_clk: Hertz) -> Hertz {
+        // The rounded divisor in 1/64 units must not exceed the register value:
+        // 64 * ref_clk / (multiplier * baudrate) < MAX_CLKSCALE + 1/2
+        let min = (128 * ref_clk.raw() as u64)
+            / (self.baud_multiplier() as u64 * (2 * MAX_CLKSCALE + 1))
+            + 1;
+        Hertz::from_raw(min as u32)
+    }
+
+    /// Checks whether the configured baudrate can be generated from the given reference clock.
+    ///
+    /// All combinations of [WordSize], [Stopbits] and [Parity] are supported by the hardware.
+    pub const fn validate(&self, ref_clk: Hertz) -> Result<(), UartConfigError> {
+        match self.clkscale(ref_clk) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Value of the clock scale register for the given reference clock.
+    ///
+    /// The clock divisor is `ref_clk / (multiplier * baudrate)`, where the multiplier is 16, or
+    /// 8 if [Self::baud8] is enabled. The integer part of the divisor is written to the INT
+    /// field and the fractional part, rounded to 1/64, to the FRAC field. A fractional part which
+    /// rounds up to 64/64 is carried into the integer part.
+    pub const fn clkscale(&self, ref_clk: Hertz) -> Result<ClockScale, UartConfigError> {
+        if self.baudrate.raw() == 0 {
+            return Err(UartConfigError::BaudrateZero);
+        }
+        let max = self.max_baudrate(ref_clk);
+        if self.baudrate.raw() > max.raw() {
+            return Err(UartConfigError::BaudrateTooHigh { ref_clk, max });
+        }
+        let divisor = self.baud_multiplier() as u64 * self.baudrate.raw() as u64;
+        let scaled = (64 * ref_clk.raw() as u64 + divisor / 2) / divisor;
+        if scaled > MAX_CLKSCALE {
+            return Err(UartConfigError::BaudrateTooLow {
+                ref_clk,
+                min: self.min_baudrate(ref_clk),
+            });
+        }
+        Ok(ClockScale::new_with_raw_value(scaled as u32))
+    }
+
+    /// Number of bits of one character on the line, including the start bit, the parity bit and
+    /// the stop bits.
+    pub const fn bits_per_character(&self) -> u32 {
+        let data_bits = self.wordsize.data_bits();
+        let parity_bits = match (self.parity_manual, self.parity) {
+            (true, _) => 1,
+            (false, Parity::None) => 0,
+            (false, Parity::Odd | Parity::Even) => 1,
+        };
+        let stop_bits = match self.stopbits {
+            Stopbits::One => 1,
+            Stopbits::Two => 2,
+        };
+        1 + data_bits + parity_bits + stop_bits
+    }
+
+    /// Transmission time of one character at the configured baudrate, rounded up to full
+    /// nanoseconds. Saturates at [u32::MAX] nanoseconds for baudrates below 3 Hz.
+    ///
+    /// Returns 0 if the baudrate is zero.
+    pub const fn character_time(&self) -> Nanoseconds {
+        if self.baudrate.raw() == 0 {
+            return Nanoseconds::from_ticks(0);
+        }
+        let bits = self.bits_per_character() as u64;
+        let ns = (bits * 1_000_000_000).div_ceil(self.baudrate.raw() as u64);
+        if ns > u32::MAX as u64 {
+            return Nanoseconds::from_ticks(u32::MAX);
+        }
+        Nanoseconds::from_ticks(ns as u32)
+    }
+
+    /// Number of characters which can be transmitted in the given duration at the configured
+    /// baudrate, rounded up. This can be used to convert a timeout to a character count.
+    pub const fn characters_in(&self, duration: Nanoseconds) -> u32 {
+        let bits = self.bits_per_character() as u64;
+        let line_bits = duration.ticks() as u64 * self.baudrate.raw() as u64;
+        let chars = line_bits.div_ceil(bits * 1_000_000_000);
+        if chars > u32::MAX as u64 {
+            return u32::MAX;
+        }
+        chars as u32
+    }
+
+    /// Duration of the given number of character times at the configured baudrate, rounded up
+    /// to full microseconds. This is the silence timeout of [Rx::read_with_character_timeout].
+    pub const fn idle_timeout(&self, idle_chars: u16) -> Duration {
+        let ns = self.character_time().ticks() as u64 * idle_chars as u64;
+        Duration::from_ticks(ns.div_ceil(1000))
+    }
+
+    /// Configure the TX pin with the configured inversion.
+    pub(crate) fn configure_tx_pin(&self, pin: DynPinId, fun_sel: FunctionSelect) {
+        IoPeriphPin::new_with_inversion(pin, fun_sel, None, false, self.invert_tx);
+    }
+
+    /// Configure the RX pin with the configured inversion.
+    pub(crate) fn configure_rx_pin(&self, pin: DynPinId, fun_sel: FunctionSelect) {
+        IoPeriphPin::new_with_inversion(pin, fun_sel, None, self.invert_rx, false);
+    }
+
+    #[inline]
+    const fn baud_multiplier(&self) -> u32 {
+        match self.baud8 {
+            false => 16,
+            true => 8,
+        }
+    }
 }
 
 impl Default for Config {
@@ -164,6 +467,10 @@ impl Default for Config {
             wordsize: WordSize::Eight,
             enable_tx: true,
             enable_rx: true,
+            parity_manual: false,
+            rts_threshold: None,
+            invert_tx: false,
+            invert_rx: false,
         }
     }
 }
@@ -178,28 +485,83 @@ impl From<Hertz> for Config {
 // IRQ Definitions
 //==================================================================================================
 
+/// Reception state of [RxWithInterrupt::on_interrupt_max_size_or_timeout_based].
+///
+/// The hardware RX timeout fires after 4 character times without receiver FIFO activity, which
+/// is not configurable. Longer idle times are detected in software: A frame is only complete
+/// after the configured number of consecutive timeout interrupts without new data in between,
+/// see [Self::new_with_idle_timeouts].
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InterruptContextTimeoutOrMaxSize {
     rx_idx: usize,
     mode: InterruptReceptionMode,
     pub max_len: usize,
+    idle_timeouts: NonZeroU8,
+    /// Consecutive timeout interrupts without new data.
+    timeouts: u8,
 }
 
 impl InterruptContextTimeoutOrMaxSize {
+    /// A single timeout interrupt completes a frame, so the idle threshold is 4 character
+    /// times.
     pub fn new(max_len: usize) -> Self {
+        Self::new_with_idle_timeouts(max_len, NonZeroU8::MIN)
+    }
+
+    /// A frame is only complete after `idle_timeouts` consecutive timeout interrupts without new
+    /// data in between, so the idle threshold is approximately `idle_timeouts` times 4
+    /// character times.
+    ///
+    /// To re-arm the hardware timeout, the last received byte is kept in the FIFO after a
+    /// timeout interrupt which does not complete the frame. Reading the other bytes restarts
+    /// the timeout.
+    pub fn new_with_idle_timeouts(max_len: usize, idle_timeouts: NonZeroU8) -> Self {
         InterruptContextTimeoutOrMaxSize {
             rx_idx: 0,
             max_len,
             mode: InterruptReceptionMode::Idle,
+            idle_timeouts,
+            timeouts: 0,
         }
     }
+
+    #[inline]
+    pub fn idle_timeouts(&self) -> NonZeroU8 {
+        self.idle_timeouts
+    }
+
+    #[inline]
+    pub fn set_idle_timeouts(&mut self, idle_timeouts: NonZeroU8) {
+        self.idle_timeouts = idle_timeouts;
+    }
 }
 
 impl InterruptContextTimeoutOrMaxSize {
     pub fn reset(&mut self) {
         self.rx_idx = 0;
         self.mode = InterruptReceptionMode::Idle;
+        self.timeouts = 0;
+    }
+
+    /// Data was received without a timeout, for example with the FIFO trigger interrupt.
+    #[inline]
+    fn on_data(&mut self) {
+        self.timeouts = 0;
+    }
+
+    /// Count a timeout interrupt with the given number of bytes in the RX FIFO. Bytes in
+    /// addition to the byte which was kept in the FIFO at the previous timeout are new data.
+    ///
+    /// Returns whether the frame is complete.
+    fn on_timeout(&mut self, fifo_level: usize) -> bool {
+        let kept = usize::from(self.timeouts > 0);
+        if fifo_level > kept {
+            self.timeouts = 1;
+        } else {
+            self.timeouts = self.timeouts.saturating_add(1);
+        }
+        self.timeouts >= self.idle_timeouts.get()
     }
 }
 
@@ -270,7 +632,7 @@ enum InterruptReceptionMode {
     Pending,
 }
 
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct UartErrors {
     overflow: bool,
@@ -308,6 +670,89 @@ impl UartErrors {
     }
 }
 
+/// Minimal view of the UART interrupt registers for interrupt handlers.
+///
+/// The view only reads the interrupt status and writes the interrupt clear register, so it can
+/// be created safely while a driver owns the UART, see [crate::ownership]. Data and FIFO access
+/// still requires the driver or [Bank::force_steal_regs].
+pub struct UartIrqView(MmioUart<'static>);
+
+impl UartIrqView {
+    #[inline]
+    pub fn new(bank: Bank) -> Self {
+        Self(regs::Uart::new_mmio(bank))
+    }
+
+    /// Pending interrupts which are enabled.
+    #[inline]
+    pub fn irq_status(&self) -> regs::InterruptStatus {
+        self.0.read_irq_status()
+    }
+
+    /// Enabled interrupts.
+    #[inline]
+    pub fn irq_enabled(&self) -> regs::InterruptControl {
+        self.0.read_irq_enabled()
+    }
+
+    /// Acknowledge the overrun interrupts selected in the clear mask.
+    #[inline]
+    pub fn clear(&mut self, clear: InterruptClear) {
+        self.0.write_irq_clr(clear);
+    }
+}
+
+/// Acknowledge a RX overrun interrupt if it is signalled in the given interrupt status.
+///
+/// The RX status interrupt is also raised for framing, parity and break errors, so the overrun
+/// flag of the RX status register is read before the interrupt is acknowledged, and an overrun is
+/// only recorded in the passed errors if that flag was set. A TX overrun is left pending for the
+/// TX interrupt handler.
+pub(crate) fn clear_rx_overrun_interrupt(
+    regs: &mut MmioUart<'static>,
+    irq_status: regs::InterruptStatus,
+    errors: &mut Option<UartErrors>,
+) {
+    let clear = InterruptClear::rx_for_status(irq_status);
+    if clear.rx_overrun() {
+        let rx_status = regs.read_rx_status();
+        regs.write_irq_clr(clear);
+        record_cleared_rx_overrun(clear, rx_status, errors);
+    }
+}
+
+/// Acknowledge a TX overrun interrupt if it is signalled in the given interrupt status and
+/// return whether it was cleared. A RX overrun is left pending for the RX interrupt handler.
+pub(crate) fn clear_tx_overrun_interrupt(
+    regs: &mut MmioUart<'static>,
+    irq_status: regs::InterruptStatus,
+) -> bool {
+    let clear = InterruptClear::tx_for_status(irq_status);
+    if clear.tx_overrun() {
+        regs.write_irq_clr(clear);
+    }
+    clear.tx_overrun()
+}
+
+/// Parity errors are not reported in manual parity mode, where the parity bit is part of the
+/// data.
+#[inline]
+pub(crate) fn parity_error_reported(regs: &MmioUart<'static>, rx_status: regs::RxStatus) -> bool {
+    rx_status.parity_error() && !regs.read_ctrl().parity_manual()
+}
+
+fn record_cleared_rx_overrun(
+    clear: InterruptClear,
+    rx_status: regs::RxStatus,
+    errors: &mut Option<UartErrors>,
+) {
+    if !clear.rx_overrun() || !rx_status.overrun_error() {
+        return;
+    }
+    // Data was lost, which is reported in addition to any framing or parity error.
+    errors.get_or_insert(UartErrors::default()).overflow = true;
+}
+
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct BufferTooShortError {
@@ -319,9 +764,24 @@ pub struct BufferTooShortError {
 // UART peripheral wrapper
 //==================================================================================================
 
+/// Largest FIFO depth of all UART peripherals, see [Bank::fifo_depth]. Buffers which receive the
+/// content of a full RX FIFO must be at least this large.
+pub const MAX_FIFO_DEPTH: usize = 16;
+
+/// Whether the interrupt driven drivers of this module unmask the UART interrupt in the NVIC.
+///
+/// On Vorago 1x devices, the user decides whether the interrupt is unmasked with the
+/// `enable_in_nvic` field of the `InterruptConfig` used for routing, so the drivers leave the
+/// NVIC untouched.
+pub(crate) const DRIVER_UNMASKS_NVIC: bool = cfg!(feature = "vor4x");
+
 pub trait UartInstance: Sealed {
     const ID: Bank;
     const PERIPH_SEL: PeripheralSelect;
+    const FIFO_DEPTH: usize = Self::ID.fifo_depth();
+    /// APB bus which clocks the peripheral.
+    #[cfg(feature = "vor4x")]
+    const APB: crate::clock::ApbBus = Self::ID.apb_bus();
 }
 
 #[cfg(feature = "vor1x")]
@@ -354,11 +814,50 @@ impl UartInstance for pac::Uart2 {
 #[cfg(feature = "vor4x")]
 impl Sealed for pac::Uart2 {}
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[error("UART ID missmatch between peripheral and pins.")]
 pub struct UartIdMissmatchError;
 
+/// Largest value of the clock scale register, which contains the clock divisor in 1/64 units.
+const MAX_CLKSCALE: u64 = (1 << 24) - 1;
+
+/// Invalid [Config] for the reference clock of the UART, see [Config::validate].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UartConfigError {
+    #[error("baudrate is zero")]
+    BaudrateZero,
+    /// The baudrate can not be generated from the reference clock.
+    #[error("baudrate too high for reference clock {ref_clk}, maximum is {max}")]
+    BaudrateTooHigh { ref_clk: Hertz, max: Hertz },
+    /// The clock divisor for the baudrate does not fit into the clock scale register.
+    #[error("baudrate too low for reference clock {ref_clk}, minimum is {min}")]
+    BaudrateTooLow { ref_clk: Hertz, min: Hertz },
+}
+
+/// The pin can not be used with the UART bank and function select, see [is_valid_pin_mapping].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("pin {pin:?} with {fun_sel:?} is not a valid UART pin for {bank:?}")]
+pub struct InvalidUartPinError {
+    pub bank: Bank,
+    pub pin: DynPinId,
+    pub fun_sel: FunctionSelect,
+}
+
+/// Error returned by the UART constructors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UartInitError {
+    #[error("{0}")]
+    IdMissmatch(#[from] UartIdMissmatchError),
+    #[error("invalid configuration: {0}")]
+    Config(#[from] UartConfigError),
+    #[error("{0}")]
+    InvalidPin(#[from] InvalidUartPinError),
+}
+
 //==================================================================================================
 // UART implementation
 //==================================================================================================
@@ -367,6 +866,12 @@ pub struct UartIdMissmatchError;
 pub struct Uart {
     tx: Tx,
     rx: Rx,
+    #[cfg(feature = "vor1x")]
+    irq_cfg: Option<InterruptConfig>,
+    /// TX and RX enable state replayed by [Self::reinitialize].
+    enabled: Enable,
+    /// Interrupt enables replayed by [Self::reinitialize].
+    irq_enabled: regs::InterruptControl,
 }
 
 impl Uart {
@@ -380,7 +885,7 @@ impl Uart {
                 sys_clk: Hertz,
                 config: Config,
                 irq_cfg: InterruptConfig,
-            ) -> Result<Self, UartIdMissmatchError> {
+            ) -> Result<Self, UartInitError> {
                 Self::new(uart, tx_pin, rx_pin, sys_clk, config, Some(irq_cfg))
             }
 
@@ -391,7 +896,7 @@ impl Uart {
                 rx_pin: Rx,
                 sys_clk: Hertz,
                 config: Config,
-            ) -> Result<Self, UartIdMissmatchError> {
+            ) -> Result<Self, UartInitError> {
                 Self::new(uart, tx_pin, rx_pin, sys_clk, config, None)
             }
 
@@ -414,9 +919,39 @@ impl Uart {
                 sys_clk: Hertz,
                 config: Config,
                 opt_irq_cfg: Option<InterruptConfig>,
-            ) -> Result<Self, UartIdMissmatchError> {
+            ) -> Result<Self, UartInitError> {
                 Self::new_internal(uart, (tx_pin, rx_pin), sys_clk, config, opt_irq_cfg)
             }
+
+            /// Create a TX-only UART driver which only requires a TX pin.
+            ///
+            /// Only the TX pin is configured and the receiver stays disabled. The transmitter is
+            /// enabled if [Config::enable_tx] is set. The arguments are the same as for
+            /// [Self::new]. The returned [Tx] can not be combined with a [Rx] to a full [Uart].
+            pub fn new_tx_only<UartI: UartInstance, TxPinI: TxPin>(
+                uart: UartI,
+                tx_pin: TxPinI,
+                sys_clk: Hertz,
+                config: Config,
+                opt_irq_cfg: Option<InterruptConfig>,
+            ) -> Result<Tx, UartInitError> {
+                Self::new_tx_only_internal(uart, tx_pin, sys_clk, config, opt_irq_cfg)
+            }
+
+            /// Create a RX-only UART driver which only requires a RX pin.
+            ///
+            /// Only the RX pin is configured and the transmitter stays disabled. The receiver is
+            /// enabled if [Config::enable_rx] is set. The arguments are the same as for
+            /// [Self::new]. The returned [Rx] can not be combined with a [Tx] to a full [Uart].
+            pub fn new_rx_only<UartI: UartInstance, RxPinI: RxPin>(
+                uart: UartI,
+                rx_pin: RxPinI,
+                sys_clk: Hertz,
+                config: Config,
+                opt_irq_cfg: Option<InterruptConfig>,
+            ) -> Result<Rx, UartInitError> {
+                Self::new_rx_only_internal(uart, rx_pin, sys_clk, config, opt_irq_cfg)
+            }
         } else if #[cfg(feature = "vor4x")] {
             /// Create a new UART peripheral driver.
             ///
@@ -432,19 +967,15 @@ impl Uart {
                 rx_pin: Rx,
                 clks: &Clocks,
                 config: Config,
-            ) -> Result<Self, UartIdMissmatchError> {
-                if UartI::ID == Bank::Uart2 {
-                    Self::new_internal(uart, (tx_pin, rx_pin), clks.apb1(), config)
-                } else {
-                    Self::new_internal(uart, (tx_pin, rx_pin), clks.apb2(), config)
-                }
+            ) -> Result<Self, UartInitError> {
+                Self::new_internal(uart, (tx_pin, rx_pin), clks.clock_for(UartI::APB), config)
             }
 
             /// Create a new UART peripheral driver given a reference clock.
             ///
             /// # Arguments
             ///
-            /// - `ref_clk`: APB1 clock for UART2, APB2 clock otherwise.
+            /// - `ref_clk`: Clock of the APB bus of the UART, see [UartInstance::APB].
             /// - `uart`: The concrete UART peripheral instance.
             /// - `pins`: UART TX and RX pin tuple.
             /// - `config`: UART specific configuration parameters like baudrate.
@@ -454,9 +985,37 @@ impl Uart {
                 rx_pin: Rx,
                 ref_clk: Hertz,
                 config: Config,
-            ) -> Result<Self, UartIdMissmatchError> {
+            ) -> Result<Self, UartInitError> {
                 Self::new_internal(uart,(tx_pin, rx_pin),ref_clk, config)
             }
+
+            /// Create a TX-only UART driver which only requires a TX pin.
+            ///
+            /// Only the TX pin is configured and the receiver stays disabled. The transmitter is
+            /// enabled if [Config::enable_tx] is set. The arguments are the same as for
+            /// [Self::new]. The returned [Tx] can not be combined with a [Rx] to a full [Uart].
+            pub fn new_tx_only<UartI: UartInstance, TxPinI: TxPin>(
+                uart: UartI,
+                tx_pin: TxPinI,
+                clks: &Clocks,
+                config: Config,
+            ) -> Result<Tx, UartInitError> {
+                Self::new_tx_only_internal(uart, tx_pin, clks.clock_for(UartI::APB), config)
+            }
+
+            /// Create a RX-only UART driver which only requires a RX pin.
+            ///
+            /// Only the RX pin is configured and the transmitter stays disabled. The receiver is
+            /// enabled if [Config::enable_rx] is set. The arguments are the same as for
+            /// [Self::new]. The returned [Rx] can not be combined with a [Tx] to a full [Uart].
+            pub fn new_rx_only<UartI: UartInstance, RxPinI: RxPin>(
+                uart: UartI,
+                rx_pin: RxPinI,
+                clks: &Clocks,
+                config: Config,
+            ) -> Result<Rx, UartInitError> {
+                Self::new_rx_only_internal(uart, rx_pin, clks.clock_for(UartI::APB), config)
+            }
         }
     }
 
@@ -466,39 +1025,193 @@ impl Uart {
         ref_clk: Hertz,
         config: Config,
         #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
-    ) -> Result<Self, UartIdMissmatchError> {
+    ) -> Result<Self, UartInitError> {
         if UartI::ID != TxPinI::BANK || UartI::ID != RxPinI::BANK {
-            return Err(UartIdMissmatchError);
+            return Err(UartIdMissmatchError.into());
         }
-        IoPeriphPin::new(TxPinI::ID, TxPinI::FUN_SEL, None);
-        IoPeriphPin::new(RxPinI::ID, TxPinI::FUN_SEL, None);
-        enable_peripheral_clock(UartI::PERIPH_SEL);
-
-        let mut reg_block = regs::Uart::new_mmio(UartI::ID);
-        let baud_multiplier = match config.baud8 {
-            false => 16,
-            true => 8,
+        config.validate(ref_clk)?;
+        config.configure_tx_pin(TxPinI::ID, TxPinI::FUN_SEL);
+        config.configure_rx_pin(RxPinI::ID, RxPinI::FUN_SEL);
+        // Safety: The peripheral and pin singletons were passed to the constructor.
+        let uart = unsafe {
+            Self::steal(
+                UartI::ID,
+                ref_clk,
+                config,
+                #[cfg(feature = "vor1x")]
+                opt_irq_cfg,
+            )
         };
+        crate::ownership::mark_owned(RegisterBlock::Uart(UartI::ID));
+        Ok(uart)
+    }
+
+    /// Create a UART driver for a bank and pins which are selected at run-time, for example
+    /// with a strap pin.
+    ///
+    /// The pins and function selects are validated against [TX_PIN_MAPPINGS] and
+    /// [RX_PIN_MAPPINGS], and the configuration is validated with [Config::validate].
+    /// Otherwise, the initialization is the same as for [Self::new]. On Vorago 4x devices,
+    /// `ref_clk` is the APB1 clock for UART2 and the APB2 clock otherwise.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL, because neither the peripheral
+    /// nor the pin singletons are consumed. The caller must ensure that the UART and the pins
+    /// are not used by another driver.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn new_dynamic(
+        bank: Bank,
+        tx_pin: DynPinId,
+        rx_pin: DynPinId,
+        fun_sel_tx: FunctionSelect,
+        fun_sel_rx: FunctionSelect,
+        ref_clk: Hertz,
+        config: Config,
+        #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
+    ) -> Result<Self, UartInitError> {
+        for (table, pin, fun_sel) in [
+            (TX_PIN_MAPPINGS, tx_pin, fun_sel_tx),
+            (RX_PIN_MAPPINGS, rx_pin, fun_sel_rx),
+        ] {
+            if !is_valid_pin_mapping(table, bank, pin, fun_sel) {
+                return Err(InvalidUartPinError { bank, pin, fun_sel }.into());
+            }
+        }
+        config.validate(ref_clk)?;
+        config.configure_tx_pin(tx_pin, fun_sel_tx);
+        config.configure_rx_pin(rx_pin, fun_sel_rx);
+        // Safety: Ensured by the caller.
+        Ok(unsafe {
+            Self::steal(
+                bank,
+                ref_clk,
+                config,
+                #[cfg(feature = "vor1x")]
+                opt_irq_cfg,
+            )
+        })
+    }
 
-        // This is the calculation: (64.0 * (x - integer_part as f32) + 0.5) as u32 without floating
-        // point calculations.
-        let frac = ((ref_clk.raw() % (config.baudrate.raw() * 16)) * 64
-            + (config.baudrate.raw() * 8))
-            / (config.baudrate.raw() * 16);
-        // Calculations here are derived from chapter 4.8.5 (p.79) of the datasheet.
-        let x = ref_clk.raw() as f32 / (config.baudrate.raw() * baud_multiplier) as f32;
-        let integer_part = x as u32;
-        reg_block.write_clkscale(
-            ClockScale::builder()
-                .with_int(u18::new(integer_part))
-                .with_frac(u6::new(frac as u8))
+    /// Create a UART driver for the given bank without requiring the peripheral or pin
+    /// singletons.
+    ///
+    /// The peripheral is initialized like in [Self::new], but no pins are configured. This can be
+    /// used for the internal loopback mode, which does not require any pins. The configuration is
+    /// not validated, [Config::validate] can be used to check it first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baudrate can not be generated from the reference clock, see
+    /// [Config::validate].
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL. The caller must ensure that the
+    /// UART is not used by another driver. Debug builds panic if the UART is owned by a driver,
+    /// see [crate::ownership].
+    #[track_caller]
+    pub unsafe fn steal(
+        bank: Bank,
+        ref_clk: Hertz,
+        config: Config,
+        #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
+    ) -> Self {
+        crate::ownership::check_steal(RegisterBlock::Uart(bank));
+        Self::init_peripheral(
+            bank,
+            ref_clk,
+            &config,
+            config.enable_tx,
+            config.enable_rx,
+            #[cfg(feature = "vor1x")]
+            opt_irq_cfg,
+        );
+        Uart {
+            tx: Tx::new(bank, ref_clk, config),
+            rx: Rx::new(bank, ref_clk, config),
+            #[cfg(feature = "vor1x")]
+            irq_cfg: opt_irq_cfg,
+            enabled: Enable::builder()
+                .with_tx(config.enable_tx)
+                .with_rx(config.enable_rx)
                 .build(),
+            irq_enabled: regs::InterruptControl::new_with_raw_value(0),
+        }
+    }
+
+    fn new_tx_only_internal<UartI: UartInstance, TxPinI: TxPin>(
+        _uart: UartI,
+        _tx_pin: TxPinI,
+        ref_clk: Hertz,
+        config: Config,
+        #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
+    ) -> Result<Tx, UartInitError> {
+        if UartI::ID != TxPinI::BANK {
+            return Err(UartIdMissmatchError.into());
+        }
+        config.validate(ref_clk)?;
+        config.configure_tx_pin(TxPinI::ID, TxPinI::FUN_SEL);
+        Self::init_peripheral(
+            UartI::ID,
+            ref_clk,
+            &config,
+            config.enable_tx,
+            false,
+            #[cfg(feature = "vor1x")]
+            opt_irq_cfg,
+        );
+        crate::ownership::mark_owned(RegisterBlock::Uart(UartI::ID));
+        Ok(Tx::new(UartI::ID, ref_clk, config))
+    }
+
+    fn new_rx_only_internal<UartI: UartInstance, RxPinI: RxPin>(
+        _uart: UartI,
+        _rx_pin: RxPinI,
+        ref_clk: Hertz,
+        config: Config,
+        #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
+    ) -> Result<Rx, UartInitError> {
+        if UartI::ID != RxPinI::BANK {
+            return Err(UartIdMissmatchError.into());
+        }
+        config.validate(ref_clk)?;
+        config.configure_rx_pin(RxPinI::ID, RxPinI::FUN_SEL);
+        Self::init_peripheral(
+            UartI::ID,
+            ref_clk,
+            &config,
+            false,
+            config.enable_rx,
+            #[cfg(feature = "vor1x")]
+            opt_irq_cfg,
         );
+        crate::ownership::mark_owned(RegisterBlock::Uart(UartI::ID));
+        Ok(Rx::new(UartI::ID, ref_clk, config))
+    }
 
-        let (paren, pareven) = match config.parity {
-            Parity::None => (false, false),
-            Parity::Odd => (true, false),
-            Parity::Even => (true, true),
+    /// Common peripheral initialization: Clock scale, control register, FIFO clear, enable
+    /// register and the optional interrupt configuration.
+    fn init_peripheral(
+        bank: Bank,
+        ref_clk: Hertz,
+        config: &Config,
+        enable_tx: bool,
+        enable_rx: bool,
+        #[cfg(feature = "vor1x")] opt_irq_cfg: Option<InterruptConfig>,
+    ) {
+        enable_peripheral_clock(bank.periph_sel());
+
+        let mut reg_block = regs::Uart::new_mmio(bank);
+        // The configuration was validated by the constructor.
+        reg_block.write_clkscale(config.clkscale(ref_clk).unwrap());
+
+        let (paren, pareven) = match (config.parity_manual, config.parity) {
+            // The parity bit is always part of the frame in manual parity mode.
+            (true, _) => (true, false),
+            (false, Parity::None) => (false, false),
+            (false, Parity::Odd) => (true, false),
+            (false, Parity::Even) => (true, true),
         };
         reg_block.write_ctrl(
             Control::builder()
@@ -510,38 +1223,56 @@ impl Uart {
                 .with_loopback(false)
                 .with_wordsize(config.wordsize)
                 .with_stopbits(config.stopbits)
-                .with_parity_manual(false)
+                .with_parity_manual(config.parity_manual)
                 .with_parity_even(pareven)
                 .with_parity_enable(paren)
                 .build(),
         );
+        if let Some(level) = config.rts_threshold {
+            reg_block
+                .write_rx_fifo_rts_trigger(FifoTrigger::new_with_raw_value(0).with_level(level));
+        }
         // Clear the FIFO
         reg_block.write_fifo_clr(FifoClear::builder().with_tx(true).with_rx(true).build());
         reg_block.write_enable(
             Enable::builder()
-                .with_tx(config.enable_tx)
-                .with_rx(config.enable_rx)
+                .with_tx(enable_tx)
+                .with_rx(enable_rx)
                 .build(),
         );
 
         #[cfg(feature = "vor1x")]
         if let Some(irq_cfg) = opt_irq_cfg {
             if irq_cfg.route {
-                enable_peripheral_clock(PeripheralSelect::Irqsel);
-                unsafe { va108xx::Irqsel::steal() }
-                    .uart(UartI::ID as usize)
-                    .write(|w| unsafe { w.bits(irq_cfg.id as u32) });
+                crate::irqsel::route_uart(bank, irq_cfg.id);
             }
             if irq_cfg.enable_in_nvic {
                 // Safety: User has specifically configured this.
                 unsafe { enable_nvic_interrupt(irq_cfg.id) };
             }
         }
+    }
 
-        Ok(Uart {
-            tx: Tx::new(UartI::ID),
-            rx: Rx::new(UartI::ID),
-        })
+    /// Reset the peripheral and replay the full register initialization with the configuration
+    /// passed to the constructor.
+    ///
+    /// This can be used to recover from faults like single event upsets without re-creating the
+    /// driver and losing its pins. The TX and RX enable states and the interrupt enables are
+    /// restored to the state set with the constructor and the enable, disable, [Self::listen] and
+    /// [Self::unlisten] methods of the driver, the FIFOs are cleared. The registers are not read
+    /// back, so a corrupted register value is not replayed.
+    pub fn reinitialize(&mut self) {
+        reset_peripheral_for_cycles(self.tx.id.periph_sel(), 2);
+        Self::init_peripheral(
+            self.tx.id,
+            self.tx.ref_clk,
+            &self.tx.cfg,
+            self.enabled.tx(),
+            self.enabled.rx(),
+            #[cfg(feature = "vor1x")]
+            self.irq_cfg,
+        );
+        self.tx.regs.write_irq_enabled(self.irq_enabled);
     }
 
     #[inline]
@@ -549,23 +1280,59 @@ impl Uart {
         self.tx.perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.tx.perid_matches()
+    }
+
+    /// Enable the receiver and the transmitter with a single register write.
+    #[inline]
+    pub fn enable(&mut self) {
+        self.enabled = Enable::builder().with_tx(true).with_rx(true).build();
+        self.tx.regs.write_enable(self.enabled);
+    }
+
+    /// Disable the receiver and the transmitter with a single register write.
+    #[inline]
+    pub fn disable(&mut self) {
+        self.enabled = Enable::builder().with_tx(false).with_rx(false).build();
+        self.tx.regs.write_enable(self.enabled);
+    }
+
+    /// Checks whether the receiver is enabled by reading back the enable register.
+    #[inline]
+    pub fn rx_enabled(&self) -> bool {
+        self.rx.is_enabled()
+    }
+
+    /// Checks whether the transmitter is enabled by reading back the enable register.
+    #[inline]
+    pub fn tx_enabled(&self) -> bool {
+        self.tx.is_enabled()
+    }
+
     #[inline]
     pub fn enable_rx(&mut self) {
+        self.enabled.set_rx(true);
         self.rx.enable();
     }
 
     #[inline]
     pub fn disable_rx(&mut self) {
+        self.enabled.set_rx(false);
         self.rx.disable();
     }
 
     #[inline]
     pub fn enable_tx(&mut self) {
+        self.enabled.set_tx(true);
         self.tx.enable();
     }
 
     #[inline]
     pub fn disable_tx(&mut self) {
+        self.enabled.set_tx(false);
         self.tx.disable();
     }
 
@@ -582,33 +1349,17 @@ impl Uart {
     }
 
     pub fn listen(&mut self, event: Event) {
-        self.tx.regs.modify_irq_enabled(|mut value| {
-            match event {
-                Event::RxError => value.set_rx_status(true),
-                Event::RxFifoHalfFull => value.set_rx(true),
-                Event::RxTimeout => value.set_rx_timeout(true),
-                Event::TxEmpty => value.set_tx_empty(true),
-                Event::TxError => value.set_tx_status(true),
-                Event::TxFifoHalfFull => value.set_tx(true),
-                Event::TxCts => value.set_tx_cts(true),
-            }
-            value
-        });
+        self.irq_enabled = event.apply(self.irq_enabled, true);
+        self.tx
+            .regs
+            .modify_irq_enabled(|value| event.apply(value, true));
     }
 
     pub fn unlisten(&mut self, event: Event) {
-        self.tx.regs.modify_irq_enabled(|mut value| {
-            match event {
-                Event::RxError => value.set_rx_status(false),
-                Event::RxFifoHalfFull => value.set_rx(false),
-                Event::RxTimeout => value.set_rx_timeout(false),
-                Event::TxEmpty => value.set_tx_empty(false),
-                Event::TxError => value.set_tx_status(false),
-                Event::TxFifoHalfFull => value.set_tx(false),
-                Event::TxCts => value.set_tx_cts(false),
-            }
-            value
-        });
+        self.irq_enabled = event.apply(self.irq_enabled, false);
+        self.tx
+            .regs
+            .modify_irq_enabled(|value| event.apply(value, false));
     }
 
     /// Poll receiver errors.
@@ -616,14 +1367,72 @@ impl Uart {
         self.rx.poll_errors()
     }
 
-    pub fn split(self) -> (Tx, Rx) {
-        (self.tx, self.rx)
+    /// Configuration passed to the constructor.
+    #[inline]
+    pub const fn config(&self) -> &Config {
+        &self.tx.cfg
     }
-}
 
-impl embedded_io::ErrorType for Uart {
-    type Error = Infallible;
-}
+    /// Reference clock the baudrate divisor was calculated for. This is the system clock on
+    /// Vorago 1x devices and the clock of the APB bus of the UART on Vorago 4x devices.
+    #[inline]
+    pub const fn ref_clk(&self) -> Hertz {
+        self.tx.ref_clk
+    }
+
+    /// See [Config::character_time].
+    #[inline]
+    pub const fn character_time(&self) -> Nanoseconds {
+        self.tx.cfg.character_time()
+    }
+
+    pub fn split(self) -> (Tx, Rx) {
+        (self.tx, self.rx)
+    }
+
+    /// Reunite the halves created by [Self::split].
+    ///
+    /// The configuration and the reference clock of the transmitter are used for the reunited
+    /// driver. On Vorago 1x devices, the interrupt configuration is not replayed by
+    /// [Self::reinitialize] after reuniting, but the IRQSEL routing and the NVIC configuration
+    /// are not affected by the peripheral reset anyway.
+    ///
+    /// The halves do not track their enable and interrupt enable state, so the state replayed by
+    /// [Self::reinitialize] is read back from the peripheral once when reuniting.
+    pub fn reunite(tx: Tx, rx: Rx) -> Result<Self, UartIdMissmatchError> {
+        if tx.id != rx.id {
+            return Err(UartIdMissmatchError);
+        }
+        let enabled = tx.regs.read_enable();
+        let irq_enabled = tx.regs.read_irq_enabled();
+        Ok(Uart {
+            tx,
+            rx,
+            #[cfg(feature = "vor1x")]
+            irq_cfg: None,
+            enabled,
+            irq_enabled,
+        })
+    }
+}
+
+impl SleepParticipant for Uart {
+    /// Prepares the transmitter before the receiver, see the [SleepParticipant] implementations
+    /// of [Tx] and [Rx].
+    fn prepare_sleep(&mut self) {
+        self.tx.prepare_sleep();
+        self.rx.prepare_sleep();
+    }
+
+    fn resume(&mut self) {
+        self.rx.resume();
+        self.tx.resume();
+    }
+}
+
+impl embedded_io::ErrorType for Uart {
+    type Error = Infallible;
+}
 
 impl embedded_hal_nb::serial::ErrorType for Uart {
     type Error = Infallible;
@@ -696,30 +1505,85 @@ pub fn disable_rx_interrupts(uart: &mut MmioUart<'static>) {
 /// Serial receiver.
 ///
 /// Can be created by using the [Uart::split] API.
+///
+/// With a [WordSize] of less than 8 bits, the byte based read APIs, for example the
+/// [embedded_io::Read] and [embedded_hal_nb::serial::Read] implementations, mask the received
+/// data to the configured number of data bits, so the upper bits of the returned bytes are
+/// always 0. The raw FIFO access functions like [Self::read_fifo] return the unmodified data
+/// register.
 pub struct Rx {
     id: Bank,
     regs: regs::MmioUart<'static>,
+    ref_clk: Hertz,
+    cfg: Config,
+    /// Enable state recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
 }
 
 impl Rx {
     /// Retrieve a TX pin without expecting an explicit UART structure
     ///
+    /// The reference clock and the configuration are only used for the getters like
+    /// [Self::baudrate], the peripheral is not re-configured.
+    ///
     /// # Safety
     ///
-    /// Circumvents the HAL safety guarantees.
+    /// Circumvents the HAL safety guarantees. Debug builds panic if the UART is owned by a
+    /// driver, see [crate::ownership].
     #[inline(always)]
-    pub unsafe fn steal(id: Bank) -> Self {
-        Self::new(id)
+    #[track_caller]
+    pub unsafe fn steal(id: Bank, ref_clk: Hertz, config: Config) -> Self {
+        crate::ownership::check_steal(RegisterBlock::Uart(id));
+        Self::new(id, ref_clk, config)
     }
 
     #[inline(always)]
-    fn new(id: Bank) -> Self {
+    fn new(id: Bank, ref_clk: Hertz, cfg: Config) -> Self {
         Self {
             id,
             regs: regs::Uart::new_mmio(id),
+            ref_clk,
+            cfg,
+            sleep_state: None,
         }
     }
 
+    /// Configuration the UART was initialized with.
+    #[inline]
+    pub const fn config(&self) -> &Config {
+        &self.cfg
+    }
+
+    #[inline]
+    pub const fn ref_clk(&self) -> Hertz {
+        self.ref_clk
+    }
+
+    /// Configured baudrate.
+    #[inline]
+    pub const fn baudrate(&self) -> Hertz {
+        self.cfg.baudrate
+    }
+
+    /// See [Config::character_time].
+    #[inline]
+    pub const fn character_time(&self) -> Nanoseconds {
+        self.cfg.character_time()
+    }
+
+    /// Depth of the RX FIFO in bytes.
+    #[inline]
+    pub const fn fifo_capacity(&self) -> usize {
+        self.id.fifo_depth()
+    }
+
+    /// Number of characters which can be received in the given duration, see
+    /// [Config::characters_in]. This can be used to convert a timeout to a character count.
+    #[inline]
+    pub const fn characters_in(&self, duration: Nanoseconds) -> u32 {
+        self.cfg.characters_in(duration)
+    }
+
     pub fn poll_errors(&self) -> Option<UartErrors> {
         let mut errors = UartErrors::default();
 
@@ -728,7 +1592,7 @@ impl Rx {
             errors.overflow = true;
         } else if status.framing_error() {
             errors.framing = true;
-        } else if status.parity_error() {
+        } else if parity_error_reported(&self.regs, status) {
             errors.parity = true;
         } else {
             return None;
@@ -741,25 +1605,94 @@ impl Rx {
         self.regs.read_perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.perid() == regs::EXPECTED_PERID
+    }
+
     #[inline]
     pub fn clear_fifo(&mut self) {
         self.regs
             .write_fifo_clr(FifoClear::builder().with_tx(false).with_rx(true).build());
     }
 
+    /// Read with an upper bound for the time waiting for data.
+    ///
+    /// This works like [embedded_io::Read::read]: It waits until at least one byte is
+    /// available and then reads all available bytes which fit into the buffer. If no data is
+    /// received until the deadline expires, [TimeoutError] is returned. The peripheral state is
+    /// not changed in that case. If the receiver is disabled and the RX FIFO is empty, 0 is
+    /// returned immediately.
+    pub fn read_with_timeout(
+        &mut self,
+        buf: &mut [u8],
+        deadline: &mut impl Deadline,
+    ) -> Result<usize, TimeoutError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let data_available = poll_with_deadline(deadline, || {
+            if self.regs.read_rx_status().data_available() {
+                return Some(true);
+            }
+            (!self.is_enabled()).then_some(false)
+        })?;
+        if !data_available {
+            return Ok(0);
+        }
+        let mut read = 0;
+        for byte in buf.iter_mut() {
+            match self.read() {
+                Ok(w) => {
+                    *byte = w;
+                    read += 1;
+                }
+                Err(nb::Error::WouldBlock) => break,
+            }
+        }
+        Ok(read)
+    }
+
+    /// Read which gives up after the given number of character times of silence on the line.
+    ///
+    /// This works like [Self::read_with_timeout], with a timeout of [Config::idle_timeout]
+    /// which is measured with the given delay provider. The line is polled four times per
+    /// character time, and the time spent polling is not accounted for, so the effective
+    /// timeout is slightly longer.
+    pub fn read_with_character_timeout(
+        &mut self,
+        buf: &mut [u8],
+        idle_chars: u16,
+        delay: &mut impl embedded_hal::delay::DelayNs,
+    ) -> Result<usize, TimeoutError> {
+        let poll_interval_us = self.character_time().ticks() / 4_000;
+        let mut deadline = DelayDeadline::new_with_poll_interval(
+            delay,
+            self.cfg.idle_timeout(idle_chars),
+            poll_interval_us,
+        );
+        self.read_with_timeout(buf, &mut deadline)
+    }
+
     #[inline]
     pub fn disable_interrupts(&mut self) {
         disable_rx_interrupts(&mut self.regs);
     }
 
+    /// Enables the RX interrupts and optionally the RX timeout interrupt.
+    ///
+    /// If `enable_in_nvic` is set, the interrupt is also unmasked in the NVIC. On Vorago 1x
+    /// devices, this is only possible if the UART interrupt was routed by the HAL, see
+    /// [Bank::routed_interrupt].
     #[inline]
-    pub fn enable_interrupts(
-        &mut self,
-        #[cfg(feature = "vor4x")] enable_in_nvic: bool,
-        timeout: bool,
-    ) {
-        #[cfg(feature = "vor4x")]
+    pub fn enable_interrupts(&mut self, enable_in_nvic: bool, timeout: bool) {
         if enable_in_nvic {
+            #[cfg(feature = "vor1x")]
+            if let Some(irq) = self.id.routed_interrupt() {
+                unsafe { enable_nvic_interrupt(irq) };
+            }
+            #[cfg(feature = "vor4x")]
             unsafe {
                 enable_nvic_interrupt(self.id.interrupt_id_rx());
             }
@@ -767,6 +1700,22 @@ impl Rx {
         enable_rx_interrupts(&mut self.regs, timeout);
     }
 
+    /// Checks whether the RX interrupt is pending in the NVIC.
+    ///
+    /// On Vorago 1x devices, the RX and TX interrupts share one interrupt line, and this
+    /// returns [false] if the UART interrupt was not routed by the HAL, see
+    /// [Bank::routed_interrupt].
+    #[inline]
+    pub fn irq_pending(&self) -> bool {
+        #[cfg(feature = "vor1x")]
+        return self
+            .id
+            .routed_interrupt()
+            .is_some_and(crate::is_interrupt_pending);
+        #[cfg(feature = "vor4x")]
+        return crate::is_interrupt_pending(self.id.interrupt_id_rx());
+    }
+
     #[inline]
     pub fn enable(&mut self) {
         enable_rx(&mut self.regs);
@@ -777,6 +1726,12 @@ impl Rx {
         disable_rx(&mut self.regs);
     }
 
+    /// Checks whether the receiver is enabled by reading back the enable register.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.regs.read_enable().rx()
+    }
+
     /// Low level function to read a word from the UART FIFO.
     ///
     /// Uses the [nb] API to allow usage in blocking and non-blocking contexts.
@@ -804,11 +1759,72 @@ impl Rx {
         self.regs.read_data().raw_value()
     }
 
+    /// Set the RX FIFO level for the automatic RTS flow control.
+    ///
+    /// If the automatic RTS flow control is enabled, RTS is deasserted when the RX FIFO count
+    /// is larger than or equal to this level, and asserted again when the FIFO was drained
+    /// below it. The remaining FIFO slots are the headroom for words which are already in
+    /// flight at the sender.
+    ///
+    /// The level should be larger than the RX FIFO trigger level of the
+    /// [Event::RxFifoHalfFull] interrupt. Otherwise, the sender is stopped before the interrupt
+    /// is generated, and the FIFO is only drained after the RX timeout.
+    #[inline]
+    pub fn set_rts_threshold(&mut self, level: u5) {
+        self.regs
+            .write_rx_fifo_rts_trigger(FifoTrigger::new_with_raw_value(0).with_level(level));
+    }
+
+    #[inline]
+    pub fn rts_threshold(&self) -> u5 {
+        self.regs.read_rx_fifo_rts_trigger().level()
+    }
+
+    /// Data byte of a word read from the data register, masked to the configured [WordSize].
+    #[inline(always)]
+    fn data_byte(&self, word: u32) -> u8 {
+        word as u8 & self.cfg.wordsize.data_mask()
+    }
+
+    /// Read a word together with its received parity bit.
+    ///
+    /// This can only be used in the manual parity mode, see [Config::parity_manual].
+    pub fn read_word_with_parity(&mut self) -> nb::Result<(u8, bool), ParityManualDisabledError> {
+        if !self.regs.read_ctrl().parity_manual() {
+            return Err(nb::Error::Other(ParityManualDisabledError));
+        }
+        if !self.regs.read_rx_status().data_available() {
+            return Err(nb::Error::WouldBlock);
+        }
+        let data = self.regs.read_data();
+        Ok((data.value() & self.cfg.wordsize.data_mask(), data.dparity()))
+    }
+
+    /// Convert into a [RxWithInterrupt]. The receiver is enabled if it is disabled, see
+    /// [RxWithInterrupt::new].
     pub fn into_rx_with_irq(self) -> RxWithInterrupt {
         RxWithInterrupt::new(self)
     }
 }
 
+impl SleepParticipant for Rx {
+    /// Disables the receiver, so noise on the line does not latch reception errors. Received
+    /// data stays in the FIFO.
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_none() {
+            self.sleep_state = Some(self.is_enabled());
+            self.disable();
+        }
+    }
+
+    /// Enables the receiver again if it was enabled before.
+    fn resume(&mut self) {
+        if self.sleep_state.take() == Some(true) {
+            self.enable();
+        }
+    }
+}
+
 impl embedded_io::ErrorType for Rx {
     type Error = Infallible;
 }
@@ -819,36 +1835,36 @@ impl embedded_hal_nb::serial::ErrorType for Rx {
 
 impl embedded_hal_nb::serial::Read<u8> for Rx {
     fn read(&mut self) -> nb::Result<u8, Self::Error> {
-        self.read_fifo().map(|val| (val & 0xff) as u8).map_err(|e| {
-            if let nb::Error::Other(_) = e {
-                unreachable!()
-            }
-            nb::Error::WouldBlock
-        })
+        self.read_fifo()
+            .map(|val| self.data_byte(val))
+            .map_err(|e| {
+                if let nb::Error::Other(_) = e {
+                    unreachable!()
+                }
+                nb::Error::WouldBlock
+            })
     }
 }
 
+/// Blocking read without an upper bound for the time waiting for data.
+///
+/// If the receiver is disabled and the RX FIFO is empty, 0 is returned instead of waiting
+/// forever, also if the receiver is disabled while waiting. Otherwise, this only returns after
+/// at least one byte was received. [Rx::read_with_timeout] and
+/// [Rx::read_with_character_timeout] can be used to bound the waiting time.
 impl embedded_io::Read for Rx {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        if buf.is_empty() {
-            return Ok(0);
-        }
-        let mut read = 0;
-        loop {
-            if self.regs.read_rx_status().data_available() {
-                break;
-            }
-        }
-        for byte in buf.iter_mut() {
-            match <Self as embedded_hal_nb::serial::Read<u8>>::read(self) {
-                Ok(w) => {
-                    *byte = w;
-                    read += 1;
-                }
-                Err(nb::Error::WouldBlock) => break,
-            }
-        }
-
+        let read = self
+            .read_with_timeout(buf, &mut NoDeadline)
+            .expect("read without deadline can not time out");
+        #[cfg(feature = "bus-trace")]
+        crate::bus_trace::event::<_, Infallible>(
+            self.id.periph_sel(),
+            crate::bus_trace::Direction::Read,
+            None,
+            &buf[..read],
+            Ok(()),
+        );
         Ok(read)
     }
 }
@@ -892,41 +1908,180 @@ pub fn disable_tx_interrupts(uart: &mut MmioUart<'static>) {
 /// Serial transmitter
 ///
 /// Can be created by using the [Uart::split] API.
+///
+/// With a [WordSize] of less than 8 bits, the byte based write APIs, for example the
+/// [embedded_io::Write] and [embedded_hal_nb::serial::Write] implementations, silently clear the
+/// bits above the configured number of data bits. [Self::write_checked] rejects such bytes
+/// instead. The raw FIFO access functions like [Self::write_fifo] write the unmodified data
+/// register.
 pub struct Tx {
     id: Bank,
     regs: regs::MmioUart<'static>,
+    ref_clk: Hertz,
+    cfg: Config,
+    /// Enable state recorded by [SleepParticipant::prepare_sleep].
+    sleep_state: Option<bool>,
 }
 
 impl Tx {
     /// Retrieve a TX pin without expecting an explicit UART structure
     ///
+    /// The reference clock and the configuration are only used for the getters like
+    /// [Self::baudrate], the peripheral is not re-configured.
+    ///
     /// # Safety
     ///
-    /// Circumvents the HAL safety guarantees.
+    /// Circumvents the HAL safety guarantees. Debug builds panic if the UART is owned by a
+    /// driver, see [crate::ownership].
     #[inline(always)]
-    pub unsafe fn steal(id: Bank) -> Self {
-        Self::new(id)
+    #[track_caller]
+    pub unsafe fn steal(id: Bank, ref_clk: Hertz, config: Config) -> Self {
+        crate::ownership::check_steal(RegisterBlock::Uart(id));
+        Self::new(id, ref_clk, config)
+    }
+
+    /// Create a [PanicWriter] for last-resort output, for example inside a panic handler.
+    ///
+    /// The transmitter is force-enabled and the TX interrupts are disabled, independently of
+    /// the current configuration of the UART.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents the HAL safety guarantees. Any ongoing transfer, for example by a [TxAsync],
+    /// might be corrupted, so this must only be used when no regular operation is expected to
+    /// follow.
+    pub unsafe fn steal_blocking_writer(bank: Bank) -> PanicWriter {
+        let mut regs = regs::Uart::new_mmio(bank);
+        disable_tx_interrupts(&mut regs);
+        enable_tx(&mut regs);
+        PanicWriter {
+            regs,
+            spin_limit: PanicWriter::DEFAULT_SPIN_LIMIT,
+            wedged: false,
+        }
     }
 
     #[inline(always)]
-    fn new(id: Bank) -> Self {
+    fn new(id: Bank, ref_clk: Hertz, cfg: Config) -> Self {
         Self {
             id,
             regs: regs::Uart::new_mmio(id),
+            ref_clk,
+            cfg,
+            sleep_state: None,
         }
     }
 
+    /// Configuration the UART was initialized with.
+    #[inline]
+    pub const fn config(&self) -> &Config {
+        &self.cfg
+    }
+
+    #[inline]
+    pub const fn ref_clk(&self) -> Hertz {
+        self.ref_clk
+    }
+
+    /// Configured baudrate.
+    #[inline]
+    pub const fn baudrate(&self) -> Hertz {
+        self.cfg.baudrate
+    }
+
+    /// See [Config::character_time].
+    #[inline]
+    pub const fn character_time(&self) -> Nanoseconds {
+        self.cfg.character_time()
+    }
+
     #[inline]
     pub fn perid(&self) -> u32 {
         self.regs.read_perid()
     }
 
+    /// Checks whether the peripheral ID register has the expected value.
+    #[inline]
+    pub fn perid_matches(&self) -> bool {
+        self.perid() == regs::EXPECTED_PERID
+    }
+
+    /// Depth of the TX FIFO in bytes.
+    #[inline]
+    pub const fn fifo_capacity(&self) -> usize {
+        self.id.fifo_depth()
+    }
+
     #[inline]
     pub fn clear_fifo(&mut self) {
         self.regs
             .write_fifo_clr(FifoClear::builder().with_tx(true).with_rx(false).build());
     }
 
+    /// Wait until all data was transmitted, with an upper bound for the waiting time.
+    ///
+    /// If the deadline expires, for example because the transmitter is blocked by the flow
+    /// control, the TX FIFO is cleared and [TimeoutError] is returned. Data which was not
+    /// transmitted yet is lost in that case.
+    pub fn flush_with_timeout(&mut self, deadline: &mut impl Deadline) -> Result<(), TimeoutError> {
+        let result = poll_with_deadline(deadline, || {
+            (!self.regs.read_tx_status().write_busy()).then_some(())
+        });
+        if result.is_err() {
+            self.clear_fifo();
+        }
+        result
+    }
+
+    /// Checks whether the transmission of all written data is complete, including the stop
+    /// bits of the last character in the shift register.
+    ///
+    /// This consults the WRBUSY bit, which is set while the TX FIFO contains data, and the
+    /// TXBUSY bit of the TXSTATUS register, which is set while the transmitter shifts out a
+    /// character. This matches the condition of the IRQ_TX_EMPTY interrupt. The transmission is
+    /// complete if both bits are cleared. Unlike [Self::flush_with_timeout] and
+    /// [embedded_io::Write::flush], which only check the WRBUSY bit, this can be used before
+    /// disabling the transmitter or switching the direction of a half-duplex transceiver.
+    #[inline]
+    pub fn is_transmission_complete(&self) -> bool {
+        let status = self.regs.read_tx_status();
+        !status.write_busy() && !status.tx_busy()
+    }
+
+    /// Blocking wait until the transmission is complete, see [Self::is_transmission_complete].
+    ///
+    /// If the transmitter is disabled, this returns immediately because the remaining data in
+    /// the TX FIFO is not transmitted.
+    pub fn wait_transmission_complete(&mut self) {
+        let mut busy_wait = BusyWait::new();
+        while self.is_enabled() && !self.is_transmission_complete() {
+            busy_wait.tick();
+        }
+    }
+
+    /// Wait until the transmission is complete and disable the transmitter afterwards, so no
+    /// character is cut off.
+    pub fn disable_after_drain(&mut self) {
+        self.wait_transmission_complete();
+        self.disable();
+    }
+
+    /// Checks whether the TX interrupt is pending in the NVIC.
+    ///
+    /// On Vorago 1x devices, the RX and TX interrupts share one interrupt line, and this
+    /// returns [false] if the UART interrupt was not routed by the HAL, see
+    /// [Bank::routed_interrupt].
+    #[inline]
+    pub fn irq_pending(&self) -> bool {
+        #[cfg(feature = "vor1x")]
+        return self
+            .id
+            .routed_interrupt()
+            .is_some_and(crate::is_interrupt_pending);
+        #[cfg(feature = "vor4x")]
+        return crate::is_interrupt_pending(self.id.interrupt_id_tx());
+    }
+
     #[inline]
     pub fn enable(&mut self) {
         self.regs.modify_enable(|mut value| {
@@ -943,22 +2098,61 @@ impl Tx {
         });
     }
 
+    /// Checks whether the transmitter is enabled by reading back the enable register.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.regs.read_enable().tx()
+    }
+
     /// Enables the IRQ_TX, IRQ_TX_STATUS and IRQ_TX_EMPTY interrupts.
     ///
     /// - The IRQ_TX interrupt is generated when the TX FIFO is at least half empty.
     /// - The IRQ_TX_STATUS interrupt is generated when write data is lost due to a FIFO overflow
     /// - The IRQ_TX_EMPTY interrupt is generated when the TX FIFO is empty and the TXBUSY signal
     ///   is 0
+    ///
+    /// If `enable_in_nvic` is set, the interrupt is also unmasked in the NVIC. On Vorago 1x
+    /// devices, this is only possible if the UART interrupt was routed by the HAL, see
+    /// [Bank::routed_interrupt].
     #[inline]
-    pub fn enable_interrupts(&mut self, #[cfg(feature = "vor4x")] enable_in_nvic: bool) {
-        #[cfg(feature = "vor4x")]
+    pub fn enable_interrupts(&mut self, enable_in_nvic: bool) {
         if enable_in_nvic {
-            unsafe { enable_nvic_interrupt(self.id.interrupt_id_tx()) };
+            self.enable_nvic_interrupt();
         }
         // Safety: We own the UART structure
         enable_tx_interrupts(&mut self.regs);
     }
 
+    /// Unmask the TX interrupt in the NVIC, see [Self::enable_interrupts].
+    #[inline]
+    pub(crate) fn enable_nvic_interrupt(&self) {
+        #[cfg(feature = "vor1x")]
+        if let Some(irq) = self.id.routed_interrupt() {
+            unsafe { enable_nvic_interrupt(irq) };
+        }
+        #[cfg(feature = "vor4x")]
+        unsafe {
+            enable_nvic_interrupt(self.id.interrupt_id_tx());
+        }
+    }
+
+    /// Set the TX FIFO trigger level. The IRQ_TX interrupt is active if the TX FIFO count is
+    /// smaller than this level, so a level of 0 never triggers the interrupt.
+    ///
+    /// [TxAsync] refills the FIFO when this interrupt occurs. A higher level leaves more time
+    /// for the interrupt handler to refill the FIFO before it runs empty, at the cost of more
+    /// interrupts per transfer. The reset value is 8, which is half the FIFO depth.
+    #[inline]
+    pub fn set_tx_fifo_trigger(&mut self, level: u5) {
+        self.regs
+            .write_tx_fifo_trigger(FifoTrigger::new_with_raw_value(0).with_level(level));
+    }
+
+    #[inline]
+    pub fn tx_fifo_trigger(&self) -> u5 {
+        self.regs.read_tx_fifo_trigger().level()
+    }
+
     /// Disables the IRQ_TX, IRQ_TX_STATUS and IRQ_TX_EMPTY interrupts.
     ///
     /// [Self::enable_interrupts] documents the interrupts.
@@ -994,11 +2188,100 @@ impl Tx {
         self.regs.write_data(Data::new_with_raw_value(data));
     }
 
+    /// Write a byte, with the bits above the configured [WordSize] cleared.
+    #[inline]
+    fn write_byte(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.write_fifo((byte & self.cfg.wordsize.data_mask()) as u32)
+    }
+
+    /// Write a byte like [embedded_hal_nb::serial::Write::write], but reject it if it has bits
+    /// set above the configured [WordSize] instead of clearing them.
+    pub fn write_checked(&mut self, byte: u8) -> nb::Result<(), WordSizeExceededError> {
+        let wordsize = self.cfg.wordsize;
+        if byte & !wordsize.data_mask() != 0 {
+            return Err(nb::Error::Other(WordSizeExceededError {
+                data: byte,
+                wordsize,
+            }));
+        }
+        self.write_fifo(byte as u32)
+            .map_err(|_| nb::Error::WouldBlock)
+    }
+
+    /// Write a word with the given parity bit.
+    ///
+    /// This can only be used in the manual parity mode, see [Config::parity_manual]. The bits
+    /// above the configured [WordSize] are cleared.
+    pub fn write_word_with_parity(
+        &mut self,
+        data: u8,
+        parity_bit: bool,
+    ) -> nb::Result<(), ParityManualDisabledError> {
+        if !self.regs.read_ctrl().parity_manual() {
+            return Err(nb::Error::Other(ParityManualDisabledError));
+        }
+        if !self.regs.read_tx_status().ready() {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.regs.write_data(
+            Data::new_with_raw_value(0)
+                .with_value(data & self.cfg.wordsize.data_mask())
+                .with_dparity(parity_bit),
+        );
+        Ok(())
+    }
+
+    /// Write the data of multiple buffers, in order, without copying them into one contiguous
+    /// buffer first.
+    ///
+    /// This works like [embedded_io::Write::write] for the concatenation of all buffers: It
+    /// blocks until the FIFO can take at least one byte and then writes as many bytes as fit
+    /// into the FIFO. The number of written bytes is returned, which might be smaller than the
+    /// total length of all buffers. Empty buffers are skipped. If all buffers are empty, 0 is
+    /// returned immediately.
+    pub fn write_vectored(&mut self, bufs: &[&[u8]]) -> Result<usize, Infallible> {
+        if bufs.iter().all(|buf| buf.is_empty()) {
+            return Ok(0);
+        }
+        let mut busy_wait = BusyWait::new();
+        while !self.regs.read_tx_status().ready() {
+            busy_wait.tick();
+        }
+        let mut written = 0;
+        for byte in bufs.iter().flat_map(|buf| buf.iter()) {
+            match self.write_byte(*byte) {
+                Ok(_) => written += 1,
+                Err(nb::Error::WouldBlock) => break,
+            }
+        }
+        Ok(written)
+    }
+
+    /// Convert into a [TxAsync]. The transmitter is enabled if it is disabled, see
+    /// [TxAsync::new].
     pub fn into_async(self) -> TxAsync {
         TxAsync::new(self)
     }
 }
 
+impl SleepParticipant for Tx {
+    /// Waits until the transmission including the shift register is complete and disables the
+    /// transmitter, see [Tx::disable_after_drain].
+    fn prepare_sleep(&mut self) {
+        if self.sleep_state.is_none() {
+            self.sleep_state = Some(self.is_enabled());
+            self.disable_after_drain();
+        }
+    }
+
+    /// Enables the transmitter again if it was enabled before.
+    fn resume(&mut self) {
+        if self.sleep_state.take() == Some(true) {
+            self.enable();
+        }
+    }
+}
+
 impl embedded_io::ErrorType for Tx {
     type Error = Infallible;
 }
@@ -1009,7 +2292,7 @@ impl embedded_hal_nb::serial::ErrorType for Tx {
 
 impl embedded_hal_nb::serial::Write<u8> for Tx {
     fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
-        self.write_fifo(word as u32)
+        self.write_byte(word)
     }
 
     fn flush(&mut self) -> nb::Result<(), Self::Error> {
@@ -1026,24 +2309,133 @@ impl embedded_io::Write for Tx {
         if buf.is_empty() {
             return Ok(0);
         }
+        let mut busy_wait = BusyWait::new();
         loop {
             if self.regs.read_tx_status().ready() {
                 break;
             }
+            busy_wait.tick();
         }
         let mut written = 0;
         for byte in buf.iter() {
             match <Self as embedded_hal_nb::serial::Write<u8>>::write(self, *byte) {
                 Ok(_) => written += 1,
-                Err(nb::Error::WouldBlock) => return Ok(written),
+                Err(nb::Error::WouldBlock) => break,
             }
         }
-
+        #[cfg(feature = "bus-trace")]
+        crate::bus_trace::event::<_, Infallible>(
+            self.id.periph_sel(),
+            crate::bus_trace::Direction::Write,
+            None,
+            &buf[..written],
+            Ok(()),
+        );
         Ok(written)
     }
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        nb::block!(<Self as embedded_hal_nb::serial::Write<u8>>::flush(self))
+        let mut busy_wait = BusyWait::new();
+        while self.regs.read_tx_status().write_busy() {
+            busy_wait.tick();
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Write for Tx {
+    /// Blocking write of the string. This busy-waits until all bytes were written to the FIFO.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for byte in s.bytes() {
+            // Infallible.
+            let _ = nb::block!(self.write_byte(byte));
+        }
+        Ok(())
+    }
+}
+
+/// Minimal blocking UART writer for last-resort output, for example inside a panic handler.
+///
+/// Can be created with [Tx::steal_blocking_writer]. The writer does not rely on interrupts and
+/// busy-waits for a bounded number of status register polls for each byte, so a wedged UART,
+/// for example one blocked by the flow control, can not hang the caller forever. After the first
+/// byte which could not be written, the UART is considered wedged and all further data is
+/// discarded immediately.
+///
+/// Using this writer may corrupt an in-flight transfer of the regular drivers, for example of
+/// the [TxAsync]. It should only be used when no regular operation is expected to follow.
+pub struct PanicWriter {
+    regs: regs::MmioUart<'static>,
+    spin_limit: u32,
+    wedged: bool,
+}
+
+impl PanicWriter {
+    /// Default number of status register polls for each byte.
+    ///
+    /// This covers the transmission time of a byte at low baud rates with the maximum system
+    /// clock.
+    pub const DEFAULT_SPIN_LIMIT: u32 = 100_000;
+
+    /// Set the number of status register polls for each byte.
+    pub fn with_spin_limit(mut self, spin_limit: u32) -> Self {
+        self.spin_limit = spin_limit;
+        self
+    }
+
+    /// The UART was considered wedged and data is discarded.
+    #[inline]
+    pub fn wedged(&self) -> bool {
+        self.wedged
+    }
+
+    /// Write a byte with a bounded busy-wait. Returns [false] if the byte was discarded.
+    pub fn write_byte(&mut self, byte: u8) -> bool {
+        if self.wedged {
+            return false;
+        }
+        for _ in 0..self.spin_limit {
+            if self.regs.read_tx_status().ready() {
+                self.regs.write_data(Data::new_with_raw_value(byte as u32));
+                return true;
+            }
+        }
+        self.wedged = true;
+        false
+    }
+
+    /// Write all bytes with a bounded busy-wait for each byte. Returns the number of written
+    /// bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> usize {
+        bytes
+            .iter()
+            .take_while(|byte| self.write_byte(**byte))
+            .count()
+    }
+
+    /// Wait until all data was transmitted, with the same bound as for a single byte. Returns
+    /// [false] if the UART is wedged.
+    pub fn flush(&mut self) -> bool {
+        if self.wedged {
+            return false;
+        }
+        for _ in 0..self.spin_limit {
+            if !self.regs.read_tx_status().write_busy() {
+                return true;
+            }
+        }
+        self.wedged = true;
+        false
+    }
+}
+
+impl core::fmt::Write for PanicWriter {
+    /// Returns [core::fmt::Error] if the UART is wedged and data was discarded.
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if self.write_bytes(s.as_bytes()) != s.len() {
+            return Err(core::fmt::Error);
+        }
+        Ok(())
     }
 }
 
@@ -1066,17 +2458,21 @@ impl embedded_io::Write for Tx {
 pub struct RxWithInterrupt(Rx);
 
 impl RxWithInterrupt {
-    pub fn new(rx: Rx) -> Self {
+    /// Create a new interrupt based receiver.
+    ///
+    /// The receiver is enabled if it is disabled, for example because the UART was created
+    /// with [Config::enable_rx] set to [false]. Otherwise, the interrupts would never fire.
+    pub fn new(mut rx: Rx) -> Self {
+        if !rx.is_enabled() {
+            rx.enable();
+        }
         Self(rx)
     }
 
     /// This function should be called once at initialization time if the regular
     /// [Self::on_interrupt] is used to read the UART receiver to enable and start the receiver.
     pub fn start(&mut self) {
-        #[cfg(feature = "vor4x")]
-        self.enable_interrupts(true, true);
-        #[cfg(feature = "vor1x")]
-        self.enable_interrupts(true);
+        self.enable_interrupts(DRIVER_UNMASKS_NVIC, true);
         self.0.enable();
     }
 
@@ -1085,6 +2481,37 @@ impl RxWithInterrupt {
         &self.0
     }
 
+    /// Read the bytes which are currently available in the RX FIFO from thread context while the
+    /// interrupts stay configured. Returns the number of read bytes, which is 0 if the FIFO is
+    /// empty.
+    ///
+    /// This can be used to drain the FIFO during periods where interrupts are masked for a long
+    /// time. The FIFO is read inside a critical section, so the interrupt handler can not preempt
+    /// the read. Polled bytes are removed from the FIFO, so they simply do not appear in the
+    /// result of the next [Self::on_interrupt] or [Self::on_interrupt_max_size_or_timeout_based]
+    /// call. In particular, they are not counted for the maximum packet size of a transfer
+    /// started with [Self::read_fixed_len_or_timeout_based_using_irq].
+    ///
+    /// The RX FIFO interrupt is level based: If the FIFO is drained below the trigger level
+    /// (half-full), a pending RX FIFO interrupt is cleared as well, and the next interrupt only
+    /// occurs once enough new data was received. RX errors are not checked, they are reported by
+    /// the next interrupt handler call or can be checked with [Rx::poll_errors].
+    pub fn poll_read(&mut self, buf: &mut [u8]) -> usize {
+        critical_section::with(|_| {
+            let mut read = 0;
+            for byte in buf.iter_mut() {
+                match self.0.read_fifo() {
+                    Ok(word) => {
+                        *byte = self.0.data_byte(word);
+                        read += 1;
+                    }
+                    Err(nb::Error::WouldBlock) => break,
+                }
+            }
+            read
+        })
+    }
+
     /// This function is used together with the [Self::on_interrupt_max_size_or_timeout_based]
     /// function to read packets with a maximum size or variable sized packets by using the
     /// receive timeout of the hardware.
@@ -1102,16 +2529,14 @@ impl RxWithInterrupt {
         }
         context.mode = InterruptReceptionMode::Pending;
         context.rx_idx = 0;
+        context.timeouts = 0;
         self.start();
         Ok(())
     }
 
     #[inline]
-    fn enable_interrupts(&mut self, #[cfg(feature = "vor4x")] enable_in_nvic: bool, timeout: bool) {
-        #[cfg(feature = "vor4x")]
+    fn enable_interrupts(&mut self, enable_in_nvic: bool, timeout: bool) {
         self.0.enable_interrupts(enable_in_nvic, timeout);
-        #[cfg(feature = "vor1x")]
-        self.0.enable_interrupts(timeout);
     }
 
     #[inline]
@@ -1132,7 +2557,11 @@ impl RxWithInterrupt {
     /// This function will not disable the RX interrupts, so you don't need to call any other
     /// API after calling this function to continue emptying the FIFO. RX errors are handled
     /// as partial errors and are returned as part of the [InterruptResult].
-    pub fn on_interrupt(&mut self, buf: &mut [u8; 16]) -> InterruptResult {
+    ///
+    /// The buffer must be able to hold the content of a full RX FIFO, which is checked at compile
+    /// time.
+    pub fn on_interrupt<const N: usize>(&mut self, buf: &mut [u8; N]) -> InterruptResult {
+        const { assert!(N >= MAX_FIFO_DEPTH, "buffer smaller than the RX FIFO") };
         let mut result = InterruptResult::default();
 
         let irq_status = self.0.regs.read_irq_status();
@@ -1146,7 +2575,8 @@ impl RxWithInterrupt {
             // If this interrupt bit is set, the trigger level is available at the very least.
             // Read everything as fast as possible
             for _ in 0..available_bytes {
-                buf[result.bytes_read] = (self.0.read_fifo_unchecked() & 0xff) as u8;
+                let word = self.0.read_fifo_unchecked();
+                buf[result.bytes_read] = self.0.data_byte(word);
                 result.bytes_read += 1;
             }
         }
@@ -1155,7 +2585,7 @@ impl RxWithInterrupt {
         if irq_status.rx_timeout() {
             // While there is data in the FIFO, write it into the reception buffer
             while let Ok(byte) = self.0.read_fifo() {
-                buf[result.bytes_read] = byte as u8;
+                buf[result.bytes_read] = self.0.data_byte(byte);
                 result.bytes_read += 1;
             }
         }
@@ -1165,13 +2595,8 @@ impl RxWithInterrupt {
             self.check_for_errors(&mut result.errors);
         }
 
-        // Clear the interrupt status bits
-        self.0.regs.write_irq_clr(
-            InterruptClear::builder()
-                .with_rx_overrun(true)
-                .with_tx_overrun(false)
-                .build(),
-        );
+        // Clear the interrupt status bits which were set
+        clear_rx_overrun_interrupt(&mut self.0.regs, irq_status, &mut result.errors);
         result
     }
 
@@ -1181,8 +2606,10 @@ impl RxWithInterrupt {
     /// packet which are bounded by sufficient delays between them, triggering a hardware timeout.
     ///
     /// If either the maximum number of packets have been read or a timeout occured, the transfer
-    /// will be deemed completed. The state information of the transfer is tracked in the
-    /// [InterruptContextTimeoutOrMaxSize] structure.
+    /// will be deemed completed. The hardware timeout fires after 4 character times without
+    /// activity. [InterruptContextTimeoutOrMaxSize::new_with_idle_timeouts] can be used to
+    /// require multiple consecutive timeouts for longer idle times. The state information of the
+    /// transfer is tracked in the [InterruptContextTimeoutOrMaxSize] structure.
     ///
     /// If passed buffer is equal to or larger than the specified maximum length, an
     /// [BufferTooShortError] will be returned. Other RX errors are treated as partial errors
@@ -1219,16 +2646,39 @@ impl RxWithInterrupt {
             // If this interrupt bit is set, the trigger level is available at the very least.
             // Read everything as fast as possible
             for _ in 0..bytes_to_read {
-                buf[context.rx_idx] = (self.0.read_fifo_unchecked() & 0xff) as u8;
+                let word = self.0.read_fifo_unchecked();
+                buf[context.rx_idx] = self.0.data_byte(word);
                 context.rx_idx += 1;
             }
+            context.on_data();
 
             // On high-baudrates, data might be available immediately, and we possible have to
             // read continuosly? Then again, the CPU should always be faster than that. I'd rather
             // rely on the hardware firing another IRQ. I have not tried baudrates higher than
             // 115200 so far.
         }
-        // Timeout, empty the FIFO completely.
+        if irq_status.rx_timeout() {
+            let fifo_level = self.0.regs.read_state().rx_fifo().as_usize();
+            if !context.on_timeout(fifo_level) {
+                // Keep the last byte in the FIFO, so the timeout fires again without new data.
+                for _ in 0..fifo_level.saturating_sub(1) {
+                    if context.rx_idx == context.max_len {
+                        break;
+                    }
+                    let word = self.0.read_fifo_unchecked();
+                    buf[context.rx_idx] = self.0.data_byte(word);
+                    context.rx_idx += 1;
+                }
+                if context.rx_idx == context.max_len {
+                    self.irq_completion_handler_max_size_timeout(&mut result, context);
+                } else if rx_enabled {
+                    self.check_for_errors(&mut result.errors);
+                }
+                clear_rx_overrun_interrupt(&mut self.0.regs, irq_status, &mut result.errors);
+                return Ok(result);
+            }
+        }
+        // Timeout which completes the frame, empty the FIFO completely.
         if irq_status.rx_timeout() {
             // While there is data in the FIFO, write it into the reception buffer
             loop {
@@ -1245,6 +2695,7 @@ impl RxWithInterrupt {
                 }
             }
             self.irq_completion_handler_max_size_timeout(&mut result, context);
+            clear_rx_overrun_interrupt(&mut self.0.regs, irq_status, &mut result.errors);
             return Ok(result);
         }
 
@@ -1253,20 +2704,16 @@ impl RxWithInterrupt {
             self.check_for_errors(&mut result.errors);
         }
 
-        // Clear the interrupt status bits
-        self.0.regs.write_irq_clr(
-            InterruptClear::builder()
-                .with_rx_overrun(true)
-                .with_tx_overrun(false)
-                .build(),
-        );
+        // Clear the interrupt status bits which were set
+        clear_rx_overrun_interrupt(&mut self.0.regs, irq_status, &mut result.errors);
         Ok(result)
     }
 
     fn check_for_errors(&self, errors: &mut Option<UartErrors>) {
         let rx_status = self.0.regs.read_rx_status();
+        let parity_error = parity_error_reported(&self.0.regs, rx_status);
 
-        if rx_status.overrun_error() || rx_status.framing_error() || rx_status.parity_error() {
+        if rx_status.overrun_error() || rx_status.framing_error() || parity_error {
             let err = errors.get_or_insert(UartErrors::default());
 
             if rx_status.overrun_error() {
@@ -1275,7 +2722,7 @@ impl RxWithInterrupt {
             if rx_status.framing_error() {
                 err.framing = true;
             }
-            if rx_status.parity_error() {
+            if parity_error {
                 err.parity = true;
             }
         }
@@ -1304,3 +2751,380 @@ impl RxWithInterrupt {
         self.0
     }
 }
+
+impl From<Rx> for RxWithInterrupt {
+    #[inline]
+    fn from(rx: Rx) -> Self {
+        Self::new(rx)
+    }
+}
+
+impl embedded_hal_nb::serial::ErrorType for RxWithInterrupt {
+    type Error = Infallible;
+}
+
+/// Polled read with the interrupts still configured. See [RxWithInterrupt::poll_read] for the
+/// interaction with the interrupt handler.
+impl embedded_hal_nb::serial::Read<u8> for RxWithInterrupt {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        let mut byte = [0; 1];
+        match self.poll_read(&mut byte) {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(byte[0]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RX_STATUS: u32 = 1 << 1;
+    const TX_STATUS: u32 = 1 << 5;
+
+    fn status(raw: u32) -> regs::InterruptStatus {
+        regs::InterruptStatus::new_with_raw_value(raw)
+    }
+
+    const OVERRUN_ERROR: u32 = 1 << 4;
+    const FRAMING_ERROR: u32 = 1 << 5;
+
+    fn rx_status(raw: u32) -> regs::RxStatus {
+        regs::RxStatus::new_with_raw_value(raw)
+    }
+
+    #[test]
+    fn fifo_depth() {
+        assert_eq!(Bank::Uart0.fifo_depth(), MAX_FIFO_DEPTH);
+        assert_eq!(Bank::Uart1.fifo_depth(), MAX_FIFO_DEPTH);
+        #[cfg(feature = "vor4x")]
+        assert_eq!(Bank::Uart2.fifo_depth(), MAX_FIFO_DEPTH);
+        assert_eq!(<Uart0 as UartInstance>::FIFO_DEPTH, MAX_FIFO_DEPTH);
+        assert_eq!(<Uart1 as UartInstance>::FIFO_DEPTH, MAX_FIFO_DEPTH);
+    }
+
+    #[test]
+    fn single_timeout_completes_frame() {
+        let mut context = InterruptContextTimeoutOrMaxSize::new(16);
+        assert_eq!(context.idle_timeouts().get(), 1);
+        assert!(context.on_timeout(3));
+    }
+
+    #[test]
+    fn consecutive_timeouts_complete_frame() {
+        let mut context = InterruptContextTimeoutOrMaxSize::new_with_idle_timeouts(
+            16,
+            NonZeroU8::new(3).unwrap(),
+        );
+        // One byte is kept in the FIFO after each timeout which does not complete the frame.
+        assert!(!context.on_timeout(4));
+        assert!(!context.on_timeout(1));
+        assert!(context.on_timeout(1));
+
+        // New data after a timeout restarts the count.
+        context.reset();
+        assert!(!context.on_timeout(2));
+        assert!(!context.on_timeout(1));
+        assert!(!context.on_timeout(2));
+        assert!(!context.on_timeout(1));
+        assert!(context.on_timeout(1));
+
+        // Data received with the FIFO trigger interrupt restarts the count as well.
+        context.reset();
+        assert!(!context.on_timeout(2));
+        assert!(!context.on_timeout(1));
+        context.on_data();
+        assert!(!context.on_timeout(1));
+        assert!(!context.on_timeout(1));
+        assert!(context.on_timeout(1));
+    }
+
+    #[test]
+    fn clear_mask_empty_without_overrun() {
+        // RX FIFO trigger, RX timeout, TX FIFO trigger and TX empty interrupts.
+        let irq_status = status(0b0101_0101);
+        assert_eq!(InterruptClear::for_status(irq_status).raw_value(), 0);
+        assert_eq!(InterruptClear::rx_for_status(irq_status).raw_value(), 0);
+        assert_eq!(InterruptClear::tx_for_status(irq_status).raw_value(), 0);
+    }
+
+    #[test]
+    fn clear_mask_both_overruns() {
+        let clear = InterruptClear::for_status(status(RX_STATUS | TX_STATUS));
+        assert!(clear.rx_overrun());
+        assert!(clear.tx_overrun());
+    }
+
+    #[test]
+    fn rx_clear_mask_leaves_tx_overrun_pending() {
+        let clear = InterruptClear::rx_for_status(status(RX_STATUS | TX_STATUS));
+        assert!(clear.rx_overrun());
+        assert!(!clear.tx_overrun());
+        assert_eq!(
+            InterruptClear::rx_for_status(status(TX_STATUS)).raw_value(),
+            0
+        );
+    }
+
+    #[test]
+    fn tx_clear_mask_leaves_rx_overrun_pending() {
+        let clear = InterruptClear::tx_for_status(status(RX_STATUS | TX_STATUS));
+        assert!(!clear.rx_overrun());
+        assert!(clear.tx_overrun());
+        assert_eq!(
+            InterruptClear::tx_for_status(status(RX_STATUS)).raw_value(),
+            0
+        );
+    }
+
+    #[test]
+    fn cleared_rx_overrun_recorded_as_overflow() {
+        let mut errors = None;
+        record_cleared_rx_overrun(
+            InterruptClear::rx_for_status(status(RX_STATUS)),
+            rx_status(OVERRUN_ERROR),
+            &mut errors,
+        );
+        assert!(errors.unwrap().overflow());
+    }
+
+    #[test]
+    fn cleared_rx_overrun_recorded_with_other_errors() {
+        let mut errors = Some(UartErrors {
+            framing: true,
+            parity: true,
+            ..Default::default()
+        });
+        record_cleared_rx_overrun(
+            InterruptClear::rx_for_status(status(RX_STATUS)),
+            rx_status(OVERRUN_ERROR | FRAMING_ERROR),
+            &mut errors,
+        );
+        let errors = errors.unwrap();
+        assert!(errors.overflow());
+        assert!(errors.framing());
+        assert!(errors.parity());
+    }
+
+    #[test]
+    fn rx_status_interrupt_without_overrun_not_recorded() {
+        let mut errors = None;
+        record_cleared_rx_overrun(
+            InterruptClear::rx_for_status(status(RX_STATUS)),
+            rx_status(FRAMING_ERROR),
+            &mut errors,
+        );
+        assert!(errors.is_none());
+
+        let mut errors = Some(UartErrors {
+            framing: true,
+            ..Default::default()
+        });
+        record_cleared_rx_overrun(
+            InterruptClear::rx_for_status(status(RX_STATUS)),
+            rx_status(FRAMING_ERROR),
+            &mut errors,
+        );
+        assert!(!errors.unwrap().overflow());
+    }
+
+    #[test]
+    fn no_error_without_cleared_rx_overrun() {
+        let mut errors = None;
+        record_cleared_rx_overrun(
+            InterruptClear::rx_for_status(status(TX_STATUS)),
+            rx_status(OVERRUN_ERROR),
+            &mut errors,
+        );
+        assert!(errors.is_none());
+    }
+
+    #[test]
+    fn bits_per_character() {
+        for (wordsize, data_bits) in [
+            (WordSize::Five, 5),
+            (WordSize::Six, 6),
+            (WordSize::Seven, 7),
+            (WordSize::Eight, 8),
+        ] {
+            let config = Config::default().wordsize(wordsize);
+            assert_eq!(wordsize.data_bits(), data_bits);
+            assert_eq!(wordsize.data_mask() as u32, (1 << data_bits) - 1);
+            assert_eq!(config.bits_per_character(), data_bits + 2);
+            assert_eq!(config.parity_even().bits_per_character(), data_bits + 3);
+            assert_eq!(config.parity_odd().bits_per_character(), data_bits + 3);
+            assert_eq!(
+                config.stopbits(Stopbits::Two).bits_per_character(),
+                data_bits + 3
+            );
+            assert_eq!(
+                config
+                    .parity_even()
+                    .stopbits(Stopbits::Two)
+                    .bits_per_character(),
+                data_bits + 4
+            );
+        }
+        // 9-bit words: The parity bit is always part of the frame in manual parity mode.
+        assert_eq!(Config::default().parity_manual().bits_per_character(), 11);
+        assert_eq!(
+            Config::default()
+                .parity_none()
+                .parity_manual()
+                .stopbits(Stopbits::Two)
+                .bits_per_character(),
+            12
+        );
+    }
+
+    #[test]
+    fn character_time() {
+        // 8N1 at 115200 baud: 10 bits in 86.806 us.
+        assert_eq!(Config::default().character_time().ticks(), 86_806);
+        // 5N1 at 9600 baud: 7 bits in 729.167 us.
+        assert_eq!(
+            Config::from(Hertz::Hz(9600))
+                .wordsize(WordSize::Five)
+                .character_time()
+                .ticks(),
+            729_167
+        );
+        // 8E2 at 1 MBaud: 12 bits.
+        assert_eq!(
+            Config::from(Hertz::MHz(1))
+                .parity_even()
+                .stopbits(Stopbits::Two)
+                .character_time()
+                .ticks(),
+            12_000
+        );
+        assert_eq!(
+            Config::from(Hertz::Hz(1)).character_time().ticks(),
+            u32::MAX
+        );
+        assert_eq!(Config::from(Hertz::Hz(0)).character_time().ticks(), 0);
+    }
+
+    #[test]
+    fn idle_timeout() {
+        // 8N1 at 115200 baud: 86.806 us per character, rounded up.
+        assert_eq!(Config::default().idle_timeout(1).ticks(), 87);
+        assert_eq!(Config::default().idle_timeout(3).ticks(), 261);
+        assert_eq!(Config::default().idle_timeout(0).ticks(), 0);
+        // 8N1 at 1 MBaud: exactly 10 us per character.
+        assert_eq!(Config::from(Hertz::MHz(1)).idle_timeout(4).ticks(), 40);
+        // No overflow for the saturated character time.
+        assert_eq!(
+            Config::from(Hertz::Hz(1)).idle_timeout(u16::MAX).ticks(),
+            (u32::MAX as u64 * u16::MAX as u64).div_ceil(1000)
+        );
+    }
+
+    #[test]
+    fn characters_in_duration() {
+        let config = Config::from(Hertz::MHz(1));
+        assert_eq!(config.characters_in(Nanoseconds::micros(100)), 10);
+        // Rounded up.
+        assert_eq!(config.characters_in(Nanoseconds::micros(101)), 11);
+        assert_eq!(config.characters_in(Nanoseconds::nanos(1)), 1);
+        assert_eq!(config.characters_in(Nanoseconds::nanos(0)), 0);
+        let config = config.wordsize(WordSize::Seven).parity_odd();
+        assert_eq!(config.characters_in(Nanoseconds::micros(100)), 10);
+        assert_eq!(config.characters_in(Nanoseconds::micros(90)), 9);
+    }
+
+    fn clkscale_parts(config: Config, ref_clk: Hertz) -> (u32, u32) {
+        let clkscale = config.clkscale(ref_clk).unwrap();
+        (clkscale.int().value(), clkscale.frac().value() as u32)
+    }
+
+    #[test]
+    fn clkscale_16x_and_8x() {
+        let ref_clk: Hertz = 50.MHz();
+        // 50 MHz / (16 * 115200) = 27.127
+        assert_eq!(clkscale_parts(Config::default(), ref_clk), (27, 8));
+        // 50 MHz / (8 * 115200) = 54.253
+        assert_eq!(
+            clkscale_parts(Config::default().baud8(true), ref_clk),
+            (54, 16)
+        );
+        for ref_clk in [
+            10.MHz(),
+            12500.kHz(),
+            20.MHz(),
+            25.MHz(),
+            50.MHz(),
+            100.MHz(),
+        ] {
+            for baudrate in [9600.Hz(), 57_600.Hz(), 115_200.Hz(), 460_800.Hz()] {
+                let x16 = Config::from(baudrate)
+                    .clkscale(ref_clk)
+                    .unwrap()
+                    .raw_value();
+                let x8 = Config::from(baudrate)
+                    .baud8(true)
+                    .clkscale(ref_clk)
+                    .unwrap()
+                    .raw_value();
+                // The 8x divisor is twice the 16x divisor, apart from the rounding.
+                assert!(x8.abs_diff(2 * x16) <= 1, "{ref_clk} {baudrate}");
+                // The generated baudrate deviates by at most half a 1/64 divisor step.
+                for (multiplier, scaled) in [(16, x16), (8, x8)] {
+                    let exact = 64 * ref_clk.raw() as u64;
+                    let generated = baudrate.raw() as u64 * multiplier * scaled as u64;
+                    assert!(
+                        exact.abs_diff(generated) <= baudrate.raw() as u64 * multiplier / 2,
+                        "{ref_clk} {baudrate} {multiplier}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clkscale_fraction_carry() {
+        // 1 MHz / (16 * 15628) = 3.99923, the fractional part rounds up to 64 / 64.
+        assert_eq!(clkscale_parts(Config::from(15_628.Hz()), 1.MHz()), (4, 0));
+    }
+
+    #[test]
+    fn clkscale_too_low() {
+        let ref_clk: Hertz = 50.MHz();
+        let config = Config::from(12.Hz());
+        assert_eq!(config.min_baudrate(ref_clk), Hertz::from_raw(12));
+        assert!(config.validate(ref_clk).is_ok());
+        assert_eq!(
+            config.baudrate(11.Hz()).validate(ref_clk),
+            Err(UartConfigError::BaudrateTooLow {
+                ref_clk,
+                min: 12.Hz()
+            })
+        );
+        // The 8x mode doubles the divisor.
+        let config = config.baud8(true);
+        assert_eq!(config.min_baudrate(ref_clk), Hertz::from_raw(24));
+        assert!(config.baudrate(23.Hz()).validate(ref_clk).is_err());
+        assert!(config.baudrate(24.Hz()).validate(ref_clk).is_ok());
+    }
+
+    #[test]
+    fn baudrate_validation_boundaries() {
+        let ref_clk: Hertz = 50.MHz();
+        let max: Hertz = 3_125_000.Hz();
+        // 50 MHz / 16
+        assert_eq!(Config::default().max_baudrate(ref_clk), max);
+        assert!(Config::from(3_124_999.Hz()).validate(ref_clk).is_ok());
+        assert!(Config::from(3_125_000.Hz()).validate(ref_clk).is_ok());
+        assert_eq!(
+            Config::from(3_125_001.Hz()).validate(ref_clk),
+            Err(UartConfigError::BaudrateTooHigh { ref_clk, max })
+        );
+        let config = Config::from(6_250_000.Hz()).baud8(true);
+        assert!(config.validate(ref_clk).is_ok());
+        assert!(config.baudrate(6_250_001.Hz()).validate(ref_clk).is_err());
+        assert_eq!(
+            Config::from(0.Hz()).validate(ref_clk),
+            Err(UartConfigError::BaudrateZero)
+        );
+    }
+}
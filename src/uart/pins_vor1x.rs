@@ -1,5 +1,3 @@
-// UART A pins
-
 use crate::{
     FunctionSelect,
     pins::{
@@ -8,105 +6,39 @@ use crate::{
     },
 };
 
-use super::{Bank, RxPin, TxPin};
-
-impl TxPin for Pin<Pa9> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-impl RxPin for Pin<Pa8> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-
-impl TxPin for Pin<Pa17> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pa16> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pa31> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pa30> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pb9> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pb8> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-
-impl TxPin for Pin<Pb23> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pb22> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-
-// UART B pins
-
-impl TxPin for Pin<Pa3> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-impl RxPin for Pin<Pa2> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-
-impl TxPin for Pin<Pa19> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pa18> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pa27> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pa26> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pb7> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pb6> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-
-impl TxPin for Pin<Pb19> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-impl RxPin for Pin<Pb18> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-
-impl TxPin for Pin<Pb21> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pb20> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
+use super::{Bank, RxPin, TxPin, UartPinMapping};
+
+uart_pins!(
+    tx: [
+        // Uart0
+            (Pa9, Bank::Uart0, FunctionSelect::Sel2),
+        (Pa17, Bank::Uart0, FunctionSelect::Sel3),
+        (Pa31, Bank::Uart0, FunctionSelect::Sel3),
+        (Pb9, Bank::Uart0, FunctionSelect::Sel1),
+        (Pb23, Bank::Uart0, FunctionSelect::Sel1),
+
+        // Uart1
+        (Pa3, Bank::Uart1, FunctionSelect::Sel2),
+        (Pa19, Bank::Uart1, FunctionSelect::Sel3),
+        (Pa27, Bank::Uart1, FunctionSelect::Sel3),
+        (Pb7, Bank::Uart1, FunctionSelect::Sel1),
+        (Pb19, Bank::Uart1, FunctionSelect::Sel2),
+        (Pb21, Bank::Uart1, FunctionSelect::Sel1),
+    ],
+    rx: [
+        // Uart0
+        (Pa8, Bank::Uart0, FunctionSelect::Sel2),
+        (Pa16, Bank::Uart0, FunctionSelect::Sel3),
+        (Pa30, Bank::Uart0, FunctionSelect::Sel3),
+        (Pb8, Bank::Uart0, FunctionSelect::Sel1),
+        (Pb22, Bank::Uart0, FunctionSelect::Sel1),
+
+        // Uart1
+        (Pa2, Bank::Uart1, FunctionSelect::Sel2),
+        (Pa18, Bank::Uart1, FunctionSelect::Sel3),
+        (Pa26, Bank::Uart1, FunctionSelect::Sel3),
+        (Pb6, Bank::Uart1, FunctionSelect::Sel1),
+        (Pb18, Bank::Uart1, FunctionSelect::Sel2),
+        (Pb20, Bank::Uart1, FunctionSelect::Sel1),
+    ]
+);
@@ -6,93 +6,41 @@ use crate::{
     pins::{Pa2, Pa3, Pb14, Pb15, Pc4, Pc5, Pc14, Pd11, Pd12, Pe2, Pe3, Pf9, Pf12, Pf13, Pg0, Pg1},
 };
 
-use super::{Bank, RxPin, TxPin};
-
-// UART 0 pins
-
-impl TxPin for Pin<Pa2> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pa3> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pc4> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-impl RxPin for Pin<Pc5> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-
-impl TxPin for Pin<Pe2> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pe3> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pg0> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pg1> {
-    const BANK: Bank = Bank::Uart0;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-
-// UART 1 pins
-
-impl TxPin for Pin<Pb14> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pb15> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pd11> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-impl RxPin for Pin<Pd12> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel3;
-}
-
-impl TxPin for Pin<Pf12> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pf13> {
-    const BANK: Bank = Bank::Uart1;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-
-// UART 2 pins
-
-impl TxPin for Pin<Pc14> {
-    const BANK: Bank = Bank::Uart2;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-#[cfg(not(feature = "va41628"))]
-impl RxPin for Pin<Pc15> {
-    const BANK: Bank = Bank::Uart2;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel2;
-}
-
-#[cfg(not(feature = "va41628"))]
-impl TxPin for Pin<Pf8> {
-    const BANK: Bank = Bank::Uart2;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
-impl RxPin for Pin<Pf9> {
-    const BANK: Bank = Bank::Uart2;
-    const FUN_SEL: FunctionSelect = FunctionSelect::Sel1;
-}
+use super::{Bank, RxPin, TxPin, UartPinMapping};
+
+uart_pins!(
+    tx: [
+        // Uart0
+        (Pa2, Bank::Uart0, FunctionSelect::Sel3),
+        (Pc4, Bank::Uart0, FunctionSelect::Sel2),
+        (Pe2, Bank::Uart0, FunctionSelect::Sel3),
+        (Pg0, Bank::Uart0, FunctionSelect::Sel1),
+
+        // Uart1
+        (Pb14, Bank::Uart1, FunctionSelect::Sel3),
+        (Pd11, Bank::Uart1, FunctionSelect::Sel3),
+        (Pf12, Bank::Uart1, FunctionSelect::Sel1),
+
+        // Uart2
+        (Pc14, Bank::Uart2, FunctionSelect::Sel2),
+        #[cfg(not(feature = "va41628"))]
+        (Pf8, Bank::Uart2, FunctionSelect::Sel1),
+    ],
+    rx: [
+        // Uart0
+        (Pa3, Bank::Uart0, FunctionSelect::Sel3),
+        (Pc5, Bank::Uart0, FunctionSelect::Sel2),
+        (Pe3, Bank::Uart0, FunctionSelect::Sel3),
+        (Pg1, Bank::Uart0, FunctionSelect::Sel1),
+
+        // Uart1
+        (Pb15, Bank::Uart1, FunctionSelect::Sel3),
+        (Pd12, Bank::Uart1, FunctionSelect::Sel3),
+        (Pf13, Bank::Uart1, FunctionSelect::Sel1),
+
+        // Uart2
+        #[cfg(not(feature = "va41628"))]
+        (Pc15, Bank::Uart2, FunctionSelect::Sel2),
+        (Pf9, Bank::Uart2, FunctionSelect::Sel1),
+    ]
+);
@@ -1,5 +1,7 @@
 use core::marker::PhantomData;
 
+use crate::PeripheralSelect;
+
 use arbitrary_int::{u5, u6, u18};
 
 cfg_if::cfg_if! {
@@ -8,6 +10,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_0: usize = 0x4004_0000;
         /// UART B base address
         pub const BASE_ADDR_1: usize = 0x4004_1000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0112_07E1;
     } else if #[cfg(feature = "vor4x")] {
         /// UART 0 base address
         pub const BASE_ADDR_0: usize = 0x4002_4000;
@@ -15,6 +19,8 @@ cfg_if::cfg_if! {
         pub const BASE_ADDR_1: usize = 0x4002_5000;
         /// UART 2 base address
         pub const BASE_ADDR_2: usize = 0x4001_7000;
+        /// Expected value of the peripheral ID register.
+        pub const EXPECTED_PERID: u32 = 0x0212_07E9;
     }
 }
 
@@ -28,15 +34,68 @@ pub enum Bank {
 }
 
 impl Bank {
-    /// Unsafely steal the GPIO peripheral block for the given port.
+    /// Unsafely steal the UART peripheral block for the given bank.
+    ///
+    /// In debug builds, this panics if the bank is owned by a driver created with a safe
+    /// constructor, see [crate::ownership].
     ///
     /// # Safety
     ///
     /// Circumvents ownership and safety guarantees by the HAL.
+    #[track_caller]
     pub unsafe fn steal_regs(&self) -> MmioUart<'static> {
+        crate::ownership::check_steal(crate::ownership::RegisterBlock::Uart(*self));
+        Uart::new_mmio(*self)
+    }
+
+    /// Same as [Self::steal_regs], but without the ownership check. [super::UartIrqView] is a safe
+    /// alternative for interrupt handlers which only need the interrupt status.
+    ///
+    /// # Safety
+    ///
+    /// Circumvents ownership and safety guarantees by the HAL.
+    pub unsafe fn force_steal_regs(&self) -> MmioUart<'static> {
         Uart::new_mmio(*self)
     }
 
+    /// Peripheral select value used for the clock enable and reset registers.
+    pub const fn periph_sel(&self) -> PeripheralSelect {
+        match self {
+            Bank::Uart0 => PeripheralSelect::Uart0,
+            Bank::Uart1 => PeripheralSelect::Uart1,
+            #[cfg(feature = "vor4x")]
+            Bank::Uart2 => PeripheralSelect::Uart2,
+        }
+    }
+
+    /// Depth of the TX and RX FIFO in bytes. All UART peripherals of the supported families have
+    /// 16 byte deep FIFOs.
+    pub const fn fifo_depth(&self) -> usize {
+        match self {
+            Bank::Uart0 | Bank::Uart1 => 16,
+            #[cfg(feature = "vor4x")]
+            Bank::Uart2 => 16,
+        }
+    }
+
+    /// APB bus which clocks the UART. UART0 and UART1 are APB2 peripherals, UART2 is an APB1
+    /// peripheral.
+    #[cfg(feature = "vor4x")]
+    pub const fn apb_bus(&self) -> crate::clock::ApbBus {
+        match self {
+            Bank::Uart0 | Bank::Uart1 => crate::clock::ApbBus::Apb2,
+            Bank::Uart2 => crate::clock::ApbBus::Apb1,
+        }
+    }
+
+    /// Interrupt the UART was routed to with the IRQSEL peripheral by the HAL. The RX and TX
+    /// interrupts share this interrupt.
+    #[cfg(feature = "vor1x")]
+    #[inline]
+    pub fn routed_interrupt(&self) -> Option<va108xx::Interrupt> {
+        crate::irq_routing::uart_interrupt(*self)
+    }
+
     #[cfg(feature = "vor4x")]
     pub const fn interrupt_id_tx(&self) -> va416xx::Interrupt {
         match self {
@@ -90,6 +149,30 @@ pub enum WordSize {
     Eight = 0b11,
 }
 
+impl WordSize {
+    /// Number of data bits of one character.
+    #[inline]
+    pub const fn data_bits(&self) -> u32 {
+        match self {
+            WordSize::Five => 5,
+            WordSize::Six => 6,
+            WordSize::Seven => 7,
+            WordSize::Eight => 8,
+        }
+    }
+
+    /// Mask of the data bits of one character.
+    #[inline]
+    pub const fn data_mask(&self) -> u8 {
+        match self {
+            WordSize::Five => 0x1F,
+            WordSize::Six => 0x3F,
+            WordSize::Seven => 0x7F,
+            WordSize::Eight => 0xFF,
+        }
+    }
+}
+
 #[bitbybit::bitfield(u32, default = 0x0, debug, defmt_fields(feature = "defmt"))]
 pub struct Control {
     #[bit(11, rw)]
@@ -228,13 +311,43 @@ pub struct InterruptStatus {
 #[bitbybit::bitfield(u32, default = 0x0)]
 #[derive(Debug)]
 pub struct InterruptClear {
-    #[bit(1, w)]
+    #[bit(1, rw)]
     rx_overrun: bool,
     /// Not sure if this does anything, the programmer guides are not consistent on this..
-    #[bit(5, w)]
+    #[bit(5, rw)]
     tx_overrun: bool,
 }
 
+impl InterruptClear {
+    /// Clear mask which only acknowledges the overrun conditions signalled in the given
+    /// interrupt status.
+    #[inline]
+    pub fn for_status(status: InterruptStatus) -> Self {
+        InterruptClear::builder()
+            .with_rx_overrun(status.rx_status())
+            .with_tx_overrun(status.tx_status())
+            .build()
+    }
+
+    /// Clear mask which only acknowledges a RX overrun signalled in the given interrupt status.
+    #[inline]
+    pub fn rx_for_status(status: InterruptStatus) -> Self {
+        InterruptClear::builder()
+            .with_rx_overrun(status.rx_status())
+            .with_tx_overrun(false)
+            .build()
+    }
+
+    /// Clear mask which only acknowledges a TX overrun signalled in the given interrupt status.
+    #[inline]
+    pub fn tx_for_status(status: InterruptStatus) -> Self {
+        InterruptClear::builder()
+            .with_rx_overrun(false)
+            .with_tx_overrun(status.tx_status())
+            .build()
+    }
+}
+
 #[bitbybit::bitfield(u32)]
 #[derive(Debug)]
 pub struct FifoTrigger {
@@ -283,7 +396,9 @@ pub struct Uart {
     irq_clr: InterruptClear,
     rx_fifo_trigger: FifoTrigger,
     tx_fifo_trigger: FifoTrigger,
-    rx_fifo_rts_trigger: u32,
+    /// RTS is deasserted by the automatic RTS flow control if the RX FIFO count is larger than
+    /// or equal to this level.
+    rx_fifo_rts_trigger: FifoTrigger,
     #[mmio(PureRead)]
     state: State,
     _reserved: [u32; 0x3ED],
@@ -295,6 +410,7 @@ pub struct Uart {
 static_assertions::const_assert_eq!(core::mem::size_of::<Uart>(), 0x1000);
 
 impl Uart {
+    #[cfg(not(feature = "test-harness"))]
     fn new_mmio_at(base: usize) -> MmioUart<'static> {
         MmioUart {
             ptr: base as *mut _,
@@ -303,6 +419,12 @@ impl Uart {
     }
 
     pub fn new_mmio(bank: Bank) -> MmioUart<'static> {
+        #[cfg(feature = "test-harness")]
+        return MmioUart {
+            ptr: crate::test_harness::uart(bank).as_mut_ptr(),
+            phantom: PhantomData,
+        };
+        #[cfg(not(feature = "test-harness"))]
         match bank {
             Bank::Uart0 => Self::new_mmio_at(BASE_ADDR_0),
             Bank::Uart1 => Self::new_mmio_at(BASE_ADDR_1),
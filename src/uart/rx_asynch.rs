@@ -12,9 +12,25 @@
 //! The first two are used for the [RxAsync] struct, while the latter two are used with the
 //! [RxAsyncOverwriting] struct. The later two will overwrite old values in the used ring buffer.
 //!
-//! Error handling is performed in the user interrupt handler by checking the [AsyncUartErrors]
-//! structure returned by the interrupt handlers.
-use core::{cell::RefCell, convert::Infallible, future::Future, sync::atomic::Ordering};
+//! The interrupt handlers return the [AsyncUartErrors] detected during the interrupt. The
+//! [on_interrupt_rx] handler additionally records them for the [RxAsync] reader, which returns
+//! them as an [AsyncRxError] once all data which was received before the error was read. They
+//! can also be polled with [RxAsync::take_errors]. The [RxAsyncInfallible] wrapper ignores them.
+//!
+//! [RxAsync::read_exact] and [RxAsync::read_until] are cancel-safe building blocks for protocol
+//! parsing: if their future is dropped, no received data is lost.
+//!
+//! Like the blocking [Rx], the interrupt handlers mask the received bytes to the
+//! [super::WordSize] configured in the control register.
+//!
+//! The `uart-rx-ring-buffer` feature enables the [ring] module which provides a zero-copy
+//! alternative to the [heapless::spsc] queue based receivers.
+use core::{
+    cell::{Cell, RefCell},
+    convert::Infallible,
+    future::Future,
+    sync::atomic::Ordering,
+};
 
 use arbitrary_int::prelude::*;
 use critical_section::Mutex;
@@ -22,14 +38,31 @@ use embassy_sync::waitqueue::AtomicWaker;
 use embedded_io::ErrorType;
 use portable_atomic::AtomicBool;
 
+#[cfg(feature = "async-timeouts")]
+use crate::time::AsyncTimeoutError;
+
 use super::{
-    Bank, Rx, UartErrors,
-    regs::{InterruptClear, MmioUart},
+    Bank, DRIVER_UNMASKS_NVIC, Rx, UartErrors, UartIrqView, clear_rx_overrun_interrupt,
+    parity_error_reported,
+    regs::{InterruptStatus, MmioUart},
 };
 
+#[cfg(feature = "uart-rx-ring-buffer")]
+pub mod ring;
+
+/// Maximum length of a delimited frame read with [RxAsync::read_until], which is the capacity of
+/// the internal carry-over buffer of [RxAsync].
+pub const READ_UNTIL_MAX_LEN: usize = 128;
+
 static UART_RX_WAKERS: [AtomicWaker; 2] = [const { AtomicWaker::new() }; 2];
 static RX_READ_ACTIVE: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
 static RX_HAS_DATA: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
+/// Set by the interrupt handlers when the RX line was idle after a reception, which is
+/// signalled by the RX timeout interrupt.
+static RX_IDLE: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
+/// Errors recorded by [on_interrupt_rx] which were not reported to the [RxAsync] reader yet.
+static RX_ERRORS: [Mutex<Cell<PendingRxErrors>>; 2] =
+    [const { Mutex::new(Cell::new(PendingRxErrors::new())) }; 2];
 
 struct RxFuture {
     id: Bank,
@@ -50,14 +83,22 @@ impl Future for RxFuture {
         cx: &mut core::task::Context<'_>,
     ) -> core::task::Poll<Self::Output> {
         UART_RX_WAKERS[self.id as usize].register(cx.waker());
-        if RX_HAS_DATA[self.id as usize].load(Ordering::Relaxed) {
+        if RX_HAS_DATA[self.id as usize].load(Ordering::Relaxed)
+            || critical_section::with(|cs| {
+                RX_ERRORS[self.id as usize]
+                    .borrow(cs)
+                    .get()
+                    .errors
+                    .is_some()
+            })
+        {
             return core::task::Poll::Ready(Ok(()));
         }
         core::task::Poll::Pending
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct AsyncUartErrors {
     /// Queue has overflowed, data might have been lost.
@@ -66,9 +107,186 @@ pub struct AsyncUartErrors {
     pub uart_errors: UartErrors,
 }
 
+impl AsyncUartErrors {
+    fn merge(&mut self, other: AsyncUartErrors) {
+        self.queue_overflow |= other.queue_overflow;
+        self.uart_errors.overflow |= other.uart_errors.overflow;
+        self.uart_errors.framing |= other.uart_errors.framing;
+        self.uart_errors.parity |= other.uart_errors.parity;
+        self.uart_errors.other |= other.uart_errors.other;
+    }
+}
+
+/// Reception error returned by the [embedded_io_async::Read] implementation of [RxAsync].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error("UART reception error: {0:?}")]
+pub struct AsyncRxError(pub AsyncUartErrors);
+
+impl embedded_io_async::Error for AsyncRxError {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        let uart_errors = self.0.uart_errors;
+        if uart_errors.framing() || uart_errors.parity() {
+            return embedded_io_async::ErrorKind::InvalidData;
+        }
+        embedded_io_async::ErrorKind::Other
+    }
+}
+
+/// Error returned by [RxAsync::read_exact].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadExactError {
+    /// The requested length is larger than the queue and the carry-over buffer can hold, so the
+    /// read could never complete.
+    #[error("read length {0} exceeds the queue capacity")]
+    ExceedsCapacity(usize),
+    #[error("{0}")]
+    Rx(#[from] AsyncRxError),
+}
+
+/// Error returned by [RxAsync::read_until].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadUntilError {
+    /// The delimiter was not found within the maximum frame length. The examined bytes were
+    /// returned in the buffer anyway, the contained value is their number.
+    #[error("no delimiter found within {0} bytes")]
+    NoDelimiter(usize),
+    #[error("{0}")]
+    Rx(#[from] AsyncRxError),
+}
+
+/// Bytes which were taken from the queue by [RxAsync::read_until] to look for the delimiter, but
+/// were not returned yet. The queue consumer can only peek at its first element, so the bytes
+/// have to be moved here to scan them. All read functions of [RxAsync] return these bytes first.
+#[derive(Debug, Default)]
+struct CarryOver(heapless::Vec<u8, READ_UNTIL_MAX_LEN>);
+
+impl CarryOver {
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the length of the complete frame in the carry-over buffer, if there is one.
+    ///
+    /// The boolean is [true] if the frame ends with the delimiter, and [false] if no delimiter
+    /// was found in the first `max_len` bytes.
+    fn frame_len(&self, delim: u8, max_len: usize) -> Option<(usize, bool)> {
+        let scanned = &self.0[..self.0.len().min(max_len)];
+        if let Some(pos) = scanned.iter().position(|&byte| byte == delim) {
+            return Some((pos + 1, true));
+        }
+        if scanned.len() == max_len {
+            return Some((max_len, false));
+        }
+        None
+    }
+
+    /// Appends data provided by the passed function until the buffer contains `max_len` bytes.
+    /// The function receives the free space and returns the number of bytes it filled.
+    fn fill_with<E>(
+        &mut self,
+        max_len: usize,
+        fill: impl FnOnce(&mut [u8]) -> Result<usize, E>,
+    ) -> Result<usize, E> {
+        let old_len = self.0.len();
+        let max_len = max_len.min(self.0.capacity());
+        if old_len >= max_len {
+            return Ok(0);
+        }
+        // Can not fail, the length is limited to the capacity.
+        let _ = self.0.resize(max_len, 0);
+        let result = fill(&mut self.0[old_len..]);
+        let filled = *result.as_ref().unwrap_or(&0);
+        self.0.truncate(old_len + filled);
+        result
+    }
+
+    /// Moves bytes from the front of the carry-over buffer into the passed buffer. Returns the
+    /// number of moved bytes.
+    fn take_front(&mut self, buf: &mut [u8]) -> usize {
+        let taken = self.0.len().min(buf.len());
+        buf[..taken].copy_from_slice(&self.0[..taken]);
+        self.0.copy_within(taken.., 0);
+        self.0.truncate(self.0.len() - taken);
+        taken
+    }
+
+    fn clear(&mut self) -> usize {
+        let len = self.0.len();
+        self.0.clear();
+        len
+    }
+}
+
+/// Errors recorded by the interrupt handler which were not reported to the reader yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PendingRxErrors {
+    errors: Option<AsyncUartErrors>,
+    /// Number of queued bytes which were received before the first recorded error. These are
+    /// delivered to the reader before the error is reported.
+    clean_bytes: usize,
+}
+
+impl PendingRxErrors {
+    const fn new() -> Self {
+        Self {
+            errors: None,
+            clean_bytes: 0,
+        }
+    }
+
+    /// Record errors detected by the interrupt handler. `queued_before` is the number of bytes
+    /// which were queued before the interrupt handler read the data the errors belong to.
+    fn record(&mut self, errors: AsyncUartErrors, queued_before: usize) {
+        match &mut self.errors {
+            Some(pending) => pending.merge(errors),
+            None => {
+                self.errors = Some(errors);
+                self.clean_bytes = queued_before;
+            }
+        }
+    }
+
+    /// Number of bytes the reader may read before the pending errors have to be reported.
+    fn read_limit(&self, queued: usize) -> usize {
+        match self.errors {
+            Some(_) => self.clean_bytes.min(queued),
+            None => queued,
+        }
+    }
+
+    fn consumed(&mut self, bytes: usize) {
+        if self.errors.is_some() {
+            self.clean_bytes = self.clean_bytes.saturating_sub(bytes);
+        }
+    }
+
+    /// Returns the pending errors once all bytes received before them were consumed.
+    fn take_if_reached(&mut self) -> Option<AsyncUartErrors> {
+        if self.clean_bytes == 0 {
+            return self.take();
+        }
+        None
+    }
+
+    fn take(&mut self) -> Option<AsyncUartErrors> {
+        self.clean_bytes = 0;
+        self.errors.take()
+    }
+}
+
 fn on_interrupt_handle_rx_errors(uart: &mut MmioUart<'static>) -> Option<UartErrors> {
     let rx_status = uart.read_rx_status();
-    if rx_status.overrun_error() || rx_status.framing_error() || rx_status.parity_error() {
+    let parity_error = parity_error_reported(uart, rx_status);
+    if rx_status.overrun_error() || rx_status.framing_error() || parity_error {
         let mut errors_val = UartErrors::default();
 
         if rx_status.overrun_error() {
@@ -77,7 +295,7 @@ fn on_interrupt_handle_rx_errors(uart: &mut MmioUart<'static>) -> Option<UartErr
         if rx_status.framing_error() {
             errors_val.framing = true;
         }
-        if rx_status.parity_error() {
+        if parity_error {
             errors_val.parity = true;
         }
         return Some(errors_val);
@@ -87,11 +305,15 @@ fn on_interrupt_handle_rx_errors(uart: &mut MmioUart<'static>) -> Option<UartErr
 
 fn on_interrupt_rx_common_post_processing(
     id: Bank,
+    irq_status: InterruptStatus,
     rx_enabled: bool,
     read_some_data: bool,
 ) -> Option<UartErrors> {
+    let line_idle = irq_status.rx_timeout();
     let idx = id as usize;
     if read_some_data {
+        // Store this before waking, so that the reader sees a consistent state.
+        RX_IDLE[idx].store(line_idle, Ordering::Relaxed);
         RX_HAS_DATA[idx].store(true, Ordering::Relaxed);
         if RX_READ_ACTIVE[idx].load(Ordering::Relaxed) {
             UART_RX_WAKERS[idx].wake();
@@ -99,19 +321,15 @@ fn on_interrupt_rx_common_post_processing(
     }
 
     let mut errors = None;
-    let mut uart_regs = unsafe { id.steal_regs() };
+    // Safety: Only the RX status is read and the RX overrun interrupt is acknowledged.
+    let mut uart_regs = unsafe { id.force_steal_regs() };
     // Check for RX errors
     if rx_enabled {
         errors = on_interrupt_handle_rx_errors(&mut uart_regs);
     }
 
-    // Clear the interrupt status bits
-    uart_regs.write_irq_clr(
-        InterruptClear::builder()
-            .with_rx_overrun(true)
-            .with_tx_overrun(false)
-            .build(),
-    );
+    // Clear the interrupt status bits which were set
+    clear_rx_overrun_interrupt(&mut uart_regs, irq_status, &mut errors);
     errors
 }
 
@@ -133,21 +351,31 @@ pub fn on_interrupt_rx_async_heapless_queue_overwriting(
     prod: &mut heapless::spsc::Producer<u8>,
     shared_consumer: &Mutex<RefCell<Option<heapless::spsc::Consumer<'static, u8>>>>,
 ) -> Result<(), AsyncUartErrors> {
-    let uart_regs = unsafe { bank.steal_regs() };
-    let irq_status = uart_regs.read_irq_status();
-    let irq_enabled = uart_regs.read_irq_enabled();
+    let irq_view = UartIrqView::new(bank);
+    let irq_status = irq_view.irq_status();
+    let irq_enabled = irq_view.irq_enabled();
+    // Safety: Only the RX FIFO is drained, which is owned by the asynchronous reader.
+    let uart_regs = unsafe { bank.force_steal_regs() };
+    let data_mask = uart_regs.read_ctrl().wordsize().data_mask();
     let rx_enabled = irq_enabled.rx();
     let mut read_some_data = false;
     let mut queue_overflow = false;
 
     // Half-Full interrupt. We have a guaranteed amount of data we can read.
     if irq_status.rx() {
-        let available_bytes = uart_regs.read_rx_fifo_trigger().level().as_usize();
+        // Leave one byte in the FIFO. The RX timeout interrupt, which is also used to detect
+        // an idle line, relies on data being in the RX FIFO. If data continues arriving,
+        // another half-full IRQ will fire.
+        let available_bytes = uart_regs
+            .read_rx_fifo_trigger()
+            .level()
+            .as_usize()
+            .saturating_sub(1);
 
         // If this interrupt bit is set, the trigger level is available at the very least.
         // Read everything as fast as possible
         for _ in 0..available_bytes {
-            let byte = uart_regs.read_data().value();
+            let byte = uart_regs.read_data().value() & data_mask;
             if !prod.ready() {
                 queue_overflow = true;
                 critical_section::with(|cs| {
@@ -164,7 +392,7 @@ pub fn on_interrupt_rx_async_heapless_queue_overwriting(
     if irq_status.rx_timeout() {
         while uart_regs.read_rx_status().data_available() {
             // While there is data in the FIFO, write it into the reception buffer
-            let byte = uart_regs.read_data().value();
+            let byte = uart_regs.read_data().value() & data_mask;
             if !prod.ready() {
                 queue_overflow = true;
                 critical_section::with(|cs| {
@@ -177,7 +405,8 @@ pub fn on_interrupt_rx_async_heapless_queue_overwriting(
         read_some_data = true;
     }
 
-    let uart_errors = on_interrupt_rx_common_post_processing(bank, rx_enabled, read_some_data);
+    let uart_errors =
+        on_interrupt_rx_common_post_processing(bank, irq_status, rx_enabled, read_some_data);
     if uart_errors.is_some() || queue_overflow {
         return Err(AsyncUartErrors {
             queue_overflow,
@@ -189,7 +418,8 @@ pub fn on_interrupt_rx_async_heapless_queue_overwriting(
 
 /// Interrupt handler for asynchronous RX operations.
 ///
-/// Should be called in the user interrupt handler to enable asynchronous reception.
+/// Should be called in the user interrupt handler to enable asynchronous reception. The returned
+/// errors are also recorded for the [RxAsync] reader.
 pub fn on_interrupt_rx(
     bank: Bank,
     prod: &mut heapless::spsc::Producer<'_, u8>,
@@ -201,21 +431,32 @@ pub fn on_interrupt_rx_async_heapless_queue(
     bank: Bank,
     prod: &mut heapless::spsc::Producer<'_, u8>,
 ) -> Result<(), AsyncUartErrors> {
-    let uart_regs = unsafe { bank.steal_regs() };
-    let irq_status = uart_regs.read_irq_status();
-    let irq_enabled = uart_regs.read_irq_enabled();
+    let irq_view = UartIrqView::new(bank);
+    let irq_status = irq_view.irq_status();
+    let irq_enabled = irq_view.irq_enabled();
+    // Safety: Only the RX FIFO is drained, which is owned by the asynchronous reader.
+    let uart_regs = unsafe { bank.force_steal_regs() };
+    let data_mask = uart_regs.read_ctrl().wordsize().data_mask();
     let rx_enabled = irq_enabled.rx();
     let mut read_some_data = false;
     let mut queue_overflow = false;
+    let queued_before = prod.len();
 
     // Half-Full interrupt. We have a guaranteed amount of data we can read.
     if irq_status.rx() {
-        let available_bytes = uart_regs.read_rx_fifo_trigger().level().as_usize();
+        // Leave one byte in the FIFO. The RX timeout interrupt, which is also used to detect
+        // an idle line, relies on data being in the RX FIFO. If data continues arriving,
+        // another half-full IRQ will fire.
+        let available_bytes = uart_regs
+            .read_rx_fifo_trigger()
+            .level()
+            .as_usize()
+            .saturating_sub(1);
 
         // If this interrupt bit is set, the trigger level is available at the very least.
         // Read everything as fast as possible
         for _ in 0..available_bytes {
-            let byte = uart_regs.read_data().value();
+            let byte = uart_regs.read_data().value() & data_mask;
             if !prod.ready() {
                 queue_overflow = true;
             }
@@ -228,7 +469,7 @@ pub fn on_interrupt_rx_async_heapless_queue(
     if irq_status.rx_timeout() {
         while uart_regs.read_rx_status().data_available() {
             // While there is data in the FIFO, write it into the reception buffer
-            let byte = uart_regs.read_data().value();
+            let byte = uart_regs.read_data().value() & data_mask;
             if !prod.ready() {
                 queue_overflow = true;
             }
@@ -237,16 +478,97 @@ pub fn on_interrupt_rx_async_heapless_queue(
         read_some_data = true;
     }
 
-    let uart_errors = on_interrupt_rx_common_post_processing(bank, rx_enabled, read_some_data);
+    let uart_errors =
+        on_interrupt_rx_common_post_processing(bank, irq_status, rx_enabled, read_some_data);
     if uart_errors.is_some() || queue_overflow {
-        return Err(AsyncUartErrors {
+        let errors = AsyncUartErrors {
             queue_overflow,
             uart_errors: uart_errors.unwrap_or_default(),
+        };
+        let idx = bank as usize;
+        critical_section::with(|cs| {
+            let pending = RX_ERRORS[idx].borrow(cs);
+            let mut value = pending.get();
+            value.record(errors, queued_before);
+            pending.set(value);
         });
+        if RX_READ_ACTIVE[idx].load(Ordering::Relaxed) {
+            UART_RX_WAKERS[idx].wake();
+        }
+        return Err(errors);
     }
     Ok(())
 }
 
+/// Moves as much data as possible from the queue into the buffer. Returns the number of read
+/// bytes.
+fn read_from_queue(consumer: &mut heapless::spsc::Consumer<'_, u8>, buf: &mut [u8]) -> usize {
+    let data_to_read = consumer.len().min(buf.len());
+    for byte in buf.iter_mut().take(data_to_read) {
+        // We own the consumer and we checked that the amount of data is guaranteed to be available.
+        *byte = unsafe { consumer.dequeue_unchecked() };
+    }
+    data_to_read
+}
+
+/// Drains the queue and resets the data and idle flags of the receiver. Returns the number of
+/// discarded bytes.
+///
+/// This must be called inside a critical section, so the interrupt handler can not enqueue new
+/// data and set the flags between draining the queue and resetting the flags.
+fn drain_queue_and_reset_flags(
+    consumer: &mut heapless::spsc::Consumer<'_, u8>,
+    has_data: &AtomicBool,
+    idle: &AtomicBool,
+) -> usize {
+    let mut discarded = 0;
+    while consumer.dequeue().is_some() {
+        discarded += 1;
+    }
+    has_data.store(false, Ordering::Relaxed);
+    idle.store(false, Ordering::Relaxed);
+    discarded
+}
+
+/// Moves data from the queue into the buffer like [read_from_queue], but stops at the position
+/// of errors recorded by the interrupt handler. Returns the errors if they were reached before any
+/// data was read.
+fn read_from_queue_until_error(
+    consumer: &mut heapless::spsc::Consumer<'_, u8>,
+    idx: usize,
+    buf: &mut [u8],
+) -> Result<usize, AsyncRxError> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    critical_section::with(|cs| {
+        let pending = RX_ERRORS[idx].borrow(cs);
+        let mut value = pending.get();
+        let limit = value.read_limit(consumer.len()).min(buf.len());
+        let result = if limit == 0 {
+            match value.take_if_reached() {
+                Some(errors) => Err(AsyncRxError(errors)),
+                None => Ok(0),
+            }
+        } else {
+            Ok(read_from_queue(consumer, &mut buf[..limit]))
+        };
+        value.consumed(limit);
+        pending.set(value);
+        result
+    })
+}
+
+/// Accounts for data which was read from the queue without checking for errors.
+fn consumed_without_error_check(idx: usize, bytes: usize) {
+    critical_section::with(|cs| {
+        let pending = RX_ERRORS[idx].borrow(cs);
+        let mut value = pending.get();
+        value.consumed(bytes);
+        pending.set(value);
+    });
+}
+
 struct ActiveReadGuard(usize);
 
 impl Drop for ActiveReadGuard {
@@ -258,16 +580,20 @@ impl Drop for ActiveReadGuard {
 struct RxAsyncInner {
     rx: Rx,
     pub queue: heapless::spsc::Consumer<'static, u8>,
+    carry_over: CarryOver,
 }
 
 /// Core data structure to allow asynchronous UART reception.
 ///
 /// If the ring buffer becomes full, data will be lost.
+///
+/// Errors detected by the interrupt handler [on_interrupt_rx] are returned as an [AsyncRxError]
+/// by [embedded_io_async::Read::read] after all data which was received before the errors was
+/// read. Use [RxAsyncInfallible] to ignore errors.
 pub struct RxAsync(Option<RxAsyncInner>);
 
 impl ErrorType for RxAsync {
-    /// Error reporting is done using the result of the interrupt functions.
-    type Error = Infallible;
+    type Error = AsyncRxError;
 }
 
 fn stop_async_rx(rx: &mut Rx) {
@@ -280,26 +606,294 @@ impl RxAsync {
     /// Create a new asynchronous receiver.
     ///
     /// The passed [heapless::spsc::Consumer] will be used to asynchronously receive data which
-    /// is filled by the interrupt handler [on_interrupt_rx].
+    /// is filled by the interrupt handler [on_interrupt_rx]. The receiver is always enabled,
+    /// even if the UART was created with [super::Config::enable_rx] set to [false].
     pub fn new(mut rx: Rx, queue: heapless::spsc::Consumer<'static, u8>) -> Self {
         rx.disable_interrupts();
         rx.disable();
         rx.clear_fifo();
         // Enable those together.
-        critical_section::with(|_| {
-            #[cfg(feature = "vor1x")]
-            rx.enable_interrupts(true);
-            #[cfg(feature = "vor4x")]
-            rx.enable_interrupts(true, true);
+        critical_section::with(|cs| {
+            // Discard errors of a previous receiver.
+            RX_ERRORS[rx.id as usize]
+                .borrow(cs)
+                .set(PendingRxErrors::new());
+            rx.enable_interrupts(DRIVER_UNMASKS_NVIC, true);
             rx.enable();
         });
-        Self(Some(RxAsyncInner { rx, queue }))
+        Self(Some(RxAsyncInner {
+            rx,
+            queue,
+            carry_over: CarryOver::default(),
+        }))
+    }
+
+    /// Convert into a receiver which ignores reception errors.
+    pub fn into_infallible(self) -> RxAsyncInfallible {
+        RxAsyncInfallible(self)
+    }
+
+    /// Returns and clears the errors recorded by the interrupt handler [on_interrupt_rx] which
+    /// were not reported by [embedded_io_async::Read::read] yet.
+    ///
+    /// After this call, [embedded_io_async::Read::read] returns the queued data without
+    /// reporting these errors.
+    pub fn take_errors(&mut self) -> Option<AsyncUartErrors> {
+        let idx = self.0.as_ref().unwrap().rx.id as usize;
+        critical_section::with(|cs| {
+            let pending = RX_ERRORS[idx].borrow(cs);
+            let mut value = pending.get();
+            let errors = value.take();
+            pending.set(value);
+            errors
+        })
     }
 
     pub fn stop(&mut self) {
         stop_async_rx(&mut self.0.as_mut().unwrap().rx);
     }
 
+    /// Number of bytes which are currently queued, including the bytes which were examined by
+    /// [Self::read_until] but not returned yet.
+    ///
+    /// The interrupt handler might enqueue more data at any time, so this is only a lower bound
+    /// for the number of bytes which can be read.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let inner = self.0.as_ref().unwrap();
+        inner.carry_over.len() + inner.queue.len()
+    }
+
+    /// Checks whether no data is queued.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        let inner = self.0.as_ref().unwrap();
+        inner.carry_over.is_empty() && inner.queue.is_empty()
+    }
+
+    /// Maximum number of bytes the queue can hold. This does not include the carry-over buffer
+    /// used by [Self::read_until].
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.as_ref().unwrap().queue.capacity()
+    }
+
+    /// Discard all queued data, for example after a protocol resynchronization, while the
+    /// reception continues. Returns the number of discarded bytes.
+    ///
+    /// The queue is drained and the internal data and idle flags are reset inside a critical
+    /// section, so the interrupt handler [on_interrupt_rx] can not enqueue data in between. All
+    /// data received after this call is queued normally. Data which is still in the hardware
+    /// FIFO is not discarded.
+    pub fn clear(&mut self) -> usize {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        let discarded = critical_section::with(|_| {
+            drain_queue_and_reset_flags(&mut inner.queue, &RX_HAS_DATA[idx], &RX_IDLE[idx])
+        });
+        consumed_without_error_check(idx, discarded);
+        discarded + inner.carry_over.clear()
+    }
+
+    /// Discard all received data and the recorded errors, for example before a new request of
+    /// a request-response protocol. Returns the number of discarded queued bytes.
+    ///
+    /// In contrast to [Self::clear], the hardware RX FIFO is cleared as well. The FIFO and the
+    /// queue are cleared inside a critical section, so the interrupt handler [on_interrupt_rx]
+    /// can not move stale data from the FIFO to the queue in between.
+    pub fn discard_stale(&mut self) -> usize {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        let discarded = critical_section::with(|cs| {
+            inner.rx.clear_fifo();
+            RX_ERRORS[idx].borrow(cs).set(PendingRxErrors::new());
+            drain_queue_and_reset_flags(&mut inner.queue, &RX_HAS_DATA[idx], &RX_IDLE[idx])
+        });
+        discarded + inner.carry_over.clear()
+    }
+
+    /// Non-blocking read of the queued data, which can be used from a non-async context.
+    ///
+    /// Returns the number of read bytes, which is 0 if no data is queued. Recorded errors are
+    /// not reported, use [Self::take_errors] to poll them.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> usize {
+        let inner = self.0.as_mut().unwrap();
+        let carried = inner.carry_over.take_front(buf);
+        let read = read_from_queue(&mut inner.queue, &mut buf[carried..]);
+        consumed_without_error_check(inner.rx.id as usize, read);
+        carried + read
+    }
+
+    /// Read exactly enough data to fill the buffer.
+    ///
+    /// This function is cancel-safe: The data is only removed from the queue once all of it is
+    /// available, so no data is lost if the future is dropped before completion. The buffer
+    /// length must therefore not exceed the queue [capacity][Self::capacity], otherwise
+    /// [ReadExactError::ExceedsCapacity] is returned immediately.
+    ///
+    /// If the interrupt handler [on_interrupt_rx] recorded errors for data within the requested
+    /// range, the errors are returned and cleared and the data stays queued, so the caller can
+    /// decide whether to resynchronize with [Self::clear] or to read the data anyway.
+    pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ReadExactError> {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        if buf.len() > inner.carry_over.len() + inner.queue.capacity() {
+            return Err(ReadExactError::ExceedsCapacity(buf.len()));
+        }
+        let _guard = ActiveReadGuard(idx);
+        loop {
+            if inner.queue.is_empty() {
+                RX_HAS_DATA[idx].store(false, Ordering::Relaxed);
+            }
+            let fut = RxFuture::new(&mut inner.rx);
+            let done = critical_section::with(|cs| {
+                let pending = RX_ERRORS[idx].borrow(cs);
+                let mut value = pending.get();
+                let needed = buf.len().saturating_sub(inner.carry_over.len());
+                if value.read_limit(inner.queue.len()) >= needed {
+                    let carried = inner.carry_over.take_front(buf);
+                    read_from_queue(&mut inner.queue, &mut buf[carried..]);
+                    value.consumed(needed);
+                    pending.set(value);
+                    return Some(Ok(()));
+                }
+                if let Some(errors) = value.take() {
+                    pending.set(value);
+                    return Some(Err(ReadExactError::Rx(AsyncRxError(errors))));
+                }
+                None
+            });
+            if let Some(result) = done {
+                return result;
+            }
+            let _ = fut.await;
+        }
+    }
+
+    /// Read a frame which is terminated by the given delimiter, for example a line terminated by
+    /// `b'\n'`. Returns the frame length including the delimiter.
+    ///
+    /// The maximum frame length is the buffer length, limited to [READ_UNTIL_MAX_LEN]. If no
+    /// delimiter is found within the maximum frame length, the examined bytes are returned in the
+    /// buffer with [ReadUntilError::NoDelimiter], so the caller can resynchronize.
+    ///
+    /// This function is cancel-safe: The examined bytes are kept in an internal carry-over buffer
+    /// until the frame is complete, so no data is lost if the future is dropped before
+    /// completion. The carry-over buffer is returned first by all other read functions.
+    ///
+    /// Errors recorded by the interrupt handler [on_interrupt_rx] are returned once all bytes
+    /// received before them were examined, like for [embedded_io_async::Read::read]. The
+    /// examined bytes stay in the carry-over buffer.
+    pub async fn read_until(&mut self, delim: u8, buf: &mut [u8]) -> Result<usize, ReadUntilError> {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        let max_len = buf.len().min(READ_UNTIL_MAX_LEN);
+        if max_len == 0 {
+            return Ok(0);
+        }
+        let _guard = ActiveReadGuard(idx);
+        loop {
+            if let Some((len, delimited)) = inner.carry_over.frame_len(delim, max_len) {
+                inner.carry_over.take_front(&mut buf[..len]);
+                if !delimited {
+                    return Err(ReadUntilError::NoDelimiter(len));
+                }
+                return Ok(len);
+            }
+            if inner.queue.is_empty() {
+                RX_HAS_DATA[idx].store(false, Ordering::Relaxed);
+            }
+            let fut = RxFuture::new(&mut inner.rx);
+            let moved = inner.carry_over.fill_with(max_len, |spare| {
+                read_from_queue_until_error(&mut inner.queue, idx, spare)
+            })?;
+            if moved == 0 {
+                let _ = fut.await;
+            }
+        }
+    }
+
+    /// Read data until the RX line becomes idle or the buffer is full.
+    ///
+    /// Unlike [embedded_io_async::Read::read], this function keeps accumulating data into the
+    /// buffer across multiple interrupts. It only returns after the hardware RX timeout
+    /// interrupt signalled an idle line with no new data received since, or when the
+    /// buffer is full. The idle threshold is the hardware RX timeout of 4 character times, so
+    /// gaps between characters shorter than that do not terminate the reception.
+    ///
+    /// Returns the number of bytes read. The interrupt handler [on_interrupt_rx] must be used
+    /// for this to work. Recorded errors are not reported, use [Self::take_errors] to poll them.
+    pub async fn read_until_idle(&mut self, buf: &mut [u8]) -> usize {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        let _guard = ActiveReadGuard(idx);
+        let mut read = inner.carry_over.take_front(buf);
+        // Discard a stale idle condition of a previous reception.
+        critical_section::with(|_| {
+            if inner.queue.is_empty() {
+                RX_IDLE[idx].store(false, Ordering::Relaxed);
+            }
+        });
+        while read < buf.len() {
+            let fut = RxFuture::new(&mut inner.rx);
+            let data_to_read = inner.queue.len().min(buf.len() - read);
+            for byte in buf[read..read + data_to_read].iter_mut() {
+                // We own the consumer and we checked that the amount of data is guaranteed to be available.
+                *byte = unsafe { inner.queue.dequeue_unchecked() };
+            }
+            read += data_to_read;
+            consumed_without_error_check(idx, data_to_read);
+            if read == buf.len() {
+                break;
+            }
+            // The interrupt handler might run between reading the queue and checking the
+            // flags, so this needs to happen atomically.
+            let idle = critical_section::with(|_| {
+                if !inner.queue.is_empty() {
+                    return false;
+                }
+                if read > 0 && RX_IDLE[idx].load(Ordering::Relaxed) {
+                    return true;
+                }
+                RX_HAS_DATA[idx].store(false, Ordering::Relaxed);
+                false
+            });
+            if idle {
+                break;
+            }
+            if inner.queue.is_empty() {
+                let _ = fut.await;
+            }
+        }
+        read
+    }
+
+    /// [embedded_io_async::Read::read] with an upper bound for the waiting time, using the
+    /// embassy time driver.
+    ///
+    /// The read is cancel-safe, so no data is lost if the timeout expires. The reception
+    /// continues and data which is received after the timeout is queued normally, so the next
+    /// read can be started immediately.
+    #[cfg(feature = "async-timeouts")]
+    pub async fn read_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: embassy_time::Duration,
+    ) -> Result<usize, AsyncTimeoutError<AsyncRxError>> {
+        let idx = self.0.as_ref().unwrap().rx.id as usize;
+        let result =
+            crate::time::with_timeout(timeout, embedded_io_async::Read::read(self, buf)).await;
+        // The dropped read future must not leave the reader marked as active, otherwise the
+        // interrupt handler wakes a stale waker.
+        debug_assert!(!RX_READ_ACTIVE[idx].load(Ordering::Relaxed));
+        match result {
+            Ok(read) => read.map_err(AsyncTimeoutError::Operation),
+            Err(_) => Err(AsyncTimeoutError::Timeout),
+        }
+    }
+
+    /// Stop the reception and release the receiver and the queue consumer. Bytes in the
+    /// carry-over buffer of [Self::read_until] are discarded.
     pub fn release(mut self) -> (Rx, heapless::spsc::Consumer<'static, u8>) {
         self.stop();
         let inner = self.0.take().unwrap();
@@ -314,32 +908,73 @@ impl Drop for RxAsync {
 }
 
 impl embedded_io_async::Read for RxAsync {
+    /// Read the queued data or wait for new data.
+    ///
+    /// If the interrupt handler recorded errors, the data which was received before the errors
+    /// is returned first, and the errors are returned by the following call.
     async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
         let inner = self.0.as_ref().unwrap();
+        let idx = inner.rx.id as usize;
         // Need to wait for the IRQ to read data and set this flag. If the queue is not
         // empty, we can read data immediately.
         if inner.queue.is_empty() {
-            RX_HAS_DATA[inner.rx.id as usize].store(false, Ordering::Relaxed);
+            RX_HAS_DATA[idx].store(false, Ordering::Relaxed);
         }
-        let _guard = ActiveReadGuard(inner.rx.id as usize);
-        let mut handle_data_in_queue = |consumer: &mut heapless::spsc::Consumer<'static, u8>| {
-            let data_to_read = consumer.len().min(buf.len());
-            for byte in buf.iter_mut().take(data_to_read) {
-                // We own the consumer and we checked that the amount of data is guaranteed to be available.
-                *byte = unsafe { consumer.dequeue_unchecked() };
-            }
-            data_to_read
-        };
+        let _guard = ActiveReadGuard(idx);
         let mut_ref = self.0.as_mut().unwrap();
+        // Bytes examined by read_until are returned first. They were received before any
+        // pending errors.
+        if !mut_ref.carry_over.is_empty() {
+            return Ok(mut_ref.carry_over.take_front(buf));
+        }
         let fut = RxFuture::new(&mut mut_ref.rx);
-        // Data is available, so read that data immediately.
-        let read_data = handle_data_in_queue(&mut mut_ref.queue);
+        // Data or errors are available, so return them immediately.
+        let read_data = read_from_queue_until_error(&mut mut_ref.queue, idx, buf)?;
         if read_data > 0 {
             return Ok(read_data);
         }
         // Await data.
         let _ = fut.await;
-        Ok(handle_data_in_queue(&mut mut_ref.queue))
+        read_from_queue_until_error(&mut mut_ref.queue, idx, buf)
+    }
+}
+
+/// Asynchronous receiver which ignores the errors recorded by the interrupt handler, which was
+/// the behaviour of [RxAsync] before it reported errors.
+///
+/// Errors are still returned by the interrupt handler [on_interrupt_rx].
+pub struct RxAsyncInfallible(RxAsync);
+
+impl RxAsyncInfallible {
+    /// Create a new asynchronous receiver, see [RxAsync::new].
+    pub fn new(rx: Rx, queue: heapless::spsc::Consumer<'static, u8>) -> Self {
+        Self(RxAsync::new(rx, queue))
+    }
+
+    /// Access to the underlying receiver.
+    #[inline]
+    pub fn inner(&mut self) -> &mut RxAsync {
+        &mut self.0
+    }
+
+    pub fn release(self) -> (Rx, heapless::spsc::Consumer<'static, u8>) {
+        self.0.release()
+    }
+}
+
+impl ErrorType for RxAsyncInfallible {
+    type Error = Infallible;
+}
+
+impl embedded_io_async::Read for RxAsyncInfallible {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            // Errors are cleared when they are returned, so the next call continues with the
+            // queued data.
+            if let Ok(read) = embedded_io_async::Read::read(&mut self.0, buf).await {
+                return Ok(read);
+            }
+        }
     }
 }
 
@@ -364,7 +999,8 @@ impl RxAsyncOverwriting {
     ///
     /// The passed shared [heapless::spsc::Consumer] will be used to asynchronously receive data
     /// which is filled by the interrupt handler. The shared property allows using it in the
-    /// interrupt handler to overwrite old data.
+    /// interrupt handler to overwrite old data. The receiver is always enabled, even if the UART
+    /// was created with [super::Config::enable_rx] set to [false].
     pub fn new(
         mut rx: Rx,
         shared_consumer: &'static Mutex<RefCell<Option<heapless::spsc::Consumer<'static, u8>>>>,
@@ -374,10 +1010,7 @@ impl RxAsyncOverwriting {
         rx.clear_fifo();
         // Enable those together.
         critical_section::with(|_| {
-            #[cfg(feature = "vor4x")]
-            rx.enable_interrupts(true, true);
-            #[cfg(feature = "vor1x")]
-            rx.enable_interrupts(true);
+            rx.enable_interrupts(DRIVER_UNMASKS_NVIC, true);
             rx.enable();
         });
         Self(Some(RxAsyncOverwritingInner {
@@ -390,6 +1023,57 @@ impl RxAsyncOverwriting {
         stop_async_rx(&mut self.0.as_mut().unwrap().rx);
     }
 
+    /// Number of bytes which are currently queued.
+    ///
+    /// The interrupt handler might enqueue more data or overwrite old data at any time.
+    pub fn len(&self) -> usize {
+        self.with_consumer(|consumer| consumer.len())
+    }
+
+    /// Checks whether no data is queued.
+    pub fn is_empty(&self) -> bool {
+        self.with_consumer(|consumer| consumer.is_empty())
+    }
+
+    /// Maximum number of bytes the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.with_consumer(|consumer| consumer.capacity())
+    }
+
+    /// Discard all queued data, for example after a protocol resynchronization, while the
+    /// reception continues. Returns the number of discarded bytes.
+    ///
+    /// The shared consumer is locked with its critical section mutex, which is also used by the
+    /// interrupt handler [on_interrupt_rx_overwriting] to overwrite old data. The queue is
+    /// drained and the internal data and idle flags are reset inside this critical section, so
+    /// the interrupt handler can not enqueue data in between. Data which is still in the
+    /// hardware FIFO is not discarded.
+    pub fn clear(&mut self) -> usize {
+        let idx = self.0.as_ref().unwrap().rx.id as usize;
+        self.with_consumer(|consumer| {
+            drain_queue_and_reset_flags(consumer, &RX_HAS_DATA[idx], &RX_IDLE[idx])
+        })
+    }
+
+    /// Non-blocking read of the queued data, which can be used from a non-async context.
+    ///
+    /// Returns the number of read bytes, which is 0 if no data is queued. The shared consumer is
+    /// locked with its critical section mutex while reading.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> usize {
+        self.with_consumer(|consumer| read_from_queue(consumer, buf))
+    }
+
+    fn with_consumer<R>(
+        &self,
+        f: impl FnOnce(&mut heapless::spsc::Consumer<'static, u8>) -> R,
+    ) -> R {
+        let inner = self.0.as_ref().unwrap();
+        critical_section::with(|cs| {
+            let mut consumer_ref = inner.shared_consumer.borrow(cs).borrow_mut();
+            f(consumer_ref.as_mut().unwrap())
+        })
+    }
+
     pub fn release(mut self) -> Rx {
         self.stop();
         let inner = self.0.take().unwrap();
@@ -417,27 +1101,233 @@ impl embedded_io_async::Read for RxAsyncOverwriting {
             }
         });
         let _guard = ActiveReadGuard(id);
-        let mut handle_data_in_queue = |inner: &mut RxAsyncOverwritingInner| {
-            critical_section::with(|cs| {
-                let mut consumer_ref = inner.shared_consumer.borrow(cs).borrow_mut();
-                let consumer = consumer_ref.as_mut().unwrap();
-                let data_to_read = consumer.len().min(buf.len());
-                for byte in buf.iter_mut().take(data_to_read) {
-                    // We own the consumer and we checked that the amount of data is guaranteed to be available.
-                    *byte = unsafe { consumer.dequeue_unchecked() };
-                }
-                data_to_read
-            })
-        };
         let fut = RxFuture::new(&mut self.0.as_mut().unwrap().rx);
         // Data is available, so read that data immediately.
-        let read_data = handle_data_in_queue(self.0.as_mut().unwrap());
+        let read_data = self.try_read(buf);
         if read_data > 0 {
             return Ok(read_data);
         }
         // Await data.
         let _ = fut.await;
-        let read_data = handle_data_in_queue(self.0.as_mut().unwrap());
-        Ok(read_data)
+        Ok(self.try_read(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_from_queue_limited_by_buffer() {
+        let mut queue: heapless::spsc::Queue<u8, 8> = heapless::spsc::Queue::new();
+        let (mut prod, mut cons) = queue.split();
+        for byte in 0..5 {
+            prod.enqueue(byte).unwrap();
+        }
+        let mut buf = [0; 3];
+        assert_eq!(read_from_queue(&mut cons, &mut buf), 3);
+        assert_eq!(buf, [0, 1, 2]);
+        let mut buf = [0; 8];
+        assert_eq!(read_from_queue(&mut cons, &mut buf), 2);
+        assert_eq!(&buf[..2], &[3, 4]);
+        assert_eq!(read_from_queue(&mut cons, &mut buf), 0);
+    }
+
+    #[test]
+    fn drain_resets_flags() {
+        let mut queue: heapless::spsc::Queue<u8, 8> = heapless::spsc::Queue::new();
+        let (mut prod, mut cons) = queue.split();
+        let has_data = AtomicBool::new(true);
+        let idle = AtomicBool::new(true);
+        for byte in 0..4 {
+            prod.enqueue(byte).unwrap();
+        }
+        assert_eq!(drain_queue_and_reset_flags(&mut cons, &has_data, &idle), 4);
+        assert!(cons.is_empty());
+        assert!(!has_data.load(Ordering::Relaxed));
+        assert!(!idle.load(Ordering::Relaxed));
+
+        // Data and flags which the interrupt handler sets after a drain are reset by the next
+        // drain, which also works across the wrap-around of the queue.
+        for byte in 4..10 {
+            prod.enqueue(byte).unwrap();
+        }
+        has_data.store(true, Ordering::Relaxed);
+        idle.store(true, Ordering::Relaxed);
+        assert_eq!(drain_queue_and_reset_flags(&mut cons, &has_data, &idle), 6);
+        assert!(cons.is_empty());
+        assert!(!has_data.load(Ordering::Relaxed));
+        assert!(!idle.load(Ordering::Relaxed));
+
+        // Stale flags are reset even if the queue is already empty.
+        has_data.store(true, Ordering::Relaxed);
+        idle.store(true, Ordering::Relaxed);
+        assert_eq!(drain_queue_and_reset_flags(&mut cons, &has_data, &idle), 0);
+        assert!(!has_data.load(Ordering::Relaxed));
+        assert!(!idle.load(Ordering::Relaxed));
+    }
+
+    fn framing_error() -> AsyncUartErrors {
+        AsyncUartErrors {
+            queue_overflow: false,
+            uart_errors: UartErrors {
+                framing: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn pending_errors_after_clean_bytes() {
+        let mut pending = PendingRxErrors::new();
+        assert_eq!(pending.read_limit(10), 10);
+        assert_eq!(pending.take_if_reached(), None);
+        // 3 bytes were queued before the erroneous data.
+        pending.record(framing_error(), 3);
+        assert_eq!(pending.read_limit(10), 3);
+        assert_eq!(pending.read_limit(2), 2);
+        pending.consumed(2);
+        assert_eq!(pending.take_if_reached(), None);
+        assert_eq!(pending.read_limit(8), 1);
+        pending.consumed(1);
+        assert_eq!(pending.read_limit(7), 0);
+        assert_eq!(pending.take_if_reached(), Some(framing_error()));
+        // The remaining data is delivered normally.
+        assert_eq!(pending.read_limit(7), 7);
+        assert_eq!(pending.take_if_reached(), None);
+    }
+
+    #[test]
+    fn pending_errors_merged() {
+        let mut pending = PendingRxErrors::new();
+        pending.record(framing_error(), 4);
+        let overflow = AsyncUartErrors {
+            queue_overflow: true,
+            uart_errors: UartErrors {
+                overflow: true,
+                ..Default::default()
+            },
+        };
+        // Later errors do not move the position of the first error.
+        pending.record(overflow, 20);
+        assert_eq!(pending.read_limit(30), 4);
+        let errors = pending.take().unwrap();
+        assert!(errors.queue_overflow);
+        assert!(errors.uart_errors.framing());
+        assert!(errors.uart_errors.overflow());
+        assert!(!errors.uart_errors.parity());
+        assert_eq!(pending.take(), None);
+        // Data read without checking for errors skips the position of the error.
+        pending.record(framing_error(), 4);
+        pending.consumed(10);
+        assert_eq!(pending.take_if_reached(), Some(framing_error()));
+    }
+
+    #[test]
+    fn async_rx_error_kind() {
+        use embedded_io_async::{Error, ErrorKind};
+        assert_eq!(AsyncRxError(framing_error()).kind(), ErrorKind::InvalidData);
+        let overflow = AsyncUartErrors {
+            queue_overflow: true,
+            uart_errors: UartErrors::default(),
+        };
+        assert_eq!(AsyncRxError(overflow).kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn carry_over_delimiter_scanning() {
+        let mut carry_over = CarryOver::default();
+        assert_eq!(carry_over.frame_len(b'\n', 8), None);
+        carry_over
+            .fill_with::<()>(8, |spare| {
+                spare[..6].copy_from_slice(b"ab\ncd\n");
+                Ok(6)
+            })
+            .unwrap();
+        assert_eq!(carry_over.frame_len(b'\n', 8), Some((3, true)));
+        // The delimiter is only searched within the maximum frame length.
+        assert_eq!(carry_over.frame_len(b'\n', 2), Some((2, false)));
+        let mut buf = [0; 3];
+        assert_eq!(carry_over.take_front(&mut buf), 3);
+        assert_eq!(&buf, b"ab\n");
+        assert_eq!(carry_over.frame_len(b'\n', 8), Some((3, true)));
+        assert_eq!(carry_over.frame_len(b'x', 8), None);
+        assert_eq!(carry_over.frame_len(b'x', 3), Some((3, false)));
+        assert_eq!(carry_over.clear(), 3);
+        assert!(carry_over.is_empty());
+    }
+
+    #[test]
+    fn carry_over_across_wakeups() {
+        let mut queue: heapless::spsc::Queue<u8, 16> = heapless::spsc::Queue::new();
+        let (mut prod, mut cons) = queue.split();
+        let mut carry_over = CarryOver::default();
+        let fill = |carry_over: &mut CarryOver, cons: &mut heapless::spsc::Consumer<u8>| {
+            carry_over.fill_with::<()>(8, |spare| Ok(read_from_queue(cons, spare)))
+        };
+
+        // First wakeup: A partial frame is moved into the carry-over buffer.
+        for &byte in b"abc" {
+            prod.enqueue(byte).unwrap();
+        }
+        assert_eq!(fill(&mut carry_over, &mut cons), Ok(3));
+        assert_eq!(carry_over.frame_len(b'\n', 8), None);
+        assert!(cons.is_empty());
+        // A cancelled read leaves the examined bytes in the carry-over buffer. Second wakeup:
+        // The frame is completed, and data of the next frame is moved as well.
+        for &byte in b"d\nef" {
+            prod.enqueue(byte).unwrap();
+        }
+        assert_eq!(fill(&mut carry_over, &mut cons), Ok(4));
+        assert_eq!(carry_over.frame_len(b'\n', 8), Some((5, true)));
+        let mut buf = [0; 8];
+        assert_eq!(carry_over.take_front(&mut buf[..5]), 5);
+        assert_eq!(&buf[..5], b"abcd\n");
+        // The start of the next frame is kept.
+        assert_eq!(carry_over.len(), 2);
+
+        // Only the free space up to the maximum frame length is filled.
+        for &byte in b"0123456789" {
+            prod.enqueue(byte).unwrap();
+        }
+        assert_eq!(fill(&mut carry_over, &mut cons), Ok(6));
+        assert_eq!(cons.len(), 4);
+        assert_eq!(carry_over.frame_len(b'\n', 8), Some((8, false)));
+        assert_eq!(carry_over.take_front(&mut buf), 8);
+        assert_eq!(&buf, b"ef012345");
+        assert!(carry_over.is_empty());
+    }
+
+    #[test]
+    fn carry_over_fill_error_keeps_data() {
+        let mut carry_over = CarryOver::default();
+        carry_over
+            .fill_with::<()>(8, |spare| {
+                spare[0] = 1;
+                Ok(1)
+            })
+            .unwrap();
+        assert_eq!(
+            carry_over.fill_with(8, |_| Err(framing_error())),
+            Err(framing_error())
+        );
+        assert_eq!(carry_over.len(), 1);
+        // The capacity limits the fill length.
+        assert_eq!(
+            carry_over.fill_with::<()>(READ_UNTIL_MAX_LEN + 1, |spare| Ok(spare.len())),
+            Ok(READ_UNTIL_MAX_LEN - 1)
+        );
+        assert_eq!(carry_over.len(), READ_UNTIL_MAX_LEN);
+    }
+
+    #[test]
+    fn drain_empty_queue() {
+        let mut queue: heapless::spsc::Queue<u8, 4> = heapless::spsc::Queue::new();
+        let (_prod, mut cons) = queue.split();
+        let has_data = AtomicBool::new(false);
+        let idle = AtomicBool::new(true);
+        assert_eq!(drain_queue_and_reset_flags(&mut cons, &has_data, &idle), 0);
+        assert!(!has_data.load(Ordering::Relaxed));
+        assert!(!idle.load(Ordering::Relaxed));
     }
 }
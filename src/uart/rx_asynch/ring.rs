@@ -0,0 +1,401 @@
+//! # Zero-copy asynchronous UART reception.
+//!
+//! This module provides an alternative backend to the [heapless::spsc] queue used by
+//! [super::RxAsync]. The interrupt handler [on_interrupt_rx_ring_buffer] writes the data read
+//! from the RX FIFO directly into contiguous regions of a user provided static
+//! [RxRingBuffer], and the application consumes the received data in place using
+//! [RxAsyncRingBuffer::read_grant] and [RxAsyncRingBuffer::release]. The ring buffer uses the
+//! bipartite buffer algorithm popularized by the `bbqueue` crate, so all grants are
+//! contiguous slices.
+//!
+//! ## Tradeoff compared to the [heapless::spsc] backend
+//!
+//! Every received byte is copied exactly once, from the RX FIFO into the ring buffer, instead of
+//! being copied into the queue and then again into the user buffer. This reduces the CPU load at
+//! high baudrates and allows protocol parsers to work on the received data directly. However, a
+//! read grant only covers the contiguous data up to the end of the buffer, so received data
+//! which wraps around the end of the buffer is returned in two separate grants. The write position
+//! only wraps around after the end of the buffer was filled, so no bytes are skipped. After the
+//! write position wrapped around, one byte before the read position always remains unused to
+//! distinguish a full buffer from an empty one. The latency until data is available to the
+//! application is the same for both backends.
+use core::{cell::UnsafeCell, sync::atomic::Ordering};
+
+use arbitrary_int::prelude::*;
+use portable_atomic::{AtomicBool, AtomicUsize};
+
+use super::{
+    AsyncUartErrors, Bank, DRIVER_UNMASKS_NVIC, RX_HAS_DATA, RX_IDLE, Rx, RxFuture,
+    on_interrupt_rx_common_post_processing, stop_async_rx,
+};
+use crate::uart::{UartIrqView, regs::MmioUart};
+
+/// Static ring buffer used for zero-copy UART reception.
+///
+/// It needs to be split into a [RxRingProducer], which is passed to the interrupt handler, and
+/// a [RxRingConsumer], which is passed to the [RxAsyncRingBuffer].
+pub struct RxRingBuffer<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// Write position. Only modified by the producer.
+    write: AtomicUsize,
+    /// Read position. Only modified by the consumer. If the read position is larger than the
+    /// write position, the write position has wrapped around and the valid data reaches up to
+    /// the end of the buffer.
+    read: AtomicUsize,
+    already_split: AtomicBool,
+}
+
+// Safety: Access to the buffer is synchronized using the producer and consumer positions, and
+// there can only be one producer and one consumer.
+unsafe impl<const N: usize> Sync for RxRingBuffer<N> {}
+
+impl<const N: usize> Default for RxRingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RxRingBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: UnsafeCell::new([0; N]),
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            already_split: AtomicBool::new(false),
+        }
+    }
+
+    /// Split the ring buffer into its producer and consumer half. Returns [None] if the buffer
+    /// was already split.
+    pub fn try_split(&'static self) -> Option<(RxRingProducer<N>, RxRingConsumer<N>)> {
+        if self.already_split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some((RxRingProducer { ring: self }, RxRingConsumer { ring: self }))
+    }
+
+    #[inline(always)]
+    fn buf_ptr(&self) -> *mut u8 {
+        self.buf.get() as *mut u8
+    }
+}
+
+/// Producer half of the [RxRingBuffer] which is used by the interrupt handler.
+pub struct RxRingProducer<const N: usize> {
+    ring: &'static RxRingBuffer<N>,
+}
+
+impl<const N: usize> RxRingProducer<N> {
+    /// Grant the largest contiguous writable region which is not larger than `max`, pass it
+    /// to the closure and commit the number of bytes returned by the closure.
+    ///
+    /// Returns [None] if there is no space left in the ring buffer.
+    fn write_with(&mut self, max: usize, f: impl FnOnce(&mut [u8]) -> usize) -> Option<usize> {
+        let write = self.ring.write.load(Ordering::Acquire);
+        let read = self.ring.read.load(Ordering::Acquire);
+        let (start, len) = if write < read {
+            // Inverted. The write position must never reach the read position, because
+            // this would be indistinguishable from an empty buffer.
+            let remaining = read - write - 1;
+            if remaining == 0 {
+                return None;
+            }
+            (write, remaining.min(max))
+        } else if write != N {
+            (write, (N - write).min(max))
+        } else if read > 1 {
+            // Wrap around to the start of the buffer.
+            (0, (read - 1).min(max))
+        } else {
+            return None;
+        };
+        // Safety: The granted region is not accessible for the consumer until it is
+        // committed, and there is only one producer.
+        let grant = unsafe { core::slice::from_raw_parts_mut(self.ring.buf_ptr().add(start), len) };
+        let used = f(grant).min(len);
+        self.ring.write.store(start + used, Ordering::Release);
+        Some(used)
+    }
+}
+
+/// Consumer half of the [RxRingBuffer] which is used by the [RxAsyncRingBuffer].
+pub struct RxRingConsumer<const N: usize> {
+    ring: &'static RxRingBuffer<N>,
+}
+
+impl<const N: usize> RxRingConsumer<N> {
+    /// Returns the contiguous slice of received data, which might be empty.
+    pub fn read_grant(&mut self) -> &[u8] {
+        let write = self.ring.write.load(Ordering::Acquire);
+        let mut read = self.ring.read.load(Ordering::Relaxed);
+        if read == N && write < read {
+            // The producer has wrapped around and all data up to the end was consumed.
+            read = 0;
+            self.ring.read.store(0, Ordering::Release);
+        }
+        let end = if write < read { N } else { write };
+        // Safety: The region between the read and write position was committed by the producer,
+        // and the producer does not modify it until it is released.
+        unsafe { core::slice::from_raw_parts(self.ring.buf_ptr().add(read), end - read) }
+    }
+
+    /// Release the given number of bytes of the last read grant. The number is clamped to the
+    /// length of the read grant.
+    pub fn release(&mut self, used: usize) {
+        let len = self.read_grant().len();
+        let read = self.ring.read.load(Ordering::Relaxed);
+        self.ring
+            .read
+            .store(read + used.min(len), Ordering::Release);
+    }
+
+    /// Number of bytes in the ring buffer, including data which wrapped around.
+    pub fn len(&self) -> usize {
+        let write = self.ring.write.load(Ordering::Acquire);
+        let read = self.ring.read.load(Ordering::Relaxed);
+        if write < read {
+            (N - read) + write
+        } else {
+            write - read
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Moves up to `max` bytes from the RX FIFO into the ring buffer. Returns whether data had to be
+/// discarded because the ring buffer was full.
+fn rx_fifo_to_ring_buffer<const N: usize>(
+    uart_regs: &MmioUart<'static>,
+    prod: &mut RxRingProducer<N>,
+    max: usize,
+) -> bool {
+    let data_mask = uart_regs.read_ctrl().wordsize().data_mask();
+    let mut read = 0;
+    while read < max && uart_regs.read_rx_status().data_available() {
+        let written = prod.write_with(max - read, |grant| {
+            let mut used = 0;
+            for byte in grant.iter_mut() {
+                if !uart_regs.read_rx_status().data_available() {
+                    break;
+                }
+                *byte = uart_regs.read_data().value() & data_mask;
+                used += 1;
+            }
+            used
+        });
+        match written {
+            Some(used) => read += used,
+            None => {
+                // Ring buffer is full, the data is lost.
+                while read < max && uart_regs.read_rx_status().data_available() {
+                    uart_regs.read_data();
+                    read += 1;
+                }
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Interrupt handler for zero-copy asynchronous RX operations.
+///
+/// Should be called in the user interrupt handler to enable asynchronous reception with the
+/// [RxAsyncRingBuffer]. If the ring buffer is full, the new data is discarded and
+/// [AsyncUartErrors::queue_overflow] is set.
+pub fn on_interrupt_rx_ring_buffer<const N: usize>(
+    bank: Bank,
+    prod: &mut RxRingProducer<N>,
+) -> Result<(), AsyncUartErrors> {
+    let irq_view = UartIrqView::new(bank);
+    let irq_status = irq_view.irq_status();
+    let irq_enabled = irq_view.irq_enabled();
+    // Safety: Only the RX FIFO is drained, which is owned by the asynchronous reader.
+    let uart_regs = unsafe { bank.force_steal_regs() };
+    let rx_enabled = irq_enabled.rx();
+    let mut read_some_data = false;
+    let mut queue_overflow = false;
+
+    // Half-Full interrupt. We have a guaranteed amount of data we can read.
+    if irq_status.rx() {
+        // Leave one byte in the FIFO so the RX timeout interrupt fires.
+        let available_bytes = uart_regs
+            .read_rx_fifo_trigger()
+            .level()
+            .as_usize()
+            .saturating_sub(1);
+        queue_overflow |= rx_fifo_to_ring_buffer(&uart_regs, prod, available_bytes);
+        read_some_data = true;
+    }
+
+    // Timeout, empty the FIFO completely.
+    if irq_status.rx_timeout() {
+        queue_overflow |= rx_fifo_to_ring_buffer(&uart_regs, prod, usize::MAX);
+        read_some_data = true;
+    }
+
+    let uart_errors =
+        on_interrupt_rx_common_post_processing(bank, irq_status, rx_enabled, read_some_data);
+    if uart_errors.is_some() || queue_overflow {
+        return Err(AsyncUartErrors {
+            queue_overflow,
+            uart_errors: uart_errors.unwrap_or_default(),
+        });
+    }
+    Ok(())
+}
+
+struct RxAsyncRingBufferInner<const N: usize> {
+    rx: Rx,
+    consumer: RxRingConsumer<N>,
+}
+
+/// Asynchronous UART receiver which allows consuming the received data in place.
+///
+/// The [on_interrupt_rx_ring_buffer] interrupt handler must be used together with this
+/// receiver.
+pub struct RxAsyncRingBuffer<const N: usize>(Option<RxAsyncRingBufferInner<N>>);
+
+impl<const N: usize> RxAsyncRingBuffer<N> {
+    /// Create a new zero-copy asynchronous receiver.
+    pub fn new(mut rx: Rx, consumer: RxRingConsumer<N>) -> Self {
+        rx.disable_interrupts();
+        rx.disable();
+        rx.clear_fifo();
+        // Enable those together.
+        critical_section::with(|_| {
+            rx.enable_interrupts(DRIVER_UNMASKS_NVIC, true);
+            rx.enable();
+        });
+        Self(Some(RxAsyncRingBufferInner { rx, consumer }))
+    }
+
+    /// Returns the contiguous slice of received data without waiting. The slice might be empty.
+    ///
+    /// The data remains in the ring buffer until it is released with [Self::release].
+    pub fn read_grant(&mut self) -> &[u8] {
+        self.0.as_mut().unwrap().consumer.read_grant()
+    }
+
+    /// Asynchronously wait until received data is available and return the contiguous slice of
+    /// received data.
+    ///
+    /// The data remains in the ring buffer until it is released with [Self::release].
+    pub async fn wait_read_grant(&mut self) -> &[u8] {
+        let inner = self.0.as_mut().unwrap();
+        let idx = inner.rx.id as usize;
+        let _guard = super::ActiveReadGuard(idx);
+        loop {
+            let fut = RxFuture::new(&mut inner.rx);
+            // The interrupt handler might run between checking the buffer and resetting the
+            // flag, so this needs to happen atomically.
+            let empty = critical_section::with(|_| {
+                if inner.consumer.is_empty() {
+                    RX_HAS_DATA[idx].store(false, Ordering::Relaxed);
+                    return true;
+                }
+                false
+            });
+            if !empty {
+                break;
+            }
+            let _ = fut.await;
+        }
+        inner.consumer.read_grant()
+    }
+
+    /// Release bytes of the last read grant, making the space available for new data.
+    pub fn release(&mut self, used: usize) {
+        self.0.as_mut().unwrap().consumer.release(used);
+    }
+
+    /// Whether the RX line was idle after the last received data, see
+    /// [super::RxAsync::read_until_idle].
+    pub fn line_idle(&self) -> bool {
+        RX_IDLE[self.0.as_ref().unwrap().rx.id as usize].load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&mut self) {
+        stop_async_rx(&mut self.0.as_mut().unwrap().rx);
+    }
+
+    pub fn release_rx(mut self) -> (Rx, RxRingConsumer<N>) {
+        self.stop();
+        let inner = self.0.take().unwrap();
+        (inner.rx, inner.consumer)
+    }
+}
+
+impl<const N: usize> Drop for RxAsyncRingBuffer<N> {
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            self.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push<const N: usize>(prod: &mut RxRingProducer<N>, data: &[u8]) -> Option<usize> {
+        prod.write_with(data.len(), |grant| {
+            grant.copy_from_slice(&data[..grant.len()]);
+            grant.len()
+        })
+    }
+
+    #[test]
+    fn empty_buffer() {
+        static RING: RxRingBuffer<8> = RxRingBuffer::new();
+        let (_prod, mut cons) = RING.try_split().unwrap();
+        assert!(RING.try_split().is_none());
+        assert!(cons.is_empty());
+        assert!(cons.read_grant().is_empty());
+        cons.release(4);
+        assert!(cons.is_empty());
+    }
+
+    #[test]
+    fn full_buffer() {
+        static RING: RxRingBuffer<8> = RxRingBuffer::new();
+        let (mut prod, mut cons) = RING.try_split().unwrap();
+        assert_eq!(push(&mut prod, &[1, 2, 3, 4, 5, 6, 7, 8, 9]), Some(8));
+        assert_eq!(cons.len(), 8);
+        assert_eq!(push(&mut prod, &[10]), None);
+        assert_eq!(cons.read_grant(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // After wrapping around, one byte before the read position remains unused.
+        cons.release(3);
+        assert_eq!(push(&mut prod, &[10, 11, 12]), Some(2));
+        assert_eq!(cons.len(), 7);
+        assert_eq!(push(&mut prod, &[13]), None);
+    }
+
+    #[test]
+    fn wrap_around() {
+        static RING: RxRingBuffer<8> = RxRingBuffer::new();
+        let (mut prod, mut cons) = RING.try_split().unwrap();
+        assert_eq!(push(&mut prod, &[0; 6]), Some(6));
+        cons.release(6);
+        assert!(cons.is_empty());
+
+        // The end of the buffer is filled first, no bytes are skipped.
+        assert_eq!(push(&mut prod, &[1, 2, 3, 4, 5]), Some(2));
+        assert_eq!(push(&mut prod, &[3, 4, 5]), Some(3));
+        assert_eq!(cons.len(), 5);
+
+        // The wrapped data is returned in a second grant.
+        assert_eq!(cons.read_grant(), &[1, 2]);
+        cons.release(2);
+        assert_eq!(cons.len(), 3);
+        assert_eq!(cons.read_grant(), &[3, 4, 5]);
+        cons.release(3);
+        assert!(cons.is_empty());
+        assert!(cons.read_grant().is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+//! # Request-response transactions
+//!
+//! Many devices connected to a UART use a strict request-response protocol: The host sends a
+//! request and the device answers with a response within a given time. [UartTransactor] and
+//! [UartTransactorAsync] implement one such exchange with [UartTransactor::transact]:
+//!
+//! 1. Stale received data, for example the late remainder of a response for which the previous
+//!    transaction timed out, is discarded together with the recorded reception errors. Otherwise,
+//!    it would be returned as the start of the next response.
+//! 2. The request is written completely, and the transactor waits until it was transmitted.
+//! 3. The response is read until the buffer is full or the line was idle for the given number of
+//!    character times.
+//!
+//! The async [UartTransactorAsync] requires the `async-timeouts` feature.
+use embedded_io::Write as _;
+
+use super::{Rx, Tx, UartErrors};
+#[cfg(feature = "async-timeouts")]
+use super::{RxAsync, TxAsync};
+
+/// Error of a request-response transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransactError {
+    /// No response byte was received within the timeout after the request was sent.
+    #[error("no response received")]
+    NoResponse,
+    /// Reception errors were detected while the response was received.
+    #[error("reception error: {0:?}")]
+    Rx(UartErrors),
+    /// The queue of the asynchronous receiver has overflowed, so response data was lost.
+    #[error("reception queue overflow")]
+    QueueOverflow,
+    /// The asynchronous transmitter reported a TX overrun.
+    #[error("TX overrun")]
+    TxOverrun,
+}
+
+/// Reception and transmission steps of a transaction, which are implemented by the blocking
+/// and the asynchronous transactors.
+trait TransactIo {
+    /// Discard all received data and the recorded reception errors.
+    fn discard_stale(&mut self);
+
+    /// Write the complete request and wait until it was transmitted.
+    fn send(&mut self, request: &[u8]) -> Result<(), TransactError>;
+
+    /// Read at least one byte. Returns 0 if the line was idle for the given number of
+    /// character times.
+    fn receive(&mut self, buf: &mut [u8], idle_chars: u16) -> Result<usize, TransactError>;
+}
+
+fn transact_with(
+    io: &mut impl TransactIo,
+    request: &[u8],
+    response: &mut [u8],
+    idle_chars: u16,
+) -> Result<usize, TransactError> {
+    io.discard_stale();
+    io.send(request)?;
+    let mut read = 0;
+    while read < response.len() {
+        match io.receive(&mut response[read..], idle_chars)? {
+            0 => break,
+            received => read += received,
+        }
+    }
+    if read == 0 && !response.is_empty() {
+        return Err(TransactError::NoResponse);
+    }
+    Ok(read)
+}
+
+//==================================================================================================
+// Blocking transactor
+//==================================================================================================
+
+/// Blocking request-response transactor owning the [Tx] and [Rx] of a UART.
+///
+/// The timeouts are measured with the given delay provider, see
+/// [Rx::read_with_character_timeout].
+pub struct UartTransactor<Delay: embedded_hal::delay::DelayNs> {
+    tx: Tx,
+    rx: Rx,
+    delay: Delay,
+}
+
+impl<Delay: embedded_hal::delay::DelayNs> UartTransactor<Delay> {
+    pub fn new(tx: Tx, rx: Rx, delay: Delay) -> Self {
+        Self { tx, rx, delay }
+    }
+
+    /// Send the request and read the response, see the [module documentation](self).
+    ///
+    /// Returns the number of response bytes, which is smaller than the response buffer length
+    /// if the line was idle for `idle_chars` character times before the buffer was full. The same
+    /// timeout applies to the first response byte after the request was transmitted.
+    /// [TransactError::NoResponse] is returned if no byte was received at all.
+    pub fn transact(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+        idle_chars: u16,
+    ) -> Result<usize, TransactError> {
+        transact_with(self, request, response, idle_chars)
+    }
+
+    pub fn release(self) -> (Tx, Rx, Delay) {
+        (self.tx, self.rx, self.delay)
+    }
+}
+
+impl<Delay: embedded_hal::delay::DelayNs> TransactIo for UartTransactor<Delay> {
+    fn discard_stale(&mut self) {
+        self.rx.clear_fifo();
+        // A byte might have been received between the FIFO clear and the status check.
+        for _ in 0..self.rx.fifo_capacity() {
+            if embedded_hal_nb::serial::Read::read(&mut self.rx).is_err() {
+                break;
+            }
+        }
+        let _ = self.rx.poll_errors();
+    }
+
+    fn send(&mut self, request: &[u8]) -> Result<(), TransactError> {
+        // Infallible.
+        let _ = self.tx.write_all(request);
+        self.tx.wait_transmission_complete();
+        Ok(())
+    }
+
+    fn receive(&mut self, buf: &mut [u8], idle_chars: u16) -> Result<usize, TransactError> {
+        let read = self
+            .rx
+            .read_with_character_timeout(buf, idle_chars, &mut self.delay)
+            .unwrap_or(0);
+        if let Some(errors) = self.rx.poll_errors() {
+            return Err(TransactError::Rx(errors));
+        }
+        Ok(read)
+    }
+}
+
+//==================================================================================================
+// Asynchronous transactor
+//==================================================================================================
+
+/// Asynchronous request-response transactor owning a [TxAsync] and a [RxAsync].
+///
+/// The timeouts are measured with the embassy time driver. Received data is queued by the
+/// interrupt handler of the [RxAsync] between the transactions and discarded at the start of the
+/// next transaction.
+#[cfg(feature = "async-timeouts")]
+pub struct UartTransactorAsync {
+    tx: TxAsync,
+    rx: RxAsync,
+}
+
+#[cfg(feature = "async-timeouts")]
+impl UartTransactorAsync {
+    pub fn new(tx: TxAsync, rx: RxAsync) -> Self {
+        Self { tx, rx }
+    }
+
+    /// Asynchronous version of [UartTransactor::transact].
+    pub async fn transact(
+        &mut self,
+        request: &[u8],
+        response: &mut [u8],
+        idle_chars: u16,
+    ) -> Result<usize, TransactError> {
+        self.rx.discard_stale();
+        self.send(request).await?;
+        let idle_timeout =
+            embassy_time::Duration::from_micros(self.tx.config().idle_timeout(idle_chars).ticks());
+        let mut read = 0;
+        while read < response.len() {
+            match self
+                .rx
+                .read_timeout(&mut response[read..], idle_timeout)
+                .await
+            {
+                Ok(0) | Err(crate::time::AsyncTimeoutError::Timeout) => break,
+                Ok(received) => read += received,
+                Err(crate::time::AsyncTimeoutError::Operation(e)) => {
+                    if e.0.queue_overflow {
+                        return Err(TransactError::QueueOverflow);
+                    }
+                    return Err(TransactError::Rx(e.0.uart_errors));
+                }
+            }
+        }
+        if read == 0 && !response.is_empty() {
+            return Err(TransactError::NoResponse);
+        }
+        Ok(read)
+    }
+
+    async fn send(&mut self, request: &[u8]) -> Result<(), TransactError> {
+        embedded_io_async::Write::write_all(&mut self.tx, request)
+            .await
+            .map_err(|_| TransactError::TxOverrun)
+    }
+
+    pub fn release(self) -> (TxAsync, RxAsync) {
+        (self.tx, self.rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Device model which answers each request with a fixed response. Data which is received
+    /// before the request, like the late response of a previous request, is stale.
+    struct Device {
+        rx: heapless::Deque<u8, 32>,
+        errors: Option<UartErrors>,
+        response: &'static [u8],
+        /// Number of bytes delivered per receive call.
+        burst: usize,
+        requests: usize,
+    }
+
+    impl Device {
+        fn new(response: &'static [u8], burst: usize) -> Self {
+            Self {
+                rx: heapless::Deque::new(),
+                errors: None,
+                response,
+                burst,
+                requests: 0,
+            }
+        }
+
+        fn push_stale(&mut self, data: &[u8]) {
+            for byte in data {
+                self.rx.push_back(*byte).unwrap();
+            }
+        }
+    }
+
+    impl TransactIo for Device {
+        fn discard_stale(&mut self) {
+            self.rx.clear();
+            self.errors = None;
+        }
+
+        fn send(&mut self, _request: &[u8]) -> Result<(), TransactError> {
+            self.requests += 1;
+            for byte in self.response {
+                self.rx.push_back(*byte).unwrap();
+            }
+            Ok(())
+        }
+
+        fn receive(&mut self, buf: &mut [u8], _idle_chars: u16) -> Result<usize, TransactError> {
+            if let Some(errors) = self.errors {
+                return Err(TransactError::Rx(errors));
+            }
+            let mut read = 0;
+            while read < buf.len().min(self.burst) {
+                match self.rx.pop_front() {
+                    Some(byte) => buf[read] = byte,
+                    None => break,
+                }
+                read += 1;
+            }
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn stale_data_is_discarded() {
+        let mut device = Device::new(&[1, 2, 3], 2);
+        // Late remainder of a response which timed out.
+        device.push_stale(&[0xEE, 0xEF]);
+        let mut response = [0; 3];
+        assert_eq!(transact_with(&mut device, &[0x10], &mut response, 4), Ok(3));
+        assert_eq!(response, [1, 2, 3]);
+        assert_eq!(device.requests, 1);
+    }
+
+    #[test]
+    fn stale_errors_are_discarded() {
+        let mut device = Device::new(&[1], 1);
+        device.errors = Some(UartErrors {
+            framing: true,
+            ..Default::default()
+        });
+        let mut response = [0; 1];
+        assert_eq!(transact_with(&mut device, &[0x10], &mut response, 4), Ok(1));
+    }
+
+    #[test]
+    fn short_response() {
+        let mut device = Device::new(&[1, 2], 1);
+        let mut response = [0; 4];
+        assert_eq!(transact_with(&mut device, &[0x10], &mut response, 4), Ok(2));
+        assert_eq!(response[..2], [1, 2]);
+        // The remainder of a response which is longer than the buffer is stale for the next
+        // transaction.
+        let mut device = Device::new(&[1, 2, 3], 3);
+        let mut response = [0; 2];
+        assert_eq!(transact_with(&mut device, &[0x10], &mut response, 4), Ok(2));
+        let mut response = [0; 3];
+        assert_eq!(transact_with(&mut device, &[0x10], &mut response, 4), Ok(3));
+        assert_eq!(response, [1, 2, 3]);
+    }
+
+    #[test]
+    fn no_response() {
+        let mut device = Device::new(&[], 1);
+        device.push_stale(&[0xEE]);
+        let mut response = [0; 4];
+        assert_eq!(
+            transact_with(&mut device, &[0x10], &mut response, 4),
+            Err(TransactError::NoResponse)
+        );
+        // An empty response buffer only sends the request.
+        assert_eq!(transact_with(&mut device, &[0x10], &mut [], 4), Ok(0));
+        assert_eq!(device.requests, 2);
+    }
+}
@@ -7,15 +7,57 @@
 //!
 //! This handler should be called in ALL user interrupt handlers which handle UART TX interrupts
 //! for a given UART bank.
+//!
+//! Like the blocking [Tx], the bits above the configured [super::WordSize] are cleared in all
+//! written bytes.
+//!
+//! ## FIFO refills
+//!
+//! A transfer fills the TX FIFO completely when it is started. While data of the user buffer is
+//! pending, the TX FIFO trigger interrupt is used as the refill point: It occurs when the FIFO
+//! count drops below the level set with [Tx::set_tx_fifo_trigger], and [on_interrupt_tx] tops the
+//! FIFO up to full again from the buffer descriptor of the bank. The FIFO therefore never runs
+//! empty as long as the interrupt latency is shorter than the transmission time of the words
+//! below the trigger level, which avoids gaps between the words on the wire. For example, with
+//! the reset trigger level of 8 at 921600 baud and 8N1 frames, the interrupt handler has
+//! 8 * 10 / 921600 s = 86.8 us to refill the FIFO.
+//!
+//! The TX empty interrupt is only enabled once the last word of the buffer was written to the
+//! FIFO, and is used to detect the completion of the transfer.
+//!
+//! ## Interrupt handler contract
+//!
+//! A transfer future enables the TX FIFO trigger or the TX empty interrupt, and the TX status
+//! interrupt of the UART and relies on [on_interrupt_tx] being called for each of those
+//! interrupts until the transfer is complete. The TX FIFO trigger level must not be 0 for
+//! transfers which do not fit into the FIFO.
+//!
+//! - Vorago 1x: The UART interrupt must be routed to an interrupt line via the `InterruptConfig`
+//!   passed to the UART constructor, and the handler of that interrupt line must call
+//!   [on_interrupt_tx].
+//! - Vorago 4x: The handler of the dedicated UART TX interrupt must call [on_interrupt_tx]. The
+//!   interrupt is enabled in the NVIC by the transfer future.
+//!
+//! TX interrupts which occur without an active transfer future disable the TX interrupts again and
+//! are counted. The counter can be retrieved with [unexpected_tx_interrupts] and helps to detect
+//! misconfigured interrupt handlers.
+//!
+//! ## Cancellation
+//!
+//! A write future which is dropped before its completion leaves the transmitter disabled with the
+//! remaining data in the TX FIFO. [TxAsync::abort_write] clears the FIFO, and
+//! [TxAsync::write_timeout] of the `async-timeouts` feature does this automatically.
 use core::{cell::RefCell, future::Future};
 
 use critical_section::Mutex;
 use embassy_sync::waitqueue::AtomicWaker;
 use embedded_io_async::Write;
-use portable_atomic::AtomicBool;
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
 use raw_slice::RawBufSlice;
 
 use super::*;
+#[cfg(feature = "async-timeouts")]
+use crate::time::AsyncTimeoutError;
 
 static UART_TX_WAKERS: [AtomicWaker; 2] = [const { AtomicWaker::new() }; 2];
 static TX_CONTEXTS: [Mutex<RefCell<TxContext>>; 2] =
@@ -23,6 +65,10 @@ static TX_CONTEXTS: [Mutex<RefCell<TxContext>>; 2] =
 // Completion flag. Kept outside of the context structure as an atomic to avoid
 // critical section.
 static TX_DONE: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
+// Set while a transfer future is active and expects TX interrupts. Only the party which resets
+// this flag completes the transfer.
+static TX_ACTIVE: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
+static TX_UNEXPECTED_IRQS: [AtomicU32; 2] = [const { AtomicU32::new(0) }; 2];
 
 /// This is a generic interrupt handler to handle asynchronous UART TX operations for a given
 /// UART bank.
@@ -30,62 +76,92 @@ static TX_DONE: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
 /// The user has to call this once in the interrupt handler responsible for the TX interrupts on
 /// the given UART bank.
 pub fn on_interrupt_tx(bank: Bank) {
-    let mut uart = unsafe { bank.steal_regs() };
     let idx = bank as usize;
-    let irq_enabled = uart.read_irq_enabled();
+    let irq_view = UartIrqView::new(bank);
+    let irq_enabled = irq_view.irq_enabled();
     // IRQ is not related to TX.
     if !irq_enabled.tx() && !irq_enabled.tx_empty() {
         return;
     }
-
-    let tx_status = uart.read_tx_status();
-    let unexpected_overrun = tx_status.wr_lost();
-    let mut context = critical_section::with(|cs| {
-        let context_ref = TX_CONTEXTS[idx].borrow(cs);
-        *context_ref.borrow()
-    });
-    context.tx_overrun = unexpected_overrun;
-    // Safety: We documented that the user provided slice must outlive the future, so we convert
-    // the raw pointer back to the slice here.
-    let slice = unsafe { context.slice.get().unwrap() };
-    if context.progress >= slice.len() && !tx_status.tx_busy() {
-        uart.modify_irq_enabled(|mut value| {
-            value.set_tx(false);
-            value.set_tx_empty(false);
-            value.set_tx_status(false);
-            value
-        });
-        uart.modify_enable(|mut value| {
-            value.set_tx(false);
-            value
-        });
-        // Write back updated context structure.
-        critical_section::with(|cs| {
-            let context_ref = TX_CONTEXTS[idx].borrow(cs);
-            *context_ref.borrow_mut() = context;
-        });
-        // Transfer is done.
-        TX_DONE[idx].store(true, core::sync::atomic::Ordering::Relaxed);
-        UART_TX_WAKERS[idx].wake();
+    // Safety: Only the TX FIFO is filled, which is owned by the asynchronous writer.
+    let mut uart = unsafe { bank.force_steal_regs() };
+    // Only acknowledge the TX overrun, a RX overrun is handled by the RX interrupt handler.
+    let irq_status = irq_view.irq_status();
+    let tx_overrun = clear_tx_overrun_interrupt(&mut uart, irq_status);
+    if !TX_ACTIVE[idx].load(Ordering::Relaxed) {
+        // No active transfer future. Disable the TX interrupts to avoid an interrupt storm.
+        disable_tx_interrupts(&mut uart);
+        TX_UNEXPECTED_IRQS[idx].fetch_add(1, Ordering::Relaxed);
         return;
     }
-    while context.progress < slice.len() {
-        if !uart.read_tx_status().ready() {
-            break;
-        }
-        // Safety: TX structure is owned by the future which does not write into the the data
-        // register, so we can assume we are the only one writing to the data register.
-        uart.write_data(Data::new_with_raw_value(slice[context.progress] as u32));
-        context.progress += 1;
-    }
 
-    // Write back updated context structure.
     critical_section::with(|cs| {
-        let context_ref = TX_CONTEXTS[idx].borrow(cs);
-        *context_ref.borrow_mut() = context;
+        let mut context = TX_CONTEXTS[idx].borrow(cs).borrow_mut();
+        context.tx_overrun |= tx_overrun || uart.read_tx_status().wr_lost();
+        // Safety: We documented that the user provided slice must outlive the future, so we
+        // convert the raw pointer back to the slice here.
+        let raw_slice = context.slice;
+        let slice = unsafe { raw_slice.get().unwrap() };
+        let data_mask = uart.read_ctrl().wordsize().data_mask();
+        while context.progress < slice.len() {
+            if !uart.read_tx_status().ready() {
+                break;
+            }
+            // Safety: TX structure is owned by the future which does not write into the the data
+            // register, so we can assume we are the only one writing to the data register.
+            uart.write_data(Data::new_with_raw_value(
+                (slice[context.progress] & data_mask) as u32,
+            ));
+            context.progress += 1;
+        }
+        if context.progress >= slice.len() {
+            if !uart.read_tx_status().tx_busy() {
+                finish_transfer(&mut uart, idx);
+            } else {
+                enable_tx_interrupts_for_stage(&mut uart, TxStage::Completion);
+            }
+        }
+    });
+}
+
+/// Number of TX interrupts which occurred while no transfer future was active.
+///
+/// A non-zero value usually means that the interrupt handler calling [on_interrupt_tx] is
+/// misconfigured, for example because it is shared with another interrupt source which enables
+/// the TX interrupts.
+pub fn unexpected_tx_interrupts(bank: Bank) -> u32 {
+    TX_UNEXPECTED_IRQS[bank as usize].load(Ordering::Relaxed)
+}
+
+/// Stage of a transfer, which determines the enabled TX interrupts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TxStage {
+    /// Data of the user buffer is pending, the FIFO is refilled by the TX FIFO trigger
+    /// interrupt.
+    Refill,
+    /// All data was written to the FIFO, the TX empty interrupt detects the completion.
+    Completion,
+}
+
+fn enable_tx_interrupts_for_stage(uart: &mut MmioUart<'static>, stage: TxStage) {
+    uart.modify_irq_enabled(|mut value| {
+        value.set_tx(stage == TxStage::Refill);
+        value.set_tx_empty(stage == TxStage::Completion);
+        value.set_tx_status(true);
+        value
     });
 }
 
+/// Must be called inside a critical section.
+fn finish_transfer(uart: &mut MmioUart<'static>, idx: usize) {
+    disable_tx_interrupts(uart);
+    disable_tx(uart);
+    if TX_ACTIVE[idx].swap(false, Ordering::Relaxed) {
+        TX_DONE[idx].store(true, Ordering::Relaxed);
+        UART_TX_WAKERS[idx].wake();
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct TxContext {
     progress: usize,
@@ -114,28 +190,39 @@ impl TxFuture {
     /// This function stores the raw pointer of the passed data slice. The user MUST ensure
     /// that the slice outlives the data structure.
     pub unsafe fn new(tx: &mut Tx, data: &[u8]) -> Self {
-        TX_DONE[tx.id as usize].store(false, core::sync::atomic::Ordering::Relaxed);
+        let idx = tx.id as usize;
+        TX_DONE[idx].store(false, Ordering::Relaxed);
         tx.disable_interrupts();
         tx.disable();
         tx.clear_fifo();
 
-        let init_fill_count = core::cmp::min(data.len(), 16);
-        // We fill the FIFO.
-        for data in data.iter().take(init_fill_count) {
-            tx.regs.write_data(Data::new_with_raw_value(*data as u32));
-        }
         critical_section::with(|cs| {
-            let context_ref = TX_CONTEXTS[tx.id as usize].borrow(cs);
-            let mut context = context_ref.borrow_mut();
+            let mut context = TX_CONTEXTS[idx].borrow(cs).borrow_mut();
             unsafe { context.slice.set(data) };
+            context.tx_overrun = false;
+            // The transfer state, the active flag and the interrupts are set up before the FIFO
+            // is filled and the transmitter is enabled, so the interrupt handler always sees a
+            // consistent state. Enabling everything inside the critical section also avoids
+            // glitches.
+            TX_ACTIVE[idx].store(true, Ordering::Relaxed);
+            let init_fill_count = core::cmp::min(data.len(), tx.fifo_capacity());
+            let data_mask = tx.cfg.wordsize.data_mask();
+            for data in data.iter().take(init_fill_count) {
+                tx.regs
+                    .write_data(Data::new_with_raw_value((*data & data_mask) as u32));
+            }
             context.progress = init_fill_count;
-
-            // Ensure those are enabled inside a critical section at the same time. Can lead to
-            // weird glitches otherwise.
-            tx.enable_interrupts(
-                #[cfg(feature = "vor4x")]
-                true,
+            enable_tx_interrupts_for_stage(
+                &mut tx.regs,
+                if init_fill_count < data.len() {
+                    TxStage::Refill
+                } else {
+                    TxStage::Completion
+                },
             );
+            if DRIVER_UNMASKS_NVIC {
+                tx.enable_nvic_interrupt();
+            }
             tx.enable();
         });
         Self { id: tx.id }
@@ -149,12 +236,28 @@ impl Future for TxFuture {
         self: core::pin::Pin<&mut Self>,
         cx: &mut core::task::Context<'_>,
     ) -> core::task::Poll<Self::Output> {
-        UART_TX_WAKERS[self.id as usize].register(cx.waker());
-        if TX_DONE[self.id as usize].swap(false, core::sync::atomic::Ordering::Relaxed) {
-            let progress = critical_section::with(|cs| {
-                TX_CONTEXTS[self.id as usize].borrow(cs).borrow().progress
+        let idx = self.id as usize;
+        UART_TX_WAKERS[idx].register(cx.waker());
+        // Check the transfer state again after registering the waker. This completes the transfer
+        // if the transmitter was drained without the completion being handled in the interrupt
+        // handler.
+        if !TX_DONE[idx].load(Ordering::Relaxed) {
+            let mut uart = unsafe { self.id.force_steal_regs() };
+            critical_section::with(|cs| {
+                let context = TX_CONTEXTS[idx].borrow(cs).borrow();
+                if context.progress >= context.slice.len().unwrap_or(0)
+                    && !uart.read_tx_status().tx_busy()
+                {
+                    finish_transfer(&mut uart, idx);
+                }
             });
-            return core::task::Poll::Ready(Ok(progress));
+        }
+        if TX_DONE[idx].swap(false, Ordering::Relaxed) {
+            let context = critical_section::with(|cs| *TX_CONTEXTS[idx].borrow(cs).borrow());
+            if context.tx_overrun {
+                return core::task::Poll::Ready(Err(TxOverrunError));
+            }
+            return core::task::Poll::Ready(Ok(context.progress));
         }
         core::task::Poll::Pending
     }
@@ -162,23 +265,78 @@ impl Future for TxFuture {
 
 impl Drop for TxFuture {
     fn drop(&mut self) {
-        let mut reg_block = unsafe { self.id.steal_regs() };
+        let mut reg_block = unsafe { self.id.force_steal_regs() };
 
         disable_tx_interrupts(&mut reg_block);
         disable_tx(&mut reg_block);
+        TX_ACTIVE[self.id as usize].store(false, Ordering::Relaxed);
     }
 }
 
 pub struct TxAsync(Tx);
 
 impl TxAsync {
-    pub fn new(tx: Tx) -> Self {
+    /// Create a new asynchronous transmitter.
+    ///
+    /// The transmitter is enabled if it is disabled, for example because the UART was created
+    /// with [super::Config::enable_tx] set to [false]. It is enabled by each write anyway, but
+    /// enabling it here makes the enable state consistent with the receiver drivers.
+    pub fn new(mut tx: Tx) -> Self {
+        if !tx.is_enabled() {
+            tx.enable();
+        }
         Self(tx)
     }
 
     pub fn release(self) -> Tx {
         self.0
     }
+
+    /// Configuration the UART was initialized with.
+    #[inline]
+    pub const fn config(&self) -> &super::Config {
+        self.0.config()
+    }
+
+    /// See [Tx::set_tx_fifo_trigger].
+    #[inline]
+    pub fn set_tx_fifo_trigger(&mut self, level: u5) {
+        self.0.set_tx_fifo_trigger(level);
+    }
+
+    /// Clean up after a write future was dropped before its completion, for example by a select
+    /// on a timeout.
+    ///
+    /// Dropping the future disables the TX interrupts and the transmitter. This additionally
+    /// clears the TX FIFO, so the data which was not transmitted yet is not sent when the
+    /// transmitter is enabled again. The next write can be started immediately afterwards.
+    pub fn abort_write(&mut self) {
+        TX_ACTIVE[self.0.id as usize].store(false, Ordering::Relaxed);
+        self.0.disable_interrupts();
+        self.0.disable();
+        self.0.clear_fifo();
+    }
+
+    /// Write a buffer with an upper bound for the transfer time, using the embassy time driver.
+    ///
+    /// If the timeout expires, for example because the transmitter is blocked by the flow
+    /// control, the transfer is cleaned up with [Self::abort_write] and
+    /// [AsyncTimeoutError::Timeout] is returned. Part of the buffer might have been transmitted
+    /// in that case.
+    #[cfg(feature = "async-timeouts")]
+    pub async fn write_timeout(
+        &mut self,
+        buf: &[u8],
+        timeout: embassy_time::Duration,
+    ) -> Result<usize, AsyncTimeoutError<TxOverrunError>> {
+        match crate::time::with_timeout(timeout, self.write(buf)).await {
+            Ok(result) => result.map_err(AsyncTimeoutError::Operation),
+            Err(_) => {
+                self.abort_write();
+                Err(AsyncTimeoutError::Timeout)
+            }
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -0,0 +1,27 @@
+//! Checks that the interrupt handler declaration macros expand to valid handlers for the
+//! selected family. The check is performed at compile time, so this test does not contain any
+//! test functions.
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+use heapless::spsc::Producer;
+
+static UART0_PRODUCER: Mutex<RefCell<Option<Producer<'static, u8>>>> =
+    Mutex::new(RefCell::new(None));
+
+#[cfg(feature = "vor1x")]
+mod vor1x {
+    vorago_shared_hal::embassy_time_driver_irqs!(timekeeper = OC30, alarm = OC29);
+    vorago_shared_hal::uart_rx_async_irqs!(Uart0 => OC2, producer = super::UART0_PRODUCER, with_tx);
+    vorago_shared_hal::uart_rx_async_irqs!(Uart1 => OC3, producer = super::UART0_PRODUCER);
+    vorago_shared_hal::uart_tx_async_irqs!(Uart1 => OC4);
+    vorago_shared_hal::gpio_async_irqs!(PortA => OC10, PortB => OC11);
+}
+
+#[cfg(feature = "vor4x")]
+mod vor4x {
+    vorago_shared_hal::embassy_time_driver_irqs!(timekeeper = TIM23, alarm = TIM22);
+    vorago_shared_hal::uart_rx_async_irqs!(Uart0 => UART0_RX, producer = super::UART0_PRODUCER);
+    vorago_shared_hal::uart_tx_async_irqs!(Uart0 => UART0_TX, Uart1 => UART1_TX);
+    vorago_shared_hal::gpio_async_irqs!(PortA => PORTA0, PortA => PORTA1, PortF => PORTF15);
+}
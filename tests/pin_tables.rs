@@ -0,0 +1,1305 @@
+//! Checks for the peripheral pin tables.
+//!
+//! Every pin mapping advertised by the UART, SPI and timer pin tables is listed here with its
+//! expected bank and function select. The lists use the same `va41628` gating as the pin tables
+//! and only compile if every listed pin implements the respective trait, so running this test
+//! for each supported feature combination catches missing or wrong `cfg` attributes:
+//!
+//! ```sh
+//! cargo test --features vor1x --test pin_tables
+//! cargo test --features vor4x --test pin_tables
+//! cargo test --features "vor4x va41628" --test pin_tables
+//! ```
+use vorago_shared_hal::{
+    FunctionSelect,
+    pins::DynPinId,
+    pwm::{PwmA, PwmPin},
+    spi::{self, HwChipSelectId, HwCsProvider, PinMiso, PinMosi, PinSck, Spi},
+    timer::TimPin,
+    uart::{self, RxPin, TxPin, Uart},
+};
+
+/// Also checks that the pin is listed in the run-time pin table.
+fn tx<P: TxPin>() -> (uart::Bank, FunctionSelect) {
+    assert!(uart::is_valid_pin_mapping(
+        uart::TX_PIN_MAPPINGS,
+        P::BANK,
+        P::ID,
+        P::FUN_SEL
+    ));
+    (P::BANK, P::FUN_SEL)
+}
+
+/// Also checks that the pin is listed in the run-time pin table.
+fn rx<P: RxPin>() -> (uart::Bank, FunctionSelect) {
+    assert!(uart::is_valid_pin_mapping(
+        uart::RX_PIN_MAPPINGS,
+        P::BANK,
+        P::ID,
+        P::FUN_SEL
+    ));
+    (P::BANK, P::FUN_SEL)
+}
+
+fn sck<P: PinSck>() -> (spi::Bank, FunctionSelect) {
+    (P::SPI_ID, P::FUN_SEL)
+}
+
+fn mosi<P: PinMosi>() -> (spi::Bank, FunctionSelect) {
+    (P::SPI_ID, P::FUN_SEL)
+}
+
+fn miso<P: PinMiso>() -> (spi::Bank, FunctionSelect) {
+    (P::SPI_ID, P::FUN_SEL)
+}
+
+fn hw_cs<P: HwCsProvider>() -> (DynPinId, spi::Bank, FunctionSelect, HwChipSelectId) {
+    (
+        P::PIN_ID.expect("HW CS provider without pin"),
+        P::SPI_ID,
+        P::FUN_SEL,
+        P::CS_ID,
+    )
+}
+
+fn rom_cs() -> (Option<DynPinId>, spi::Bank, HwChipSelectId) {
+    (
+        <spi::RomCs as HwCsProvider>::PIN_ID,
+        <spi::RomCs as HwCsProvider>::SPI_ID,
+        <spi::RomCs as HwCsProvider>::CS_ID,
+    )
+}
+
+fn tim<P: TimPin>() -> (DynPinId, FunctionSelect, u8) {
+    (P::PIN_ID, P::FUN_SEL, P::TIM_ID.value())
+}
+
+#[cfg(feature = "vor1x")]
+mod vor1x {
+    use super::*;
+    use va108xx as pac;
+    use vorago_shared_hal::pins::*;
+    use vorago_shared_hal::spi::pins_vor1x::*;
+
+    #[test]
+    fn uart_pins() {
+        use vorago_shared_hal::uart::Bank;
+        assert_eq!(tx::<Pin<Pa9>>(), (Bank::Uart0, FunctionSelect::Sel2));
+        assert_eq!(rx::<Pin<Pa8>>(), (Bank::Uart0, FunctionSelect::Sel2));
+        assert_eq!(tx::<Pin<Pa17>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pa16>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pa31>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pa30>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pb9>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pb8>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(tx::<Pin<Pb23>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pb22>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(tx::<Pin<Pa3>>(), (Bank::Uart1, FunctionSelect::Sel2));
+        assert_eq!(rx::<Pin<Pa2>>(), (Bank::Uart1, FunctionSelect::Sel2));
+        assert_eq!(tx::<Pin<Pa19>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pa18>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pa27>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pa26>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pb7>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pb6>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(tx::<Pin<Pb19>>(), (Bank::Uart1, FunctionSelect::Sel2));
+        assert_eq!(rx::<Pin<Pb18>>(), (Bank::Uart1, FunctionSelect::Sel2));
+        assert_eq!(tx::<Pin<Pb21>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pb20>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(uart::TX_PIN_MAPPINGS.len(), 11);
+        assert_eq!(uart::RX_PIN_MAPPINGS.len(), 11);
+    }
+
+    #[test]
+    fn spi_pins() {
+        use vorago_shared_hal::spi::Bank;
+        assert_eq!(spi::ROM_SPI_BANK, Bank::Spi2);
+        assert_eq!(rom_cs(), (None, Bank::Spi2, HwChipSelectId::Id0));
+        assert_eq!(sck::<Pin<Pa31>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(mosi::<Pin<Pa30>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(miso::<Pin<Pa29>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(sck::<Pin<Pb9>>(), (Bank::Spi0, FunctionSelect::Sel2));
+        assert_eq!(mosi::<Pin<Pb8>>(), (Bank::Spi0, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pb7>>(), (Bank::Spi0, FunctionSelect::Sel2));
+        assert_eq!(
+            hw_cs::<Pin<Pb0>>(),
+            (
+                Pb0::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb1>>(),
+            (
+                Pb1::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb2>>(),
+            (
+                Pb2::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb3>>(),
+            (
+                Pb3::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb4>>(),
+            (
+                Pb4::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb5>>(),
+            (
+                Pb5::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb6>>(),
+            (
+                Pb6::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa24>>(),
+            (
+                Pa24::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa25>>(),
+            (
+                Pa25::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa26>>(),
+            (
+                Pa26::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa27>>(),
+            (
+                Pa27::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa28>>(),
+            (
+                Pa28::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb0SpiaHwCsId1>(),
+            (
+                Pb0::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb1SpiaHwCsId2>(),
+            (
+                Pb1::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb2SpiaHwCsId3>(),
+            (
+                Pb2::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa21SpiaHwCsId7>(),
+            (
+                Pa21::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa22SpiaHwCsId6>(),
+            (
+                Pa22::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa23SpiaHwCsId5>(),
+            (
+                Pa23::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(sck::<Pin<Pa20>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(mosi::<Pin<Pa19>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pa18>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(sck::<Pin<Pb19>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(mosi::<Pin<Pb18>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(miso::<Pin<Pb17>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(sck::<Pin<Pb5>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(mosi::<Pin<Pb4>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(miso::<Pin<Pb3>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(
+            hw_cs::<Pin<Pb16>>(),
+            (
+                Pb16::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb15>>(),
+            (
+                Pb15::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb14>>(),
+            (
+                Pb14::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb13>>(),
+            (
+                Pb13::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa17>>(),
+            (
+                Pa17::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa16>>(),
+            (
+                Pa16::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa15>>(),
+            (
+                Pa15::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa14>>(),
+            (
+                Pa14::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa13>>(),
+            (
+                Pa13::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa12>>(),
+            (
+                Pa12::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa11>>(),
+            (
+                Pa11::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa10>>(),
+            (
+                Pa10::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa23>>(),
+            (
+                Pa23::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa22>>(),
+            (
+                Pa22::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa21>>(),
+            (
+                Pa21::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb0SpibHwCsId2>(),
+            (
+                Pb0::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb1SpibHwCsId1>(),
+            (
+                Pb1::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb2SpibHwCsId0>(),
+            (
+                Pb2::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb10SpibHwCsId6>(),
+            (
+                Pb10::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb11SpibHwCsId5>(),
+            (
+                Pb11::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb12SpibHwCsId4>(),
+            (
+                Pb12::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb10SpibHwCsId2>(),
+            (
+                Pb10::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb11SpibHwCsId1>(),
+            (
+                Pb11::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPb12SpibHwCsId0>(),
+            (
+                Pb12::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa21SpibHwCsId7>(),
+            (
+                Pa21::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa22SpibHwCsId6>(),
+            (
+                Pa22::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa23SpibHwCsId5>(),
+            (
+                Pa23::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb9>>(),
+            (
+                Pb9::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb8>>(),
+            (
+                Pb8::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb7>>(),
+            (
+                Pb7::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb23>>(),
+            (
+                Pb23::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb22>>(),
+            (
+                Pb22::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa20>>(),
+            (
+                Pa20::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa19>>(),
+            (
+                Pa19::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb18>>(),
+            (
+                Pb18::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa21SpicHwCsId3>(),
+            (
+                Pa21::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa22SpicHwCsId2>(),
+            (
+                Pa22::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa23SpicHwCsId1>(),
+            (
+                Pa23::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa20SpicHwCsId1>(),
+            (
+                Pa20::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<PinPa20SpicHwCsId4>(),
+            (
+                Pa20::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id4
+            )
+        );
+    }
+
+    #[test]
+    fn tim_pins() {
+        assert_eq!(tim::<Pin<Pa0>>(), (Pa0::ID, FunctionSelect::Sel1, 0));
+        assert_eq!(tim::<Pin<Pa1>>(), (Pa1::ID, FunctionSelect::Sel1, 1));
+        assert_eq!(tim::<Pin<Pa2>>(), (Pa2::ID, FunctionSelect::Sel1, 2));
+        assert_eq!(tim::<Pin<Pa3>>(), (Pa3::ID, FunctionSelect::Sel1, 3));
+        assert_eq!(tim::<Pin<Pa4>>(), (Pa4::ID, FunctionSelect::Sel1, 4));
+        assert_eq!(tim::<Pin<Pa5>>(), (Pa5::ID, FunctionSelect::Sel1, 5));
+        assert_eq!(tim::<Pin<Pa6>>(), (Pa6::ID, FunctionSelect::Sel1, 6));
+        assert_eq!(tim::<Pin<Pa7>>(), (Pa7::ID, FunctionSelect::Sel1, 7));
+        assert_eq!(tim::<Pin<Pa8>>(), (Pa8::ID, FunctionSelect::Sel1, 8));
+        assert_eq!(tim::<Pin<Pa9>>(), (Pa9::ID, FunctionSelect::Sel1, 9));
+        assert_eq!(tim::<Pin<Pa10>>(), (Pa10::ID, FunctionSelect::Sel1, 10));
+        assert_eq!(tim::<Pin<Pa11>>(), (Pa11::ID, FunctionSelect::Sel1, 11));
+        assert_eq!(tim::<Pin<Pa12>>(), (Pa12::ID, FunctionSelect::Sel1, 12));
+        assert_eq!(tim::<Pin<Pa13>>(), (Pa13::ID, FunctionSelect::Sel1, 13));
+        assert_eq!(tim::<Pin<Pa14>>(), (Pa14::ID, FunctionSelect::Sel1, 14));
+        assert_eq!(tim::<Pin<Pa15>>(), (Pa15::ID, FunctionSelect::Sel1, 15));
+        assert_eq!(tim::<Pin<Pa24>>(), (Pa24::ID, FunctionSelect::Sel2, 16));
+        assert_eq!(tim::<Pin<Pa25>>(), (Pa25::ID, FunctionSelect::Sel2, 17));
+        assert_eq!(tim::<Pin<Pa26>>(), (Pa26::ID, FunctionSelect::Sel2, 18));
+        assert_eq!(tim::<Pin<Pa27>>(), (Pa27::ID, FunctionSelect::Sel2, 19));
+        assert_eq!(tim::<Pin<Pa28>>(), (Pa28::ID, FunctionSelect::Sel2, 20));
+        assert_eq!(tim::<Pin<Pa29>>(), (Pa29::ID, FunctionSelect::Sel2, 21));
+        assert_eq!(tim::<Pin<Pa30>>(), (Pa30::ID, FunctionSelect::Sel2, 22));
+        assert_eq!(tim::<Pin<Pa31>>(), (Pa31::ID, FunctionSelect::Sel2, 23));
+        assert_eq!(tim::<Pin<Pb0>>(), (Pb0::ID, FunctionSelect::Sel3, 0));
+        assert_eq!(tim::<Pin<Pb1>>(), (Pb1::ID, FunctionSelect::Sel3, 1));
+        assert_eq!(tim::<Pin<Pb2>>(), (Pb2::ID, FunctionSelect::Sel3, 2));
+        assert_eq!(tim::<Pin<Pb3>>(), (Pb3::ID, FunctionSelect::Sel3, 3));
+        assert_eq!(tim::<Pin<Pb4>>(), (Pb4::ID, FunctionSelect::Sel3, 4));
+        assert_eq!(tim::<Pin<Pb5>>(), (Pb5::ID, FunctionSelect::Sel3, 5));
+        assert_eq!(tim::<Pin<Pb6>>(), (Pb6::ID, FunctionSelect::Sel3, 6));
+        assert_eq!(tim::<Pin<Pb10>>(), (Pb10::ID, FunctionSelect::Sel3, 10));
+        assert_eq!(tim::<Pin<Pb11>>(), (Pb11::ID, FunctionSelect::Sel3, 11));
+        assert_eq!(tim::<Pin<Pb12>>(), (Pb12::ID, FunctionSelect::Sel3, 12));
+        assert_eq!(tim::<Pin<Pb13>>(), (Pb13::ID, FunctionSelect::Sel3, 13));
+        assert_eq!(tim::<Pin<Pb14>>(), (Pb14::ID, FunctionSelect::Sel3, 14));
+        assert_eq!(tim::<Pin<Pb15>>(), (Pb15::ID, FunctionSelect::Sel3, 15));
+        assert_eq!(tim::<Pin<Pb16>>(), (Pb16::ID, FunctionSelect::Sel3, 16));
+        assert_eq!(tim::<Pin<Pb17>>(), (Pb17::ID, FunctionSelect::Sel3, 17));
+        assert_eq!(tim::<Pin<Pb18>>(), (Pb18::ID, FunctionSelect::Sel3, 18));
+        assert_eq!(tim::<Pin<Pb19>>(), (Pb19::ID, FunctionSelect::Sel3, 19));
+        assert_eq!(tim::<Pin<Pb20>>(), (Pb20::ID, FunctionSelect::Sel3, 20));
+        assert_eq!(tim::<Pin<Pb21>>(), (Pb21::ID, FunctionSelect::Sel3, 21));
+        assert_eq!(tim::<Pin<Pb22>>(), (Pb22::ID, FunctionSelect::Sel3, 22));
+        assert_eq!(tim::<Pin<Pb23>>(), (Pb23::ID, FunctionSelect::Sel3, 23));
+    }
+
+    /// Only names the constructors with concrete pin types, which checks the trait bounds
+    /// without touching any peripheral.
+    #[test]
+    fn driver_constructors() {
+        let _ = Uart::new::<uart::Uart0, Pin<Pa9>, Pin<Pa8>>;
+        let _ = Uart::new::<uart::Uart1, Pin<Pa3>, Pin<Pa2>>;
+        let _ = Spi::<u8>::new::<spi::Spi0, Pin<Pa31>, Pin<Pa29>, Pin<Pa30>>;
+        let _ = Spi::<u8>::new_for_rom::<spi::Spi2>;
+        let _ = PwmPin::<PwmA>::new::<Pin<Pa0>, pac::Tim0>;
+    }
+
+    /// The pin validation fails before any peripheral is touched.
+    #[test]
+    fn uart_dynamic_pin_validation() {
+        use fugit::RateExtU32;
+        // PA9 is the TX pin of UART0, not of UART1.
+        let result = unsafe {
+            Uart::new_dynamic(
+                uart::Bank::Uart1,
+                Pa9::ID,
+                Pa8::ID,
+                FunctionSelect::Sel2,
+                FunctionSelect::Sel2,
+                50.MHz(),
+                uart::Config::default(),
+                None,
+            )
+        };
+        assert_eq!(
+            result.err(),
+            Some(uart::UartInitError::InvalidPin(uart::InvalidUartPinError {
+                bank: uart::Bank::Uart1,
+                pin: Pa9::ID,
+                fun_sel: FunctionSelect::Sel2,
+            }))
+        );
+        // Wrong function select of the RX pin.
+        let result = unsafe {
+            Uart::new_dynamic(
+                uart::Bank::Uart0,
+                Pa9::ID,
+                Pa8::ID,
+                FunctionSelect::Sel2,
+                FunctionSelect::Sel3,
+                50.MHz(),
+                uart::Config::default(),
+                None,
+            )
+        };
+        assert!(matches!(
+            result.err(),
+            Some(uart::UartInitError::InvalidPin(uart::InvalidUartPinError { pin, .. })) if pin == Pa8::ID
+        ));
+    }
+}
+
+#[cfg(feature = "vor4x")]
+mod vor4x {
+    use super::*;
+    use va416xx as pac;
+    use vorago_shared_hal::pins::*;
+    #[cfg(not(feature = "va41628"))]
+    use vorago_shared_hal::spi::pins_vor4x::*;
+
+    #[test]
+    fn uart_pins() {
+        use vorago_shared_hal::uart::Bank;
+        assert_eq!(tx::<Pin<Pa2>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pa3>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pc4>>(), (Bank::Uart0, FunctionSelect::Sel2));
+        assert_eq!(rx::<Pin<Pc5>>(), (Bank::Uart0, FunctionSelect::Sel2));
+        assert_eq!(tx::<Pin<Pe2>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pe3>>(), (Bank::Uart0, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pg0>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pg1>>(), (Bank::Uart0, FunctionSelect::Sel1));
+        assert_eq!(tx::<Pin<Pb14>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pb15>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pd11>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(rx::<Pin<Pd12>>(), (Bank::Uart1, FunctionSelect::Sel3));
+        assert_eq!(tx::<Pin<Pf12>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pf13>>(), (Bank::Uart1, FunctionSelect::Sel1));
+        assert_eq!(tx::<Pin<Pc14>>(), (Bank::Uart2, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(rx::<Pin<Pc15>>(), (Bank::Uart2, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tx::<Pin<Pf8>>(), (Bank::Uart2, FunctionSelect::Sel1));
+        assert_eq!(rx::<Pin<Pf9>>(), (Bank::Uart2, FunctionSelect::Sel1));
+        #[cfg(not(feature = "va41628"))]
+        let num_uart2_pins = 2;
+        #[cfg(feature = "va41628")]
+        let num_uart2_pins = 1;
+        assert_eq!(uart::TX_PIN_MAPPINGS.len(), 7 + num_uart2_pins);
+        assert_eq!(uart::RX_PIN_MAPPINGS.len(), 7 + num_uart2_pins);
+    }
+
+    #[test]
+    fn spi_pins() {
+        use vorago_shared_hal::spi::Bank;
+        assert_eq!(spi::ROM_SPI_BANK, Bank::Spi3);
+        assert_eq!(rom_cs(), (None, Bank::Spi3, HwChipSelectId::Id0));
+        assert_eq!(sck::<Pin<Pb15>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(mosi::<Pin<Pc1>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(miso::<Pin<Pc0>>(), (Bank::Spi0, FunctionSelect::Sel1));
+        assert_eq!(
+            hw_cs::<Pin<Pb14>>(),
+            (
+                Pb14::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb13>>(),
+            (
+                Pb13::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb12>>(),
+            (
+                Pb12::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id2
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pb11>>(),
+            (
+                Pb11::ID,
+                Bank::Spi0,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id3
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(sck::<Pin<Pb8>>(), (Bank::Spi1, FunctionSelect::Sel3));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(mosi::<Pin<Pb10>>(), (Bank::Spi1, FunctionSelect::Sel3));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(miso::<Pin<Pb9>>(), (Bank::Spi1, FunctionSelect::Sel3));
+        assert_eq!(sck::<Pin<Pc9>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(mosi::<Pin<Pc11>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pc10>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(sck::<Pin<Pe13>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(mosi::<Pin<Pe15>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pe14>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(sck::<Pin<Pf3>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(mosi::<Pin<Pf5>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(miso::<Pin<Pf4>>(), (Bank::Spi1, FunctionSelect::Sel1));
+        assert_eq!(sck::<Pin<Pg3>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pg4>>(), (Bank::Spi1, FunctionSelect::Sel2));
+        assert_eq!(
+            hw_cs::<Pin<Pb4>>(),
+            (
+                Pb4::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb3>>(),
+            (
+                Pb3::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb2>>(),
+            (
+                Pb2::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb1>>(),
+            (
+                Pb1::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pb0>>(),
+            (
+                Pb0::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pc8>>(),
+            (
+                Pc8::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pc7>>(),
+            (
+                Pc7::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe12>>(),
+            (
+                Pe12::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe9>>(),
+            (
+                Pe9::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe8>>(),
+            (
+                Pe8::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe7>>(),
+            (
+                Pe7::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe6>>(),
+            (
+                Pe6::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pe5>>(),
+            (
+                Pe5::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id7
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pg2>>(),
+            (
+                Pg2::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pb7>>(),
+            (
+                Pb7::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id0
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pb6>>(),
+            (
+                Pb6::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id1
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pb5>>(),
+            (
+                Pb5::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel3,
+                HwChipSelectId::Id2
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pe11>>(),
+            (
+                Pe11::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pe10>>(),
+            (
+                Pe10::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<PinPf2Spi1HwCsId0>(),
+            (
+                Pf2::ID,
+                Bank::Spi1,
+                FunctionSelect::Sel1,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(sck::<Pin<Pa5>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        assert_eq!(mosi::<Pin<Pa7>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        assert_eq!(miso::<Pin<Pa6>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(sck::<Pin<Pf5>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(mosi::<Pin<Pf7>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(miso::<Pin<Pf6>>(), (Bank::Spi2, FunctionSelect::Sel2));
+        assert_eq!(
+            hw_cs::<Pin<Pa4>>(),
+            (
+                Pa4::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa3>>(),
+            (
+                Pa3::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa2>>(),
+            (
+                Pa2::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa1>>(),
+            (
+                Pa1::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa0>>(),
+            (
+                Pa0::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa8>>(),
+            (
+                Pa8::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id5
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pa9>>(),
+            (
+                Pa9::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id6
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pf0>>(),
+            (
+                Pf0::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id4
+            )
+        );
+        assert_eq!(
+            hw_cs::<Pin<Pf1>>(),
+            (
+                Pf1::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id3
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pf3>>(),
+            (
+                Pf3::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id1
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<Pin<Pf4>>(),
+            (
+                Pf4::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id0
+            )
+        );
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(
+            hw_cs::<PinPf2Spi2HwCsId2>(),
+            (
+                Pf2::ID,
+                Bank::Spi2,
+                FunctionSelect::Sel2,
+                HwChipSelectId::Id2
+            )
+        );
+    }
+
+    #[test]
+    fn tim_pins() {
+        assert_eq!(tim::<Pin<Pa0>>(), (Pa0::ID, FunctionSelect::Sel1, 0));
+        assert_eq!(tim::<Pin<Pa1>>(), (Pa1::ID, FunctionSelect::Sel1, 1));
+        assert_eq!(tim::<Pin<Pa2>>(), (Pa2::ID, FunctionSelect::Sel1, 2));
+        assert_eq!(tim::<Pin<Pa3>>(), (Pa3::ID, FunctionSelect::Sel1, 3));
+        assert_eq!(tim::<Pin<Pa4>>(), (Pa4::ID, FunctionSelect::Sel1, 4));
+        assert_eq!(tim::<Pin<Pa5>>(), (Pa5::ID, FunctionSelect::Sel1, 5));
+        assert_eq!(tim::<Pin<Pa6>>(), (Pa6::ID, FunctionSelect::Sel1, 6));
+        assert_eq!(tim::<Pin<Pa7>>(), (Pa7::ID, FunctionSelect::Sel1, 7));
+        assert_eq!(tim::<Pin<Pa8>>(), (Pa8::ID, FunctionSelect::Sel3, 8));
+        assert_eq!(tim::<Pin<Pa10>>(), (Pa10::ID, FunctionSelect::Sel2, 23));
+        assert_eq!(tim::<Pin<Pa11>>(), (Pa11::ID, FunctionSelect::Sel2, 22));
+        assert_eq!(tim::<Pin<Pa12>>(), (Pa12::ID, FunctionSelect::Sel2, 21));
+        assert_eq!(tim::<Pin<Pa13>>(), (Pa13::ID, FunctionSelect::Sel2, 20));
+        assert_eq!(tim::<Pin<Pa14>>(), (Pa14::ID, FunctionSelect::Sel2, 19));
+        assert_eq!(tim::<Pin<Pa15>>(), (Pa15::ID, FunctionSelect::Sel2, 18));
+        assert_eq!(tim::<Pin<Pb0>>(), (Pb0::ID, FunctionSelect::Sel2, 17));
+        assert_eq!(tim::<Pin<Pb1>>(), (Pb1::ID, FunctionSelect::Sel2, 16));
+        assert_eq!(tim::<Pin<Pb2>>(), (Pb2::ID, FunctionSelect::Sel2, 15));
+        assert_eq!(tim::<Pin<Pb3>>(), (Pb3::ID, FunctionSelect::Sel2, 14));
+        assert_eq!(tim::<Pin<Pb4>>(), (Pb4::ID, FunctionSelect::Sel2, 13));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb5>>(), (Pb5::ID, FunctionSelect::Sel2, 12));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb6>>(), (Pb6::ID, FunctionSelect::Sel2, 11));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb7>>(), (Pb7::ID, FunctionSelect::Sel2, 10));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb8>>(), (Pb8::ID, FunctionSelect::Sel2, 9));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb9>>(), (Pb9::ID, FunctionSelect::Sel2, 8));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb10>>(), (Pb10::ID, FunctionSelect::Sel2, 7));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pb11>>(), (Pb11::ID, FunctionSelect::Sel2, 6));
+        assert_eq!(tim::<Pin<Pb12>>(), (Pb12::ID, FunctionSelect::Sel2, 5));
+        assert_eq!(tim::<Pin<Pb13>>(), (Pb13::ID, FunctionSelect::Sel2, 4));
+        assert_eq!(tim::<Pin<Pb14>>(), (Pb14::ID, FunctionSelect::Sel2, 3));
+        assert_eq!(tim::<Pin<Pb15>>(), (Pb15::ID, FunctionSelect::Sel2, 2));
+        assert_eq!(tim::<Pin<Pc0>>(), (Pc0::ID, FunctionSelect::Sel2, 1));
+        assert_eq!(tim::<Pin<Pc1>>(), (Pc1::ID, FunctionSelect::Sel2, 0));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd0>>(), (Pd0::ID, FunctionSelect::Sel2, 0));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd1>>(), (Pd1::ID, FunctionSelect::Sel2, 1));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd2>>(), (Pd2::ID, FunctionSelect::Sel2, 2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd3>>(), (Pd3::ID, FunctionSelect::Sel2, 3));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd4>>(), (Pd4::ID, FunctionSelect::Sel2, 4));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd5>>(), (Pd5::ID, FunctionSelect::Sel2, 5));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd6>>(), (Pd6::ID, FunctionSelect::Sel2, 6));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd7>>(), (Pd7::ID, FunctionSelect::Sel2, 7));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd8>>(), (Pd8::ID, FunctionSelect::Sel2, 8));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pd9>>(), (Pd9::ID, FunctionSelect::Sel2, 9));
+        assert_eq!(tim::<Pin<Pd10>>(), (Pd10::ID, FunctionSelect::Sel2, 10));
+        assert_eq!(tim::<Pin<Pd11>>(), (Pd11::ID, FunctionSelect::Sel2, 11));
+        assert_eq!(tim::<Pin<Pd12>>(), (Pd12::ID, FunctionSelect::Sel2, 12));
+        assert_eq!(tim::<Pin<Pd13>>(), (Pd13::ID, FunctionSelect::Sel2, 13));
+        assert_eq!(tim::<Pin<Pd14>>(), (Pd14::ID, FunctionSelect::Sel2, 14));
+        assert_eq!(tim::<Pin<Pd15>>(), (Pd15::ID, FunctionSelect::Sel2, 15));
+        assert_eq!(tim::<Pin<Pe0>>(), (Pe0::ID, FunctionSelect::Sel2, 16));
+        assert_eq!(tim::<Pin<Pe1>>(), (Pe1::ID, FunctionSelect::Sel2, 17));
+        assert_eq!(tim::<Pin<Pe2>>(), (Pe2::ID, FunctionSelect::Sel2, 18));
+        assert_eq!(tim::<Pin<Pe3>>(), (Pe3::ID, FunctionSelect::Sel2, 19));
+        assert_eq!(tim::<Pin<Pe4>>(), (Pe4::ID, FunctionSelect::Sel2, 20));
+        assert_eq!(tim::<Pin<Pe5>>(), (Pe5::ID, FunctionSelect::Sel2, 21));
+        assert_eq!(tim::<Pin<Pe6>>(), (Pe6::ID, FunctionSelect::Sel2, 22));
+        assert_eq!(tim::<Pin<Pe7>>(), (Pe7::ID, FunctionSelect::Sel2, 23));
+        assert_eq!(tim::<Pin<Pe8>>(), (Pe8::ID, FunctionSelect::Sel3, 16));
+        assert_eq!(tim::<Pin<Pe9>>(), (Pe9::ID, FunctionSelect::Sel3, 17));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pe10>>(), (Pe10::ID, FunctionSelect::Sel3, 18));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pe11>>(), (Pe11::ID, FunctionSelect::Sel3, 19));
+        assert_eq!(tim::<Pin<Pe12>>(), (Pe12::ID, FunctionSelect::Sel3, 20));
+        assert_eq!(tim::<Pin<Pe13>>(), (Pe13::ID, FunctionSelect::Sel3, 21));
+        assert_eq!(tim::<Pin<Pe14>>(), (Pe14::ID, FunctionSelect::Sel3, 22));
+        assert_eq!(tim::<Pin<Pe15>>(), (Pe15::ID, FunctionSelect::Sel3, 23));
+        assert_eq!(tim::<Pin<Pf0>>(), (Pf0::ID, FunctionSelect::Sel3, 0));
+        assert_eq!(tim::<Pin<Pf1>>(), (Pf1::ID, FunctionSelect::Sel3, 1));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf2>>(), (Pf2::ID, FunctionSelect::Sel3, 2));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf3>>(), (Pf3::ID, FunctionSelect::Sel3, 3));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf4>>(), (Pf4::ID, FunctionSelect::Sel3, 4));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf5>>(), (Pf5::ID, FunctionSelect::Sel3, 5));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf6>>(), (Pf6::ID, FunctionSelect::Sel3, 6));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf7>>(), (Pf7::ID, FunctionSelect::Sel3, 7));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf8>>(), (Pf8::ID, FunctionSelect::Sel3, 8));
+        assert_eq!(tim::<Pin<Pf9>>(), (Pf9::ID, FunctionSelect::Sel3, 9));
+        #[cfg(not(feature = "va41628"))]
+        assert_eq!(tim::<Pin<Pf10>>(), (Pf10::ID, FunctionSelect::Sel3, 10));
+        assert_eq!(tim::<Pin<Pf11>>(), (Pf11::ID, FunctionSelect::Sel3, 11));
+        assert_eq!(tim::<Pin<Pf12>>(), (Pf12::ID, FunctionSelect::Sel3, 12));
+        assert_eq!(tim::<Pin<Pf13>>(), (Pf13::ID, FunctionSelect::Sel2, 19));
+        assert_eq!(tim::<Pin<Pf14>>(), (Pf14::ID, FunctionSelect::Sel2, 20));
+        assert_eq!(tim::<Pin<Pf15>>(), (Pf15::ID, FunctionSelect::Sel2, 21));
+        assert_eq!(tim::<Pin<Pg0>>(), (Pg0::ID, FunctionSelect::Sel2, 22));
+        assert_eq!(tim::<Pin<Pg1>>(), (Pg1::ID, FunctionSelect::Sel2, 23));
+        assert_eq!(tim::<Pin<Pg2>>(), (Pg2::ID, FunctionSelect::Sel1, 9));
+        assert_eq!(tim::<Pin<Pg3>>(), (Pg3::ID, FunctionSelect::Sel1, 10));
+        assert_eq!(tim::<Pin<Pg6>>(), (Pg6::ID, FunctionSelect::Sel1, 12));
+    }
+
+    /// Only names the constructors with concrete pin types, which checks the trait bounds
+    /// without touching any peripheral.
+    #[test]
+    fn driver_constructors() {
+        let _ = Uart::new::<uart::Uart0, Pin<Pa2>, Pin<Pa3>>;
+        let _ = Uart::new::<uart::Uart1, Pin<Pb14>, Pin<Pb15>>;
+        #[cfg(not(feature = "va41628"))]
+        let _ = Uart::new::<pac::Uart2, Pin<Pc14>, Pin<Pc15>>;
+        let _ = Uart::new_tx_only::<pac::Uart2, Pin<Pc14>>;
+        let _ = Uart::new_rx_only::<pac::Uart2, Pin<Pf9>>;
+        let _ = Spi::<u8>::new::<spi::Spi0, Pin<Pb15>, Pin<Pc0>, Pin<Pc1>>;
+        let _ = Spi::<u8>::new_for_rom::<pac::Spi3>;
+        let _ = PwmPin::<PwmA>::new::<Pin<Pa0>, pac::Tim0>;
+    }
+}
@@ -0,0 +1,13 @@
+//! Checks that the prelude can be glob imported and provides the re-exported names.
+use vorago_shared_hal::prelude::*;
+
+#[test]
+fn config_aliases() {
+    let uart_cfg: vorago_shared_hal::uart::Config = UartConfig::default().baudrate(9600.Hz());
+    assert_eq!(uart_cfg.baudrate.raw(), 9600);
+    let i2c_cfg: vorago_shared_hal::i2c::MasterConfig = I2cMasterConfig::default();
+    assert_eq!(i2c_cfg, vorago_shared_hal::i2c::MasterConfig::default());
+    assert!(SpiConfig::default().blockmode);
+    let _: Hertz = 1.MHz();
+    assert_eq!(PinState::from(true), PinState::High);
+}
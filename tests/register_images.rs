@@ -0,0 +1,2245 @@
+//! Host-side checks of the register initialization performed by the driver constructors.
+//!
+//! The register blocks are backed by the RAM images of the `test-harness` feature:
+//!
+//! ```sh
+//! cargo test --features "vor1x test-harness" --test register_images
+//! cargo test --features "vor4x test-harness" --test register_images
+//! ```
+#![cfg(feature = "test-harness")]
+use core::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "vor1x")]
+use core::{
+    future::Future,
+    pin::{Pin, pin},
+    task::{Context, Poll, Waker},
+};
+
+use arbitrary_int::{u5, u20};
+use embedded_hal::spi::MODE_3;
+use fugit::RateExtU32;
+#[cfg(feature = "vor1x")]
+use va108xx as pac;
+#[cfg(feature = "vor4x")]
+use va416xx as pac;
+use vorago_shared_hal::{
+    PeripheralSelect, Port,
+    busy_wait::{self, BUSY_WAIT_HOOK_INTERVAL},
+    gpio,
+    i2c::{self, I2cAddress, I2cMaster, I2cSpeed, MasterConfig},
+    ownership::{self, RegisterBlock},
+    power::{SleepGuard, SleepParticipant},
+    pwm::{self, PwmPin},
+    spi::{
+        self, ChipSelect, HwChipSelectId, RawSpiWord, Spi, SpiClockConfig, SpiConfig,
+        SpiInterrupts, TransferConfig,
+    },
+    test_harness,
+    timer::{self, CountdownTimer, PhaseTrigger, TimInstance},
+    uart::{self, Uart},
+};
+
+fn clock_enabled(periph_sel: PeripheralSelect) -> bool {
+    (test_harness::peripheral_clk_enable() >> periph_sel as u32) & 1 == 1
+}
+
+#[test]
+fn uart_init_registers() {
+    let _harness = test_harness::lock();
+    let config = uart::Config::default()
+        .baudrate(115_200.Hz())
+        .parity_even()
+        .stopbits(uart::Stopbits::Two)
+        .rts_threshold(u5::new(12));
+    let _uart = unsafe {
+        Uart::steal(
+            uart::Bank::Uart1,
+            50.MHz(),
+            config,
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    };
+    let regs = uart::regs::Uart::new_mmio(uart::Bank::Uart1);
+    // 50 MHz / (16 * 115200) = 27.127, the fractional part is rounded to 8 / 64.
+    let clkscale = regs.read_clkscale();
+    assert_eq!(clkscale.int().value(), 27);
+    assert_eq!(clkscale.frac().value(), 8);
+    let ctrl = regs.read_ctrl();
+    assert!(!ctrl.baud8());
+    assert!(ctrl.parity_enable());
+    assert!(ctrl.parity_even());
+    assert!(!ctrl.parity_manual());
+    assert_eq!(ctrl.stopbits(), uart::Stopbits::Two);
+    assert_eq!(ctrl.wordsize(), uart::WordSize::Eight);
+    assert_eq!(regs.read_rx_fifo_rts_trigger().level(), u5::new(12));
+    let enable = regs.read_enable();
+    assert!(enable.tx());
+    assert!(enable.rx());
+    assert!(clock_enabled(PeripheralSelect::Uart1));
+    assert!(!clock_enabled(PeripheralSelect::Uart0));
+    // The other bank is not touched.
+    assert_eq!(test_harness::uart(uart::Bank::Uart0).read(0x08), 0);
+}
+
+#[test]
+fn spi_init_registers() {
+    let _harness = test_harness::lock();
+    let config = SpiConfig::default()
+        .mode(MODE_3)
+        .clk_cfg(SpiClockConfig::new(4, 9))
+        .blockmode(true)
+        .bmstall(false)
+        .loopback(true);
+    let _spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi2, config) };
+    let regs = spi::regs::Spi::new_mmio(spi::Bank::Spi2);
+    let ctrl0 = regs.read_ctrl0();
+    assert_eq!(ctrl0.scrdv(), 9);
+    assert!(ctrl0.spo());
+    assert!(ctrl0.sph());
+    assert_eq!(ctrl0.word_size(), Ok(spi::regs::WordSize::EightBits));
+    let ctrl1 = regs.read_ctrl1();
+    assert!(ctrl1.enable());
+    assert!(ctrl1.blockmode());
+    assert!(!ctrl1.bm_stall());
+    assert!(!ctrl1.bm_start());
+    assert!(!ctrl1.mdlycap());
+    assert!(!ctrl1.mtxpause());
+    assert!(!ctrl1.slave_mode());
+    assert!(ctrl1.lbm());
+    assert_eq!(ctrl1.ss(), HwChipSelectId::Id0);
+    assert_eq!(regs.read_clkprescale().value(), 4);
+    assert!(clock_enabled(PeripheralSelect::Spi2));
+}
+
+#[test]
+fn spi_master_delayer_capture_keeps_enable_state() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi2, SpiConfig::default()) };
+    let mut regs = spi::regs::Spi::new_mmio(spi::Bank::Spi2);
+    spi.set_master_delayer_capture(true);
+    assert!(spi.master_delayer_capture());
+    assert!(regs.read_ctrl1().enable());
+    // A disabled peripheral stays disabled.
+    regs.modify_ctrl1(|value| value.with_enable(false));
+    spi.set_master_delayer_capture(false);
+    assert!(!spi.master_delayer_capture());
+    assert!(!regs.read_ctrl1().enable());
+}
+
+#[test]
+fn spi_transfer_cfg_keeps_hw_cs_disabled() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    let regs = spi::regs::Spi::new_mmio(spi::Bank::Spi1);
+    spi.cfg_hw_cs(HwChipSelectId::Id3);
+    spi.cfg_hw_cs_disable();
+    assert_eq!(spi.current_cs(), ChipSelect::Gpio);
+    let transfer_cfg = TransferConfig {
+        clk_cfg: None,
+        mode: None,
+        sod: false,
+        blockmode: true,
+        bmstall: true,
+        hw_cs: None,
+    };
+    spi.cfg_transfer(&transfer_cfg);
+    assert_eq!(spi.current_cs(), ChipSelect::Gpio);
+    assert!(regs.read_ctrl1().sod());
+
+    spi.cfg_transfer(&TransferConfig {
+        hw_cs: Some(HwChipSelectId::Id2),
+        ..transfer_cfg
+    });
+    assert_eq!(spi.current_cs(), ChipSelect::Hw(HwChipSelectId::Id2));
+    let ctrl1 = regs.read_ctrl1();
+    assert!(!ctrl1.sod());
+    assert_eq!(ctrl1.ss(), HwChipSelectId::Id2);
+}
+
+#[test]
+fn spi_hw_cs_pin_bank_check() {
+    let _harness = test_harness::lock();
+    let mut cs = spi::HwCsPin::new(spi::RomCs);
+    assert_eq!(cs.pin_id(), None);
+    assert_eq!(cs.cs_id(), HwChipSelectId::Id0);
+    // The dedicated ROM chip select has no GPIO function.
+    assert_eq!(cs.as_gpio_temporarily(gpio::PinState::High, |_| ()), None);
+
+    let config = SpiConfig::default().slave_output_disable(true);
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::ROM_SPI_BANK, config) };
+    assert_eq!(spi.current_cs(), ChipSelect::None);
+    spi.cfg_hw_cs_pin(&cs).unwrap();
+    assert_eq!(spi.current_cs(), ChipSelect::Hw(HwChipSelectId::Id0));
+    assert!(
+        !spi::regs::Spi::new_mmio(spi::ROM_SPI_BANK)
+            .read_ctrl1()
+            .sod()
+    );
+
+    let mut other: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi0, config) };
+    assert!(other.cfg_hw_cs_pin(&cs).is_err());
+    assert_eq!(other.current_cs(), ChipSelect::None);
+}
+
+#[test]
+fn spi_word_size_16_bits() {
+    let _harness = test_harness::lock();
+    let _spi: Spi<u16> = unsafe { Spi::steal(spi::Bank::Spi0, SpiConfig::default()) };
+    let regs = spi::regs::Spi::new_mmio(spi::Bank::Spi0);
+    assert_eq!(
+        regs.read_ctrl0().word_size(),
+        Ok(spi::regs::WordSize::SixteenBits)
+    );
+}
+
+#[test]
+fn spi_fifo_prefill() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    assert_eq!(spi.fifo_depth(), 16);
+    assert_eq!(spi.fifo_prefill(), 12);
+    assert!(spi.set_fifo_prefill(17).is_err());
+    spi.set_fifo_prefill(16).unwrap();
+    assert_eq!(spi.fifo_prefill(), 16);
+
+    let config = SpiConfig::default().fifo_prefill(4).unwrap();
+    let spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, config) };
+    assert_eq!(spi.fifo_prefill(), 4);
+}
+
+fn image_snapshot<const WORDS: usize>(image: &test_harness::RegisterImage<WORDS>) -> Vec<u32> {
+    (0..WORDS).map(|word| image.read(word * 4)).collect()
+}
+
+/// Emulates an upset of the configuration registers at the given offsets. The peripheral reset
+/// of the `reinitialize` methods clears the register image.
+fn upset_registers<const WORDS: usize>(
+    image: &test_harness::RegisterImage<WORDS>,
+    offsets: &[usize],
+) {
+    for &offset in offsets {
+        image.write(offset, 0xFFFF_FFFF);
+    }
+}
+
+#[test]
+fn uart_reinitialize_replays_init() {
+    let _harness = test_harness::lock();
+    let mut uart = uart_with_config(
+        uart::Config::default()
+            .baudrate(115_200.Hz())
+            .parity_odd()
+            .rts_threshold(u5::new(12)),
+    );
+    let image = test_harness::uart(uart::Bank::Uart0);
+    let expected = image_snapshot(image);
+    // Enable, control and clock scale registers.
+    upset_registers(image, &[0x04, 0x08, 0x0C]);
+    uart.reinitialize();
+    assert_eq!(image_snapshot(image), expected);
+}
+
+#[test]
+fn uart_reinitialize_replays_driver_state() {
+    let _harness = test_harness::lock();
+    let mut uart = uart_with_config(uart::Config::default());
+    uart.listen(uart::Event::RxTimeout);
+    uart.listen(uart::Event::TxEmpty);
+    uart.unlisten(uart::Event::TxEmpty);
+    uart.disable_tx();
+    let image = test_harness::uart(uart::Bank::Uart0);
+    let expected = image_snapshot(image);
+    assert_eq!(image.read(0x04), 1 << 0);
+    assert_eq!(image.read(0x28), 1 << 2);
+    // Upset enable and interrupt enable registers are not replayed.
+    upset_registers(image, &[0x04, 0x28]);
+    uart.reinitialize();
+    assert_eq!(image_snapshot(image), expected);
+}
+
+#[test]
+fn spi_reinitialize_replays_init() {
+    let _harness = test_harness::lock();
+    let config = SpiConfig::default()
+        .mode(MODE_3)
+        .clk_cfg(SpiClockConfig::new(4, 9))
+        .blockmode(true)
+        .loopback(true);
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi2, config) };
+    let image = test_harness::spi(spi::Bank::Spi2);
+    let expected = image_snapshot(image);
+    // Control and clock prescaler registers.
+    upset_registers(image, &[0x00, 0x04, 0x10]);
+    spi.reinitialize();
+    assert_eq!(image_snapshot(image), expected);
+}
+
+#[test]
+fn i2c_reinitialize_replays_init() {
+    let _harness = test_harness::lock();
+    let config = MasterConfig {
+        loopback: true,
+        ..Default::default()
+    };
+    let mut i2c = i2c_master_with_config(config, I2cSpeed::Fast400khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    let expected = image_snapshot(image);
+    // Control and clock scale registers.
+    upset_registers(image, &[0x00, 0x04]);
+    i2c.reinitialize();
+    assert_eq!(image_snapshot(image), expected);
+}
+
+/// Clocks with a 100 MHz system clock. The ADC clock does not exist on the VA41628.
+#[cfg(feature = "vor4x")]
+fn test_clocks() -> vorago_shared_hal::clock::Clocks {
+    vorago_shared_hal::clock::Clocks::__new(
+        100.MHz(),
+        #[cfg(not(feature = "va41628"))]
+        12500.kHz(),
+    )
+}
+
+fn i2c_master(speed_mode: I2cSpeed) -> I2cMaster {
+    i2c_master_with_config(MasterConfig::default(), speed_mode)
+}
+
+fn i2c_master_with_config(config: MasterConfig, speed_mode: I2cSpeed) -> I2cMaster {
+    #[cfg(feature = "vor4x")]
+    let clks = test_clocks();
+    unsafe {
+        I2cMaster::steal(
+            i2c::Bank::I2c1,
+            // The I2C peripherals of Vorago 4x devices use the APB1 clock, which is half the
+            // system clock.
+            #[cfg(feature = "vor1x")]
+            50.MHz(),
+            #[cfg(feature = "vor4x")]
+            &clks,
+            config,
+            speed_mode,
+        )
+        .unwrap()
+    }
+}
+
+#[test]
+fn i2c_clkscale_regular_mode() {
+    let _harness = test_harness::lock();
+    let _i2c = i2c_master(I2cSpeed::Regular100khz);
+    let clkscale = i2c::regs::I2c::new_mmio(i2c::Bank::I2c1).read_clkscale();
+    // 50 MHz / 100 kHz / 20 - 1
+    assert_eq!(clkscale.div(), 24);
+    assert_eq!(clkscale.fastmode(), I2cSpeed::Regular100khz);
+    assert!(clock_enabled(PeripheralSelect::I2c1));
+    // The reset was released again.
+    assert_eq!(
+        test_harness::peripheral_reset(),
+        test_harness::PERIPHERAL_RESET_DEFAULT
+    );
+}
+
+#[test]
+fn i2c_clkscale_fast_mode() {
+    let _harness = test_harness::lock();
+    let _i2c = i2c_master(I2cSpeed::Fast400khz);
+    let clkscale = i2c::regs::I2c::new_mmio(i2c::Bank::I2c1).read_clkscale();
+    // 50 MHz / 400 kHz / 25 - 1
+    assert_eq!(clkscale.div(), 4);
+    assert_eq!(clkscale.fastmode(), I2cSpeed::Fast400khz);
+}
+
+fn uart_with_config(config: uart::Config) -> Uart {
+    unsafe {
+        Uart::steal(
+            uart::Bank::Uart0,
+            50.MHz(),
+            config,
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    }
+}
+
+#[test]
+fn uart_manual_parity_words() {
+    let _harness = test_harness::lock();
+    let (mut tx, mut rx) = uart_with_config(uart::Config::default().parity_manual()).split();
+    let regs = uart::regs::Uart::new_mmio(uart::Bank::Uart0);
+    let ctrl = regs.read_ctrl();
+    assert!(ctrl.parity_manual());
+    assert!(ctrl.parity_enable());
+
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // TX FIFO ready.
+    image.write(0x14, 0b1);
+    tx.write_word_with_parity(0xA5, true).unwrap();
+    assert_eq!(image.read(0x00), (1 << 15) | 0xA5);
+    tx.write_word_with_parity(0x5A, false).unwrap();
+    assert_eq!(image.read(0x00), 0x5A);
+
+    assert_eq!(rx.read_word_with_parity(), Err(nb::Error::WouldBlock));
+    image.write(0x00, (1 << 15) | 0x3C);
+    // Data available and a parity error, which is not reported in manual mode.
+    image.write(0x10, (1 << 6) | 0b1);
+    assert_eq!(rx.read_word_with_parity(), Ok((0x3C, true)));
+    assert!(rx.poll_errors().is_none());
+    // The regular API only returns the data bits.
+    assert_eq!(embedded_hal_nb::serial::Read::read(&mut rx), Ok(0x3C));
+}
+
+#[test]
+fn uart_parity_words_require_manual_mode() {
+    let _harness = test_harness::lock();
+    let (mut tx, mut rx) = uart_with_config(uart::Config::default().parity_even()).split();
+    let image = test_harness::uart(uart::Bank::Uart0);
+    image.write(0x10, (1 << 6) | 0b1);
+    image.write(0x14, 0b1);
+    assert_eq!(
+        tx.write_word_with_parity(0x00, true),
+        Err(nb::Error::Other(uart::ParityManualDisabledError))
+    );
+    assert_eq!(
+        rx.read_word_with_parity(),
+        Err(nb::Error::Other(uart::ParityManualDisabledError))
+    );
+    assert!(rx.poll_errors().unwrap().parity());
+}
+
+#[test]
+fn uart_word_size_masking() {
+    let _harness = test_harness::lock();
+    for (wordsize, mask) in [
+        (uart::WordSize::Five, 0x1F),
+        (uart::WordSize::Seven, 0x7F),
+        (uart::WordSize::Eight, 0xFF),
+    ] {
+        let (mut tx, mut rx) = uart_with_config(uart::Config::default().wordsize(wordsize)).split();
+        assert_eq!(wordsize.data_mask(), mask);
+        let image = test_harness::uart(uart::Bank::Uart0);
+        // TX FIFO ready and data available.
+        image.write(0x14, 0b1);
+        image.write(0x10, 0b1);
+
+        embedded_hal_nb::serial::Write::write(&mut tx, 0xFF).unwrap();
+        assert_eq!(image.read(0x00), mask as u32);
+        embedded_io::Write::write_all(&mut tx, &[0xA5]).unwrap();
+        assert_eq!(image.read(0x00), 0xA5 & mask as u32);
+        core::fmt::Write::write_str(&mut tx, "~").unwrap();
+        assert_eq!(image.read(0x00), b'~' as u32 & mask as u32);
+        tx.write_checked(mask).unwrap();
+        assert_eq!(image.read(0x00), mask as u32);
+        if mask != 0xFF {
+            image.write(0x00, 0);
+            assert_eq!(
+                tx.write_checked(mask + 1),
+                Err(nb::Error::Other(uart::WordSizeExceededError {
+                    data: mask + 1,
+                    wordsize,
+                }))
+            );
+            // Nothing was written.
+            assert_eq!(image.read(0x00), 0);
+        }
+
+        // Upper bits of the data register, which are not driven for smaller word sizes.
+        image.write(0x00, 0xFF);
+        assert_eq!(embedded_hal_nb::serial::Read::read(&mut rx), Ok(mask));
+        let mut buf = [0; 2];
+        assert_eq!(embedded_io::Read::read(&mut rx, &mut buf), Ok(2));
+        assert_eq!(buf, [mask; 2]);
+        // The raw FIFO access is not masked.
+        assert_eq!(rx.read_fifo(), Ok(0xFF));
+    }
+}
+
+#[test]
+fn i2c_loopback() {
+    let _harness = test_harness::lock();
+    let config = MasterConfig::builder().loopback(true).build().unwrap();
+    let mut i2c = i2c_master_with_config(config, I2cSpeed::Regular100khz);
+    assert!(i2c.loopback());
+    i2c.set_loopback(false);
+    assert!(!i2c.loopback());
+    assert!(
+        !i2c::regs::I2c::new_mmio(i2c::Bank::I2c1)
+            .read_control()
+            .loopback()
+    );
+}
+
+#[test]
+fn uart_rts_threshold() {
+    let _harness = test_harness::lock();
+    let (_tx, mut rx) = uart_with_config(uart::Config::default()).split();
+    // The reset value is kept.
+    assert_eq!(rx.rts_threshold(), u5::new(0));
+    rx.set_rts_threshold(u5::new(14));
+    assert_eq!(rx.rts_threshold(), u5::new(14));
+}
+
+#[test]
+fn uart_enable_read_back() {
+    let _harness = test_harness::lock();
+    let config = uart::Config {
+        enable_tx: false,
+        enable_rx: false,
+        ..Default::default()
+    };
+    let mut uart = uart_with_config(config);
+    assert!(!uart.rx_enabled());
+    assert!(!uart.tx_enabled());
+    uart.enable();
+    assert!(uart.rx_enabled());
+    assert!(uart.tx_enabled());
+    uart.disable();
+    assert!(!uart.rx_enabled());
+    assert!(!uart.tx_enabled());
+}
+
+#[test]
+fn uart_conversions_enable_disabled_directions() {
+    let _harness = test_harness::lock();
+    let config = uart::Config {
+        enable_tx: false,
+        enable_rx: false,
+        ..Default::default()
+    };
+    let (tx, rx) = uart_with_config(config).split();
+    assert!(!rx.is_enabled());
+    assert!(!tx.is_enabled());
+    let rx = rx.into_rx_with_irq();
+    assert!(rx.rx().is_enabled());
+    let tx = tx.into_async().release();
+    assert!(tx.is_enabled());
+    let enable = uart::regs::Uart::new_mmio(uart::Bank::Uart0).read_enable();
+    assert!(enable.rx());
+    assert!(enable.tx());
+}
+
+/// Delay provider which only accumulates the requested delays.
+#[derive(Default)]
+struct RecordingDelay {
+    elapsed_ns: u64,
+}
+
+impl embedded_hal::delay::DelayNs for RecordingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+#[test]
+fn uart_read_with_rx_disabled() {
+    let _harness = test_harness::lock();
+    let config = uart::Config {
+        enable_rx: false,
+        ..Default::default()
+    };
+    let (_tx, mut rx) = uart_with_config(config).split();
+    let mut buf = [0; 4];
+    // Returns immediately instead of waiting forever.
+    assert_eq!(embedded_io::Read::read(&mut rx, &mut buf), Ok(0));
+    let mut delay = RecordingDelay::default();
+    assert_eq!(
+        rx.read_with_character_timeout(&mut buf, 10, &mut delay),
+        Ok(0)
+    );
+    assert_eq!(delay.elapsed_ns, 0);
+
+    // Data which is still in the FIFO is read.
+    let image = test_harness::uart(uart::Bank::Uart0);
+    image.write(0x00, 0x42);
+    image.write(0x10, 0b1);
+    assert_eq!(embedded_io::Read::read(&mut rx, &mut buf[..1]), Ok(1));
+    assert_eq!(buf[0], 0x42);
+}
+
+#[test]
+fn uart_transmission_complete() {
+    let _harness = test_harness::lock();
+    let (mut tx, _rx) = uart_with_config(uart::Config::default()).split();
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // TX FIFO empty, but the last character is still shifted out.
+    image.write(0x14, 1 << 2);
+    assert!(!tx.is_transmission_complete());
+    image.write(0x14, 1 << 1);
+    assert!(!tx.is_transmission_complete());
+    image.write(0x14, 0);
+    assert!(tx.is_transmission_complete());
+    assert!(tx.is_enabled());
+    tx.disable_after_drain();
+    assert!(!tx.is_enabled());
+
+    // A disabled transmitter does not drain the FIFO, so waiting returns immediately.
+    image.write(0x14, (1 << 2) | (1 << 1));
+    tx.wait_transmission_complete();
+}
+
+/// Poll a future once, like an executor which is woken by the interrupt handler.
+#[cfg(feature = "vor1x")]
+fn poll_once<F: Future>(fut: Pin<&mut F>) -> Poll<F::Output> {
+    fut.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+// The TX future unmasks the TX interrupt in the NVIC, which is only skipped for an unrouted
+// VA108xx UART.
+#[cfg(feature = "vor1x")]
+#[test]
+fn uart_async_write_abort() {
+    let _harness = test_harness::lock();
+    let (tx, _rx) = uart_with_config(uart::Config::default()).split();
+    let mut tx = tx.into_async();
+    let regs = uart::regs::Uart::new_mmio(uart::Bank::Uart0);
+    let image = test_harness::uart(uart::Bank::Uart0);
+    let buf = [0x55; 32];
+    {
+        // The buffer does not fit into the FIFO, so the write waits for the interrupt handler.
+        let fut = pin!(embedded_io_async::Write::write(&mut tx, &buf));
+        assert!(poll_once(fut).is_pending());
+        assert!(regs.read_enable().tx());
+    }
+    image.write(0x18, 0);
+    tx.abort_write();
+    let irq_enabled = regs.read_irq_enabled();
+    assert!(!irq_enabled.tx());
+    assert!(!irq_enabled.tx_empty());
+    assert!(!irq_enabled.tx_status());
+    assert!(!regs.read_enable().tx());
+    // TX FIFO cleared.
+    assert_eq!(image.read(0x18), 1 << 1);
+
+    // The next write completes normally.
+    let fut = pin!(embedded_io_async::Write::write(&mut tx, &[1, 2, 3]));
+    assert!(matches!(poll_once(fut), Poll::Ready(Ok(3))));
+    assert_eq!(image.read(0x00), 3);
+}
+
+// The RX interrupt is only unmasked in the NVIC for a routed VA108xx UART.
+#[cfg(feature = "vor1x")]
+#[test]
+fn uart_async_read_cancelled() {
+    let _harness = test_harness::lock();
+    let (_tx, rx) = uart_with_config(uart::Config::default()).split();
+    let queue = Box::leak(Box::new(heapless::spsc::Queue::<u8, 8>::new()));
+    let (mut producer, consumer) = queue.split();
+    let mut rx = uart::RxAsync::new(rx, consumer);
+    let mut buf = [0; 4];
+    {
+        let fut = pin!(embedded_io_async::Read::read(&mut rx, &mut buf));
+        assert!(poll_once(fut).is_pending());
+    }
+    // The reception continues after the read was cancelled.
+    let regs = uart::regs::Uart::new_mmio(uart::Bank::Uart0);
+    assert!(regs.read_enable().rx());
+    assert!(regs.read_irq_enabled().rx());
+    assert!(regs.read_irq_enabled().rx_timeout());
+
+    producer.enqueue(0x42).unwrap();
+    {
+        let fut = pin!(embedded_io_async::Read::read(&mut rx, &mut buf));
+        assert_eq!(poll_once(fut), Poll::Ready(Ok(1)));
+    }
+    assert_eq!(buf[0], 0x42);
+}
+
+#[test]
+fn uart_read_character_timeout() {
+    let _harness = test_harness::lock();
+    let (_tx, mut rx) = uart_with_config(uart::Config::default()).split();
+    let mut buf = [0; 4];
+    let mut delay = RecordingDelay::default();
+    assert_eq!(
+        rx.read_with_character_timeout(&mut buf, 3, &mut delay),
+        Err(vorago_shared_hal::time::TimeoutError)
+    );
+    // Three character times of 86.806 us each, rounded up to full microseconds.
+    assert_eq!(delay.elapsed_ns, 261_000);
+}
+
+#[test]
+fn spi_stale_rx_words() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    assert_eq!(spi.flush_policy(), spi::FlushPolicy::DiscardStale);
+    spi.set_flush_policy(spi::FlushPolicy::ErrorOnStale);
+    let image = test_harness::spi(spi::Bank::Spi1);
+    // Three words in the RX FIFO, the TX FIFO is empty.
+    image.write(0x0C, (1 << 2) | 0b1);
+    image.write(0x30, 3 << 8);
+    image.write(0x08, 0x1A5);
+    let mut buf = [0; 4];
+    assert_eq!(
+        embedded_hal::spi::SpiBus::read(&mut spi, &mut buf),
+        Err(spi::StaleDataInFifoError { words: 3 })
+    );
+    assert_eq!(spi.rx_fifo_level(), 3);
+    // The status image is static, so the drain only stops at the end of the buffer.
+    let mut drained = [0; 2];
+    assert_eq!(spi.drain_rx(&mut drained), 2);
+    assert_eq!(drained, [0xA5, 0xA5]);
+    image.write(0x0C, 0b1);
+    assert_eq!(spi.drain_rx(&mut drained), 0);
+}
+
+#[test]
+fn spi_raw_words_keep_flags() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    let image = test_harness::spi(spi::Bank::Spi1);
+    // TX FIFO empty and not full.
+    image.write(0x0C, 0b11);
+    spi.write_raw_words(&[RawSpiWord::new(0x12), RawSpiWord::new(0x34)])
+        .unwrap();
+    // BMSTALL is enabled by default, but no BMSTOP bit is added to the last word.
+    assert_eq!(image.read(0x08), 0x34);
+    spi.write_raw_words(&[RawSpiWord::FRAME_END]).unwrap();
+    assert_eq!(
+        image.read(0x08),
+        spi::BMSTART_BMSTOP_MASK | spi::BMSKIPDATA_MASK
+    );
+    embedded_hal::spi::SpiBus::write(&mut spi, &[0x12, 0x34]).unwrap();
+    assert_eq!(image.read(0x08), 0x34 | spi::BMSTART_BMSTOP_MASK);
+}
+
+#[test]
+fn spi_write_vectored_empty_slices() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    let image = test_harness::spi(spi::Bank::Spi1);
+    image.write(0x0C, 0b11);
+    let ctrl1 = image.read(0x04);
+    image.write(0x08, 0xA5);
+    // Nothing is written and the FIFO pause is not touched.
+    spi.write_vectored(&[]).unwrap();
+    spi.write_vectored(&[&[], &[], &[]]).unwrap();
+    assert_eq!(image.read(0x08), 0xA5);
+    assert_eq!(image.read(0x04), ctrl1);
+
+    // An empty slice after the last non-empty slice does not hide the last word.
+    spi.write_vectored(&[&[], &[0x12], &[], &[0x34, 0x56], &[]])
+        .unwrap();
+    assert_eq!(image.read(0x08), 0x56 | spi::BMSTART_BMSTOP_MASK);
+
+    let mut spi: Spi<u8> =
+        unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default().bmstall(false)) };
+    image.write(0x0C, 0b11);
+    spi.write_vectored(&[&[0x12], &[0x34], &[]]).unwrap();
+    assert_eq!(image.read(0x08), 0x34);
+}
+
+/// Data register values sampled by [sample_spi_data].
+static SPI_DATA_SAMPLES: std::sync::Mutex<Vec<u32>> = std::sync::Mutex::new(Vec::new());
+
+fn sample_spi_data() {
+    let word = test_harness::spi(spi::Bank::Spi1).read(0x08);
+    SPI_DATA_SAMPLES.lock().unwrap().push(word);
+}
+
+#[test]
+fn spi_write_vectored_bmstop_on_last_word() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    let image = test_harness::spi(spi::Bank::Spi1);
+    image.write(0x0C, 0b11);
+    // The hook is called before the words at the indices 255 and 511 are written, so it samples
+    // the last word of the first slice and the second to last word of the transfer.
+    let first = [0x11; 255];
+    let mut last = [0x22; 257];
+    last[255] = 0x33;
+    last[256] = 0x44;
+    SPI_DATA_SAMPLES.lock().unwrap().clear();
+    busy_wait::set_busy_wait_hook(sample_spi_data);
+    spi.write_vectored(&[&[], &first, &[], &last, &[]]).unwrap();
+    busy_wait::clear_busy_wait_hook();
+    assert_eq!(*SPI_DATA_SAMPLES.lock().unwrap(), [0x11, 0x33]);
+    assert_eq!(image.read(0x08), 0x44 | spi::BMSTART_BMSTOP_MASK);
+}
+
+#[test]
+fn uart_write_vectored_empty_buffers() {
+    let _harness = test_harness::lock();
+    let (mut tx, _rx) = uart_with_config(uart::Config::default()).split();
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // The TX FIFO is not ready, so only writes without data return.
+    image.write(0x14, 0);
+    assert_eq!(tx.write_vectored(&[]), Ok(0));
+    assert_eq!(tx.write_vectored(&[&[], &[]]), Ok(0));
+    assert_eq!(image.read(0x00), 0);
+
+    image.write(0x14, 1);
+    assert_eq!(
+        tx.write_vectored(&[&[], &[0x12, 0x34], &[], &[0x56], &[]]),
+        Ok(3)
+    );
+    assert_eq!(image.read(0x00), 0x56);
+}
+
+#[test]
+fn spi_abort_and_recover() {
+    let _harness = test_harness::lock();
+    let config = SpiConfig::default()
+        .mode(MODE_3)
+        .clk_cfg(SpiClockConfig::new(4, 9));
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, config) };
+    spi.cfg_hw_cs(HwChipSelectId::Id2);
+    spi.set_fill_word(0x5A);
+    let image = test_harness::spi(spi::Bank::Spi1);
+    let mut regs = spi::regs::Spi::new_mmio(spi::Bank::Spi1);
+    let ctrl0 = regs.read_ctrl0();
+    let clkprescale = regs.read_clkprescale();
+    // Block mode frame in progress with an asserted chip select.
+    regs.modify_ctrl1(|value| value.with_bm_start(true).with_mtxpause(true));
+    let ctrl1 = regs.read_ctrl1();
+    image.write(0x20, 0);
+    image.write(0x2C, 0);
+
+    spi.abort_and_recover();
+    assert_eq!(regs.read_ctrl0().raw_value(), ctrl0.raw_value());
+    assert_eq!(regs.read_clkprescale().value(), clkprescale.value());
+    let recovered = regs.read_ctrl1();
+    assert!(recovered.enable());
+    assert!(!recovered.bm_start());
+    assert!(!recovered.mtxpause());
+    assert_eq!(recovered.ss(), HwChipSelectId::Id2);
+    assert_eq!(
+        recovered.raw_value(),
+        ctrl1.with_bm_start(false).with_mtxpause(false).raw_value()
+    );
+    // Both FIFOs and the RX timeout and overrun interrupts were cleared.
+    assert_eq!(
+        image.read(0x2C),
+        spi::regs::FifoClear::builder()
+            .with_tx_fifo(true)
+            .with_rx_fifo(true)
+            .build()
+            .raw_value()
+    );
+    assert_eq!(image.read(0x20), 0b11);
+    assert_eq!(spi.fill_word(), 0);
+    assert_eq!(spi.current_cs(), spi::ChipSelect::Hw(HwChipSelectId::Id2));
+}
+
+static BUSY_WAIT_HOOK_CALLS: AtomicU32 = AtomicU32::new(0);
+
+fn count_busy_wait_hook_call() {
+    BUSY_WAIT_HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn busy_wait_hook_called_by_spi_write() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi0, SpiConfig::default()) };
+    // TX FIFO empty and not full.
+    test_harness::spi(spi::Bank::Spi0).write(0x0C, 0b11);
+    let words = [0x5A; 4 * BUSY_WAIT_HOOK_INTERVAL as usize];
+    BUSY_WAIT_HOOK_CALLS.store(0, Ordering::Relaxed);
+    busy_wait::set_busy_wait_hook(count_busy_wait_hook_call);
+    embedded_hal::spi::SpiBus::write(&mut spi, &words).unwrap();
+    busy_wait::clear_busy_wait_hook();
+    // The first words are written to the FIFO before the blocking loop starts.
+    assert_eq!(BUSY_WAIT_HOOK_CALLS.load(Ordering::Relaxed), 3);
+    embedded_hal::spi::SpiBus::write(&mut spi, &words).unwrap();
+    assert_eq!(BUSY_WAIT_HOOK_CALLS.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn uart_tx_fifo_trigger() {
+    let _harness = test_harness::lock();
+    let (mut tx, _rx) = uart_with_config(uart::Config::default()).split();
+    tx.set_tx_fifo_trigger(u5::new(12));
+    assert_eq!(tx.tx_fifo_trigger(), u5::new(12));
+    assert_eq!(test_harness::uart(uart::Bank::Uart0).read(0x3C), 12);
+}
+
+#[test]
+fn uart_split_keeps_config() {
+    let _harness = test_harness::lock();
+    let config = uart::Config::default()
+        .baudrate(9600.Hz())
+        .parity_even()
+        .stopbits(uart::Stopbits::Two);
+    let (tx, rx) = uart_with_config(config).split();
+    for (ref_clk, cfg) in [(tx.ref_clk(), tx.config()), (rx.ref_clk(), rx.config())] {
+        assert_eq!(ref_clk, 50.MHz::<1, 1>());
+        assert_eq!(*cfg, config);
+    }
+    assert_eq!(tx.baudrate(), 9600.Hz::<1, 1>());
+    assert_eq!(rx.character_time(), config.character_time());
+    let uart = Uart::reunite(tx, rx).unwrap();
+    assert_eq!(*uart.config(), config);
+    assert_eq!(uart.ref_clk(), 50.MHz::<1, 1>());
+    let (tx, _) = uart.split();
+    let other_rx = unsafe { uart::Rx::steal(uart::Bank::Uart1, 50.MHz(), config) };
+    assert!(Uart::reunite(tx, other_rx).is_err());
+}
+
+#[test]
+fn uart_fifo_sized_interrupt_buffer() {
+    let _harness = test_harness::lock();
+    let (tx, rx) = uart_with_config(uart::Config::default()).split();
+    assert_eq!(tx.fifo_capacity(), uart::MAX_FIFO_DEPTH);
+    assert_eq!(rx.fifo_capacity(), uart::MAX_FIFO_DEPTH);
+    let mut rx = uart::RxWithInterrupt::new(rx);
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // RX FIFO trigger interrupt with a trigger level of 8 bytes.
+    image.write(0x30, 1 << 0);
+    image.write(0x38, 8);
+    image.write(0x00, 0x55);
+    // Buffers larger than the FIFO are accepted as well.
+    let mut buf = [0; 2 * uart::MAX_FIFO_DEPTH];
+    let result = rx.on_interrupt(&mut buf);
+    assert_eq!(result.bytes_read, 8);
+    assert!(buf[..8].iter().all(|byte| *byte == 0x55));
+    assert!(buf[8..].iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn uart_framing_error_is_not_an_overrun() {
+    let _harness = test_harness::lock();
+    let (_tx, rx) = uart_with_config(uart::Config::default()).split();
+    let mut rx = uart::RxWithInterrupt::new(rx);
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // RX and RX status interrupts enabled, only the RX status interrupt is pending.
+    image.write(0x28, (1 << 0) | (1 << 1));
+    image.write(0x30, 1 << 1);
+    // Framing error without an overrun.
+    image.write(0x10, 1 << 5);
+    let mut buf = [0; uart::MAX_FIFO_DEPTH];
+    let errors = rx.on_interrupt(&mut buf).errors.unwrap();
+    assert!(errors.framing());
+    assert!(!errors.overflow());
+    // The RX status interrupt was acknowledged.
+    assert_eq!(image.read(0x34), 1 << 1);
+
+    // An overrun together with the framing error is reported as both.
+    image.write(0x10, (1 << 5) | (1 << 4));
+    let errors = rx.on_interrupt(&mut buf).errors.unwrap();
+    assert!(errors.framing());
+    assert!(errors.overflow());
+}
+
+#[test]
+fn tim_peripheral_id_check() {
+    let _harness = test_harness::lock();
+    let tim0 = timer::TimId::new_unchecked(0);
+    let tim1 = timer::TimId::new_unchecked(1);
+    let perid_offset = core::mem::size_of::<timer::regs::Timer>() - 4;
+    test_harness::timer(tim0).write(perid_offset, timer::regs::EXPECTED_PERID);
+    test_harness::timer(tim1).write(perid_offset, 0);
+    timer::enable_tim_clk(tim1);
+
+    assert_eq!(
+        vorago_shared_hal::verify_tim_peripheral_ids(&[tim0]),
+        Ok(())
+    );
+    let mismatch = vorago_shared_hal::verify_tim_peripheral_ids(&[tim0, tim1]).unwrap_err();
+    assert!(!mismatch.contains(tim0));
+    assert!(mismatch.contains(tim1));
+    // The clock of TIM0 was only enabled for the check.
+    assert!(!timer::is_tim_clk_enabled(tim0));
+    assert!(timer::is_tim_clk_enabled(tim1));
+}
+
+#[test]
+fn gpio_port_snapshot() {
+    let _harness = test_harness::lock();
+    test_harness::gpio(Port::B).write(0x00, 0b1010_0001);
+    assert_eq!(gpio::read_port_snapshot(Port::B), 0b1010_0001);
+    assert_eq!(gpio::read_port_snapshot(Port::A), 0);
+}
+
+#[test]
+fn gpio_interrupt_enable_without_port_constructor() {
+    let _harness = test_harness::lock();
+    let id = gpio::DynPinId::new(Port::B, 3).unwrap();
+    // Pins created without the port constructor, for example after a bootloader handoff.
+    let mut pin = gpio::ll::LowLevelGpio::new(id);
+    assert!(!clock_enabled(Port::B.periph_sel()));
+    assert!(!pin.interrupt_enabled());
+    pin.configure_edge_interrupt(gpio::InterruptEdge::LowToHigh);
+    #[cfg(feature = "vor1x")]
+    pin.enable_interrupt(vorago_shared_hal::InterruptConfig::new(
+        pac::Interrupt::OC0,
+        false,
+        false,
+    ));
+    #[cfg(feature = "vor4x")]
+    pin.enable_interrupt(false).unwrap();
+    assert!(clock_enabled(PeripheralSelect::IoConfig));
+    assert!(clock_enabled(Port::B.periph_sel()));
+    #[cfg(feature = "vor1x")]
+    assert!(clock_enabled(PeripheralSelect::Gpio));
+    // IRQ_EVT and IRQ_ENB.
+    assert_eq!(test_harness::gpio(Port::B).read(0x3C), 1 << 3);
+    assert_eq!(test_harness::gpio(Port::B).read(0x40), 1 << 3);
+    assert!(pin.interrupt_enabled());
+
+    // Enabling the clocks again does not change anything.
+    let clk_enable = test_harness::peripheral_clk_enable();
+    gpio::ensure_port_clocks(Port::B);
+    assert_eq!(test_harness::peripheral_clk_enable(), clk_enable);
+}
+
+#[test]
+fn spi_current_sck() {
+    let _harness = test_harness::lock();
+    let config = SpiConfig::default().clk_cfg(SpiClockConfig::new(4, 9));
+    let spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi2, config) };
+    // 50 MHz / (4 * 10)
+    assert_eq!(spi.current_sck(50.MHz()), 1_250.kHz::<1, 1>());
+}
+
+#[test]
+fn i2c_diagnostics() {
+    let _harness = test_harness::lock();
+    let i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    // State 5, step 3, 2 RX words, 7 TX words, bitstate 0x101.
+    image.write(0x1C, 5 | (3 << 4) | (2 << 8) | (7 << 14) | (0x101 << 20));
+    assert_eq!(
+        i2c.diagnostic_state(),
+        i2c::I2cDiagnostics {
+            state: 5,
+            step: 3,
+            rx_fifo: 2,
+            tx_fifo: 7,
+            bitstate: 0x101,
+        }
+    );
+    // SDA held low by a slave.
+    image.write(0x18, 1 << 31);
+    assert_eq!(i2c.bus_lines(), (false, true));
+    image.write(0x18, (1 << 31) | (1 << 30));
+    assert_eq!(i2c.bus_lines(), (true, true));
+}
+
+#[test]
+fn i2c_read_insufficient_data() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    // Controller idle with an empty RX FIFO.
+    image.write(0x18, 0b11);
+    let mut buf = [0; 4];
+    assert_eq!(
+        i2c.read_blocking(I2cAddress::Regular(0x50), &mut buf),
+        Err(i2c::Error::InsufficientDataReceived {
+            expected: 4,
+            received: 0
+        })
+    );
+    assert_eq!(
+        i2c.read_blocking(I2cAddress::Regular(0x50), &mut []),
+        Ok(())
+    );
+    // The error reports the hardware RX count instead of the number of bytes read from the
+    // FIFO.
+    image.write(0x24, 3);
+    assert_eq!(
+        i2c.read_blocking(I2cAddress::Regular(0x50), &mut buf),
+        Err(i2c::Error::InsufficientDataReceived {
+            expected: 4,
+            received: 3
+        })
+    );
+}
+
+#[test]
+fn i2c_progress_counters() {
+    let _harness = test_harness::lock();
+    let i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    assert_eq!((i2c.tx_progress(), i2c.rx_progress()), (0, 0));
+    image.write(0x20, 0x7FE);
+    image.write(0x24, 17);
+    assert_eq!(i2c.tx_progress(), 0x7FE);
+    assert_eq!(i2c.rx_progress(), 17);
+}
+
+#[test]
+fn i2c_read_excess_data_is_drained() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    image.write(0x10, 0xA5);
+    // Controller idle, but the RX FIFO never runs empty.
+    image.write(0x18, 0b11 | (1 << 8));
+    let mut buf = [0; 4];
+    // The FIFO is drained completely after the completion was detected, which reads at most
+    // the FIFO depth.
+    assert_eq!(
+        i2c.read_blocking(I2cAddress::Regular(0x50), &mut buf),
+        Err(i2c::Error::ExcessDataReceived(
+            i2c::MAX_FIFO_DEPTH - buf.len()
+        ))
+    );
+    assert_eq!(buf, [0xA5; 4]);
+}
+
+/// Value of the CMD register if the transfer guard issued no command after the initial start
+/// with stop.
+const I2C_CMD_START_WITH_STOP: u32 = 0b011;
+const I2C_CMD_STOP: u32 = 0b010;
+const I2C_CMD_CANCEL: u32 = 0b100;
+/// Clock timeout, TX overflow and RX overflow.
+const I2C_STICKY_IRQS: u32 = (1 << 7) | (1 << 10) | (1 << 11);
+
+/// Checks the CMD, FIFO_CLEAR and IRQ_CLEAR writes after a failed transaction.
+fn assert_i2c_cleanup<const WORDS: usize>(image: &test_harness::RegisterImage<WORDS>, cmd: u32) {
+    assert_eq!(image.read(0x14), cmd);
+    assert_eq!(image.read(0x40), 0b11);
+    assert_eq!(image.read(0x34), I2C_STICKY_IRQS);
+}
+
+#[test]
+fn i2c_error_cleanup() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    let addr = I2cAddress::Regular(0x50);
+    let mut buf = [0; 4];
+
+    image.write(0x18, 1 << 5);
+    assert_eq!(i2c.read_blocking(addr, &mut buf), Err(i2c::Error::NackAddr));
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    image.write(0x18, 1 << 4);
+    assert_eq!(
+        i2c.read_blocking(addr, &mut buf),
+        Err(i2c::Error::ArbitrationLost)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    image.write(0x18, 1 << 6);
+    assert_eq!(i2c.write_blocking(addr, &[1, 2]), Err(i2c::Error::NackData));
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    // The failed write of a write-read holds the bus after the start without stop.
+    assert_eq!(
+        i2c.write_read_blocking(addr, &[1, 2], &mut buf),
+        Err(i2c::Error::NackData)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_STOP);
+
+    // Controller idle with an empty RX FIFO.
+    image.write(0x18, 0b11);
+    assert_eq!(
+        i2c.read_blocking(addr, &mut buf),
+        Err(i2c::Error::InsufficientDataReceived {
+            expected: buf.len(),
+            received: 0
+        })
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+}
+
+#[test]
+fn i2c_shared_bus_write_read_repeated_start() {
+    let _harness = test_harness::lock();
+    let manager = i2c::share::I2cBusManager::new(i2c_master(I2cSpeed::Regular100khz));
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    let mut device = manager.device();
+    let mut buf = [0; 4];
+    image.write(0x18, 1 << 6);
+    // The write is started without a stop, so the bus is held for the repeated start of the
+    // read and only the cleanup of the failed write issues the stop.
+    assert_eq!(
+        embedded_hal::i2c::I2c::write_read(&mut device, 0x50, &[1, 2], &mut buf),
+        Err(i2c::Error::NackData)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_STOP);
+    // A plain write ends with a stop.
+    assert_eq!(
+        embedded_hal::i2c::I2c::write(&mut device, 0x50, &[1, 2]),
+        Err(i2c::Error::NackData)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+}
+
+#[test]
+fn i2c_clock_timeout_cleanup() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    i2c.set_clock_low_timeout(u20::new(100));
+    // Busy controller with a pending clock timeout interrupt.
+    image.write(0x18, 0);
+    image.write(0x30, 1 << 7);
+    let mut buf = [0; 4];
+    assert_eq!(
+        i2c.read_blocking(I2cAddress::Regular(0x50), &mut buf),
+        Err(i2c::Error::ClockTimeout(u20::new(100)))
+    );
+    // The stalled transfer is cancelled.
+    assert_i2c_cleanup(image, I2C_CMD_CANCEL);
+    // The clock timeout interrupt is disabled again.
+    assert_eq!(image.read(0x28) & (1 << 7), 0);
+}
+
+#[test]
+fn i2c_no_cleanup_on_success() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    image.write(0x18, 0b11);
+    assert_eq!(
+        i2c.write_blocking(I2cAddress::Regular(0x50), &[1, 2]),
+        Ok(())
+    );
+    assert_eq!(image.read(0x14), I2C_CMD_START_WITH_STOP);
+    // Only the TX FIFO clear before the transfer.
+    assert_eq!(image.read(0x40), 0b10);
+    assert_eq!(image.read(0x34), 0);
+}
+
+#[test]
+fn i2c_write_read_too_large_starts_no_transfer() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    i2c.set_chunked_transfers(false);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    image.write(0x18, 0b11);
+    // Discard the FIFO clear of the initialization.
+    image.write(0x40, 0);
+    let mut buf = [0; i2c::MAX_CHUNK_LEN + 1];
+    assert_eq!(
+        i2c.write_read_blocking(I2cAddress::Regular(0x50), &[1, 2], &mut buf),
+        Err(i2c::Error::DataTooLarge)
+    );
+    // The write phase was not started, so the bus is not held for the repeated start.
+    assert_eq!(image.read(0x14), 0);
+    assert_eq!(image.read(0x40), 0);
+    assert_eq!(image.read(0x34), 0);
+    assert_eq!(image.read(0x18), 0b11);
+}
+
+#[test]
+fn i2c_probe_ack() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    // Controller and bus idle after the stop condition.
+    image.write(0x18, 0b11);
+    assert_eq!(i2c.probe(I2cAddress::Regular(0x50)), Ok(true));
+    // Zero words, address 0x50 with the send direction.
+    assert_eq!(image.read(0x08), 0);
+    assert_eq!(image.read(0x0C), 0x50 << 1);
+    assert_eq!(image.read(0x14), I2C_CMD_START_WITH_STOP);
+    // No cleanup, only the TX FIFO clear before the transfer.
+    assert_eq!(image.read(0x40), 0b10);
+    assert!(!i2c.is_busy());
+}
+
+#[test]
+fn i2c_probe_nack() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    // Latched address NACK.
+    image.write(0x18, 0b11 | (1 << 5));
+    assert_eq!(i2c.probe(I2cAddress::Regular(0x50)), Ok(false));
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+    assert!(!i2c.is_busy());
+
+    // Other errors are returned.
+    image.write(0x18, 1 << 4);
+    assert_eq!(
+        i2c.probe(I2cAddress::Regular(0x50)),
+        Err(i2c::Error::ArbitrationLost)
+    );
+}
+
+#[test]
+fn i2c_scan() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    let mut found = [false; 128];
+
+    image.write(0x18, 0b11);
+    // Addresses larger than 0x7F are skipped.
+    assert_eq!(i2c.scan(0x7D..=0xFF, &mut found), Ok(3));
+    assert_eq!(image.read(0x0C), 0x7F << 1);
+    assert!(found[0x7D..].iter().all(|ack| *ack));
+    assert!(found[..0x7D].iter().all(|ack| !*ack));
+
+    image.write(0x18, 0b11 | (1 << 5));
+    assert_eq!(i2c.scan(0x08..=0x77, &mut found), Ok(0));
+    assert!(found[0x08..=0x77].iter().all(|ack| !*ack));
+    // Addresses outside of the range are not touched.
+    assert!(found[0x7D..].iter().all(|ack| *ack));
+}
+
+/// Status value written by [inject_i2c_fault] once the blocking loop is running.
+static I2C_FAULT_STATUS: AtomicU32 = AtomicU32::new(0);
+/// Interrupt status value written by [inject_i2c_fault].
+static I2C_FAULT_IRQ_STATUS: AtomicU32 = AtomicU32::new(0);
+
+fn inject_i2c_fault() {
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    image.write(0x18, I2C_FAULT_STATUS.load(Ordering::Relaxed));
+    image.write(0x30, I2C_FAULT_IRQ_STATUS.load(Ordering::Relaxed));
+}
+
+/// Runs the transaction with a fault which is injected by the busy wait hook after the blocking
+/// loop started, and checks that the bus and the transaction flag were released.
+fn i2c_fault_partway(
+    i2c: &mut I2cMaster,
+    initial_status: u32,
+    fault_status: u32,
+    fault_irq_status: u32,
+    transaction: impl FnOnce(&mut I2cMaster) -> Result<(), i2c::Error>,
+) -> Result<(), i2c::Error> {
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    image.write(0x18, initial_status);
+    image.write(0x30, 0);
+    I2C_FAULT_STATUS.store(fault_status, Ordering::Relaxed);
+    I2C_FAULT_IRQ_STATUS.store(fault_irq_status, Ordering::Relaxed);
+    busy_wait::set_busy_wait_hook(inject_i2c_fault);
+    let result = transaction(i2c);
+    busy_wait::clear_busy_wait_hook();
+    assert!(!i2c.is_busy());
+    result
+}
+
+#[test]
+fn i2c_fault_partway_releases_bus() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    let addr = I2cAddress::Regular(0x50);
+    let mut buf = [0; 4];
+    // TX FIFO not full, so the write keeps loading the FIFO until the NACK.
+    let tx_not_full = 1 << 13;
+    // RX FIFO not empty, so the read keeps draining the FIFO until the fault.
+    let rx_not_empty = 1 << 8;
+
+    let data = [0xA5; 32];
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, tx_not_full, 1 << 6, 0, |i2c| i2c
+            .write_blocking(addr, &data)),
+        Err(i2c::Error::NackData)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, tx_not_full, 1 << 5, 0, |i2c| i2c
+            .write_blocking(addr, &data)),
+        Err(i2c::Error::NackAddr)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    // The data register is shared by both FIFOs.
+    image.write(0x10, 0x5A);
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, rx_not_empty, 1 << 4, 0, |i2c| i2c
+            .read_blocking(addr, &mut buf)),
+        Err(i2c::Error::ArbitrationLost)
+    );
+    assert_eq!(buf, [0x5A; 4]);
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, rx_not_empty, 1 << 5, 0, |i2c| i2c
+            .read_blocking(addr, &mut buf)),
+        Err(i2c::Error::NackAddr)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_START_WITH_STOP);
+
+    // The write of a write-read holds the bus, which is released with a stop.
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, tx_not_full, 1 << 6, 0, |i2c| i2c
+            .write_read_blocking(addr, &data, &mut buf)),
+        Err(i2c::Error::NackData)
+    );
+    assert_i2c_cleanup(image, I2C_CMD_STOP);
+
+    // A clock timeout partway through a read cancels the stalled transfer.
+    i2c.set_clock_low_timeout(u20::new(100));
+    assert_eq!(
+        i2c_fault_partway(&mut i2c, rx_not_empty, 0, 1 << 7, |i2c| i2c
+            .read_blocking(addr, &mut buf)),
+        Err(i2c::Error::ClockTimeout(u20::new(100)))
+    );
+    assert_i2c_cleanup(image, I2C_CMD_CANCEL);
+
+    // The next transaction is not rejected with a busy error.
+    image.write(0x18, 0b11);
+    image.write(0x30, 0);
+    assert_eq!(i2c.write_blocking(addr, &[1, 2]), Ok(()));
+}
+
+fn countdown_timer<Tim: TimInstance>(tim: Tim) -> CountdownTimer {
+    #[cfg(feature = "vor1x")]
+    return CountdownTimer::new(tim, 50.MHz());
+    #[cfg(feature = "vor4x")]
+    return CountdownTimer::new(tim, &test_clocks());
+}
+
+#[test]
+fn timer_wait_without_interrupt_handler() {
+    let _harness = test_harness::lock();
+    let mut tim = countdown_timer(unsafe { pac::Tim7::steal() });
+    tim.load(1.kHz());
+    #[cfg(feature = "vor1x")]
+    tim.enable_interrupt(vorago_shared_hal::InterruptConfig::new(
+        pac::Interrupt::OC0,
+        false,
+        false,
+    ));
+    #[cfg(feature = "vor4x")]
+    tim.enable_interrupt(false);
+    let image = test_harness::timer(tim.id());
+    // The interrupt is enabled, but the handler is not called, so the expiry is inferred from
+    // the count.
+    image.write(0x08, 1000);
+    assert_eq!(tim.wait(), Err(nb::Error::WouldBlock));
+    image.write(0x08, 0);
+    assert_eq!(tim.wait(), Ok(()));
+    // Once the handler was called, only the counted expirations are returned. The handler
+    // counts the expiry which was already inferred from the count, so it is not reported again.
+    timer::on_interrupt(tim.id());
+    assert_eq!(tim.wait(), Err(nb::Error::WouldBlock));
+    timer::on_interrupt(tim.id());
+    assert_eq!(tim.wait(), Ok(()));
+    assert_eq!(tim.wait(), Err(nb::Error::WouldBlock));
+    timer::on_interrupt(tim.id());
+    timer::on_interrupt(tim.id());
+    assert_eq!(tim.poll_expirations(), 2);
+}
+
+#[test]
+fn monotonic_clock_rejects_slow_ref_clock() {
+    let _harness = test_harness::lock();
+    let clock = vorago_shared_hal::time::MonotonicClock::new();
+    let tim = unsafe { pac::Tim9::steal() };
+    #[cfg(feature = "vor1x")]
+    let result = clock.init(tim, 500.kHz(), pac::Interrupt::OC0);
+    #[cfg(feature = "vor4x")]
+    let result = clock.init(
+        tim,
+        &vorago_shared_hal::clock::Clocks::__new(
+            1.MHz(),
+            #[cfg(not(feature = "va41628"))]
+            1.MHz(),
+        ),
+    );
+    let err = result.unwrap_err();
+    assert_eq!(err.tick_hz, 1_000_000);
+    assert!(err.ref_clk.raw() < 1_000_000);
+    // The timer was not started.
+    assert_eq!(test_harness::tim_clk_enable() & (1 << 9), 0);
+    assert_eq!(clock.now().ticks(), 0);
+}
+
+#[test]
+fn timer_compare_value() {
+    let _harness = test_harness::lock();
+    let mut tim = countdown_timer(unsafe { pac::Tim5::steal() });
+    assert_eq!(test_harness::tim_clk_enable(), 1 << 5);
+    assert_eq!(test_harness::tim_reset(), test_harness::TIM_RESET_DEFAULT);
+    tim.set_compare(1234);
+    assert_eq!(tim.compare(), 1234);
+    assert_eq!(test_harness::timer(tim.id()).read(0x20), 1234);
+    // The compare value is restored after a reset of the peripheral.
+    test_harness::timer(tim.id()).write(0x20, 0);
+    tim.reinitialize();
+    assert_eq!(tim.compare(), 1234);
+}
+
+#[cfg(feature = "vor4x")]
+#[test]
+fn timer_apb2_reference_clock() {
+    let _harness = test_harness::lock();
+    let clks = test_clocks();
+    // TIM16 to TIM23 are clocked by APB2.
+    let mut tim = countdown_timer(unsafe { pac::Tim20::steal() });
+    assert_eq!(tim.ref_clk(), clks.apb2());
+    tim.load(1.kHz());
+    assert_eq!(test_harness::timer(tim.id()).read(0x04), 25_000);
+    let mut tim = countdown_timer(unsafe { pac::Tim15::steal() });
+    assert_eq!(tim.ref_clk(), clks.apb1());
+    tim.load(1.kHz());
+    assert_eq!(test_harness::timer(tim.id()).read(0x04), 50_000);
+}
+
+#[test]
+fn timer_phase_trigger_cascade() {
+    let _harness = test_harness::lock();
+    let mut main = countdown_timer(unsafe { pac::Tim3::steal() });
+    let helper = countdown_timer(unsafe { pac::Tim4::steal() });
+    // 10 kHz period at 50 MHz: 5000 cycles.
+    main.load(10.kHz());
+    assert_eq!(main.reload(), 5000);
+    let mut trigger = PhaseTrigger::new(&main, helper, 300).unwrap();
+    assert_eq!(trigger.delay_ticks(), 1500);
+    let image = test_harness::timer(trigger.helper().id());
+    // Same period as the main timer, the first expiry is delayed by the phase offset.
+    assert_eq!(image.read(0x04), 5000);
+    assert_eq!(image.read(0x08), 1500);
+    assert_eq!(image.read(0x0C), 1);
+    // Auto-disable and auto-deactivate are cleared.
+    assert_eq!(image.read(0x00) & 0b1100, 0);
+    // Cascade 0 enabled in trigger mode.
+    assert_eq!(image.read(0x10), (1 << 6) | 1);
+    assert_eq!(
+        image.read(0x14),
+        timer::CascadeSource::Tim(3).id().unwrap().value() as u32
+    );
+
+    trigger.set_phase(9999, 500).unwrap();
+    assert_eq!(image.read(0x04), 9999);
+    assert_eq!(image.read(0x08), 5000);
+    assert_eq!(
+        trigger.set_phase(9999, 1000),
+        Err(timer::PhaseTriggerError::InvalidPhase(1000))
+    );
+    let _helper = trigger.release();
+    assert_eq!(image.read(0x0C), 0);
+}
+
+#[cfg(feature = "vor4x")]
+#[test]
+fn irq_router_dma_routing() {
+    use vorago_shared_hal::irq_router::{
+        self, Dac, DmaRequestSource, DmaRoutingError, DmaTriggerType, InvalidDmaChannelError,
+    };
+
+    let _harness = test_harness::lock();
+    let image = test_harness::irq_router();
+    irq_router::route_dma_request(DmaRequestSource::UartRx(uart::Bank::Uart1), 2).unwrap();
+    assert!(clock_enabled(PeripheralSelect::IrqRouter));
+    assert_eq!(image.read(0x08), pac::Interrupt::UART1_RX as u32);
+    assert_eq!(
+        irq_router::dma_request_routing(2),
+        Ok(DmaRequestSource::UartRx(uart::Bank::Uart1))
+    );
+    irq_router::route_dma_request(DmaRequestSource::SpiTx(spi::Bank::Spi3), 0).unwrap();
+    assert_eq!(image.read(0x00), pac::Interrupt::SPI3_TX as u32);
+    irq_router::route_dma_request(DmaRequestSource::None, 2).unwrap();
+    assert_eq!(image.read(0x08), 0x7F);
+    assert_eq!(
+        irq_router::dma_request_routing(2),
+        Ok(DmaRequestSource::None)
+    );
+    // Values written with the PAC can be read back.
+    image.write(0x0C, pac::Interrupt::Ethernet as u32);
+    let unknown = irq_router::dma_request_routing(3).unwrap();
+    assert_eq!(
+        unknown,
+        DmaRequestSource::Unknown(pac::Interrupt::Ethernet as u8)
+    );
+
+    // Validation.
+    assert_eq!(
+        irq_router::route_dma_request(DmaRequestSource::Adc, 4),
+        Err(DmaRoutingError::InvalidChannel(InvalidDmaChannelError(4)))
+    );
+    assert_eq!(
+        irq_router::route_dma_request(unknown, 1),
+        Err(DmaRoutingError::UnknownSource(
+            pac::Interrupt::Ethernet as u8
+        ))
+    );
+    assert_eq!(image.read(0x04), 0);
+    assert_eq!(
+        irq_router::dma_request_routing(4),
+        Err(InvalidDmaChannelError(4))
+    );
+
+    irq_router::set_dma_trigger_type(3, DmaTriggerType::Burst).unwrap();
+    irq_router::set_dma_trigger_type(1, DmaTriggerType::Burst).unwrap();
+    irq_router::set_dma_trigger_type(3, DmaTriggerType::Single).unwrap();
+    assert_eq!(image.read(0x10), 0b0010);
+    assert_eq!(irq_router::dma_trigger_type(1), Ok(DmaTriggerType::Burst));
+    assert_eq!(irq_router::dma_trigger_type(3), Ok(DmaTriggerType::Single));
+
+    let tim = timer::TimId::new(7).unwrap();
+    irq_router::route_adc_trigger(Some(tim));
+    irq_router::route_dac_trigger(Dac::Dac1, Some(tim));
+    assert_eq!(image.read(0x14), 7);
+    assert_eq!(image.read(0x1C), 7);
+    assert_eq!(irq_router::adc_trigger(), Some(tim));
+    assert_eq!(irq_router::dac_trigger(Dac::Dac1), Some(tim));
+    irq_router::route_adc_trigger(None);
+    assert_eq!(image.read(0x14), 0x1F);
+    assert_eq!(irq_router::adc_trigger(), None);
+
+    image.write(0x24, 1 << (pac::Interrupt::Ethernet as u32 - 32));
+    assert!(irq_router::interrupt_line_active(pac::Interrupt::Ethernet));
+    assert!(!irq_router::interrupt_line_active(pac::Interrupt::U37));
+}
+
+#[cfg(feature = "vor1x")]
+#[test]
+fn irqsel_routing() {
+    use vorago_shared_hal::{irqsel, timer::TimId};
+
+    let _harness = test_harness::lock();
+    let image = test_harness::irqsel();
+    let pin = gpio::DynPinId::new(Port::B, 3).unwrap();
+    let tim = TimId::new_unchecked(7);
+    irqsel::route_uart(uart::Bank::Uart1, pac::Interrupt::OC5);
+    irqsel::route_tim(tim, pac::Interrupt::OC3);
+    irqsel::route_gpio(pin, pac::Interrupt::OC9);
+    assert!(clock_enabled(PeripheralSelect::Irqsel));
+    // UART selection registers start at 0x180, TIM at 0x100 and PORTB at 0x80.
+    assert_eq!(image.read(0x184), 5);
+    assert_eq!(image.read(0x100 + 7 * 4), 3);
+    assert_eq!(image.read(0x80 + 3 * 4), 9);
+    assert_eq!(
+        uart::Bank::Uart1.routed_interrupt(),
+        Some(pac::Interrupt::OC5)
+    );
+    assert_eq!(tim.routed_interrupt(), Some(pac::Interrupt::OC3));
+    assert_eq!(pin.routed_interrupt(), Some(pac::Interrupt::OC9));
+
+    let info = irqsel::routing_for(pac::Interrupt::OC9);
+    assert_eq!(info.pins.port_b, 1 << 3);
+    assert_eq!(info.num_sources(), 1);
+    assert!(irqsel::find_conflicts().is_empty());
+
+    // A second pin on the same line is not a conflict, a TIM is.
+    irqsel::route_gpio(
+        gpio::DynPinId::new(Port::A, 0).unwrap(),
+        pac::Interrupt::OC9,
+    );
+    assert!(irqsel::find_conflicts().is_empty());
+    // Routing which was not performed by the HAL is read back as well.
+    image.write(0x100 + 2 * 4, 9);
+    let conflicts = irqsel::find_conflicts();
+    assert!(conflicts.contains(pac::Interrupt::OC9));
+    assert_eq!(conflicts.iter().count(), 1);
+    assert_eq!(irqsel::routing_table()[9].tims, 1 << 2);
+
+    irqsel::unroute_uart(uart::Bank::Uart1);
+    irqsel::unroute_tim(tim);
+    irqsel::unroute_gpio(pin);
+    assert_eq!(image.read(0x184), u32::MAX);
+    assert_eq!(image.read(0x100 + 7 * 4), u32::MAX);
+    assert_eq!(image.read(0x80 + 3 * 4), u32::MAX);
+    assert_eq!(uart::Bank::Uart1.routed_interrupt(), None);
+    assert_eq!(tim.routed_interrupt(), None);
+    assert_eq!(pin.routed_interrupt(), None);
+    assert!(irqsel::routing_for(pac::Interrupt::OC5).is_empty());
+}
+
+/// Prepares the participant twice, where the second preparation must have no effect, and
+/// resumes it.
+fn sleep_and_resume(participant: &mut dyn SleepParticipant, check_sleeping: impl Fn()) {
+    participant.prepare_sleep();
+    check_sleeping();
+    participant.prepare_sleep();
+    check_sleeping();
+    participant.resume();
+}
+
+#[test]
+fn sleep_uart() {
+    let _harness = test_harness::lock();
+    let mut uart = unsafe {
+        Uart::steal(
+            uart::Bank::Uart1,
+            50.MHz(),
+            uart::Config::default(),
+            #[cfg(feature = "vor1x")]
+            None,
+        )
+    };
+    let image = test_harness::uart(uart::Bank::Uart1);
+    // Transmitter enabled, receiver disabled.
+    image.write(0x04, 0b10);
+    sleep_and_resume(&mut uart, || assert_eq!(image.read(0x04), 0));
+    assert_eq!(image.read(0x04), 0b10);
+
+    let (mut tx, mut rx) = uart.split();
+    image.write(0x04, 0b01);
+    sleep_and_resume(&mut rx, || assert_eq!(image.read(0x04), 0));
+    assert_eq!(image.read(0x04), 0b01);
+    image.write(0x04, 0b11);
+    {
+        let participants: &mut [&mut dyn SleepParticipant] = &mut [&mut tx, &mut rx];
+        let _guard = SleepGuard::enter(participants);
+        assert_eq!(image.read(0x04), 0);
+    }
+    assert_eq!(image.read(0x04), 0b11);
+}
+
+#[test]
+fn sleep_spi() {
+    let _harness = test_harness::lock();
+    let mut spi: Spi<u8> = unsafe { Spi::steal(spi::Bank::Spi1, SpiConfig::default()) };
+    let image = test_harness::spi(spi::Bank::Spi1);
+    spi.enable_interrupts(SpiInterrupts {
+        rx_trigger: true,
+        rx_timeout: true,
+        ..Default::default()
+    });
+    // Block mode start with TX pause.
+    image.write(0x04, image.read(0x04) | (1 << 8) | (1 << 11));
+    let ctrl1 = image.read(0x04);
+    let irq_enb = image.read(0x14);
+    assert_ne!(irq_enb, 0);
+    // Idle with an empty TX FIFO.
+    image.write(0x0C, 0b1);
+    sleep_and_resume(&mut spi, || {
+        assert_eq!(image.read(0x04), ctrl1 & !((1 << 1) | (1 << 8) | (1 << 11)));
+        assert_eq!(image.read(0x14), 0);
+    });
+    assert_eq!(image.read(0x04), ctrl1);
+    assert_eq!(image.read(0x14), irq_enb);
+}
+
+#[test]
+fn sleep_i2c() {
+    let _harness = test_harness::lock();
+    let mut i2c = i2c_master(I2cSpeed::Regular100khz);
+    let image = test_harness::i2c(i2c::Bank::I2c1);
+    i2c.enable();
+    let control = image.read(0x00);
+    assert_eq!(control & (1 << 2), 1 << 2);
+    sleep_and_resume(&mut i2c, || {
+        assert_eq!(image.read(0x00), control & !(1 << 2))
+    });
+    assert_eq!(image.read(0x00), control);
+    // A disabled controller stays disabled.
+    i2c.disable();
+    sleep_and_resume(&mut i2c, || ());
+    assert_eq!(image.read(0x00), control & !(1 << 2));
+}
+
+#[test]
+fn sleep_timer() {
+    let _harness = test_harness::lock();
+    let mut tim = countdown_timer(unsafe { pac::Tim5::steal() });
+    let image = test_harness::timer(tim.id());
+    tim.start(1.kHz());
+    // The enable bit of the control register is set by the hardware.
+    image.write(0x00, image.read(0x00) | 1);
+    sleep_and_resume(&mut tim, || assert_eq!(image.read(0x0C), 0));
+    assert_eq!(image.read(0x0C), 1);
+    // A stopped timer is not started.
+    image.write(0x00, image.read(0x00) & !1);
+    image.write(0x0C, 0);
+    sleep_and_resume(&mut tim, || ());
+    assert_eq!(image.read(0x0C), 0);
+}
+
+#[test]
+fn sleep_pwm() {
+    let _harness = test_harness::lock();
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let mut pwm: PwmPin = PwmPin::new(
+        pin,
+        unsafe { pac::Tim2::steal() },
+        #[cfg(feature = "vor1x")]
+        50.MHz(),
+        #[cfg(feature = "vor4x")]
+        &test_clocks(),
+        10.kHz(),
+    )
+    .unwrap();
+    let image = test_harness::timer(pwm.tim_id());
+    pwm.enable();
+    image.write(0x00, image.read(0x00) | 1);
+    let reload = image.read(0x04);
+    sleep_and_resume(&mut pwm, || assert_eq!(image.read(0x0C), 0));
+    assert_eq!(image.read(0x0C), 1);
+    assert_eq!(image.read(0x04), reload);
+}
+
+#[test]
+fn pwm_resolution() {
+    let _harness = test_harness::lock();
+    let pwm_with_resolution = |min_bits| {
+        let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+        PwmPin::<pwm::PwmA>::new_with_resolution(
+            pin,
+            unsafe { pac::Tim2::steal() },
+            #[cfg(feature = "vor1x")]
+            50.MHz(),
+            #[cfg(feature = "vor4x")]
+            &test_clocks(),
+            200.kHz(),
+            min_bits,
+        )
+    };
+    let available = match pwm_with_resolution(16) {
+        Err(pwm::PwmInitError::InsufficientResolution {
+            requested,
+            available,
+        }) => {
+            assert_eq!(requested, 16);
+            available
+        }
+        _ => panic!("insufficient resolution not detected"),
+    };
+    let mut pwm = pwm_with_resolution(available).unwrap();
+    assert_eq!(pwm.resolution_bits(), available);
+    let reload = pwm.reload();
+    assert!(1 << available <= reload + 1 && reload + 1 < 1 << (available + 1));
+    assert_eq!(pwm.max_effective_duty() as u32, reload);
+
+    let image = test_harness::timer(pwm.tim_id());
+    pwm.set_duty_exact(reload + 1).unwrap();
+    assert_eq!(image.read(0x20), 0);
+    assert_eq!(pwm.duty(), u16::MAX);
+    pwm.set_duty_exact(1).unwrap();
+    assert_eq!(image.read(0x20), reload);
+    pwm.set_duty_exact(0).unwrap();
+    assert_eq!(image.read(0x20), reload + 1);
+    assert_eq!(pwm.duty(), 0);
+    assert_eq!(
+        pwm.set_duty_exact(reload + 2),
+        Err(pwm::DutyOutOfRange {
+            ticks: reload + 2,
+            max: reload + 1
+        })
+    );
+    assert_eq!(image.read(0x20), reload + 1);
+}
+
+#[test]
+fn sleep_output() {
+    let _harness = test_harness::lock();
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let mut output = gpio::Output::new(pin, gpio::PinState::High);
+    let image = test_harness::gpio(Port::A);
+    let mask = 1 << 2;
+    assert_eq!(image.read(0x10), mask);
+    // The images do not apply SETOUT and CLROUT to DATAOUT.
+    image.write(0x08, mask);
+    image.write(0x10, 0);
+
+    // Without a sleep level, the level is not changed.
+    sleep_and_resume(&mut output, || ());
+    assert_eq!(image.read(0x14), 0);
+
+    output.set_sleep_level(Some(gpio::PinState::Low));
+    sleep_and_resume(&mut output, || assert_eq!(image.read(0x14), mask));
+    assert_eq!(image.read(0x10), mask);
+}
+
+#[test]
+fn ownership_and_irq_views() {
+    let _harness = test_harness::lock();
+    let tim = countdown_timer(unsafe { pac::Tim7::steal() });
+    let id = tim.id();
+    assert_eq!(
+        ownership::is_owned(RegisterBlock::Tim(id)),
+        cfg!(debug_assertions)
+    );
+    assert!(!ownership::is_owned(RegisterBlock::Uart(uart::Bank::Uart1)));
+    if cfg!(debug_assertions) {
+        assert!(std::panic::catch_unwind(move || unsafe { id.steal_regs() }).is_err());
+    }
+
+    let image = test_harness::timer(id);
+    let mut view = timer::TimIrqView::new(id);
+    image.write(0x00, (1 << 4) | 0b11);
+    image.write(0x08, 0x1234);
+    assert!(view.is_enabled() && view.is_active() && view.irq_enabled());
+    assert_eq!(view.count_value(), 0x1234);
+    view.disable_irq();
+    assert_eq!(image.read(0x00), 0b11);
+
+    let image = test_harness::uart(uart::Bank::Uart1);
+    let mut view = uart::UartIrqView::new(uart::Bank::Uart1);
+    image.write(0x28, 0b11);
+    image.write(0x30, 0b1);
+    assert_eq!(view.irq_enabled().raw_value(), 0b11);
+    assert!(view.irq_status().rx());
+    view.clear(
+        uart::regs::InterruptClear::builder()
+            .with_rx_overrun(true)
+            .with_tx_overrun(false)
+            .build(),
+    );
+    assert_ne!(image.read(0x34), 0);
+
+    let image = test_harness::gpio(Port::A);
+    let mut view = gpio::GpioIrqView::new(Port::A);
+    image.write(0x40, 0b110);
+    image.write(0x48, 0b100);
+    image.write(0x4C, 0b010);
+    assert_eq!(view.irq_enabled(), 0b110);
+    assert_eq!(view.irq_status(), 0b100);
+    assert_eq!(view.edge_status(), 0b010);
+    image.write(0x4C, 0);
+    view.clear_edge_status(0b010);
+    assert_eq!(image.read(0x4C), 0b010);
+    assert_eq!(gpio::port_interrupt_status(Port::A), 0b100);
+}
+
+#[test]
+fn gpio_output_config_matrix() {
+    let _harness = test_harness::lock();
+    let ioconfig = test_harness::ioconfig();
+    let image = test_harness::gpio(Port::A);
+    let mask = 1 << 2;
+    for bits in 0..16 {
+        let open_drain = bits & 0b0001 != 0;
+        let pull = (bits & 0b0010 != 0).then_some(gpio::Pull::Up);
+        let pull_when_output_active = bits & 0b0100 != 0;
+        let invert_output = bits & 0b1000 != 0;
+        let config = gpio::OutputConfig {
+            open_drain,
+            pull,
+            pull_when_output_active,
+            invert_output,
+            delay: gpio::PinDelay::None,
+        };
+        ioconfig.reset();
+        image.reset();
+        let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+        let _output = gpio::Output::new_with_config(pin, gpio::PinState::Low, config);
+        let pin_config = ioconfig.read(0x08);
+        assert_eq!(pin_config & (1 << 8) != 0, open_drain, "{config:?}");
+        assert_eq!(pin_config & (1 << 11) != 0, pull.is_some(), "{config:?}");
+        // Pull-up direction.
+        assert_eq!(pin_config & (1 << 10), 0, "{config:?}");
+        assert_eq!(
+            pin_config & (1 << 12) != 0,
+            pull_when_output_active,
+            "{config:?}"
+        );
+        assert_eq!(pin_config & (1 << 9) != 0, invert_output, "{config:?}");
+        // Input enabled while output, GPIO function.
+        assert_eq!(pin_config & (1 << 7), 1 << 7);
+        assert_eq!(pin_config & (0b11 << 13), 0);
+        assert_eq!(image.read(0x14), mask);
+        assert_eq!(image.read(0x20), mask);
+
+        let decoded =
+            gpio::ll::LowLevelGpio::new(gpio::DynPinId::new_unchecked(Port::A, 2)).current_config();
+        assert_eq!(decoded.open_drain, open_drain);
+        assert_eq!(decoded.pull, pull);
+        assert_eq!(decoded.pull_when_output_active, pull_when_output_active);
+        assert_eq!(decoded.invert_output, invert_output);
+    }
+
+    // The pull-down and the open-drain constructor.
+    ioconfig.reset();
+    image.reset();
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let _output =
+        gpio::Output::new_open_drain(pin, gpio::PinState::High, Some(gpio::Pull::Down), true);
+    assert_eq!(
+        ioconfig.read(0x08),
+        (1 << 12) | (1 << 11) | (1 << 10) | (1 << 8) | (1 << 7)
+    );
+    assert_eq!(image.read(0x10), mask);
+    assert_eq!(
+        gpio::OutputConfig::open_drain(),
+        gpio::OutputConfig {
+            open_drain: true,
+            pull: Some(gpio::Pull::Up),
+            pull_when_output_active: false,
+            invert_output: false,
+            delay: gpio::PinDelay::None,
+        }
+    );
+}
+
+#[test]
+fn gpio_output_config_delay() {
+    let _harness = test_harness::lock();
+    let ioconfig = test_harness::ioconfig();
+    let image = test_harness::gpio(Port::A);
+    let mask = 1 << 2;
+    ioconfig.reset();
+    image.reset();
+    // Delays of other pins are kept.
+    image.write(0x2C, 1 << 3);
+    image.write(0x30, 1 << 3);
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let output = gpio::Output::new_with_config(
+        pin,
+        gpio::PinState::Low,
+        gpio::OutputConfig::open_drain().delay(gpio::PinDelay::Two),
+    );
+    assert_eq!(image.read(0x2C), 1 << 3);
+    assert_eq!(image.read(0x30), mask | (1 << 3));
+    assert_eq!(output.delay(), gpio::PinDelay::Two);
+    assert_eq!(ioconfig.read(0x08) & (1 << 8), 1 << 8);
+
+    // Re-configuring the output applies the delay of the new configuration.
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let _output = gpio::Output::new_with_delay(pin, gpio::PinState::Low, gpio::PinDelay::One);
+    assert_eq!(image.read(0x2C), mask | (1 << 3));
+    assert_eq!(image.read(0x30), 1 << 3);
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let _output = gpio::Output::new(pin, gpio::PinState::Low);
+    assert_eq!(image.read(0x2C), 1 << 3);
+    assert_eq!(image.read(0x30), 1 << 3);
+}
+
+#[test]
+fn gpio_input_config() {
+    let _harness = test_harness::lock();
+    let ioconfig = test_harness::ioconfig();
+    let image = test_harness::gpio(Port::A);
+    ioconfig.reset();
+    image.reset();
+    image.write(0x20, 1 << 2);
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let _input = gpio::Input::new_with_config(
+        pin,
+        gpio::InputConfig::floating()
+            .pull(Some(gpio::Pull::Down))
+            .invert_input(true)
+            .filter(
+                gpio::FilterType::FilterThreeCycles,
+                gpio::FilterClockSelect::Clk5,
+            ),
+    );
+    // PEN, pull-down, INVINP, FLTCLK 5 and the three cycle filter.
+    assert_eq!(
+        ioconfig.read(0x08),
+        (1 << 11) | (1 << 10) | (1 << 6) | (5 << 3) | 4
+    );
+    assert_eq!(image.read(0x20), 0);
+
+    // Without a filter, the filter settings of the pin are kept. The pull direction is kept
+    // as well, but the pull is disabled.
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pa2>::steal() };
+    let _input = gpio::Input::new_with_config(pin, gpio::InputConfig::default());
+    assert_eq!(ioconfig.read(0x08), (1 << 10) | (5 << 3) | 4);
+    let decoded =
+        gpio::ll::LowLevelGpio::new(gpio::DynPinId::new_unchecked(Port::A, 2)).current_config();
+    assert_eq!(decoded.pull, None);
+    assert!(!decoded.invert_input);
+    assert_eq!(decoded.filter_type, Ok(gpio::FilterType::FilterThreeCycles));
+    assert_eq!(decoded.filter_clk_sel, gpio::FilterClockSelect::Clk5);
+}
+
+/// Port G pins create inputs without the interrupt functions, which can still be grouped.
+#[cfg(feature = "vor4x")]
+#[test]
+fn gpio_port_g_input_without_interrupts() {
+    let _harness = test_harness::lock();
+    let pin = unsafe { vorago_shared_hal::pins::Pin::<vorago_shared_hal::pins::Pg2>::steal() };
+    let input: gpio::Input<gpio::NoInterrupts> = gpio::Input::new_floating(pin);
+    assert_eq!(input.id(), gpio::DynPinId::new_unchecked(Port::G, 2));
+    let group = gpio::InputGroup::new([input]).unwrap();
+    assert_eq!(group.port(), Port::G);
+    assert_eq!(group.mask(), 1 << 2);
+    // The low-level API reports the missing interrupt.
+    let mut ll = gpio::ll::LowLevelGpio::new(gpio::DynPinId::new_unchecked(Port::G, 2));
+    assert!(ll.enable_interrupt(true).is_err());
+}
+
+#[test]
+fn gpio_pin_mux() {
+    use gpio::{MuxFunction, PeripheralFunction, PinMux, PinMuxError};
+    use vorago_shared_hal::pins::Pb4;
+    #[cfg(feature = "vor1x")]
+    type Sck = vorago_shared_hal::pins::Pb9;
+    #[cfg(feature = "vor4x")]
+    type Sck = vorago_shared_hal::pins::Pb15;
+
+    let _harness = test_harness::lock();
+    let ioconfig = test_harness::ioconfig();
+    let gpio_b = test_harness::gpio(Port::B);
+    let id = <Sck as vorago_shared_hal::pins::PinId>::ID;
+    let word_offset = (vorago_shared_hal::NUM_PORT_A + id.offset()) * 4;
+    let mask = 1 << id.offset();
+    let sck = unsafe { vorago_shared_hal::pins::Pin::<Sck>::steal() };
+    let functions = [
+        MuxFunction::Peripheral(PeripheralFunction::spi_sck::<
+            vorago_shared_hal::pins::Pin<Sck>,
+        >(None)),
+        MuxFunction::Output {
+            config: gpio::OutputConfig::push_pull().delay(gpio::PinDelay::One),
+            level: gpio::PinState::High,
+        },
+        MuxFunction::Input {
+            pull: Some(gpio::Pull::Down),
+        },
+    ];
+
+    // The words which the regular constructors produce, starting from the same filter setting.
+    let filter = 0b101_010;
+    ioconfig.write(word_offset, filter);
+    gpio::IoPeriphPin::new(
+        id,
+        <vorago_shared_hal::pins::Pin<Sck> as spi::PinSck>::FUN_SEL,
+        None,
+    );
+    let periph_word = ioconfig.read(word_offset);
+    ioconfig.write(word_offset, filter);
+    let _ = gpio::Output::new(
+        unsafe { vorago_shared_hal::pins::Pin::<Sck>::steal() },
+        gpio::PinState::High,
+    );
+    let output_word = ioconfig.read(word_offset);
+    ioconfig.write(word_offset, filter);
+    let _ = gpio::Input::new_with_pull(
+        unsafe { vorago_shared_hal::pins::Pin::<Sck>::steal() },
+        gpio::Pull::Down,
+    );
+    let input_word = ioconfig.read(word_offset);
+
+    ioconfig.write(word_offset, filter);
+    gpio_b.write(0x20, 0);
+    let mut mux = PinMux::new(sck, functions, 0).unwrap();
+    assert_eq!(mux.config_word(0), Some(periph_word));
+    assert_eq!(mux.config_word(1), Some(output_word));
+    assert_eq!(mux.config_word(2), Some(input_word));
+    assert_eq!(mux.config_word(3), None);
+    assert_eq!(ioconfig.read(word_offset), periph_word);
+    assert_eq!(mux.current(), Some(0));
+
+    // The level, the delay and the direction are prepared before the function is switched.
+    gpio_b.write(0x10, 0);
+    assert_eq!(gpio_b.read(0x2C), 0);
+    mux.select(1).unwrap();
+    assert_eq!(gpio_b.read(0x10), mask);
+    assert_eq!(gpio_b.read(0x2C), mask);
+    assert_eq!(gpio_b.read(0x20), mask);
+    assert_eq!(ioconfig.read(word_offset), output_word);
+    assert_eq!(mux.current(), Some(1));
+
+    mux.select(2).unwrap();
+    assert_eq!(gpio_b.read(0x20), 0);
+    assert_eq!(ioconfig.read(word_offset), input_word);
+    assert_eq!(mux.current(), Some(2));
+
+    // The DIR bit is not touched for the peripheral function.
+    gpio_b.write(0x20, mask);
+    mux.select(0).unwrap();
+    assert_eq!(gpio_b.read(0x20), mask);
+    assert_eq!(mux.current(), Some(0));
+    assert_eq!(mux.select(3), Err(PinMuxError::InvalidIndex(3)));
+
+    // Re-configured by other means.
+    ioconfig.write(word_offset, 0);
+    assert_eq!(mux.current(), None);
+
+    let other = unsafe { vorago_shared_hal::pins::Pin::<Pb4>::steal() };
+    let other_id = <Pb4 as vorago_shared_hal::pins::PinId>::ID;
+    assert_eq!(
+        PinMux::new(other, functions, 0).err(),
+        Some(PinMuxError::PinMissmatch {
+            expected: other_id,
+            found: id
+        })
+    );
+    let other = unsafe { vorago_shared_hal::pins::Pin::<Pb4>::steal() };
+    assert_eq!(
+        PinMux::new(other, [MuxFunction::Input { pull: None }], 1).err(),
+        Some(PinMuxError::InvalidIndex(1))
+    );
+}
+
+/// TX and RX pins of [emulate_uart_wire] in the compact form.
+#[cfg(feature = "selftest")]
+static WIRE_PINS: [core::sync::atomic::AtomicU8; 2] =
+    [const { core::sync::atomic::AtomicU8::new(0) }; 2];
+/// Inversion combinations seen by [emulate_uart_wire]. Bit 1 is the TX inversion, bit 0 the RX
+/// inversion.
+#[cfg(feature = "selftest")]
+static WIRE_SEEN: AtomicU32 = AtomicU32::new(0);
+
+/// Emulates a wire from the TX pin to the RX pin of UART 0. The data register is shared by both
+/// FIFOs, so the RX FIFO returns the last written byte. If only one side is inverted, the
+/// receiver sees a framing error and a break.
+#[cfg(feature = "selftest")]
+fn emulate_uart_wire() {
+    let config = |index: usize| {
+        let id = gpio::DynPinId::from_compact(WIRE_PINS[index].load(Ordering::Relaxed)).unwrap();
+        gpio::ll::LowLevelGpio::new(id).current_config()
+    };
+    let invert_tx = config(0).invert_output;
+    let invert_rx = config(1).invert_input;
+    WIRE_SEEN.fetch_or(
+        1 << ((invert_tx as u32) * 2 + invert_rx as u32),
+        Ordering::Relaxed,
+    );
+    let image = test_harness::uart(uart::Bank::Uart0);
+    // Data available, with framing error and break on a mismatch.
+    let rx_status = if invert_tx == invert_rx {
+        1
+    } else {
+        (1 << 7) | (1 << 5) | 1
+    };
+    image.write(0x10, rx_status);
+    // TX FIFO ready.
+    image.write(0x14, 1);
+}
+
+#[cfg(feature = "selftest")]
+#[test]
+fn uart_inverted_pair_selftest() {
+    use vorago_shared_hal::selftest;
+
+    let _harness = test_harness::lock();
+    let tx_pin = *uart::TX_PIN_MAPPINGS
+        .iter()
+        .find(|mapping| mapping.bank == uart::Bank::Uart0)
+        .unwrap();
+    let rx_pin = *uart::RX_PIN_MAPPINGS
+        .iter()
+        .find(|mapping| mapping.bank == uart::Bank::Uart0)
+        .unwrap();
+    WIRE_PINS[0].store(tx_pin.pin.to_compact(), Ordering::Relaxed);
+    WIRE_PINS[1].store(rx_pin.pin.to_compact(), Ordering::Relaxed);
+    WIRE_SEEN.store(0, Ordering::Relaxed);
+    #[cfg(feature = "vor4x")]
+    let clks = test_clocks();
+    let run = |tx_pin, rx_pin| {
+        selftest::uart_inverted_pair_test(
+            tx_pin,
+            rx_pin,
+            #[cfg(feature = "vor1x")]
+            50.MHz(),
+            #[cfg(feature = "vor4x")]
+            &clks,
+            uart::Config::default().invert_tx(true).invert_rx(true),
+        )
+    };
+
+    busy_wait::set_busy_wait_hook(emulate_uart_wire);
+    let report = run(tx_pin, rx_pin).unwrap();
+    busy_wait::clear_busy_wait_hook();
+    assert_eq!(report.peripheral(), PeripheralSelect::Uart0);
+    let checks: Vec<_> = report
+        .checks()
+        .iter()
+        .map(|check| (check.name, check.passed))
+        .collect();
+    assert_eq!(
+        checks,
+        [("plain", true), ("mismatch", true), ("inverted", true)]
+    );
+    assert!(report.passed());
+    // Plain, only TX inverted and both inverted. The inversion of the passed configuration is
+    // ignored.
+    assert_eq!(WIRE_SEEN.load(Ordering::Relaxed), 0b1101);
+    // The pins keep their function with the inversion disabled.
+    for (mapping, id) in [(tx_pin, tx_pin.pin), (rx_pin, rx_pin.pin)] {
+        let config = gpio::ll::LowLevelGpio::new(id).current_config();
+        assert_eq!(config.funsel, mapping.fun_sel);
+        assert!(!config.invert_input && !config.invert_output);
+    }
+    // The peripheral is reset and its clock is disabled again.
+    assert!(!clock_enabled(PeripheralSelect::Uart0));
+    assert_eq!(test_harness::uart(uart::Bank::Uart0).read(0x04), 0);
+
+    // A line which never changes fails the data checks and does not block.
+    let report = run(tx_pin, rx_pin).unwrap();
+    assert!(!report.passed());
+
+    // The TX pin can not be used as the RX pin.
+    let invalid = tx_pin;
+    assert_eq!(
+        run(tx_pin, invalid).err(),
+        Some(uart::InvalidUartPinError {
+            bank: invalid.bank,
+            pin: invalid.pin,
+            fun_sel: invalid.fun_sel,
+        })
+    );
+}